@@ -49,6 +49,8 @@ CREATE TABLE "columns" (
 	"pattern_regex" text,
 	"enum_values" text[],
 	"is_array" boolean DEFAULT false,
+	"searchable" boolean DEFAULT false,
+	"title" text,
 	"description" text
 );
 
@@ -75,9 +77,57 @@ CREATE TABLE "users" (
 	"updated_at" timestamp DEFAULT now() NOT NULL,
 	"trashed_at" timestamp,
 	"deleted_at" timestamp,
+	"email" text,
 	CONSTRAINT "users_auth_unique" UNIQUE("auth")
 );
 
+-- Login secrets of tenant users; kept out of "users", which the data API serves, and
+-- never registered as a schema
+CREATE TABLE "user_credentials" (
+    "user_id" uuid PRIMARY KEY REFERENCES "users" ("id") ON DELETE CASCADE,
+    "password_hash" text,
+    "activation_token_hash" text,
+    "activation_expires_at" timestamp,
+    -- TOTP secret, encrypted under SECURITY_MFA_ENCRYPTION_KEY
+    "mfa_secret" text,
+    "mfa_enabled_at" timestamp,
+    "mfa_last_step" bigint
+);
+
+CREATE UNIQUE INDEX "user_credentials_activation_token_idx" ON "user_credentials" ("activation_token_hash")
+    WHERE "activation_token_hash" IS NOT NULL;
+
+-- Refresh tokens issued at login; rotated on each use, one family per login session
+CREATE TABLE "refresh_tokens" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "user_id" uuid NOT NULL REFERENCES "users" ("id") ON DELETE CASCADE,
+    "family_id" uuid NOT NULL,
+    "token_hash" text NOT NULL,
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "expires_at" timestamp NOT NULL,
+    "used_at" timestamp,
+    "revoked_at" timestamp,
+    "mfa" boolean DEFAULT false NOT NULL,
+    CONSTRAINT "refresh_tokens_token_hash_unique" UNIQUE("token_hash")
+);
+
+CREATE INDEX "refresh_tokens_family_idx" ON "refresh_tokens" ("family_id");
+
+-- API keys for machine-to-machine access; only a SHA-256 digest of each key is stored
+CREATE TABLE "api_keys" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "name" text NOT NULL,
+    "user_id" uuid NOT NULL REFERENCES "users" ("id") ON DELETE CASCADE,
+    "key_hash" text NOT NULL,
+    "hint" text NOT NULL,
+    "scopes" jsonb DEFAULT '[]'::jsonb NOT NULL,
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "expires_at" timestamp,
+    "last_used_at" timestamp,
+    "revoked_at" timestamp,
+    CONSTRAINT "api_keys_key_hash_unique" UNIQUE("key_hash")
+);
+
 -- Ping logging table to record all ping requests
 CREATE TABLE "pings" (
     "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
@@ -94,6 +144,361 @@ CREATE TABLE "pings" (
     "created_at" timestamp DEFAULT now() NOT NULL
 );
 
+-- Exact record counts per schema, seeded on first exact count and kept current by
+-- the RecordCountMaintainer observer; rows exclude trashed and deleted records
+CREATE TABLE "record_counts" (
+    "schema_name" text PRIMARY KEY NOT NULL,
+    "count" bigint DEFAULT 0 NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL
+);
+
+-- Per-tenant feature flag overrides; flags without a row use the API's built-in default
+CREATE TABLE "feature_flags" (
+    "name" text PRIMARY KEY NOT NULL,
+    "enabled" boolean NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL
+);
+
+-- Reusable import configurations selected by name with `monk data import --template`
+CREATE TABLE "import_templates" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "name" text NOT NULL,
+    "schema_name" text NOT NULL,
+    "column_mapping" jsonb DEFAULT '{}'::jsonb NOT NULL,
+    "delimiter" text,
+    "date_formats" jsonb DEFAULT '{}'::jsonb NOT NULL,
+    "transform" jsonb DEFAULT '[]'::jsonb NOT NULL,
+    "dedupe_key" text,
+    "access_read" uuid[] DEFAULT '{}'::uuid[],
+    "access_edit" uuid[] DEFAULT '{}'::uuid[],
+    "access_full" uuid[] DEFAULT '{}'::uuid[],
+    "access_deny" uuid[] DEFAULT '{}'::uuid[],
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    "trashed_at" timestamp,
+    "deleted_at" timestamp,
+    CONSTRAINT "import_templates_name_unique" UNIQUE("name")
+);
+
+-- Handlebars templates rendered against a record by POST /api/data/:schema/:id/render
+CREATE TABLE "document_templates" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "name" text NOT NULL,
+    "schema_name" text,
+    "format" text DEFAULT 'html' NOT NULL,
+    "body" text NOT NULL,
+    "filename" text,
+    "access_read" uuid[] DEFAULT '{}'::uuid[],
+    "access_edit" uuid[] DEFAULT '{}'::uuid[],
+    "access_full" uuid[] DEFAULT '{}'::uuid[],
+    "access_deny" uuid[] DEFAULT '{}'::uuid[],
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    "trashed_at" timestamp,
+    "deleted_at" timestamp,
+    CONSTRAINT "document_templates_name_unique" UNIQUE("name")
+);
+
+-- Threaded comments on records of any schema, managed through /api/comments; their
+-- permissions follow the commented record's access lists. mentions holds the ids of the
+-- users named with @login in body
+CREATE TABLE "comments" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "schema_name" text NOT NULL,
+    "record_id" uuid NOT NULL,
+    "parent_id" uuid REFERENCES "comments"("id"),
+    "body" text,
+    "mentions" uuid[] DEFAULT '{}'::uuid[] NOT NULL,
+    "author_id" uuid,
+    "author" text,
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    "edited_at" timestamp,
+    "deleted_at" timestamp
+);
+CREATE INDEX "comments_record_idx" ON "comments" ("schema_name", "record_id", "created_at");
+
+-- Labels for records of any schema, attached through record_tags; created through the
+-- data API or on first use, and renamed, merged or trashed by root only
+CREATE TABLE "tags" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "name" text NOT NULL,
+    "color" text,
+    "description" text,
+    "access_read" uuid[] DEFAULT '{}'::uuid[],
+    "access_edit" uuid[] DEFAULT '{}'::uuid[],
+    "access_full" uuid[] DEFAULT '{}'::uuid[],
+    "access_deny" uuid[] DEFAULT '{}'::uuid[],
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    "trashed_at" timestamp,
+    "deleted_at" timestamp,
+    CONSTRAINT "tags_name_unique" UNIQUE("name")
+);
+
+-- Tags on records, one row per tag and record; the primary key serves $tagged filters,
+-- which look records up by tag, and the index serves listing a record's tags
+CREATE TABLE "record_tags" (
+    "tag_id" uuid NOT NULL REFERENCES "tags"("id") ON DELETE CASCADE,
+    "schema_name" text NOT NULL,
+    "record_id" uuid NOT NULL,
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    PRIMARY KEY ("tag_id", "schema_name", "record_id")
+);
+CREATE INDEX "record_tags_record_idx" ON "record_tags" ("schema_name", "record_id");
+
+-- Records each user opened recently, on schemas with "x-monk-track-views"; kept to the
+-- user's api.recent_views_per_user latest by the RecentViewTracker observer
+CREATE TABLE "record_views" (
+    "user_id" uuid NOT NULL,
+    "schema_name" text NOT NULL,
+    "record_id" uuid NOT NULL,
+    "views" integer DEFAULT 1 NOT NULL,
+    "viewed_at" timestamp DEFAULT now() NOT NULL,
+    PRIMARY KEY ("user_id", "schema_name", "record_id")
+);
+CREATE INDEX "record_views_user_idx" ON "record_views" ("user_id", "viewed_at");
+
+-- Records each user marked as a favorite through /api/auth/favorites
+CREATE TABLE "favorites" (
+    "user_id" uuid NOT NULL,
+    "schema_name" text NOT NULL,
+    "record_id" uuid NOT NULL,
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    PRIMARY KEY ("user_id", "schema_name", "record_id")
+);
+
+-- Saved list configurations, one user's named filter, sort, columns and page size for a
+-- schema, managed through /api/views only; default_for lists the access levels a view is
+-- the schema default for, set by root. Not registered as a schema, and the data API
+-- refuses writes to it (ManagedSchemaGuard)
+CREATE TABLE "views" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "user_id" uuid NOT NULL,
+    "schema_name" text NOT NULL,
+    "name" text NOT NULL,
+    "filter" jsonb,
+    "sort" jsonb,
+    "columns" text[],
+    "page_size" integer CHECK ("page_size" > 0),
+    "default_for" text[] DEFAULT '{}'::text[] NOT NULL,
+    "access_read" uuid[] DEFAULT '{}'::uuid[],
+    "access_edit" uuid[] DEFAULT '{}'::uuid[],
+    "access_full" uuid[] DEFAULT '{}'::uuid[],
+    "access_deny" uuid[] DEFAULT '{}'::uuid[],
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    "trashed_at" timestamp,
+    "deleted_at" timestamp
+);
+CREATE UNIQUE INDEX "views_user_schema_name_idx" ON "views" ("user_id", "schema_name", "name")
+    WHERE "trashed_at" IS NULL AND "deleted_at" IS NULL;
+CREATE INDEX "views_schema_idx" ON "views" ("schema_name");
+
+-- Background exports started with POST /api/export; last_id/processed_rows/bytes_written
+-- are the resume checkpoint
+CREATE TABLE "export_jobs" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "schema_name" text NOT NULL,
+    "filter" jsonb DEFAULT '{}'::jsonb NOT NULL,
+    "format" text NOT NULL,
+    "compression" text DEFAULT 'none' NOT NULL,
+    "destination" text DEFAULT 'download' NOT NULL,
+    "upload_url" text,
+    "status" text DEFAULT 'pending' NOT NULL,
+    "columns" jsonb,
+    "last_id" text,
+    "processed_rows" bigint DEFAULT 0 NOT NULL,
+    "bytes_written" bigint DEFAULT 0 NOT NULL,
+    "checksum" text,
+    "error" text,
+    "created_by" uuid,
+    "access" uuid[],
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    "completed_at" timestamp
+);
+
+-- SFTP/FTP directories polled for CSV drops, imported into schema_name with an optional
+-- import template; processed files are moved to archive_path
+CREATE TABLE "ingestion_sources" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "name" text NOT NULL,
+    "url" text NOT NULL,
+    "username" text NOT NULL,
+    "password" text DEFAULT '' NOT NULL,
+    "schema_name" text NOT NULL,
+    "import_template" text,
+    "archive_path" text DEFAULT 'processed' NOT NULL,
+    "webhook_url" text,
+    "webhook_secret" text DEFAULT '' NOT NULL,
+    "enabled" boolean DEFAULT true NOT NULL,
+    "last_polled_at" timestamp,
+    "last_error" text,
+    "created_by" uuid,
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    CONSTRAINT "ingestion_sources_name_unique" UNIQUE("name")
+);
+
+-- One processed file per row; errors holds the failed row ranges and their messages
+CREATE TABLE "ingestion_runs" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "source_id" uuid NOT NULL REFERENCES "ingestion_sources"("id") ON DELETE CASCADE,
+    "file_name" text NOT NULL,
+    "status" text NOT NULL,
+    "records" integer DEFAULT 0 NOT NULL,
+    "imported" integer DEFAULT 0 NOT NULL,
+    "failed" integer DEFAULT 0 NOT NULL,
+    "errors" jsonb DEFAULT '[]'::jsonb NOT NULL,
+    "archived" boolean DEFAULT false NOT NULL,
+    "webhook_status" integer,
+    "created_at" timestamp DEFAULT now() NOT NULL
+);
+CREATE INDEX "ingestion_runs_source_idx" ON "ingestion_runs" ("source_id", "created_at");
+
+-- Inbound email webhooks: each message received becomes a record in schema_name, with
+-- field_mapping naming the record field for each message part (from, to, subject, text,
+-- html, message_id); signing_key verifies Mailgun's webhook signature
+CREATE TABLE "inbound_mailboxes" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "name" text NOT NULL,
+    "schema_name" text NOT NULL,
+    "provider" text NOT NULL,
+    "field_mapping" jsonb DEFAULT '{"from": "from", "to": "to", "subject": "subject", "text": "body"}'::jsonb NOT NULL,
+    "signing_key" text,
+    "enabled" boolean DEFAULT true NOT NULL,
+    "last_received_at" timestamp,
+    "created_by" uuid,
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    CONSTRAINT "inbound_mailboxes_name_unique" UNIQUE("name")
+);
+
+-- Per-user digest settings: digests of followed changes are emailed to "email" daily, or
+-- weekly on weekly_day (ISO, 1 = Monday), covering changes since last_digest_at
+CREATE TABLE "notification_preferences" (
+    "user_id" uuid PRIMARY KEY REFERENCES "users"("id") ON DELETE CASCADE,
+    "email" text NOT NULL,
+    "frequency" text DEFAULT 'daily' CHECK ("frequency" IN ('daily', 'weekly', 'off')) NOT NULL,
+    "weekly_day" smallint DEFAULT 1 CHECK ("weekly_day" BETWEEN 1 AND 7) NOT NULL,
+    "last_digest_at" timestamp DEFAULT now(),
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL
+);
+
+-- Schemas a user follows; changes to records matching where_clause go into their digest
+CREATE TABLE "notification_subscriptions" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "user_id" uuid NOT NULL REFERENCES "users"("id") ON DELETE CASCADE,
+    "schema_name" text NOT NULL,
+    "name" text,
+    "where_clause" jsonb DEFAULT '{}'::jsonb NOT NULL,
+    "created_at" timestamp DEFAULT now() NOT NULL
+);
+CREATE INDEX "notification_subscriptions_user_idx" ON "notification_subscriptions" ("user_id");
+
+-- Row changes awaiting the warehouse sync, written by monk_warehouse_outbox() on the
+-- tables the sync has enrolled. The sync deletes entries once their Parquet file is
+-- stored, so a change committed after later-numbered ones is still picked up
+CREATE TABLE "warehouse_outbox" (
+    "seq" bigserial PRIMARY KEY NOT NULL,
+    "table_name" text NOT NULL,
+    "record_id" uuid NOT NULL,
+    "operation" text NOT NULL,
+    "changed_at" timestamp DEFAULT clock_timestamp() NOT NULL
+);
+CREATE INDEX "warehouse_outbox_table_idx" ON "warehouse_outbox" ("table_name", "seq");
+
+-- Warehouse sync progress per schema
+CREATE TABLE "warehouse_sync_state" (
+    "schema_name" text PRIMARY KEY NOT NULL,
+    "rows_synced" bigint DEFAULT 0 NOT NULL,
+    "last_synced_at" timestamp
+);
+
+-- Attachment metadata for /api/file/:schema/:id; bytes live in the configured file
+-- storage backend (location 'local') or the presigned-upload bucket ('s3') under
+-- storage_key. Presigned uploads stay 'pending' until their bytes are confirmed
+CREATE TABLE "files" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "schema_name" text NOT NULL,
+    "record_id" uuid NOT NULL,
+    "filename" text DEFAULT '' NOT NULL,
+    "content_type" text DEFAULT 'application/octet-stream' NOT NULL,
+    "size" bigint NOT NULL,
+    "checksum" text NOT NULL,
+    "storage_key" text NOT NULL,
+    "location" text DEFAULT 'local' NOT NULL,
+    "status" text DEFAULT 'ready' NOT NULL,
+    "scan_result" text,
+    "variants" text[] DEFAULT '{}' NOT NULL,
+    "created_by" uuid,
+    "created_at" timestamp DEFAULT now() NOT NULL,
+    "updated_at" timestamp DEFAULT now() NOT NULL,
+    "deleted_at" timestamp
+);
+CREATE INDEX "files_record_idx" ON "files" ("schema_name", "record_id");
+CREATE INDEX "files_pending_idx" ON "files" ("created_at") WHERE "status" = 'pending';
+CREATE INDEX "files_storage_key_idx" ON "files" ("storage_key");
+
+-- Content-addressed attachment bytes, one per distinct upload; refs counts the live files
+-- sharing each blob and the bytes are removed when it reaches zero
+CREATE TABLE "file_blobs" (
+    "storage_key" text PRIMARY KEY NOT NULL,
+    "checksum" text NOT NULL,
+    "size" bigint NOT NULL,
+    "refs" integer NOT NULL,
+    "created_at" timestamp DEFAULT now() NOT NULL
+);
+
+-- Audit trail of record writes, one row per record, filled by the AuditLogger observer
+-- when security.enable_audit_logging is on and read through /api/audit
+CREATE TABLE "audit_log" (
+    "id" uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+    "schema_name" text NOT NULL,
+    "record_id" uuid,
+    "operation" text NOT NULL,
+    "actor_id" uuid,
+    "actor" text,
+    "address" text,
+    "changes" jsonb DEFAULT '{}' NOT NULL,
+    "created_at" timestamp DEFAULT now() NOT NULL
+);
+CREATE INDEX "audit_log_record_idx" ON "audit_log" ("schema_name", "record_id");
+CREATE INDEX "audit_log_created_at_idx" ON "audit_log" ("created_at");
+
+-- Row change notifications on the monk_changes channel, bridged into the API's
+-- event bus so writes from psql or ETL tools are seen like API writes
+CREATE OR REPLACE FUNCTION monk_notify_change() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('monk_changes', json_build_object(
+        'table', TG_TABLE_NAME,
+        'operation', lower(TG_OP),
+        'id', CASE WHEN TG_OP = 'DELETE' THEN OLD.id ELSE NEW.id END
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Row changes of warehouse-synced tables, queued for the warehouse sync; attached to a
+-- table by the sync when it first copies it
+CREATE OR REPLACE FUNCTION monk_warehouse_outbox() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO warehouse_outbox (table_name, record_id, operation) VALUES (
+        TG_TABLE_NAME,
+        CASE WHEN TG_OP = 'DELETE' THEN OLD.id ELSE NEW.id END,
+        lower(TG_OP)
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER "schemas_notify_change" AFTER INSERT OR UPDATE OR DELETE ON "schemas"
+    FOR EACH ROW EXECUTE FUNCTION monk_notify_change();
+CREATE TRIGGER "columns_notify_change" AFTER INSERT OR UPDATE OR DELETE ON "columns"
+    FOR EACH ROW EXECUTE FUNCTION monk_notify_change();
+
 -- Insert self-reference row to enable recursive schema discovery via data API
 -- This allows GET /api/data/schemas to work by querying the schema table itself
 INSERT INTO "schemas" (name, table_name, status, definition, field_count, json_checksum)
@@ -190,3 +595,151 @@ VALUES (
     '3',
     null
 );
+
+-- Insert import template schema registration to enable management via the data API
+-- This allows GET /api/data/import_templates to work
+INSERT INTO "schemas" (name, table_name, status, definition, field_count, json_checksum)
+VALUES (
+    'import_templates',
+    'import_templates',
+    'system',
+    '{
+        "type": "object",
+        "title": "Import Templates",
+        "description": "Reusable import configurations for recurring CSV and NDJSON imports",
+        "properties": {
+            "name": {
+                "type": "string",
+                "minLength": 1,
+                "maxLength": 100,
+                "description": "Unique template name",
+                "example": "monthly-contacts"
+            },
+            "schema_name": {
+                "type": "string",
+                "minLength": 1,
+                "maxLength": 100,
+                "description": "Schema the template imports into",
+                "example": "contacts"
+            },
+            "column_mapping": {
+                "type": "object",
+                "description": "Source column to schema field renames",
+                "additionalProperties": { "type": "string" }
+            },
+            "delimiter": {
+                "type": "string",
+                "minLength": 1,
+                "maxLength": 1,
+                "description": "Field delimiter for delimited text input",
+                "example": ","
+            },
+            "date_formats": {
+                "type": "object",
+                "description": "Field to chrono date format, e.g. %m/%d/%Y",
+                "additionalProperties": { "type": "string" }
+            },
+            "transform": {
+                "type": "array",
+                "description": "Transform steps applied after mapping and date parsing"
+            },
+            "dedupe_key": {
+                "type": "string",
+                "description": "Field whose first occurrence wins when rows repeat",
+                "example": "email"
+            }
+        },
+        "required": ["name", "schema_name"],
+        "additionalProperties": false
+    }',
+    '7',
+    null
+);
+
+-- Insert document template schema registration to enable management via the data API
+-- This allows GET /api/data/document_templates to work
+INSERT INTO "schemas" (name, table_name, status, definition, field_count, json_checksum)
+VALUES (
+    'document_templates',
+    'document_templates',
+    'system',
+    '{
+        "type": "object",
+        "title": "Document Templates",
+        "description": "Handlebars templates rendering records into HTML or PDF documents",
+        "properties": {
+            "name": {
+                "type": "string",
+                "minLength": 1,
+                "maxLength": 100,
+                "description": "Unique template name, passed as ?template= when rendering",
+                "example": "invoice"
+            },
+            "schema_name": {
+                "type": "string",
+                "maxLength": 100,
+                "description": "Schema whose records the template renders; any schema when empty",
+                "example": "invoices"
+            },
+            "format": {
+                "type": "string",
+                "enum": ["html", "pdf"],
+                "description": "Default output format",
+                "example": "pdf"
+            },
+            "body": {
+                "type": "string",
+                "minLength": 1,
+                "description": "Handlebars HTML rendered with the record and its relationships"
+            },
+            "filename": {
+                "type": "string",
+                "maxLength": 200,
+                "description": "Handlebars pattern for the stored file name, without extension",
+                "example": "invoice-{{number}}"
+            }
+        },
+        "required": ["name", "body"],
+        "additionalProperties": false
+    }',
+    '5',
+    null
+);
+
+-- Insert tag schema registration to enable management via the data API
+-- This allows GET /api/data/tags to work
+INSERT INTO "schemas" (name, table_name, status, definition, field_count, json_checksum)
+VALUES (
+    'tags',
+    'tags',
+    'system',
+    '{
+        "type": "object",
+        "title": "Tags",
+        "description": "Labels attached to records of any schema",
+        "properties": {
+            "name": {
+                "type": "string",
+                "minLength": 1,
+                "maxLength": 100,
+                "description": "Unique tag name, used by $tagged filters",
+                "example": "urgent"
+            },
+            "color": {
+                "type": "string",
+                "pattern": "^#[0-9a-fA-F]{6}$",
+                "description": "Display color as a hex triplet",
+                "example": "#d93f0b"
+            },
+            "description": {
+                "type": "string",
+                "maxLength": 500,
+                "description": "What the tag is used for"
+            }
+        },
+        "required": ["name"],
+        "additionalProperties": false
+    }',
+    '3',
+    null
+);
@@ -0,0 +1,178 @@
+// api/format.rs - Per-version wire-format adapters
+//
+// Handlers always build the canonical response shape (the current version's
+// `{"success": true, "data": ...}` envelope). When an older API version is still
+// being served, its adapter rewrites that canonical body into the shape clients
+// of that version expect, so handlers never branch on version themselves.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde_json::{json, Map, Value};
+
+/// API versions this server can serve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    /// Every version with mounted routes, oldest first
+    pub const SUPPORTED: &'static [ApiVersion] = &[ApiVersion::V1];
+
+    /// Parse "v1", "V1" or "1"
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().trim_start_matches(['v', 'V']) {
+            "1" => Some(ApiVersion::V1),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+        }
+    }
+
+    /// Route prefix for explicitly versioned requests, e.g. "/api/v1"
+    pub fn prefix(&self) -> String {
+        format!("/api/{}", self.as_str())
+    }
+
+    pub fn adapter(&self) -> &'static dyn FormatAdapter {
+        match self {
+            ApiVersion::V1 => &V1Format,
+        }
+    }
+}
+
+/// Rewrites canonical response bodies into a version's wire format
+pub trait FormatAdapter: Send + Sync {
+    /// Transform a canonical JSON response body
+    fn response(&self, body: Value) -> Value;
+
+    /// True when `response` is the identity, letting callers skip buffering the body
+    fn is_passthrough(&self) -> bool {
+        false
+    }
+}
+
+/// v1 is the canonical format
+pub struct V1Format;
+
+impl FormatAdapter for V1Format {
+    fn response(&self, body: Value) -> Value {
+        body
+    }
+
+    fn is_passthrough(&self) -> bool {
+        true
+    }
+}
+
+/// Response shapes served side by side while clients migrate to a new wire format
+///
+/// Orthogonal to `ApiVersion`: a tenant or client opts into the new shape (via the
+/// `wire_format_v2` feature flag, an `Accept: application/vnd.api+json` header or an
+/// explicit `X-Wire-Format` header) without changing versions. Canonical stays the
+/// default until adoption counters show the old shape can be retired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireFormat {
+    Canonical,
+    JsonApi,
+}
+
+impl WireFormat {
+    pub const JSON_API_MEDIA_TYPE: &'static str = "application/vnd.api+json";
+
+    /// Parse an `X-Wire-Format` header value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "canonical" | "v1" => Some(WireFormat::Canonical),
+            "jsonapi" | "json-api" | "v2" => Some(WireFormat::JsonApi),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WireFormat::Canonical => "canonical",
+            WireFormat::JsonApi => "jsonapi",
+        }
+    }
+
+    /// Rewrite a canonical response body; `resource_type` names records (the schema)
+    pub fn response(&self, body: Value, resource_type: Option<&str>, status: u16) -> Value {
+        match self {
+            WireFormat::Canonical => body,
+            WireFormat::JsonApi => json_api_response(body, resource_type, status),
+        }
+    }
+
+    /// Count a response served in this format
+    pub fn record_usage(&self) {
+        let counter = match self {
+            WireFormat::Canonical => &CANONICAL_RESPONSES,
+            WireFormat::JsonApi => &JSON_API_RESPONSES,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Responses served per format since startup
+    pub fn usage() -> Value {
+        json!({
+            WireFormat::Canonical.as_str(): CANONICAL_RESPONSES.load(Ordering::Relaxed),
+            WireFormat::JsonApi.as_str(): JSON_API_RESPONSES.load(Ordering::Relaxed),
+        })
+    }
+}
+
+static CANONICAL_RESPONSES: AtomicU64 = AtomicU64::new(0);
+static JSON_API_RESPONSES: AtomicU64 = AtomicU64::new(0);
+
+/// Canonical envelope -> JSON:API document
+///
+/// `{"success": true, "data": ..., "meta": ...}` becomes `{"data": ..., "meta": ...}`
+/// with records as `{type, id, attributes}` resource objects; error bodies become an
+/// `errors` array. Data without record ids (aggregates, stats) is passed through as is.
+fn json_api_response(body: Value, resource_type: Option<&str>, status: u16) -> Value {
+    let Value::Object(mut envelope) = body else { return body };
+
+    if envelope.get("error").and_then(Value::as_bool) == Some(true) {
+        let mut error = Map::new();
+        error.insert("status".to_string(), json!(status.to_string()));
+        if let Some(code) = envelope.remove("code") {
+            error.insert("code".to_string(), code);
+        }
+        if let Some(message) = envelope.remove("message") {
+            error.insert("title".to_string(), message);
+        }
+        if let Some(field_errors) = envelope.remove("field_errors") {
+            error.insert("meta".to_string(), json!({ "field_errors": field_errors }));
+        }
+        return json!({ "errors": [Value::Object(error)] });
+    }
+
+    let mut document = Map::new();
+    if let Some(data) = envelope.remove("data") {
+        document.insert("data".to_string(), json_api_data(data, resource_type));
+    }
+    if let Some(meta) = envelope.remove("meta") {
+        document.insert("meta".to_string(), meta);
+    }
+    Value::Object(document)
+}
+
+fn json_api_data(data: Value, resource_type: Option<&str>) -> Value {
+    match data {
+        Value::Array(items) => Value::Array(items.into_iter().map(|item| json_api_data(item, resource_type)).collect()),
+        Value::Object(mut attributes) if attributes.contains_key("id") => {
+            let id = attributes.remove("id").unwrap_or(Value::Null);
+            json!({
+                "type": resource_type.unwrap_or("record"),
+                "id": id,
+                "attributes": attributes,
+            })
+        }
+        other => other,
+    }
+}
@@ -1 +1,3 @@
 pub mod format;
+pub mod odata;
+pub mod postgrest;
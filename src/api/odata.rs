@@ -0,0 +1,551 @@
+// api/odata.rs - OData v4 query adapter for BI and low-code tools
+//
+// Translates the read-only subset of OData system query options that Excel,
+// Power BI and similar clients send ($filter, $select, $orderby, $top, $skip,
+// $count) into FilterData, and renders stored schemas as a CSDL $metadata
+// document. Everything downstream is the regular find pipeline.
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+use crate::filter::FilterData;
+
+/// Namespace for entity types in $metadata
+pub const ODATA_NAMESPACE: &str = "Monk";
+
+#[derive(Debug, Error)]
+pub enum ODataError {
+    #[error("Invalid $filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("Invalid {0}: {1}")]
+    InvalidOption(&'static str, String),
+}
+
+/// System query options accepted on /odata/:schema
+#[derive(Debug, Default, Deserialize)]
+pub struct ODataQuery {
+    #[serde(rename = "$filter")]
+    pub filter: Option<String>,
+    #[serde(rename = "$select")]
+    pub select: Option<String>,
+    #[serde(rename = "$orderby")]
+    pub orderby: Option<String>,
+    #[serde(rename = "$top")]
+    pub top: Option<i32>,
+    #[serde(rename = "$skip")]
+    pub skip: Option<i32>,
+    #[serde(rename = "$count")]
+    pub count: Option<bool>,
+}
+
+impl ODataQuery {
+    pub fn to_filter_data(&self) -> Result<FilterData, ODataError> {
+        let where_clause = match self.filter.as_deref().map(str::trim) {
+            Some(filter) if !filter.is_empty() => Some(parse_filter(filter)?),
+            _ => None,
+        };
+
+        let select = self.select.as_deref().map(|select| {
+            select.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect::<Vec<_>>()
+        });
+
+        let order = match self.orderby.as_deref() {
+            Some(orderby) => {
+                for part in orderby.split(',') {
+                    let mut tokens = part.split_whitespace();
+                    tokens.next().ok_or_else(|| ODataError::InvalidOption("$orderby", orderby.to_string()))?;
+                    match tokens.next() {
+                        None | Some("asc") | Some("desc") if tokens.next().is_none() => {}
+                        _ => return Err(ODataError::InvalidOption("$orderby", orderby.to_string())),
+                    }
+                }
+                Some(Value::String(orderby.to_string()))
+            }
+            None => None,
+        };
+
+        if self.top.is_some_and(|top| top < 0) {
+            return Err(ODataError::InvalidOption("$top", "must not be negative".to_string()));
+        }
+        if self.skip.is_some_and(|skip| skip < 0) {
+            return Err(ODataError::InvalidOption("$skip", "must not be negative".to_string()));
+        }
+
+        Ok(FilterData {
+            select,
+            where_clause,
+            order,
+            limit: self.top,
+            offset: self.skip,
+            ..Default::default()
+        })
+    }
+}
+
+/// Parse a $filter expression into a where clause
+///
+/// Supports eq/ne/gt/ge/lt/le, in (...), and/or/not, parentheses and the
+/// contains/startswith/endswith functions. Property paths like `customer/region`
+/// become relationship paths (`customer.region`).
+pub fn parse_filter(filter: &str) -> Result<Value, ODataError> {
+    let tokens = tokenize(filter)?;
+    let mut parser = FilterParser { tokens, pos: 0 };
+    let expr = parser.or_expr()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(token) => Err(ODataError::InvalidFilter(format!("unexpected {:?}", token))),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Literal(Value),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ODataError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => { tokens.push(Token::Open); i += 1; }
+            ')' => { tokens.push(Token::Close); i += 1; }
+            ',' => { tokens.push(Token::Comma); i += 1; }
+            '\'' => {
+                // Quoted string; '' is an escaped quote
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        Some('\'') if chars.get(i + 1) == Some(&'\'') => { value.push('\''); i += 2; }
+                        Some('\'') => { i += 1; break; }
+                        Some(&ch) => { value.push(ch); i += 1; }
+                        None => return Err(ODataError::InvalidFilter("unterminated string literal".to_string())),
+                    }
+                }
+                tokens.push(Token::Literal(Value::String(value)));
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !"(),'".contains(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(word_token(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn word_token(word: String) -> Token {
+    match word.as_str() {
+        "true" => return Token::Literal(Value::Bool(true)),
+        "false" => return Token::Literal(Value::Bool(false)),
+        "null" => return Token::Literal(Value::Null),
+        _ => {}
+    }
+    let first = word.chars().next().unwrap_or_default();
+    if first.is_ascii_digit() || first == '-' {
+        if let Ok(n) = word.parse::<i64>() {
+            return Token::Literal(json!(n));
+        }
+        if let Ok(n) = word.parse::<f64>() {
+            return Token::Literal(json!(n));
+        }
+        // Unquoted GUID and date/time literals
+        return Token::Literal(Value::String(word));
+    }
+    Token::Word(word)
+}
+
+struct FilterParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ODataError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            other => Err(ODataError::InvalidFilter(format!("expected {:?}, found {:?}", expected, other))),
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w == keyword)
+    }
+
+    fn or_expr(&mut self) -> Result<Value, ODataError> {
+        let mut terms = vec![self.and_expr()?];
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            terms.push(self.and_expr()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { json!({ "$or": terms }) })
+    }
+
+    fn and_expr(&mut self) -> Result<Value, ODataError> {
+        let mut terms = vec![self.unary_expr()?];
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            terms.push(self.unary_expr()?);
+        }
+        Ok(if terms.len() == 1 { terms.remove(0) } else { json!({ "$and": terms }) })
+    }
+
+    fn unary_expr(&mut self) -> Result<Value, ODataError> {
+        if self.peek_keyword("not") {
+            self.pos += 1;
+            return Ok(json!({ "$not": self.unary_expr()? }));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let expr = self.or_expr()?;
+            self.expect(Token::Close)?;
+            return Ok(expr);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Value, ODataError> {
+        let name = match self.next() {
+            Some(Token::Word(name)) => name,
+            other => return Err(ODataError::InvalidFilter(format!("expected a property, found {:?}", other))),
+        };
+
+        if let Some(pattern) = match name.as_str() {
+            "contains" => Some(("%", "%")),
+            "startswith" => Some(("", "%")),
+            "endswith" => Some(("%", "")),
+            _ => None,
+        } {
+            self.expect(Token::Open)?;
+            let property = self.property()?;
+            self.expect(Token::Comma)?;
+            let value = match self.next() {
+                Some(Token::Literal(Value::String(value))) => value,
+                other => return Err(ODataError::InvalidFilter(format!("{} requires a string, found {:?}", name, other))),
+            };
+            self.expect(Token::Close)?;
+            let like = format!("{}{}{}", pattern.0, escape_like(&value), pattern.1);
+            return Ok(json!({ property: { "$like": like } }));
+        }
+
+        let property = property_path(&name)?;
+        let operator = match self.next() {
+            Some(Token::Word(op)) => op,
+            other => return Err(ODataError::InvalidFilter(format!("expected an operator after {}, found {:?}", name, other))),
+        };
+
+        if operator == "in" {
+            self.expect(Token::Open)?;
+            let mut values = vec![self.literal()?];
+            while self.peek() == Some(&Token::Comma) {
+                self.pos += 1;
+                values.push(self.literal()?);
+            }
+            self.expect(Token::Close)?;
+            return Ok(json!({ property: { "$in": values } }));
+        }
+
+        let op = match operator.as_str() {
+            "eq" => "$eq",
+            "ne" => "$ne",
+            "gt" => "$gt",
+            "ge" => "$gte",
+            "lt" => "$lt",
+            "le" => "$lte",
+            other => return Err(ODataError::InvalidFilter(format!("unsupported operator '{}'", other))),
+        };
+        let value = self.literal()?;
+        Ok(json!({ property: { op: value } }))
+    }
+
+    fn property(&mut self) -> Result<String, ODataError> {
+        match self.next() {
+            Some(Token::Word(name)) => property_path(&name),
+            other => Err(ODataError::InvalidFilter(format!("expected a property, found {:?}", other))),
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value, ODataError> {
+        match self.next() {
+            Some(Token::Literal(value)) => Ok(value),
+            other => Err(ODataError::InvalidFilter(format!("expected a literal, found {:?}", other))),
+        }
+    }
+}
+
+fn property_path(name: &str) -> Result<String, ODataError> {
+    let valid = name
+        .split('/')
+        .all(|segment| !segment.is_empty() && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    if !valid {
+        return Err(ODataError::InvalidFilter(format!("invalid property '{}'", name)));
+    }
+    Ok(name.replace('/', "."))
+}
+
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Entity set payload: `{"@odata.context": ..., "value": [...]}` plus `@odata.count` when requested
+pub fn entity_set(schema: &str, records: Value, count: Option<i64>) -> Value {
+    let mut body = Map::new();
+    body.insert("@odata.context".to_string(), json!(format!("$metadata#{}", schema)));
+    if let Some(count) = count {
+        body.insert("@odata.count".to_string(), json!(count));
+    }
+    body.insert("value".to_string(), records);
+    Value::Object(body)
+}
+
+/// Service document listing one entity set per schema
+pub fn service_document(schemas: &[String]) -> Value {
+    let sets: Vec<Value> = schemas
+        .iter()
+        .map(|name| json!({ "name": name, "kind": "EntitySet", "url": name }))
+        .collect();
+    json!({ "@odata.context": "$metadata", "value": sets })
+}
+
+/// CSDL (EDMX 4.0) document for (schema name, JSON Schema definition) pairs
+pub fn metadata_document(schemas: &[(String, Value)]) -> String {
+    let mut types = String::new();
+    let mut sets = String::new();
+
+    for (name, definition) in schemas {
+        let name = xml_escape(name);
+        types += &format!("      <EntityType Name=\"{}\">\n        <Key><PropertyRef Name=\"id\"/></Key>\n", name);
+        types += "        <Property Name=\"id\" Type=\"Edm.Guid\" Nullable=\"false\"/>\n";
+        types += "        <Property Name=\"created_at\" Type=\"Edm.DateTimeOffset\"/>\n";
+        types += "        <Property Name=\"updated_at\" Type=\"Edm.DateTimeOffset\"/>\n";
+
+        let required: Vec<&str> = definition
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if let Some(properties) = definition.get("properties").and_then(Value::as_object) {
+            for (property, spec) in properties {
+                if ["id", "created_at", "updated_at"].contains(&property.as_str()) {
+                    continue;
+                }
+                let nullable = if required.contains(&property.as_str()) { " Nullable=\"false\"" } else { "" };
+                types += &format!(
+                    "        <Property Name=\"{}\" Type=\"{}\"{}/>\n",
+                    xml_escape(property),
+                    edm_type(spec),
+                    nullable
+                );
+            }
+        }
+        types += "      </EntityType>\n";
+        sets += &format!(
+            "        <EntitySet Name=\"{}\" EntityType=\"{}.{}\"/>\n",
+            name, ODATA_NAMESPACE, name
+        );
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <edmx:Edmx Version=\"4.0\" xmlns:edmx=\"http://docs.oasis-open.org/odata/ns/edmx\">\n\
+         \x20 <edmx:DataServices>\n\
+         \x20   <Schema Namespace=\"{ns}\" xmlns=\"http://docs.oasis-open.org/odata/ns/edm\">\n\
+         {types}\
+         \x20     <EntityContainer Name=\"Container\">\n\
+         {sets}\
+         \x20     </EntityContainer>\n\
+         \x20   </Schema>\n\
+         \x20 </edmx:DataServices>\n\
+         </edmx:Edmx>\n",
+        ns = ODATA_NAMESPACE,
+        types = types,
+        sets = sets,
+    )
+}
+
+/// EDM primitive type for a JSON Schema property
+fn edm_type(spec: &Value) -> &'static str {
+    let format = spec.get("format").and_then(Value::as_str);
+    match (spec.get("type").and_then(Value::as_str), format) {
+        (Some("string"), Some("uuid")) => "Edm.Guid",
+        (Some("string"), Some("date-time")) => "Edm.DateTimeOffset",
+        (Some("string"), Some("date")) => "Edm.Date",
+        (Some("integer"), _) => "Edm.Int64",
+        (Some("number"), _) => "Edm.Double",
+        (Some("boolean"), _) => "Edm.Boolean",
+        _ => "Edm.String",
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::filter_where::FilterWhere;
+    use crate::filter::FilterWhereOptions;
+
+    fn query(filter: Option<&str>, orderby: Option<&str>, top: Option<i32>, skip: Option<i32>) -> ODataQuery {
+        ODataQuery {
+            filter: filter.map(String::from),
+            orderby: orderby.map(String::from),
+            top,
+            skip,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_comparisons_and_functions() {
+        assert_eq!(parse_filter("name eq 'O''Brien'").unwrap(), json!({ "name": { "$eq": "O'Brien" } }));
+        assert_eq!(parse_filter("age ne 30").unwrap(), json!({ "age": { "$ne": 30 } }));
+        assert_eq!(parse_filter("age ge 2.5").unwrap(), json!({ "age": { "$gte": 2.5 } }));
+        assert_eq!(parse_filter("age le -1").unwrap(), json!({ "age": { "$lte": -1 } }));
+        assert_eq!(parse_filter("active eq true").unwrap(), json!({ "active": { "$eq": true } }));
+        assert_eq!(parse_filter("manager_id eq null").unwrap(), json!({ "manager_id": { "$eq": null } }));
+        assert_eq!(
+            parse_filter("created_at gt 2024-01-01T00:00:00Z").unwrap(),
+            json!({ "created_at": { "$gt": "2024-01-01T00:00:00Z" } })
+        );
+        assert_eq!(
+            parse_filter("status in ('open', 'held', 3)").unwrap(),
+            json!({ "status": { "$in": ["open", "held", 3] } })
+        );
+        assert_eq!(parse_filter("customer/region eq 'EU'").unwrap(), json!({ "customer.region": { "$eq": "EU" } }));
+        assert_eq!(parse_filter("contains(name, '50%_off')").unwrap(), json!({ "name": { "$like": "%50\\%\\_off%" } }));
+        assert_eq!(parse_filter("startswith(name, 'Al')").unwrap(), json!({ "name": { "$like": "Al%" } }));
+        assert_eq!(parse_filter("endswith(name, 'ce')").unwrap(), json!({ "name": { "$like": "%ce" } }));
+    }
+
+    #[test]
+    fn test_filter_logic_and_precedence() {
+        assert_eq!(
+            parse_filter("a eq 1 or b eq 2 and c eq 3").unwrap(),
+            json!({ "$or": [{ "a": { "$eq": 1 } }, { "$and": [{ "b": { "$eq": 2 } }, { "c": { "$eq": 3 } }] }] })
+        );
+        assert_eq!(
+            parse_filter("(a eq 1 or b eq 2) and not c eq 3").unwrap(),
+            json!({ "$and": [
+                { "$or": [{ "a": { "$eq": 1 } }, { "b": { "$eq": 2 } }] },
+                { "$not": { "c": { "$eq": 3 } } }
+            ] })
+        );
+    }
+
+    #[test]
+    fn test_malformed_filters_are_rejected() {
+        for filter in [
+            "name eq 'unterminated",
+            "name eq",
+            "name like 'x'",
+            "eq 1",
+            "(a eq 1",
+            "a eq 1)",
+            "a eq 1 b eq 2",
+            "contains(name, 3)",
+            "bad-name eq 1",
+            "a/ eq 1",
+            "status in ()",
+        ] {
+            assert!(matches!(parse_filter(filter), Err(ODataError::InvalidFilter(_))), "{}", filter);
+        }
+    }
+
+    #[test]
+    fn test_orderby_top_and_skip() {
+        let filter_data = query(Some("  "), Some("age desc,name"), Some(10), Some(20)).to_filter_data().unwrap();
+        assert_eq!(filter_data.where_clause, None);
+        assert_eq!(filter_data.order, Some(json!("age desc,name")));
+        assert_eq!((filter_data.limit, filter_data.offset), (Some(10), Some(20)));
+
+        for orderby in ["age sideways", "age desc extra", "age,,name"] {
+            assert!(matches!(
+                query(None, Some(orderby), None, None).to_filter_data(),
+                Err(ODataError::InvalidOption("$orderby", _))
+            ), "{}", orderby);
+        }
+        assert!(matches!(query(None, None, Some(-1), None).to_filter_data(), Err(ODataError::InvalidOption("$top", _))));
+        assert!(matches!(query(None, None, None, Some(-1)).to_filter_data(), Err(ODataError::InvalidOption("$skip", _))));
+    }
+
+    #[test]
+    fn test_metadata_document() {
+        let definition = json!({
+            "required": ["name"],
+            "properties": {
+                "id": { "type": "string", "format": "uuid" },
+                "name": { "type": "string" },
+                "owner_id": { "type": "string", "format": "uuid" },
+                "born": { "type": "string", "format": "date" },
+                "seen_at": { "type": "string", "format": "date-time" },
+                "visits": { "type": "integer" },
+                "score": { "type": "number" },
+                "active": { "type": "boolean" },
+                "tags": { "type": "array" }
+            }
+        });
+        let document = metadata_document(&[("a&b".to_string(), definition)]);
+
+        assert!(document.contains("<Schema Namespace=\"Monk\""));
+        assert!(document.contains("<EntityType Name=\"a&amp;b\">"));
+        assert!(document.contains("<EntitySet Name=\"a&amp;b\" EntityType=\"Monk.a&amp;b\"/>"));
+        assert_eq!(document.matches("<Property Name=\"id\"").count(), 1);
+        for property in [
+            "<Property Name=\"name\" Type=\"Edm.String\" Nullable=\"false\"/>",
+            "<Property Name=\"owner_id\" Type=\"Edm.Guid\"/>",
+            "<Property Name=\"born\" Type=\"Edm.Date\"/>",
+            "<Property Name=\"seen_at\" Type=\"Edm.DateTimeOffset\"/>",
+            "<Property Name=\"visits\" Type=\"Edm.Int64\"/>",
+            "<Property Name=\"score\" Type=\"Edm.Double\"/>",
+            "<Property Name=\"active\" Type=\"Edm.Boolean\"/>",
+            "<Property Name=\"tags\" Type=\"Edm.String\"/>",
+        ] {
+            assert!(document.contains(property), "{}", property);
+        }
+    }
+
+    #[test]
+    fn test_nested_groups_number_placeholders_in_order() {
+        let where_data = parse_filter("(a eq 1 or (b eq 2 and not c eq 3)) and (d eq 4 or e eq 5) and f eq 6").unwrap();
+        let options = FilterWhereOptions { include_trashed: true, include_deleted: true, ..Default::default() };
+        let (sql, params) = FilterWhere::generate(&where_data, 0, &options).unwrap();
+
+        let placeholders: Vec<String> = sql
+            .split('$')
+            .skip(1)
+            .map(|rest| rest.chars().take_while(|c| c.is_ascii_digit()).collect())
+            .collect();
+        assert_eq!(placeholders, ["1", "2", "3", "4", "5", "6"], "{}", sql);
+        assert_eq!(params, (1..=6).map(|n| json!(n)).collect::<Vec<_>>());
+    }
+}
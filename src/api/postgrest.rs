@@ -0,0 +1,331 @@
+// api/postgrest.rs - PostgREST query dialect for GET /api/data/:schema
+//
+// Lets clients migrating from PostgREST keep their query strings:
+// `?name=eq.Alice&age=gt.30&order=age.desc&select=id,name&or=(a.eq.1,b.lt.2)`
+// is translated into the equivalent FilterData. Only horizontal filtering,
+// column selection, ordering and pagination are supported; resource embedding
+// and column renaming are rejected.
+
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::filter::FilterData;
+
+/// Query parameters with API meaning; every other parameter is a column filter
+pub const RESERVED_PARAMS: &[&str] = &[
+    "select", "order", "limit", "offset", "meta", "count_mode", "include_total", "cursor", "debug", "dry_run",
+];
+
+#[derive(Debug, Error)]
+pub enum PostgrestError {
+    #[error("Invalid filter '{0}': {1}")]
+    InvalidFilter(String, String),
+
+    #[error("Invalid {0}: {1}")]
+    InvalidParam(&'static str, String),
+}
+
+/// Whether the query string uses the PostgREST dialect (column filters, select or order)
+pub fn is_postgrest_query(params: &[(String, String)]) -> bool {
+    params
+        .iter()
+        .any(|(key, _)| key == "select" || key == "order" || !RESERVED_PARAMS.contains(&key.as_str()))
+}
+
+/// Translate PostgREST query parameters into FilterData
+///
+/// Repeated filters are combined with AND, as in PostgREST.
+pub fn to_filter_data(params: &[(String, String)]) -> Result<FilterData, PostgrestError> {
+    let mut conditions = Vec::new();
+    let mut filter_data = FilterData::default();
+
+    for (key, value) in params {
+        match key.as_str() {
+            "select" => filter_data.select = Some(parse_select(value)?),
+            "order" => filter_data.order = Some(Value::String(parse_order(value)?)),
+            "limit" => filter_data.limit = Some(parse_count("limit", value)?),
+            "offset" => filter_data.offset = Some(parse_count("offset", value)?),
+            "meta" | "count_mode" | "include_total" | "cursor" | "debug" | "dry_run" => {}
+            "or" | "and" | "not.or" | "not.and" => {
+                let (negated, op) = match key.strip_prefix("not.") {
+                    Some(op) => (true, op),
+                    None => (false, key.as_str()),
+                };
+                let group = parse_group(op, value)?;
+                conditions.push(if negated { json!({ "$not": group }) } else { group });
+            }
+            column => conditions.push(parse_condition(column, value)?),
+        }
+    }
+
+    filter_data.where_clause = match conditions.len() {
+        0 => None,
+        1 => conditions.pop(),
+        _ => Some(json!({ "$and": conditions })),
+    };
+    Ok(filter_data)
+}
+
+fn parse_select(value: &str) -> Result<Vec<String>, PostgrestError> {
+    let columns: Vec<String> = value.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect();
+    if columns.iter().any(|c| c.contains(':') || c.contains('(')) {
+        return Err(PostgrestError::InvalidParam("select", "renaming and embedded resources are not supported".to_string()));
+    }
+    Ok(columns)
+}
+
+/// "age.desc,name.asc.nullslast" -> "age desc, name asc"
+fn parse_order(value: &str) -> Result<String, PostgrestError> {
+    let mut terms = Vec::new();
+    for term in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let mut parts = term.split('.');
+        let column = parts.next().unwrap_or_default();
+        let direction = match parts.next() {
+            None | Some("asc") | Some("nullsfirst") | Some("nullslast") => "asc",
+            Some("desc") => "desc",
+            Some(other) => return Err(PostgrestError::InvalidParam("order", format!("unknown direction '{}'", other))),
+        };
+        terms.push(format!("{} {}", column, direction));
+    }
+    Ok(terms.join(", "))
+}
+
+fn parse_count(name: &'static str, value: &str) -> Result<i32, PostgrestError> {
+    value
+        .parse::<i32>()
+        .ok()
+        .filter(|n| *n >= 0)
+        .ok_or_else(|| PostgrestError::InvalidParam(name, format!("'{}' is not a non-negative integer", value)))
+}
+
+/// `column=[not.]op.value`
+fn parse_condition(column: &str, expression: &str) -> Result<Value, PostgrestError> {
+    let invalid = |reason: &str| PostgrestError::InvalidFilter(format!("{}={}", column, expression), reason.to_string());
+
+    let (negated, expression) = match expression.strip_prefix("not.") {
+        Some(rest) => (true, rest),
+        None => (false, expression),
+    };
+    let (op, value) = expression.split_once('.').ok_or_else(|| invalid("expected operator.value"))?;
+
+    let condition = match op {
+        "eq" | "neq" | "gt" | "gte" | "lt" | "lte" => {
+            let op = if op == "neq" { "$ne".to_string() } else { format!("${}", op) };
+            json!({ column: { op: scalar(value) } })
+        }
+        "like" | "ilike" => json!({ column: { format!("${}", op): value.replace('*', "%") } }),
+        "in" => {
+            let list = value
+                .strip_prefix('(')
+                .and_then(|v| v.strip_suffix(')'))
+                .ok_or_else(|| invalid("in requires a parenthesized list"))?;
+            let values: Vec<Value> = split_top_level(list).iter().map(|v| scalar(v)).collect();
+            json!({ column: { "$in": values } })
+        }
+        "is" => match value {
+            "null" => json!({ column: { "$eq": Value::Null } }),
+            "true" => json!({ column: { "$eq": true } }),
+            "false" => json!({ column: { "$eq": false } }),
+            _ => return Err(invalid("is accepts null, true or false")),
+        },
+        _ => return Err(invalid(&format!("unsupported operator '{}'", op))),
+    };
+
+    Ok(if negated { json!({ "$not": condition }) } else { condition })
+}
+
+/// `or=(a.eq.1,and(b.gt.2,c.lt.3))`
+fn parse_group(op: &str, value: &str) -> Result<Value, PostgrestError> {
+    let inner = value
+        .strip_prefix('(')
+        .and_then(|v| v.strip_suffix(')'))
+        .ok_or_else(|| PostgrestError::InvalidFilter(format!("{}={}", op, value), "expected a parenthesized list".to_string()))?;
+
+    let mut members = Vec::new();
+    for item in split_top_level(inner) {
+        let (negated, item) = match item.strip_prefix("not.") {
+            Some(rest) if rest.starts_with("and(") || rest.starts_with("or(") => (true, rest),
+            _ => (false, item.as_str()),
+        };
+        let member = if let Some(nested) = item.strip_prefix("and").filter(|n| n.starts_with('(')) {
+            parse_group("and", nested)?
+        } else if let Some(nested) = item.strip_prefix("or").filter(|n| n.starts_with('(')) {
+            parse_group("or", nested)?
+        } else {
+            let (column, expression) = item.split_once('.').ok_or_else(|| {
+                PostgrestError::InvalidFilter(item.to_string(), "expected column.operator.value".to_string())
+            })?;
+            parse_condition(column, expression)?
+        };
+        members.push(if negated { json!({ "$not": member }) } else { member });
+    }
+
+    Ok(json!({ format!("${}", op): members }))
+}
+
+/// Split on commas outside parentheses and double quotes
+fn split_top_level(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quoted = false;
+
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                items.push(std::mem::take(&mut current).trim().to_string());
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        items.push(current.trim().to_string());
+    }
+    items
+}
+
+/// Numbers become JSON numbers; double-quoted values stay strings
+fn scalar(value: &str) -> Value {
+    if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        return Value::String(quoted.to_string());
+    }
+    if let Ok(n) = value.parse::<i64>() {
+        return json!(n);
+    }
+    if let Ok(n) = value.parse::<f64>() {
+        return json!(n);
+    }
+    Value::String(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(query: &[(&str, &str)]) -> Vec<(String, String)> {
+        query.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_debug_and_dry_run_are_not_filters() {
+        assert!(!is_postgrest_query(&params(&[("debug", "pipeline"), ("dry_run", "true")])));
+
+        let query = params(&[("select", "id,name"), ("debug", "pipeline"), ("dry_run", "true")]);
+        assert!(is_postgrest_query(&query));
+        let filter_data = to_filter_data(&query).unwrap();
+        assert_eq!(filter_data.select, Some(vec!["id".to_string(), "name".to_string()]));
+        assert_eq!(filter_data.where_clause, None);
+    }
+
+    #[test]
+    fn test_cursor_is_not_a_filter() {
+        let select = to_filter_data(&params(&[("select", "id,name"), ("cursor", "eyJpZCI6IjEifQ")])).unwrap();
+        assert_eq!(select.select, Some(vec!["id".to_string(), "name".to_string()]));
+        assert_eq!(select.where_clause, None);
+
+        let order = to_filter_data(&params(&[("order", "name.desc"), ("cursor", "eyJpZCI6IjEifQ")])).unwrap();
+        assert_eq!(order.order, Some(json!("name desc")));
+        assert_eq!(order.where_clause, None);
+    }
+
+    fn where_of(query: &[(&str, &str)]) -> Value {
+        to_filter_data(&params(query)).unwrap().where_clause.unwrap()
+    }
+
+    #[test]
+    fn test_is_postgrest_query() {
+        assert!(!is_postgrest_query(&[]));
+        assert!(!is_postgrest_query(&params(&[("limit", "10"), ("offset", "20"), ("include_total", "true")])));
+        assert!(is_postgrest_query(&params(&[("order", "name.asc")])));
+        assert!(is_postgrest_query(&params(&[("limit", "10"), ("name", "eq.Alice")])));
+    }
+
+    #[test]
+    fn test_comparison_operators() {
+        assert_eq!(where_of(&[("name", "eq.Alice")]), json!({ "name": { "$eq": "Alice" } }));
+        assert_eq!(where_of(&[("age", "neq.30")]), json!({ "age": { "$ne": 30 } }));
+        assert_eq!(where_of(&[("age", "gt.30")]), json!({ "age": { "$gt": 30 } }));
+        assert_eq!(where_of(&[("age", "gte.30.5")]), json!({ "age": { "$gte": 30.5 } }));
+        assert_eq!(where_of(&[("age", "lt.30")]), json!({ "age": { "$lt": 30 } }));
+        assert_eq!(where_of(&[("code", "lte.\"007\"")]), json!({ "code": { "$lte": "007" } }));
+    }
+
+    #[test]
+    fn test_pattern_list_and_is_operators() {
+        assert_eq!(where_of(&[("name", "like.Al*")]), json!({ "name": { "$like": "Al%" } }));
+        assert_eq!(where_of(&[("name", "ilike.*ice")]), json!({ "name": { "$ilike": "%ice" } }));
+        assert_eq!(
+            where_of(&[("status", "in.(active,\"on,hold\",3)")]),
+            json!({ "status": { "$in": ["active", "on,hold", 3] } })
+        );
+        assert_eq!(where_of(&[("status", "in.()")]), json!({ "status": { "$in": [] } }));
+        assert_eq!(where_of(&[("deleted_at", "is.null")]), json!({ "deleted_at": { "$eq": null } }));
+        assert_eq!(where_of(&[("active", "is.true")]), json!({ "active": { "$eq": true } }));
+        assert_eq!(where_of(&[("active", "is.false")]), json!({ "active": { "$eq": false } }));
+    }
+
+    #[test]
+    fn test_negation_and_groups() {
+        assert_eq!(where_of(&[("age", "not.gt.30")]), json!({ "$not": { "age": { "$gt": 30 } } }));
+        assert_eq!(
+            where_of(&[("status", "not.in.(a,b)")]),
+            json!({ "$not": { "status": { "$in": ["a", "b"] } } })
+        );
+        assert_eq!(
+            where_of(&[("or", "(a.eq.1,and(b.gt.2,c.not.lt.3))")]),
+            json!({ "$or": [
+                { "a": { "$eq": 1 } },
+                { "$and": [{ "b": { "$gt": 2 } }, { "$not": { "c": { "$lt": 3 } } }] }
+            ] })
+        );
+        assert_eq!(
+            where_of(&[("not.and", "(a.eq.1,not.or(b.eq.2,c.eq.3))")]),
+            json!({ "$not": { "$and": [
+                { "a": { "$eq": 1 } },
+                { "$not": { "$or": [{ "b": { "$eq": 2 } }, { "c": { "$eq": 3 } }] } }
+            ] } })
+        );
+        // Repeated filters are combined with AND
+        assert_eq!(
+            where_of(&[("age", "gt.30"), ("age", "lt.40")]),
+            json!({ "$and": [{ "age": { "$gt": 30 } }, { "age": { "$lt": 40 } }] })
+        );
+    }
+
+    #[test]
+    fn test_select_order_and_paging() {
+        let filter_data = to_filter_data(&params(&[
+            ("select", " id, name ,,email"),
+            ("order", "age.desc,name.asc.nullslast,created_at"),
+            ("limit", "10"),
+            ("offset", "20"),
+        ]))
+        .unwrap();
+        assert_eq!(filter_data.select, Some(vec!["id".to_string(), "name".to_string(), "email".to_string()]));
+        assert_eq!(filter_data.order, Some(json!("age desc, name asc, created_at asc")));
+        assert_eq!((filter_data.limit, filter_data.offset), (Some(10), Some(20)));
+        assert_eq!(filter_data.where_clause, None);
+    }
+
+    #[test]
+    fn test_malformed_input_is_rejected() {
+        let rejected = |query: &[(&str, &str)]| to_filter_data(&params(query)).unwrap_err();
+
+        assert!(matches!(rejected(&[("name", "Alice")]), PostgrestError::InvalidFilter(..)));
+        assert!(matches!(rejected(&[("name", "between.1")]), PostgrestError::InvalidFilter(..)));
+        assert!(matches!(rejected(&[("status", "in.a,b")]), PostgrestError::InvalidFilter(..)));
+        assert!(matches!(rejected(&[("active", "is.maybe")]), PostgrestError::InvalidFilter(..)));
+        assert!(matches!(rejected(&[("or", "a.eq.1,b.eq.2")]), PostgrestError::InvalidFilter(..)));
+        assert!(matches!(rejected(&[("or", "(a)")]), PostgrestError::InvalidFilter(..)));
+        assert!(matches!(rejected(&[("select", "id,full_name:name")]), PostgrestError::InvalidParam("select", _)));
+        assert!(matches!(rejected(&[("select", "id,orders(id)")]), PostgrestError::InvalidParam("select", _)));
+        assert!(matches!(rejected(&[("order", "age.sideways")]), PostgrestError::InvalidParam("order", _)));
+        assert!(matches!(rejected(&[("limit", "-1")]), PostgrestError::InvalidParam("limit", _)));
+        assert!(matches!(rejected(&[("offset", "ten")]), PostgrestError::InvalidParam("offset", _)));
+    }
+}
@@ -1,9 +1,15 @@
-use chrono::{Duration, Utc};
-use jsonwebtoken::{encode, EncodingKey, Header};
+pub mod oidc;
+pub mod password;
+pub mod signed_url;
+pub mod totp;
+
+use chrono::Duration;
+use jsonwebtoken::{decode, encode, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::config;
+use crate::clock::{Clock, SystemClock};
+use crate::config::{self, SecurityConfig};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
@@ -14,13 +20,23 @@ pub struct Claims {
     pub user_id: Uuid,
     pub exp: i64,
     pub iat: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// Reason given to POST /api/auth/sudo; only elevated tokens carry it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elevation: Option<String>,
+    /// Whether a TOTP code was verified for this session
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mfa: bool,
 }
 
 impl Claims {
     pub fn new(tenant: String, user: String, database: String, access: String, user_id: Uuid) -> Self {
-        let now = Utc::now();
-        let expiry_hours = config::config().security.jwt_expiry_hours;
-        let exp = (now + Duration::hours(expiry_hours as i64)).timestamp();
+        let now = crate::clock::now();
+        let security = &config::config().security;
+        let exp = (now + Duration::hours(security.jwt_expiry_hours as i64)).timestamp();
         
         Self {
             tenant,
@@ -30,14 +46,37 @@ impl Claims {
             user_id,
             exp,
             iat: now.timestamp(),
+            iss: Some(security.jwt_issuer.clone()).filter(|iss| !iss.is_empty()),
+            aud: Some(security.jwt_audience.clone()).filter(|aud| !aud.is_empty()),
+            elevation: None,
+            mfa: false,
         }
     }
+
+    /// The same claims marked as MFA-verified
+    pub fn with_mfa(mut self, mfa: bool) -> Self {
+        self.mfa = mfa;
+        self
+    }
+
+    /// Root-scoped claims for a sudo session, expiring after `sudo_expiry_minutes`
+    pub fn elevated(tenant: String, user: String, database: String, user_id: Uuid, reason: String) -> Self {
+        let minutes = config::config().security.sudo_expiry_minutes as i64;
+        let mut claims = Self::new(tenant, user, database, "root".to_string(), user_id);
+        claims.exp = (crate::clock::now() + Duration::minutes(minutes)).timestamp();
+        claims.elevation = Some(reason);
+        claims
+    }
 }
 
 #[derive(Debug)]
 pub enum JwtError {
     TokenGeneration(String),
     InvalidSecret,
+    InvalidKey(String),
+    UnsupportedAlgorithm(String),
+    Expired,
+    InvalidToken(String),
 }
 
 impl std::fmt::Display for JwtError {
@@ -45,22 +84,193 @@ impl std::fmt::Display for JwtError {
         match self {
             JwtError::TokenGeneration(msg) => write!(f, "JWT generation error: {}", msg),
             JwtError::InvalidSecret => write!(f, "Invalid JWT secret"),
+            JwtError::InvalidKey(msg) => write!(f, "Invalid JWT key: {}", msg),
+            JwtError::UnsupportedAlgorithm(alg) => write!(f, "Unsupported JWT algorithm '{}': use HS256 or RS256", alg),
+            JwtError::Expired => write!(f, "JWT token expired"),
+            JwtError::InvalidToken(msg) => write!(f, "Invalid JWT token: {}", msg),
         }
     }
 }
 
 impl std::error::Error for JwtError {}
 
+/// The configured signing algorithm
+fn algorithm(security: &SecurityConfig) -> Result<Algorithm, JwtError> {
+    let name = &security.jwt_algorithm;
+    match name.to_uppercase().as_str() {
+        "HS256" => Ok(Algorithm::HS256),
+        "RS256" => Ok(Algorithm::RS256),
+        _ => Err(JwtError::UnsupportedAlgorithm(name.clone())),
+    }
+}
+
+fn encoding_key(security: &SecurityConfig, algorithm: Algorithm) -> Result<EncodingKey, JwtError> {
+    match algorithm {
+        Algorithm::RS256 => EncodingKey::from_rsa_pem(security.jwt_private_key.as_bytes())
+            .map_err(|e| JwtError::InvalidKey(e.to_string())),
+        _ if security.jwt_secret.is_empty() => Err(JwtError::InvalidSecret),
+        _ => Ok(EncodingKey::from_secret(security.jwt_secret.as_bytes())),
+    }
+}
+
+fn decoding_key(security: &SecurityConfig, algorithm: Algorithm) -> Result<DecodingKey, JwtError> {
+    match algorithm {
+        Algorithm::RS256 => DecodingKey::from_rsa_pem(security.jwt_public_key.as_bytes())
+            .map_err(|e| JwtError::InvalidKey(e.to_string())),
+        _ if security.jwt_secret.is_empty() => Err(JwtError::InvalidSecret),
+        _ => Ok(DecodingKey::from_secret(security.jwt_secret.as_bytes())),
+    }
+}
+
 pub fn generate_jwt(claims: Claims) -> Result<String, JwtError> {
-    let secret = &config::config().security.jwt_secret;
-    
-    if secret.is_empty() {
-        return Err(JwtError::InvalidSecret);
-    }
-    
-    let encoding_key = EncodingKey::from_secret(secret.as_bytes());
-    let header = Header::default();
-    
-    encode(&header, &claims, &encoding_key)
+    sign(&config::config().security, &claims)
+}
+
+fn sign(security: &SecurityConfig, claims: &Claims) -> Result<String, JwtError> {
+    let algorithm = algorithm(security)?;
+    let encoding_key = encoding_key(security, algorithm)?;
+
+    encode(&Header::new(algorithm), claims, &encoding_key)
         .map_err(|e| JwtError::TokenGeneration(e.to_string()))
 }
+
+/// Verify a token's signature, expiry and configured issuer/audience, returning its claims
+///
+/// Tokens signed with any other algorithm than the configured one are rejected.
+/// `allow_expired` skips only the expiry check, for refresh flows that reissue a lapsed token.
+pub fn validate_jwt(token: &str, allow_expired: bool) -> Result<Claims, JwtError> {
+    validate(&config::config().security, &SystemClock, token, allow_expired)
+}
+
+fn validate(security: &SecurityConfig, clock: &dyn Clock, token: &str, allow_expired: bool) -> Result<Claims, JwtError> {
+    let algorithm = algorithm(security)?;
+    let decoding_key = decoding_key(security, algorithm)?;
+
+    // Expiry is checked against `clock` below rather than by jsonwebtoken's system time
+    let mut validation = Validation::new(algorithm);
+    validation.validate_exp = false;
+    if !security.jwt_issuer.is_empty() {
+        validation.set_issuer(&[&security.jwt_issuer]);
+        validation.required_spec_claims.insert("iss".to_string());
+    }
+    if security.jwt_audience.is_empty() {
+        validation.validate_aud = false;
+    } else {
+        validation.set_audience(&[&security.jwt_audience]);
+        validation.required_spec_claims.insert("aud".to_string());
+    }
+
+    let claims = decode::<Claims>(token, &decoding_key, &validation)
+        .map(|data| data.claims)
+        .map_err(|e| match e.kind() {
+            ErrorKind::ExpiredSignature => JwtError::Expired,
+            _ => JwtError::InvalidToken(e.to_string()),
+        })?;
+    if !allow_expired && claims.exp < clock.now().timestamp() - validation.leeway as i64 {
+        return Err(JwtError::Expired);
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use chrono::TimeZone;
+
+    /// Lifetime of the test tokens, in seconds
+    const TTL: i64 = 3600;
+
+    fn clock() -> MockClock {
+        MockClock::new(chrono::Utc.with_ymd_and_hms(2030, 1, 1, 12, 0, 0).unwrap())
+    }
+
+    fn security() -> SecurityConfig {
+        let mut security = config::config().security.clone();
+        security.jwt_algorithm = "HS256".to_string();
+        security.jwt_secret = "test-secret-that-is-long-enough-for-hs256".to_string();
+        security.jwt_issuer = "monk".to_string();
+        security.jwt_audience = "monk-api".to_string();
+        security
+    }
+
+    fn claims(security: &SecurityConfig, clock: &MockClock, exp_offset: i64) -> Claims {
+        let now = clock.now().timestamp();
+        Claims {
+            tenant: "acme".to_string(),
+            user: "ada".to_string(),
+            database: "tenant_acme".to_string(),
+            access: "full".to_string(),
+            user_id: Uuid::new_v4(),
+            exp: now + exp_offset,
+            iat: now,
+            iss: Some(security.jwt_issuer.clone()),
+            aud: Some(security.jwt_audience.clone()),
+            elevation: None,
+            mfa: true,
+        }
+    }
+
+    #[test]
+    fn test_validate_round_trip() {
+        let security = security();
+        let clock = clock();
+        let token = sign(&security, &claims(&security, &clock, TTL)).unwrap();
+        let decoded = validate(&security, &clock, &token, false).unwrap();
+        assert_eq!(decoded.user, "ada");
+        assert!(decoded.mfa);
+    }
+
+    #[test]
+    fn test_validate_rejects_other_algorithm() {
+        let security = security();
+        let clock = clock();
+        let key = EncodingKey::from_secret(security.jwt_secret.as_bytes());
+        let token = encode(&Header::new(Algorithm::HS384), &claims(&security, &clock, TTL), &key).unwrap();
+        assert!(matches!(validate(&security, &clock, &token, false), Err(JwtError::InvalidToken(_))));
+
+        let mut unsupported = security.clone();
+        unsupported.jwt_algorithm = "none".to_string();
+        assert!(matches!(validate(&unsupported, &clock, &token, false), Err(JwtError::UnsupportedAlgorithm(_))));
+    }
+
+    #[test]
+    fn test_validate_rejects_wrong_issuer_and_audience() {
+        let security = security();
+        let clock = clock();
+
+        let mut other_issuer = claims(&security, &clock, TTL);
+        other_issuer.iss = Some("someone-else".to_string());
+        let token = sign(&security, &other_issuer).unwrap();
+        assert!(matches!(validate(&security, &clock, &token, false), Err(JwtError::InvalidToken(_))));
+
+        let mut other_audience = claims(&security, &clock, TTL);
+        other_audience.aud = Some("another-api".to_string());
+        let token = sign(&security, &other_audience).unwrap();
+        assert!(matches!(validate(&security, &clock, &token, false), Err(JwtError::InvalidToken(_))));
+
+        let mut missing = claims(&security, &clock, TTL);
+        missing.iss = None;
+        missing.aud = None;
+        let token = sign(&security, &missing).unwrap();
+        assert!(matches!(validate(&security, &clock, &token, false), Err(JwtError::InvalidToken(_))));
+    }
+
+    #[test]
+    fn test_validate_allow_expired() {
+        let security = security();
+        let clock = clock();
+        let token = sign(&security, &claims(&security, &clock, TTL)).unwrap();
+        assert_eq!(validate(&security, &clock, &token, false).unwrap().user, "ada");
+
+        // Expired once the clock passes exp and the leeway
+        clock.advance(Duration::seconds(TTL + 120));
+        assert!(matches!(validate(&security, &clock, &token, false), Err(JwtError::Expired)));
+        assert_eq!(validate(&security, &clock, &token, true).unwrap().user, "ada");
+
+        // allow_expired skips only the expiry check
+        let mut other = security.clone();
+        other.jwt_secret = "a-different-secret-that-is-long-enough".to_string();
+        assert!(matches!(validate(&other, &clock, &token, true), Err(JwtError::InvalidToken(_))));
+    }
+}
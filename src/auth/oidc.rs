@@ -0,0 +1,205 @@
+//! OAuth2 / OpenID Connect sign-in with external identity providers
+//!
+//! The authorization code flow runs through the public /auth/oidc/:provider/* endpoints.
+//! Its `state` parameter is signed so the callback can trust the tenant chosen at the
+//! start without server-side storage; a cookie holding the state's nonce binds the flow
+//! to the browser that started it.
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::{self, OidcProvider};
+
+/// Cookie holding the nonce of the flow in progress
+pub const STATE_COOKIE: &str = "monk_oidc_state";
+
+/// How long a user has to complete the provider's sign-in
+pub const STATE_TTL_SECS: i64 = 10 * 60;
+
+/// What the signed state carries from start to callback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcState {
+    pub tenant: String,
+    pub nonce: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+fn state_mac(provider: &str, nonce: &str, expires: i64, tenant: &str) -> Hmac<Sha256> {
+    let secret = &config::config().security.url_signing_secret;
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("oidc:{}:{}:{}:{}", provider, nonce, expires, tenant).as_bytes());
+    mac
+}
+
+/// Signed `state` for a flow of `provider` into `tenant`: `<nonce>.<expires>.<tenant>.<signature>`
+pub fn sign_state(provider: &str, tenant: &str, nonce: &str) -> String {
+    sign_state_with(provider, tenant, nonce, &SystemClock)
+}
+
+fn sign_state_with(provider: &str, tenant: &str, nonce: &str, clock: &dyn Clock) -> String {
+    let expires = clock.now().timestamp() + STATE_TTL_SECS;
+    let signature = hex::encode(state_mac(provider, nonce, expires, tenant).finalize().into_bytes());
+    format!("{}.{}.{}.{}", nonce, expires, tenant, signature)
+}
+
+/// The state's tenant and nonce, if it was signed for `provider` and has not expired
+pub fn verify_state(provider: &str, state: &str) -> Option<OidcState> {
+    verify_state_with(provider, state, &SystemClock)
+}
+
+fn verify_state_with(provider: &str, state: &str, clock: &dyn Clock) -> Option<OidcState> {
+    let (payload, signature) = state.rsplit_once('.')?;
+    let mut parts = payload.splitn(3, '.');
+    let (nonce, expires, tenant) = (parts.next()?, parts.next()?.parse::<i64>().ok()?, parts.next()?);
+    if expires < clock.now().timestamp() {
+        return None;
+    }
+    let signature = hex::decode(signature).ok()?;
+    state_mac(provider, nonce, expires, tenant).verify_slice(&signature).ok()?;
+    Some(OidcState { tenant: tenant.to_string(), nonce: nonce.to_string() })
+}
+
+/// The provider's sign-in page for a flow carrying `state`
+pub fn authorization_url(provider: &OidcProvider, state: &str) -> Result<String, String> {
+    let mut url = url::Url::parse(&provider.authorization_url).map_err(|e| e.to_string())?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &provider.client_id)
+        .append_pair("redirect_uri", &provider.redirect_url)
+        .append_pair("scope", &provider.scopes.join(" "))
+        .append_pair("state", state);
+    Ok(url.into())
+}
+
+/// Exchange an authorization code for an access token and fetch the user's claims with it
+pub async fn fetch_userinfo(provider: &OidcProvider, code: &str) -> Result<Value, String> {
+    let client = reqwest::Client::new();
+    let token: TokenResponse = client
+        .post(&provider.token_url)
+        // GitHub answers form-encoded unless JSON is asked for
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &provider.redirect_url),
+            ("client_id", &provider.client_id),
+            ("client_secret", &provider.client_secret),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("token exchange failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("unreadable token response: {}", e))?;
+
+    client
+        .get(&provider.userinfo_url)
+        .bearer_auth(&token.access_token)
+        .header(reqwest::header::ACCEPT, "application/json")
+        // GitHub rejects API requests without a User-Agent
+        .header(reqwest::header::USER_AGENT, "monk-api-rust")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("userinfo request failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("unreadable userinfo response: {}", e))
+}
+
+/// The identity to look up in `users.auth`, from the provider's `user_claim`
+///
+/// Email claims are only accepted when the provider marks them verified; a missing
+/// `email_verified` counts as unverified.
+pub fn identity(provider: &OidcProvider, userinfo: &Value) -> Option<String> {
+    if provider.user_claim == "email" && userinfo.get("email_verified").and_then(|v| v.as_bool()) != Some(true) {
+        return None;
+    }
+    match userinfo.get(&provider.user_claim)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use serde_json::json;
+
+    fn provider(user_claim: &str) -> OidcProvider {
+        OidcProvider {
+            name: "google".to_string(),
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            authorization_url: "https://accounts.example.com/authorize".to_string(),
+            token_url: "https://accounts.example.com/token".to_string(),
+            userinfo_url: "https://accounts.example.com/userinfo".to_string(),
+            redirect_url: "https://api.example.com/auth/oidc/google/callback".to_string(),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+            user_claim: user_claim.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        let state = sign_state("google", "acme", "nonce123");
+        let verified = verify_state("google", &state).unwrap();
+        assert_eq!(verified, OidcState { tenant: "acme".to_string(), nonce: "nonce123".to_string() });
+    }
+
+    #[test]
+    fn test_state_rejects_tampering() {
+        let state = sign_state("google", "acme", "nonce123");
+        let (payload, signature) = state.rsplit_once('.').unwrap();
+
+        let other_tenant = format!("{}.{}", payload.replace(".acme", ".globex"), signature);
+        assert_eq!(verify_state("google", &other_tenant), None);
+
+        let mut flipped = signature.to_string();
+        let last = if flipped.ends_with('0') { "1" } else { "0" };
+        flipped.replace_range(flipped.len() - 1.., last);
+        assert_eq!(verify_state("google", &format!("{}.{}", payload, flipped)), None);
+
+        assert_eq!(verify_state("google", payload), None);
+        assert_eq!(verify_state("google", "not-a-state"), None);
+    }
+
+    #[test]
+    fn test_state_rejects_other_provider() {
+        let state = sign_state("google", "acme", "nonce123");
+        assert_eq!(verify_state("github", &state), None);
+    }
+
+    #[test]
+    fn test_state_rejects_expired() {
+        let clock = MockClock::new(crate::clock::now());
+        let state = sign_state_with("google", "acme", "nonce123", &clock);
+        clock.advance(chrono::Duration::seconds(STATE_TTL_SECS));
+        assert!(verify_state_with("google", &state, &clock).is_some());
+        clock.advance(chrono::Duration::seconds(1));
+        assert_eq!(verify_state_with("google", &state, &clock), None);
+    }
+
+    #[test]
+    fn test_identity() {
+        let email = provider("email");
+        assert_eq!(identity(&email, &json!({ "email": "ada@example.com", "email_verified": true })), Some("ada@example.com".to_string()));
+        assert_eq!(identity(&email, &json!({ "email": "ada@example.com", "email_verified": false })), None);
+        assert_eq!(identity(&email, &json!({ "email": "ada@example.com" })), None);
+        assert_eq!(identity(&email, &json!({ "email": "ada@example.com", "email_verified": "true" })), None);
+        assert_eq!(identity(&email, &json!({ "email": "", "email_verified": true })), None);
+        assert_eq!(identity(&email, &json!({ "sub": "123" })), None);
+
+        let id = provider("id");
+        assert_eq!(identity(&id, &json!({ "id": 583231, "email_verified": false })), Some("583231".to_string()));
+    }
+}
@@ -0,0 +1,88 @@
+//! Argon2id password hashing (RFC 9106), stored as PHC strings
+//!
+//! `$argon2id$v=19$m=<KiB>,t=<passes>,p=<lanes>$<salt>$<hash>` with unpadded base64, the
+//! format other Argon2 implementations read and write. Parameters travel with each hash, so
+//! raising the configured cost only affects passwords hashed afterwards.
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Version, ARGON2ID_IDENT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Params {
+    /// Memory per hash in KiB
+    pub memory_kib: u32,
+    /// Passes over memory
+    pub iterations: u32,
+    /// Lanes
+    pub parallelism: u32,
+}
+
+impl Params {
+    fn hasher(&self) -> Result<Argon2<'static>, String> {
+        let params = argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| e.to_string())?;
+        Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+    }
+}
+
+/// Hash `password` with a fresh random salt, as a PHC string
+pub fn hash(password: &str, params: Params) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = params.hasher()?.hash_password(password.as_bytes(), &salt).map_err(|e| e.to_string())?;
+    Ok(hash.to_string())
+}
+
+/// Whether `password` matches a PHC string produced by `hash`
+///
+/// A malformed or non-argon2id hash is an error rather than a mismatch.
+pub fn verify(password: &str, phc: &str) -> Result<bool, String> {
+    let parsed = parse_phc(phc)?;
+    // The cost parameters are taken from the stored hash
+    match Argon2::default().verify_password(password.as_bytes(), &parsed) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// The parameters recorded in a stored hash, to decide whether it needs rehashing
+pub fn params_of(phc: &str) -> Result<Params, String> {
+    let params = argon2::Params::try_from(&parse_phc(phc)?).map_err(|e| e.to_string())?;
+    Ok(Params { memory_kib: params.m_cost(), iterations: params.t_cost(), parallelism: params.p_cost() })
+}
+
+fn parse_phc(phc: &str) -> Result<PasswordHash<'_>, String> {
+    let parsed = PasswordHash::new(phc).map_err(|_| "Malformed password hash".to_string())?;
+    if parsed.algorithm != ARGON2ID_IDENT {
+        return Err("Password hash is not argon2id".to_string());
+    }
+    if parsed.version != Some(Version::V0x13.into()) {
+        return Err("Unsupported argon2 version".to_string());
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify_round_trip() {
+        let params = Params { memory_kib: 64, iterations: 1, parallelism: 1 };
+        let phc = hash("correct horse", params).unwrap();
+        assert!(phc.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+        assert!(verify("correct horse", &phc).unwrap());
+        assert!(!verify("battery staple", &phc).unwrap());
+        assert_eq!(params_of(&phc).unwrap(), params);
+        assert!(verify("correct horse", "$bcrypt$nope").is_err());
+        assert!(verify("correct horse", "$argon2i$v=19$m=64,t=1,p=1$c2FsdHNhbHQ$aGFzaA").is_err());
+    }
+
+    #[test]
+    fn test_invalid_params_are_rejected() {
+        assert!(hash("correct horse", Params { memory_kib: 64, iterations: 0, parallelism: 1 }).is_err());
+        assert!(hash("correct horse", Params { memory_kib: 64, iterations: 1, parallelism: 0 }).is_err());
+        assert!(hash("correct horse", Params { memory_kib: 4, iterations: 1, parallelism: 1 }).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+// canonical.rs - Canonical JSON serialization
+//
+// Clients that hash or diff responses need the same bytes for the same data. The
+// canonical form has object keys sorted by code point, no insignificant whitespace,
+// and one spelling per number: integral floats in the exactly representable range
+// are written as integers and negative zero as 0. Export checksums and webhook
+// signatures are always computed over this form; API responses use it on request.
+
+use axum::http::HeaderMap;
+use serde_json::{Number, Value};
+
+/// Header a client sends to receive canonical response bodies
+pub const REQUEST_HEADER: &str = "x-canonical-json";
+
+/// Largest integer an f64 holds exactly (2^53)
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Whether the request asked for canonical output ("true", "1" or "yes")
+pub fn is_requested(headers: &HeaderMap) -> bool {
+    headers
+        .get(REQUEST_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "true" | "1" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Serialize a value in canonical form
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// Canonical form as bytes, ready for hashing or a response body
+pub fn to_vec(value: &Value) -> Vec<u8> {
+    to_string(value).into_bytes()
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => out.push_str(&value.to_string()),
+        Value::Number(number) => write_number(out, number),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|&(key, _)| key);
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_value(out, item);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_number(out: &mut String, number: &Number) {
+    match number.as_f64() {
+        Some(float) if number.is_f64() && float.fract() == 0.0 && float.abs() <= MAX_EXACT_INTEGER => {
+            // `as` saturates and -0.0 becomes 0, both of which are what we want here
+            out.push_str(&(float as i64).to_string());
+        }
+        _ => out.push_str(&number.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn sorts_keys_at_every_level() {
+        let value = json!({ "b": 1, "a": { "z": true, "m": [ { "y": null, "x": "s" } ] } });
+        assert_eq!(to_string(&value), r#"{"a":{"m":[{"x":"s","y":null}],"z":true},"b":1}"#);
+    }
+
+    #[test]
+    fn normalizes_number_spellings() {
+        let value = json!([1.0, -0.0, 2.5, 42, -7, 1e300]);
+        assert_eq!(to_string(&value), "[1,0,2.5,42,-7,1e300]");
+    }
+
+    #[test]
+    fn reads_request_header() {
+        let mut headers = HeaderMap::new();
+        assert!(!is_requested(&headers));
+        headers.insert(REQUEST_HEADER, "True".parse().unwrap());
+        assert!(is_requested(&headers));
+        headers.insert(REQUEST_HEADER, "no".parse().unwrap());
+        assert!(!is_requested(&headers));
+    }
+}
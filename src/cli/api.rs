@@ -0,0 +1,179 @@
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::Value;
+use crate::cli::config::*;
+
+/// Server version and feature flags reported by GET /api/version
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerVersion {
+    pub version: String,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+impl ServerVersion {
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+
+    /// Same major.minor as this CLI build
+    pub fn is_compatible(&self) -> bool {
+        major_minor(&self.version) == major_minor(env!("CARGO_PKG_VERSION"))
+    }
+}
+
+pub fn major_minor(version: &str) -> String {
+    version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+/// HTTP client bound to the current server and its stored session token
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    pub server_name: String,
+    pub base_url: String,
+    pub token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(server_name: String, base_url: String, token: Option<String>) -> Self {
+        Self {
+            server_name,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Build a client for the current server, using MONK_TOKEN or the saved session token
+    pub fn from_current() -> anyhow::Result<Self> {
+        let env_config = load_environment_config()?;
+        let server_name = env_config
+            .current_server
+            .ok_or_else(|| anyhow::anyhow!("No current server set"))?;
+
+        let server_config = load_server_config()?;
+        let server_info = server_config
+            .servers
+            .get(&server_name)
+            .ok_or_else(|| anyhow::anyhow!("Current server '{}' not found in configuration", server_name))?;
+
+        let token = match std::env::var("MONK_TOKEN") {
+            Ok(token) if !token.is_empty() => Some(token),
+            _ => load_auth_config()?
+                .sessions
+                .get(&server_name)
+                .map(|session| session.token.clone()),
+        };
+
+        Ok(Self::new(server_name, server_info.url(), token))
+    }
+
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}{}", self.base_url, path);
+        let builder = self.client.request(method, url);
+
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    /// Send a JSON request and unwrap the `{"success": true, "data": ...}` envelope
+    pub async fn send_json(&self, method: Method, path: &str, body: Option<&Value>) -> anyhow::Result<Value> {
+        let mut builder = self.request(method, path);
+        if let Some(body) = body {
+            builder = builder.json(body);
+        }
+
+        let payload = Self::send_envelope(builder).await?;
+        Ok(payload.get("data").cloned().unwrap_or(payload))
+    }
+
+    /// GET every record of a listing such as /api/data/:schema, following `next_cursor`
+    /// page by page since the server caps each page at its configured limit
+    pub async fn get_all(&self, path: &str) -> anyhow::Result<Vec<Value>> {
+        let mut records = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut builder = self.request(Method::GET, path);
+            if let Some(cursor) = &cursor {
+                builder = builder.query(&[("cursor", cursor)]);
+            }
+            let payload = Self::send_envelope(builder).await?;
+            let page = payload
+                .get("data")
+                .and_then(|d| d.as_array())
+                .ok_or_else(|| anyhow::anyhow!("Expected an array of records from the server"))?;
+            records.extend(page.iter().cloned());
+
+            cursor = payload
+                .pointer("/meta/next_cursor")
+                .and_then(|c| c.as_str())
+                .map(String::from);
+            if cursor.is_none() || page.is_empty() {
+                return Ok(records);
+            }
+        }
+    }
+
+    /// Send a request and return the whole response envelope, failing on error statuses
+    async fn send_envelope(builder: RequestBuilder) -> anyhow::Result<Value> {
+        let response = builder.send().await?;
+        let status = response.status();
+        let payload: Value = response.json().await.unwrap_or(Value::Null);
+
+        if !status.is_success() {
+            let message = payload
+                .get("message")
+                .or_else(|| payload.get("error"))
+                .and_then(|m| m.as_str())
+                .unwrap_or("Request failed")
+                .to_string();
+            return Err(anyhow::anyhow!("HTTP {}: {}", status.as_u16(), message));
+        }
+
+        Ok(payload)
+    }
+
+    /// Fetch the server version, or None for servers that predate /api/version
+    pub async fn server_version(&self) -> anyhow::Result<Option<ServerVersion>> {
+        let response = self.request(Method::GET, "/api/version").send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let payload: Value = response.error_for_status()?.json().await?;
+        let data = payload.get("data").cloned().unwrap_or(payload);
+        Ok(Some(serde_json::from_value(data)?))
+    }
+
+    /// Check the server supports a feature before running a command that needs it
+    ///
+    /// Warns (without failing) when the server's major.minor version differs from the CLI.
+    pub async fn require_feature(&self, feature: &str) -> anyhow::Result<()> {
+        let cli_version = env!("CARGO_PKG_VERSION");
+
+        match self.server_version().await? {
+            Some(server) => {
+                if !server.is_compatible() {
+                    eprintln!(
+                        "Warning: server '{}' runs {} but this CLI is {}; some commands may not work",
+                        self.server_name, server.version, cli_version
+                    );
+                }
+                if !server.supports(feature) {
+                    return Err(anyhow::anyhow!(
+                        "Server '{}' ({}) does not support '{}'; upgrade the server to use this command",
+                        self.server_name, server.version, feature
+                    ));
+                }
+                Ok(())
+            }
+            None => Err(anyhow::anyhow!(
+                "Server '{}' predates version negotiation and does not support '{}'; upgrade the server",
+                self.server_name, feature
+            )),
+        }
+    }
+}
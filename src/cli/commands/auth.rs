@@ -1,4 +1,11 @@
+use chrono::Utc;
 use clap::Subcommand;
+use reqwest::Method;
+use serde_json::json;
+
+use crate::cli::api::ApiClient;
+use crate::cli::config::{load_auth_config, load_environment_config, save_auth_config, SessionInfo};
+use crate::cli::utils::output_success;
 use crate::cli::OutputFormat;
 
 #[derive(Subcommand)]
@@ -9,6 +16,8 @@ pub enum AuthCommands {
         username: String,
         #[arg(long, help = "Password (will prompt if not provided)")]
         password: Option<String>,
+        #[arg(long, help = "Current TOTP code, for users with MFA enabled")]
+        mfa_code: Option<String>,
     },
     
     #[command(about = "Logout from server")]
@@ -34,13 +43,9 @@ pub enum AuthCommands {
     },
 }
 
-pub async fn handle(cmd: AuthCommands, _output_format: OutputFormat) -> anyhow::Result<()> {
+pub async fn handle(cmd: AuthCommands, output_format: OutputFormat) -> anyhow::Result<()> {
     match cmd {
-        AuthCommands::Login { username, password } => {
-            println!("Logging in user: {} (password provided: {})", username, password.is_some());
-            // TODO: Implement login
-            Ok(())
-        }
+        AuthCommands::Login { username, password, mfa_code } => login(username, password, mfa_code, &output_format).await,
         AuthCommands::Logout => {
             println!("Logging out...");
             // TODO: Implement logout
@@ -67,4 +72,48 @@ pub async fn handle(cmd: AuthCommands, _output_format: OutputFormat) -> anyhow::
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+
+/// Log in to the current tenant and save the session token for the current server
+async fn login(username: String, password: Option<String>, mfa_code: Option<String>, output_format: &OutputFormat) -> anyhow::Result<()> {
+    let tenant = load_environment_config()?
+        .current_tenant
+        .ok_or_else(|| anyhow::anyhow!("No current tenant set"))?;
+    let password = match password {
+        Some(password) => password,
+        None => prompt("Password: ")?,
+    };
+
+    let client = ApiClient::from_current()?;
+    let data = client
+        .send_json(
+            Method::POST,
+            &format!("/auth/login/{}/{}", tenant, username),
+            Some(&json!({ "password": password, "mfa_code": mfa_code })),
+        )
+        .await?;
+    let token = data
+        .get("token")
+        .and_then(|token| token.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Login response has no token"))?;
+
+    let mut auth_config = load_auth_config()?;
+    auth_config.sessions.insert(client.server_name.clone(), SessionInfo {
+        tenant: tenant.clone(),
+        user: username.clone(),
+        token: token.to_string(),
+        created_at: Utc::now(),
+    });
+    save_auth_config(&auth_config)?;
+
+    output_success(
+        output_format,
+        &format!("Logged in as '{}' to tenant '{}' on server '{}'", username, tenant, client.server_name),
+        Some(json!({ "server": client.server_name, "tenant": tenant, "user": username })),
+    )
+}
+
+/// Read a password from the terminal without echoing it
+fn prompt(label: &str) -> anyhow::Result<String> {
+    Ok(rpassword::prompt_password(label)?)
+}
@@ -1,4 +1,21 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
 use clap::Subcommand;
+use futures::stream::{self, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::cli::api::ApiClient;
+use crate::cli::import_template::{self, ImportTemplate, TEMPLATE_SCHEMA};
+use crate::cli::mirror::Mirror;
+use crate::cli::transform::Transform;
+use crate::cli::utils::output_success;
 use crate::cli::OutputFormat;
 
 #[derive(Subcommand)]
@@ -11,12 +28,16 @@ pub enum DataCommands {
         id: Option<String>,
         #[arg(long, help = "JSON filter for query parameters (limit, offset, order)")]
         filter: Option<String>,
+        #[arg(long, help = "Read from the local mirror instead of the server")]
+        offline: bool,
     },
     
     #[command(about = "Create record from stdin")]
     Create {
         #[arg(help = "Schema name")]
         schema: String,
+        #[arg(long, help = "Write to the local mirror; sync later with `monk data push`")]
+        offline: bool,
     },
     
     #[command(about = "Update record(s) from stdin")]
@@ -25,6 +46,8 @@ pub enum DataCommands {
         schema: String,
         #[arg(help = "Record ID to update")]
         id: String,
+        #[arg(long, help = "Write to the local mirror; sync later with `monk data push`")]
+        offline: bool,
     },
     
     #[command(about = "Delete record(s)")]
@@ -49,14 +72,77 @@ pub enum DataCommands {
     Import {
         #[arg(help = "Schema name")]
         schema: String,
-        #[arg(help = "Input file path")]
+        #[arg(help = "Input file path (JSON array, newline-delimited JSON, or CSV)")]
         input: String,
+        #[arg(long, help = "Stored import template to apply (see `monk data template`)")]
+        template: Option<String>,
+        #[arg(long, help = "Records per upload request", default_value = "500")]
+        chunk_size: usize,
+        #[arg(long, help = "Number of concurrent upload requests", default_value = "4")]
+        parallel: usize,
+        #[arg(long, help = "Resume from the state file of an interrupted import")]
+        resume: bool,
+        #[arg(long, help = "File to write failed chunks to (default: <input>.errors.json)")]
+        errors: Option<PathBuf>,
+        #[arg(long, help = "JSON file of transform steps, or a .lua script defining transform(row), applied to each row before upload (replaces the template's steps)")]
+        transform: Option<String>,
+        #[arg(long, help = "Print transformed rows without uploading anything")]
+        dry_run: bool,
+        #[arg(long, help = "Rows to show with --dry-run", default_value = "10")]
+        preview: usize,
+    },
+    
+    #[command(about = "Manage stored import templates")]
+    Template {
+        #[command(subcommand)]
+        cmd: TemplateCommands,
+    },
+    
+    #[command(about = "Snapshot records into the local mirror for offline use")]
+    Pull {
+        #[arg(help = "Schema name")]
+        schema: String,
+    },
+    
+    #[command(about = "Sync offline edits from the local mirror to the server")]
+    Push {
+        #[arg(help = "Schema name")]
+        schema: String,
+        #[arg(long, help = "Overwrite server changes made since the last pull")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateCommands {
+    #[command(about = "List import templates")]
+    List,
+    
+    #[command(about = "Show an import template")]
+    Show {
+        #[arg(help = "Template name")]
+        name: String,
+    },
+    
+    #[command(about = "Create or replace an import template from a JSON file")]
+    Save {
+        #[arg(help = "JSON file with name, schema_name, column_mapping, delimiter, date_formats, transform, dedupe_key")]
+        file: String,
+    },
+    
+    #[command(about = "Delete an import template")]
+    Delete {
+        #[arg(help = "Template name")]
+        name: String,
     },
 }
 
-pub async fn handle(cmd: DataCommands, _output_format: OutputFormat) -> anyhow::Result<()> {
+pub async fn handle(cmd: DataCommands, output_format: OutputFormat) -> anyhow::Result<()> {
     match cmd {
-        DataCommands::Select { schema, id, filter } => {
+        DataCommands::Select { schema, id, filter: _, offline: true } => {
+            handle_offline_select(schema, id, output_format)
+        }
+        DataCommands::Select { schema, id, filter, offline: false } => {
             match id {
                 Some(record_id) => println!("Selecting record {} from schema: {}", record_id, schema),
                 None => println!("Selecting records from schema: {} (filter: {:?})", schema, filter),
@@ -64,12 +150,32 @@ pub async fn handle(cmd: DataCommands, _output_format: OutputFormat) -> anyhow::
             // TODO: Implement data selection
             Ok(())
         }
-        DataCommands::Create { schema } => {
+        DataCommands::Create { schema, offline: true } => {
+            let data = read_stdin_json()?;
+            let id = uuid::Uuid::new_v4().to_string();
+            let mirror = Mirror::open(&ApiClient::from_current()?.server_name)?;
+            mirror.write_local(&schema, &id, &data)?;
+            output_success(&output_format, &format!("Created local record {} in '{}'", id, schema), Some(json!({ "id": id })))
+        }
+        DataCommands::Create { schema, offline: false } => {
             println!("Creating record in schema: {} (reading from stdin)", schema);
             // TODO: Implement data creation
             Ok(())
         }
-        DataCommands::Update { schema, id } => {
+        DataCommands::Update { schema, id, offline: true } => {
+            let changes = read_stdin_json()?;
+            let mirror = Mirror::open(&ApiClient::from_current()?.server_name)?;
+            let existing = mirror.select_one(&schema, &id)?
+                .ok_or_else(|| anyhow::anyhow!("Record '{}' not found in local mirror for '{}'", id, schema))?;
+
+            let mut data = existing.data;
+            if let (Some(target), Some(source)) = (data.as_object_mut(), changes.as_object()) {
+                target.extend(source.clone());
+            }
+            mirror.write_local(&schema, &id, &data)?;
+            output_success(&output_format, &format!("Updated local record {} in '{}'", id, schema), None)
+        }
+        DataCommands::Update { schema, id, offline: false } => {
             println!("Updating record {} in schema: {} (reading from stdin)", id, schema);
             // TODO: Implement data update
             Ok(())
@@ -84,10 +190,559 @@ pub async fn handle(cmd: DataCommands, _output_format: OutputFormat) -> anyhow::
             // TODO: Implement data export
             Ok(())
         }
-        DataCommands::Import { schema, input } => {
-            println!("Importing records to schema: {} from {}", schema, input);
-            // TODO: Implement data import
+        DataCommands::Import { schema, input, template, chunk_size, parallel, resume, errors, transform, dry_run, preview } => {
+            let template = match template {
+                Some(name) => {
+                    let client = ApiClient::from_current()?;
+                    client.require_feature("data.import_templates").await?;
+                    let template = import_template::find_template(&client, &name).await?;
+                    if template.schema_name != schema {
+                        return Err(anyhow::anyhow!(
+                            "Import template '{}' targets '{}', not '{}'", name, template.schema_name, schema
+                        ));
+                    }
+                    Some(template)
+                }
+                None => None,
+            };
+            let options = ImportOptions::new(template.as_ref(), transform.as_deref())?;
+            if dry_run {
+                handle_import_preview(input, &options, preview, output_format)
+            } else {
+                handle_import(schema, input, chunk_size, parallel, resume, errors, &options, output_format).await
+            }
+        }
+        DataCommands::Template { cmd } => handle_template(cmd, output_format).await,
+        DataCommands::Pull { schema } => handle_pull(schema, output_format).await,
+        DataCommands::Push { schema, force } => handle_push(schema, force, output_format).await,
+    }
+}
+
+/// Progress of an import, persisted after every chunk so an interrupted run can resume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ImportState {
+    schema: String,
+    input: String,
+    chunk_size: usize,
+    total_records: usize,
+    completed_chunks: BTreeSet<usize>,
+}
+
+impl ImportState {
+    fn matches(&self, schema: &str, input: &str, chunk_size: usize, total_records: usize) -> bool {
+        self.schema == schema
+            && self.input == input
+            && self.chunk_size == chunk_size
+            && self.total_records == total_records
+    }
+}
+
+fn import_state_path(input: &str) -> PathBuf {
+    PathBuf::from(format!("{}.import-state.json", input))
+}
+
+fn save_import_state(path: &Path, state: &ImportState) -> anyhow::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(state)?)?;
+    Ok(())
+}
+
+/// How input rows are read and reshaped before upload
+#[derive(Default)]
+struct ImportOptions {
+    delimiter: Option<char>,
+    transform: Transform,
+    dedupe_key: Option<String>,
+}
+
+impl ImportOptions {
+    /// Template settings, with an explicit --transform file replacing the template's own steps
+    fn new(template: Option<&ImportTemplate>, transform_path: Option<&str>) -> anyhow::Result<Self> {
+        let mut options = match template {
+            Some(template) => {
+                let mut template = template.clone();
+                if transform_path.is_some() {
+                    template.transform.clear();
+                }
+                Self {
+                    delimiter: template.delimiter_char()?,
+                    transform: template.to_transform()?,
+                    dedupe_key: template.dedupe_key.clone(),
+                }
+            }
+            None => Self::default(),
+        };
+        if let Some(path) = transform_path {
+            options.transform = options.transform.then(Transform::load(path)?);
+        }
+        Ok(options)
+    }
+
+    /// Read, transform and dedupe the input, naming the first row that fails to transform
+    fn load_records(&self, input: &str) -> anyhow::Result<Vec<Value>> {
+        let records = read_import_records(input, self.delimiter)?
+            .iter()
+            .enumerate()
+            .map(|(idx, record)| {
+                self.transform
+                    .apply(record)
+                    .map_err(|e| anyhow::anyhow!("Transform failed on row {}: {}", idx + 1, e))
+            })
+            .collect::<anyhow::Result<Vec<Value>>>()?;
+        Ok(self.dedupe(records))
+    }
+
+    fn dedupe(&self, records: Vec<Value>) -> Vec<Value> {
+        match &self.dedupe_key {
+            Some(key) => crate::import::dedupe(records, key),
+            None => records,
+        }
+    }
+}
+
+/// Read records from a JSON array file, or newline-delimited JSON when that fails to parse
+///
+/// Files ending in .csv, or any file when a delimiter is given, are read as delimited
+/// text whose first line names the columns.
+fn read_import_records(input: &str, delimiter: Option<char>) -> anyhow::Result<Vec<Value>> {
+    let content = fs::read_to_string(input)
+        .map_err(|e| anyhow::anyhow!("Failed to read input file '{}': {}", input, e))?;
+
+    if delimiter.is_some() || input.to_ascii_lowercase().ends_with(".csv") {
+        return crate::import::delimited::parse(&content, delimiter.unwrap_or(','));
+    }
+
+    if let Ok(Value::Array(records)) = serde_json::from_str::<Value>(&content) {
+        return Ok(records);
+    }
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| anyhow::anyhow!("Invalid JSON on line {}: {}", idx + 1, e))
+        })
+        .collect()
+}
+
+/// Show what `--transform` produces for the first rows of an input, uploading nothing
+fn handle_import_preview(
+    input: String,
+    options: &ImportOptions,
+    preview: usize,
+    output_format: OutputFormat,
+) -> anyhow::Result<()> {
+    let records = read_import_records(&input, options.delimiter)?;
+    let mut rows = Vec::new();
+    let mut failed = 0usize;
+
+    for (idx, record) in records.iter().enumerate() {
+        let result = options.transform.apply(record);
+        if result.is_err() {
+            failed += 1;
+        }
+        if rows.len() < preview {
+            rows.push(match result {
+                Ok(output) => json!({ "row": idx + 1, "output": output }),
+                Err(e) => json!({ "row": idx + 1, "error": e.to_string() }),
+            });
+        }
+    }
+
+    match output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&json!({
+                "input": input,
+                "total_records": records.len(),
+                "failed": failed,
+                "preview": rows
+            }))?);
+        }
+        OutputFormat::Text => {
+            for row in &rows {
+                match row.get("output") {
+                    Some(output) => println!("{:>6}: {}", row["row"], output),
+                    None => println!("{:>6}: ✗ {}", row["row"], row["error"].as_str().unwrap_or_default()),
+                }
+            }
+            println!("Dry run: {} of {} records transform cleanly; nothing was uploaded", records.len() - failed, records.len());
+        }
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn handle_import(
+    schema: String,
+    input: String,
+    chunk_size: usize,
+    parallel: usize,
+    resume: bool,
+    errors: Option<PathBuf>,
+    options: &ImportOptions,
+    output_format: OutputFormat,
+) -> anyhow::Result<()> {
+    if chunk_size == 0 || parallel == 0 {
+        return Err(anyhow::anyhow!("--chunk-size and --parallel must be greater than zero"));
+    }
+
+    let client = ApiClient::from_current()?;
+    client.require_feature("data.bulk").await?;
+    let records = options.load_records(&input)?;
+    let total_records = records.len();
+    let chunks: Vec<Vec<Value>> = records.chunks(chunk_size).map(|c| c.to_vec()).collect();
+
+    let state_path = import_state_path(&input);
+    let errors_path = errors.unwrap_or_else(|| PathBuf::from(format!("{}.errors.json", input)));
+
+    let mut state = match (resume, state_path.exists()) {
+        (true, true) => {
+            let existing: ImportState = serde_json::from_str(&fs::read_to_string(&state_path)?)?;
+            if !existing.matches(&schema, &input, chunk_size, total_records) {
+                return Err(anyhow::anyhow!(
+                    "State file '{}' does not match this import (schema, input, chunk size, or record count changed)",
+                    state_path.display()
+                ));
+            }
+            existing
+        }
+        (true, false) => {
+            return Err(anyhow::anyhow!("No import state found at '{}'", state_path.display()));
+        }
+        (false, _) => ImportState {
+            schema: schema.clone(),
+            input: input.clone(),
+            chunk_size,
+            total_records,
+            completed_chunks: BTreeSet::new(),
+        },
+    };
+    save_import_state(&state_path, &state)?;
+
+    let pending: Vec<(usize, Vec<Value>)> = chunks
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| !state.completed_chunks.contains(idx))
+        .collect();
+
+    let already_imported: usize = state
+        .completed_chunks
+        .iter()
+        .map(|idx| chunk_size.min(total_records - idx * chunk_size))
+        .sum();
+
+    let progress = match output_format {
+        OutputFormat::Text => ProgressBar::new(total_records as u64),
+        OutputFormat::Json => ProgressBar::hidden(),
+    };
+    progress.set_style(
+        ProgressStyle::with_template(
+            "{spinner} [{elapsed_precise}] {bar:40} {pos}/{len} rows ({per_sec}, ETA {eta}) {msg}",
+        )?
+        .progress_chars("=> "),
+    );
+    progress.set_position(already_imported as u64);
+
+    let started = Instant::now();
+    let path = format!("/api/data/{}", schema);
+    let mut uploads = stream::iter(pending)
+        .map(|(idx, chunk)| {
+            let client = client.clone();
+            let path = path.clone();
+            async move {
+                let body = Value::Array(chunk.clone());
+                let result = client.send_json(Method::POST, &path, Some(&body)).await;
+                (idx, chunk, result)
+            }
+        })
+        .buffer_unordered(parallel);
+
+    let mut imported = 0usize;
+    let mut failures = Vec::new();
+
+    while let Some((idx, chunk, result)) = uploads.next().await {
+        match result {
+            Ok(_) => {
+                imported += chunk.len();
+                state.completed_chunks.insert(idx);
+                save_import_state(&state_path, &state)?;
+            }
+            Err(e) => {
+                progress.set_message(format!("{} chunk(s) failed", failures.len() + 1));
+                failures.push(json!({
+                    "chunk": idx,
+                    "offset": idx * chunk_size,
+                    "count": chunk.len(),
+                    "error": e.to_string(),
+                    "records": chunk
+                }));
+            }
+        }
+        progress.inc(chunk.len() as u64);
+    }
+    progress.finish_and_clear();
+
+    let failed_records: usize = failures
+        .iter()
+        .filter_map(|f| f.get("count").and_then(|c| c.as_u64()))
+        .sum::<u64>() as usize;
+
+    if failures.is_empty() {
+        // Nothing left to resume
+        let _ = fs::remove_file(&state_path);
+    } else {
+        fs::write(&errors_path, serde_json::to_string_pretty(&failures)?)?;
+    }
+
+    let elapsed = started.elapsed().as_secs_f64();
+    let summary = json!({
+        "schema": schema,
+        "input": input,
+        "total_records": total_records,
+        "imported": imported,
+        "skipped": already_imported,
+        "failed": failed_records,
+        "failed_chunks": failures.len(),
+        "elapsed_secs": elapsed,
+        "errors_file": if failures.is_empty() { Value::Null } else { json!(errors_path.display().to_string()) },
+        "state_file": if failures.is_empty() { Value::Null } else { json!(state_path.display().to_string()) }
+    });
+
+    match output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        }
+        OutputFormat::Text => {
+            println!("✓ Imported {} of {} records into '{}' in {:.1}s", imported, total_records, schema, elapsed);
+            if already_imported > 0 {
+                println!("  Skipped (already imported): {}", already_imported);
+            }
+            if !failures.is_empty() {
+                println!("  Failed: {} records in {} chunk(s)", failed_records, failures.len());
+                println!("  Errors written to: {}", errors_path.display());
+                println!("  Re-run with --resume to retry failed chunks");
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("{} record(s) failed to import", failed_records))
+    }
+}
+
+fn read_stdin_json() -> anyhow::Result<Value> {
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    serde_json::from_str(&input).map_err(|e| anyhow::anyhow!("Invalid JSON on stdin: {}", e))
+}
+
+/// Strip fields the server manages so local copies can be sent back as input
+fn strip_system_fields(data: &Value) -> Value {
+    const SYSTEM_FIELDS: [&str; 5] = ["id", "created_at", "updated_at", "trashed_at", "deleted_at"];
+
+    let mut data = data.clone();
+    if let Some(object) = data.as_object_mut() {
+        object.retain(|key, _| !SYSTEM_FIELDS.contains(&key.as_str()) && !key.starts_with("access_"));
+    }
+    data
+}
+
+fn handle_offline_select(schema: String, id: Option<String>, _output_format: OutputFormat) -> anyhow::Result<()> {
+    let mirror = Mirror::open(&ApiClient::from_current()?.server_name)?;
+
+    let result = match id {
+        Some(id) => mirror
+            .select_one(&schema, &id)?
+            .map(|record| record.data)
+            .ok_or_else(|| anyhow::anyhow!("Record '{}' not found in local mirror for '{}'", id, schema))?,
+        None => Value::Array(mirror.select_all(&schema)?.into_iter().map(|r| r.data).collect()),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&result)?);
+    Ok(())
+}
+
+async fn handle_template(cmd: TemplateCommands, output_format: OutputFormat) -> anyhow::Result<()> {
+    let client = ApiClient::from_current()?;
+    client.require_feature("data.import_templates").await?;
+
+    match cmd {
+        TemplateCommands::List => {
+            let templates = import_template::list_templates(&client).await?;
+            match output_format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&templates)?),
+                OutputFormat::Text => {
+                    for template in &templates {
+                        println!("{:<30} -> {}", template.name, template.schema_name);
+                    }
+                    println!("{} import template(s)", templates.len());
+                }
+            }
+            Ok(())
+        }
+        TemplateCommands::Show { name } => {
+            let template = import_template::find_template(&client, &name).await?;
+            println!("{}", serde_json::to_string_pretty(&template)?);
             Ok(())
         }
+        TemplateCommands::Save { file } => {
+            let content = fs::read_to_string(&file)
+                .map_err(|e| anyhow::anyhow!("Failed to read template file '{}': {}", file, e))?;
+            let template: ImportTemplate = serde_json::from_str(&content)
+                .map_err(|e| anyhow::anyhow!("Invalid template file '{}': {}", file, e))?;
+            template.delimiter_char()?;
+
+            let existing = import_template::list_templates(&client)
+                .await?
+                .into_iter()
+                .find(|t| t.name == template.name)
+                .and_then(|t| t.id);
+            let body = template.to_input()?;
+            match &existing {
+                Some(id) => {
+                    client
+                        .send_json(Method::PUT, &format!("/api/data/{}/{}", TEMPLATE_SCHEMA, id), Some(&body))
+                        .await?;
+                }
+                None => {
+                    client
+                        .send_json(Method::POST, &format!("/api/data/{}", TEMPLATE_SCHEMA), Some(&Value::Array(vec![body])))
+                        .await?;
+                }
+            }
+
+            let action = if existing.is_some() { "Updated" } else { "Created" };
+            output_success(
+                &output_format,
+                &format!("{} import template '{}' for '{}'", action, template.name, template.schema_name),
+                Some(json!({ "name": template.name, "schema_name": template.schema_name, "created": existing.is_none() })),
+            )
+        }
+        TemplateCommands::Delete { name } => {
+            let template = import_template::find_template(&client, &name).await?;
+            let id = template.id.ok_or_else(|| anyhow::anyhow!("Import template '{}' has no id", name))?;
+            client
+                .send_json(Method::DELETE, &format!("/api/data/{}/{}", TEMPLATE_SCHEMA, id), None)
+                .await?;
+            output_success(&output_format, &format!("Deleted import template '{}'", name), None)
+        }
     }
-}
\ No newline at end of file
+}
+
+async fn handle_pull(schema: String, output_format: OutputFormat) -> anyhow::Result<()> {
+    let client = ApiClient::from_current()?;
+    client.require_feature("data.versioned").await?;
+    let records = client.get_all(&format!("/api/data/{}", schema)).await?;
+
+    let mut mirror = Mirror::open(&client.server_name)?;
+    let stored = mirror.replace_snapshot(&schema, &records)?;
+    let pending = mirror.select_dirty(&schema)?.len();
+
+    output_success(
+        &output_format,
+        &format!("Pulled {} record(s) from '{}' into local mirror ({} unpushed local edit(s) kept)", stored, schema, pending),
+        Some(json!({ "schema": schema, "pulled": stored, "pending": pending })),
+    )
+}
+
+async fn handle_push(schema: String, force: bool, output_format: OutputFormat) -> anyhow::Result<()> {
+    let client = ApiClient::from_current()?;
+    client.require_feature("data.versioned").await?;
+    let mirror = Mirror::open(&client.server_name)?;
+    let dirty = mirror.select_dirty(&schema)?;
+
+    let mut created = 0;
+    let mut updated = 0;
+    let mut conflicts = Vec::new();
+    let mut failures = Vec::new();
+
+    for record in dirty {
+        let body = strip_system_fields(&record.data);
+
+        let result = match &record.version {
+            // Created offline: the local id is a placeholder until the server assigns one
+            None => client
+                .send_json(Method::POST, &format!("/api/data/{}", schema), Some(&Value::Array(vec![body])))
+                .await
+                .map(|created| created.as_array().and_then(|a| a.first().cloned()).unwrap_or(created))
+                .map(|server_record| (server_record, true)),
+            Some(base_version) => {
+                let path = format!("/api/data/{}/{}", schema, record.id);
+                let remote = client.send_json(Method::GET, &path, None).await;
+                let remote_version = remote
+                    .as_ref()
+                    .ok()
+                    .and_then(|r| r.get("updated_at").and_then(|v| v.as_str()).map(String::from));
+
+                if !force && remote.is_ok() && remote_version.as_deref() != Some(base_version.as_str()) {
+                    conflicts.push(json!({
+                        "id": record.id,
+                        "local_version": base_version,
+                        "remote_version": remote_version
+                    }));
+                    continue;
+                }
+
+                client
+                    .send_json(Method::PATCH, &path, Some(&body))
+                    .await
+                    .map(|server_record| (server_record, false))
+            }
+        };
+
+        match result {
+            Ok((server_record, was_created)) => {
+                mirror.mark_synced(&schema, &record.id, &server_record)?;
+                if was_created {
+                    created += 1;
+                } else {
+                    updated += 1;
+                }
+            }
+            Err(e) => failures.push(json!({ "id": record.id, "error": e.to_string() })),
+        }
+    }
+
+    match output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&json!({
+                "success": conflicts.is_empty() && failures.is_empty(),
+                "schema": schema,
+                "created": created,
+                "updated": updated,
+                "conflicts": conflicts,
+                "failures": failures
+            }))?);
+        }
+        OutputFormat::Text => {
+            println!("✓ Pushed '{}': {} created, {} updated", schema, created, updated);
+            for conflict in &conflicts {
+                println!(
+                    "  conflict: {} changed on server since last pull (local {}, remote {})",
+                    conflict["id"].as_str().unwrap_or_default(),
+                    conflict["local_version"].as_str().unwrap_or("none"),
+                    conflict["remote_version"].as_str().unwrap_or("none"),
+                );
+            }
+            for failure in &failures {
+                println!(
+                    "  failed: {} {}",
+                    failure["id"].as_str().unwrap_or_default(),
+                    failure["error"].as_str().unwrap_or_default()
+                );
+            }
+            if !conflicts.is_empty() {
+                println!("  Run `monk data pull {}` to refresh, or push with --force to overwrite", schema);
+            }
+        }
+    }
+
+    if conflicts.is_empty() && failures.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("{} conflict(s), {} failure(s)", conflicts.len(), failures.len()))
+    }
+}
@@ -1,4 +1,11 @@
+use std::io::Read;
+use std::path::PathBuf;
+
 use clap::Subcommand;
+use reqwest::Method;
+use serde_json::Value;
+
+use crate::cli::api::ApiClient;
 use crate::cli::OutputFormat;
 
 #[derive(Subcommand)]
@@ -32,9 +39,15 @@ pub enum DescribeCommands {
         #[arg(help = "Schema name")]
         schema: String,
     },
+
+    #[command(about = "Check a schema definition (YAML/JSON) for problems before applying it")]
+    Lint {
+        #[arg(help = "Schema file; reads stdin when omitted")]
+        file: Option<PathBuf>,
+    },
 }
 
-pub async fn handle(cmd: DescribeCommands, _output_format: OutputFormat) -> anyhow::Result<()> {
+pub async fn handle(cmd: DescribeCommands, output_format: OutputFormat) -> anyhow::Result<()> {
     match cmd {
         DescribeCommands::Select { schema } => {
             println!("Selecting schema: {}", schema);
@@ -66,5 +79,49 @@ pub async fn handle(cmd: DescribeCommands, _output_format: OutputFormat) -> anyh
             // TODO: Implement schema column listing
             Ok(())
         }
+        DescribeCommands::Lint { file } => handle_lint(file, output_format).await,
+    }
+}
+
+async fn handle_lint(file: Option<PathBuf>, output_format: OutputFormat) -> anyhow::Result<()> {
+    let input = match &file {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
+    // YAML is a superset of JSON, so one parser reads both
+    let definition: Value = serde_yaml::from_str(&input).map_err(|e| anyhow::anyhow!("Invalid schema definition: {}", e))?;
+
+    let client = ApiClient::from_current()?;
+    client.require_feature("describe.lint").await?;
+    let report = client.send_json(Method::POST, "/api/meta/_lint", Some(&definition)).await?;
+
+    let issues = |key: &str| report.get(key).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+    let (errors, warnings) = (issues("errors"), issues("warnings"));
+    match output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Text => {
+            for (label, list) in [("error", &errors), ("warning", &warnings)] {
+                for issue in list {
+                    let path = issue.get("path").and_then(|p| p.as_str()).unwrap_or_default();
+                    let message = issue.get("message").and_then(|m| m.as_str()).unwrap_or_default();
+                    if path.is_empty() {
+                        println!("{}: {}", label, message);
+                    } else {
+                        println!("{}: {}: {}", label, path, message);
+                    }
+                }
+            }
+            println!("{} error(s), {} warning(s)", errors.len(), warnings.len());
+        }
+    }
+
+    // A failing exit status lets scripts stop before applying a broken schema
+    if !errors.is_empty() {
+        return Err(anyhow::anyhow!("Schema definition has {} error(s)", errors.len()));
     }
+    Ok(())
 }
\ No newline at end of file
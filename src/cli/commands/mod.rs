@@ -3,4 +3,5 @@ pub mod server;
 pub mod auth;
 pub mod data;
 pub mod describe;
-pub mod fixture;
\ No newline at end of file
+pub mod fixture;
+pub mod doctor;
\ No newline at end of file
@@ -2,6 +2,7 @@ use clap::Subcommand;
 use serde_json::json;
 use url::Url;
 use crate::cli::config::*;
+use crate::cli::registry::*;
 use crate::cli::utils::*;
 use crate::cli::OutputFormat;
 
@@ -53,10 +54,40 @@ pub enum ServerCommands {
         #[arg(help = "Server name")]
         name: Option<String>,
     },
+    
+    #[command(about = "Configure where the server and tenant registry is stored")]
+    Registry {
+        #[command(subcommand)]
+        cmd: RegistryCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RegistryCommands {
+    #[command(about = "Show the active registry backend")]
+    Show,
+    
+    #[command(about = "Store the registry in local files (default)")]
+    UseFile {
+        #[arg(long, help = "Reject all registry writes")]
+        read_only: bool,
+    },
+    
+    #[command(about = "Use a team-managed registry endpoint with local overrides")]
+    UseHttp {
+        #[arg(help = "Registry URL returning {\"servers\": {...}, \"tenants\": {...}}")]
+        url: String,
+        #[arg(long, help = "Reject all registry writes, including local overrides")]
+        read_only: bool,
+    },
+    
+    #[command(about = "Fetch the latest team registry into the local cache")]
+    Pull,
 }
 
 pub async fn handle(cmd: ServerCommands, output_format: OutputFormat) -> anyhow::Result<()> {
     match cmd {
+        ServerCommands::Registry { cmd } => handle_registry(cmd, output_format).await,
         ServerCommands::Add { url, name } => {
             let parsed_url = Url::parse(&url)?;
             let hostname = parsed_url.host_str()
@@ -504,4 +535,84 @@ pub async fn handle(cmd: ServerCommands, output_format: OutputFormat) -> anyhow:
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+
+async fn handle_registry(cmd: RegistryCommands, output_format: OutputFormat) -> anyhow::Result<()> {
+    match cmd {
+        RegistryCommands::Show => {
+            let settings = load_registry_settings()?;
+            let registry = active_registry()?;
+            let cache = match (&settings.backend, &settings.url) {
+                (RegistryBackend::Http, Some(url)) => {
+                    HttpRegistry::new(url.clone(), FileRegistry::new(get_config_dir()?, settings.read_only)).load_cache()?
+                }
+                _ => None,
+            };
+            
+            match output_format {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&json!({
+                        "backend": settings.backend,
+                        "url": settings.url,
+                        "read_only": registry.is_read_only(),
+                        "pulled_at": cache.as_ref().map(|c| c.pulled_at),
+                        "team_servers": cache.as_ref().map(|c| c.servers.len()),
+                        "team_tenants": cache.as_ref().map(|c| c.tenants.len())
+                    }))?);
+                }
+                OutputFormat::Text => {
+                    println!("Registry: {}", registry.describe());
+                    println!("Read-only: {}", registry.is_read_only());
+                    if settings.backend == RegistryBackend::Http {
+                        match cache {
+                            Some(cache) => println!(
+                                "Last pull: {} ({} servers, {} tenants)",
+                                cache.pulled_at.format("%Y-%m-%d %H:%M"),
+                                cache.servers.len(),
+                                cache.tenants.len()
+                            ),
+                            None => println!("Last pull: never (run `monk server registry pull`)"),
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        RegistryCommands::UseFile { read_only } => {
+            save_registry_settings(&RegistrySettings {
+                backend: RegistryBackend::File,
+                url: None,
+                read_only,
+            })?;
+            output_success(&output_format, "Registry set to local files", Some(json!({ "backend": "file", "read_only": read_only })))
+        }
+        RegistryCommands::UseHttp { url, read_only } => {
+            Url::parse(&url)?;
+            save_registry_settings(&RegistrySettings {
+                backend: RegistryBackend::Http,
+                url: Some(url.clone()),
+                read_only,
+            })?;
+            output_success(
+                &output_format,
+                &format!("Registry set to {} (run `monk server registry pull` to fetch it)", url),
+                Some(json!({ "backend": "http", "url": url, "read_only": read_only })),
+            )
+        }
+        RegistryCommands::Pull => {
+            let settings = load_registry_settings()?;
+            let url = match (settings.backend, settings.url) {
+                (RegistryBackend::Http, Some(url)) => url,
+                _ => return Err(anyhow::anyhow!("No HTTP registry configured; use `monk server registry use-http <url>`")),
+            };
+            
+            let registry = HttpRegistry::new(url.clone(), FileRegistry::new(get_config_dir()?, settings.read_only));
+            let cache = registry.pull().await?;
+            output_success(
+                &output_format,
+                &format!("Pulled {} servers and {} tenants from {}", cache.servers.len(), cache.tenants.len(), url),
+                Some(json!({ "servers": cache.servers.len(), "tenants": cache.tenants.len() })),
+            )
+        }
+    }
+}
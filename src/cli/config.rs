@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::cli::registry::active_registry;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerInfo {
     pub hostname: String,
@@ -126,47 +129,19 @@ pub fn get_config_dir() -> anyhow::Result<PathBuf> {
 }
 
 pub fn load_server_config() -> anyhow::Result<ServerConfig> {
-    let config_dir = get_config_dir()?;
-    let server_file = config_dir.join("server.json");
-
-    if !server_file.exists() {
-        return Ok(ServerConfig::default());
-    }
-
-    let content = fs::read_to_string(server_file)?;
-    let config: ServerConfig = serde_json::from_str(&content)?;
-    Ok(config)
+    active_registry()?.load_servers()
 }
 
 pub fn save_server_config(config: &ServerConfig) -> anyhow::Result<()> {
-    let config_dir = get_config_dir()?;
-    let server_file = config_dir.join("server.json");
-
-    let content = serde_json::to_string_pretty(config)?;
-    fs::write(server_file, content)?;
-    Ok(())
+    active_registry()?.save_servers(config)
 }
 
 pub fn load_tenant_config() -> anyhow::Result<TenantConfig> {
-    let config_dir = get_config_dir()?;
-    let tenant_file = config_dir.join("tenant.json");
-
-    if !tenant_file.exists() {
-        return Ok(TenantConfig::default());
-    }
-
-    let content = fs::read_to_string(tenant_file)?;
-    let config: TenantConfig = serde_json::from_str(&content)?;
-    Ok(config)
+    active_registry()?.load_tenants()
 }
 
 pub fn save_tenant_config(config: &TenantConfig) -> anyhow::Result<()> {
-    let config_dir = get_config_dir()?;
-    let tenant_file = config_dir.join("tenant.json");
-
-    let content = serde_json::to_string_pretty(config)?;
-    fs::write(tenant_file, content)?;
-    Ok(())
+    active_registry()?.save_tenants(config)
 }
 
 pub fn load_environment_config() -> anyhow::Result<EnvironmentConfig> {
@@ -186,9 +161,7 @@ pub fn save_environment_config(config: &EnvironmentConfig) -> anyhow::Result<()>
     let config_dir = get_config_dir()?;
     let env_file = config_dir.join("env.json");
 
-    let content = serde_json::to_string_pretty(config)?;
-    fs::write(env_file, content)?;
-    Ok(())
+    write_private(&env_file, &serde_json::to_string_pretty(config)?)
 }
 
 pub async fn ping_server(server_info: &ServerInfo) -> ServerStatus {
@@ -199,4 +172,55 @@ pub async fn ping_server(server_info: &ServerInfo) -> ServerStatus {
         Ok(response) if response.status().is_success() => ServerStatus::Up,
         _ => ServerStatus::Down,
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub tenant: String,
+    pub user: String,
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Sessions keyed by server name
+    pub sessions: HashMap<String, SessionInfo>,
+}
+
+pub fn load_auth_config() -> anyhow::Result<AuthConfig> {
+    let config_dir = get_config_dir()?;
+    let auth_file = config_dir.join("auth.json");
+
+    if !auth_file.exists() {
+        return Ok(AuthConfig::default());
+    }
+
+    let content = fs::read_to_string(auth_file)?;
+    let config: AuthConfig = serde_json::from_str(&content)?;
+    Ok(config)
+}
+
+pub fn save_auth_config(config: &AuthConfig) -> anyhow::Result<()> {
+    let config_dir = get_config_dir()?;
+    let auth_file = config_dir.join("auth.json");
+
+    write_private(&auth_file, &serde_json::to_string_pretty(config)?)
+}
+
+/// Write a file of the config directory, readable by its owner only
+///
+/// Session tokens, server and tenant registries and settings all live there, so every
+/// file gets the same mode rather than deciding per file which ones hold credentials.
+pub fn write_private(path: &Path, content: &str) -> anyhow::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files; tighten one written by an older version too
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
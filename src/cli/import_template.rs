@@ -0,0 +1,26 @@
+use reqwest::Method;
+
+use crate::cli::api::ApiClient;
+pub use crate::import::template::{ImportTemplate, TEMPLATE_SCHEMA};
+
+/// Every import template visible to the current user
+pub async fn list_templates(client: &ApiClient) -> anyhow::Result<Vec<ImportTemplate>> {
+    let records = client.send_json(Method::GET, &format!("/api/data/{}", TEMPLATE_SCHEMA), None).await?;
+    let records = records
+        .as_array()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Expected an array of import templates from the server"))?;
+    records
+        .into_iter()
+        .map(|record| serde_json::from_value(record).map_err(|e| anyhow::anyhow!("Invalid import template: {}", e)))
+        .collect()
+}
+
+/// Look up a template by name
+pub async fn find_template(client: &ApiClient, name: &str) -> anyhow::Result<ImportTemplate> {
+    list_templates(client)
+        .await?
+        .into_iter()
+        .find(|template| template.name == name)
+        .ok_or_else(|| anyhow::anyhow!("Import template '{}' not found", name))
+}
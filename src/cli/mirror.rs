@@ -0,0 +1,218 @@
+use std::path::PathBuf;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+
+use crate::cli::config::get_config_dir;
+
+/// A record held in the local mirror
+///
+/// `version` is the server's `updated_at` at the time of the last pull or push and is
+/// used to detect conflicting remote edits. Records created offline have no version.
+#[derive(Debug, Clone)]
+pub struct MirrorRecord {
+    pub id: String,
+    pub data: Value,
+    pub version: Option<String>,
+    pub dirty: bool,
+}
+
+/// Local SQLite snapshot of server records for offline use
+pub struct Mirror {
+    conn: Connection,
+}
+
+impl Mirror {
+    pub fn path(server_name: &str) -> anyhow::Result<PathBuf> {
+        Ok(get_config_dir()?.join(format!("mirror-{}.db", server_name)))
+    }
+
+    pub fn open(server_name: &str) -> anyhow::Result<Self> {
+        Self::with_connection(Connection::open(Self::path(server_name)?)?)
+    }
+
+    fn with_connection(conn: Connection) -> anyhow::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS records (
+                schema_name TEXT NOT NULL,
+                id TEXT NOT NULL,
+                data TEXT NOT NULL,
+                version TEXT,
+                dirty INTEGER NOT NULL DEFAULT 0,
+                pulled_at TEXT NOT NULL,
+                PRIMARY KEY (schema_name, id)
+            );",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Replace the clean snapshot of a schema, keeping any unpushed local edits
+    pub fn replace_snapshot(&mut self, schema: &str, records: &[Value]) -> anyhow::Result<usize> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM records WHERE schema_name = ?1 AND dirty = 0", params![schema])?;
+
+        let mut stored = 0;
+        for record in records {
+            let Some(id) = record.get("id").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let version = record.get("updated_at").and_then(|v| v.as_str());
+            stored += tx.execute(
+                "INSERT INTO records (schema_name, id, data, version, dirty, pulled_at)
+                 VALUES (?1, ?2, ?3, ?4, 0, ?5)
+                 ON CONFLICT (schema_name, id) DO NOTHING",
+                params![schema, id, record.to_string(), version, now],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(stored)
+    }
+
+    pub fn select_all(&self, schema: &str) -> anyhow::Result<Vec<MirrorRecord>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, data, version, dirty FROM records WHERE schema_name = ?1 ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![schema], Self::map_row)?;
+        rows.map(|row| row?.map_err(anyhow::Error::from)).collect()
+    }
+
+    pub fn select_one(&self, schema: &str, id: &str) -> anyhow::Result<Option<MirrorRecord>> {
+        let row = self
+            .conn
+            .query_row(
+                "SELECT id, data, version, dirty FROM records WHERE schema_name = ?1 AND id = ?2",
+                params![schema, id],
+                Self::map_row,
+            )
+            .optional()?;
+        row.transpose().map_err(anyhow::Error::from)
+    }
+
+    pub fn select_dirty(&self, schema: &str) -> anyhow::Result<Vec<MirrorRecord>> {
+        Ok(self.select_all(schema)?.into_iter().filter(|r| r.dirty).collect())
+    }
+
+    /// Store a local edit, keeping the base version it was made against
+    pub fn write_local(&self, schema: &str, id: &str, data: &Value) -> anyhow::Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO records (schema_name, id, data, version, dirty, pulled_at)
+             VALUES (?1, ?2, ?3, NULL, 1, ?4)
+             ON CONFLICT (schema_name, id) DO UPDATE SET data = excluded.data, dirty = 1",
+            params![schema, id, data.to_string(), now],
+        )?;
+        Ok(())
+    }
+
+    /// Mark a record as synced with the server copy
+    pub fn mark_synced(&self, schema: &str, local_id: &str, server_record: &Value) -> anyhow::Result<()> {
+        let id = server_record.get("id").and_then(|v| v.as_str()).unwrap_or(local_id);
+        let version = server_record.get("updated_at").and_then(|v| v.as_str());
+        let now = chrono::Utc::now().to_rfc3339();
+
+        self.conn.execute(
+            "DELETE FROM records WHERE schema_name = ?1 AND id = ?2",
+            params![schema, local_id],
+        )?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO records (schema_name, id, data, version, dirty, pulled_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5)",
+            params![schema, id, server_record.to_string(), version, now],
+        )?;
+        Ok(())
+    }
+
+    fn map_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Result<MirrorRecord, serde_json::Error>> {
+        let id: String = row.get(0)?;
+        let data: String = row.get(1)?;
+        let version: Option<String> = row.get(2)?;
+        let dirty: i64 = row.get(3)?;
+
+        Ok(serde_json::from_str(&data).map(|data| MirrorRecord {
+            id,
+            data,
+            version,
+            dirty: dirty != 0,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn mirror() -> Mirror {
+        Mirror::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_replace_snapshot_keeps_local_edits() {
+        let mut mirror = mirror();
+        let stored = mirror
+            .replace_snapshot("accounts", &[
+                json!({ "id": "a", "name": "Ada", "updated_at": "v1" }),
+                json!({ "id": "b", "name": "Bob", "updated_at": "v1" }),
+                json!({ "name": "no id" }),
+            ])
+            .unwrap();
+        assert_eq!(stored, 2);
+        mirror.replace_snapshot("contacts", &[json!({ "id": "c", "updated_at": "v1" })]).unwrap();
+        mirror.write_local("accounts", "a", &json!({ "id": "a", "name": "Ada Lovelace" })).unwrap();
+
+        // b is gone on the server and a changed there, but a's local edit is kept
+        let stored = mirror
+            .replace_snapshot("accounts", &[json!({ "id": "a", "name": "Ada L.", "updated_at": "v2" })])
+            .unwrap();
+        assert_eq!(stored, 0);
+
+        let records = mirror.select_all("accounts").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].data["name"], "Ada Lovelace");
+        assert_eq!(records[0].version.as_deref(), Some("v1"));
+        assert!(records[0].dirty);
+        assert_eq!(mirror.select_all("contacts").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_write_local_keeps_the_base_version() {
+        let mut mirror = mirror();
+        mirror.replace_snapshot("accounts", &[json!({ "id": "a", "name": "Ada", "updated_at": "v1" })]).unwrap();
+        mirror.write_local("accounts", "a", &json!({ "id": "a", "name": "Ada Lovelace" })).unwrap();
+        mirror.write_local("accounts", "a", &json!({ "id": "a", "name": "Countess" })).unwrap();
+        mirror.write_local("accounts", "local-1", &json!({ "name": "Offline" })).unwrap();
+
+        let edited = mirror.select_one("accounts", "a").unwrap().unwrap();
+        assert_eq!((edited.data["name"].as_str(), edited.version.as_deref(), edited.dirty), (Some("Countess"), Some("v1"), true));
+        let created = mirror.select_one("accounts", "local-1").unwrap().unwrap();
+        assert_eq!((created.version, created.dirty), (None, true));
+
+        let dirty: Vec<String> = mirror.select_dirty("accounts").unwrap().into_iter().map(|r| r.id).collect();
+        assert_eq!(dirty, ["a", "local-1"]);
+        assert!(mirror.select_one("accounts", "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_mark_synced_swaps_offline_ids() {
+        let mirror = mirror();
+        mirror.write_local("accounts", "local-1", &json!({ "name": "Offline" })).unwrap();
+        mirror
+            .mark_synced("accounts", "local-1", &json!({ "id": "srv-9", "name": "Offline", "updated_at": "v1" }))
+            .unwrap();
+
+        assert!(mirror.select_one("accounts", "local-1").unwrap().is_none());
+        let synced = mirror.select_one("accounts", "srv-9").unwrap().unwrap();
+        assert_eq!((synced.version.as_deref(), synced.dirty), (Some("v1"), false));
+        assert_eq!(synced.data["id"], "srv-9");
+
+        // An existing record keeps its id and takes the server's version
+        mirror.write_local("accounts", "srv-9", &json!({ "id": "srv-9", "name": "Edited" })).unwrap();
+        mirror.mark_synced("accounts", "srv-9", &json!({ "id": "srv-9", "name": "Edited", "updated_at": "v2" })).unwrap();
+        let records = mirror.select_all("accounts").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].version.as_deref(), records[0].dirty), (Some("v2"), false));
+        assert!(mirror.select_dirty("accounts").unwrap().is_empty());
+    }
+}
@@ -1,5 +1,10 @@
+pub mod api;
 pub mod commands;
 pub mod config;
+pub mod import_template;
+pub mod mirror;
+pub mod registry;
+pub use crate::import::transform;
 pub mod utils;
 
 use clap::{Parser, Subcommand};
@@ -58,6 +63,9 @@ pub enum Commands {
         #[command(subcommand)]
         cmd: commands::fixture::FixtureCommands,
     },
+    
+    #[command(about = "Diagnose configuration, connectivity, and environment problems")]
+    Doctor(commands::doctor::DoctorArgs),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,5 +94,6 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
         Commands::Data { cmd } => commands::data::handle(cmd, output_format).await,
         Commands::Describe { cmd } => commands::describe::handle(cmd, output_format).await,
         Commands::Fixture { cmd } => commands::fixture::handle(cmd, output_format).await,
+        Commands::Doctor(args) => commands::doctor::handle(args, output_format).await,
     }
 }
\ No newline at end of file
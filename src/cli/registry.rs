@@ -0,0 +1,434 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::config::{get_config_dir, write_private, ServerConfig, ServerInfo, TenantConfig, TenantInfo};
+
+/// Storage backend for the server and tenant registries
+///
+/// The file backend keeps everything in the CLI config directory. Other backends may
+/// source entries elsewhere but must still honour `is_read_only` on every write.
+pub trait RegistryStore {
+    fn load_servers(&self) -> anyhow::Result<ServerConfig>;
+    fn save_servers(&self, config: &ServerConfig) -> anyhow::Result<()>;
+    fn load_tenants(&self) -> anyhow::Result<TenantConfig>;
+    fn save_tenants(&self, config: &TenantConfig) -> anyhow::Result<()>;
+    fn is_read_only(&self) -> bool;
+    fn describe(&self) -> String;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistryBackend {
+    File,
+    Http,
+}
+
+/// Registry selection, stored in registry.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrySettings {
+    pub backend: RegistryBackend,
+    /// Team registry endpoint for the http backend
+    pub url: Option<String>,
+    /// Reject all writes, including local overrides
+    pub read_only: bool,
+}
+
+impl Default for RegistrySettings {
+    fn default() -> Self {
+        Self {
+            backend: RegistryBackend::File,
+            url: None,
+            read_only: false,
+        }
+    }
+}
+
+/// Team registry snapshot cached by `monk server registry pull`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryCache {
+    pub url: String,
+    pub pulled_at: DateTime<Utc>,
+    #[serde(default)]
+    pub servers: HashMap<String, ServerInfo>,
+    #[serde(default)]
+    pub tenants: HashMap<String, TenantInfo>,
+}
+
+pub fn load_registry_settings() -> anyhow::Result<RegistrySettings> {
+    let registry_file = get_config_dir()?.join("registry.json");
+
+    let mut settings = if registry_file.exists() {
+        serde_json::from_str(&fs::read_to_string(registry_file)?)?
+    } else {
+        RegistrySettings::default()
+    };
+
+    if let Ok(url) = std::env::var("MONK_CLI_REGISTRY_URL") {
+        if !url.is_empty() {
+            settings.backend = RegistryBackend::Http;
+            settings.url = Some(url);
+        }
+    }
+
+    Ok(settings)
+}
+
+pub fn save_registry_settings(settings: &RegistrySettings) -> anyhow::Result<()> {
+    let registry_file = get_config_dir()?.join("registry.json");
+    write_private(&registry_file, &serde_json::to_string_pretty(settings)?)
+}
+
+/// The registry selected by registry.json (file backend when unset)
+pub fn active_registry() -> anyhow::Result<Box<dyn RegistryStore>> {
+    let settings = load_registry_settings()?;
+    let local = FileRegistry::new(get_config_dir()?, settings.read_only);
+
+    match settings.backend {
+        RegistryBackend::File => Ok(Box::new(local)),
+        RegistryBackend::Http => {
+            let url = settings
+                .url
+                .ok_or_else(|| anyhow::anyhow!("HTTP registry selected but no URL configured"))?;
+            Ok(Box::new(HttpRegistry::new(url, local)))
+        }
+    }
+}
+
+/// Registry stored as server.json / tenant.json in the config directory
+pub struct FileRegistry {
+    dir: PathBuf,
+    read_only: bool,
+}
+
+impl FileRegistry {
+    pub fn new(dir: PathBuf, read_only: bool) -> Self {
+        Self { dir, read_only }
+    }
+
+    fn read<T: for<'de> Deserialize<'de> + Default>(&self, file: &str) -> anyhow::Result<T> {
+        let path = self.dir.join(file);
+        if !path.exists() {
+            return Ok(T::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn write<T: Serialize>(&self, file: &str, value: &T) -> anyhow::Result<()> {
+        if self.read_only {
+            return Err(anyhow::anyhow!("Registry is read-only"));
+        }
+        write_private(&self.dir.join(file), &serde_json::to_string_pretty(value)?)
+    }
+}
+
+impl RegistryStore for FileRegistry {
+    fn load_servers(&self) -> anyhow::Result<ServerConfig> {
+        self.read("server.json")
+    }
+
+    fn save_servers(&self, config: &ServerConfig) -> anyhow::Result<()> {
+        self.write("server.json", config)
+    }
+
+    fn load_tenants(&self) -> anyhow::Result<TenantConfig> {
+        self.read("tenant.json")
+    }
+
+    fn save_tenants(&self, config: &TenantConfig) -> anyhow::Result<()> {
+        self.write("tenant.json", config)
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn describe(&self) -> String {
+        format!("file ({})", self.dir.display())
+    }
+}
+
+/// Team-managed registry pulled from an HTTP endpoint, layered under local overrides
+///
+/// Team entries come from the cached snapshot written by `pull`; entries in the local
+/// files shadow team entries of the same name. Team entries can never be removed
+/// locally, and only entries that differ from the team copy are written back.
+pub struct HttpRegistry {
+    url: String,
+    local: FileRegistry,
+}
+
+impl HttpRegistry {
+    pub fn new(url: String, local: FileRegistry) -> Self {
+        Self { url, local }
+    }
+
+    fn cache_path(&self) -> PathBuf {
+        self.local.dir.join("registry-cache.json")
+    }
+
+    pub fn load_cache(&self) -> anyhow::Result<Option<RegistryCache>> {
+        let path = self.cache_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let cache: RegistryCache = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Ok((cache.url == self.url).then_some(cache))
+    }
+
+    /// Fetch the team registry and refresh the local cache
+    pub async fn pull(&self) -> anyhow::Result<RegistryCache> {
+        let response = reqwest::Client::new()
+            .get(&self.url)
+            .timeout(std::time::Duration::from_secs(10))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        #[derive(Deserialize)]
+        struct RemoteRegistry {
+            #[serde(default)]
+            servers: HashMap<String, ServerInfo>,
+            #[serde(default)]
+            tenants: HashMap<String, TenantInfo>,
+        }
+
+        let remote: RemoteRegistry = response.json().await?;
+        let cache = RegistryCache {
+            url: self.url.clone(),
+            pulled_at: Utc::now(),
+            servers: remote.servers,
+            tenants: remote.tenants,
+        };
+
+        // The cache is not user data, so it is refreshed even in read-only mode
+        write_private(&self.cache_path(), &serde_json::to_string_pretty(&cache)?)?;
+        Ok(cache)
+    }
+
+    fn team_servers(&self) -> anyhow::Result<HashMap<String, ServerInfo>> {
+        Ok(self.load_cache()?.map(|c| c.servers).unwrap_or_default())
+    }
+
+    fn team_tenants(&self) -> anyhow::Result<HashMap<String, TenantInfo>> {
+        Ok(self.load_cache()?.map(|c| c.tenants).unwrap_or_default())
+    }
+}
+
+fn same_server(a: &ServerInfo, b: &ServerInfo) -> bool {
+    a.hostname == b.hostname && a.port == b.port && a.protocol == b.protocol && a.description == b.description
+}
+
+fn same_tenant(a: &TenantInfo, b: &TenantInfo) -> bool {
+    a.display_name == b.display_name && a.description == b.description && a.server == b.server
+}
+
+impl RegistryStore for HttpRegistry {
+    fn load_servers(&self) -> anyhow::Result<ServerConfig> {
+        let mut servers = self.team_servers()?;
+        servers.extend(self.local.load_servers()?.servers);
+        Ok(ServerConfig { servers })
+    }
+
+    fn save_servers(&self, config: &ServerConfig) -> anyhow::Result<()> {
+        let team = self.team_servers()?;
+        if let Some(name) = team.keys().find(|name| !config.servers.contains_key(*name)) {
+            return Err(anyhow::anyhow!("Server '{}' is managed by the team registry and cannot be removed", name));
+        }
+
+        let overrides = config
+            .servers
+            .iter()
+            .filter(|(name, info)| team.get(*name).is_none_or(|t| !same_server(t, info)))
+            .map(|(name, info)| (name.clone(), info.clone()))
+            .collect();
+        self.local.save_servers(&ServerConfig { servers: overrides })
+    }
+
+    fn load_tenants(&self) -> anyhow::Result<TenantConfig> {
+        let mut tenants = self.team_tenants()?;
+        tenants.extend(self.local.load_tenants()?.tenants);
+        Ok(TenantConfig { tenants })
+    }
+
+    fn save_tenants(&self, config: &TenantConfig) -> anyhow::Result<()> {
+        let team = self.team_tenants()?;
+        if let Some(name) = team.keys().find(|name| !config.tenants.contains_key(*name)) {
+            return Err(anyhow::anyhow!("Tenant '{}' is managed by the team registry and cannot be removed", name));
+        }
+
+        let overrides = config
+            .tenants
+            .iter()
+            .filter(|(name, info)| team.get(*name).is_none_or(|t| !same_tenant(t, info)))
+            .map(|(name, info)| (name.clone(), info.clone()))
+            .collect();
+        self.local.save_tenants(&TenantConfig { tenants: overrides })
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.local.read_only
+    }
+
+    fn describe(&self) -> String {
+        format!("http ({}) with local overrides in {}", self.url, self.local.dir.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::config::ServerStatus;
+
+    const URL: &str = "https://registry.example.com/monk.json";
+
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("monk-registry-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn server(hostname: &str) -> ServerInfo {
+        ServerInfo {
+            hostname: hostname.to_string(),
+            port: 443,
+            protocol: "https".to_string(),
+            description: String::new(),
+            added_at: Utc::now(),
+            last_ping: None,
+            status: ServerStatus::Unknown,
+        }
+    }
+
+    fn tenant(server: &str) -> TenantInfo {
+        TenantInfo {
+            display_name: "Acme".to_string(),
+            description: String::new(),
+            server: server.to_string(),
+            added_at: Utc::now(),
+        }
+    }
+
+    /// An HTTP registry over `dir` whose cache holds team servers prod and staging and
+    /// team tenant acme
+    fn team_registry(dir: &PathBuf, read_only: bool) -> HttpRegistry {
+        let cache = RegistryCache {
+            url: URL.to_string(),
+            pulled_at: Utc::now(),
+            servers: HashMap::from([
+                ("prod".to_string(), server("prod.example.com")),
+                ("staging".to_string(), server("staging.example.com")),
+            ]),
+            tenants: HashMap::from([("acme".to_string(), tenant("prod"))]),
+        };
+        fs::write(dir.join("registry-cache.json"), serde_json::to_string(&cache).unwrap()).unwrap();
+        HttpRegistry::new(URL.to_string(), FileRegistry::new(dir.clone(), read_only))
+    }
+
+    fn local_servers(dir: &PathBuf) -> HashMap<String, ServerInfo> {
+        FileRegistry::new(dir.clone(), true).load_servers().unwrap().servers
+    }
+
+    #[test]
+    fn test_local_entries_shadow_team_entries() {
+        let dir = scratch_dir();
+        let registry = team_registry(&dir, false);
+        let local = ServerConfig { servers: HashMap::from([("prod".to_string(), server("localhost"))]) };
+        FileRegistry::new(dir.clone(), false).save_servers(&local).unwrap();
+
+        let servers = registry.load_servers().unwrap().servers;
+        assert_eq!(servers["prod"].hostname, "localhost");
+        assert_eq!(servers["staging"].hostname, "staging.example.com");
+
+        // A cache pulled from another registry URL is ignored
+        let other = HttpRegistry::new("https://other.example.com".to_string(), FileRegistry::new(dir.clone(), false));
+        assert!(other.load_cache().unwrap().is_none());
+        assert_eq!(other.load_servers().unwrap().servers.len(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_team_entries_are_not_removable() {
+        let dir = scratch_dir();
+        let registry = team_registry(&dir, false);
+
+        let mut servers = registry.load_servers().unwrap();
+        servers.servers.remove("staging");
+        let error = registry.save_servers(&servers).unwrap_err();
+        assert!(error.to_string().contains("'staging'"), "{}", error);
+
+        let mut tenants = registry.load_tenants().unwrap();
+        tenants.tenants.remove("acme");
+        assert!(registry.save_tenants(&tenants).is_err());
+        assert!(!dir.join("server.json").exists() && !dir.join("tenant.json").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_only_differences_are_written_back() {
+        let dir = scratch_dir();
+        let registry = team_registry(&dir, false);
+
+        let mut servers = registry.load_servers().unwrap();
+        servers.servers.get_mut("staging").unwrap().port = 8443;
+        servers.servers.insert("dev".to_string(), server("localhost"));
+        registry.save_servers(&servers).unwrap();
+
+        let written = local_servers(&dir);
+        let mut names: Vec<&str> = written.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["dev", "staging"]);
+        assert_eq!(written["staging"].port, 8443);
+        assert_eq!(registry.load_servers().unwrap().servers.len(), 3);
+
+        // Changing an override back to the team copy drops it
+        servers.servers.get_mut("staging").unwrap().port = 443;
+        registry.save_servers(&servers).unwrap();
+        assert!(!local_servers(&dir).contains_key("staging"));
+
+        let tenants = registry.load_tenants().unwrap();
+        registry.save_tenants(&tenants).unwrap();
+        assert!(FileRegistry::new(dir.clone(), true).load_tenants().unwrap().tenants.is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_read_only_registries_reject_writes() {
+        let dir = scratch_dir();
+        let registry = team_registry(&dir, true);
+        assert!(registry.is_read_only());
+
+        let mut servers = registry.load_servers().unwrap();
+        servers.servers.insert("dev".to_string(), server("localhost"));
+        assert!(registry.save_servers(&servers).is_err());
+        assert!(registry.save_tenants(&registry.load_tenants().unwrap()).is_err());
+
+        let file = FileRegistry::new(dir.clone(), true);
+        assert!(file.save_servers(&servers).is_err());
+        assert!(!dir.join("server.json").exists() && !dir.join("tenant.json").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_registry_files_are_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch_dir();
+        let path = dir.join("server.json");
+        fs::write(&path, "{\"servers\":{}}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let registry = FileRegistry::new(dir.clone(), false);
+        registry.save_servers(&ServerConfig { servers: HashMap::from([("dev".to_string(), server("localhost"))]) }).unwrap();
+        registry.save_tenants(&TenantConfig { tenants: HashMap::new() }).unwrap();
+        for file in ["server.json", "tenant.json"] {
+            let mode = fs::metadata(dir.join(file)).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600, "{}", file);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+//! Source of the current time for token expiry, record timestamps, TTLs and schedules
+//!
+//! Code reads the time through `clock::now()` instead of `Utc::now()`. Checks whose outcome
+//! depends on the time (token expiry, login lockout, refresh token and OIDC state lifetimes)
+//! take a `&dyn Clock` in the function doing the check, so tests pass a [`MockClock`] they
+//! move themselves instead of swapping a process-wide clock. Database-side `NOW()` and
+//! `Instant` durations are unaffected.
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system wall clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to
+pub struct MockClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl MockClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += by;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Current time according to the system clock
+pub fn now() -> DateTime<Utc> {
+    SystemClock.now()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_clock_only_moves_when_told() {
+        let start = Utc::now() - Duration::days(30);
+        let mock = MockClock::new(start);
+        assert_eq!(mock.now(), start);
+
+        mock.advance(Duration::hours(2));
+        assert_eq!(mock.now(), start + Duration::hours(2));
+
+        mock.set(start);
+        assert_eq!(mock.now(), start);
+        assert!(now() > start + Duration::days(29));
+    }
+}
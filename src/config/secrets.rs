@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use thiserror::Error;
+
+/// Prefix marking a config value as a reference to a secret rather than the secret itself
+pub const SECRET_SCHEME: &str = "secret://";
+
+/// Placeholder shown in place of secret values by config introspection
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("Invalid secret reference '{0}': expected secret://<provider>/<path>")]
+    InvalidReference(String),
+
+    #[error("No secret provider registered for '{0}'")]
+    UnknownProvider(String),
+
+    #[error("Secret not found: {0}")]
+    NotFound(String),
+
+    #[error("Failed to read secret {0}: {1}")]
+    ReadFailed(String, String),
+}
+
+/// Source of secret values, selected by the provider segment of a `secret://` URI
+///
+/// Built-in providers cover environment variables and mounted files; managed stores
+/// (Vault, AWS or GCP secret managers) plug in through `register_provider` so their
+/// client dependencies stay out of the base build.
+pub trait SecretProvider: Send + Sync {
+    fn resolve(&self, path: &str) -> Result<String, SecretError>;
+}
+
+/// `secret://env/JWT_SECRET` reads the JWT_SECRET environment variable
+pub struct EnvSecretProvider;
+
+impl SecretProvider for EnvSecretProvider {
+    fn resolve(&self, path: &str) -> Result<String, SecretError> {
+        std::env::var(path).map_err(|_| SecretError::NotFound(format!("environment variable {}", path)))
+    }
+}
+
+/// `secret://file/run/secrets/jwt` reads /run/secrets/jwt, without its trailing newline
+pub struct FileSecretProvider;
+
+impl SecretProvider for FileSecretProvider {
+    fn resolve(&self, path: &str) -> Result<String, SecretError> {
+        let path = format!("/{}", path.trim_start_matches('/'));
+        std::fs::read_to_string(&path)
+            .map(|contents| contents.trim_end_matches(['\r', '\n']).to_string())
+            .map_err(|e| SecretError::ReadFailed(path, e.to_string()))
+    }
+}
+
+static PROVIDERS: Lazy<RwLock<HashMap<String, Box<dyn SecretProvider>>>> = Lazy::new(|| {
+    let mut providers: HashMap<String, Box<dyn SecretProvider>> = HashMap::new();
+    providers.insert("env".to_string(), Box::new(EnvSecretProvider));
+    providers.insert("file".to_string(), Box::new(FileSecretProvider));
+    RwLock::new(providers)
+});
+
+/// Register a provider for `secret://<name>/...` references
+///
+/// Must happen before the config is first read for startup secrets to use it.
+pub fn register_provider(name: &str, provider: Box<dyn SecretProvider>) {
+    PROVIDERS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), provider);
+}
+
+/// Whether a config value is a `secret://` reference
+pub fn is_reference(value: &str) -> bool {
+    value.starts_with(SECRET_SCHEME)
+}
+
+/// Resolve a `secret://` reference; any other value is returned unchanged
+pub fn resolve(value: &str) -> Result<String, SecretError> {
+    let Some(reference) = value.strip_prefix(SECRET_SCHEME) else {
+        return Ok(value.to_string());
+    };
+    let (provider, path) = reference
+        .split_once('/')
+        .filter(|(provider, path)| !provider.is_empty() && !path.is_empty())
+        .ok_or_else(|| SecretError::InvalidReference(value.to_string()))?;
+
+    let providers = PROVIDERS.read().unwrap_or_else(|e| e.into_inner());
+    providers
+        .get(provider)
+        .ok_or_else(|| SecretError::UnknownProvider(provider.to_string()))?
+        .resolve(path)
+}
@@ -0,0 +1,436 @@
+//! Startup self-check: configuration invariants and external dependencies
+//!
+//! Runs once before the server binds its port so misconfiguration is reported up front
+//! instead of on the first request that trips over it.
+
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+use super::{AppConfig, Environment};
+use crate::database::manager::DatabaseManager;
+
+/// Secrets shipped in the built-in presets; fine for development, never for a deployment
+const PLACEHOLDER_SECRETS: &[&str] = &[
+    "dev-secret-key-change-in-production",
+    "staging-secret-set-via-env",
+    "production-secret-must-set-via-env",
+    "dev-url-signing-secret-change-in-production",
+    "staging-url-signing-secret-set-via-env",
+    "production-url-signing-secret-must-set-via-env",
+    "dev-mfa-encryption-key-change-in-production",
+    "staging-mfa-encryption-key-set-via-env",
+    "production-mfa-encryption-key-must-set-via-env",
+];
+
+/// Shortest JWT, URL-signing or MFA encryption secret accepted outside development
+const MIN_SECRET_LENGTH: usize = 32;
+
+/// Checks whose fatal findings stop a production server even with `allow_degraded_start`
+const NEVER_DEGRADED_IN_PRODUCTION: &[&str] = &["jwt_secret", "url_signing_secret", "mfa_encryption_key"];
+
+/// How long the registry database gets to answer
+const DATABASE_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    /// Works, but not as configured or not as intended
+    Warning,
+    /// The server cannot serve requests correctly; startup is refused unless overridden
+    Fatal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub check: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl CheckResult {
+    fn ok(check: &'static str, message: impl Into<String>) -> Self {
+        Self { check, severity: Severity::Ok, message: message.into() }
+    }
+
+    fn warning(check: &'static str, message: impl Into<String>) -> Self {
+        Self { check, severity: Severity::Warning, message: message.into() }
+    }
+
+    fn fatal(check: &'static str, message: impl Into<String>) -> Self {
+        Self { check, severity: Severity::Fatal, message: message.into() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfCheckReport {
+    /// Worst severity among the checks
+    pub status: Severity,
+    /// Started despite fatal findings because `api.allow_degraded_start` is set
+    pub degraded: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfCheckReport {
+    fn new(checks: Vec<CheckResult>, config: &AppConfig) -> Self {
+        let status = checks.iter().map(|c| c.severity).max().unwrap_or(Severity::Ok);
+        let blocking = matches!(config.environment, Environment::Production)
+            && checks
+                .iter()
+                .any(|c| c.severity == Severity::Fatal && NEVER_DEGRADED_IN_PRODUCTION.contains(&c.check));
+        let degraded = status == Severity::Fatal && config.api.allow_degraded_start && !blocking;
+        Self { status, degraded, checks }
+    }
+
+    /// Whether the server should refuse to start
+    pub fn refuses_start(&self) -> bool {
+        self.status == Severity::Fatal && !self.degraded
+    }
+
+    /// One log line per finding, plus a summary line
+    pub fn log(&self) {
+        for check in &self.checks {
+            match check.severity {
+                Severity::Ok => tracing::info!(check = check.check, "Self-check ok: {}", check.message),
+                Severity::Warning => tracing::warn!(check = check.check, "Self-check warning: {}", check.message),
+                Severity::Fatal => tracing::error!(check = check.check, "Self-check failed: {}", check.message),
+            }
+        }
+        let failed = self.checks.iter().filter(|c| c.severity == Severity::Fatal).count();
+        let warned = self.checks.iter().filter(|c| c.severity == Severity::Warning).count();
+        tracing::info!(
+            "Self-check finished: {} checks, {} warnings, {} failures{}",
+            self.checks.len(), warned, failed,
+            if self.degraded { " (starting degraded)" } else { "" }
+        );
+    }
+}
+
+static REPORT: OnceCell<SelfCheckReport> = OnceCell::new();
+
+/// Report of the startup self-check, once it has run
+pub fn report() -> Option<&'static SelfCheckReport> {
+    REPORT.get()
+}
+
+/// Check `config` and the services it points at, keeping the report for `report()`
+pub async fn run(config: &AppConfig) -> &'static SelfCheckReport {
+    let mut checks = validate(config);
+    checks.push(check_registry().await);
+    checks.extend(check_directories(config).await);
+    REPORT.get_or_init(|| SelfCheckReport::new(checks, config))
+}
+
+/// Invariants of the configuration itself, without touching anything external
+pub fn validate(config: &AppConfig) -> Vec<CheckResult> {
+    vec![
+        check_jwt_secret(config),
+        check_url_signing_secret(config),
+        check_mfa_encryption_key(config),
+        check_cors_origins(config),
+        check_database_settings(config),
+        check_clusters(config),
+        check_restricted_roles(config),
+        check_choices(config),
+        check_tenant_limits(config),
+        check_oidc_providers(config),
+    ]
+}
+
+fn check_jwt_secret(config: &AppConfig) -> CheckResult {
+    let security = &config.security;
+    match security.jwt_algorithm.to_uppercase().as_str() {
+        "HS256" => {}
+        "RS256" => return check_jwt_keys(config),
+        other => return CheckResult::fatal("jwt_secret", format!("SECURITY_JWT_ALGORITHM '{}' is not HS256 or RS256", other)),
+    }
+
+    let secret = &security.jwt_secret;
+    let deployed = !matches!(config.environment, Environment::Development);
+    if secret.is_empty() {
+        return CheckResult::fatal("jwt_secret", "SECURITY_JWT_SECRET is empty; tokens cannot be signed");
+    }
+    if PLACEHOLDER_SECRETS.contains(&secret.as_str()) {
+        let message = "SECURITY_JWT_SECRET is the built-in placeholder; anyone can forge tokens";
+        return if deployed { CheckResult::fatal("jwt_secret", message) } else { CheckResult::warning("jwt_secret", message) };
+    }
+    if deployed && secret.len() < MIN_SECRET_LENGTH {
+        return CheckResult::warning(
+            "jwt_secret",
+            format!("SECURITY_JWT_SECRET is {} bytes; use at least {}", secret.len(), MIN_SECRET_LENGTH),
+        );
+    }
+    CheckResult::ok("jwt_secret", "JWT secret set")
+}
+
+/// Signed links and OIDC state are HMACs under `url_signing_secret` whatever the JWT
+/// algorithm, so a placeholder lets anyone mint download links or forge callbacks
+fn check_url_signing_secret(config: &AppConfig) -> CheckResult {
+    let secret = &config.security.url_signing_secret;
+    let deployed = !matches!(config.environment, Environment::Development);
+    if secret.is_empty() {
+        let message = "SECURITY_URL_SIGNING_SECRET is empty; signed links can be forged";
+        return if deployed { CheckResult::fatal("url_signing_secret", message) } else { CheckResult::warning("url_signing_secret", message) };
+    }
+    if PLACEHOLDER_SECRETS.contains(&secret.as_str()) {
+        let message = "SECURITY_URL_SIGNING_SECRET is a built-in placeholder; anyone can forge signed links";
+        return if deployed { CheckResult::fatal("url_signing_secret", message) } else { CheckResult::warning("url_signing_secret", message) };
+    }
+    if deployed && secret.len() < MIN_SECRET_LENGTH {
+        return CheckResult::warning(
+            "url_signing_secret",
+            format!("SECURITY_URL_SIGNING_SECRET is {} bytes; use at least {}", secret.len(), MIN_SECRET_LENGTH),
+        );
+    }
+    CheckResult::ok("url_signing_secret", "URL signing secret set")
+}
+
+/// TOTP secrets are stored encrypted under `mfa_encryption_key`; a placeholder key
+/// leaves them readable to anyone with a copy of the database
+fn check_mfa_encryption_key(config: &AppConfig) -> CheckResult {
+    let key = &config.security.mfa_encryption_key;
+    let deployed = !matches!(config.environment, Environment::Development);
+    if key.is_empty() || PLACEHOLDER_SECRETS.contains(&key.as_str()) {
+        let message = "SECURITY_MFA_ENCRYPTION_KEY is empty or a built-in placeholder; stored TOTP secrets can be decrypted";
+        return if deployed { CheckResult::fatal("mfa_encryption_key", message) } else { CheckResult::warning("mfa_encryption_key", message) };
+    }
+    if deployed && key.len() < MIN_SECRET_LENGTH {
+        return CheckResult::warning(
+            "mfa_encryption_key",
+            format!("SECURITY_MFA_ENCRYPTION_KEY is {} bytes; use at least {}", key.len(), MIN_SECRET_LENGTH),
+        );
+    }
+    CheckResult::ok("mfa_encryption_key", "MFA encryption key set")
+}
+
+/// RS256 signs with the private key and verifies with the public key; both must parse
+fn check_jwt_keys(config: &AppConfig) -> CheckResult {
+    let security = &config.security;
+    if let Err(e) = jsonwebtoken::EncodingKey::from_rsa_pem(security.jwt_private_key.as_bytes()) {
+        return CheckResult::fatal("jwt_secret", format!("SECURITY_JWT_PRIVATE_KEY is not a PEM RSA private key: {}", e));
+    }
+    if let Err(e) = jsonwebtoken::DecodingKey::from_rsa_pem(security.jwt_public_key.as_bytes()) {
+        return CheckResult::fatal("jwt_secret", format!("SECURITY_JWT_PUBLIC_KEY is not a PEM RSA public key: {}", e));
+    }
+    CheckResult::ok("jwt_secret", "RS256 key pair loaded")
+}
+
+fn check_cors_origins(config: &AppConfig) -> CheckResult {
+    let security = &config.security;
+    if !security.enable_cors {
+        return CheckResult::ok("cors_origins", "CORS disabled");
+    }
+
+    let mut invalid = Vec::new();
+    let mut insecure = Vec::new();
+    for origin in &security.cors_origins {
+        if origin == "*" {
+            continue;
+        }
+        match url::Url::parse(origin) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() && url.path() == "/" && url.query().is_none() => {
+                if url.scheme() == "http" {
+                    insecure.push(origin.as_str());
+                }
+            }
+            _ => invalid.push(origin.as_str()),
+        }
+    }
+
+    if !invalid.is_empty() {
+        return CheckResult::fatal(
+            "cors_origins",
+            format!("Not origins of the form scheme://host[:port]: {}", invalid.join(", ")),
+        );
+    }
+    if security.cors_allow_credentials && security.cors_origins.iter().any(|o| o == "*") {
+        return CheckResult::fatal("cors_origins", "SECURITY_CORS_ALLOW_CREDENTIALS cannot be combined with origin '*'");
+    }
+    let unknown: Vec<&str> = security
+        .cors_methods
+        .iter()
+        .filter(|m| !matches!(m.as_str(), "GET" | "HEAD" | "POST" | "PUT" | "PATCH" | "DELETE" | "OPTIONS"))
+        .map(|m| m.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        return CheckResult::fatal("cors_origins", format!("SECURITY_CORS_METHODS has unknown methods: {}", unknown.join(", ")));
+    }
+    if security.require_https && !insecure.is_empty() {
+        return CheckResult::warning(
+            "cors_origins",
+            format!("HTTPS is required but these origins use http: {}", insecure.join(", ")),
+        );
+    }
+    CheckResult::ok("cors_origins", format!("{} origins allowed", security.cors_origins.len()))
+}
+
+fn check_database_settings(config: &AppConfig) -> CheckResult {
+    let database = &config.database;
+    if database.max_connections == 0 {
+        return CheckResult::fatal("database_pool", "DATABASE_MAX_CONNECTIONS must be at least 1");
+    }
+    if database.connection_timeout == 0 {
+        return CheckResult::warning("database_pool", "DATABASE_CONNECTION_TIMEOUT is 0; queries fail whenever the pool is busy");
+    }
+    CheckResult::ok("database_pool", format!("{} connections per database", database.max_connections))
+}
+
+/// DATABASE_CLUSTERS entries need unique names, a region and a parseable URL; replicas must
+/// name a known cluster, and write forwarding wants an endpoint for every other region
+fn check_clusters(config: &AppConfig) -> CheckResult {
+    let database = &config.database;
+    let mut names = vec![crate::database::regions::DEFAULT_CLUSTER];
+    for cluster in &database.clusters {
+        if names.contains(&cluster.name.as_str()) {
+            return CheckResult::fatal("database_clusters", format!("Cluster name '{}' is used twice", cluster.name));
+        }
+        if cluster.name.is_empty() || cluster.region.is_empty() {
+            return CheckResult::fatal("database_clusters", "DATABASE_CLUSTERS entries need a name and a region (name@region=url)");
+        }
+        if url::Url::parse(&cluster.url).is_err() {
+            return CheckResult::fatal("database_clusters", format!("Cluster '{}' has an invalid URL", cluster.name));
+        }
+        names.push(&cluster.name);
+    }
+    for replica in &database.replicas {
+        if !names.contains(&replica.cluster.as_str()) {
+            return CheckResult::fatal("database_clusters", format!("DATABASE_REPLICAS names unknown cluster '{}'", replica.cluster));
+        }
+        if url::Url::parse(&replica.url).is_err() {
+            return CheckResult::fatal("database_clusters", format!("Replica of '{}' in '{}' has an invalid URL", replica.cluster, replica.region));
+        }
+    }
+    if config.api.write_forwarding != "off" {
+        let local = if database.local_region.is_empty() { &database.default_region } else { &database.local_region };
+        let mut unreachable: Vec<&str> = std::iter::once(&database.default_region)
+            .chain(database.clusters.iter().map(|cluster| &cluster.region))
+            .filter(|region| *region != local && !config.api.region_endpoints.iter().any(|e| &e.region == *region))
+            .map(String::as_str)
+            .collect();
+        unreachable.sort();
+        unreachable.dedup();
+        if !unreachable.is_empty() {
+            return CheckResult::warning(
+                "database_clusters",
+                format!("No API_REGION_ENDPOINTS for {}; writes for their tenants go to the remote primary directly", unreachable.join(", ")),
+            );
+        }
+    }
+    CheckResult::ok(
+        "database_clusters",
+        format!("{} cluster(s), default region '{}'", names.len(), database.default_region),
+    )
+}
+
+fn check_restricted_roles(config: &AppConfig) -> CheckResult {
+    let database = &config.database;
+    if database.enable_restricted_roles && database.restricted_role_password.is_empty() {
+        return CheckResult::fatal(
+            "restricted_roles",
+            "DATABASE_ENABLE_RESTRICTED_ROLES is set without DATABASE_RESTRICTED_ROLE_PASSWORD",
+        );
+    }
+    CheckResult::ok("restricted_roles", if database.enable_restricted_roles { "enabled" } else { "disabled" })
+}
+
+/// String settings that only accept a fixed set of values and fall back silently otherwise
+fn check_choices(config: &AppConfig) -> CheckResult {
+    let mut unknown = Vec::new();
+    let choices: [(&str, &str, &[&str]); 5] = [
+        ("DATABASE_SQL_AUDIT_MODE", &config.database.sql_audit_mode, &["off", "log", "panic"]),
+        ("FILTER_DEFAULT_COUNT_MODE", &config.filter.default_count_mode, &["exact", "estimated", "none"]),
+        ("API_FILE_STORAGE_BACKEND", &config.api.file_storage_backend, &["local"]),
+        ("API_ERROR_REPORTING_BACKEND", &config.api.error_reporting_backend, &["none", "log", "sentry"]),
+        ("API_WRITE_FORWARDING", &config.api.write_forwarding, &["off", "redirect", "proxy"]),
+    ];
+    for (name, value, allowed) in choices {
+        if !allowed.contains(&value) {
+            unknown.push(format!("{}='{}' (expected {})", name, value, allowed.join(" | ")));
+        }
+    }
+    if crate::database::index_advisor::parse_window(&config.database.index_advisor_window).is_none() {
+        unknown.push(format!("DATABASE_INDEX_ADVISOR_WINDOW='{}' (expected HH:MM-HH:MM)", config.database.index_advisor_window));
+    }
+
+    if unknown.is_empty() {
+        CheckResult::ok("settings", "all enumerated settings valid")
+    } else {
+        CheckResult::warning("settings", format!("Unrecognized values, defaults apply: {}", unknown.join("; ")))
+    }
+}
+
+fn check_tenant_limits(config: &AppConfig) -> CheckResult {
+    let api = &config.api;
+    if api.enable_tenant_limits && api.tenant_max_in_flight == 0 {
+        return CheckResult::warning("tenant_limits", "API_TENANT_MAX_IN_FLIGHT is 0; each tenant gets one request slot");
+    }
+    CheckResult::ok("tenant_limits", if api.enable_tenant_limits { "enabled" } else { "disabled" })
+}
+
+/// Each provider needs credentials and absolute endpoint URLs; names must be unique
+fn check_oidc_providers(config: &AppConfig) -> CheckResult {
+    let providers = &config.security.oidc_providers;
+    let mut problems = Vec::new();
+    for (index, provider) in providers.iter().enumerate() {
+        if provider.name.is_empty() || providers[..index].iter().any(|other| other.name == provider.name) {
+            problems.push(format!("'{}' is empty or a duplicate name", provider.name));
+        }
+        if provider.client_id.is_empty() || provider.client_secret.is_empty() || provider.user_claim.is_empty() {
+            problems.push(format!("{} needs client_id, client_secret and user_claim", provider.name));
+        }
+        for url in [&provider.authorization_url, &provider.token_url, &provider.userinfo_url, &provider.redirect_url] {
+            if !url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+                problems.push(format!("{} has an invalid URL '{}'", provider.name, url));
+            }
+        }
+    }
+
+    if !problems.is_empty() {
+        return CheckResult::fatal("oidc_providers", format!("SECURITY_OIDC_PROVIDERS: {}", problems.join("; ")));
+    }
+    CheckResult::ok("oidc_providers", format!("{} providers configured", providers.len()))
+}
+
+/// The registry database holds tenants and their users; nothing authenticates without it
+async fn check_registry() -> CheckResult {
+    if std::env::var("DATABASE_URL").is_err() {
+        return CheckResult::fatal("registry_database", "DATABASE_URL is not set");
+    }
+    let reachable = tokio::time::timeout(DATABASE_CHECK_TIMEOUT, async {
+        let pool = DatabaseManager::main_pool().await?;
+        sqlx::query("SELECT 1 FROM tenants LIMIT 1").execute(&pool).await?;
+        Ok::<_, crate::database::manager::DatabaseError>(())
+    })
+    .await;
+
+    match reachable {
+        Ok(Ok(())) => CheckResult::ok("registry_database", "reachable"),
+        Ok(Err(e)) => CheckResult::fatal("registry_database", format!("unusable: {}", e)),
+        Err(_) => CheckResult::fatal(
+            "registry_database",
+            format!("no answer within {}s", DATABASE_CHECK_TIMEOUT.as_secs()),
+        ),
+    }
+}
+
+/// Directories the server writes to must exist or be creatable
+async fn check_directories(config: &AppConfig) -> Vec<CheckResult> {
+    let mut directories = vec![
+        ("export_directory", config.api.export_directory.as_str()),
+        ("file_storage_directory", config.api.file_storage_directory.as_str()),
+    ];
+    if config.database.enable_warehouse_sync && config.database.warehouse_s3_endpoint.is_empty() {
+        directories.push(("warehouse_directory", config.database.warehouse_directory.as_str()));
+    }
+
+    let mut checks = Vec::with_capacity(directories.len());
+    for (check, directory) in directories {
+        checks.push(match tokio::fs::create_dir_all(directory).await {
+            Ok(()) => CheckResult::ok(check, directory),
+            Err(e) => CheckResult::warning(check, format!("{} is not writable: {}", directory, e)),
+        });
+    }
+    checks
+}
@@ -0,0 +1,232 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::manager::DatabaseError;
+
+/// Prefix of every API key: `mk.<tenant>.<secret>`
+pub const KEY_PREFIX: &str = "mk";
+
+/// Operations an API key scope can grant
+pub const OPERATIONS: &[&str] = &["read", "create", "update", "delete"];
+
+/// What a key may do: `operations` on `schema`, where `"*"` matches every schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyScope {
+    pub schema: String,
+    pub operations: Vec<String>,
+}
+
+/// A stored API key; the secret itself is never kept, only its SHA-256 digest
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub user_id: Uuid,
+    /// First characters of the secret, to tell keys apart in listings
+    pub hint: String,
+    pub scopes: Json<Vec<ApiKeyScope>>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl ApiKey {
+    /// Whether the key's scopes grant `operation` on `schema`
+    pub fn allows(&self, schema: &str, operation: &str) -> bool {
+        self.scopes.iter().any(|scope| {
+            (scope.schema == "*" || scope.schema == schema)
+                && scope.operations.iter().any(|op| op == operation)
+        })
+    }
+}
+
+/// The key's owner as stored now, for building the request's auth context
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiKeyOwner {
+    pub auth: String,
+    pub access: String,
+}
+
+/// Scope operation a key needs for `method` on a route of `group` (data, find, file or
+/// odata), or None when keys cannot make the request
+///
+/// Find routes carry a filter in the body: POST on /find/:schema and its explain, sample,
+/// aggregate and count routes only reads, while PATCH updates and DELETE removes every
+/// matching record.
+pub fn route_operation(group: &str, method: &str) -> Option<&'static str> {
+    match (group, method) {
+        ("find", "POST") => Some("read"),
+        ("find", "PATCH") => Some("update"),
+        ("find", "DELETE") => Some("delete"),
+        ("find", _) => None,
+        (_, "GET") | (_, "HEAD") => Some("read"),
+        (_, "POST") => Some("create"),
+        (_, "PUT") | (_, "PATCH") => Some("update"),
+        (_, "DELETE") => Some("delete"),
+        _ => None,
+    }
+}
+
+/// Split `mk.<tenant>.<secret>` into tenant and secret
+pub fn parse_key(key: &str) -> Option<(&str, &str)> {
+    let rest = key.strip_prefix(KEY_PREFIX)?.strip_prefix('.')?;
+    let (tenant, secret) = rest.rsplit_once('.')?;
+    (!tenant.is_empty() && !secret.is_empty()).then_some((tenant, secret))
+}
+
+fn key_hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Create a key for `user_id`; the full key is returned once and cannot be recovered later
+pub async fn create(
+    pool: &PgPool,
+    tenant: &str,
+    user_id: Uuid,
+    name: &str,
+    scopes: Vec<ApiKeyScope>,
+    expires_at: Option<NaiveDateTime>,
+) -> Result<(ApiKey, String), DatabaseError> {
+    let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let key = format!("{}.{}.{}", KEY_PREFIX, tenant, secret);
+
+    let created: ApiKey = sqlx::query_as(
+        "INSERT INTO api_keys (name, user_id, key_hash, hint, scopes, created_at, expires_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) \
+         RETURNING id, name, user_id, hint, scopes, created_at, expires_at, last_used_at, revoked_at"
+    )
+        .bind(name)
+        .bind(user_id)
+        .bind(key_hash(&key))
+        .bind(&secret[..8])
+        .bind(Json(scopes))
+        .bind(crate::clock::now().naive_utc())
+        .bind(expires_at)
+        .fetch_one(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok((created, key))
+}
+
+/// Keys of `user_id`, newest first, including revoked and expired ones
+pub async fn list(pool: &PgPool, user_id: Uuid) -> Result<Vec<ApiKey>, DatabaseError> {
+    sqlx::query_as(
+        "SELECT id, name, user_id, hint, scopes, created_at, expires_at, last_used_at, revoked_at \
+         FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC"
+    )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)
+}
+
+/// Revoke a key of `user_id`; false when there is no such active key
+pub async fn revoke(pool: &PgPool, id: Uuid, user_id: Uuid) -> Result<bool, DatabaseError> {
+    let result = sqlx::query(
+        "UPDATE api_keys SET revoked_at = $3 WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+        .bind(id)
+        .bind(user_id)
+        .bind(crate::clock::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// The active, unexpired key matching `key` and its active owner, recording the use
+pub async fn authenticate(pool: &PgPool, key: &str) -> Result<Option<(ApiKey, ApiKeyOwner)>, DatabaseError> {
+    let now = crate::clock::now().naive_utc();
+    let found: Option<ApiKey> = sqlx::query_as(
+        "UPDATE api_keys SET last_used_at = $2 \
+         WHERE key_hash = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > $2) \
+         RETURNING id, name, user_id, hint, scopes, created_at, expires_at, last_used_at, revoked_at"
+    )
+        .bind(key_hash(key))
+        .bind(now)
+        .fetch_optional(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    let Some(found) = found else {
+        return Ok(None);
+    };
+    let owner: Option<ApiKeyOwner> = sqlx::query_as(
+        "SELECT auth, access FROM users WHERE id = $1 AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .bind(found.user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(owner.map(|owner| (found, owner)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(scopes: Vec<ApiKeyScope>) -> ApiKey {
+        ApiKey {
+            id: Uuid::new_v4(),
+            name: "etl".to_string(),
+            user_id: Uuid::new_v4(),
+            hint: "abcd".to_string(),
+            scopes: Json(scopes),
+            created_at: chrono::Utc::now().naive_utc(),
+            expires_at: None,
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+
+    fn scope(schema: &str, operations: &[&str]) -> ApiKeyScope {
+        ApiKeyScope { schema: schema.to_string(), operations: operations.iter().map(|op| op.to_string()).collect() }
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("mk.acme.0123abcd"), Some(("acme", "0123abcd")));
+        assert_eq!(parse_key("mk.acme.corp.0123abcd"), Some(("acme.corp", "0123abcd")));
+        assert_eq!(parse_key("mk..0123abcd"), None);
+        assert_eq!(parse_key("mk.acme."), None);
+        assert_eq!(parse_key("mk.acme"), None);
+        assert_eq!(parse_key("mkx.acme.0123abcd"), None);
+        assert_eq!(parse_key("eyJhbGciOiJIUzI1NiJ9.e30.sig"), None);
+    }
+
+    #[test]
+    fn test_key_allows_scoped_operations() {
+        let api_key = key(vec![scope("orders", &["read", "create"]), scope("*", &["read"])]);
+        assert!(api_key.allows("orders", "create"));
+        assert!(api_key.allows("customers", "read"));
+        assert!(!api_key.allows("customers", "create"));
+        assert!(!api_key.allows("orders", "delete"));
+        assert!(!key(Vec::new()).allows("orders", "read"));
+    }
+
+    #[test]
+    fn test_route_operation() {
+        assert_eq!(route_operation("data", "GET"), Some("read"));
+        assert_eq!(route_operation("odata", "HEAD"), Some("read"));
+        assert_eq!(route_operation("data", "POST"), Some("create"));
+        assert_eq!(route_operation("data", "PUT"), Some("update"));
+        assert_eq!(route_operation("data", "PATCH"), Some("update"));
+        assert_eq!(route_operation("file", "DELETE"), Some("delete"));
+        assert_eq!(route_operation("data", "OPTIONS"), None);
+
+        // Find reads with POST but updates or deletes every match with PATCH and DELETE
+        assert_eq!(route_operation("find", "POST"), Some("read"));
+        assert_eq!(route_operation("find", "PATCH"), Some("update"));
+        assert_eq!(route_operation("find", "DELETE"), Some("delete"));
+        assert_eq!(route_operation("find", "PUT"), None);
+        assert_eq!(route_operation("find", "GET"), None);
+    }
+}
@@ -0,0 +1,236 @@
+//! Audit trail of record changes
+//!
+//! Each create, update, delete and revert that goes through the observer pipeline adds one
+//! row per record to the tenant's `audit_log` table, recorded by the Ring 7 `AuditLogger`
+//! when `security.enable_audit_logging` is on.
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::database::manager::DatabaseError;
+use crate::database::record::Record;
+use crate::types::Operation;
+
+/// Fields left out of change summaries: they change on every write or are set by the system
+const UNAUDITED_FIELDS: &[&str] = &["id", "created_at", "updated_at", "trashed_at", "deleted_at"];
+
+/// Largest page returned by `list`
+pub const MAX_AUDIT_LIMIT: i64 = 1000;
+
+const ENTRY_COLUMNS: &str = "id, schema_name, record_id, operation, actor_id, actor, address, changes, created_at";
+
+/// Who made a change, attached to write pipelines by the request handler
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditActor {
+    pub user_id: Uuid,
+    /// Login name of the user
+    pub user: String,
+    /// Client IP address, when known
+    pub address: Option<String>,
+}
+
+/// One audited change to one record
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub schema_name: String,
+    pub record_id: Option<Uuid>,
+    /// create | update | delete | revert, or an event such as quarantine (see `record_event`)
+    pub operation: String,
+    pub actor_id: Option<Uuid>,
+    pub actor: Option<String>,
+    pub address: Option<String>,
+    /// Changed fields as `{ field: { "old": ..., "new": ... } }`; `old` is omitted when unknown
+    pub changes: Value,
+    pub created_at: NaiveDateTime,
+}
+
+/// A change to one record, before it is logged
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub schema_name: String,
+    pub record_id: Option<Uuid>,
+    pub operation: Operation,
+    pub changes: Value,
+}
+
+/// Filters for `list`; every field is optional and they combine with AND
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub schema: Option<String>,
+    pub record_id: Option<Uuid>,
+    pub operation: Option<String>,
+    /// Login name of the acting user
+    pub actor: Option<String>,
+    /// Only entries at or after this time
+    pub since: Option<NaiveDateTime>,
+    /// Only entries before this time
+    pub until: Option<NaiveDateTime>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub fn operation_name(operation: Operation) -> &'static str {
+    match operation {
+        Operation::Create => "create",
+        Operation::Update => "update",
+        Operation::Delete => "delete",
+        Operation::Revert => "revert",
+        Operation::Select => "select",
+    }
+}
+
+/// Changed fields of a written record
+///
+/// `written` is the row as stored; `request` is the record sent through the pipeline,
+/// whose original values (when loaded) supply `old`. Deletes and reverts only touch
+/// timestamps, so their summary is empty.
+pub fn change_summary(operation: Operation, written: &Map<String, Value>, request: Option<&Record>) -> Value {
+    let mut changes = Map::new();
+    let fields: Vec<&String> = match (operation, request) {
+        (Operation::Create, _) => written.keys().collect(),
+        (Operation::Update, Some(request)) => written.keys().filter(|k| request.get(k).is_some()).collect(),
+        _ => Vec::new(),
+    };
+    for field in fields {
+        if UNAUDITED_FIELDS.contains(&field.as_str()) {
+            continue;
+        }
+        let new = written.get(field).cloned().unwrap_or(Value::Null);
+        let old = request.and_then(|r| r.get_original(field));
+        if old == Some(&new) {
+            continue;
+        }
+        let mut change = Map::new();
+        if let Some(old) = old {
+            change.insert("old".to_string(), old.clone());
+        }
+        change.insert("new".to_string(), new);
+        changes.insert(field.clone(), Value::Object(change));
+    }
+    Value::Object(changes)
+}
+
+/// Write audit entries for one pipeline run in a single statement
+pub async fn record(executor: impl PgExecutor<'_>, actor: Option<&AuditActor>, entries: Vec<NewAuditEntry>) -> Result<(), DatabaseError> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut schema_names = Vec::with_capacity(entries.len());
+    let mut record_ids = Vec::with_capacity(entries.len());
+    let mut operations = Vec::with_capacity(entries.len());
+    let mut changes = Vec::with_capacity(entries.len());
+    for entry in entries {
+        schema_names.push(entry.schema_name);
+        record_ids.push(entry.record_id);
+        operations.push(operation_name(entry.operation).to_string());
+        changes.push(entry.changes);
+    }
+
+    sqlx::query(
+        "INSERT INTO audit_log (schema_name, record_id, operation, changes, actor_id, actor, address) \
+         SELECT e.schema_name, e.record_id, e.operation, e.changes, $5, $6, $7 \
+         FROM UNNEST($1::text[], $2::uuid[], $3::text[], $4::jsonb[]) AS e(schema_name, record_id, operation, changes)"
+    )
+        .bind(schema_names)
+        .bind(record_ids)
+        .bind(operations)
+        .bind(changes)
+        .bind(actor.map(|a| a.user_id))
+        .bind(actor.map(|a| a.user.clone()))
+        .bind(actor.and_then(|a| a.address.clone()))
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Log an event that is not a record write, such as a quarantined upload; `event` is
+/// stored as the entry's operation and `details` as its changes
+pub async fn record_event(
+    pool: &PgPool,
+    actor: Option<&AuditActor>,
+    schema_name: &str,
+    record_id: Option<Uuid>,
+    event: &str,
+    details: Value,
+) -> Result<(), DatabaseError> {
+    sqlx::query(
+        "INSERT INTO audit_log (schema_name, record_id, operation, changes, actor_id, actor, address) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+        .bind(schema_name)
+        .bind(record_id)
+        .bind(event)
+        .bind(details)
+        .bind(actor.map(|a| a.user_id))
+        .bind(actor.map(|a| a.user.clone()))
+        .bind(actor.and_then(|a| a.address.clone()))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Audit entries matching `query`, newest first
+pub async fn list(pool: &PgPool, query: &AuditQuery) -> Result<Vec<AuditEntry>, DatabaseError> {
+    let mut conditions = Vec::new();
+    let mut index = 0;
+    let mut next = |column: &str, op: &str| {
+        index += 1;
+        conditions.push(format!("{} {} ${}", column, op, index));
+    };
+    if query.schema.is_some() { next("schema_name", "="); }
+    if query.record_id.is_some() { next("record_id", "="); }
+    if query.operation.is_some() { next("operation", "="); }
+    if query.actor.is_some() { next("actor", "="); }
+    if query.since.is_some() { next("created_at", ">="); }
+    if query.until.is_some() { next("created_at", "<"); }
+
+    let where_clause = if conditions.is_empty() { String::new() } else { format!("WHERE {}", conditions.join(" AND ")) };
+    let sql = format!(
+        "SELECT {} FROM audit_log {} ORDER BY created_at DESC, id DESC LIMIT {} OFFSET {}",
+        ENTRY_COLUMNS,
+        where_clause,
+        query.limit.unwrap_or(100).clamp(1, MAX_AUDIT_LIMIT),
+        query.offset.unwrap_or(0).max(0),
+    );
+
+    let mut sql_query = sqlx::query_as::<_, AuditEntry>(&sql);
+    if let Some(ref schema) = query.schema { sql_query = sql_query.bind(schema); }
+    if let Some(record_id) = query.record_id { sql_query = sql_query.bind(record_id); }
+    if let Some(ref operation) = query.operation { sql_query = sql_query.bind(operation.to_lowercase()); }
+    if let Some(ref actor) = query.actor { sql_query = sql_query.bind(actor); }
+    if let Some(since) = query.since { sql_query = sql_query.bind(since); }
+    if let Some(until) = query.until { sql_query = sql_query.bind(until); }
+
+    Ok(sql_query.fetch_all(pool).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_change_summary() {
+        let written = json!({ "id": "x", "name": "Ann", "total": 5, "updated_at": "now" });
+        let written = written.as_object().unwrap();
+        assert_eq!(
+            change_summary(Operation::Create, written, None),
+            json!({ "name": { "new": "Ann" }, "total": { "new": 5 } })
+        );
+
+        let mut request = Record::from_sql_data(HashMap::from([
+            ("name".to_string(), json!("Ann")),
+            ("total".to_string(), json!(3)),
+        ]));
+        request.set("total", 5);
+        assert_eq!(
+            change_summary(Operation::Update, written, Some(&request)),
+            json!({ "total": { "old": 3, "new": 5 } })
+        );
+        assert_eq!(change_summary(Operation::Delete, written, Some(&request)), json!({}));
+    }
+}
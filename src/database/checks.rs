@@ -0,0 +1,193 @@
+use serde_json::{Map, Value};
+
+/// A CHECK constraint enforcing one JSON Schema bound of a property in Postgres
+///
+/// Constraints are named `{table}_{field}_{rule}_check` so a violation can be traced back
+/// to the property and rule it enforces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckConstraint {
+    pub name: String,
+    pub field: String,
+    pub rule: &'static str,
+    pub expression: String,
+}
+
+/// Rule names as they appear in constraint names
+const RULES: &[&str] = &["min_length", "max_length", "minimum", "maximum", "pattern", "enum"];
+
+/// CHECK constraints for the bounds of one JSON Schema property
+///
+/// Numeric properties get `minimum`/`maximum`; plain string properties get
+/// `minLength`/`maxLength`/`pattern`; `enum` applies to any string property.
+pub fn property_checks(table: &str, field: &str, property: &Value) -> Vec<CheckConstraint> {
+    let column = format!("\"{}\"", field);
+    let mut checks = Vec::new();
+    let mut push = |rule: &'static str, expression: String| {
+        checks.push(CheckConstraint {
+            name: format!("{}_{}_{}_check", table, field, rule),
+            field: field.to_string(),
+            rule,
+            expression,
+        });
+    };
+
+    match property.get("type").and_then(|t| t.as_str()) {
+        Some("integer") | Some("number") => {
+            if let Some(min) = property.get("minimum").and_then(numeric_literal) {
+                push("minimum", format!("{} >= {}", column, min));
+            }
+            if let Some(max) = property.get("maximum").and_then(numeric_literal) {
+                push("maximum", format!("{} <= {}", column, max));
+            }
+        }
+        Some("string") => {
+            // uuid and date-time strings are stored as UUID/TIMESTAMP columns
+            let textual = property.get("format").and_then(|f| f.as_str()).is_none_or(|f| !["uuid", "date-time"].contains(&f));
+            if textual {
+                if let Some(min) = property.get("minLength").and_then(length) {
+                    push("min_length", format!("char_length({}) >= {}", column, min));
+                }
+                if let Some(max) = property.get("maxLength").and_then(length) {
+                    push("max_length", format!("char_length({}) <= {}", column, max));
+                }
+                if let Some(pattern) = property.get("pattern").and_then(|p| p.as_str()) {
+                    push("pattern", format!("{} ~ {}", column, quote_literal(pattern)));
+                }
+            }
+            let values: Vec<String> = property
+                .get("enum")
+                .and_then(|e| e.as_array())
+                .map(|values| values.iter().filter_map(|v| v.as_str()).map(quote_literal).collect())
+                .unwrap_or_default();
+            if !values.is_empty() {
+                push("enum", format!("{}::text IN ({})", column, values.join(", ")));
+            }
+        }
+        _ => {}
+    }
+
+    checks
+}
+
+/// CHECK constraints for a `columns` record, which stores string lengths in minimum/maximum
+pub fn column_checks(table: &str, field: &str, column: &Map<String, Value>) -> Vec<CheckConstraint> {
+    let json_type = column
+        .get("json_type")
+        .and_then(|t| t.as_str())
+        .map(str::to_string)
+        .or_else(|| column.get("pg_type").and_then(|t| t.as_str()).map(json_type_of))
+        .unwrap_or_default();
+
+    let mut property = Map::new();
+    property.insert("type".to_string(), Value::String(json_type.clone()));
+    if let Some(format) = column.get("format") {
+        property.insert("format".to_string(), format.clone());
+    }
+    let (min_key, max_key) = if json_type == "string" { ("minLength", "maxLength") } else { ("minimum", "maximum") };
+    if let Some(min) = column.get("minimum").and_then(numeric_literal) {
+        property.insert(min_key.to_string(), number_value(&min));
+    }
+    if let Some(max) = column.get("maximum").and_then(numeric_literal) {
+        property.insert(max_key.to_string(), number_value(&max));
+    }
+    if let Some(pattern) = column.get("pattern_regex").filter(|p| p.is_string()) {
+        property.insert("pattern".to_string(), pattern.clone());
+    }
+    if let Some(values) = column.get("enum_values").filter(|v| v.is_array()) {
+        property.insert("enum".to_string(), values.clone());
+    }
+
+    property_checks(table, field, &Value::Object(property))
+}
+
+/// `ALTER TABLE ... ADD CONSTRAINT` for `check`
+pub fn add_check_ddl(table: &str, check: &CheckConstraint) -> String {
+    format!(
+        "ALTER TABLE \"{}\" ADD CONSTRAINT \"{}\" CHECK ({})",
+        table, check.name, check.expression
+    )
+}
+
+/// `ALTER TABLE ... DROP CONSTRAINT IF EXISTS` for every rule of `field`
+pub fn drop_checks_ddl(table: &str, field: &str) -> Vec<String> {
+    RULES
+        .iter()
+        .map(|rule| format!("ALTER TABLE \"{}\" DROP CONSTRAINT IF EXISTS \"{}_{}_{}_check\"", table, table, field, rule))
+        .collect()
+}
+
+/// Field and rule of a check violation (SQLSTATE 23514) on one of our named constraints
+///
+/// Returns `None` for other errors and for CHECK constraints not named by [`property_checks`].
+pub fn violated_check(error: &sqlx::Error) -> Option<(String, &'static str)> {
+    let sqlx::Error::Database(e) = error else {
+        return None;
+    };
+    if e.code().as_deref() != Some("23514") {
+        return None;
+    }
+
+    let pg = e.try_downcast_ref::<sqlx::postgres::PgDatabaseError>()?;
+    let rest = pg.constraint()?.strip_prefix(pg.table()?)?.strip_prefix('_')?.strip_suffix("_check")?;
+    RULES.iter().find_map(|rule| {
+        rest.strip_suffix(rule)
+            .and_then(|field| field.strip_suffix('_'))
+            .filter(|field| !field.is_empty())
+            .map(|field| (field.to_string(), *rule))
+    })
+}
+
+/// Message for a value of `field` rejected by `rule`
+pub fn violation_message(field: &str, rule: &str) -> String {
+    match rule {
+        "minimum" => format!("{} is below the schema minimum", field),
+        "maximum" => format!("{} is above the schema maximum", field),
+        "min_length" => format!("{} is shorter than the schema minLength", field),
+        "max_length" => format!("{} is longer than the schema maxLength", field),
+        "pattern" => format!("{} does not match the schema pattern", field),
+        "enum" => format!("{} is not one of the schema's enum values", field),
+        _ => format!("{} violates the schema", field),
+    }
+}
+
+/// A number usable verbatim in SQL, from a JSON number or numeric string
+fn numeric_literal(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) if s.parse::<f64>().is_ok_and(f64::is_finite) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// A non-negative whole number, which column records store as a float
+fn length(value: &Value) -> Option<u64> {
+    value
+        .as_u64()
+        .or_else(|| value.as_f64().filter(|f| *f >= 0.0 && f.fract() == 0.0).map(|f| f as u64))
+}
+
+fn number_value(literal: &str) -> Value {
+    literal
+        .parse::<u64>()
+        .map(Value::from)
+        .or_else(|_| literal.parse::<f64>().map(Value::from))
+        .unwrap_or(Value::Null)
+}
+
+fn quote_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+fn json_type_of(pg_type: &str) -> String {
+    let pg_type = pg_type.to_uppercase();
+    if pg_type.starts_with("INT") || pg_type.starts_with("BIGINT") || pg_type.starts_with("SMALLINT") {
+        "integer"
+    } else if pg_type.starts_with("DECIMAL") || pg_type.starts_with("NUMERIC") || pg_type.starts_with("REAL") || pg_type.starts_with("DOUBLE") {
+        "number"
+    } else if pg_type.starts_with("TEXT") || pg_type.starts_with("VARCHAR") || pg_type.starts_with("CHAR") {
+        "string"
+    } else {
+        ""
+    }
+    .to_string()
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use serde_json::Value;
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+use crate::database::manager::DatabaseError;
+
+/// Physical columns of a table as (column_name, data_type), in table order
+pub async fn load_columns(executor: impl PgExecutor<'_>, table: &str) -> Result<Vec<(String, String)>, DatabaseError> {
+    sqlx::query_as(
+        "SELECT column_name::text, data_type::text FROM information_schema.columns \
+         WHERE table_schema = current_schema() AND table_name = $1 ORDER BY ordinal_position"
+    )
+        .bind(table)
+        .fetch_all(executor)
+        .await
+        .map_err(DatabaseError::Sqlx)
+}
+
+/// Names of the JSON and JSONB columns among `load_columns` results
+pub fn json_columns(columns: &[(String, String)]) -> Vec<String> {
+    columns
+        .iter()
+        .filter(|(_, data_type)| data_type == "jsonb" || data_type == "json")
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Column metadata that drives enrichment of new records
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnFormat {
+    pub column_name: String,
+    pub pg_type: String,
+    /// Parsed `default_value`, which holds the property's default as JSON text
+    pub default: Option<Value>,
+}
+
+/// Active columns of the schema stored in `table`, from the columns table
+pub async fn load_column_formats(executor: impl PgExecutor<'_>, table: &str) -> Result<Vec<ColumnFormat>, DatabaseError> {
+    let columns: Vec<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT c.column_name, c.pg_type, c.default_value FROM columns c \
+         JOIN schemas s ON s.name = c.schema_name \
+         WHERE s.table_name = $1 AND s.trashed_at IS NULL AND s.deleted_at IS NULL \
+         AND c.trashed_at IS NULL AND c.deleted_at IS NULL"
+    )
+        .bind(table)
+        .fetch_all(executor)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(columns
+        .into_iter()
+        .map(|(column_name, pg_type, default_value)| ColumnFormat {
+            column_name,
+            pg_type,
+            // Defaults written before they were stored as JSON are plain text
+            default: default_value.map(|text| serde_json::from_str(&text).unwrap_or(Value::String(text))),
+        })
+        .collect())
+}
+
+/// Number written as a string, e.g. from a form or CSV; integers only when `integer`
+pub fn parse_number(text: &str, integer: bool) -> Option<Value> {
+    let text = text.trim();
+    if let Ok(number) = text.parse::<i64>() {
+        return Some(Value::from(number));
+    }
+    if integer {
+        return None;
+    }
+    text.parse::<f64>().ok().filter(|n| n.is_finite()).map(Value::from)
+}
+
+/// `value` in the canonical form for a column of `pg_type`, if it needs converting
+///
+/// Numeric strings become numbers, UUIDs are lowercased and hyphenated, and timestamps
+/// (RFC 3339, `YYYY-MM-DD HH:MM:SS` or a bare date) become RFC 3339 in UTC. Values that
+/// do not parse are left for Postgres to reject.
+pub fn coerce(pg_type: &str, value: &Value) -> Option<Value> {
+    let text = value.as_str()?;
+    let pg_type = pg_type.to_uppercase();
+    if pg_type.starts_with("INT") || pg_type.starts_with("BIGINT") || pg_type.starts_with("SMALLINT") {
+        parse_number(text, true)
+    } else if pg_type.starts_with("DECIMAL") || pg_type.starts_with("NUMERIC") || pg_type.starts_with("REAL") || pg_type.starts_with("DOUBLE") {
+        parse_number(text, false)
+    } else if pg_type == "UUID" {
+        let uuid = Uuid::parse_str(text.trim()).ok()?.hyphenated().to_string();
+        (uuid != text).then_some(Value::String(uuid))
+    } else if pg_type.starts_with("TIMESTAMP") {
+        let timestamp = parse_timestamp(text.trim())?.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+        (timestamp != text).then_some(Value::String(timestamp))
+    } else {
+        None
+    }
+}
+
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(text) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(text, "%Y-%m-%d").ok().and_then(|date| date.and_hms_opt(0, 0, 0)))
+        .map(|naive| naive.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_coerce() {
+        assert_eq!(coerce("INTEGER", &json!("42")), Some(json!(42)));
+        assert_eq!(coerce("INTEGER", &json!("4.2")), None);
+        assert_eq!(coerce("DECIMAL", &json!(" 4.5 ")), Some(json!(4.5)));
+        assert_eq!(coerce("DECIMAL", &json!(4.5)), None);
+        assert_eq!(
+            coerce("UUID", &json!("7C9E6679-7425-40DE-944B-E07FC1F90AE7")),
+            Some(json!("7c9e6679-7425-40de-944b-e07fc1f90ae7"))
+        );
+        assert_eq!(coerce("UUID", &json!("7c9e6679-7425-40de-944b-e07fc1f90ae7")), None);
+        assert_eq!(coerce("TIMESTAMP", &json!("2024-03-01")), Some(json!("2024-03-01T00:00:00Z")));
+        assert_eq!(coerce("TIMESTAMP", &json!("2024-03-01T12:30:00+02:00")), Some(json!("2024-03-01T10:30:00Z")));
+        assert_eq!(coerce("TEXT", &json!("42")), None);
+    }
+}
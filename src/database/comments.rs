@@ -0,0 +1,292 @@
+//! Threaded comments attached to records
+//!
+//! Comments live in the tenant's `comments` table, keyed by the schema and id of the record
+//! they discuss, and take their permissions from that record: whoever can read it can read
+//! and post comments, authors edit and delete their own, and users who can edit the record
+//! may delete anyone's. `@login` mentions are resolved to users who can read the record. A
+//! deleted comment keeps its place in the thread while it has replies, without its body.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::manager::DatabaseError;
+use crate::database::repository::Repository;
+use crate::filter::{FilterData, RecordAccess};
+
+/// Longest comment body accepted
+pub const MAX_COMMENT_LENGTH: usize = 10_000;
+
+/// Mentions resolved per comment; further names are left as plain text
+const MAX_MENTIONS: usize = 20;
+
+const COMMENT_COLUMNS: &str = "id, schema_name, record_id, parent_id, body, mentions, author_id, author, \
+     created_at, updated_at, edited_at, deleted_at";
+
+/// A comment, stored in the tenant's comments table
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Comment {
+    pub id: Uuid,
+    pub schema_name: String,
+    pub record_id: Uuid,
+    /// Comment this one replies to
+    pub parent_id: Option<Uuid>,
+    /// None once the comment is deleted
+    pub body: Option<String>,
+    /// Ids of the users mentioned with @login
+    pub mentions: Vec<Uuid>,
+    pub author_id: Option<Uuid>,
+    /// Login name of the author
+    pub author: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub edited_at: Option<NaiveDateTime>,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+/// Check the commented record is readable with `access`; returns whether it is also editable
+///
+/// Fails with NotFound when the record does not exist or is hidden from the caller.
+pub async fn check_record(
+    pool: &PgPool,
+    schema: &str,
+    record_id: Uuid,
+    access: Option<&RecordAccess>,
+) -> Result<bool, DatabaseError> {
+    Repository::new(schema, pool.clone())
+        .with_access(access.cloned())
+        .select_404(record_id)
+        .await?;
+    let Some(access) = access else {
+        return Ok(true);
+    };
+    // same rule as the AccessControl observer applies to writes
+    let (editable,): (bool,) = sqlx::query_as(&format!(
+        "SELECT {} FROM \"{}\" WHERE id = $1",
+        RecordAccess::editable_condition("$2"),
+        schema
+    ))
+        .bind(record_id)
+        .bind(access.to_array_literal())
+        .fetch_one(pool)
+        .await?;
+    Ok(editable)
+}
+
+/// Every comment on a record, oldest first, deleted ones included
+pub async fn list(pool: &PgPool, schema: &str, record_id: Uuid) -> Result<Vec<Comment>, DatabaseError> {
+    let comments = sqlx::query_as::<_, Comment>(&format!(
+        "SELECT {} FROM comments WHERE schema_name = $1 AND record_id = $2 ORDER BY created_at, id",
+        COMMENT_COLUMNS
+    ))
+        .bind(schema)
+        .bind(record_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(comments)
+}
+
+pub async fn get(pool: &PgPool, schema: &str, record_id: Uuid, id: Uuid) -> Result<Comment, DatabaseError> {
+    sqlx::query_as::<_, Comment>(&format!(
+        "SELECT {} FROM comments WHERE id = $1 AND schema_name = $2 AND record_id = $3",
+        COMMENT_COLUMNS
+    ))
+        .bind(id)
+        .bind(schema)
+        .bind(record_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DatabaseError::NotFound(format!("Comment '{}'", id)))
+}
+
+/// Add a comment to a record, replying to `parent_id` when given
+pub async fn create(
+    pool: &PgPool,
+    schema: &str,
+    record_id: Uuid,
+    parent_id: Option<Uuid>,
+    body: &str,
+    author_id: Uuid,
+    author: &str,
+) -> Result<Comment, DatabaseError> {
+    if let Some(parent_id) = parent_id {
+        let parent = get(pool, schema, record_id, parent_id).await?;
+        if parent.deleted_at.is_some() {
+            return Err(DatabaseError::InvalidOperation(format!("Comment '{}' is deleted", parent_id)));
+        }
+    }
+    let mentions = resolve_mentions(pool, schema, record_id, body).await?;
+
+    let comment = sqlx::query_as::<_, Comment>(&format!(
+        "INSERT INTO comments (schema_name, record_id, parent_id, body, mentions, author_id, author) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING {}",
+        COMMENT_COLUMNS
+    ))
+        .bind(schema)
+        .bind(record_id)
+        .bind(parent_id)
+        .bind(body)
+        .bind(&mentions)
+        .bind(author_id)
+        .bind(author)
+        .fetch_one(pool)
+        .await?;
+    Ok(comment)
+}
+
+/// Replace a comment's body, resolving its mentions again
+pub async fn edit(pool: &PgPool, comment: &Comment, body: &str) -> Result<Comment, DatabaseError> {
+    let mentions = resolve_mentions(pool, &comment.schema_name, comment.record_id, body).await?;
+    let comment = sqlx::query_as::<_, Comment>(&format!(
+        "UPDATE comments SET body = $2, mentions = $3, edited_at = now(), updated_at = now() \
+         WHERE id = $1 RETURNING {}",
+        COMMENT_COLUMNS
+    ))
+        .bind(comment.id)
+        .bind(body)
+        .bind(&mentions)
+        .fetch_one(pool)
+        .await?;
+    Ok(comment)
+}
+
+/// Delete a comment's body and mentions; replies stay in the thread
+pub async fn delete(pool: &PgPool, comment: &Comment) -> Result<(), DatabaseError> {
+    sqlx::query(
+        "UPDATE comments SET body = NULL, mentions = '{}', deleted_at = now(), updated_at = now() WHERE id = $1"
+    )
+        .bind(comment.id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Login names mentioned as `@login` in a comment body, in order and without repeats
+///
+/// A mention starts at an `@` that does not follow a word character, so email addresses
+/// in the text are not mentions, and ends before trailing punctuation.
+pub fn mentioned_logins(body: &str) -> Vec<String> {
+    let mut logins: Vec<String> = Vec::new();
+    let mut previous = ' ';
+    for (i, c) in body.char_indices() {
+        if c == '@' && !previous.is_alphanumeric() {
+            let login: String = body[i + 1..]
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '.' | '_' | '-' | '@' | '+'))
+                .collect();
+            let login = login.trim_end_matches(['.', '-', '@']);
+            if !login.is_empty() && !logins.iter().any(|l| l == login) {
+                logins.push(login.to_string());
+            }
+        }
+        previous = c;
+    }
+    logins
+}
+
+/// Ids of the mentioned users who can read the record
+async fn resolve_mentions(pool: &PgPool, schema: &str, record_id: Uuid, body: &str) -> Result<Vec<Uuid>, DatabaseError> {
+    let mut logins = mentioned_logins(body);
+    logins.truncate(MAX_MENTIONS);
+    if logins.is_empty() {
+        return Ok(Vec::new());
+    }
+    let users: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT id, access FROM users WHERE auth = ANY($1) AND access <> 'deny' \
+         AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .bind(&logins)
+        .fetch_all(pool)
+        .await?;
+
+    let mut mentions = Vec::new();
+    for (user_id, access) in users {
+        let readable = access == "root"
+            || Repository::new(schema, pool.clone())
+                .with_access(Some(RecordAccess::new(vec![user_id])))
+                .select_one(FilterData { where_clause: Some(json!({ "id": record_id })), ..Default::default() })
+                .await?
+                .is_some();
+        if readable {
+            mentions.push(user_id);
+        }
+    }
+    Ok(mentions)
+}
+
+/// Nest comments into threads: top-level comments oldest first, each with its `replies`
+///
+/// Deleted comments are dropped unless they still have replies. Replies whose parent is
+/// missing are shown at the top level.
+pub fn thread(comments: Vec<Comment>) -> Vec<Value> {
+    let ids: Vec<Uuid> = comments.iter().map(|c| c.id).collect();
+    let mut children: HashMap<Option<Uuid>, Vec<Comment>> = HashMap::new();
+    for comment in comments {
+        let parent = comment.parent_id.filter(|parent| ids.contains(parent));
+        children.entry(parent).or_default().push(comment);
+    }
+    nest(None, &mut children)
+}
+
+fn nest(parent: Option<Uuid>, children: &mut HashMap<Option<Uuid>, Vec<Comment>>) -> Vec<Value> {
+    let Some(comments) = children.remove(&parent) else {
+        return Vec::new();
+    };
+    comments
+        .into_iter()
+        .filter_map(|comment| {
+            let replies = nest(Some(comment.id), children);
+            if comment.deleted_at.is_some() && replies.is_empty() {
+                return None;
+            }
+            let mut value = json!(comment);
+            value["replies"] = Value::Array(replies);
+            Some(value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mentions_are_parsed_and_comments_nested_into_threads() {
+        assert_eq!(
+            mentioned_logins("@ada, can you and @grace.hopper@example.com check? cc @ada. mail bob@example.com"),
+            vec!["ada", "grace.hopper@example.com"]
+        );
+        assert!(mentioned_logins("@ @. no mentions").is_empty());
+
+        let at = NaiveDateTime::default();
+        let comment = |id: u128, parent: Option<u128>, deleted: bool| Comment {
+            id: Uuid::from_u128(id),
+            schema_name: "tickets".to_string(),
+            record_id: Uuid::from_u128(100),
+            parent_id: parent.map(Uuid::from_u128),
+            body: (!deleted).then(|| format!("comment {}", id)),
+            mentions: vec![],
+            author_id: None,
+            author: None,
+            created_at: at,
+            updated_at: at,
+            edited_at: None,
+            deleted_at: deleted.then_some(at),
+        };
+        let threads = thread(vec![
+            comment(1, None, true),
+            comment(2, Some(1), false),
+            comment(3, None, true),
+            comment(4, Some(99), false),
+        ]);
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0]["id"], json!(Uuid::from_u128(1)));
+        assert_eq!(threads[0]["body"], Value::Null);
+        assert_eq!(threads[0]["replies"][0]["id"], json!(Uuid::from_u128(2)));
+        assert_eq!(threads[1]["id"], json!(Uuid::from_u128(4)));
+    }
+}
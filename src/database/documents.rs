@@ -0,0 +1,260 @@
+//! Documents rendered from a record with a stored Handlebars template
+//!
+//! Templates are `document_templates` records. A template is rendered with the record's
+//! fields plus its relationships: each x-monk-relationship the record declares adds the
+//! related record under the relationship name (`customer_id` -> `customer`), and each
+//! schema pointing at the record adds its matching records as an array under that
+//! schema's name (`invoice_lines`). The output is HTML, or a PDF printed from it by a
+//! headless browser.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use sqlx::PgPool;
+use thiserror::Error;
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::database::manager::DatabaseError;
+use crate::database::relationships::load_relationships;
+use crate::database::repository::Repository;
+use crate::filter::{FilterData, RecordAccess};
+use crate::services::handlebars::Template;
+
+/// Schema holding document templates in every tenant database
+pub const DOCUMENT_TEMPLATE_SCHEMA: &str = "document_templates";
+
+pub const DOCUMENT_FORMATS: &[&str] = &["html", "pdf"];
+
+/// Records included per schema that points at the rendered record
+const RELATED_RECORDS_LIMIT: i32 = 1000;
+
+#[derive(Debug, Error)]
+pub enum DocumentError {
+    #[error("Document template '{0}' not found")]
+    TemplateNotFound(String),
+
+    #[error("Document template '{template}' renders '{expected}' records, not '{schema}'")]
+    WrongSchema { template: String, expected: String, schema: String },
+
+    #[error("Unsupported document format '{0}'; expected html or pdf")]
+    Format(String),
+
+    #[error("Invalid document template '{template}': {message}")]
+    Template { template: String, message: String },
+
+    #[error("PDF rendering failed: {0}")]
+    Pdf(String),
+
+    #[error("PDF rendering took longer than {0} seconds")]
+    Timeout(u64),
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+/// A `document_templates` record
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocumentTemplate {
+    pub name: String,
+    /// Schema the template renders; any schema when unset
+    #[serde(default)]
+    pub schema_name: Option<String>,
+    #[serde(default = "default_format")]
+    pub format: String,
+    pub body: String,
+    /// Handlebars pattern for the stored file name, without extension
+    #[serde(default)]
+    pub filename: Option<String>,
+}
+
+fn default_format() -> String {
+    "html".to_string()
+}
+
+/// Which relationships to include in the render context; None includes all of them
+pub type Includes<'a> = Option<&'a [String]>;
+
+pub async fn load_template(pool: &PgPool, name: &str) -> Result<DocumentTemplate, DocumentError> {
+    let row: Option<(Value,)> = sqlx::query_as(&format!(
+        "SELECT row_to_json(t)::jsonb FROM \"{}\" t WHERE name = $1 AND trashed_at IS NULL AND deleted_at IS NULL",
+        DOCUMENT_TEMPLATE_SCHEMA
+    ))
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(DatabaseError::from)?;
+    let (row,) = row.ok_or_else(|| DocumentError::TemplateNotFound(name.to_string()))?;
+    serde_json::from_value(row).map_err(|e| DocumentError::Template { template: name.to_string(), message: e.to_string() })
+}
+
+/// The record's fields plus its related records, as the template sees them
+///
+/// Related records are read with the caller's `access`, so a document never shows rows
+/// the requesting user could not read through the data API.
+pub async fn context(
+    pool: &PgPool,
+    schema: &str,
+    record: Value,
+    access: Option<RecordAccess>,
+    includes: Includes<'_>,
+) -> Result<Value, DatabaseError> {
+    let mut context = match record {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    };
+    let included = |name: &str| includes.is_none_or(|names| names.iter().any(|n| n == name));
+
+    for relationship in load_relationships(pool).await? {
+        if relationship.table == schema && included(&relationship.name) {
+            let related = match context.get(&relationship.column) {
+                Some(key) if !key.is_null() => {
+                    Repository::new(&relationship.related_table, pool.clone())
+                        .with_access(access.clone())
+                        .select_one(FilterData {
+                            where_clause: Some(json!({ relationship.related_column.as_str(): key })),
+                            ..Default::default()
+                        })
+                        .await?
+                }
+                _ => None,
+            };
+            let related = related.map(|record| record.to_api_output()).unwrap_or(Value::Null);
+            context.entry(relationship.name.clone()).or_insert(related);
+        } else if relationship.related_table == schema && included(&relationship.table) {
+            if context.contains_key(&relationship.table) {
+                continue;
+            }
+            let Some(key) = context.get(&relationship.related_column).filter(|key| !key.is_null()).cloned() else {
+                continue;
+            };
+            let children = Repository::new(&relationship.table, pool.clone())
+                .with_access(access.clone())
+                .select_any(FilterData {
+                    where_clause: Some(json!({ relationship.column.as_str(): key })),
+                    order: Some(json!("created_at asc")),
+                    limit: Some(RELATED_RECORDS_LIMIT),
+                    ..Default::default()
+                })
+                .await?;
+            let children = children.into_iter().map(|record| record.to_api_output()).collect();
+            context.insert(relationship.table.clone(), Value::Array(children));
+        }
+    }
+    Ok(Value::Object(context))
+}
+
+/// A rendered document, ready to be stored as an attachment
+#[derive(Debug, Clone)]
+pub struct RenderedDocument {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+/// Render `template` for one record of `schema`, given its `context`
+pub async fn render(
+    template: &DocumentTemplate,
+    schema: &str,
+    record_id: Uuid,
+    context: &Value,
+    format: &str,
+) -> Result<RenderedDocument, DocumentError> {
+    if let Some(expected) = template.schema_name.as_deref().filter(|s| !s.is_empty() && *s != schema) {
+        return Err(DocumentError::WrongSchema {
+            template: template.name.clone(),
+            expected: expected.to_string(),
+            schema: schema.to_string(),
+        });
+    }
+    if !DOCUMENT_FORMATS.contains(&format) {
+        return Err(DocumentError::Format(format.to_string()));
+    }
+    let invalid = |message: String| DocumentError::Template { template: template.name.clone(), message };
+
+    let html = Template::parse(&template.body).map_err(invalid)?.render(context);
+    let stem = match template.filename.as_deref().filter(|f| !f.is_empty()) {
+        Some(pattern) => Template::parse(pattern).map_err(invalid)?.render_text(context),
+        None => format!("{}-{}", template.name, record_id),
+    };
+
+    let (bytes, content_type) = match format {
+        "pdf" => (print_pdf(&html).await?, "application/pdf"),
+        _ => (html.into_bytes(), "text/html; charset=utf-8"),
+    };
+    Ok(RenderedDocument { filename: format!("{}.{}", file_stem(&stem), format), content_type, bytes })
+}
+
+/// A rendered file name with the characters attachments may not contain replaced
+fn file_stem(rendered: &str) -> String {
+    let stem: String = rendered
+        .trim()
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | '"') || c.is_control() { '_' } else { c })
+        .collect();
+    if stem.is_empty() { "document".to_string() } else { stem }
+}
+
+/// Print HTML to PDF with the configured headless browser
+async fn print_pdf(html: &str) -> Result<Vec<u8>, DocumentError> {
+    let config = crate::config::config();
+    let dir = std::env::temp_dir().join(format!("monk-render-{}", Uuid::new_v4()));
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| DocumentError::Pdf(e.to_string()))?;
+    let result = print_pdf_in(&dir, html, &config.api.render_pdf_command, config.api.render_timeout_secs).await;
+    let _ = tokio::fs::remove_dir_all(&dir).await;
+    result
+}
+
+async fn print_pdf_in(dir: &std::path::Path, html: &str, command: &str, timeout_secs: u64) -> Result<Vec<u8>, DocumentError> {
+    let input = dir.join("document.html");
+    let output = dir.join("document.pdf");
+    tokio::fs::write(&input, html).await.map_err(|e| DocumentError::Pdf(e.to_string()))?;
+
+    let child = Command::new(command)
+        .arg("--headless")
+        .arg("--disable-gpu")
+        .arg("--no-sandbox")
+        .arg("--no-pdf-header-footer")
+        .arg(format!("--user-data-dir={}", dir.join("profile").display()))
+        .arg(format!("--print-to-pdf={}", output.display()))
+        .arg(format!("file://{}", input.display()))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| DocumentError::Pdf(format!("Could not run '{}': {}", command, e)))?;
+    let finished = tokio::time::timeout(Duration::from_secs(timeout_secs), child.wait_with_output())
+        .await
+        .map_err(|_| DocumentError::Timeout(timeout_secs))?
+        .map_err(|e| DocumentError::Pdf(e.to_string()))?;
+    if !finished.status.success() {
+        return Err(DocumentError::Pdf(String::from_utf8_lossy(&finished.stderr).trim().to_string()));
+    }
+    tokio::fs::read(&output)
+        .await
+        .map_err(|_| DocumentError::Pdf(format!("'{}' produced no PDF", command)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_stems_are_safe_attachment_names() {
+        assert_eq!(file_stem(" invoice/2026\"07\n "), "invoice_2026_07");
+        assert_eq!(file_stem(""), "document");
+        let template: DocumentTemplate = serde_json::from_value(json!({
+            "id": "9a1c7f1e-0000-4000-8000-000000000000",
+            "name": "invoice",
+            "body": "<h1>{{number}}</h1>",
+            "schema_name": null,
+            "access_read": [],
+        }))
+        .unwrap();
+        assert_eq!(template.format, "html");
+        assert!(template.schema_name.is_none());
+    }
+}
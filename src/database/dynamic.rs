@@ -30,9 +30,9 @@ impl DynamicRepository {
             || filter_data.offset.is_some()
         {
             let mut filter = Filter::new(&self.table_name)
-                .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
-            filter.assign(filter_data).map_err(|e| DatabaseError::QueryError(e.to_string()))?;
-            filter.to_sql().map_err(|e| DatabaseError::QueryError(e.to_string()))?
+                .map_err(DatabaseError::Filter)?;
+            filter.assign(filter_data).map_err(DatabaseError::Filter)?;
+            filter.to_sql().map_err(DatabaseError::Filter)?
         } else {
             SqlResult { query: format!("SELECT * FROM \"{}\"", self.table_name), params: vec![] }
         };
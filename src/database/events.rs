@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+
+use crate::database::manager::{DatabaseError, DatabaseManager};
+
+/// Channel the monk_notify_change() trigger publishes on
+pub const NOTIFY_CHANNEL: &str = "monk_changes";
+
+/// How often the listener supervisor looks for newly created tenants
+const TENANT_REFRESH_SECS: u64 = 60;
+
+/// Delay before reconnecting a listener whose connection dropped
+const RECONNECT_DELAY_SECS: u64 = 5;
+
+/// Buffered events per subscriber before slow subscribers start lagging
+const BUS_CAPACITY: usize = 1024;
+
+/// A row change in a tenant database
+///
+/// Published for every insert, update and delete on tables carrying the notify trigger,
+/// whether the write came through the API or from an out-of-band tool such as psql.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Tenant database the change happened in
+    #[serde(default)]
+    pub database: String,
+    pub table: String,
+    /// insert | update | delete
+    pub operation: String,
+    pub id: Option<String>,
+}
+
+static EVENT_BUS: Lazy<broadcast::Sender<ChangeEvent>> = Lazy::new(|| broadcast::channel(BUS_CAPACITY).0);
+
+/// Subscribe to change events from every bridged tenant database
+pub fn subscribe() -> broadcast::Receiver<ChangeEvent> {
+    EVENT_BUS.subscribe()
+}
+
+/// Publish a change event to in-process subscribers (a no-op when nobody is subscribed)
+pub fn publish(event: ChangeEvent) {
+    let _ = EVENT_BUS.send(event);
+}
+
+/// Bridge LISTEN/NOTIFY from every active tenant database into the event bus
+///
+/// Runs forever: tenants created after startup are picked up on the next refresh.
+pub async fn run_listeners() {
+    let mut listening: HashSet<String> = HashSet::new();
+
+    loop {
+        match active_tenant_databases().await {
+            Ok(databases) => {
+                for database in databases {
+                    if listening.insert(database.clone()) {
+                        tokio::spawn(listen(database));
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Change notification bridge could not list tenants: {}", e),
+        }
+
+        tokio::time::sleep(Duration::from_secs(TENANT_REFRESH_SECS)).await;
+    }
+}
+
+/// Databases of every active tenant
+pub(crate) async fn active_tenant_databases() -> Result<Vec<String>, DatabaseError> {
+    let pool = DatabaseManager::main_pool().await?;
+    let databases: Vec<(String,)> = sqlx::query_as(
+        "SELECT database FROM tenants WHERE is_active = true AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .fetch_all(&pool)
+        .await?;
+    Ok(databases.into_iter().map(|(database,)| database).collect())
+}
+
+/// Forward notifications from one tenant database, reconnecting after failures
+async fn listen(database: String) {
+    loop {
+        if let Err(e) = forward_notifications(&database).await {
+            tracing::warn!("Change notification listener for {} failed: {}", database, e);
+        }
+        tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+    }
+}
+
+async fn forward_notifications(database: &str) -> Result<(), DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    let mut listener = PgListener::connect_with(&pool).await?;
+    listener.listen(NOTIFY_CHANNEL).await?;
+    tracing::info!("Listening for change notifications on {}", database);
+
+    loop {
+        let notification = listener.recv().await?;
+        match serde_json::from_str::<ChangeEvent>(notification.payload()) {
+            Ok(mut event) => {
+                event.database = database.to_string();
+                publish(event);
+            }
+            Err(e) => tracing::warn!(
+                "Ignoring malformed change notification from {}: {}", database, e
+            ),
+        }
+    }
+}
@@ -0,0 +1,510 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::NaiveDateTime;
+use flate2::{write::GzEncoder, Compression};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::auth::signed_url;
+use crate::canonical;
+use crate::database::manager::DatabaseError;
+use crate::database::record::Record;
+use crate::database::repository::Repository;
+use crate::filter::{FilterData, RecordAccess};
+
+pub const EXPORT_FORMATS: &[&str] = &["json", "ndjson", "csv"];
+pub const EXPORT_COMPRESSIONS: &[&str] = &["none", "gzip"];
+pub const EXPORT_DESTINATIONS: &[&str] = &["download", "s3"];
+
+const JOB_COLUMNS: &str = "id, schema_name, filter, format, compression, destination, upload_url, status, \
+     columns, last_id, processed_rows, bytes_written, checksum, error, created_by, access, created_at, updated_at, completed_at";
+
+/// A background export of one schema, stored in the tenant's export_jobs table
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExportJob {
+    pub id: Uuid,
+    pub schema_name: String,
+    pub filter: Value,
+    /// json | ndjson | csv
+    pub format: String,
+    /// none | gzip
+    pub compression: String,
+    /// download | s3
+    pub destination: String,
+    /// Presigned PUT URL for s3 destinations; never echoed back
+    #[serde(skip)]
+    pub upload_url: Option<String>,
+    /// pending | running | completed | failed
+    pub status: String,
+    /// CSV header, fixed by the first page so resumed jobs keep the same layout
+    pub columns: Option<Value>,
+    /// Cursor: id of the last exported record
+    pub last_id: Option<String>,
+    pub processed_rows: i64,
+    pub bytes_written: i64,
+    /// SHA-256 of the finished file; rows are canonical JSON, so equal data hashes equally
+    pub checksum: Option<String>,
+    pub error: Option<String>,
+    pub created_by: Option<Uuid>,
+    /// The creator's `RecordAccess` ids, which exported rows are checked against; NULL for root
+    #[serde(skip)]
+    pub access: Option<Vec<Uuid>>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+impl ExportJob {
+    pub fn file_name(&self) -> String {
+        let extension = if self.compression == "gzip" { format!("{}.gz", self.format) } else { self.format.clone() };
+        format!("{}-{}.{}", self.schema_name, self.id, extension)
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match (self.compression.as_str(), self.format.as_str()) {
+            ("gzip", _) => "application/gzip",
+            (_, "csv") => "text/csv",
+            (_, "ndjson") => "application/x-ndjson",
+            _ => "application/json",
+        }
+    }
+}
+
+/// Request for a new export job
+#[derive(Debug, Clone)]
+pub struct NewExportJob {
+    pub schema_name: String,
+    pub filter: FilterData,
+    pub format: String,
+    pub compression: String,
+    pub destination: String,
+    pub upload_url: Option<String>,
+    pub created_by: Uuid,
+    /// Rows the creator may read; None exports every row
+    pub access: Option<RecordAccess>,
+}
+
+#[derive(Debug, Error)]
+enum ExportError {
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    #[error("Export file error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Upload failed: {0}")]
+    Upload(String),
+}
+
+/// Jobs with a worker in this process; a job that is `running` but absent here was interrupted
+static ACTIVE_JOBS: Lazy<Mutex<HashSet<Uuid>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+struct ActiveJob(Uuid);
+
+impl Drop for ActiveJob {
+    fn drop(&mut self) {
+        ACTIVE_JOBS.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.0);
+    }
+}
+
+pub async fn create_job(pool: &PgPool, job: NewExportJob) -> Result<ExportJob, DatabaseError> {
+    let filter = serde_json::to_value(&job.filter).map_err(|e| DatabaseError::InvalidOperation(e.to_string()))?;
+    let created = sqlx::query_as::<_, ExportJob>(&format!(
+        "INSERT INTO export_jobs (schema_name, filter, format, compression, destination, upload_url, created_by, access) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING {}",
+        JOB_COLUMNS
+    ))
+        .bind(&job.schema_name)
+        .bind(filter)
+        .bind(&job.format)
+        .bind(&job.compression)
+        .bind(&job.destination)
+        .bind(&job.upload_url)
+        .bind(job.created_by)
+        .bind(job.access.map(|access| access.ids))
+        .fetch_one(pool)
+        .await?;
+    Ok(created)
+}
+
+pub async fn get_job(pool: &PgPool, id: Uuid) -> Result<ExportJob, DatabaseError> {
+    sqlx::query_as::<_, ExportJob>(&format!("SELECT {} FROM export_jobs WHERE id = $1", JOB_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DatabaseError::NotFound(format!("Export job '{}'", id)))
+}
+
+/// Most recent jobs first
+pub async fn list_jobs(pool: &PgPool, limit: i64) -> Result<Vec<ExportJob>, DatabaseError> {
+    let jobs = sqlx::query_as::<_, ExportJob>(&format!(
+        "SELECT {} FROM export_jobs ORDER BY created_at DESC LIMIT $1",
+        JOB_COLUMNS
+    ))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(jobs)
+}
+
+/// Whether a worker in this process is currently running the job
+pub fn is_active(id: Uuid) -> bool {
+    ACTIVE_JOBS.lock().unwrap_or_else(|e| e.into_inner()).contains(&id)
+}
+
+/// Start (or resume) a job in the background; false when it is already running here
+pub fn spawn(database: String, pool: PgPool, id: Uuid) -> bool {
+    if !ACTIVE_JOBS.lock().unwrap_or_else(|e| e.into_inner()).insert(id) {
+        return false;
+    }
+
+    tokio::spawn(async move {
+        let _active = ActiveJob(id);
+        if let Err(e) = run(&database, &pool, id).await {
+            tracing::warn!("Export job {} in {} failed: {}", id, database, e);
+            let _ = sqlx::query(
+                "UPDATE export_jobs SET status = 'failed', error = $2, updated_at = now() WHERE id = $1"
+            )
+                .bind(id)
+                .bind(e.to_string())
+                .execute(&pool)
+                .await;
+        }
+    });
+    true
+}
+
+/// Location of a job's export file
+pub fn file_path(database: &str, job: &ExportJob) -> PathBuf {
+    PathBuf::from(&crate::config::config().api.export_directory)
+        .join(database)
+        .join(job.file_name())
+}
+
+async fn run(database: &str, pool: &PgPool, id: Uuid) -> Result<(), ExportError> {
+    let mut job = get_job(pool, id).await?;
+    if job.status == "completed" {
+        return Ok(());
+    }
+
+    sqlx::query("UPDATE export_jobs SET status = 'running', error = NULL, updated_at = now() WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    let path = file_path(database, &job);
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut file = tokio::fs::OpenOptions::new().create(true).write(true).truncate(false).open(&path).await?;
+    // Drop anything written after the last checkpoint before appending again
+    file.set_len(job.bytes_written as u64).await?;
+    file.seek(std::io::SeekFrom::End(0)).await?;
+
+    let filter: FilterData = serde_json::from_value(job.filter.clone()).unwrap_or_default();
+    let page_size = crate::config::config().api.export_page_size.max(1);
+    let repository = Repository::new(&job.schema_name, pool.clone()).with_access(job.access.clone().map(RecordAccess::new));
+
+    loop {
+        let page = repository.select_any(page_filter(&filter, job.last_id.as_deref(), page_size)).await?;
+        let Some(last) = page.last() else { break };
+        let last_id = last
+            .get("id")
+            .map(|id| id.as_str().map(String::from).unwrap_or_else(|| id.to_string()))
+            .ok_or_else(|| DatabaseError::InvalidOperation("Exported record has no id".to_string()))?;
+
+        let rows: Vec<Value> = page.iter().map(|record| output_row(record, &filter)).collect();
+        if job.format == "csv" && job.columns.is_none() {
+            job.columns = Some(json!(csv_columns(&rows, &filter)));
+        }
+        let chunk = encode_page(&job, &rows);
+        let bytes = compress(&job, &chunk)?;
+        file.write_all(&bytes).await?;
+        file.sync_data().await?;
+
+        job.last_id = Some(last_id);
+        job.processed_rows += page.len() as i64;
+        job.bytes_written += bytes.len() as i64;
+        sqlx::query(
+            "UPDATE export_jobs SET last_id = $2, processed_rows = $3, bytes_written = $4, columns = $5, \
+             updated_at = now() WHERE id = $1"
+        )
+            .bind(id)
+            .bind(&job.last_id)
+            .bind(job.processed_rows)
+            .bind(job.bytes_written)
+            .bind(&job.columns)
+            .execute(pool)
+            .await
+            .map_err(DatabaseError::Sqlx)?;
+
+        if (page.len() as i32) < page_size {
+            break;
+        }
+    }
+
+    let footer = match (job.format.as_str(), job.processed_rows) {
+        ("json", 0) => "[]\n",
+        ("json", _) => "\n]\n",
+        _ => "",
+    };
+    let bytes = compress(&job, footer.as_bytes())?;
+    file.write_all(&bytes).await?;
+    file.sync_all().await?;
+    drop(file);
+    job.bytes_written += bytes.len() as i64;
+    let checksum = file_checksum(&path).await?;
+
+    if job.destination == "s3" {
+        upload(&job, &path).await?;
+        tokio::fs::remove_file(&path).await?;
+    }
+
+    sqlx::query(
+        "UPDATE export_jobs SET status = 'completed', bytes_written = $2, checksum = $3, completed_at = now(), \
+         updated_at = now() WHERE id = $1"
+    )
+        .bind(id)
+        .bind(job.bytes_written)
+        .bind(&checksum)
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    tracing::info!("Export job {} in {} completed: {} rows", id, database, job.processed_rows);
+    Ok(())
+}
+
+/// The job's filter restricted to records after the cursor, in id order
+///
+/// Keyset pagination keeps every page cheap on large tables and makes the cursor a
+/// stable resume point; any `order` in the job's filter is therefore ignored.
+fn page_filter(filter: &FilterData, last_id: Option<&str>, page_size: i32) -> FilterData {
+    let mut page = filter.clone();
+    if let Some(last_id) = last_id {
+        let after = json!({ "id": { "$gt": last_id } });
+        page.where_clause = Some(match page.where_clause.take() {
+            Some(condition) => json!({ "$and": [condition, after] }),
+            None => after,
+        });
+    }
+    if let Some(select) = page.select.as_mut() {
+        if !select.iter().any(|c| c == "id") {
+            select.push("id".to_string());
+        }
+    }
+    page.order = Some(json!("id asc"));
+    page.limit = Some(page_size);
+    page.offset = None;
+    page.cursor = None;
+    page
+}
+
+/// Record as exported: the selected fields only, in select order when given
+fn output_row(record: &Record, filter: &FilterData) -> Value {
+    let data = record.to_json();
+    match &filter.select {
+        Some(select) => Value::Object(
+            select
+                .iter()
+                .map(|column| (column.clone(), data.get(column).cloned().unwrap_or(Value::Null)))
+                .collect(),
+        ),
+        None => data,
+    }
+}
+
+fn csv_columns(rows: &[Value], filter: &FilterData) -> Vec<String> {
+    match &filter.select {
+        Some(select) => select.clone(),
+        None => rows
+            .first()
+            .and_then(|row| row.as_object())
+            .map(|object| object.keys().cloned().collect())
+            .unwrap_or_default(),
+    }
+}
+
+/// Serialize one page, including the header or separator that precedes it
+fn encode_page(job: &ExportJob, rows: &[Value]) -> Vec<u8> {
+    let mut out = String::new();
+    match job.format.as_str() {
+        "csv" => {
+            let columns: Vec<String> = job
+                .columns
+                .as_ref()
+                .and_then(|c| serde_json::from_value(c.clone()).ok())
+                .unwrap_or_default();
+            if job.processed_rows == 0 {
+                out.push_str(&columns.iter().map(|c| csv_field(&Value::String(c.clone()))).collect::<Vec<_>>().join(","));
+                out.push('\n');
+            }
+            for row in rows {
+                let fields: Vec<String> = columns.iter().map(|c| csv_field(row.get(c).unwrap_or(&Value::Null))).collect();
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+        }
+        "ndjson" => {
+            for row in rows {
+                out.push_str(&canonical::to_string(row));
+                out.push('\n');
+            }
+        }
+        _ => {
+            out.push_str(if job.processed_rows == 0 { "[\n" } else { ",\n" });
+            out.push_str(&rows.iter().map(canonical::to_string).collect::<Vec<_>>().join(",\n"));
+        }
+    }
+    out.into_bytes()
+}
+
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return String::new(),
+        Value::String(s) => s.clone(),
+        other => canonical::to_string(other),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// Hex SHA-256 of a finished export file
+///
+/// gzip members carry no timestamp, so compressed exports of the same rows hash alike too.
+async fn file_checksum(path: &PathBuf) -> Result<String, std::io::Error> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Each page is its own gzip member; concatenated members form a valid gzip file
+fn compress(job: &ExportJob, bytes: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+    if job.compression != "gzip" || bytes.is_empty() {
+        return Ok(bytes.to_vec());
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+/// PUT the finished file to the job's presigned S3 URL
+async fn upload(job: &ExportJob, path: &PathBuf) -> Result<(), ExportError> {
+    let url = job
+        .upload_url
+        .as_deref()
+        .ok_or_else(|| ExportError::Upload("s3 destination has no upload_url".to_string()))?;
+    let file = tokio::fs::File::open(path).await?;
+    let length = file.metadata().await?.len();
+
+    reqwest::Client::new()
+        .put(url)
+        .header(reqwest::header::CONTENT_LENGTH, length)
+        .header(reqwest::header::CONTENT_TYPE, job.content_type())
+        .body(reqwest::Body::from(file))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| ExportError::Upload(e.to_string()))?;
+    Ok(())
+}
+
+/// Signed, expiring download path for a completed job; valid without a bearer token
+pub fn download_url(database: &str, id: Uuid) -> (String, i64) {
+    let expires = crate::clock::now().timestamp() + crate::config::config().api.export_url_ttl_secs as i64;
+    let signature = signed_url::sign_until("export", &[database, &id.to_string()], expires);
+    (
+        format!("/api/export/download/{}/{}?expires={}&signature={}", database, id, expires, signature),
+        expires,
+    )
+}
+
+/// Check a download signature and its expiry
+pub fn verify_download(database: &str, id: Uuid, expires: i64, signature: &str) -> bool {
+    signed_url::verify_until("export", &[database, &id.to_string()], expires, signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLES: &str = "\
+        CREATE TEMPORARY TABLE export_jobs (
+            id uuid PRIMARY KEY DEFAULT gen_random_uuid(), schema_name text NOT NULL, filter jsonb DEFAULT '{}' NOT NULL,
+            format text NOT NULL, compression text DEFAULT 'none' NOT NULL, destination text DEFAULT 'download' NOT NULL,
+            upload_url text, status text DEFAULT 'pending' NOT NULL, columns jsonb, last_id text,
+            processed_rows bigint DEFAULT 0 NOT NULL, bytes_written bigint DEFAULT 0 NOT NULL, checksum text, error text,
+            created_by uuid, access uuid[], created_at timestamp DEFAULT now() NOT NULL,
+            updated_at timestamp DEFAULT now() NOT NULL, completed_at timestamp
+        );
+        CREATE TEMPORARY TABLE items (
+            id uuid PRIMARY KEY DEFAULT gen_random_uuid(), name text,
+            access_read uuid[] DEFAULT '{}', access_edit uuid[] DEFAULT '{}', access_full uuid[] DEFAULT '{}',
+            access_deny uuid[] DEFAULT '{}', trashed_at timestamp, deleted_at timestamp
+        );";
+
+    #[tokio::test]
+    async fn test_export_excludes_rows_the_creator_cannot_read() {
+        let Some(pool) = crate::testing::scratch_pool(TABLES).await else { return };
+        let user = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let rows: &[(&str, &[Uuid], &[Uuid])] = &[
+            ("open", &[], &[]),
+            ("mine", &[user], &[]),
+            ("others", &[other], &[]),
+            ("denied", &[], &[user]),
+        ];
+        for (name, read, deny) in rows {
+            sqlx::query("INSERT INTO items (name, access_read, access_deny) VALUES ($1, $2, $3)")
+                .bind(name).bind(read).bind(deny)
+                .execute(&pool).await.unwrap();
+        }
+
+        let job = create_job(&pool, NewExportJob {
+            schema_name: "items".to_string(),
+            filter: FilterData::default(),
+            format: "ndjson".to_string(),
+            compression: "none".to_string(),
+            destination: "download".to_string(),
+            upload_url: None,
+            created_by: user,
+            access: Some(RecordAccess::new(vec![user])),
+        }).await.unwrap();
+        let database = format!("test_export_{}", Uuid::new_v4().simple());
+        run(&database, &pool, job.id).await.unwrap();
+
+        let job = get_job(&pool, job.id).await.unwrap();
+        let path = file_path(&database, &job);
+        let contents = tokio::fs::read_to_string(&path).await.unwrap();
+        tokio::fs::remove_dir_all(path.parent().unwrap()).await.unwrap();
+
+        let mut names: Vec<String> = contents
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["mine", "open"]);
+        assert_eq!(job.processed_rows, 2);
+    }
+}
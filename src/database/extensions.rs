@@ -0,0 +1,29 @@
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::database::manager::DatabaseError;
+use crate::filter::filter_where::FilterWhere;
+
+/// Whether the named extension is installed in the database behind `pool`
+pub async fn extension_installed(pool: &PgPool, name: &str) -> Result<bool, DatabaseError> {
+    let installed: Option<(i32,)> = sqlx::query_as("SELECT 1 FROM pg_extension WHERE extname = $1")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+    Ok(installed.is_some())
+}
+
+/// Whether `$ilike` in `where_clause` should ignore accents
+///
+/// True when `filter.enable_unaccent` is on, the clause uses `$ilike`, and the tenant
+/// database has the unaccent extension; otherwise `$ilike` stays accent-sensitive.
+pub async fn use_unaccent(pool: &PgPool, where_clause: Option<&Value>) -> Result<bool, DatabaseError> {
+    if !crate::config::config().filter.enable_unaccent {
+        return Ok(false);
+    }
+    match where_clause {
+        Some(where_data) if FilterWhere::uses_operator(where_data, "$ilike") => extension_installed(pool, "unaccent").await,
+        _ => Ok(false),
+    }
+}
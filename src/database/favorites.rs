@@ -0,0 +1,203 @@
+//! Per-user favorites and recently viewed records
+//!
+//! Views are kept only for schemas whose definition sets `"x-monk-track-views": true`.
+//! Opening a record through GET /api/data/:schema/:id marks the select with a
+//! `RecordViewer`, and the RecentViewTracker observer records the view after the response
+//! is sent. Each user keeps their latest `api.recent_views_per_user` views; favorites are
+//! explicit and capped at `api.favorites_per_user`. Listings fetch the records with the
+//! user's access, so records since deleted or hidden from them are left out.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::manager::DatabaseError;
+use crate::database::repository::Repository;
+use crate::filter::{FilterData, RecordAccess};
+
+/// Schema definition key opting a schema in to view tracking
+pub const TRACK_VIEWS_KEY: &str = "x-monk-track-views";
+
+/// Entries of schemas since trashed or dropped are not listed
+const ACTIVE_SCHEMA: &str = "schema_name IN (SELECT table_name FROM schemas WHERE trashed_at IS NULL AND deleted_at IS NULL)";
+
+/// Marks a select as a user opening a record, for the RecentViewTracker observer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordViewer {
+    pub user_id: Uuid,
+}
+
+/// A record a user has opened
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct RecordView {
+    pub schema_name: String,
+    pub record_id: Uuid,
+    /// Times the user opened it
+    pub views: i32,
+    pub viewed_at: NaiveDateTime,
+}
+
+/// A record a user marked as favorite
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Favorite {
+    pub schema_name: String,
+    pub record_id: Uuid,
+    pub created_at: NaiveDateTime,
+}
+
+/// Whether an active schema opted in to view tracking
+pub async fn tracks_views(pool: &PgPool, table: &str) -> Result<bool, DatabaseError> {
+    let tracked: Option<bool> = sqlx::query_scalar(
+        "SELECT COALESCE(definition -> $2 = 'true'::jsonb, false) FROM schemas \
+         WHERE table_name = $1 AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .bind(table)
+        .bind(TRACK_VIEWS_KEY)
+        .fetch_optional(pool)
+        .await?;
+    Ok(tracked.unwrap_or(false))
+}
+
+/// Record that a user opened records of a schema, then drop their views beyond the latest `keep`
+pub async fn record_views(pool: &PgPool, user_id: Uuid, schema: &str, record_ids: &[Uuid], keep: u32) -> Result<(), DatabaseError> {
+    sqlx::query(
+        "INSERT INTO record_views (user_id, schema_name, record_id) SELECT $1, $2, unnest($3::uuid[]) \
+         ON CONFLICT (user_id, schema_name, record_id) DO UPDATE SET views = record_views.views + 1, viewed_at = now()"
+    )
+        .bind(user_id)
+        .bind(schema)
+        .bind(record_ids)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "DELETE FROM record_views WHERE user_id = $1 AND (schema_name, record_id) NOT IN \
+         (SELECT schema_name, record_id FROM record_views WHERE user_id = $1 ORDER BY viewed_at DESC LIMIT $2)"
+    )
+        .bind(user_id)
+        .bind(keep as i64)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// A user's views, latest first, optionally of one schema
+pub async fn recent(pool: &PgPool, user_id: Uuid, schema: Option<&str>, limit: i64) -> Result<Vec<RecordView>, DatabaseError> {
+    let views = sqlx::query_as::<_, RecordView>(&format!(
+        "SELECT schema_name, record_id, views, viewed_at FROM record_views \
+         WHERE user_id = $1 AND ($2::text IS NULL OR schema_name = $2) AND {} ORDER BY viewed_at DESC LIMIT $3",
+        ACTIVE_SCHEMA
+    ))
+        .bind(user_id)
+        .bind(schema)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(views)
+}
+
+/// A user's favorites, latest first, optionally of one schema
+pub async fn favorites(pool: &PgPool, user_id: Uuid, schema: Option<&str>) -> Result<Vec<Favorite>, DatabaseError> {
+    let favorites = sqlx::query_as::<_, Favorite>(&format!(
+        "SELECT schema_name, record_id, created_at FROM favorites \
+         WHERE user_id = $1 AND ($2::text IS NULL OR schema_name = $2) AND {} ORDER BY created_at DESC",
+        ACTIVE_SCHEMA
+    ))
+        .bind(user_id)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+    Ok(favorites)
+}
+
+pub async fn count_favorites(pool: &PgPool, user_id: Uuid) -> Result<i64, DatabaseError> {
+    let count = sqlx::query_scalar("SELECT COUNT(*) FROM favorites WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count)
+}
+
+/// Mark a record as favorite; a record already marked keeps its original date
+pub async fn add_favorite(pool: &PgPool, user_id: Uuid, schema: &str, record_id: Uuid) -> Result<Favorite, DatabaseError> {
+    let favorite = sqlx::query_as::<_, Favorite>(
+        "INSERT INTO favorites (user_id, schema_name, record_id) VALUES ($1, $2, $3) \
+         ON CONFLICT (user_id, schema_name, record_id) DO UPDATE SET created_at = favorites.created_at \
+         RETURNING schema_name, record_id, created_at"
+    )
+        .bind(user_id)
+        .bind(schema)
+        .bind(record_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(favorite)
+}
+
+pub async fn is_favorite(pool: &PgPool, user_id: Uuid, schema: &str, record_id: Uuid) -> Result<bool, DatabaseError> {
+    let found = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM favorites WHERE user_id = $1 AND schema_name = $2 AND record_id = $3)"
+    )
+        .bind(user_id)
+        .bind(schema)
+        .bind(record_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(found)
+}
+
+/// Unmark a favorite; returns whether it was marked
+pub async fn remove_favorite(pool: &PgPool, user_id: Uuid, schema: &str, record_id: Uuid) -> Result<bool, DatabaseError> {
+    let result = sqlx::query("DELETE FROM favorites WHERE user_id = $1 AND schema_name = $2 AND record_id = $3")
+        .bind(user_id)
+        .bind(schema)
+        .bind(record_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Serialize `entries` with their record under `"record"`, fetched with `access`
+///
+/// Entries whose record is gone, or no longer visible to `access`, are left out.
+pub async fn with_records<T: Serialize>(
+    pool: &PgPool,
+    access: Option<RecordAccess>,
+    entries: &[T],
+    key: impl Fn(&T) -> (&str, Uuid),
+) -> Result<Vec<Value>, DatabaseError> {
+    let mut ids: HashMap<&str, Vec<String>> = HashMap::new();
+    for entry in entries {
+        let (schema, record_id) = key(entry);
+        ids.entry(schema).or_default().push(record_id.to_string());
+    }
+
+    let mut records: HashMap<(String, String), Value> = HashMap::new();
+    for (schema, ids) in ids {
+        let filter = FilterData {
+            where_clause: Some(json!({ "id": { "$in": ids } })),
+            ..Default::default()
+        };
+        let found = Repository::new(schema, pool.clone()).with_access(access.clone()).select_any(filter).await?;
+        for record in found {
+            let record = record.to_api_output();
+            if let Some(id) = record.get("id").and_then(|id| id.as_str()) {
+                records.insert((schema.to_string(), id.to_string()), record.clone());
+            }
+        }
+    }
+
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            let (schema, record_id) = key(entry);
+            let record = records.remove(&(schema.to_string(), record_id.to_string()))?;
+            let mut value = serde_json::to_value(entry).ok()?;
+            value["record"] = record;
+            Some(value)
+        })
+        .collect())
+}
@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use sqlx::PgPool;
+
+use crate::database::manager::DatabaseError;
+
+/// Known feature flags as (name, default, description)
+///
+/// A tenant's feature_flags rows override these defaults; rows naming unknown flags are
+/// ignored so a flag can be retired by deleting it here.
+pub const FEATURE_FLAGS: &[(&str, bool, &str)] = &[
+    ("find_aggregate", true, "Grouped counts, sums and averages via POST /api/find/:schema/aggregate"),
+    ("find_explain", true, "Query plans via POST /api/find/:schema/explain"),
+    ("find_sample", true, "Random samples via POST /api/find/:schema/sample"),
+    ("graphql", false, "GraphQL endpoint"),
+    ("mfa_required", false, "Require a verified TOTP code for sudo elevation"),
+    ("render_pdf", false, "PDF output from POST /api/data/:schema/:id/render"),
+    ("search", false, "Full-text search"),
+    ("webhooks", false, "Outbound webhooks on record changes"),
+    ("wire_format_v2", false, "Next response wire format"),
+];
+
+/// Whether `name` is a registered feature flag
+pub fn is_known(name: &str) -> bool {
+    FEATURE_FLAGS.iter().any(|(flag, _, _)| *flag == name)
+}
+
+/// Every known flag evaluated for the tenant owning `pool`
+///
+/// Tenants provisioned before the feature_flags table existed get the defaults.
+pub async fn load_feature_flags(pool: &PgPool) -> Result<BTreeMap<String, bool>, DatabaseError> {
+    let mut flags: BTreeMap<String, bool> = FEATURE_FLAGS
+        .iter()
+        .map(|(name, default, _)| (name.to_string(), *default))
+        .collect();
+
+    let rows: Vec<(String, bool)> = match sqlx::query_as("SELECT name, enabled FROM feature_flags")
+        .fetch_all(pool)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("42P01") => Vec::new(),
+        Err(e) => return Err(DatabaseError::Sqlx(e)),
+    };
+
+    for (name, enabled) in rows {
+        if let Some(flag) = flags.get_mut(&name) {
+            *flag = enabled;
+        }
+    }
+    Ok(flags)
+}
+
+/// Evaluate a single flag for the tenant owning `pool`
+pub async fn is_enabled(pool: &PgPool, name: &str) -> Result<bool, DatabaseError> {
+    Ok(load_feature_flags(pool).await?.get(name).copied().unwrap_or(false))
+}
+
+/// Set a tenant override for a known flag
+pub async fn set_feature_flag(pool: &PgPool, name: &str, enabled: bool) -> Result<(), DatabaseError> {
+    if !is_known(name) {
+        return Err(DatabaseError::NotFound(format!("Feature flag '{}'", name)));
+    }
+    sqlx::query(
+        "INSERT INTO feature_flags (name, enabled) VALUES ($1, $2) \
+         ON CONFLICT (name) DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = now()"
+    )
+        .bind(name)
+        .bind(enabled)
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+    Ok(())
+}
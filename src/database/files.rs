@@ -0,0 +1,751 @@
+//! Record attachments: metadata in the tenant's `files` table, bytes in a [`FileStorage`] backend
+//!
+//! Uploads and downloads are streamed chunk by chunk; neither side holds a whole file in memory.
+//! Local bytes are stored once per tenant by content hash: identical uploads share a blob,
+//! counted in `file_blobs`, and downloads are checked against the file's SHA-256.
+//! Large files can instead go straight to an S3-compatible bucket through a presigned URL:
+//! the file is recorded as `pending` and becomes `ready` once its bytes are confirmed.
+//! Image uploads lose their metadata on the way in, and resized variants are generated on
+//! first request and cached next to the original (see [`images`](crate::database::images)).
+//! When a scanner is configured, every upload is scanned before it becomes downloadable and
+//! infected files are quarantined (see [`scanner`](crate::database::scanner)).
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::io::StreamReader;
+use uuid::Uuid;
+
+use crate::database::events::active_tenant_databases;
+use crate::database::audit::{self, AuditActor};
+use crate::database::images;
+use crate::database::manager::{DatabaseError, DatabaseManager};
+use crate::database::s3::S3Bucket;
+use crate::database::scanner::{self, ScanVerdict};
+
+const FILE_COLUMNS: &str = "id, schema_name, record_id, filename, content_type, size, checksum, storage_key, \
+     location, status, scan_result, variants, created_by, created_at, updated_at";
+
+#[derive(Debug, Error)]
+pub enum FileError {
+    #[error("File exceeds the {0} byte upload limit")]
+    TooLarge(u64),
+
+    #[error("File is empty")]
+    Empty,
+
+    #[error("Upload interrupted: {0}")]
+    Interrupted(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Presigned uploads are not configured")]
+    PresignDisabled,
+
+    #[error("File has not been uploaded yet")]
+    NotUploaded,
+
+    #[error("Object storage error: {0}")]
+    Remote(String),
+
+    #[error("Cannot resize image: {0}")]
+    Image(String),
+
+    #[error("File is infected with {signature} and has been quarantined")]
+    Infected { file: Box<FileRecord>, signature: String },
+
+    #[error("File could not be scanned: {0}")]
+    ScanFailed(String),
+
+    #[error("Storage quota exceeded: {used} of {quota} bytes in use")]
+    QuotaExceeded { used: u64, quota: u64 },
+
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+}
+
+/// Where attachment bytes live; keys are relative paths such as `<database>/blobs/<checksum>`
+#[async_trait]
+pub trait FileStorage: Send + Sync {
+    /// Writer for a new object; callers delete the key if writing fails partway
+    async fn create(&self, key: &str) -> std::io::Result<Box<dyn AsyncWrite + Send + Unpin>>;
+
+    async fn open(&self, key: &str) -> std::io::Result<Box<dyn AsyncRead + Send + Unpin>>;
+
+    async fn delete(&self, key: &str) -> std::io::Result<()>;
+
+    /// Move an object to a new key, replacing any object already there
+    async fn rename(&self, from: &str, to: &str) -> std::io::Result<()>;
+}
+
+/// Files under a local directory
+pub struct LocalFileStorage {
+    root: PathBuf,
+}
+
+impl LocalFileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl FileStorage for LocalFileStorage {
+    async fn create(&self, key: &str) -> std::io::Result<Box<dyn AsyncWrite + Send + Unpin>> {
+        let path = self.root.join(key);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        Ok(Box::new(tokio::fs::File::create(path).await?))
+    }
+
+    async fn open(&self, key: &str) -> std::io::Result<Box<dyn AsyncRead + Send + Unpin>> {
+        Ok(Box::new(tokio::fs::File::open(self.root.join(key)).await?))
+    }
+
+    async fn delete(&self, key: &str) -> std::io::Result<()> {
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> std::io::Result<()> {
+        let path = self.root.join(to);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::rename(self.root.join(from), path).await
+    }
+}
+
+static STORAGE: Lazy<Box<dyn FileStorage>> = Lazy::new(|| {
+    let config = &crate::config::config().api;
+    match config.file_storage_backend.as_str() {
+        "local" => {}
+        other => tracing::warn!("Unknown file storage backend '{}', using local", other),
+    }
+    Box::new(LocalFileStorage::new(&config.file_storage_directory))
+});
+
+/// The configured storage backend
+pub fn storage() -> &'static dyn FileStorage {
+    STORAGE.as_ref()
+}
+
+/// An attachment's metadata, stored in the tenant's files table
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct FileRecord {
+    pub id: Uuid,
+    pub schema_name: String,
+    pub record_id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub size: i64,
+    /// Hex SHA-256 of the bytes
+    pub checksum: String,
+    #[serde(skip)]
+    pub storage_key: String,
+    /// local (the configured `FileStorage`) or s3 (presigned uploads)
+    pub location: String,
+    /// ready, pending until a presigned upload is confirmed, or quarantined when a scan
+    /// found malware
+    pub status: String,
+    /// clean or the matched signature; None when uploads are not scanned
+    pub scan_result: Option<String>,
+    /// Storage keys of resized variants generated so far, removed along with the file
+    #[serde(skip)]
+    pub variants: Vec<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Metadata for an upload, known before the bytes arrive
+#[derive(Debug, Clone)]
+pub struct NewFile {
+    pub schema_name: String,
+    pub record_id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub created_by: Option<Uuid>,
+}
+
+/// Stream `body` into storage and record its metadata
+///
+/// The bytes are written first and removed again if the upload fails, exceeds
+/// `max_size` or the tenant's quota, cannot be scanned or cannot be recorded, so a
+/// metadata row always has its bytes. An infected file is recorded as quarantined and returned as `FileError::Infected`.
+pub async fn upload<S, B, E>(
+    pool: &PgPool,
+    database: &str,
+    file: NewFile,
+    body: S,
+    max_size: u64,
+) -> Result<FileRecord, FileError>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    // A tenant already at its quota is refused before the body is read
+    check_quota(pool, 0).await?;
+    let id = Uuid::new_v4();
+    let storage_key = format!("{}/{}", database, id);
+
+    let written = match write_stream(&storage_key, body, max_size).await {
+        Ok((0, _)) => Err(FileError::Empty),
+        Ok(written) if file.content_type.starts_with("image/") && crate::config::config().api.file_strip_image_metadata => {
+            strip_stored(&storage_key).await.map(|stripped| stripped.unwrap_or(written))
+        }
+        other => other,
+    };
+    let scanned = match written {
+        Ok(written) => scan_stored(&storage_key).await.map(|verdict| (written, verdict)),
+        Err(e) => Err(e),
+    };
+    let ((size, checksum), verdict) = match scanned {
+        Ok(scanned) => scanned,
+        Err(e) => {
+            storage().delete(&storage_key).await.ok();
+            return Err(e);
+        }
+    };
+    let (status, scan_result) = scan_status(&verdict);
+
+    // Identical bytes share one blob; the file is written under its own key until it is known
+    // whether the blob already exists
+    let blob_key = format!("{}/blobs/{}", database, checksum);
+    let mut created_blob = false;
+    let recorded = async {
+        let mut tx = pool.begin().await.map_err(DatabaseError::from)?;
+        // Bytes that duplicate an existing blob take no extra space
+        let shared: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM file_blobs WHERE storage_key = $1)")
+            .bind(&blob_key)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?;
+        if !shared {
+            check_quota(pool, size).await?;
+        }
+        let refs: i32 = sqlx::query_scalar(
+            "INSERT INTO file_blobs (storage_key, checksum, size, refs) VALUES ($1, $2, $3, 1) \
+             ON CONFLICT (storage_key) DO UPDATE SET refs = file_blobs.refs + 1 RETURNING refs"
+        )
+            .bind(&blob_key)
+            .bind(&checksum)
+            .bind(size as i64)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?;
+
+        // The blob row stays locked until commit, so a concurrent delete cannot remove the bytes meanwhile
+        if refs == 1 {
+            storage().rename(&storage_key, &blob_key).await?;
+            created_blob = true;
+        } else {
+            storage().delete(&storage_key).await?;
+        }
+
+        let record = sqlx::query_as::<_, FileRecord>(&format!(
+            "INSERT INTO files (id, schema_name, record_id, filename, content_type, size, checksum, storage_key, \
+             created_by, status, scan_result) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING {}",
+            FILE_COLUMNS
+        ))
+            .bind(id)
+            .bind(&file.schema_name)
+            .bind(file.record_id)
+            .bind(&file.filename)
+            .bind(&file.content_type)
+            .bind(size as i64)
+            .bind(&checksum)
+            .bind(&blob_key)
+            .bind(file.created_by)
+            .bind(status)
+            .bind(scan_result)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(DatabaseError::from)?;
+        tx.commit().await.map_err(DatabaseError::from)?;
+        Ok::<_, FileError>(record)
+    }
+    .await;
+
+    match recorded {
+        Ok(record) => quarantine_check(record, verdict),
+        Err(e) => {
+            storage().delete(&storage_key).await.ok();
+            if created_blob {
+                storage().delete(&blob_key).await.ok();
+            }
+            Err(e)
+        }
+    }
+}
+
+async fn write_stream<S, B, E>(key: &str, mut body: S, max_size: u64) -> Result<(u64, String), FileError>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let mut writer = storage().create(key).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| FileError::Interrupted(e.to_string()))?;
+        let bytes = chunk.as_ref();
+        size += bytes.len() as u64;
+        if size > max_size {
+            return Err(FileError::TooLarge(max_size));
+        }
+        hasher.update(bytes);
+        writer.write_all(bytes).await?;
+    }
+    writer.shutdown().await?;
+
+    Ok((size, hex::encode(hasher.finalize())))
+}
+
+/// Run the configured scanner over `reader`; None when uploads are not scanned
+async fn scan(mut reader: Box<dyn AsyncRead + Send + Unpin>) -> Result<Option<ScanVerdict>, FileError> {
+    let Some(scanner) = scanner::scanner() else {
+        return Ok(None);
+    };
+    scanner.scan(&mut reader).await.map(Some).map_err(FileError::ScanFailed)
+}
+
+async fn scan_stored(key: &str) -> Result<Option<ScanVerdict>, FileError> {
+    if scanner::scanner().is_none() {
+        return Ok(None);
+    }
+    scan(storage().open(key).await?).await
+}
+
+/// Status and scan_result columns for a verdict
+fn scan_status(verdict: &Option<ScanVerdict>) -> (&'static str, Option<String>) {
+    match verdict {
+        None => ("ready", None),
+        Some(ScanVerdict::Clean) => ("ready", Some("clean".to_string())),
+        Some(ScanVerdict::Infected(signature)) => ("quarantined", Some(signature.clone())),
+    }
+}
+
+/// The recorded file, or `FileError::Infected` when it was quarantined
+fn quarantine_check(file: FileRecord, verdict: Option<ScanVerdict>) -> Result<FileRecord, FileError> {
+    match verdict {
+        Some(ScanVerdict::Infected(signature)) => Err(FileError::Infected { file: Box::new(file), signature }),
+        _ => Ok(file),
+    }
+}
+
+/// Log a quarantined upload and add it to the tenant's audit log as a `quarantine` event
+pub async fn report_infection(pool: &PgPool, file: &FileRecord, signature: &str, actor: Option<&AuditActor>) {
+    tracing::warn!(
+        "Quarantined file {} on {}/{}: {} (uploaded by {:?})",
+        file.id, file.schema_name, file.record_id, signature, actor.map(|a| &a.user)
+    );
+    let details = serde_json::json!({
+        "file_id": file.id,
+        "filename": file.filename,
+        "signature": signature,
+    });
+    if let Err(e) = audit::record_event(pool, actor, &file.schema_name, Some(file.record_id), "quarantine", details).await {
+        tracing::error!("Recording quarantine of file {} failed: {}", file.id, e);
+    }
+}
+
+/// Rewrite a stored image without its metadata; None when there was nothing to strip
+async fn strip_stored(key: &str) -> Result<Option<(u64, String)>, FileError> {
+    let mut bytes = Vec::new();
+    storage().open(key).await?.read_to_end(&mut bytes).await?;
+    let Some(stripped) = images::strip_metadata(&bytes) else {
+        return Ok(None);
+    };
+
+    let mut writer = storage().create(key).await?;
+    writer.write_all(&stripped).await?;
+    writer.shutdown().await?;
+    Ok(Some((stripped.len() as u64, hex::encode(Sha256::digest(&stripped)))))
+}
+
+/// Attachments of one record, oldest first
+pub async fn list_files(pool: &PgPool, schema_name: &str, record_id: Uuid) -> Result<Vec<FileRecord>, DatabaseError> {
+    Ok(sqlx::query_as::<_, FileRecord>(&format!(
+        "SELECT {} FROM files WHERE schema_name = $1 AND record_id = $2 AND status <> 'pending' \
+         AND deleted_at IS NULL ORDER BY created_at",
+        FILE_COLUMNS
+    ))
+        .bind(schema_name)
+        .bind(record_id)
+        .fetch_all(pool)
+        .await?)
+}
+
+pub async fn get_file(pool: &PgPool, schema_name: &str, record_id: Uuid, id: Uuid) -> Result<FileRecord, DatabaseError> {
+    sqlx::query_as::<_, FileRecord>(&format!(
+        "SELECT {} FROM files WHERE id = $1 AND schema_name = $2 AND record_id = $3 AND status <> 'pending' \
+         AND deleted_at IS NULL",
+        FILE_COLUMNS
+    ))
+        .bind(id)
+        .bind(schema_name)
+        .bind(record_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DatabaseError::NotFound(format!("File '{}' not found", id)))
+}
+
+/// Attachment storage of one tenant
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsage {
+    /// Stored bytes, counting each blob once however many files share it
+    pub used_bytes: u64,
+    /// Files not deleted, quarantined ones included
+    pub files: u64,
+    /// Configured limit on `used_bytes`, None when unlimited
+    pub quota_bytes: Option<u64>,
+}
+
+/// Current attachment storage of the tenant behind `pool`; pending presigned uploads are not counted
+pub async fn storage_usage(pool: &PgPool) -> Result<StorageUsage, DatabaseError> {
+    let (used, files): (i64, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(size) FILTER (WHERE first), 0)::bigint, COUNT(*) FROM ( \
+             SELECT size, row_number() OVER (PARTITION BY storage_key) = 1 AS first FROM files \
+             WHERE deleted_at IS NULL AND status <> 'pending' \
+         ) stored"
+    )
+        .fetch_one(pool)
+        .await?;
+    let quota = crate::config::config().api.file_quota_bytes;
+    Ok(StorageUsage { used_bytes: used as u64, files: files as u64, quota_bytes: (quota > 0).then_some(quota) })
+}
+
+/// Fail with `QuotaExceeded` when the tenant is at its quota, or `additional` more bytes
+/// would take it past the quota
+async fn check_quota(pool: &PgPool, additional: u64) -> Result<(), FileError> {
+    let quota = crate::config::config().api.file_quota_bytes;
+    if quota == 0 {
+        return Ok(());
+    }
+    let used = storage_usage(pool).await?.used_bytes;
+    if used >= quota || used + additional > quota {
+        return Err(FileError::QuotaExceeded { used, quota });
+    }
+    Ok(())
+}
+
+/// Mark the metadata deleted, then remove the bytes unless other files share them
+pub async fn delete_file(pool: &PgPool, file: &FileRecord) -> Result<(), FileError> {
+    sqlx::query("UPDATE files SET deleted_at = now(), updated_at = now() WHERE id = $1")
+        .bind(file.id)
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::from)?;
+    match file.location.as_str() {
+        "s3" => {
+            S3Bucket::configured()
+                .ok_or(FileError::PresignDisabled)?
+                .delete(&file.storage_key)
+                .await
+                .map_err(FileError::Remote)?;
+            for key in &file.variants {
+                storage().delete(key).await?;
+            }
+            Ok(())
+        }
+        _ => release_blob(pool, &file.storage_key).await,
+    }
+}
+
+/// Drop one reference to a local blob, removing its bytes and variants with the last one
+///
+/// Files stored before blobs were shared have no `file_blobs` row and are removed at once.
+async fn release_blob(pool: &PgPool, storage_key: &str) -> Result<(), FileError> {
+    let mut tx = pool.begin().await.map_err(DatabaseError::from)?;
+    let refs: Option<i32> = sqlx::query_scalar("UPDATE file_blobs SET refs = refs - 1 WHERE storage_key = $1 RETURNING refs")
+        .bind(storage_key)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+    if refs.is_some_and(|refs| refs > 0) {
+        tx.commit().await.map_err(DatabaseError::from)?;
+        return Ok(());
+    }
+
+    sqlx::query("DELETE FROM file_blobs WHERE storage_key = $1")
+        .bind(storage_key)
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+    // Variants are keyed by the blob, so any file that shared it may have generated some
+    let variants: Vec<String> = sqlx::query_scalar("SELECT DISTINCT unnest(variants) FROM files WHERE storage_key = $1")
+        .bind(storage_key)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(DatabaseError::from)?;
+    storage().delete(storage_key).await?;
+    for key in &variants {
+        storage().delete(key).await?;
+    }
+    tx.commit().await.map_err(DatabaseError::from)?;
+    Ok(())
+}
+
+/// `stream` passed through unchanged, ending in an `InvalidData` error instead of a clean end
+/// when its bytes do not hash to `checksum`
+pub fn verify_stream<S, B>(stream: S, checksum: String) -> impl Stream<Item = std::io::Result<B>>
+where
+    S: Stream<Item = std::io::Result<B>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    futures::stream::unfold((stream, Sha256::new(), Some(checksum)), |(mut stream, mut hasher, checksum)| async move {
+        let expected = checksum?;
+        match stream.next().await {
+            Some(Ok(chunk)) => {
+                hasher.update(chunk.as_ref());
+                Some((Ok(chunk), (stream, hasher, Some(expected))))
+            }
+            Some(Err(e)) => Some((Err(e), (stream, hasher, None))),
+            None => {
+                let actual = hex::encode(hasher.finalize_reset());
+                if actual == expected {
+                    return None;
+                }
+                tracing::error!("Stored file bytes hash to {} instead of {}", actual, expected);
+                let error = std::io::Error::new(std::io::ErrorKind::InvalidData, "file failed its integrity check");
+                Some((Err(error), (stream, hasher, None)))
+            }
+        }
+    })
+}
+
+/// The image in `file` scaled to fit `width` x `height`, generated on first request
+///
+/// Variants are kept in the configured `FileStorage` whatever the original's location and
+/// listed on the file so `delete_file` removes them too.
+pub async fn variant(pool: &PgPool, file: &FileRecord, width: Option<u32>, height: Option<u32>) -> Result<Vec<u8>, FileError> {
+    let key = images::variant_key(&file.storage_key, width, height);
+    if file.variants.contains(&key) {
+        let mut bytes = Vec::new();
+        match storage().open(&key).await {
+            Ok(mut reader) => {
+                reader.read_to_end(&mut bytes).await?;
+                return Ok(bytes);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let original = read_all(file).await?;
+    let content_type = file.content_type.clone();
+    let bytes = tokio::task::spawn_blocking(move || images::resize(&original, &content_type, width, height))
+        .await
+        .map_err(|e| FileError::Image(e.to_string()))?
+        .map_err(|e| FileError::Image(e.to_string()))?;
+
+    let mut writer = storage().create(&key).await?;
+    writer.write_all(&bytes).await?;
+    writer.shutdown().await?;
+    sqlx::query("UPDATE files SET variants = array_append(variants, $2) WHERE id = $1 AND NOT ($2 = ANY(variants))")
+        .bind(file.id)
+        .bind(&key)
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::from)?;
+    tracing::debug!("Generated variant {} ({} bytes)", key, bytes.len());
+    Ok(bytes)
+}
+
+/// Whole contents of a stored file
+async fn read_all(file: &FileRecord) -> Result<Vec<u8>, FileError> {
+    if file.location == "s3" {
+        let bucket = S3Bucket::configured().ok_or(FileError::PresignDisabled)?;
+        let response = bucket.get(&file.storage_key).await.map_err(FileError::Remote)?;
+        let response = response.error_for_status().map_err(|e| FileError::Remote(e.to_string()))?;
+        return Ok(response.bytes().await.map_err(|e| FileError::Remote(e.to_string()))?.to_vec());
+    }
+    let mut bytes = Vec::new();
+    storage().open(&file.storage_key).await?.read_to_end(&mut bytes).await?;
+    Ok(bytes)
+}
+
+/// Record a presigned upload; the file stays hidden until `finalize` confirms its bytes
+pub async fn create_pending(pool: &PgPool, database: &str, file: NewFile) -> Result<FileRecord, FileError> {
+    if S3Bucket::configured().is_none() {
+        return Err(FileError::PresignDisabled);
+    }
+    check_quota(pool, 0).await?;
+    let id = Uuid::new_v4();
+    let record = sqlx::query_as::<_, FileRecord>(&format!(
+        "INSERT INTO files (id, schema_name, record_id, filename, content_type, size, checksum, storage_key, \
+         location, status, created_by) \
+         VALUES ($1, $2, $3, $4, $5, 0, '', $6, 's3', 'pending', $7) RETURNING {}",
+        FILE_COLUMNS
+    ))
+        .bind(id)
+        .bind(&file.schema_name)
+        .bind(file.record_id)
+        .bind(&file.filename)
+        .bind(&file.content_type)
+        .bind(format!("{}/{}", database, id))
+        .bind(file.created_by)
+        .fetch_one(pool)
+        .await
+        .map_err(DatabaseError::from)?;
+    Ok(record)
+}
+
+/// A presigned upload that has not been confirmed yet
+pub async fn get_pending(pool: &PgPool, id: Uuid) -> Result<FileRecord, DatabaseError> {
+    sqlx::query_as::<_, FileRecord>(&format!(
+        "SELECT {} FROM files WHERE id = $1 AND status = 'pending' AND deleted_at IS NULL",
+        FILE_COLUMNS
+    ))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DatabaseError::NotFound(format!("Pending upload '{}' not found", id)))
+}
+
+/// Read a presigned upload back from the bucket and mark the file ready with its size and checksum
+///
+/// An upload that does not fit the tenant's quota is deleted along with its object.
+pub async fn finalize(pool: &PgPool, file: &FileRecord) -> Result<FileRecord, FileError> {
+    let bucket = S3Bucket::configured().ok_or(FileError::PresignDisabled)?;
+    let response = bucket.get(&file.storage_key).await.map_err(FileError::Remote)?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(FileError::NotUploaded);
+    }
+    let response = response.error_for_status().map_err(|e| FileError::Remote(e.to_string()))?;
+
+    let mut body = response.bytes_stream();
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| FileError::Interrupted(e.to_string()))?;
+        size += chunk.len() as u64;
+        hasher.update(&chunk);
+    }
+    if size == 0 {
+        return Err(FileError::Empty);
+    }
+    if let Err(e) = check_quota(pool, size).await {
+        if matches!(e, FileError::QuotaExceeded { .. }) {
+            delete_file(pool, file).await?;
+        }
+        return Err(e);
+    }
+
+    // The scanner reads the object a second time, so nothing is buffered here
+    let verdict = match scanner::scanner() {
+        Some(_) => {
+            let body = bucket.get(&file.storage_key).await.map_err(FileError::Remote)?
+                .error_for_status().map_err(|e| FileError::Remote(e.to_string()))?
+                .bytes_stream()
+                .map(|chunk| chunk.map_err(std::io::Error::other));
+            scan(Box::new(StreamReader::new(body))).await?
+        }
+        None => None,
+    };
+    let (status, scan_result) = scan_status(&verdict);
+
+    let record = sqlx::query_as::<_, FileRecord>(&format!(
+        "UPDATE files SET size = $2, checksum = $3, status = $4, scan_result = $5, updated_at = now() \
+         WHERE id = $1 AND status = 'pending' RETURNING {}",
+        FILE_COLUMNS
+    ))
+        .bind(file.id)
+        .bind(size as i64)
+        .bind(hex::encode(hasher.finalize()))
+        .bind(status)
+        .bind(scan_result)
+        .fetch_optional(pool)
+        .await
+        .map_err(DatabaseError::from)?
+        .ok_or_else(|| DatabaseError::NotFound(format!("Pending upload '{}' not found", file.id)))?;
+    quarantine_check(record, verdict)
+}
+
+/// Finalize or discard every tenant's expired pending uploads on the configured interval; runs forever
+pub async fn run_reconciliation() {
+    let interval = crate::config::config().api.file_reconcile_interval_secs.max(1);
+
+    loop {
+        match active_tenant_databases().await {
+            Ok(databases) => {
+                for database in databases {
+                    if let Err(e) = reconcile_tenant(&database).await {
+                        tracing::warn!("Upload reconciliation of {} failed: {}", database, e);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Upload reconciliation could not list tenants: {}", e),
+        }
+
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+/// Pending uploads whose URL has expired: finalized when the bytes arrived, otherwise removed
+pub async fn reconcile_tenant(database: &str) -> Result<(), FileError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    let ttl = crate::config::config().api.file_presign_ttl_secs as i64;
+    let expired = sqlx::query_as::<_, FileRecord>(&format!(
+        "SELECT {} FROM files WHERE status = 'pending' AND deleted_at IS NULL \
+         AND created_at < now() - make_interval(secs => $1)",
+        FILE_COLUMNS
+    ))
+        .bind(ttl as f64)
+        .fetch_all(&pool)
+        .await
+        .map_err(DatabaseError::from)?;
+
+    for file in expired {
+        match finalize(&pool, &file).await {
+            Ok(file) => tracing::info!("Reconciled presigned upload {} in {} ({} bytes)", file.id, database, file.size),
+            Err(FileError::Infected { file, signature }) => report_infection(&pool, &file, &signature, None).await,
+            Err(FileError::NotUploaded | FileError::Empty) => {
+                delete_file(&pool, &file).await?;
+                tracing::info!("Discarded presigned upload {} in {} that never arrived", file.id, database);
+            }
+            Err(e @ FileError::QuotaExceeded { .. }) => {
+                tracing::info!("Discarded presigned upload {} in {}: {}", file.id, database, e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn verify_stream_fails_on_a_checksum_mismatch() {
+        let chunks = || futures::stream::iter(vec![Ok::<_, std::io::Error>(b"hello ".to_vec()), Ok(b"world".to_vec())]);
+        let checksum = hex::encode(Sha256::digest(b"hello world"));
+
+        let verified: Vec<_> = verify_stream(chunks(), checksum).collect().await;
+        assert_eq!(verified.len(), 2);
+        assert!(verified.iter().all(Result::is_ok));
+
+        let corrupted: Vec<_> = verify_stream(chunks(), "0".repeat(64)).collect().await;
+        assert_eq!(corrupted.len(), 3);
+        assert_eq!(corrupted[2].as_ref().unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
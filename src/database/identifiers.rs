@@ -0,0 +1,167 @@
+//! Naming policy for tables and columns created from schema definitions
+//!
+//! Schema and property names become quoted Postgres identifiers. Postgres silently cuts
+//! identifiers to 63 bytes, so two long names, or the constraint names derived from them,
+//! could collide; names are therefore checked up front instead of being truncated.
+//! Human-readable names belong in `title`, which is kept alongside the physical name.
+//! Names the system derives for its own objects are shortened with `derived_name` instead.
+
+use sha2::{Digest, Sha256};
+
+/// Longest identifier Postgres keeps
+pub const MAX_IDENTIFIER_BYTES: usize = 63;
+
+/// SQL keywords that cannot be used unquoted as a table or column name
+pub const RESERVED_WORDS: &[&str] = &[
+    "all", "analyse", "analyze", "and", "any", "array", "as", "asc", "asymmetric", "both",
+    "case", "cast", "check", "collate", "column", "constraint", "create", "current_date",
+    "current_role", "current_time", "current_timestamp", "current_user", "default",
+    "deferrable", "desc", "distinct", "do", "else", "end", "except", "false", "fetch", "for",
+    "foreign", "from", "grant", "group", "having", "in", "initially", "intersect", "into",
+    "lateral", "leading", "limit", "localtime", "localtimestamp", "not", "null", "offset",
+    "on", "only", "or", "order", "placing", "primary", "references", "returning", "select",
+    "session_user", "some", "symmetric", "table", "then", "to", "trailing", "true", "union",
+    "unique", "user", "using", "variadic", "when", "where", "window", "with",
+];
+
+/// Why a name cannot be used as an identifier
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IdentifierError {
+    #[error("{kind} name is empty")]
+    Empty { kind: &'static str },
+    #[error("{kind} name '{name}' is {bytes} bytes; Postgres identifiers are limited to 63")]
+    TooLong { kind: &'static str, name: String, bytes: usize },
+    #[error("{kind} name '{name}' must be lowercase letters, digits and underscores, starting with a letter or underscore; try '{suggestion}'")]
+    InvalidCharacters { kind: &'static str, name: String, suggestion: String },
+    #[error("{kind} name '{name}' is a reserved SQL keyword; try '{suggestion}'")]
+    Reserved { kind: &'static str, name: String, suggestion: String },
+    #[error("{kind} name '{name}' uses the pg_ prefix reserved for Postgres system objects")]
+    SystemPrefix { kind: &'static str, name: String },
+    #[error("derived name '{derived}' would be cut to 63 bytes and could collide; shorten the table or column name")]
+    DerivedTooLong { derived: String },
+}
+
+/// Check that `name` can be used as-is for a table or column
+///
+/// `kind` names the thing being checked in the error, e.g. "Schema" or "Column".
+pub fn validate_identifier(kind: &'static str, name: &str) -> Result<(), IdentifierError> {
+    if name.is_empty() {
+        return Err(IdentifierError::Empty { kind });
+    }
+    if name.len() > MAX_IDENTIFIER_BYTES {
+        return Err(IdentifierError::TooLong { kind, name: name.to_string(), bytes: name.len() });
+    }
+    let valid_start = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_');
+    if !valid_start || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        return Err(IdentifierError::InvalidCharacters {
+            kind,
+            name: name.to_string(),
+            suggestion: normalize_identifier(name),
+        });
+    }
+    if RESERVED_WORDS.contains(&name) {
+        return Err(IdentifierError::Reserved { kind, name: name.to_string(), suggestion: normalize_identifier(name) });
+    }
+    if name.starts_with("pg_") {
+        return Err(IdentifierError::SystemPrefix { kind, name: name.to_string() });
+    }
+    Ok(())
+}
+
+/// Check that a name derived from table and column names, such as a constraint, index
+/// or trigger name, fits without truncation
+pub fn validate_derived(derived: &str) -> Result<(), IdentifierError> {
+    if derived.len() > MAX_IDENTIFIER_BYTES {
+        return Err(IdentifierError::DerivedTooLong { derived: derived.to_string() });
+    }
+    Ok(())
+}
+
+/// `<base>_<suffix>` for an object the system names itself, such as a trigger, bounded to
+/// 63 bytes
+///
+/// Names that fit are kept as-is. Longer ones keep the start of `base` and replace the
+/// rest with 8 hex digits of its SHA-256, so two long bases sharing a prefix still get
+/// distinct names and the same base always gets the same name.
+pub fn derived_name(base: &str, suffix: &str) -> String {
+    let name = format!("{}_{}", base, suffix);
+    if name.len() <= MAX_IDENTIFIER_BYTES {
+        return name;
+    }
+    let hash = hex::encode(&Sha256::digest(base.as_bytes())[..4]);
+    let mut keep = MAX_IDENTIFIER_BYTES - hash.len() - suffix.len() - 2;
+    while !base.is_char_boundary(keep) {
+        keep -= 1;
+    }
+    format!("{}_{}_{}", &base[..keep], hash, suffix)
+}
+
+/// The closest valid identifier to a display name, e.g. "Sales Orders" -> "sales_orders"
+///
+/// Used to suggest a physical name; the display name itself can be kept as `title`.
+pub fn normalize_identifier(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            normalized.push(c.to_ascii_lowercase());
+        } else if !normalized.ends_with('_') {
+            normalized.push('_');
+        }
+    }
+    let mut normalized = normalized.trim_matches('_').to_string();
+    if normalized.is_empty() || normalized.starts_with(|c: char| c.is_ascii_digit()) {
+        normalized.insert(0, '_');
+    }
+    if RESERVED_WORDS.contains(&normalized.as_str()) || normalized.starts_with("pg_") {
+        normalized.push('_');
+        if normalized.starts_with("pg_") {
+            normalized.insert(0, '_');
+        }
+    }
+    normalized.truncate(MAX_IDENTIFIER_BYTES);
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_policy() {
+        assert!(validate_identifier("Column", "customer_id").is_ok());
+        assert!(validate_identifier("Column", "_private2").is_ok());
+        assert!(matches!(validate_identifier("Schema", ""), Err(IdentifierError::Empty { .. })));
+        assert!(matches!(validate_identifier("Schema", &"a".repeat(64)), Err(IdentifierError::TooLong { .. })));
+        assert!(matches!(validate_identifier("Schema", "pg_stats"), Err(IdentifierError::SystemPrefix { .. })));
+        assert_eq!(
+            validate_identifier("Column", "order"),
+            Err(IdentifierError::Reserved { kind: "Column", name: "order".into(), suggestion: "order_".into() })
+        );
+        assert_eq!(
+            validate_identifier("Schema", "Sales Orders").unwrap_err().to_string(),
+            "Schema name 'Sales Orders' must be lowercase letters, digits and underscores, starting with a letter or underscore; try 'sales_orders'"
+        );
+
+        assert_eq!(normalize_identifier("  Crème-Brûlée 2024! "), "cr_me_br_l_e_2024");
+        assert_eq!(normalize_identifier("2nd Place"), "_2nd_place");
+        assert_eq!(normalize_identifier("User"), "user_");
+        assert!(validate_derived(&format!("{}_notify_change", "t".repeat(50))).is_err());
+    }
+
+    #[test]
+    fn test_derived_name() {
+        assert_eq!(derived_name("orders", "warehouse_outbox"), "orders_warehouse_outbox");
+
+        let long_a = format!("{}_a", "t".repeat(60));
+        let long_b = format!("{}_b", "t".repeat(60));
+        let a = derived_name(&long_a, "warehouse_outbox");
+        assert_eq!(a.len(), MAX_IDENTIFIER_BYTES);
+        assert!(a.starts_with("tttt") && a.ends_with("_warehouse_outbox"), "{}", a);
+        assert_ne!(a, derived_name(&long_b, "warehouse_outbox"));
+        assert_eq!(a, derived_name(&long_a, "warehouse_outbox"));
+
+        let multibyte = derived_name(&"é".repeat(40), "warehouse_outbox");
+        assert!(multibyte.len() <= MAX_IDENTIFIER_BYTES);
+        assert!(validate_derived(&multibyte).is_ok());
+    }
+}
@@ -0,0 +1,224 @@
+//! Image attachments: metadata stripping on upload and resized variants on download
+//!
+//! Stripping is lossless: JPEG and PNG files are copied segment by segment, leaving out
+//! the ones that carry camera, location or editing metadata. A JPEG's Exif orientation is
+//! the one tag kept, rewritten into a minimal Exif segment, so photos still display upright.
+//! Variants are decoded, rotated upright, scaled down and re-encoded without any metadata.
+
+use std::io::Cursor;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// APP1 payload prefixes of Exif and XMP segments
+const EXIF_PREFIX: &[u8] = b"Exif\0\0";
+const XMP_PREFIX: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// PNG chunks holding Exif data, free text or modification times
+const PNG_METADATA_CHUNKS: &[&[u8; 4]] = &[b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// Quality of re-encoded JPEG variants
+const JPEG_QUALITY: u8 = 85;
+
+/// Content types variants can be generated from
+const RESIZABLE_TYPES: &[&str] = &["image/jpeg", "image/png", "image/gif", "image/webp"];
+
+pub fn is_resizable(content_type: &str) -> bool {
+    RESIZABLE_TYPES.contains(&content_type)
+}
+
+/// Content type of the variants of a `content_type` image: JPEG stays JPEG, the rest become PNG
+pub fn variant_content_type(content_type: &str) -> &'static str {
+    if content_type == "image/jpeg" { "image/jpeg" } else { "image/png" }
+}
+
+/// Storage key of the `width` x `height` variant of the file stored at `storage_key`
+pub fn variant_key(storage_key: &str, width: Option<u32>, height: Option<u32>) -> String {
+    format!("{}@{}x{}", storage_key, width.unwrap_or(0), height.unwrap_or(0))
+}
+
+/// `bytes` without metadata, or None when the format is not JPEG or PNG, nothing was
+/// removed, or the file does not parse
+pub fn strip_metadata(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.starts_with(&JPEG_SOI) {
+        strip_jpeg(bytes)
+    } else if bytes.starts_with(&PNG_SIGNATURE) {
+        strip_png(bytes)
+    } else {
+        None
+    }
+}
+
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&JPEG_SOI);
+    let mut pos = 2;
+    let mut stripped = false;
+
+    loop {
+        if bytes.get(pos)? != &0xFF {
+            return None;
+        }
+        let marker = *bytes.get(pos + 1)?;
+        match marker {
+            // Fill byte before a marker
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            // Start of scan: the entropy-coded data and everything after it is kept as is
+            0xDA | 0xD9 => {
+                out.extend_from_slice(&bytes[pos..]);
+                break;
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&bytes[pos..pos + 2]);
+                pos += 2;
+                continue;
+            }
+            _ => {}
+        }
+
+        let length = u16::from_be_bytes([*bytes.get(pos + 2)?, *bytes.get(pos + 3)?]) as usize;
+        if length < 2 {
+            return None;
+        }
+        let end = pos + 2 + length;
+        let segment = bytes.get(pos..end)?;
+        let payload = &segment[4..];
+        match marker {
+            0xE1 if payload.starts_with(EXIF_PREFIX) => {
+                stripped = true;
+                let orientation = Orientation::from_exif_chunk(&payload[EXIF_PREFIX.len()..]);
+                if let Some(orientation) = orientation.filter(|o| *o != Orientation::NoTransforms) {
+                    out.extend_from_slice(&orientation_segment(orientation));
+                }
+            }
+            // XMP, Photoshop/IPTC and comments
+            0xE1 if payload.starts_with(XMP_PREFIX) => stripped = true,
+            0xED | 0xFE => stripped = true,
+            _ => out.extend_from_slice(segment),
+        }
+        pos = end;
+    }
+
+    stripped.then_some(out)
+}
+
+/// APP1 segment whose Exif data is only the orientation tag
+fn orientation_segment(orientation: Orientation) -> Vec<u8> {
+    let mut exif = EXIF_PREFIX.to_vec();
+    // Big-endian TIFF header with IFD0 right after it
+    exif.extend_from_slice(&[b'M', b'M', 0, 42, 0, 0, 0, 8]);
+    // One entry: tag 0x0112 (Orientation), type SHORT, count 1; then no next IFD
+    exif.extend_from_slice(&[0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, orientation.to_exif(), 0, 0]);
+    exif.extend_from_slice(&[0, 0, 0, 0]);
+
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+    segment.extend_from_slice(&exif);
+    segment
+}
+
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&PNG_SIGNATURE);
+    let mut pos = PNG_SIGNATURE.len();
+    let mut stripped = false;
+
+    while pos < bytes.len() {
+        let length = u32::from_be_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        // Length, type, data and CRC
+        let end = pos.checked_add(12 + length)?;
+        let chunk = bytes.get(pos..end)?;
+        if PNG_METADATA_CHUNKS.iter().any(|kind| &chunk[4..8] == *kind) {
+            stripped = true;
+        } else {
+            out.extend_from_slice(chunk);
+        }
+        pos = end;
+    }
+
+    stripped.then_some(out)
+}
+
+/// Scale `bytes` down to fit within `width` x `height`, keeping the aspect ratio
+///
+/// A missing dimension is unconstrained; images are never enlarged. Returns the encoded
+/// variant in the format `variant_content_type` reports for the source.
+pub fn resize(bytes: &[u8], content_type: &str, width: Option<u32>, height: Option<u32>) -> image::ImageResult<Vec<u8>> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    let (max_width, max_height) = (width.unwrap_or(u32::MAX), height.unwrap_or(u32::MAX));
+    if image.width() > max_width || image.height() > max_height {
+        image = image.resize(max_width, max_height, FilterType::Lanczos3);
+    }
+
+    let mut out = Cursor::new(Vec::new());
+    if variant_content_type(content_type) == "image/jpeg" {
+        JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY).encode_image(&image.to_rgb8())?;
+    } else {
+        image.write_to(&mut out, ImageFormat::Png)?;
+    }
+    Ok(out.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    #[test]
+    fn strips_jpeg_metadata_but_keeps_orientation() {
+        let mut exif = EXIF_PREFIX.to_vec();
+        exif.extend_from_slice(&[b'I', b'I', 42, 0, 8, 0, 0, 0, 2, 0]);
+        // Orientation 6 (rotate 90) and a GPS IFD pointer
+        exif.extend_from_slice(&[0x12, 0x01, 3, 0, 1, 0, 0, 0, 6, 0, 0, 0]);
+        exif.extend_from_slice(&[0x25, 0x88, 4, 0, 1, 0, 0, 0, 0x26, 0, 0, 0]);
+        exif.extend_from_slice(&[0, 0, 0, 0]);
+
+        let jfif = segment(0xE0, b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        let mut jpeg = JPEG_SOI.to_vec();
+        jpeg.extend(&jfif);
+        jpeg.extend(segment(0xE1, &exif));
+        jpeg.extend(segment(0xFE, b"taken at home"));
+        jpeg.extend([0xFF, 0xDA, 0, 2, 1, 2, 3, 0xFF, 0xD9]);
+
+        let stripped = strip_metadata(&jpeg).unwrap();
+        let mut expected = JPEG_SOI.to_vec();
+        expected.extend(&jfif);
+        expected.extend(orientation_segment(Orientation::Rotate90));
+        expected.extend([0xFF, 0xDA, 0, 2, 1, 2, 3, 0xFF, 0xD9]);
+        assert_eq!(stripped, expected);
+        assert_eq!(Orientation::from_exif_chunk(&stripped[2 + jfif.len() + 4 + EXIF_PREFIX.len()..]), Some(Orientation::Rotate90));
+        assert_eq!(strip_metadata(&stripped), Some(stripped.clone()));
+        assert!(strip_metadata(b"GIF89a").is_none());
+    }
+
+    #[test]
+    fn resizes_within_bounds_without_enlarging() {
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::new_rgb8(400, 200).write_to(&mut png, ImageFormat::Png).unwrap();
+        let png = png.into_inner();
+
+        let variant = image::load_from_memory(&resize(&png, "image/png", Some(100), None).unwrap()).unwrap();
+        assert_eq!((variant.width(), variant.height()), (100, 50));
+        let variant = image::load_from_memory(&resize(&png, "image/png", Some(1000), Some(1000)).unwrap()).unwrap();
+        assert_eq!((variant.width(), variant.height()), (400, 200));
+    }
+}
@@ -0,0 +1,219 @@
+//! Remote SFTP/FTP directories that CSV files are dropped into
+//!
+//! Transfers go through the `curl` binary, which speaks SFTP (via libssh2), FTP and FTPS.
+//! Its options, including the credentials, are written to curl's stdin as a config file
+//! so they never appear in the process list. Archiving moves a file into a subdirectory
+//! with curl's quote commands: `mkdir`/`rename` over SFTP, `MKD`/`RNFR`/`RNTO` over FTP.
+
+use std::process::Stdio;
+
+use percent_encoding::percent_decode_str;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use url::Url;
+
+pub const INBOX_SCHEMES: &[&str] = &["sftp", "ftp", "ftps"];
+
+/// Seconds allowed to connect, and for a whole listing, download or archive
+const CONNECT_TIMEOUT_SECS: u64 = 30;
+const TRANSFER_TIMEOUT_SECS: u64 = 600;
+
+/// One remote directory and the login used to reach it
+#[derive(Debug, Clone)]
+pub struct Inbox {
+    /// Directory URL, always ending in `/`
+    url: Url,
+    username: String,
+    password: String,
+    /// Where processed files are moved; relative paths are inside the inbox directory
+    archive_path: String,
+}
+
+impl Inbox {
+    pub fn new(url: &str, username: &str, password: &str, archive_path: &str) -> Result<Self, String> {
+        let mut url = Url::parse(url).map_err(|e| format!("Invalid inbox URL '{}': {}", url, e))?;
+        if !INBOX_SCHEMES.contains(&url.scheme()) {
+            return Err(format!("Inbox URL must use one of {}", INBOX_SCHEMES.join(", ")));
+        }
+        if url.host_str().is_none() {
+            return Err("Inbox URL has no host".to_string());
+        }
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        url.set_query(None);
+        url.set_fragment(None);
+        Ok(Self {
+            url,
+            username: username.to_string(),
+            password: password.to_string(),
+            archive_path: archive_path.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Names of the CSV files waiting in the directory, sorted
+    pub async fn list(&self) -> Result<Vec<String>, String> {
+        let mut options = self.options(self.url.as_str());
+        options.push(("list-only", None));
+        let listing = run_curl(&options).await?;
+        Ok(csv_files(&String::from_utf8_lossy(&listing)))
+    }
+
+    pub async fn fetch(&self, name: &str) -> Result<Vec<u8>, String> {
+        let url = self.file_url(name);
+        run_curl(&self.options(url.as_str())).await
+    }
+
+    /// Move a processed file into the archive directory, creating it when missing
+    pub async fn archive(&self, name: &str) -> Result<(), String> {
+        let mut options = self.options(self.url.as_str());
+        options.push(("list-only", None));
+        for command in self.archive_commands(name) {
+            options.push(("quote", Some(command)));
+        }
+        run_curl(&options).await.map(|_| ())
+    }
+
+    fn file_url(&self, name: &str) -> Url {
+        let mut url = self.url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().push(name);
+        }
+        url
+    }
+
+    /// Inbox directory as the server sees it, without the trailing slash
+    fn directory(&self) -> String {
+        let path = percent_decode_str(self.url.path()).decode_utf8_lossy();
+        let path = path.trim_end_matches('/');
+        match self.url.scheme() {
+            // FTP URL paths are relative to the login directory; quote commands run from there
+            "sftp" => path.to_string(),
+            _ => path.trim_start_matches('/').to_string(),
+        }
+    }
+
+    fn archive_directory(&self) -> String {
+        let directory = self.directory();
+        if self.archive_path.starts_with('/') || directory.is_empty() {
+            self.archive_path.clone()
+        } else {
+            format!("{}/{}", directory, self.archive_path)
+        }
+    }
+
+    /// Quote commands moving `name` into the archive; a leading `*` lets the mkdir fail
+    fn archive_commands(&self, name: &str) -> Vec<String> {
+        let directory = self.directory();
+        let source = if directory.is_empty() { name.to_string() } else { format!("{}/{}", directory, name) };
+        let archive = self.archive_directory();
+        let target = format!("{}/{}", archive, name);
+        if self.url.scheme() == "sftp" {
+            vec![
+                format!("*mkdir {}", sftp_quote(&archive)),
+                format!("rename {} {}", sftp_quote(&source), sftp_quote(&target)),
+            ]
+        } else {
+            vec![format!("*MKD {}", archive), format!("RNFR {}", source), format!("RNTO {}", target)]
+        }
+    }
+
+    fn options(&self, url: &str) -> Vec<(&'static str, Option<String>)> {
+        vec![
+            ("url", Some(url.to_string())),
+            ("user", Some(format!("{}:{}", self.username, self.password))),
+            ("connect-timeout", Some(CONNECT_TIMEOUT_SECS.to_string())),
+            ("max-time", Some(TRANSFER_TIMEOUT_SECS.to_string())),
+            ("silent", None),
+            ("show-error", None),
+        ]
+    }
+}
+
+/// Visible `.csv` entries of a `--list-only` listing
+fn csv_files(listing: &str) -> Vec<String> {
+    let mut names: Vec<String> = listing
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|name| !name.starts_with('.') && !name.contains('/'))
+        .filter(|name| name.to_ascii_lowercase().ends_with(".csv"))
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names
+}
+
+/// SFTP quote command argument; curl splits these on spaces unless double-quoted
+fn sftp_quote(path: &str) -> String {
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// curl config file with one option per line; values are quoted and escaped
+fn config_text(options: &[(&str, Option<String>)]) -> String {
+    options
+        .iter()
+        .map(|(name, value)| match value {
+            Some(value) => format!(
+                "{} = \"{}\"\n",
+                name,
+                value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r")
+            ),
+            None => format!("{}\n", name),
+        })
+        .collect()
+}
+
+/// Run curl with `options` on stdin and return what it wrote to stdout
+async fn run_curl(options: &[(&str, Option<String>)]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new("curl")
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Could not run curl: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(config_text(options).as_bytes())
+            .await
+            .map_err(|e| format!("Could not configure curl: {}", e))?;
+    }
+    let output = child.wait_with_output().await.map_err(|e| format!("curl failed: {}", e))?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_curl_config_listings_and_archive_commands() {
+        let inbox = Inbox::new("sftp://files.example.com/home/acme/drop", "acme", "p\"a\\ss", "processed").unwrap();
+        let config = config_text(&inbox.options(inbox.url.as_str()));
+        assert!(config.starts_with("url = \"sftp://files.example.com/home/acme/drop/\"\nuser = \"acme:p\\\"a\\\\ss\"\n"));
+        assert!(config.ends_with("silent\nshow-error\n"));
+        assert_eq!(inbox.file_url("May orders.csv").as_str(), "sftp://files.example.com/home/acme/drop/May%20orders.csv");
+        assert_eq!(
+            inbox.archive_commands("May orders.csv"),
+            vec![
+                "*mkdir \"/home/acme/drop/processed\"".to_string(),
+                "rename \"/home/acme/drop/May orders.csv\" \"/home/acme/drop/processed/May orders.csv\"".to_string(),
+            ]
+        );
+
+        let ftp = Inbox::new("ftp://legacy.example.com/outbound/", "acme", "secret", "/archive").unwrap();
+        assert_eq!(
+            ftp.archive_commands("a.csv"),
+            vec!["*MKD /archive".to_string(), "RNFR outbound/a.csv".to_string(), "RNTO /archive/a.csv".to_string()]
+        );
+
+        assert_eq!(csv_files(".\r\n..\r\nb.CSV\r\n.partial.csv\r\nnotes.txt\r\na.csv\r\n"), vec!["a.csv", "b.CSV"]);
+        assert!(Inbox::new("https://example.com/drop", "u", "p", "processed").is_err());
+    }
+}
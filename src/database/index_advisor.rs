@@ -0,0 +1,330 @@
+//! Index suggestions for tenant tables
+//!
+//! Filtered columns are read from `pg_stat_statements` (when the extension is installed
+//! in the tenant database) by matching the quoted `"column" <op>` predicates the filter
+//! compiler generates. A column is suggested when it is filtered often, is not already
+//! the leading column of an index, and `pg_stats` says it is selective enough to help.
+//! Tables that are mostly sequentially scanned are reported even without statement
+//! statistics, and non-unique indexes that have never been scanned are flagged as unused.
+//! Suggestions are only applied automatically (see [`run_maintenance`]); unused indexes
+//! are never dropped.
+
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
+
+use chrono::NaiveTime;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::database::events::active_tenant_databases;
+use crate::database::identifiers;
+use crate::database::manager::{DatabaseError, DatabaseManager};
+
+/// Tables smaller than this are cheap to scan and never get suggestions
+const MIN_TABLE_ROWS: f64 = 10_000.0;
+
+/// Statements examined per tenant, most expensive first
+const STATEMENT_LIMIT: i64 = 500;
+
+/// Columns with fewer distinct values than this are too unselective for a btree index
+const MIN_DISTINCT_VALUES: f64 = 20.0;
+
+/// How often the maintenance task checks whether the window is open
+const MAINTENANCE_CHECK_SECS: u64 = 5 * 60;
+
+/// Columns maintained by the API rather than filtered by clients
+const SKIPPED_COLUMNS: &[&str] = &["id", "trashed_at", "deleted_at", "access_read", "access_edit", "access_full", "access_deny"];
+
+const OPERATORS: &[&str] = &["=", "<>", ">=", "<=", ">", "<", "LIKE", "ILIKE", "IN "];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexSuggestion {
+    pub schema_name: String,
+    pub table_name: String,
+    pub column: String,
+    /// Calls of statements filtering on the column
+    pub calls: i64,
+    /// Execution time of those statements, in milliseconds
+    pub total_exec_time_ms: f64,
+    pub estimated_rows: f64,
+    pub estimated_distinct: f64,
+    pub statement: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SequentialScanTable {
+    pub schema_name: String,
+    pub table_name: String,
+    pub seq_scan: i64,
+    pub seq_tup_read: i64,
+    pub idx_scan: i64,
+    pub estimated_rows: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnusedIndex {
+    pub table_name: String,
+    pub index_name: String,
+    pub size_bytes: i64,
+    pub statement: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdvisorReport {
+    /// Whether statement statistics were available; without them only scans are reported
+    pub pg_stat_statements: bool,
+    pub suggestions: Vec<IndexSuggestion>,
+    pub sequential_scans: Vec<SequentialScanTable>,
+    pub unused_indexes: Vec<UnusedIndex>,
+}
+
+/// Analyze one tenant database
+pub async fn analyze(pool: &PgPool) -> Result<AdvisorReport, DatabaseError> {
+    // table name -> schema name
+    let tables: BTreeMap<String, String> = sqlx::query_as::<_, (String, String)>(
+        "SELECT table_name, name FROM schemas \
+         WHERE status <> 'system' AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .collect();
+
+    let table_rows: BTreeMap<String, (i64, i64, i64, f64)> = sqlx::query_as::<_, (String, i64, i64, i64, f64)>(
+        "SELECT s.relname::text, COALESCE(s.seq_scan, 0), COALESCE(s.seq_tup_read, 0), \
+         COALESCE(s.idx_scan, 0), c.reltuples::float8 \
+         FROM pg_stat_user_tables s JOIN pg_class c ON c.oid = s.relid \
+         WHERE s.schemaname = current_schema()"
+    )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(table, seq_scan, seq_tup_read, idx_scan, rows)| (table, (seq_scan, seq_tup_read, idx_scan, rows)))
+        .collect();
+
+    let sequential_scans = tables
+        .iter()
+        .filter_map(|(table, schema)| {
+            let &(seq_scan, seq_tup_read, idx_scan, rows) = table_rows.get(table)?;
+            (rows >= MIN_TABLE_ROWS && seq_scan > idx_scan).then(|| SequentialScanTable {
+                schema_name: schema.clone(),
+                table_name: table.clone(),
+                seq_scan,
+                seq_tup_read,
+                idx_scan,
+                estimated_rows: rows,
+            })
+        })
+        .collect();
+
+    let statements = load_statements(pool).await?;
+    let pg_stat_statements = statements.is_some();
+    let mut suggestions = Vec::new();
+
+    if let Some(statements) = statements {
+        let indexed = leading_index_columns(pool).await?;
+        let distinct = column_distinct_values(pool).await?;
+
+        for (table, schema) in &tables {
+            let rows = table_rows.get(table).map_or(0.0, |t| t.3);
+            if rows < MIN_TABLE_ROWS {
+                continue;
+            }
+            let quoted_table = format!("\"{}\"", table);
+
+            // column -> (calls, total time)
+            let mut usage: BTreeMap<String, (i64, f64)> = BTreeMap::new();
+            for (query, calls, total_time) in statements.iter().filter(|(query, _, _)| query.contains(&quoted_table)) {
+                for column in filtered_columns(query) {
+                    let entry = usage.entry(column).or_default();
+                    entry.0 += calls;
+                    entry.1 += total_time;
+                }
+            }
+
+            for (column, (calls, total_exec_time_ms)) in usage {
+                if SKIPPED_COLUMNS.contains(&column.as_str()) || indexed.contains(&(table.clone(), column.clone())) {
+                    continue;
+                }
+                // Columns pg_stats has never seen are not columns of this table
+                let Some(&n_distinct) = distinct.get(&(table.clone(), column.clone())) else { continue };
+                // Negative n_distinct is a fraction of the row count
+                let estimated_distinct = if n_distinct < 0.0 { -n_distinct * rows } else { n_distinct };
+                if estimated_distinct < MIN_DISTINCT_VALUES {
+                    continue;
+                }
+                suggestions.push(IndexSuggestion {
+                    schema_name: schema.clone(),
+                    table_name: table.clone(),
+                    statement: create_index_statement(table, &column),
+                    column,
+                    calls,
+                    total_exec_time_ms,
+                    estimated_rows: rows,
+                    estimated_distinct,
+                });
+            }
+        }
+        suggestions.sort_by(|a, b| b.total_exec_time_ms.total_cmp(&a.total_exec_time_ms));
+    }
+
+    let unused_indexes = sqlx::query_as::<_, (String, String, i64)>(
+        "SELECT s.relname::text, s.indexrelname::text, pg_relation_size(s.indexrelid) \
+         FROM pg_stat_user_indexes s JOIN pg_index i ON i.indexrelid = s.indexrelid \
+         WHERE s.schemaname = current_schema() AND s.idx_scan = 0 \
+         AND NOT i.indisunique AND NOT i.indisprimary \
+         ORDER BY pg_relation_size(s.indexrelid) DESC"
+    )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .filter(|(table, _, _)| tables.contains_key(table))
+        .map(|(table_name, index_name, size_bytes)| UnusedIndex {
+            statement: format!("DROP INDEX CONCURRENTLY IF EXISTS \"{}\"", index_name),
+            table_name,
+            index_name,
+            size_bytes,
+        })
+        .collect();
+
+    Ok(AdvisorReport { pg_stat_statements, suggestions, sequential_scans, unused_indexes })
+}
+
+/// Create every suggested index; returns the statements that succeeded
+pub async fn apply(pool: &PgPool, suggestions: &[IndexSuggestion]) -> Vec<String> {
+    let mut applied = Vec::new();
+    for suggestion in suggestions {
+        match sqlx::query(&suggestion.statement).execute(pool).await {
+            Ok(_) => applied.push(suggestion.statement.clone()),
+            Err(e) => tracing::warn!("Index advisor could not run '{}': {}", suggestion.statement, e),
+        }
+    }
+    applied
+}
+
+/// Apply suggestions for every active tenant once per day inside the configured window; runs forever
+pub async fn run_maintenance() {
+    let window = &crate::config::config().database.index_advisor_window;
+    let Some((start, end)) = parse_window(window) else {
+        tracing::error!("Invalid DATABASE_INDEX_ADVISOR_WINDOW '{}': expected HH:MM-HH:MM", window);
+        return;
+    };
+    let mut last_run = None;
+
+    loop {
+        let now = crate::clock::now();
+        let time = now.time();
+        let open = if start <= end { time >= start && time < end } else { time >= start || time < end };
+
+        if open && last_run != Some(now.date_naive()) {
+            last_run = Some(now.date_naive());
+            match active_tenant_databases().await {
+                Ok(databases) => {
+                    for database in databases {
+                        if let Err(e) = apply_tenant(&database).await {
+                            tracing::warn!("Index advisor maintenance of {} failed: {}", database, e);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Index advisor could not list tenants: {}", e),
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(MAINTENANCE_CHECK_SECS)).await;
+    }
+}
+
+async fn apply_tenant(database: &str) -> Result<(), DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    let report = analyze(&pool).await?;
+    for statement in apply(&pool, &report.suggestions).await {
+        tracing::info!("Index advisor applied on {}: {}", database, statement);
+    }
+    Ok(())
+}
+
+/// `"HH:MM-HH:MM"` in UTC; the end may be earlier than the start to span midnight
+pub(crate) fn parse_window(window: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let (start, end) = window.split_once('-')?;
+    Some((
+        NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?,
+        NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?,
+    ))
+}
+
+/// `(query, calls, total_exec_time)` for this database, or `None` without pg_stat_statements
+async fn load_statements(pool: &PgPool) -> Result<Option<Vec<(String, i64, f64)>>, DatabaseError> {
+    let installed: Option<(i32,)> = sqlx::query_as("SELECT 1 FROM pg_extension WHERE extname = 'pg_stat_statements'")
+        .fetch_optional(pool)
+        .await?;
+    if installed.is_none() {
+        return Ok(None);
+    }
+
+    let statements = sqlx::query_as(
+        "SELECT query, calls, total_exec_time FROM pg_stat_statements \
+         WHERE dbid = (SELECT oid FROM pg_database WHERE datname = current_database()) \
+         ORDER BY total_exec_time DESC LIMIT $1"
+    )
+        .bind(STATEMENT_LIMIT)
+        .fetch_all(pool)
+        .await;
+
+    match statements {
+        Ok(statements) => Ok(Some(statements)),
+        // Installed but not loaded through shared_preload_libraries
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("55000") => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `(table, column)` pairs that lead an existing index
+async fn leading_index_columns(pool: &PgPool) -> Result<HashSet<(String, String)>, DatabaseError> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT t.relname::text, a.attname::text \
+         FROM pg_index i \
+         JOIN pg_class t ON t.oid = i.indrelid \
+         JOIN pg_namespace n ON n.oid = t.relnamespace \
+         JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = i.indkey[0] \
+         WHERE n.nspname = current_schema()"
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().collect())
+}
+
+async fn column_distinct_values(pool: &PgPool) -> Result<BTreeMap<(String, String), f64>, DatabaseError> {
+    let rows: Vec<(String, String, f64)> = sqlx::query_as(
+        "SELECT tablename::text, attname::text, n_distinct::float8 FROM pg_stats WHERE schemaname = current_schema()"
+    )
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.into_iter().map(|(table, column, n)| ((table, column), n)).collect())
+}
+
+/// Columns compared in a statement as `"column" <op>`, the form the filter compiler emits
+fn filtered_columns(query: &str) -> HashSet<String> {
+    let mut columns = HashSet::new();
+    let mut rest = query;
+
+    while let Some(start) = rest.find('"') {
+        let Some(len) = rest[start + 1..].find('"') else { break };
+        let column = &rest[start + 1..start + 1 + len];
+        rest = &rest[start + len + 2..];
+
+        // Skip a table qualifier: "table"."column"
+        if rest.starts_with('.') {
+            continue;
+        }
+        let next = rest.trim_start().to_ascii_uppercase();
+        if OPERATORS.iter().any(|op| next.starts_with(op)) {
+            columns.insert(column.to_string());
+        }
+    }
+    columns
+}
+
+fn create_index_statement(table: &str, column: &str) -> String {
+    let name = identifiers::derived_name(&format!("{}_{}", table, column), "advisor_idx");
+    format!("CREATE INDEX CONCURRENTLY IF NOT EXISTS \"{}\" ON \"{}\" (\"{}\")", name, table, column)
+}
@@ -0,0 +1,479 @@
+//! Ingestion watcher: CSV files dropped on a tenant's SFTP/FTP server, imported on a schedule
+//!
+//! Each source in the tenant's `ingestion_sources` table names a remote directory, the
+//! schema to import into and optionally an import template. Every poll lists the
+//! directory's `.csv` files and, one file at a time, parses and transforms the rows the
+//! way `monk data import --template` does, creates them through the observer pipeline in
+//! chunks, moves the file into the archive directory, records an `ingestion_runs` row and
+//! posts the run to the source's webhook. A file that cannot be parsed is archived too,
+//! with a failed run, so it is not retried forever; one that cannot be archived stops the
+//! source's poll and is imported again on the next one.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::canonical;
+use crate::config::WebhookEndpoint;
+use crate::database::audit::AuditActor;
+use crate::database::events::active_tenant_databases;
+use crate::database::inbox::Inbox;
+use crate::database::manager::{DatabaseError, DatabaseManager};
+use crate::database::record::Record;
+use crate::database::repository::Repository;
+use crate::import::template::{ImportTemplate, TEMPLATE_SCHEMA};
+use crate::observer::WriteMode;
+use crate::services::webhook_signing;
+
+/// Records created per pipeline call; a failing chunk is rolled back on its own
+const CHUNK_SIZE: usize = 500;
+
+const SOURCE_COLUMNS: &str = "id, name, url, username, password, schema_name, import_template, archive_path, \
+     webhook_url, webhook_secret, enabled, last_polled_at, last_error, created_by, created_at, updated_at";
+
+const RUN_COLUMNS: &str = "id, source_id, file_name, status, records, imported, failed, errors, archived, \
+     webhook_status, created_at";
+
+/// A remote directory polled for CSV files, stored in the tenant's ingestion_sources table
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct IngestionSource {
+    pub id: Uuid,
+    pub name: String,
+    /// sftp://, ftp:// or ftps:// URL of the directory
+    pub url: String,
+    pub username: String,
+    /// Never echoed back
+    #[serde(skip)]
+    pub password: String,
+    pub schema_name: String,
+    /// Name of an import_templates record applied to every file
+    pub import_template: Option<String>,
+    /// Where processed files are moved; relative paths are inside the polled directory
+    pub archive_path: String,
+    pub webhook_url: Option<String>,
+    /// HMAC key for webhook signatures; never echoed back
+    #[serde(skip)]
+    pub webhook_secret: String,
+    pub enabled: bool,
+    pub last_polled_at: Option<NaiveDateTime>,
+    /// Why the last poll stopped early, if it did
+    pub last_error: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl IngestionSource {
+    pub fn inbox(&self) -> Result<Inbox, String> {
+        Inbox::new(&self.url, &self.username, &self.password, &self.archive_path)
+    }
+}
+
+/// Request for a new ingestion source
+#[derive(Debug, Clone)]
+pub struct NewIngestionSource {
+    pub name: String,
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    pub schema_name: String,
+    pub import_template: Option<String>,
+    pub archive_path: String,
+    pub webhook_url: Option<String>,
+    pub webhook_secret: String,
+    pub created_by: Uuid,
+}
+
+/// The outcome of importing one file
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct IngestionRun {
+    pub id: Uuid,
+    pub source_id: Uuid,
+    pub file_name: String,
+    /// completed | partial | failed
+    pub status: String,
+    /// Rows read from the file, after deduplication
+    pub records: i32,
+    pub imported: i32,
+    pub failed: i32,
+    /// `{"rows": "first-last", "error": ...}` per failed chunk or row; `{"error": ...}` for the file
+    pub errors: Value,
+    /// Whether the file was moved to the archive directory
+    pub archived: bool,
+    /// HTTP status of the webhook delivery; null when there is no webhook or it failed
+    pub webhook_status: Option<i32>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Error)]
+enum IngestionError {
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    #[error("{0}")]
+    Remote(String),
+}
+
+/// Sources with a poll running in this process
+static ACTIVE_SOURCES: Lazy<Mutex<HashSet<Uuid>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+struct ActiveSource(Uuid);
+
+impl Drop for ActiveSource {
+    fn drop(&mut self) {
+        ACTIVE_SOURCES.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.0);
+    }
+}
+
+pub async fn create_source(pool: &PgPool, source: NewIngestionSource) -> Result<IngestionSource, DatabaseError> {
+    let created = sqlx::query_as::<_, IngestionSource>(&format!(
+        "INSERT INTO ingestion_sources (name, url, username, password, schema_name, import_template, archive_path, \
+         webhook_url, webhook_secret, created_by) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) RETURNING {}",
+        SOURCE_COLUMNS
+    ))
+        .bind(&source.name)
+        .bind(&source.url)
+        .bind(&source.username)
+        .bind(&source.password)
+        .bind(&source.schema_name)
+        .bind(&source.import_template)
+        .bind(&source.archive_path)
+        .bind(&source.webhook_url)
+        .bind(&source.webhook_secret)
+        .bind(source.created_by)
+        .fetch_one(pool)
+        .await?;
+    Ok(created)
+}
+
+pub async fn get_source(pool: &PgPool, id: Uuid) -> Result<IngestionSource, DatabaseError> {
+    sqlx::query_as::<_, IngestionSource>(&format!("SELECT {} FROM ingestion_sources WHERE id = $1", SOURCE_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DatabaseError::NotFound(format!("Ingestion source '{}'", id)))
+}
+
+pub async fn list_sources(pool: &PgPool) -> Result<Vec<IngestionSource>, DatabaseError> {
+    let sources = sqlx::query_as::<_, IngestionSource>(&format!(
+        "SELECT {} FROM ingestion_sources ORDER BY name",
+        SOURCE_COLUMNS
+    ))
+        .fetch_all(pool)
+        .await?;
+    Ok(sources)
+}
+
+/// Remove a source and its run history; files already imported stay
+pub async fn delete_source(pool: &PgPool, id: Uuid) -> Result<(), DatabaseError> {
+    let deleted = sqlx::query("DELETE FROM ingestion_sources WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(DatabaseError::NotFound(format!("Ingestion source '{}'", id)));
+    }
+    Ok(())
+}
+
+/// Most recent runs first
+pub async fn list_runs(pool: &PgPool, source_id: Uuid, limit: i64) -> Result<Vec<IngestionRun>, DatabaseError> {
+    let runs = sqlx::query_as::<_, IngestionRun>(&format!(
+        "SELECT {} FROM ingestion_runs WHERE source_id = $1 ORDER BY created_at DESC LIMIT $2",
+        RUN_COLUMNS
+    ))
+        .bind(source_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(runs)
+}
+
+/// Poll one source in the background; false when a poll of it is already running here
+pub fn spawn_poll(database: String, pool: PgPool, source: IngestionSource) -> bool {
+    if !ACTIVE_SOURCES.lock().unwrap_or_else(|e| e.into_inner()).insert(source.id) {
+        return false;
+    }
+
+    tokio::spawn(async move {
+        let _active = ActiveSource(source.id);
+        poll_source(&database, &pool, &source).await;
+    });
+    true
+}
+
+/// Poll every active tenant's due sources on the configured interval; runs forever
+pub async fn run_watcher() {
+    let interval = crate::config::config().api.ingestion_poll_interval_secs.max(1);
+
+    loop {
+        match active_tenant_databases().await {
+            Ok(databases) => {
+                for database in databases {
+                    if let Err(e) = poll_tenant(&database, interval).await {
+                        tracing::warn!("Ingestion poll of {} failed: {}", database, e);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Ingestion watcher could not list tenants: {}", e),
+        }
+
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+/// Claim the tenant's enabled sources not polled within `interval` secs and poll them in turn
+async fn poll_tenant(database: &str, interval: u64) -> Result<(), DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    let due = sqlx::query_as::<_, IngestionSource>(&format!(
+        "UPDATE ingestion_sources SET last_polled_at = now() \
+         WHERE enabled AND (last_polled_at IS NULL OR last_polled_at < now() - make_interval(secs => $1)) \
+         RETURNING {}",
+        SOURCE_COLUMNS
+    ))
+        .bind(interval as f64)
+        .fetch_all(&pool)
+        .await?;
+
+    for source in due {
+        if !ACTIVE_SOURCES.lock().unwrap_or_else(|e| e.into_inner()).insert(source.id) {
+            continue;
+        }
+        let _active = ActiveSource(source.id);
+        poll_source(database, &pool, &source).await;
+    }
+    Ok(())
+}
+
+/// Import every waiting file of one source, recording why the poll stopped if it did
+async fn poll_source(database: &str, pool: &PgPool, source: &IngestionSource) {
+    let outcome = import_waiting(database, pool, source).await;
+    if let Err(e) = &outcome {
+        tracing::warn!("Ingestion source '{}' in {} stopped: {}", source.name, database, e);
+    }
+    let _ = sqlx::query(
+        "UPDATE ingestion_sources SET last_polled_at = now(), last_error = $2, updated_at = now() WHERE id = $1"
+    )
+        .bind(source.id)
+        .bind(outcome.err().map(|e| e.to_string()))
+        .execute(pool)
+        .await;
+}
+
+async fn import_waiting(database: &str, pool: &PgPool, source: &IngestionSource) -> Result<(), IngestionError> {
+    let inbox = source.inbox().map_err(IngestionError::Remote)?;
+    let names = inbox.list().await.map_err(|e| IngestionError::Remote(format!("Listing failed: {}", e)))?;
+    if names.is_empty() {
+        return Ok(());
+    }
+    let template = load_template(pool, source).await?;
+
+    for name in names {
+        let bytes = inbox
+            .fetch(&name)
+            .await
+            .map_err(|e| IngestionError::Remote(format!("Download of '{}' failed: {}", name, e)))?;
+        let mut outcome = import_file(pool, source, template.as_ref(), &bytes).await;
+        let archived = inbox.archive(&name).await;
+        outcome.archived = archived.is_ok();
+
+        let run = record_run(pool, source, &name, &outcome).await?;
+        tracing::info!(
+            "Ingested '{}' from source '{}' in {}: {} of {} record(s) imported",
+            name, source.name, database, run.imported, run.records
+        );
+        notify(pool, source, run).await;
+
+        if let Err(e) = archived {
+            return Err(IngestionError::Remote(format!("Archiving '{}' failed: {}", name, e)));
+        }
+    }
+    Ok(())
+}
+
+async fn load_template(pool: &PgPool, source: &IngestionSource) -> Result<Option<ImportTemplate>, IngestionError> {
+    let Some(name) = &source.import_template else {
+        return Ok(None);
+    };
+    let row: Option<(Value,)> = sqlx::query_as(&format!(
+        "SELECT row_to_json(t)::jsonb FROM \"{}\" t WHERE name = $1 AND trashed_at IS NULL AND deleted_at IS NULL",
+        TEMPLATE_SCHEMA
+    ))
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(DatabaseError::from)?;
+    let (row,) = row.ok_or_else(|| IngestionError::Remote(format!("Import template '{}' not found", name)))?;
+    serde_json::from_value(row)
+        .map(Some)
+        .map_err(|e| IngestionError::Remote(format!("Invalid import template '{}': {}", name, e)))
+}
+
+/// Counts and errors of one file, before it is stored as a run
+#[derive(Debug, Default)]
+struct FileOutcome {
+    records: usize,
+    imported: usize,
+    failed: usize,
+    errors: Vec<Value>,
+    archived: bool,
+}
+
+impl FileOutcome {
+    fn status(&self) -> &'static str {
+        if self.failed == 0 && self.errors.is_empty() {
+            "completed"
+        } else if self.imported > 0 {
+            "partial"
+        } else {
+            "failed"
+        }
+    }
+}
+
+async fn import_file(pool: &PgPool, source: &IngestionSource, template: Option<&ImportTemplate>, bytes: &[u8]) -> FileOutcome {
+    let mut outcome = FileOutcome::default();
+    let records = match read_rows(template, bytes) {
+        Ok(records) => records,
+        Err(e) => {
+            outcome.errors.push(json!({ "error": e }));
+            return outcome;
+        }
+    };
+
+    let transform = match template.map(ImportTemplate::to_transform).transpose() {
+        Ok(transform) => transform.unwrap_or_default(),
+        Err(e) => {
+            outcome.errors.push(json!({ "error": e.to_string() }));
+            return outcome;
+        }
+    };
+    let mut rows = Vec::with_capacity(records.len());
+    for (idx, record) in records.iter().enumerate() {
+        match transform.apply(record) {
+            Ok(row) => rows.push(row),
+            Err(e) => {
+                outcome.failed += 1;
+                outcome.errors.push(json!({ "rows": format!("{}", idx + 1), "error": format!("Transform failed: {}", e) }));
+            }
+        }
+    }
+    if let Some(key) = template.and_then(|t| t.dedupe_key.as_deref()) {
+        rows = crate::import::dedupe(rows, key);
+    }
+    outcome.records = rows.len() + outcome.failed;
+
+    let repository = Repository::new(&source.schema_name, pool.clone())
+        .with_actor(Some(AuditActor {
+            user_id: source.created_by.unwrap_or_else(Uuid::nil),
+            user: format!("ingestion:{}", source.name),
+            address: None,
+        }))
+        .with_write_mode(WriteMode::Atomic);
+
+    for (chunk_idx, chunk) in rows.chunks(CHUNK_SIZE).enumerate() {
+        let first = chunk_idx * CHUNK_SIZE + 1;
+        let result = match Record::from_json_array(Value::Array(chunk.to_vec())) {
+            Ok(records) => repository.create_all(records).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match result {
+            Ok(created) => outcome.imported += created.len(),
+            Err(e) => {
+                outcome.failed += chunk.len();
+                outcome.errors.push(json!({ "rows": format!("{}-{}", first, first + chunk.len() - 1), "error": e }));
+            }
+        }
+    }
+    outcome
+}
+
+/// Rows of a CSV file as header-keyed objects, using the template's delimiter
+fn read_rows(template: Option<&ImportTemplate>, bytes: &[u8]) -> Result<Vec<Value>, String> {
+    let delimiter = match template {
+        Some(template) => template.delimiter_char().map_err(|e| e.to_string())?,
+        None => None,
+    };
+    let content = std::str::from_utf8(bytes).map_err(|e| format!("File is not UTF-8: {}", e))?;
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    crate::import::delimited::parse(content, delimiter.unwrap_or(',')).map_err(|e| e.to_string())
+}
+
+async fn record_run(pool: &PgPool, source: &IngestionSource, file_name: &str, outcome: &FileOutcome) -> Result<IngestionRun, DatabaseError> {
+    let run = sqlx::query_as::<_, IngestionRun>(&format!(
+        "INSERT INTO ingestion_runs (source_id, file_name, status, records, imported, failed, errors, archived) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING {}",
+        RUN_COLUMNS
+    ))
+        .bind(source.id)
+        .bind(file_name)
+        .bind(outcome.status())
+        .bind(outcome.records as i32)
+        .bind(outcome.imported as i32)
+        .bind(outcome.failed as i32)
+        .bind(Value::Array(outcome.errors.clone()))
+        .bind(outcome.archived)
+        .fetch_one(pool)
+        .await?;
+    Ok(run)
+}
+
+/// Post the run to the source's webhook, signed with its secret, and store the response status
+async fn notify(pool: &PgPool, source: &IngestionSource, run: IngestionRun) {
+    let Some(url) = source.webhook_url.clone().filter(|url| !url.is_empty()) else {
+        return;
+    };
+    let endpoint = WebhookEndpoint {
+        id: format!("ingestion:{}", source.name),
+        url,
+        secret: source.webhook_secret.clone(),
+    };
+    let body = canonical::to_string(&json!({
+        "event": "ingestion.run",
+        "source": { "id": source.id, "name": source.name, "schema": source.schema_name },
+        "run": run,
+    }));
+
+    match webhook_signing::deliver(crate::observer::deferred::http_client(), &endpoint, &body).await {
+        Ok(status) => {
+            let _ = sqlx::query("UPDATE ingestion_runs SET webhook_status = $2 WHERE id = $1")
+                .bind(run.id)
+                .bind(status as i32)
+                .execute(pool)
+                .await;
+        }
+        Err(e) => tracing::warn!("Ingestion webhook for source '{}' failed: {}", source.name, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_rows_with_the_template_delimiter() {
+        let template: ImportTemplate = serde_json::from_value(json!({
+            "name": "legacy",
+            "schema_name": "orders",
+            "delimiter": ";",
+            "column_mapping": { "Order No": "number" },
+        }))
+        .unwrap();
+        let rows = read_rows(Some(&template), "\u{feff}Order No;Total\r\nA-1;9.50\r\n".as_bytes()).unwrap();
+        assert_eq!(rows, vec![json!({ "Order No": "A-1", "Total": "9.50" })]);
+        assert_eq!(template.to_transform().unwrap().apply(&rows[0]).unwrap(), json!({ "number": "A-1", "Total": "9.50" }));
+        assert!(read_rows(None, b"a,b\n\xff,1\n").is_err());
+
+        let outcome = FileOutcome { records: 3, imported: 2, failed: 1, ..Default::default() };
+        assert_eq!(outcome.status(), "partial");
+        assert_eq!(FileOutcome { errors: vec![json!({ "error": "x" })], ..Default::default() }.status(), "failed");
+    }
+}
@@ -0,0 +1,200 @@
+//! Failed login tracking for account lockout
+//!
+//! Counters are kept per tenant, user and client address in the registry database, so a
+//! lockout survives restarts and applies across every server instance.
+use chrono::{Duration, NaiveDateTime};
+use sqlx::PgPool;
+use tokio::sync::OnceCell;
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::SecurityConfig;
+use crate::database::manager::{DatabaseError, DatabaseManager};
+
+static TABLE_READY: OnceCell<()> = OnceCell::const_new();
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS login_attempts (
+    tenant TEXT NOT NULL,
+    auth TEXT NOT NULL,
+    address TEXT NOT NULL,
+    failures INTEGER NOT NULL DEFAULT 0,
+    window_started_at TIMESTAMP NOT NULL,
+    locked_until TIMESTAMP,
+    PRIMARY KEY (tenant, auth, address)
+)";
+
+async fn registry_pool() -> Result<PgPool, DatabaseError> {
+    let pool = DatabaseManager::main_pool().await?;
+    TABLE_READY
+        .get_or_try_init(|| async { sqlx::query(CREATE_TABLE).execute(&pool).await.map(|_| ()) })
+        .await?;
+    Ok(pool)
+}
+
+/// Seconds left on a lockout of `auth` in `tenant` from `address`, if one is in force
+pub async fn locked_for(tenant: &str, auth: &str, address: &str) -> Result<Option<i64>, DatabaseError> {
+    if crate::config::config().security.login_max_failures == 0 {
+        return Ok(None);
+    }
+    let pool = registry_pool().await?;
+    lockout_left(&pool, tenant, auth, address, &SystemClock).await
+}
+
+async fn lockout_left(pool: &PgPool, tenant: &str, auth: &str, address: &str, clock: &dyn Clock) -> Result<Option<i64>, DatabaseError> {
+    let now = clock.now().naive_utc();
+    let locked_until: Option<(Option<NaiveDateTime>,)> = sqlx::query_as(
+        "SELECT locked_until FROM login_attempts WHERE tenant = $1 AND auth = $2 AND address = $3"
+    )
+        .bind(tenant)
+        .bind(auth)
+        .bind(address)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(locked_until
+        .and_then(|(until,)| until)
+        .filter(|until| *until > now)
+        .map(|until| (until - now).num_seconds().max(1)))
+}
+
+/// Count a failed login; returns the lockout in seconds when this failure starts one
+///
+/// Failures outside the current window start a new count.
+pub async fn record_failure(tenant: &str, auth: &str, address: &str) -> Result<Option<i64>, DatabaseError> {
+    let security = &crate::config::config().security;
+    if security.login_max_failures == 0 {
+        return Ok(None);
+    }
+    let pool = registry_pool().await?;
+    count_failure(&pool, security, tenant, auth, address, &SystemClock).await
+}
+
+async fn count_failure(
+    pool: &PgPool,
+    security: &SecurityConfig,
+    tenant: &str,
+    auth: &str,
+    address: &str,
+    clock: &dyn Clock,
+) -> Result<Option<i64>, DatabaseError> {
+    let now = clock.now().naive_utc();
+    let window_start = now - Duration::seconds(security.login_failure_window_secs as i64);
+    let lockout = security.login_lockout_secs as i64;
+
+    let (failures,): (i32,) = sqlx::query_as(
+        "INSERT INTO login_attempts (tenant, auth, address, failures, window_started_at)
+         VALUES ($1, $2, $3, 1, $4)
+         ON CONFLICT (tenant, auth, address) DO UPDATE SET
+             failures = CASE WHEN login_attempts.window_started_at < $5 THEN 1 ELSE login_attempts.failures + 1 END,
+             window_started_at = CASE WHEN login_attempts.window_started_at < $5 THEN $4 ELSE login_attempts.window_started_at END
+         RETURNING failures"
+    )
+        .bind(tenant)
+        .bind(auth)
+        .bind(address)
+        .bind(now)
+        .bind(window_start)
+        .fetch_one(pool)
+        .await?;
+    if (failures as u32) < security.login_max_failures {
+        return Ok(None);
+    }
+
+    // The count starts over once the lockout is in place
+    sqlx::query(
+        "UPDATE login_attempts SET failures = 0, window_started_at = $4, locked_until = $5
+         WHERE tenant = $1 AND auth = $2 AND address = $3"
+    )
+        .bind(tenant)
+        .bind(auth)
+        .bind(address)
+        .bind(now)
+        .bind(now + Duration::seconds(lockout))
+        .execute(pool)
+        .await?;
+    Ok(Some(lockout))
+}
+
+/// Forget failures after a successful login
+pub async fn clear(tenant: &str, auth: &str, address: &str) -> Result<(), DatabaseError> {
+    if crate::config::config().security.login_max_failures == 0 {
+        return Ok(());
+    }
+    let pool = registry_pool().await?;
+    sqlx::query("DELETE FROM login_attempts WHERE tenant = $1 AND auth = $2 AND address = $3")
+        .bind(tenant)
+        .bind(auth)
+        .bind(address)
+        .execute(&pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use chrono::Timelike;
+
+    /// The registry database, if one is reachable; the tests are skipped without one
+    async fn test_pool() -> Option<PgPool> {
+        let pool = crate::testing::database_pool().await?;
+        sqlx::query(CREATE_TABLE).execute(&pool).await.ok()?;
+        Some(pool)
+    }
+
+    fn security() -> SecurityConfig {
+        let mut security = crate::config::config().security.clone();
+        security.login_max_failures = 3;
+        security.login_failure_window_secs = 60;
+        security.login_lockout_secs = 900;
+        security
+    }
+
+    /// A clock at `secs` seconds after `start`
+    fn at(clock: &MockClock, start: chrono::DateTime<chrono::Utc>, secs: i64) -> &MockClock {
+        clock.set(start + Duration::seconds(secs));
+        clock
+    }
+
+    #[tokio::test]
+    async fn test_failures_lock_out_within_the_window() {
+        let Some(pool) = test_pool().await else { return };
+        let security = security();
+        let tenant = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let start = crate::clock::now().with_nanosecond(0).unwrap();
+        let clock = MockClock::new(start);
+
+        assert_eq!(count_failure(&pool, &security, &tenant, "ada", "10.0.0.1", at(&clock, start, 0)).await.unwrap(), None);
+        assert_eq!(count_failure(&pool, &security, &tenant, "ada", "10.0.0.1", at(&clock, start, 30)).await.unwrap(), None);
+        assert_eq!(lockout_left(&pool, &tenant, "ada", "10.0.0.1", &clock).await.unwrap(), None);
+        assert_eq!(count_failure(&pool, &security, &tenant, "ada", "10.0.0.1", at(&clock, start, 59)).await.unwrap(), Some(900));
+
+        assert_eq!(lockout_left(&pool, &tenant, "ada", "10.0.0.1", &clock).await.unwrap(), Some(900));
+        assert_eq!(lockout_left(&pool, &tenant, "ada", "10.0.0.1", at(&clock, start, 959)).await.unwrap(), None);
+        assert_eq!(lockout_left(&pool, &tenant, "ada", "10.0.0.1", at(&clock, start, 958)).await.unwrap(), Some(1));
+        // Other addresses and users are unaffected
+        assert_eq!(lockout_left(&pool, &tenant, "ada", "10.0.0.2", at(&clock, start, 60)).await.unwrap(), None);
+        assert_eq!(lockout_left(&pool, &tenant, "grace", "10.0.0.1", &clock).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_failures_outside_the_window_start_over() {
+        let Some(pool) = test_pool().await else { return };
+        let security = security();
+        let tenant = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let start = crate::clock::now().with_nanosecond(0).unwrap();
+        let clock = MockClock::new(start);
+
+        assert_eq!(count_failure(&pool, &security, &tenant, "ada", "10.0.0.1", &clock).await.unwrap(), None);
+        clock.advance(Duration::seconds(50));
+        assert_eq!(count_failure(&pool, &security, &tenant, "ada", "10.0.0.1", &clock).await.unwrap(), None);
+        // The window opened at 0 has closed, so this is the first failure of a new one
+        clock.advance(Duration::seconds(11));
+        assert_eq!(count_failure(&pool, &security, &tenant, "ada", "10.0.0.1", &clock).await.unwrap(), None);
+        clock.advance(Duration::seconds(39));
+        assert_eq!(count_failure(&pool, &security, &tenant, "ada", "10.0.0.1", &clock).await.unwrap(), None);
+        assert_eq!(lockout_left(&pool, &tenant, "ada", "10.0.0.1", &clock).await.unwrap(), None);
+        clock.advance(Duration::seconds(20));
+        assert_eq!(count_failure(&pool, &security, &tenant, "ada", "10.0.0.1", &clock).await.unwrap(), Some(900));
+    }
+}
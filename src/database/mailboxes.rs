@@ -0,0 +1,240 @@
+//! Inbound mailboxes: provider webhooks that turn received email into records
+//!
+//! A mailbox belongs to one schema and one provider. Its webhook URL is signed with the
+//! server secret like export download links, but does not expire; deleting the mailbox
+//! revokes it. Each message becomes one record whose fields are filled from the
+//! message parts named in the mailbox's field mapping, and its attachments are stored
+//! as files on that record.
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::signed_url;
+use crate::database::audit::AuditActor;
+use crate::database::files::{self, FileRecord, NewFile};
+use crate::database::manager::DatabaseError;
+use crate::database::record::Record;
+use crate::database::repository::Repository;
+use crate::services::inbound_email::InboundEmail;
+
+const MAILBOX_COLUMNS: &str = "id, name, schema_name, provider, field_mapping, signing_key, enabled, \
+     last_received_at, created_by, created_at, updated_at";
+
+/// Record field per message part when a mailbox is created without a mapping
+pub fn default_field_mapping() -> Map<String, Value> {
+    let mut mapping = Map::new();
+    for (part, field) in [("from", "from"), ("to", "to"), ("subject", "subject"), ("text", "body")] {
+        mapping.insert(part.to_string(), Value::String(field.to_string()));
+    }
+    mapping
+}
+
+/// A provider webhook target, stored in the tenant's inbound_mailboxes table
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Mailbox {
+    pub id: Uuid,
+    pub name: String,
+    pub schema_name: String,
+    /// sendgrid | mailgun | ses
+    pub provider: String,
+    /// Message part (from, to, subject, text, html, message_id) -> record field
+    pub field_mapping: Value,
+    /// Mailgun webhook signing key; never echoed back
+    #[serde(skip)]
+    pub signing_key: Option<String>,
+    pub enabled: bool,
+    pub last_received_at: Option<NaiveDateTime>,
+    pub created_by: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// Request for a new mailbox
+#[derive(Debug, Clone)]
+pub struct NewMailbox {
+    pub name: String,
+    pub schema_name: String,
+    pub provider: String,
+    pub field_mapping: Map<String, Value>,
+    pub signing_key: Option<String>,
+    pub created_by: Uuid,
+}
+
+/// The record a message became, and what happened to its attachments
+#[derive(Debug, Clone, Serialize)]
+pub struct Delivery {
+    pub record: Value,
+    pub files: Vec<FileRecord>,
+    /// `{"filename": ..., "error": ...}` per attachment that could not be stored
+    pub rejected: Vec<Value>,
+}
+
+pub async fn create_mailbox(pool: &PgPool, mailbox: NewMailbox) -> Result<Mailbox, DatabaseError> {
+    let created = sqlx::query_as::<_, Mailbox>(&format!(
+        "INSERT INTO inbound_mailboxes (name, schema_name, provider, field_mapping, signing_key, created_by) \
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        MAILBOX_COLUMNS
+    ))
+        .bind(&mailbox.name)
+        .bind(&mailbox.schema_name)
+        .bind(&mailbox.provider)
+        .bind(Value::Object(mailbox.field_mapping))
+        .bind(&mailbox.signing_key)
+        .bind(mailbox.created_by)
+        .fetch_one(pool)
+        .await?;
+    Ok(created)
+}
+
+pub async fn get_mailbox(pool: &PgPool, id: Uuid) -> Result<Mailbox, DatabaseError> {
+    sqlx::query_as::<_, Mailbox>(&format!("SELECT {} FROM inbound_mailboxes WHERE id = $1", MAILBOX_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| DatabaseError::NotFound(format!("Mailbox '{}'", id)))
+}
+
+pub async fn list_mailboxes(pool: &PgPool) -> Result<Vec<Mailbox>, DatabaseError> {
+    let mailboxes = sqlx::query_as::<_, Mailbox>(&format!(
+        "SELECT {} FROM inbound_mailboxes ORDER BY name",
+        MAILBOX_COLUMNS
+    ))
+        .fetch_all(pool)
+        .await?;
+    Ok(mailboxes)
+}
+
+/// Remove a mailbox, revoking its webhook URL; records already created stay
+pub async fn delete_mailbox(pool: &PgPool, id: Uuid) -> Result<(), DatabaseError> {
+    let deleted = sqlx::query("DELETE FROM inbound_mailboxes WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(DatabaseError::NotFound(format!("Mailbox '{}'", id)));
+    }
+    Ok(())
+}
+
+/// Signed path to configure as the provider's webhook; valid without a bearer token
+pub fn webhook_path(database: &str, id: Uuid) -> String {
+    let signature = signed_url::sign("inbound", &[database, &id.to_string()]);
+    format!("/api/inbound/email/{}/{}?signature={}", database, id, signature)
+}
+
+pub fn verify_webhook(database: &str, id: Uuid, signature: &str) -> bool {
+    signed_url::verify("inbound", &[database, &id.to_string()], signature)
+}
+
+/// Record fields for a message, per the mailbox's field mapping
+pub fn record_fields(mailbox: &Mailbox, email: &InboundEmail) -> Map<String, Value> {
+    let mut fields = Map::new();
+    if let Some(mapping) = mailbox.field_mapping.as_object() {
+        for (part, field) in mapping {
+            if let (Some(field), Some(value)) = (field.as_str(), email.part(part)) {
+                fields.insert(field.to_string(), value);
+            }
+        }
+    }
+    fields
+}
+
+/// Create the message's record, then store each attachment as a file on it
+///
+/// The record is created through the observer pipeline, so schema validation applies.
+/// Attachments that are too large, over the tenant's quota or infected are reported in
+/// `rejected` without failing the delivery.
+pub async fn deliver(pool: &PgPool, database: &str, mailbox: &Mailbox, email: InboundEmail) -> Result<Delivery, DatabaseError> {
+    let actor = AuditActor {
+        user_id: mailbox.created_by.unwrap_or_else(Uuid::nil),
+        user: format!("mailbox:{}", mailbox.name),
+        address: None,
+    };
+    let created = Repository::new(&mailbox.schema_name, pool.clone())
+        .with_actor(Some(actor))
+        .create_all(vec![Record::from(record_fields(mailbox, &email))])
+        .await?;
+    let record_id = created
+        .first()
+        .and_then(|record| record.id())
+        .ok_or_else(|| DatabaseError::QueryError("Created record has no id".to_string()))?;
+
+    let max_size = crate::config::config().api.file_max_size_bytes;
+    let mut stored = Vec::new();
+    let mut rejected = Vec::new();
+    for attachment in email.attachments {
+        let file = NewFile {
+            schema_name: mailbox.schema_name.clone(),
+            record_id,
+            filename: attachment.filename.clone(),
+            content_type: attachment.content_type,
+            created_by: mailbox.created_by,
+        };
+        let body = futures::stream::iter([Ok::<_, std::io::Error>(attachment.bytes)]);
+        match files::upload(pool, database, file, body, max_size).await {
+            Ok(file) => stored.push(file),
+            Err(e) => {
+                tracing::warn!("Attachment '{}' to mailbox '{}' rejected: {}", attachment.filename, mailbox.name, e);
+                rejected.push(json!({ "filename": attachment.filename, "error": e.to_string() }));
+            }
+        }
+    }
+
+    sqlx::query("UPDATE inbound_mailboxes SET last_received_at = now() WHERE id = $1")
+        .bind(mailbox.id)
+        .execute(pool)
+        .await?;
+
+    Ok(Delivery {
+        record: created.into_iter().next().map(|record| record.to_api_output()).unwrap_or(Value::Null),
+        files: stored,
+        rejected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_message_parts_to_record_fields() {
+        let mut mapping = default_field_mapping();
+        mapping.insert("message_id".to_string(), json!("external_id"));
+        mapping.insert("cc".to_string(), json!("cc"));
+        let now = chrono::Utc::now().naive_utc();
+        let mailbox = Mailbox {
+            id: Uuid::new_v4(),
+            name: "support".to_string(),
+            schema_name: "tickets".to_string(),
+            provider: "sendgrid".to_string(),
+            field_mapping: Value::Object(mapping),
+            signing_key: None,
+            enabled: true,
+            last_received_at: None,
+            created_by: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let email = InboundEmail {
+            from: "ada@example.com".to_string(),
+            subject: "Printer on fire".to_string(),
+            text: "Still burning".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(Value::Object(record_fields(&mailbox, &email)), json!({
+            "from": "ada@example.com",
+            "to": "",
+            "subject": "Printer on fire",
+            "body": "Still burning",
+            "external_id": null,
+        }));
+
+        let path = webhook_path("tenant_a", mailbox.id);
+        let signature = path.rsplit_once("signature=").unwrap().1;
+        assert!(verify_webhook("tenant_a", mailbox.id, signature));
+        assert!(!verify_webhook("tenant_b", mailbox.id, signature));
+    }
+}
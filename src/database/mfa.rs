@@ -0,0 +1,83 @@
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::feature_flags;
+use crate::database::manager::DatabaseError;
+
+/// Feature flag that makes a verified TOTP code a condition of sudo elevation
+pub const MFA_REQUIRED_FLAG: &str = "mfa_required";
+
+/// A user's TOTP enrollment, kept in `user_credentials` out of reach of the data API
+#[derive(Debug, Clone, Default, sqlx::FromRow)]
+pub struct MfaState {
+    /// TOTP secret sealed by `totp::seal_secret`; set from enrollment until MFA is disabled
+    pub mfa_secret: Option<String>,
+    /// When the first code was verified; codes only gate logins from then on
+    pub mfa_enabled_at: Option<NaiveDateTime>,
+}
+
+impl MfaState {
+    pub fn is_enabled(&self) -> bool {
+        self.mfa_enabled_at.is_some()
+    }
+}
+
+/// Enrollment state of `user_id`; users without credentials have none
+pub async fn load(pool: &PgPool, user_id: Uuid) -> Result<MfaState, DatabaseError> {
+    let state: Option<MfaState> = sqlx::query_as("SELECT mfa_secret, mfa_enabled_at FROM user_credentials WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+    Ok(state.unwrap_or_default())
+}
+
+/// Store a new, not yet verified sealed secret; false when MFA is already enabled
+///
+/// Enrolling again before verifying replaces the pending secret.
+pub async fn start_enrollment(pool: &PgPool, user_id: Uuid, sealed_secret: &str) -> Result<bool, DatabaseError> {
+    let result = sqlx::query(
+        "INSERT INTO user_credentials (user_id, mfa_secret) VALUES ($1, $2) \
+         ON CONFLICT (user_id) DO UPDATE SET mfa_secret = $2, mfa_last_step = NULL \
+         WHERE user_credentials.mfa_enabled_at IS NULL"
+    )
+        .bind(user_id)
+        .bind(sealed_secret)
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Record a verified code's time step, enabling MFA on first use
+///
+/// False when a code of this or a later step was already used, so each code works once.
+pub async fn consume_step(pool: &PgPool, user_id: Uuid, step: i64) -> Result<bool, DatabaseError> {
+    let result = sqlx::query(
+        "UPDATE user_credentials SET mfa_last_step = $2, mfa_enabled_at = COALESCE(mfa_enabled_at, $3) \
+         WHERE user_id = $1 AND mfa_secret IS NOT NULL AND (mfa_last_step IS NULL OR mfa_last_step < $2)"
+    )
+        .bind(user_id)
+        .bind(step)
+        .bind(crate::clock::now().naive_utc())
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Remove the user's secret, turning MFA off
+pub async fn disable(pool: &PgPool, user_id: Uuid) -> Result<(), DatabaseError> {
+    sqlx::query("UPDATE user_credentials SET mfa_secret = NULL, mfa_enabled_at = NULL, mfa_last_step = NULL WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+    Ok(())
+}
+
+/// Whether the tenant owning `pool` requires MFA for sudo elevation
+pub async fn is_required(pool: &PgPool) -> Result<bool, DatabaseError> {
+    feature_flags::is_enabled(pool, MFA_REQUIRED_FLAG).await
+}
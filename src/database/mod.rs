@@ -1,10 +1,46 @@
 pub mod manager;
+pub mod api_keys;
+pub mod audit;
 pub mod query_builder;
 pub mod record;
 pub mod repository;
 pub mod models;
 pub mod dynamic;
+pub mod documents;
 pub mod service;
+pub mod refresh_tokens;
+pub mod login_attempts;
+pub mod relationships;
+pub mod sequences;
+pub mod unique;
+pub mod checks;
+pub mod validation;
+pub mod identifiers;
+pub mod slugs;
+pub mod columns;
+pub mod comments;
+pub mod extensions;
+pub mod favorites;
+pub mod events;
+pub mod export_jobs;
+pub mod feature_flags;
+pub mod mfa;
+pub mod files;
+pub mod images;
+pub mod inbox;
+pub mod index_advisor;
+pub mod ingestion;
+pub mod mailboxes;
+pub mod notifications;
+pub mod regions;
+pub mod s3;
+pub mod saved_views;
+pub mod scanner;
+pub mod search;
+pub mod tags;
+pub mod transaction;
+pub mod warehouse;
+pub mod warmup;
 
 pub use manager::{DatabaseManager, DatabaseError};
 pub use record::{Record, RecordError, FieldChange, ChangeType, RecordDiff, RecordVecExt, RecordResultExt, RecordResultError};
@@ -17,5 +17,23 @@ pub struct User {
     pub updated_at: DateTime<Utc>,
     pub trashed_at: Option<DateTime<Utc>>,
     pub deleted_at: Option<DateTime<Utc>>,
+    /// Argon2id PHC string; None for accounts that have not set a password
+    #[serde(default, skip_serializing)]
+    #[sqlx(default)]
+    pub password_hash: Option<String>,
+    /// SHA-256 of the outstanding activation token; set until the account is activated
+    #[serde(default, skip_serializing)]
+    #[sqlx(default)]
+    pub activation_token_hash: Option<String>,
+}
+
+impl User {
+    pub fn is_activated(&self) -> bool {
+        self.activation_token_hash.is_none()
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.trashed_at.is_some() || self.deleted_at.is_some()
+    }
 }
 
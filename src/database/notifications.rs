@@ -0,0 +1,537 @@
+//! Notification preferences, subscriptions and change digests
+//!
+//! Users follow schemas through `notification_subscriptions`, each optionally narrowed by
+//! a where clause. With `api.enable_notification_digests` on, [`run_digests`] visits every
+//! tenant and emails each user whose daily or weekly digest is due a summary of the
+//! records created or updated since their last digest, as recorded in the audit log.
+//! Changes users made themselves are left out, and records are read with the user's
+//! access, so a digest never shows what its reader could not read through the data API.
+//! Every digest carries signed unsubscribe links that work without a bearer token.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::signed_url;
+use crate::database::manager::{DatabaseError, DatabaseManager};
+use crate::database::repository::Repository;
+use crate::filter::{FilterData, RecordAccess};
+use crate::services::handlebars;
+use crate::services::mailer::{self, OutgoingMail};
+
+pub const FREQUENCIES: &[&str] = &["daily", "weekly", "off"];
+
+/// How often tenants are checked for due digests
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Changed records considered per subscription and digest, newest first
+const DIGEST_CANDIDATES_LIMIT: i64 = 500;
+
+/// Changes listed per subscription; the rest are only counted
+const DIGEST_CHANGES_SHOWN: usize = 25;
+
+const PREFERENCE_COLUMNS: &str = "user_id, email, frequency, weekly_day, last_digest_at, created_at, updated_at";
+const SUBSCRIPTION_COLUMNS: &str = "id, user_id, schema_name, name, where_clause, created_at";
+
+const DIGEST_HTML: &str = r#"<h2>{{title}}</h2>
+<p>Changes from {{since}} to {{until}} (UTC)</p>
+{{#each sections}}<h3>{{name}}</h3>
+<ul>
+{{#each changes}}<li>{{label}} {{operation}}{{#if actor}} by {{actor}}{{/if}} at {{changed_at}}</li>
+{{/each}}</ul>
+{{#if more}}<p>and {{more}} more</p>
+{{/if}}<p><a href="{{unsubscribe_url}}">Stop following {{name}}</a></p>
+{{/each}}<p><a href="{{unsubscribe_url}}">Unsubscribe from all digests</a></p>
+"#;
+
+const DIGEST_TEXT: &str = r#"{{title}}
+Changes from {{since}} to {{until}} (UTC)
+{{#each sections}}
+{{name}}
+{{#each changes}}- {{label}} {{operation}}{{#if actor}} by {{actor}}{{/if}} at {{changed_at}}
+{{/each}}{{#if more}}and {{more}} more
+{{/if}}Stop following {{name}}: {{unsubscribe_url}}
+{{/each}}
+Unsubscribe from all digests: {{unsubscribe_url}}
+"#;
+
+/// A user's digest settings, stored in the tenant's notification_preferences table
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct NotificationPreferences {
+    pub user_id: Uuid,
+    pub email: String,
+    /// daily | weekly | off
+    pub frequency: String,
+    /// ISO day of the week weekly digests are sent on, 1 = Monday
+    pub weekly_day: i16,
+    /// End of the period covered by the last digest
+    pub last_digest_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// A schema followed by a user
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Subscription {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub schema_name: String,
+    pub name: Option<String>,
+    /// Where clause a changed record must match, as in POST /api/find/:schema
+    #[serde(rename = "where")]
+    pub where_clause: Value,
+    pub created_at: NaiveDateTime,
+}
+
+impl Subscription {
+    /// Heading of the subscription's digest section
+    pub fn title(&self) -> &str {
+        self.name.as_deref().filter(|name| !name.is_empty()).unwrap_or(&self.schema_name)
+    }
+}
+
+/// Request for a new subscription
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSubscription {
+    pub schema: String,
+    pub name: Option<String>,
+    #[serde(rename = "where")]
+    pub where_clause: Option<Value>,
+}
+
+pub async fn get_preferences(pool: &PgPool, user_id: Uuid) -> Result<Option<NotificationPreferences>, DatabaseError> {
+    let preferences = sqlx::query_as::<_, NotificationPreferences>(&format!(
+        "SELECT {} FROM notification_preferences WHERE user_id = $1",
+        PREFERENCE_COLUMNS
+    ))
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(preferences)
+}
+
+/// Create or replace a user's preferences; a new user's first digest covers changes from now on
+pub async fn save_preferences(
+    pool: &PgPool,
+    user_id: Uuid,
+    email: &str,
+    frequency: &str,
+    weekly_day: i16,
+) -> Result<NotificationPreferences, DatabaseError> {
+    let preferences = sqlx::query_as::<_, NotificationPreferences>(&format!(
+        "INSERT INTO notification_preferences (user_id, email, frequency, weekly_day) VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (user_id) DO UPDATE SET email = EXCLUDED.email, frequency = EXCLUDED.frequency, \
+         weekly_day = EXCLUDED.weekly_day, updated_at = now() RETURNING {}",
+        PREFERENCE_COLUMNS
+    ))
+        .bind(user_id)
+        .bind(email)
+        .bind(frequency)
+        .bind(weekly_day)
+        .fetch_one(pool)
+        .await?;
+    Ok(preferences)
+}
+
+pub async fn list_subscriptions(pool: &PgPool, user_id: Uuid) -> Result<Vec<Subscription>, DatabaseError> {
+    let subscriptions = sqlx::query_as::<_, Subscription>(&format!(
+        "SELECT {} FROM notification_subscriptions WHERE user_id = $1 ORDER BY created_at, id",
+        SUBSCRIPTION_COLUMNS
+    ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(subscriptions)
+}
+
+/// Follow a schema, after checking the where clause compiles against its columns
+pub async fn create_subscription(pool: &PgPool, user_id: Uuid, new: NewSubscription) -> Result<Subscription, DatabaseError> {
+    let where_clause = new.where_clause.unwrap_or_else(|| json!({}));
+    Repository::new(&new.schema, pool.clone())
+        .select_one(FilterData { where_clause: Some(where_clause.clone()), ..Default::default() })
+        .await?;
+
+    let subscription = sqlx::query_as::<_, Subscription>(&format!(
+        "INSERT INTO notification_subscriptions (user_id, schema_name, name, where_clause) \
+         VALUES ($1, $2, $3, $4) RETURNING {}",
+        SUBSCRIPTION_COLUMNS
+    ))
+        .bind(user_id)
+        .bind(&new.schema)
+        .bind(&new.name)
+        .bind(&where_clause)
+        .fetch_one(pool)
+        .await?;
+    Ok(subscription)
+}
+
+pub async fn delete_subscription(pool: &PgPool, user_id: Uuid, id: Uuid) -> Result<(), DatabaseError> {
+    let deleted = sqlx::query("DELETE FROM notification_subscriptions WHERE id = $1 AND user_id = $2")
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    if deleted.rows_affected() == 0 {
+        return Err(DatabaseError::NotFound(format!("Subscription '{}'", id)));
+    }
+    Ok(())
+}
+
+/// Stop one subscription, or all digests when `subscription` is None
+pub async fn unsubscribe(pool: &PgPool, user_id: Uuid, subscription: Option<Uuid>) -> Result<(), DatabaseError> {
+    match subscription {
+        Some(id) => match delete_subscription(pool, user_id, id).await {
+            // already gone: unsubscribe links may be followed more than once
+            Err(DatabaseError::NotFound(_)) => Ok(()),
+            other => other,
+        },
+        None => {
+            sqlx::query("UPDATE notification_preferences SET frequency = 'off', updated_at = now() WHERE user_id = $1")
+                .bind(user_id)
+                .execute(pool)
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+/// Signed fields of an unsubscribe link: the database, the user and the subscription, or
+/// "all" for every digest
+fn unsubscribe_fields(database: &str, user_id: Uuid, subscription: Option<Uuid>) -> [String; 3] {
+    let target = subscription.map(|id| id.to_string()).unwrap_or_else(|| "all".to_string());
+    [database.to_string(), user_id.to_string(), target]
+}
+
+/// Signed link that stops one subscription, or all digests; valid without a bearer token
+pub fn unsubscribe_url(database: &str, user_id: Uuid, subscription: Option<Uuid>) -> String {
+    let fields = unsubscribe_fields(database, user_id, subscription);
+    let signature = signed_url::sign("unsubscribe", &fields.each_ref().map(String::as_str));
+    let base = crate::config::config().api.public_url.trim_end_matches('/');
+    match subscription {
+        Some(id) => format!(
+            "{}/api/notifications/unsubscribe/{}/{}?subscription={}&signature={}",
+            base, database, user_id, id, signature
+        ),
+        None => format!("{}/api/notifications/unsubscribe/{}/{}?signature={}", base, database, user_id, signature),
+    }
+}
+
+pub fn verify_unsubscribe(database: &str, user_id: Uuid, subscription: Option<Uuid>, signature: &str) -> bool {
+    let fields = unsubscribe_fields(database, user_id, subscription);
+    signed_url::verify("unsubscribe", &fields.each_ref().map(String::as_str), signature)
+}
+
+/// Whether a digest is due at `now`: from `hour` (UTC) on the day it is sent, once per period
+pub fn is_due(preferences: &NotificationPreferences, now: DateTime<Utc>, hour: u32) -> bool {
+    match preferences.frequency.as_str() {
+        "daily" => {}
+        "weekly" if now.weekday().number_from_monday() == preferences.weekly_day as u32 => {}
+        _ => return false,
+    }
+    let Some(send_at) = now.date_naive().and_hms_opt(hour.min(23), 0, 0) else {
+        return false;
+    };
+    now.naive_utc() >= send_at && preferences.last_digest_at.is_none_or(|last| last < send_at)
+}
+
+/// One changed record in a digest
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestChange {
+    pub record_id: Uuid,
+    /// created | updated
+    pub operation: &'static str,
+    /// Login name of the last user to change the record
+    pub actor: Option<String>,
+    pub changed_at: NaiveDateTime,
+    pub record: Value,
+}
+
+/// Changes matching one subscription
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestSection {
+    pub subscription: Subscription,
+    /// Matching changed records, newest first, at most DIGEST_CHANGES_SHOWN of them
+    pub changes: Vec<DigestChange>,
+    /// Matching changed records, including those not listed
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub user_id: Uuid,
+    pub email: String,
+    pub since: NaiveDateTime,
+    pub until: NaiveDateTime,
+    pub sections: Vec<DigestSection>,
+}
+
+impl Digest {
+    pub fn is_empty(&self) -> bool {
+        self.sections.iter().all(|section| section.total == 0)
+    }
+}
+
+/// Start of the period a digest covers when the user has not had one yet
+fn default_since(preferences: &NotificationPreferences, until: NaiveDateTime) -> NaiveDateTime {
+    let days = if preferences.frequency == "weekly" { 7 } else { 1 };
+    preferences.last_digest_at.unwrap_or(until - chrono::Duration::days(days))
+}
+
+/// Collect the changes a user's subscriptions match between their last digest and `until`
+pub async fn build_digest(
+    pool: &PgPool,
+    preferences: &NotificationPreferences,
+    access: Option<RecordAccess>,
+    until: NaiveDateTime,
+) -> Result<Digest, DatabaseError> {
+    let since = default_since(preferences, until);
+    let mut sections = Vec::new();
+    for subscription in list_subscriptions(pool, preferences.user_id).await? {
+        match section(pool, &subscription, access.clone(), since, until).await {
+            Ok(section) => sections.push(section),
+            Err(e) => tracing::warn!(
+                "Digest section '{}' of user {} skipped: {}",
+                subscription.title(),
+                preferences.user_id,
+                e
+            ),
+        }
+    }
+    Ok(Digest { user_id: preferences.user_id, email: preferences.email.clone(), since, until, sections })
+}
+
+/// Audited writes to one record within a digest period
+#[derive(sqlx::FromRow)]
+struct ChangedRecord {
+    record_id: Uuid,
+    /// Whether the record was created within the period
+    created: Option<bool>,
+    changed_at: Option<NaiveDateTime>,
+    /// Login name of the last writer
+    actor: Option<String>,
+}
+
+async fn section(
+    pool: &PgPool,
+    subscription: &Subscription,
+    access: Option<RecordAccess>,
+    since: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Result<DigestSection, DatabaseError> {
+    let changed: Vec<ChangedRecord> = sqlx::query_as(
+        "SELECT record_id, bool_or(operation = 'create') AS created, max(created_at) AS changed_at, \
+         (array_agg(actor ORDER BY created_at DESC))[1] AS actor \
+         FROM audit_log WHERE schema_name = $1 AND record_id IS NOT NULL \
+         AND operation IN ('create', 'update') AND created_at > $2 AND created_at <= $3 \
+         AND actor_id IS DISTINCT FROM $4 \
+         GROUP BY record_id ORDER BY max(created_at) DESC LIMIT $5"
+    )
+        .bind(&subscription.schema_name)
+        .bind(since)
+        .bind(until)
+        .bind(subscription.user_id)
+        .bind(DIGEST_CANDIDATES_LIMIT)
+        .fetch_all(pool)
+        .await?;
+    if changed.is_empty() {
+        return Ok(DigestSection { subscription: subscription.clone(), changes: Vec::new(), total: 0 });
+    }
+
+    let ids: Vec<Value> = changed.iter().map(|c| json!(c.record_id)).collect();
+    let by_id = json!({ "id": { "$in": ids } });
+    let where_clause = match &subscription.where_clause {
+        Value::Object(conditions) if !conditions.is_empty() => json!({ "$and": [subscription.where_clause, by_id] }),
+        _ => by_id,
+    };
+    let records = Repository::new(&subscription.schema_name, pool.clone())
+        .with_access(access)
+        .select_any(FilterData {
+            where_clause: Some(where_clause),
+            limit: Some(changed.len() as i32),
+            ..Default::default()
+        })
+        .await?;
+    let mut records: Vec<Value> = records.into_iter().map(|record| record.to_api_output()).collect();
+
+    let mut changes = Vec::new();
+    for c in changed {
+        let Some(position) = records.iter().position(|r| r.get("id") == Some(&json!(c.record_id))) else {
+            continue;
+        };
+        changes.push(DigestChange {
+            record_id: c.record_id,
+            operation: if c.created.unwrap_or(false) { "created" } else { "updated" },
+            actor: c.actor,
+            changed_at: c.changed_at.unwrap_or(until),
+            record: records.swap_remove(position),
+        });
+    }
+    let total = changes.len();
+    changes.truncate(DIGEST_CHANGES_SHOWN);
+    Ok(DigestSection { subscription: subscription.clone(), changes, total })
+}
+
+/// Short name of a record for digest lines: its name, title or subject, else its id
+fn record_label(change: &DigestChange) -> String {
+    ["name", "title", "subject"]
+        .iter()
+        .find_map(|field| change.record.get(*field).and_then(Value::as_str).filter(|s| !s.is_empty()))
+        .map(str::to_string)
+        .unwrap_or_else(|| change.record_id.to_string())
+}
+
+/// The digest as an email with HTML and plain text parts
+pub fn compose(database: &str, frequency: &str, digest: &Digest) -> OutgoingMail {
+    let total: usize = digest.sections.iter().map(|section| section.total).sum();
+    let title = format!("Your {} digest: {} change{}", frequency, total, if total == 1 { "" } else { "s" });
+    let sections: Vec<Value> = digest
+        .sections
+        .iter()
+        .filter(|section| section.total > 0)
+        .map(|section| json!({
+            "name": section.subscription.title(),
+            "changes": section.changes.iter().map(|change| json!({
+                "label": record_label(change),
+                "operation": change.operation,
+                "actor": change.actor,
+                "changed_at": change.changed_at.format("%Y-%m-%d %H:%M").to_string(),
+            })).collect::<Vec<_>>(),
+            "more": (section.total > section.changes.len()).then(|| section.total - section.changes.len()),
+            "unsubscribe_url": unsubscribe_url(database, digest.user_id, Some(section.subscription.id)),
+        }))
+        .collect();
+    let unsubscribe_all = unsubscribe_url(database, digest.user_id, None);
+    let context = json!({
+        "title": title,
+        "since": digest.since.format("%Y-%m-%d %H:%M").to_string(),
+        "until": digest.until.format("%Y-%m-%d %H:%M").to_string(),
+        "sections": sections,
+        "unsubscribe_url": unsubscribe_all,
+    });
+
+    let render = |source: &str, html: bool| {
+        let template = handlebars::Template::parse(source).expect("built-in digest templates parse");
+        if html { template.render(&context) } else { template.render_text(&context) }
+    };
+    OutgoingMail {
+        to: digest.email.clone(),
+        subject: title.clone(),
+        html: render(DIGEST_HTML, true),
+        text: render(DIGEST_TEXT, false),
+        unsubscribe_url: Some(unsubscribe_all),
+    }
+}
+
+/// Send the digests due in one tenant; returns how many were sent
+pub async fn send_due_digests(database: &str) -> Result<usize, DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    let now = crate::clock::now();
+    let hour = crate::config::config().api.digest_hour_utc;
+
+    let preferences = sqlx::query_as::<_, NotificationPreferences>(&format!(
+        "SELECT {} FROM notification_preferences WHERE frequency <> 'off' ORDER BY user_id",
+        PREFERENCE_COLUMNS
+    ))
+        .fetch_all(&pool)
+        .await?;
+
+    let mut sent = 0;
+    for preferences in preferences.iter().filter(|p| is_due(p, now, hour)) {
+        let access: Option<(String,)> = sqlx::query_as(
+            "SELECT access FROM users WHERE id = $1 AND trashed_at IS NULL AND deleted_at IS NULL"
+        )
+            .bind(preferences.user_id)
+            .fetch_optional(&pool)
+            .await?;
+        let access = match access {
+            Some((access,)) if access != "deny" => (access != "root").then(|| RecordAccess::new(vec![preferences.user_id])),
+            _ => continue,
+        };
+        let digest = build_digest(&pool, preferences, access, now.naive_utc()).await?;
+        if !digest.is_empty() {
+            let mail = compose(database, &preferences.frequency, &digest);
+            if let Err(e) = mailer::send(mail).await {
+                // the period stays open and is retried on the next pass
+                tracing::warn!("Digest for user {} in {} not sent: {}", preferences.user_id, database, e);
+                continue;
+            }
+            sent += 1;
+        }
+        sqlx::query("UPDATE notification_preferences SET last_digest_at = $2 WHERE user_id = $1")
+            .bind(preferences.user_id)
+            .bind(digest.until)
+            .execute(&pool)
+            .await?;
+    }
+    Ok(sent)
+}
+
+/// Send due digests for every active tenant, forever
+pub async fn run_digests() {
+    let config = crate::config::config();
+    if !mailer::configured() {
+        tracing::warn!("Notification digests are enabled but api.smtp_url is empty; no digests will be sent");
+    }
+    if !config.security.enable_audit_logging {
+        tracing::warn!("Notification digests are built from the audit log, but security.enable_audit_logging is off");
+    }
+
+    loop {
+        match crate::database::events::active_tenant_databases().await {
+            Ok(databases) => {
+                for database in databases {
+                    match send_due_digests(&database).await {
+                        Ok(0) => {}
+                        Ok(sent) => tracing::info!("Sent {} notification digest(s) for {}", sent, database),
+                        Err(e) => tracing::warn!("Notification digests of {} failed: {}", database, e),
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Notification digests could not list tenants: {}", e),
+        }
+
+        tokio::time::sleep(DIGEST_CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn digests_are_due_once_per_period_from_the_send_hour() {
+        let at = |day: u32, hour: u32| Utc.with_ymd_and_hms(2026, 10, day, hour, 30, 0).unwrap();
+        let mut preferences = NotificationPreferences {
+            user_id: Uuid::new_v4(),
+            email: "ada@example.com".to_string(),
+            frequency: "daily".to_string(),
+            weekly_day: 1,
+            last_digest_at: Some(at(14, 7).naive_utc()),
+            created_at: at(1, 0).naive_utc(),
+            updated_at: at(1, 0).naive_utc(),
+        };
+        assert!(!is_due(&preferences, at(15, 6), 7));
+        assert!(is_due(&preferences, at(15, 7), 7));
+        preferences.last_digest_at = Some(at(15, 7).naive_utc());
+        assert!(!is_due(&preferences, at(15, 9), 7));
+
+        // 2026-10-19 is a Monday
+        preferences.frequency = "weekly".to_string();
+        assert!(!is_due(&preferences, at(18, 9), 7));
+        assert!(is_due(&preferences, at(19, 9), 7));
+        preferences.frequency = "off".to_string();
+        assert!(!is_due(&preferences, at(19, 9), 7));
+
+        let id = Uuid::new_v4();
+        let url = unsubscribe_url("tenant_a", preferences.user_id, Some(id));
+        let signature = url.rsplit("signature=").next().unwrap();
+        assert!(verify_unsubscribe("tenant_a", preferences.user_id, Some(id), signature));
+        assert!(!verify_unsubscribe("tenant_a", preferences.user_id, None, signature));
+        assert!(!verify_unsubscribe("tenant_b", preferences.user_id, Some(id), signature));
+    }
+}
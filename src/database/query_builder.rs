@@ -19,7 +19,7 @@ where
     pub fn new(table_name: impl Into<String>) -> Result<Self, DatabaseError> {
         let name = table_name.into();
         // Reuse Filter table name validation
-        crate::filter::filter::Filter::new(&name).map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        crate::filter::filter::Filter::new(&name).map_err(DatabaseError::Filter)?;
         Ok(Self {
             table_name: name,
             select_columns: None,
@@ -34,10 +34,10 @@ where
     }
 
     pub fn filter(mut self, filter_data: FilterData) -> Result<Self, DatabaseError> {
-        let mut filter = Filter::new(&self.table_name).map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        let mut filter = Filter::new(&self.table_name).map_err(DatabaseError::Filter)?;
         filter
             .assign(filter_data)
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+            .map_err(DatabaseError::Filter)?;
         self.filter = Some(filter);
         Ok(self)
     }
@@ -74,7 +74,7 @@ where
 
     pub async fn count(self, pool: &PgPool) -> Result<i64, DatabaseError> {
         let sql_result = if let Some(filter) = self.filter {
-            filter.to_count_sql().map_err(|e| DatabaseError::QueryError(e.to_string()))?
+            filter.to_count_sql().map_err(DatabaseError::Filter)?
         } else {
             SqlResult { query: format!("SELECT COUNT(*) as count FROM \"{}\"", self.table_name), params: vec![] }
         };
@@ -92,7 +92,7 @@ where
         if let Some(filter) = &self.filter {
             filter
                 .to_sql()
-                .map_err(|e| DatabaseError::QueryError(e.to_string()))
+                .map_err(DatabaseError::Filter)
         } else {
             Ok(SqlResult { query: format!("SELECT * FROM \"{}\"", self.table_name), params: vec![] })
         }
@@ -244,12 +244,12 @@ impl Record {
 
     /// Touch updated_at field (for observers)
     pub fn touch_updated_at(&mut self) -> &mut Self {
-        self.set_system_field("updated_at", Value::String(Utc::now().to_rfc3339()))
+        self.set_system_field("updated_at", Value::String(crate::clock::now().to_rfc3339()))
     }
 
     /// Mark record as deleted (soft delete)
     pub fn mark_deleted(&mut self) -> &mut Self {
-        self.set_system_field("trashed_at", Value::String(Utc::now().to_rfc3339()));
+        self.set_system_field("trashed_at", Value::String(crate::clock::now().to_rfc3339()));
         self.operation = Operation::Delete;
         self
     }
@@ -0,0 +1,267 @@
+use chrono::{Duration, NaiveDateTime};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::clock::{Clock, SystemClock};
+use crate::database::manager::DatabaseError;
+
+/// A freshly issued refresh token; only its SHA-256 digest is stored
+#[derive(Debug, Clone)]
+pub struct IssuedRefreshToken {
+    pub token: String,
+    pub family_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    /// Whether the login that started the family verified a TOTP code; kept by rotations
+    pub mfa: bool,
+}
+
+/// Result of presenting a refresh token to [`rotate`]
+#[derive(Debug, Clone)]
+pub enum Rotation {
+    /// The token was valid and has been replaced by `next`
+    Rotated(IssuedRefreshToken),
+    /// The token was already used or revoked: its whole family is now revoked
+    Reused { family_id: Uuid },
+    Expired,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredToken {
+    id: Uuid,
+    user_id: Uuid,
+    family_id: Uuid,
+    expires_at: NaiveDateTime,
+    used_at: Option<NaiveDateTime>,
+    revoked_at: Option<NaiveDateTime>,
+    mfa: bool,
+}
+
+pub fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// Issue the first refresh token of a new family, at login
+pub async fn issue(pool: &PgPool, user_id: Uuid, mfa: bool) -> Result<IssuedRefreshToken, DatabaseError> {
+    issue_with(pool, user_id, mfa, &SystemClock).await
+}
+
+async fn issue_with(pool: &PgPool, user_id: Uuid, mfa: bool, clock: &dyn Clock) -> Result<IssuedRefreshToken, DatabaseError> {
+    let mut conn = pool.acquire().await.map_err(DatabaseError::Sqlx)?;
+    insert_token(&mut conn, user_id, Uuid::new_v4(), mfa, clock).await
+}
+
+async fn insert_token(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+    family_id: Uuid,
+    mfa: bool,
+    clock: &dyn Clock,
+) -> Result<IssuedRefreshToken, DatabaseError> {
+    let ttl_days = crate::config::config().security.refresh_token_ttl_days as i64;
+    let now = clock.now();
+    let issued = IssuedRefreshToken {
+        token: new_token(),
+        family_id,
+        expires_at: (now + Duration::days(ttl_days)).naive_utc(),
+        mfa,
+    };
+
+    sqlx::query(
+        "INSERT INTO refresh_tokens (user_id, family_id, token_hash, created_at, expires_at, mfa) \
+         VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+        .bind(user_id)
+        .bind(family_id)
+        .bind(token_hash(&issued.token))
+        .bind(now.naive_utc())
+        .bind(issued.expires_at)
+        .bind(mfa)
+        .execute(conn)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(issued)
+}
+
+/// Exchange a refresh token of `user_id` for a new one in the same family
+///
+/// The new token carries the family's MFA flag, so refreshed sessions stay MFA-verified.
+/// Tokens of other users are reported as unknown. A token can be used once: presenting a
+/// used or revoked token means it leaked (or the client lost a response), so every token
+/// of the family is revoked and the session ends.
+pub async fn rotate(pool: &PgPool, token: &str, user_id: Uuid) -> Result<Rotation, DatabaseError> {
+    rotate_with(pool, token, user_id, &SystemClock).await
+}
+
+async fn rotate_with(pool: &PgPool, token: &str, user_id: Uuid, clock: &dyn Clock) -> Result<Rotation, DatabaseError> {
+    let mut tx = pool.begin().await.map_err(DatabaseError::Sqlx)?;
+
+    let stored: Option<StoredToken> = sqlx::query_as(
+        "SELECT id, user_id, family_id, expires_at, used_at, revoked_at, mfa \
+         FROM refresh_tokens WHERE token_hash = $1 AND user_id = $2 FOR UPDATE"
+    )
+        .bind(token_hash(token))
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    let Some(stored) = stored else {
+        return Ok(Rotation::Unknown);
+    };
+    let now = clock.now().naive_utc();
+
+    if stored.used_at.is_some() || stored.revoked_at.is_some() {
+        revoke_family_on(&mut tx, stored.family_id, now).await?;
+        tx.commit().await.map_err(DatabaseError::Sqlx)?;
+        tracing::warn!("Refresh token reuse for user {}; revoked family {}", stored.user_id, stored.family_id);
+        return Ok(Rotation::Reused { family_id: stored.family_id });
+    }
+    if stored.expires_at < now {
+        return Ok(Rotation::Expired);
+    }
+
+    sqlx::query("UPDATE refresh_tokens SET used_at = $2 WHERE id = $1")
+        .bind(stored.id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+    let next = insert_token(&mut tx, stored.user_id, stored.family_id, stored.mfa, clock).await?;
+    tx.commit().await.map_err(DatabaseError::Sqlx)?;
+
+    Ok(Rotation::Rotated(next))
+}
+
+/// Revoke the family `token` belongs to, if it belongs to `user_id`; returns the tokens revoked
+pub async fn revoke(pool: &PgPool, token: &str, user_id: Uuid) -> Result<Option<u64>, DatabaseError> {
+    let family: Option<(Uuid,)> = sqlx::query_as(
+        "SELECT family_id FROM refresh_tokens WHERE token_hash = $1 AND user_id = $2"
+    )
+        .bind(token_hash(token))
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    match family {
+        Some((family_id,)) => {
+            let mut conn = pool.acquire().await.map_err(DatabaseError::Sqlx)?;
+            Ok(Some(revoke_family_on(&mut conn, family_id, crate::clock::now().naive_utc()).await?))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn revoke_family_on(conn: &mut sqlx::PgConnection, family_id: Uuid, now: NaiveDateTime) -> Result<u64, DatabaseError> {
+    let result = sqlx::query(
+        "UPDATE refresh_tokens SET revoked_at = $2 WHERE family_id = $1 AND revoked_at IS NULL"
+    )
+        .bind(family_id)
+        .bind(now)
+        .execute(conn)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    /// The refresh_tokens table without its users reference
+    const TABLE: &str = "CREATE TEMPORARY TABLE refresh_tokens (
+        id uuid PRIMARY KEY DEFAULT gen_random_uuid() NOT NULL,
+        user_id uuid NOT NULL,
+        family_id uuid NOT NULL,
+        token_hash text NOT NULL UNIQUE,
+        created_at timestamp DEFAULT now() NOT NULL,
+        expires_at timestamp NOT NULL,
+        used_at timestamp,
+        revoked_at timestamp,
+        mfa boolean DEFAULT false NOT NULL
+    )";
+
+    async fn family_revoked(pool: &PgPool, family_id: Uuid) -> bool {
+        sqlx::query_scalar("SELECT bool_and(revoked_at IS NOT NULL) FROM refresh_tokens WHERE family_id = $1")
+            .bind(family_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_rotate_replaces_the_token_and_keeps_mfa() {
+        let Some(pool) = crate::testing::scratch_pool(TABLE).await else { return };
+        let user = Uuid::new_v4();
+        let first = issue(&pool, user, true).await.unwrap();
+
+        let Rotation::Rotated(second) = rotate(&pool, &first.token, user).await.unwrap() else { panic!("not rotated") };
+        assert_ne!(second.token, first.token);
+        assert_eq!(second.family_id, first.family_id);
+        assert!(second.mfa);
+
+        let Rotation::Rotated(third) = rotate(&pool, &second.token, user).await.unwrap() else { panic!("not rotated") };
+        assert!(third.mfa);
+        assert!(!family_revoked(&pool, first.family_id).await);
+
+        let plain = issue(&pool, user, false).await.unwrap();
+        let Rotation::Rotated(next) = rotate(&pool, &plain.token, user).await.unwrap() else { panic!("not rotated") };
+        assert!(!next.mfa);
+    }
+
+    #[tokio::test]
+    async fn test_reusing_a_token_revokes_its_family() {
+        let Some(pool) = crate::testing::scratch_pool(TABLE).await else { return };
+        let user = Uuid::new_v4();
+        let first = issue(&pool, user, false).await.unwrap();
+        let other_session = issue(&pool, user, false).await.unwrap();
+        let Rotation::Rotated(second) = rotate(&pool, &first.token, user).await.unwrap() else { panic!("not rotated") };
+
+        assert!(matches!(rotate(&pool, &first.token, user).await.unwrap(), Rotation::Reused { family_id } if family_id == first.family_id));
+        assert!(family_revoked(&pool, first.family_id).await);
+        // The latest token of the family is revoked with it
+        assert!(matches!(rotate(&pool, &second.token, user).await.unwrap(), Rotation::Reused { .. }));
+        // Other logins of the user are unaffected
+        assert!(matches!(rotate(&pool, &other_session.token, user).await.unwrap(), Rotation::Rotated(_)));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_rejects_expired_and_foreign_tokens() {
+        let Some(pool) = crate::testing::scratch_pool(TABLE).await else { return };
+        let user = Uuid::new_v4();
+        let clock = MockClock::new(crate::clock::now());
+        let issued = issue_with(&pool, user, false, &clock).await.unwrap();
+
+        assert!(matches!(rotate_with(&pool, &issued.token, Uuid::new_v4(), &clock).await.unwrap(), Rotation::Unknown));
+        assert!(matches!(rotate_with(&pool, "not-a-token", user, &clock).await.unwrap(), Rotation::Unknown));
+        assert_eq!(revoke(&pool, &issued.token, Uuid::new_v4()).await.unwrap(), None);
+
+        let ttl_days = crate::config::config().security.refresh_token_ttl_days as i64;
+        clock.advance(Duration::days(ttl_days) + Duration::minutes(1));
+        assert!(matches!(rotate_with(&pool, &issued.token, user, &clock).await.unwrap(), Rotation::Expired));
+        // An expired token is not consumed or revoked
+        assert!(!family_revoked(&pool, issued.family_id).await);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_ends_the_family() {
+        let Some(pool) = crate::testing::scratch_pool(TABLE).await else { return };
+        let user = Uuid::new_v4();
+        let first = issue(&pool, user, false).await.unwrap();
+        let Rotation::Rotated(second) = rotate(&pool, &first.token, user).await.unwrap() else { panic!("not rotated") };
+
+        // The used first token was not revoked yet, so both are
+        assert_eq!(revoke(&pool, &second.token, user).await.unwrap(), Some(2));
+        assert!(matches!(rotate(&pool, &second.token, user).await.unwrap(), Rotation::Reused { .. }));
+        assert_eq!(revoke(&pool, &second.token, user).await.unwrap(), Some(0));
+    }
+}
@@ -0,0 +1,112 @@
+//! Tenant data residency: the region and cluster each tenant database lives on
+//!
+//! The cluster at DATABASE_URL is called `default` and sits in `database.default_region`;
+//! `database.clusters` adds more. Each tenant row in monk_main records its region and
+//! cluster (NULL meaning the default cluster), and `DatabaseManager` connects to the
+//! tenant's database on that cluster. Tenants are cloned from templates within their
+//! cluster, so templates must be installed on every cluster that takes new tenants.
+//!
+//! An API instance runs in `database.local_region`. Tenants whose region is elsewhere can
+//! be read from a local replica of their cluster (`database.replicas`) and have their
+//! writes forwarded to the instances of their own region (`api.region_endpoints`).
+use sqlx::PgPool;
+use tokio::sync::OnceCell;
+
+use crate::config::{DatabaseCluster, DatabaseReplica};
+use crate::database::manager::DatabaseError;
+
+/// Name of the cluster at DATABASE_URL
+pub const DEFAULT_CLUSTER: &str = "default";
+
+static COLUMNS_READY: OnceCell<()> = OnceCell::const_new();
+
+const ADD_COLUMNS: &str = "ALTER TABLE tenants ADD COLUMN IF NOT EXISTS region TEXT, ADD COLUMN IF NOT EXISTS cluster TEXT";
+
+/// Add the placement columns to the tenants table once per process
+pub async fn ensure_columns(main_pool: &PgPool) -> Result<(), DatabaseError> {
+    COLUMNS_READY
+        .get_or_try_init(|| async { sqlx::query(ADD_COLUMNS).execute(main_pool).await.map(|_| ()) })
+        .await?;
+    Ok(())
+}
+
+/// Every cluster tenants can be placed on, the default one first
+pub fn clusters() -> Vec<DatabaseCluster> {
+    let database = &crate::config::config().database;
+    let default = DatabaseCluster {
+        name: DEFAULT_CLUSTER.to_string(),
+        region: database.default_region.clone(),
+        url: std::env::var("DATABASE_URL").unwrap_or_default(),
+    };
+    std::iter::once(default).chain(database.clusters.iter().cloned()).collect()
+}
+
+pub fn find_cluster(name: &str) -> Option<DatabaseCluster> {
+    clusters().into_iter().find(|cluster| cluster.name == name)
+}
+
+/// Region this API instance runs in
+pub fn local_region() -> String {
+    let database = &crate::config::config().database;
+    if database.local_region.is_empty() {
+        database.default_region.clone()
+    } else {
+        database.local_region.clone()
+    }
+}
+
+/// Replica of `cluster` in this instance's region, if one is configured
+pub fn local_replica(cluster: &str) -> Option<DatabaseReplica> {
+    let region = local_region();
+    crate::config::config()
+        .database
+        .replicas
+        .iter()
+        .find(|replica| replica.cluster == cluster && replica.region == region)
+        .cloned()
+}
+
+/// Base URL of the API instances serving `region`
+pub fn endpoint(region: &str) -> Option<String> {
+    crate::config::config()
+        .api
+        .region_endpoints
+        .iter()
+        .find(|endpoint| endpoint.region == region)
+        .map(|endpoint| endpoint.url.clone())
+}
+
+/// Configured regions, sorted
+pub fn regions() -> Vec<String> {
+    let mut regions: Vec<String> = clusters().into_iter().map(|cluster| cluster.region).collect();
+    regions.sort();
+    regions.dedup();
+    regions
+}
+
+/// Cluster `database` is registered on; the default cluster for unregistered databases
+pub async fn cluster_of(main_pool: &PgPool, database: &str) -> Result<String, DatabaseError> {
+    ensure_columns(main_pool).await?;
+    let cluster: Option<(Option<String>,)> = sqlx::query_as("SELECT cluster FROM tenants WHERE database = $1")
+        .bind(database)
+        .fetch_optional(main_pool)
+        .await?;
+    Ok(cluster.and_then(|(cluster,)| cluster).unwrap_or_else(|| DEFAULT_CLUSTER.to_string()))
+}
+
+/// Cluster in `region` holding the fewest tenants, or None if the region has no clusters
+pub async fn choose_cluster(main_pool: &PgPool, region: &str) -> Result<Option<DatabaseCluster>, DatabaseError> {
+    ensure_columns(main_pool).await?;
+    let counts: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT COALESCE(cluster, $1), COUNT(*) FROM tenants WHERE deleted_at IS NULL GROUP BY 1"
+    )
+        .bind(DEFAULT_CLUSTER)
+        .fetch_all(main_pool)
+        .await?;
+    let load = |name: &str| counts.iter().find(|(cluster, _)| cluster == name).map_or(0, |(_, count)| *count);
+
+    Ok(clusters()
+        .into_iter()
+        .filter(|cluster| cluster.region == region)
+        .min_by_key(|cluster| load(&cluster.name)))
+}
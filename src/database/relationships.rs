@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+use sqlx::PgExecutor;
+
+use crate::database::manager::DatabaseError;
+use crate::filter::FilterRelationship;
+
+/// All declared x-monk-relationship edges between active schemas, for relationship filters
+///
+/// Each property carrying x-monk-relationship becomes an edge named after the property
+/// without its `_id` suffix, so `orders.customer_id` is filtered as `customer.<field>`.
+/// Reads the schemas table directly since this runs inside the select pipeline.
+pub async fn load_relationships(executor: impl PgExecutor<'_>) -> Result<Vec<FilterRelationship>, DatabaseError> {
+    let rows: Vec<(String, String, Value)> = sqlx::query_as(
+        "SELECT name, table_name, definition FROM schemas WHERE trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .fetch_all(executor)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    let tables: HashMap<&str, &str> = rows
+        .iter()
+        .map(|(name, table, _)| (name.as_str(), table.as_str()))
+        .collect();
+
+    let mut relationships = Vec::new();
+    for (_, table, definition) in &rows {
+        let properties = match definition.get("properties").and_then(|p| p.as_object()) {
+            Some(properties) => properties,
+            None => continue,
+        };
+
+        for (property, spec) in properties {
+            let relationship = match spec.get("x-monk-relationship") {
+                Some(relationship) if relationship.is_object() => relationship,
+                _ => continue,
+            };
+            let related_table = match relationship
+                .get("schema")
+                .and_then(|s| s.as_str())
+                .and_then(|schema| tables.get(schema))
+            {
+                Some(related_table) => related_table.to_string(),
+                None => continue,
+            };
+
+            relationships.push(FilterRelationship {
+                table: table.clone(),
+                name: property.strip_suffix("_id").unwrap_or(property).to_string(),
+                column: property.clone(),
+                related_table,
+                related_column: relationship
+                    .get("column")
+                    .and_then(|c| c.as_str())
+                    .unwrap_or("id")
+                    .to_string(),
+                required: relationship.get("required").and_then(|r| r.as_bool()).unwrap_or(false),
+            });
+        }
+    }
+
+    Ok(relationships)
+}
@@ -5,9 +5,17 @@ use std::collections::HashMap;
 
 use crate::database::manager::DatabaseError;
 use crate::database::record::Record;
+use crate::database::audit::AuditActor;
+use crate::database::favorites::RecordViewer;
 use crate::types::Operation;
-use crate::filter::FilterData;
-use crate::observer::{ObserverPipeline, register_all_sql_executors};
+use crate::filter::{AggregateData, CountMode, FilterData, RecordAccess};
+use crate::filter::filter_join::FilterJoin;
+use crate::database::relationships::load_relationships;
+use crate::database::columns::{json_columns, load_columns};
+use crate::database::extensions::use_unaccent;
+use crate::database::transaction::TenantTransaction;
+use crate::observer::{ObserverPipeline, WriteMode, register_all_sql_executors};
+use crate::observer::preview::SqlOperation;
 
 /// Query parameter that can be either a UUID or a FilterData
 #[derive(Debug, Clone)]
@@ -41,9 +49,46 @@ impl QueryParam {
     }
 }
 
+/// Generated SQL and PostgreSQL plan for a filter, as returned by `Repository::explain`
+#[derive(Debug, Clone)]
+pub struct QueryExplain {
+    pub query: String,
+    pub params: Vec<Value>,
+    /// `EXPLAIN (FORMAT JSON)` output
+    pub plan: Value,
+}
+
+/// Random sample of filtered rows plus per-column statistics, as returned by `Repository::sample`
+#[derive(Debug, Clone)]
+pub struct QuerySample {
+    /// Rows matching the filter
+    pub total: i64,
+    /// "random" (ORDER BY random()) or "tablesample" (BERNOULLI pre-sampling on large tables)
+    pub method: &'static str,
+    pub records: Vec<Record>,
+    /// Column name -> { distinct, min, max } over the whole filtered set
+    pub stats: serde_json::Map<String, Value>,
+}
+
+/// Above this estimated table size, rows are pre-sampled with TABLESAMPLE before shuffling
+const SAMPLE_TABLESAMPLE_THRESHOLD: f64 = 100_000.0;
+
+/// Column types whose min/max are meaningful and supported by PostgreSQL
+const SAMPLE_ORDERED_TYPES: &[&str] = &[
+    "smallint", "integer", "bigint", "numeric", "real", "double precision",
+    "text", "character varying", "character",
+    "date", "time without time zone", "time with time zone",
+    "timestamp without time zone", "timestamp with time zone",
+];
+
 pub struct Repository {
     table_name: String,
     pool: PgPool,
+    access: Option<RecordAccess>,
+    actor: Option<AuditActor>,
+    viewer: Option<RecordViewer>,
+    write_mode: WriteMode,
+    transaction: Option<TenantTransaction>,
 }
 
 impl Repository {
@@ -51,9 +96,47 @@ impl Repository {
         Self {
             table_name: table_name.into(),
             pool,
+            access: None,
+            actor: None,
+            viewer: None,
+            write_mode: WriteMode::default(),
+            transaction: None,
         }
     }
 
+    /// Limit reads to rows the given ids may see through their access_* lists, and writes to
+    /// rows they may edit; None reads and writes everything
+    pub fn with_access(mut self, access: Option<RecordAccess>) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Record writes through this repository in the audit log as made by `actor`
+    pub fn with_actor(mut self, actor: Option<AuditActor>) -> Self {
+        self.actor = actor;
+        self
+    }
+
+    /// Count reads through this repository as `user_id` opening the records, on schemas
+    /// that track views
+    pub fn with_viewer(mut self, user_id: Option<Uuid>) -> Self {
+        self.viewer = user_id.map(|user_id| RecordViewer { user_id });
+        self
+    }
+
+    /// How multi-record writes are committed; atomic unless changed
+    pub fn with_write_mode(mut self, write_mode: WriteMode) -> Self {
+        self.write_mode = write_mode;
+        self
+    }
+
+    /// Run the observer pipelines of this repository's reads and writes inside `transaction`,
+    /// which the caller commits; None runs them on the pool
+    pub fn with_transaction(mut self, transaction: Option<TenantTransaction>) -> Self {
+        self.transaction = transaction;
+        self
+    }
+
     /// Create an observer pipeline with all SQL executors registered
     /// REST API requires all CRUD operations to be available
     fn create_pipeline() -> ObserverPipeline {
@@ -141,6 +224,20 @@ impl Repository {
                     Ok(Value::Null)
                 }
             }
+            "FLOAT4" => {
+                if let Ok(num) = row.try_get::<Option<f32>, _>(index) {
+                    Ok(num.and_then(|n| serde_json::Number::from_f64(n.into())).map(Value::Number).unwrap_or(Value::Null))
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            "FLOAT8" => {
+                if let Ok(num) = row.try_get::<Option<f64>, _>(index) {
+                    Ok(num.and_then(serde_json::Number::from_f64).map(Value::Number).unwrap_or(Value::Null))
+                } else {
+                    Ok(Value::Null)
+                }
+            }
             "BOOL" => {
                 if let Ok(b) = row.try_get::<Option<bool>, _>(index) {
                     Ok(b.map(Value::Bool).unwrap_or(Value::Null))
@@ -209,6 +306,9 @@ impl Repository {
             order: None,
             limit,
             offset,
+            rank_by: None,
+            top_n_per_group: None,
+            cursor: None,
         };
         self.select_any(filter_data).await
     }
@@ -217,8 +317,8 @@ impl Repository {
     pub async fn select_any(&self, filter_data: FilterData) -> Result<Vec<Record>, DatabaseError> {
         // Use pipeline's Record-aware method (handles all conversion internally)
         let pipeline = Self::create_pipeline();
-        pipeline.select(&self.table_name, filter_data, self.pool.clone()).await
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))
+        pipeline.select(&self.table_name, filter_data, self.pool.clone(), self.transaction.clone(), self.access.clone(), self.viewer).await
+            .map_err(DatabaseError::Observer)
     }
 
     /// Select single record - accepts either UUID or FilterData
@@ -239,25 +339,282 @@ impl Repository {
     // REMOVED: update_by_id_404() - use update_404(uuid, record) instead
     // The unified update_404() method now handles Uuid inputs seamlessly
 
+    /// Exact number of rows matching a filter's conditions
+    ///
+    /// Select, order and pagination are ignored.
     pub async fn count(&self, filter_data: FilterData) -> Result<i64, DatabaseError> {
-        use crate::filter::Filter;
+        self.filtered_count(FilterData {
+            where_clause: filter_data.where_clause,
+            rank_by: filter_data.rank_by,
+            top_n_per_group: filter_data.top_n_per_group,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Total rows matching a filter's conditions, produced according to `mode`
+    ///
+    /// Select, order and pagination are ignored. Returns None for `CountMode::None`.
+    pub async fn total(&self, filter_data: &FilterData, mode: CountMode) -> Result<Option<i64>, DatabaseError> {
+        let counted = FilterData {
+            where_clause: filter_data.where_clause.clone(),
+            rank_by: filter_data.rank_by.clone(),
+            top_n_per_group: filter_data.top_n_per_group,
+            ..Default::default()
+        };
+        // Table-wide counts would include rows hidden by access lists
+        let filtered = counted.rank_by.is_some()
+            || self.access.is_some()
+            || counted.where_clause.as_ref().is_some_and(|w| match w {
+                Value::Null => false,
+                Value::Object(obj) => !obj.is_empty(),
+                _ => true,
+            });
+
+        match mode {
+            CountMode::None => Ok(None),
+            CountMode::Estimated if !filtered => {
+                let estimate: Option<f32> = sqlx::query_scalar(
+                    "SELECT reltuples FROM pg_class WHERE oid = to_regclass($1)"
+                )
+                    .bind(format!("\"{}\"", self.table_name))
+                    .fetch_optional(&self.pool)
+                    .await
+                    .map_err(DatabaseError::Sqlx)?
+                    .flatten();
+                match estimate {
+                    // reltuples is -1 until the table is first analyzed
+                    Some(estimate) if estimate >= 0.0 => Ok(Some(estimate.round() as i64)),
+                    _ => self.filtered_count(counted).await.map(Some),
+                }
+            }
+            CountMode::Estimated => {
+                let explain = self.explain(counted, false).await?;
+                let rows = explain.plan.pointer("/0/Plan/Plan Rows").and_then(|v| v.as_f64()).unwrap_or(0.0);
+                Ok(Some(rows.round() as i64))
+            }
+            CountMode::Exact if !filtered => self.maintained_count().await.map(Some),
+            CountMode::Exact => self.filtered_count(counted).await.map(Some),
+        }
+    }
+
+    /// COUNT(*) over the rows a filter matches
+    async fn filtered_count(&self, filter_data: FilterData) -> Result<i64, DatabaseError> {
+        let mut filter = self.prepare_filter(filter_data.where_clause.as_ref()).await?;
+
+        filter.assign(filter_data)
+            .map_err(DatabaseError::Filter)?;
+
+        let sql_result = filter.to_count_sql()
+            .map_err(DatabaseError::Filter)?;
+
+        let mut query = sqlx::query(&sql_result.query);
+        for param in &sql_result.params {
+            query = self.bind_param(query, param);
+        }
+        let row = query.fetch_one(&self.pool).await.map_err(DatabaseError::Sqlx)?;
+        row.try_get(0).map_err(DatabaseError::Sqlx)
+    }
+
+    /// Live row count from record_counts, seeding the row on first use
+    ///
+    /// Tenants created before record_counts existed fall back to COUNT(*).
+    async fn maintained_count(&self) -> Result<i64, DatabaseError> {
+        let select = "SELECT count FROM record_counts WHERE schema_name = $1";
+        let existing: Result<Option<i64>, sqlx::Error> = sqlx::query_scalar(select)
+            .bind(&self.table_name)
+            .fetch_optional(&self.pool)
+            .await;
+
+        match existing {
+            Ok(Some(count)) => Ok(count),
+            Ok(None) => {
+                let seed = format!(
+                    "INSERT INTO record_counts (schema_name, count) \
+                     SELECT $1, COUNT(*) FROM \"{}\" WHERE trashed_at IS NULL AND deleted_at IS NULL \
+                     ON CONFLICT (schema_name) DO NOTHING",
+                    self.table_name
+                );
+                sqlx::query(&seed)
+                    .bind(&self.table_name)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(DatabaseError::Sqlx)?;
+                sqlx::query_scalar(select)
+                    .bind(&self.table_name)
+                    .fetch_one(&self.pool)
+                    .await
+                    .map_err(DatabaseError::Sqlx)
+            }
+            Err(e) => {
+                tracing::debug!("record_counts unavailable ({}), counting {} directly", e, self.table_name);
+                self.filtered_count(FilterData::default()).await
+            }
+        }
+    }
+
+    /// Filter for this table, with declared relationships loaded when the where clause
+    /// filters on related-schema fields such as "customer.country", and the table's
+    /// columns loaded so references to unknown columns are rejected before any SQL runs
+    async fn prepare_filter(&self, where_clause: Option<&Value>) -> Result<crate::filter::Filter, DatabaseError> {
+        let mut filter = crate::filter::Filter::new(&self.table_name)
+            .map_err(DatabaseError::Filter)?;
+
+        let dotted = where_clause.is_some_and(|w| !FilterJoin::paths(w, &[]).is_empty());
+        if dotted {
+            let relationships = load_relationships(&self.pool).await?;
+            filter.relationships(relationships);
+        }
+        let columns = load_columns(&self.pool, &self.table_name).await?;
+        filter.json_columns(json_columns(&columns));
+        filter.column_types(&columns);
+        filter.columns(columns.into_iter().map(|(name, _)| name).collect());
+        if let Some(ref access) = self.access {
+            filter.access(access.clone());
+        }
+        filter.unaccent(use_unaccent(&self.pool, where_clause).await?);
+
+        Ok(filter)
+    }
+
+    /// Build the SELECT a filter would run and ask PostgreSQL for its plan
+    ///
+    /// With `analyze` the query is actually executed, so it runs inside a transaction
+    /// that is always rolled back.
+    pub async fn explain(&self, filter_data: FilterData, analyze: bool) -> Result<QueryExplain, DatabaseError> {
+        let mut filter = self.prepare_filter(filter_data.where_clause.as_ref()).await?;
+
+        filter.assign(filter_data)
+            .map_err(DatabaseError::Filter)?;
+
+        let sql_result = filter.to_sql()
+            .map_err(DatabaseError::Filter)?;
+
+        let options = if analyze { "ANALYZE, BUFFERS, FORMAT JSON" } else { "FORMAT JSON" };
+        let explain_sql = format!("EXPLAIN ({}) {}", options, sql_result.query);
+
+        let mut query = sqlx::query(&explain_sql);
+        for param in &sql_result.params {
+            query = self.bind_param(query, param);
+        }
+
+        let mut tx = self.pool.begin().await.map_err(DatabaseError::Sqlx)?;
+        let row = query.fetch_one(&mut *tx).await.map_err(DatabaseError::Sqlx)?;
+        tx.rollback().await.map_err(DatabaseError::Sqlx)?;
 
-        // Use Filter system to build count query
-        let mut filter = Filter::new(&self.table_name)
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+        let plan: Value = row.try_get(0).map_err(DatabaseError::Sqlx)?;
+
+        Ok(QueryExplain {
+            query: sql_result.query,
+            params: sql_result.params,
+            plan,
+        })
+    }
+
+    /// Return up to `size` random rows matching a filter, with optional column statistics
+    ///
+    /// Only the filter's WHERE conditions are used; its order and limit are ignored.
+    /// Statistics (distinct count, min/max where orderable) cover the full filtered set,
+    /// not just the sample.
+    pub async fn sample(&self, filter_data: FilterData, size: i32, with_stats: bool) -> Result<QuerySample, DatabaseError> {
+        let mut filter = self.prepare_filter(filter_data.where_clause.as_ref()).await?;
 
         filter.assign(filter_data)
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))?;
+            .map_err(DatabaseError::Filter)?;
+
+        let where_result = filter.to_where_sql()
+            .map_err(DatabaseError::Filter)?;
+        let join_sql = filter.to_join_sql()
+            .map_err(DatabaseError::Filter)?;
+        let where_sql = if where_result.query.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_result.query)
+        };
 
-        // Build count query instead of select
-        let query = format!("SELECT COUNT(*) FROM \"{}\"", self.table_name);
-        let row = sqlx::query(&query)
-            .fetch_one(&self.pool)
+        // Planner estimate is enough to decide whether a full shuffle is too expensive
+        let estimate: Option<f32> = sqlx::query_scalar(
+            "SELECT reltuples FROM pg_class WHERE oid = to_regclass($1)"
+        )
+            .bind(format!("\"{}\"", self.table_name))
+            .fetch_optional(&self.pool)
             .await
-            .map_err(DatabaseError::Sqlx)?;
+            .map_err(DatabaseError::Sqlx)?
+            .flatten();
+        let estimate = estimate.map(f64::from).unwrap_or(0.0);
+
+        let size = size.max(1);
+        let (method, tablesample) = if estimate > SAMPLE_TABLESAMPLE_THRESHOLD {
+            // Oversample 10x so the WHERE clause still leaves enough rows to choose from
+            let percent = (f64::from(size) * 10.0 / estimate * 100.0).clamp(0.01, 100.0);
+            ("tablesample", format!(" TABLESAMPLE BERNOULLI ({:.4})", percent))
+        } else {
+            ("random", String::new())
+        };
+
+        let sample_sql = format!(
+            "SELECT \"{}\".* FROM \"{}\"{} {} {} ORDER BY random() LIMIT {}",
+            self.table_name, self.table_name, tablesample, join_sql, where_sql, size
+        );
+        let records = self.execute_sql(&sample_sql, &where_result.params).await?;
+
+        let columns = load_columns(&self.pool, &self.table_name).await?;
+
+        // Plain json has no equality operator, so it cannot be counted distinctly
+        let columns: Vec<(String, String)> = if with_stats {
+            columns.into_iter().filter(|(_, data_type)| data_type != "json").collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut selects = vec!["COUNT(*)".to_string()];
+        for (name, data_type) in &columns {
+            let quoted = format!("\"{}\".\"{}\"", self.table_name, name.replace('"', "\"\""));
+            selects.push(format!("COUNT(DISTINCT {})", quoted));
+            if SAMPLE_ORDERED_TYPES.contains(&data_type.as_str()) {
+                selects.push(format!("MIN({})::text", quoted));
+                selects.push(format!("MAX({})::text", quoted));
+            }
+        }
+
+        let stats_sql = format!("SELECT {} FROM \"{}\" {} {}", selects.join(", "), self.table_name, join_sql, where_sql);
+        let mut stats_query = sqlx::query(&stats_sql);
+        for param in &where_result.params {
+            stats_query = self.bind_param(stats_query, param);
+        }
+        let row = stats_query.fetch_one(&self.pool).await.map_err(DatabaseError::Sqlx)?;
+
+        let total: i64 = row.try_get(0).map_err(DatabaseError::Sqlx)?;
+        let mut stats = serde_json::Map::new();
+        let mut index = 1;
+        for (name, data_type) in &columns {
+            let distinct: i64 = row.try_get(index).map_err(DatabaseError::Sqlx)?;
+            index += 1;
+            let mut column = json!({ "type": data_type, "distinct": distinct });
+            if SAMPLE_ORDERED_TYPES.contains(&data_type.as_str()) {
+                let min: Option<String> = row.try_get(index).map_err(DatabaseError::Sqlx)?;
+                let max: Option<String> = row.try_get(index + 1).map_err(DatabaseError::Sqlx)?;
+                index += 2;
+                column["min"] = json!(min);
+                column["max"] = json!(max);
+            }
+            stats.insert(name.clone(), column);
+        }
+
+        Ok(QuerySample { total, method, records, stats })
+    }
+
+    /// Grouped counts for a filter, e.g. records per day or per numeric bucket
+    pub async fn aggregate(&self, aggregate_data: AggregateData) -> Result<Vec<Record>, DatabaseError> {
+        let mut filter = self.prepare_filter(aggregate_data.where_clause.as_ref()).await?;
+
+        filter.assign_aggregate(aggregate_data)
+            .map_err(DatabaseError::Filter)?;
+
+        let sql_result = filter.to_aggregate_sql()
+            .map_err(DatabaseError::Filter)?;
 
-        let count: i64 = row.try_get(0).map_err(DatabaseError::Sqlx)?;
-        Ok(count)
+        self.execute_sql(&sql_result.query, &sql_result.params).await
     }
 
     pub async fn select_ids(&self, ids: Vec<Uuid>) -> Result<Vec<Record>, DatabaseError> {
@@ -290,8 +647,19 @@ impl Repository {
 
         // Use pipeline's Record-aware method (handles all conversion internally)
         let pipeline = Self::create_pipeline();
-        pipeline.modify(crate::types::Operation::Create, &self.table_name, records, self.pool.clone()).await
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))
+        pipeline.modify(crate::types::Operation::Create, &self.table_name, records, self.pool.clone(), self.transaction.clone(), self.access.clone(), self.actor.clone(), self.write_mode).await
+            .map_err(DatabaseError::Observer)
+    }
+
+    /// Run `records` through the pipeline up to the Database ring without writing them,
+    /// returning the statements `operation` would execute and the changes it would make
+    pub async fn preview(&self, operation: Operation, mut records: Vec<Record>) -> Result<Vec<SqlOperation>, DatabaseError> {
+        for record in &mut records {
+            record.set_operation(operation);
+        }
+        let pipeline = Self::create_pipeline();
+        pipeline.preview(operation, &self.table_name, records, self.pool.clone(), self.access.clone()).await
+            .map_err(DatabaseError::Observer)
     }
 
     // ========================================
@@ -372,8 +740,8 @@ impl Repository {
 
         // Use pipeline's Record-aware method (handles all conversion internally)
         let pipeline = Self::create_pipeline();
-        pipeline.modify(crate::types::Operation::Update, &self.table_name, records, self.pool.clone()).await
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))
+        pipeline.modify(crate::types::Operation::Update, &self.table_name, records, self.pool.clone(), self.transaction.clone(), self.access.clone(), self.actor.clone(), self.write_mode).await
+            .map_err(DatabaseError::Observer)
     }
 
     // REMOVED: update_any(filter, HashMap) - API layer should build Records with changes
@@ -410,8 +778,8 @@ impl Repository {
 
         // Use pipeline's Record-aware method (handles all conversion internally)
         let pipeline = Self::create_pipeline();
-        pipeline.modify(crate::types::Operation::Delete, &self.table_name, records, self.pool.clone()).await
-            .map_err(|e| DatabaseError::QueryError(e.to_string()))
+        pipeline.modify(crate::types::Operation::Delete, &self.table_name, records, self.pool.clone(), self.transaction.clone(), self.access.clone(), self.actor.clone(), self.write_mode).await
+            .map_err(DatabaseError::Observer)
     }
 
     /// Delete record or return 404 - accepts either UUID or FilterData
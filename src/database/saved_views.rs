@@ -0,0 +1,224 @@
+//! Saved list views
+//!
+//! A view is a named list configuration one user keeps for one schema: a where clause, a
+//! sort, the visible columns and a page size, applied by clients when listing records.
+//! Views are rows of the `views` table, owned by the user who saved them and written only
+//! here; the data API may read them but not write them. Root can mark a view as the
+//! default of a schema for access levels ("read", "edit", ...), listed in its
+//! `default_for`; each level has at most one default view per schema.
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::manager::DatabaseError;
+
+const VIEW_COLUMNS: &str = "id, user_id, schema_name, name, filter, sort, columns, page_size, default_for, created_at, updated_at";
+
+const ACTIVE: &str = "trashed_at IS NULL AND deleted_at IS NULL";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SavedView {
+    pub id: Uuid,
+    /// Owner; only they and root change the view
+    pub user_id: Uuid,
+    pub schema_name: String,
+    pub name: String,
+    /// Where clause, as in find requests
+    pub filter: Option<Value>,
+    /// Order, as in find requests, e.g. "created_at desc"
+    pub sort: Option<Value>,
+    /// Visible columns, in display order
+    pub columns: Option<Vec<String>>,
+    pub page_size: Option<i32>,
+    /// Access levels this view is the schema default for
+    pub default_for: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// The settings of a view, as saved
+#[derive(Debug, Clone, Default)]
+pub struct ViewSettings {
+    pub name: String,
+    pub filter: Option<Value>,
+    pub sort: Option<Value>,
+    pub columns: Option<Vec<String>>,
+    pub page_size: Option<i32>,
+}
+
+/// Views a user may use, by schema and name: their own and the defaults of `access`
+pub async fn list(pool: &PgPool, user_id: Uuid, access: &str, schema: Option<&str>) -> Result<Vec<SavedView>, DatabaseError> {
+    let views = sqlx::query_as::<_, SavedView>(&format!(
+        "SELECT {} FROM views WHERE (user_id = $1 OR $2 = ANY(default_for)) \
+         AND ($3::text IS NULL OR schema_name = $3) AND {} ORDER BY schema_name, name",
+        VIEW_COLUMNS, ACTIVE
+    ))
+        .bind(user_id)
+        .bind(access)
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+    Ok(views)
+}
+
+pub async fn get(pool: &PgPool, id: Uuid) -> Result<Option<SavedView>, DatabaseError> {
+    let view = sqlx::query_as::<_, SavedView>(&format!("SELECT {} FROM views WHERE id = $1 AND {}", VIEW_COLUMNS, ACTIVE))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(view)
+}
+
+/// The view a schema lists with for an access level, if root marked one
+pub async fn default_for(pool: &PgPool, schema: &str, access: &str) -> Result<Option<SavedView>, DatabaseError> {
+    let view = sqlx::query_as::<_, SavedView>(&format!(
+        "SELECT {} FROM views WHERE schema_name = $1 AND $2 = ANY(default_for) AND {}",
+        VIEW_COLUMNS, ACTIVE
+    ))
+        .bind(schema)
+        .bind(access)
+        .fetch_optional(pool)
+        .await?;
+    Ok(view)
+}
+
+/// Whether the user already has a view of that name for the schema, other than `except`
+pub async fn name_taken(pool: &PgPool, user_id: Uuid, schema: &str, name: &str, except: Option<Uuid>) -> Result<bool, DatabaseError> {
+    let taken = sqlx::query_scalar(&format!(
+        "SELECT EXISTS (SELECT 1 FROM views WHERE user_id = $1 AND schema_name = $2 AND name = $3 \
+         AND ($4::uuid IS NULL OR id <> $4) AND {})",
+        ACTIVE
+    ))
+        .bind(user_id)
+        .bind(schema)
+        .bind(name)
+        .bind(except)
+        .fetch_one(pool)
+        .await?;
+    Ok(taken)
+}
+
+/// Save a new view owned by `user_id`; its record is readable by them alone through the
+/// data API
+pub async fn create(pool: &PgPool, user_id: Uuid, schema: &str, settings: ViewSettings) -> Result<SavedView, DatabaseError> {
+    let view = sqlx::query_as::<_, SavedView>(&format!(
+        "INSERT INTO views (user_id, schema_name, name, filter, sort, columns, page_size, access_full) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, ARRAY[$1]) RETURNING {}",
+        VIEW_COLUMNS
+    ))
+        .bind(user_id)
+        .bind(schema)
+        .bind(settings.name)
+        .bind(settings.filter)
+        .bind(settings.sort)
+        .bind(settings.columns)
+        .bind(settings.page_size)
+        .fetch_one(pool)
+        .await?;
+    Ok(view)
+}
+
+/// Replace the settings of a view; its owner and defaults are kept
+pub async fn update(pool: &PgPool, id: Uuid, settings: ViewSettings) -> Result<SavedView, DatabaseError> {
+    let view = sqlx::query_as::<_, SavedView>(&format!(
+        "UPDATE views SET name = $2, filter = $3, sort = $4, columns = $5, page_size = $6, updated_at = now() \
+         WHERE id = $1 AND {} RETURNING {}",
+        ACTIVE, VIEW_COLUMNS
+    ))
+        .bind(id)
+        .bind(settings.name)
+        .bind(settings.filter)
+        .bind(settings.sort)
+        .bind(settings.columns)
+        .bind(settings.page_size)
+        .fetch_optional(pool)
+        .await?;
+    view.ok_or_else(|| DatabaseError::NotFound("View".to_string()))
+}
+
+/// Trash a view; returns whether it existed
+pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, DatabaseError> {
+    let result = sqlx::query(&format!("UPDATE views SET trashed_at = now(), updated_at = now() WHERE id = $1 AND {}", ACTIVE))
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Make a view the schema default for exactly the `levels` access levels
+///
+/// Levels it no longer lists lose their default, and the listed ones are taken from the
+/// schema's other views, in one transaction.
+pub async fn set_defaults(pool: &PgPool, id: Uuid, levels: &[String]) -> Result<SavedView, DatabaseError> {
+    let mut tx = pool.begin().await?;
+    let schema: Option<String> = sqlx::query_scalar(&format!("SELECT schema_name FROM views WHERE id = $1 AND {} FOR UPDATE", ACTIVE))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let schema = schema.ok_or_else(|| DatabaseError::NotFound("View".to_string()))?;
+
+    sqlx::query(
+        "UPDATE views SET default_for = ARRAY(SELECT unnest(default_for) EXCEPT SELECT unnest($3::text[])), updated_at = now() \
+         WHERE schema_name = $1 AND id <> $2 AND default_for && $3::text[]"
+    )
+        .bind(&schema)
+        .bind(id)
+        .bind(levels)
+        .execute(&mut *tx)
+        .await?;
+    let view = sqlx::query_as::<_, SavedView>(&format!(
+        "UPDATE views SET default_for = $2, updated_at = now() WHERE id = $1 RETURNING {}",
+        VIEW_COLUMNS
+    ))
+        .bind(id)
+        .bind(levels)
+        .fetch_one(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(view)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The columns of `views` these functions touch, as a temporary table
+    pub(crate) const VIEWS_DDL: &str = "CREATE TEMPORARY TABLE views (\
+        id uuid PRIMARY KEY DEFAULT gen_random_uuid(), user_id uuid NOT NULL, schema_name text NOT NULL, \
+        name text NOT NULL, filter jsonb, sort jsonb, columns text[], page_size integer, \
+        default_for text[] DEFAULT '{}' NOT NULL, access_full uuid[] DEFAULT '{}', \
+        created_at timestamp DEFAULT now() NOT NULL, updated_at timestamp DEFAULT now() NOT NULL, \
+        trashed_at timestamp, deleted_at timestamp)";
+
+    pub(crate) fn settings(name: &str) -> ViewSettings {
+        ViewSettings { name: name.to_string(), ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn test_each_level_has_one_default_per_schema() {
+        let Some(pool) = crate::testing::scratch_pool(VIEWS_DDL).await else { return };
+        let owner = Uuid::new_v4();
+        let first = create(&pool, owner, "accounts", settings("Open")).await.unwrap();
+        let second = create(&pool, owner, "accounts", settings("Closed")).await.unwrap();
+        let other_schema = create(&pool, owner, "contacts", settings("All")).await.unwrap();
+        set_defaults(&pool, other_schema.id, &["read".to_string()]).await.unwrap();
+
+        let levels = |levels: &[&str]| levels.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        set_defaults(&pool, first.id, &levels(&["read", "edit"])).await.unwrap();
+        let second = set_defaults(&pool, second.id, &levels(&["read"])).await.unwrap();
+        assert_eq!(second.default_for, levels(&["read"]));
+
+        // "read" moved to the second view; the first keeps "edit"
+        assert_eq!(default_for(&pool, "accounts", "read").await.unwrap().unwrap().id, second.id);
+        assert_eq!(default_for(&pool, "accounts", "edit").await.unwrap().unwrap().id, first.id);
+        // Defaults of other schemas are untouched
+        assert_eq!(default_for(&pool, "contacts", "read").await.unwrap().unwrap().id, other_schema.id);
+
+        set_defaults(&pool, second.id, &[]).await.unwrap();
+        assert!(default_for(&pool, "accounts", "read").await.unwrap().is_none());
+        assert!(matches!(set_defaults(&pool, Uuid::new_v4(), &[]).await, Err(DatabaseError::NotFound(_))));
+    }
+}
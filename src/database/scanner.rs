@@ -0,0 +1,158 @@
+//! Malware scanning of uploaded attachments
+//!
+//! Uploads are scanned once their bytes are stored and before they become downloadable.
+//! An infected file keeps its bytes and metadata but is marked `quarantined`, which blocks
+//! downloads. [`ClamdScanner`] streams files to a ClamAV daemon with its INSTREAM command,
+//! over TCP (`host:port`) or a Unix socket (an absolute path).
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UnixStream};
+
+/// Bytes sent to clamd per INSTREAM chunk
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    Clean,
+    /// Name of the signature that matched
+    Infected(String),
+}
+
+/// Something that can tell whether a file's bytes are malicious
+#[async_trait]
+pub trait FileScanner: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Read `reader` to the end and judge its contents; Err when no verdict could be reached
+    async fn scan(&self, reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<ScanVerdict, String>;
+}
+
+/// ClamAV daemon reached over TCP or a Unix socket
+pub struct ClamdScanner {
+    address: String,
+    timeout: Duration,
+}
+
+impl ClamdScanner {
+    pub fn new(address: impl Into<String>, timeout: Duration) -> Self {
+        Self { address: address.into(), timeout }
+    }
+}
+
+#[async_trait]
+impl FileScanner for ClamdScanner {
+    fn name(&self) -> &'static str {
+        "clamav"
+    }
+
+    async fn scan(&self, reader: &mut (dyn AsyncRead + Send + Unpin)) -> Result<ScanVerdict, String> {
+        let exchange = async {
+            if self.address.starts_with('/') {
+                instream(UnixStream::connect(&self.address).await?, reader).await
+            } else {
+                instream(TcpStream::connect(&self.address).await?, reader).await
+            }
+        };
+        let reply = tokio::time::timeout(self.timeout, exchange)
+            .await
+            .map_err(|_| format!("clamd at {} did not answer within {:?}", self.address, self.timeout))?
+            .map_err(|e| format!("clamd at {}: {}", self.address, e))?;
+        parse_reply(&reply)
+    }
+}
+
+/// Send the file as length-prefixed chunks ending with an empty one, then read the reply
+async fn instream<S>(mut stream: S, reader: &mut (dyn AsyncRead + Send + Unpin)) -> std::io::Result<String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(b"zINSTREAM\0").await?;
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        stream.write_all(&(n as u32).to_be_bytes()).await?;
+        stream.write_all(&buf[..n]).await?;
+    }
+    stream.write_all(&[0, 0, 0, 0]).await?;
+
+    let mut reply = Vec::new();
+    stream.read_to_end(&mut reply).await?;
+    Ok(String::from_utf8_lossy(&reply).trim_end_matches(['\0', '\n']).to_string())
+}
+
+/// `stream: OK`, `stream: <signature> FOUND`, or a message ending in `ERROR`
+fn parse_reply(reply: &str) -> Result<ScanVerdict, String> {
+    let body = reply.strip_prefix("stream: ").unwrap_or(reply);
+    if body == "OK" {
+        Ok(ScanVerdict::Clean)
+    } else if let Some(signature) = body.strip_suffix(" FOUND") {
+        Ok(ScanVerdict::Infected(signature.to_string()))
+    } else {
+        Err(format!("clamd: {}", reply))
+    }
+}
+
+static SCANNER: Lazy<Option<Box<dyn FileScanner>>> = Lazy::new(|| {
+    let config = &crate::config::config().api;
+    match config.file_scanner.as_str() {
+        "" | "none" => None,
+        "clamav" => Some(Box::new(ClamdScanner::new(
+            &config.file_clamav_address,
+            Duration::from_secs(config.file_scan_timeout_secs),
+        ))),
+        other => {
+            tracing::warn!("Unknown file scanner '{}', uploads are not scanned", other);
+            None
+        }
+    }
+});
+
+/// The configured scanner, or None when uploads are not scanned
+pub fn scanner() -> Option<&'static dyn FileScanner> {
+    SCANNER.as_deref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn streams_chunks_to_clamd_and_reads_the_verdict() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let daemon = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut command = [0u8; 10];
+            socket.read_exact(&mut command).await.unwrap();
+            assert_eq!(&command, b"zINSTREAM\0");
+            let mut received = Vec::new();
+            loop {
+                let length = socket.read_u32().await.unwrap() as usize;
+                if length == 0 {
+                    break;
+                }
+                let mut chunk = vec![0u8; length];
+                socket.read_exact(&mut chunk).await.unwrap();
+                received.extend(chunk);
+            }
+            socket.write_all(b"stream: Eicar-Test-Signature FOUND\0").await.unwrap();
+            received
+        });
+
+        let scanner = ClamdScanner::new(address, Duration::from_secs(5));
+        let verdict = scanner.scan(&mut &b"X5O!P%@AP"[..]).await.unwrap();
+        assert_eq!(verdict, ScanVerdict::Infected("Eicar-Test-Signature".to_string()));
+        assert_eq!(daemon.await.unwrap(), b"X5O!P%@AP");
+
+        assert_eq!(parse_reply("stream: OK"), Ok(ScanVerdict::Clean));
+        assert!(parse_reply("INSTREAM size limit exceeded. ERROR").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+//! Generated full-text search vectors
+//!
+//! String properties declared with `"x-monk-searchable": true` are indexed together in a
+//! `search_vector` tsvector column, generated by PostgreSQL from their values and covered
+//! by a GIN index, for the `$search` where operator (see `FilterSearch`). The Ring 6 DDL
+//! observers rebuild the column when the set of searchable columns changes. A comment on
+//! the column records the text search configuration and columns it was generated from, so
+//! unchanged schemas are left alone.
+
+use serde_json::Value;
+use sqlx::{PgConnection, PgExecutor};
+
+use crate::database::manager::DatabaseError;
+use crate::filter::filter_search::FilterSearch;
+
+/// Property key marking a string property as searchable
+pub const SEARCHABLE_KEY: &str = "x-monk-searchable";
+
+/// Searchable properties of a schema definition, sorted, e.g.
+/// `"title": {"type": "string", "x-monk-searchable": true}`
+pub fn searchable_fields(definition: &Value) -> Vec<String> {
+    let Some(properties) = definition.get("properties").and_then(|p| p.as_object()) else {
+        return Vec::new();
+    };
+    let mut fields: Vec<String> = properties
+        .iter()
+        .filter(|(_, spec)| spec.get(SEARCHABLE_KEY) == Some(&Value::Bool(true)))
+        .filter(|(_, spec)| spec.get("type").and_then(|t| t.as_str()) == Some("string"))
+        // uuid and date-time strings are stored in non-text columns
+        .filter(|(_, spec)| !matches!(spec.get("format").and_then(|f| f.as_str()), Some("uuid" | "date-time")))
+        .map(|(field, _)| field.clone())
+        .collect();
+    fields.sort();
+    fields
+}
+
+/// Searchable columns of a schema from its active column records, sorted; `except` is
+/// left out, for a column being dropped
+pub async fn searchable_columns(executor: impl PgExecutor<'_>, schema_name: &str, except: Option<&str>) -> Result<Vec<String>, DatabaseError> {
+    let columns = sqlx::query_scalar(
+        "SELECT column_name FROM columns WHERE schema_name = $1 AND searchable \
+         AND pg_type IN ('TEXT', 'VARCHAR(255)') AND ($2::text IS NULL OR column_name <> $2) \
+         AND trashed_at IS NULL AND deleted_at IS NULL ORDER BY column_name"
+    )
+        .bind(schema_name)
+        .bind(except)
+        .fetch_all(executor)
+        .await?;
+    Ok(columns)
+}
+
+/// Statements replacing the search vector of `table` with one over `fields`; with no
+/// fields the column and its index are only dropped
+pub fn search_vector_ddl(table: &str, fields: &[String], language: &str) -> Vec<String> {
+    let column = FilterSearch::COLUMN;
+    let mut ddl = vec![format!("ALTER TABLE \"{}\" DROP COLUMN IF EXISTS \"{}\"", table, column)];
+    if fields.is_empty() {
+        return ddl;
+    }
+
+    let document = fields
+        .iter()
+        .map(|field| format!("coalesce(\"{}\", '')", field))
+        .collect::<Vec<_>>()
+        .join(" || ' ' || ");
+    ddl.push(format!(
+        "ALTER TABLE \"{}\" ADD COLUMN \"{}\" tsvector GENERATED ALWAYS AS (to_tsvector('{}'::regconfig, {})) STORED",
+        table, column, language, document
+    ));
+    ddl.push(format!("CREATE INDEX \"{}_search_idx\" ON \"{}\" USING GIN (\"{}\")", table, table, column));
+    ddl.push(format!(
+        "COMMENT ON COLUMN \"{}\".\"{}\" IS '{}'",
+        table, column, generated_from(fields, language)
+    ));
+    ddl
+}
+
+/// Make the search vector of `table` cover exactly `fields`, rebuilding it only when its
+/// columns or text search configuration differ
+pub async fn sync_search_vector(connection: &mut PgConnection, table: &str, fields: &[String]) -> Result<(), DatabaseError> {
+    let language = FilterSearch::language();
+    let current: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT col_description(a.attrelid, a.attnum) FROM pg_attribute a \
+         WHERE a.attrelid = to_regclass(quote_ident($1)) AND a.attname = $2 AND NOT a.attisdropped"
+    )
+        .bind(table)
+        .bind(FilterSearch::COLUMN)
+        .fetch_optional(&mut *connection)
+        .await?;
+
+    let wanted = (!fields.is_empty()).then(|| generated_from(fields, &language));
+    if current.map(|comment| comment.unwrap_or_default()) == wanted {
+        return Ok(());
+    }
+
+    for statement in search_vector_ddl(table, fields, &language) {
+        sqlx::query(&statement).execute(&mut *connection).await?;
+    }
+    tracing::info!("Rebuilt search vector of '{}' over [{}]", table, fields.join(", "));
+    Ok(())
+}
+
+/// Make the search vector of `table` cover the searchable column records of its schema,
+/// leaving out `except`
+pub async fn sync_schema_search(connection: &mut PgConnection, schema_name: &str, table: &str, except: Option<&str>) -> Result<(), DatabaseError> {
+    let fields = searchable_columns(&mut *connection, schema_name, except).await?;
+    sync_search_vector(connection, table, &fields).await
+}
+
+/// Column comment naming what a search vector was generated from, e.g. "english:body,title"
+fn generated_from(fields: &[String], language: &str) -> String {
+    format!("{}:{}", language, fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_searchable_fields_and_vector_ddl() {
+        let definition = json!({
+            "properties": {
+                "title": { "type": "string", "x-monk-searchable": true },
+                "body": { "type": "string", "x-monk-searchable": true },
+                "summary": { "type": "string" },
+                "published_at": { "type": "string", "format": "date-time", "x-monk-searchable": true },
+                "pages": { "type": "integer", "x-monk-searchable": true }
+            }
+        });
+        let fields = searchable_fields(&definition);
+        assert_eq!(fields, vec!["body", "title"]);
+
+        let ddl = search_vector_ddl("articles", &fields, "english");
+        assert_eq!(ddl[0], "ALTER TABLE \"articles\" DROP COLUMN IF EXISTS \"search_vector\"");
+        assert_eq!(
+            ddl[1],
+            "ALTER TABLE \"articles\" ADD COLUMN \"search_vector\" tsvector GENERATED ALWAYS AS \
+             (to_tsvector('english'::regconfig, coalesce(\"body\", '') || ' ' || coalesce(\"title\", ''))) STORED"
+        );
+        assert_eq!(ddl[3], "COMMENT ON COLUMN \"articles\".\"search_vector\" IS 'english:body,title'");
+        assert_eq!(search_vector_ddl("articles", &[], "english").len(), 1);
+    }
+}
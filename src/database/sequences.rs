@@ -0,0 +1,106 @@
+use serde_json::Value;
+use sqlx::PgExecutor;
+
+use crate::database::manager::DatabaseError;
+
+/// A property declared with `x-monk-sequence`, numbered from a Postgres sequence
+///
+/// `"invoice_number": {"type": "string", "x-monk-sequence": {"prefix": "INV-", "padding": 6}}`
+/// yields INV-000001, INV-000002, ... Integer properties store the bare number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceField {
+    pub field: String,
+    pub prefix: String,
+    pub padding: usize,
+    pub start: i64,
+    /// Whether the property is a string, formatted with prefix and padding
+    pub formatted: bool,
+}
+
+impl SequenceField {
+    /// The value stored for sequence number `n`
+    pub fn format(&self, n: i64) -> Value {
+        if self.formatted {
+            Value::String(format!("{}{:0width$}", self.prefix, n, width = self.padding))
+        } else {
+            Value::from(n)
+        }
+    }
+}
+
+/// Sequence properties of a schema definition
+pub fn sequence_fields(definition: &Value) -> Vec<SequenceField> {
+    let properties = match definition.get("properties").and_then(|p| p.as_object()) {
+        Some(properties) => properties,
+        None => return Vec::new(),
+    };
+
+    properties
+        .iter()
+        .filter_map(|(field, spec)| {
+            let sequence = spec.get("x-monk-sequence").filter(|s| s.is_object())?;
+            Some(SequenceField {
+                field: field.clone(),
+                prefix: sequence.get("prefix").and_then(|p| p.as_str()).unwrap_or_default().to_string(),
+                padding: sequence.get("padding").and_then(|p| p.as_u64()).unwrap_or(0) as usize,
+                start: sequence.get("start").and_then(|s| s.as_i64()).unwrap_or(1),
+                formatted: spec.get("type").and_then(|t| t.as_str()) != Some("integer"),
+            })
+        })
+        .collect()
+}
+
+/// Sequence properties of the active schema stored in `table`
+///
+/// Reads the schemas table directly since this runs inside the observer pipeline.
+pub async fn load_sequence_fields(executor: impl PgExecutor<'_>, table: &str) -> Result<Vec<SequenceField>, DatabaseError> {
+    let definition: Option<(Value,)> = sqlx::query_as(
+        "SELECT definition FROM schemas WHERE table_name = $1 AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .bind(table)
+        .fetch_optional(executor)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(definition.map(|(definition,)| sequence_fields(&definition)).unwrap_or_default())
+}
+
+/// Name of the Postgres sequence backing `table.field`
+pub fn sequence_name(table: &str, field: &str) -> String {
+    format!("{}_{}_seq", table, field)
+}
+
+/// DDL creating the sequence, owned by its column so it is dropped with the table, and a
+/// unique index on the column
+pub fn sequence_ddl(table: &str, sequence: &SequenceField) -> [String; 2] {
+    let name = sequence_name(table, &sequence.field);
+    [
+        format!(
+            "CREATE SEQUENCE IF NOT EXISTS \"{}\" START WITH {} MINVALUE {} OWNED BY \"{}\".\"{}\"",
+            name,
+            sequence.start,
+            sequence.start.min(1),
+            table,
+            sequence.field
+        ),
+        format!(
+            "CREATE UNIQUE INDEX IF NOT EXISTS \"{}_{}_sequence_idx\" ON \"{}\" (\"{}\")",
+            table, sequence.field, table, sequence.field
+        ),
+    ]
+}
+
+/// Allocate `count` numbers from the sequence of `table.field`
+///
+/// `nextval` is not transactional: numbers taken by a failed insert are not reused, so the
+/// stored values are unique and increasing but may have gaps.
+pub async fn next_values(executor: impl PgExecutor<'_>, table: &str, field: &str, count: usize) -> Result<Vec<i64>, DatabaseError> {
+    let rows: Vec<(i64,)> = sqlx::query_as("SELECT nextval(quote_ident($1)::regclass) FROM generate_series(1, $2)")
+        .bind(sequence_name(table, field))
+        .bind(count as i32)
+        .fetch_all(executor)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(rows.into_iter().map(|(n,)| n).collect())
+}
@@ -1,4 +1,7 @@
 
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
 use crate::database::manager::{DatabaseError, DatabaseManager};
 use crate::database::models::tenant::Tenant;
 use crate::database::models::user::User;
@@ -19,15 +22,32 @@ pub async fn find_tenant_by_name(tenant_name: &str) -> Result<Option<Tenant>, Da
     Ok(tenant)
 }
 
+/// Tenants in the main database that have not been deleted, trashed ones included, by name
+pub async fn list_tenants() -> Result<Vec<Tenant>, DatabaseError> {
+    let pool = DatabaseManager::main_pool().await?;
+
+    let tenants = sqlx::query_as::<_, Tenant>(
+        "SELECT id, name, database, created_at, updated_at, trashed_at, deleted_at 
+         FROM tenants 
+         WHERE deleted_at IS NULL 
+         ORDER BY name"
+    )
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(tenants)
+}
+
 /// Check if a user exists in the tenant database by auth (username)
 pub async fn find_user_by_auth(tenant_db: &str, user_auth: &str) -> Result<Option<User>, DatabaseError> {
     let pool = DatabaseManager::tenant_pool(tenant_db).await?;
     
     let user = sqlx::query_as::<_, User>(
-        "SELECT id, name, auth, access, access_read, access_edit, access_full, access_deny,
-         created_at, updated_at, trashed_at, deleted_at
-         FROM users 
-         WHERE auth = $1"
+        "SELECT u.id, u.name, u.auth, u.access, u.access_read, u.access_edit, u.access_full, u.access_deny,
+         u.created_at, u.updated_at, u.trashed_at, u.deleted_at, c.password_hash, c.activation_token_hash
+         FROM users u
+         LEFT JOIN user_credentials c ON c.user_id = u.id
+         WHERE u.auth = $1"
     )
     .bind(user_auth)
     .fetch_optional(&pool)
@@ -35,3 +55,143 @@ pub async fn find_user_by_auth(tenant_db: &str, user_auth: &str) -> Result<Optio
     
     Ok(user)
 }
+
+/// A user to create, pending activation
+#[derive(Debug, Clone)]
+pub struct NewUser<'a> {
+    pub name: &'a str,
+    pub auth: &'a str,
+    pub access: &'a str,
+    pub email: Option<&'a str>,
+    /// argon2id PHC string
+    pub password_hash: Option<&'a str>,
+    /// Digest of the emailed activation token
+    pub activation_token_hash: &'a str,
+    pub activation_expires_at: NaiveDateTime,
+}
+
+/// Create a user in the tenant database, pending activation with the given token digest
+///
+/// Returns `Ok(None)` when the auth identifier is already taken.
+pub async fn create_user(tenant_db: &str, user: NewUser<'_>) -> Result<Option<User>, DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(tenant_db).await?;
+
+    let created = sqlx::query_as::<_, User>(
+        "WITH created AS (
+             INSERT INTO users (name, auth, access, email)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (auth) DO NOTHING
+             RETURNING *
+         ), credentials AS (
+             INSERT INTO user_credentials (user_id, password_hash, activation_token_hash, activation_expires_at)
+             SELECT id, $5, $6, $7 FROM created
+         )
+         SELECT id, name, auth, access, access_read, access_edit, access_full, access_deny,
+         created_at, updated_at, trashed_at, deleted_at, $5::text AS password_hash, $6::text AS activation_token_hash
+         FROM created"
+    )
+    .bind(user.name)
+    .bind(user.auth)
+    .bind(user.access)
+    .bind(user.email)
+    .bind(user.password_hash)
+    .bind(user.activation_token_hash)
+    .bind(user.activation_expires_at)
+    .fetch_optional(&pool)
+    .await?;
+
+    Ok(created)
+}
+
+/// Remove an account that was never activated, freeing its username
+///
+/// Used when the activation email of a new registration cannot be sent.
+pub async fn discard_pending_user(tenant_db: &str, user_id: Uuid) -> Result<bool, DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(tenant_db).await?;
+
+    let result = sqlx::query(
+        "DELETE FROM users
+         WHERE id = $1 AND id IN (SELECT user_id FROM user_credentials WHERE activation_token_hash IS NOT NULL)"
+    )
+        .bind(user_id)
+        .execute(&pool)
+        .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+/// A registered account waiting for activation
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PendingActivation {
+    pub id: Uuid,
+    pub auth: String,
+    pub activation_expires_at: NaiveDateTime,
+    pub has_password: bool,
+}
+
+/// Find the account an activation token was issued to, by the token's digest
+pub async fn find_pending_activation(
+    tenant_db: &str,
+    activation_token_hash: &str,
+) -> Result<Option<PendingActivation>, DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(tenant_db).await?;
+
+    let pending = sqlx::query_as::<_, PendingActivation>(
+        "SELECT u.id, u.auth, c.activation_expires_at, c.password_hash IS NOT NULL AS has_password
+         FROM users u
+         JOIN user_credentials c ON c.user_id = u.id
+         WHERE c.activation_token_hash = $1 AND u.trashed_at IS NULL AND u.deleted_at IS NULL"
+    )
+    .bind(activation_token_hash)
+    .fetch_optional(&pool)
+    .await?;
+
+    Ok(pending)
+}
+
+/// Clear a user's activation token, setting the password when one is given
+///
+/// Returns false when the token was already used.
+pub async fn activate_user(
+    tenant_db: &str,
+    user_id: Uuid,
+    activation_token_hash: &str,
+    password_hash: Option<&str>,
+) -> Result<bool, DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(tenant_db).await?;
+
+    let activated: Option<Uuid> = sqlx::query_scalar(
+        "WITH activated AS (
+             UPDATE user_credentials
+             SET activation_token_hash = NULL, activation_expires_at = NULL,
+                 password_hash = COALESCE($3, password_hash)
+             WHERE user_id = $1 AND activation_token_hash = $2
+             RETURNING user_id
+         )
+         UPDATE users SET updated_at = now() WHERE id IN (SELECT user_id FROM activated) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(activation_token_hash)
+    .bind(password_hash)
+    .fetch_optional(&pool)
+    .await?;
+
+    Ok(activated.is_some())
+}
+
+/// Soft delete a user: trashed users can no longer log in and their tokens stop working
+///
+/// Returns false when the user does not exist or was already deleted.
+pub async fn soft_delete_user(tenant_db: &str, user_id: Uuid) -> Result<bool, DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(tenant_db).await?;
+
+    let result = sqlx::query(
+        "UPDATE users SET trashed_at = now(), updated_at = now()
+         WHERE id = $1 AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+    .bind(user_id)
+    .execute(&pool)
+    .await?;
+
+    Ok(result.rows_affected() == 1)
+}
@@ -0,0 +1,105 @@
+use serde_json::Value;
+use sqlx::PgExecutor;
+
+use crate::database::manager::DatabaseError;
+
+/// A property declared with `x-monk-slug`: the slug column and the property it is derived from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlugField {
+    pub field: String,
+    pub from: String,
+}
+
+/// Slug properties of a schema definition, e.g. `"slug": {"type": "string", "x-monk-slug": {"from": "title"}}`
+pub fn slug_fields(definition: &Value) -> Vec<SlugField> {
+    let properties = match definition.get("properties").and_then(|p| p.as_object()) {
+        Some(properties) => properties,
+        None => return Vec::new(),
+    };
+
+    properties
+        .iter()
+        .filter_map(|(field, spec)| {
+            let from = spec.get("x-monk-slug")?.get("from")?.as_str()?;
+            Some(SlugField { field: field.clone(), from: from.to_string() })
+        })
+        .collect()
+}
+
+/// Slug properties of the active schema stored in `table`
+///
+/// Reads the schemas table directly since this runs inside the observer pipeline.
+pub async fn load_slug_fields(executor: impl PgExecutor<'_>, table: &str) -> Result<Vec<SlugField>, DatabaseError> {
+    let definition: Option<(Value,)> = sqlx::query_as(
+        "SELECT definition FROM schemas WHERE table_name = $1 AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .bind(table)
+        .fetch_optional(executor)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(definition.map(|(definition,)| slug_fields(&definition)).unwrap_or_default())
+}
+
+/// Lowercase ASCII letters and digits, with every other run of characters collapsed to one `-`
+///
+/// Latin letters with common diacritics are folded to their base letter first, so
+/// "Crème Brûlée" becomes "creme-brulee".
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match fold_diacritic(c) {
+            Some(c) if c.is_ascii_alphanumeric() => slug.push(c),
+            _ if !slug.is_empty() && !slug.ends_with('-') => slug.push('-'),
+            _ => {}
+        }
+    }
+    while slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+/// Whether `slug` is already in the form `slugify` produces and cannot be mistaken for an id
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty() && slugify(slug) == slug && uuid::Uuid::parse_str(slug).is_err()
+}
+
+/// Unique index enforcing one record per slug in a table
+pub fn slug_index_ddl(table: &str, field: &str) -> String {
+    format!(
+        "CREATE UNIQUE INDEX IF NOT EXISTS \"{}_{}_slug_idx\" ON \"{}\" (\"{}\")",
+        table, field, table, field
+    )
+}
+
+fn fold_diacritic(c: char) -> Option<char> {
+    let folded = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'ç' => 'c',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        c if c.is_ascii() => c,
+        _ => return None,
+    };
+    Some(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Crème Brûlée -- 2024 "), "creme-brulee-2024");
+        assert_eq!(slugify("日本"), "");
+        assert!(is_valid_slug("hello-world"));
+        assert!(!is_valid_slug("Hello-World"));
+        assert!(!is_valid_slug("0191e3f2-7c1a-7000-8000-000000000000"));
+    }
+}
@@ -0,0 +1,177 @@
+//! Tags on records of any schema
+//!
+//! Tags are rows of the `tags` system schema, so they can also be created and described
+//! through the data API, and are attached to records through `record_tags`. Attaching a
+//! name that has no tag yet creates it. Renaming keeps every attachment, since they refer
+//! to the tag's id; merging moves the attachments of the merged tags onto the target and
+//! deletes them, in one transaction. Where clauses select tagged records with `$tagged`.
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::manager::DatabaseError;
+
+/// Longest tag name accepted, as in the tags schema definition
+pub const MAX_TAG_LENGTH: usize = 100;
+
+const TAG_COLUMNS: &str = "t.id, t.name, t.color, t.description, t.created_at, t.updated_at, \
+     (SELECT COUNT(*) FROM record_tags rt WHERE rt.tag_id = t.id) AS records";
+
+const ACTIVE: &str = "t.trashed_at IS NULL AND t.deleted_at IS NULL";
+
+/// A tag and the number of records carrying it
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub color: Option<String>,
+    pub description: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Records of any schema the tag is attached to
+    pub records: i64,
+}
+
+/// Every tag, by name
+pub async fn list(pool: &PgPool) -> Result<Vec<Tag>, DatabaseError> {
+    let tags = sqlx::query_as::<_, Tag>(&format!("SELECT {} FROM tags t WHERE {} ORDER BY t.name", TAG_COLUMNS, ACTIVE))
+        .fetch_all(pool)
+        .await?;
+    Ok(tags)
+}
+
+pub async fn get(pool: &PgPool, name: &str) -> Result<Option<Tag>, DatabaseError> {
+    let tag = sqlx::query_as::<_, Tag>(&format!("SELECT {} FROM tags t WHERE t.name = $1 AND {}", TAG_COLUMNS, ACTIVE))
+        .bind(name)
+        .fetch_optional(pool)
+        .await?;
+    Ok(tag)
+}
+
+/// Tags attached to one record, by name
+pub async fn for_record(pool: &PgPool, schema: &str, record_id: Uuid) -> Result<Vec<Tag>, DatabaseError> {
+    let tags = sqlx::query_as::<_, Tag>(&format!(
+        "SELECT {} FROM tags t JOIN record_tags r ON r.tag_id = t.id \
+         WHERE r.schema_name = $1 AND r.record_id = $2 AND {} ORDER BY t.name",
+        TAG_COLUMNS, ACTIVE
+    ))
+        .bind(schema)
+        .bind(record_id)
+        .fetch_all(pool)
+        .await?;
+    Ok(tags)
+}
+
+/// Attach tags to a record by name, creating tags that do not exist yet; returns the
+/// record's tags. Names already attached are left as they are
+pub async fn attach(pool: &PgPool, schema: &str, record_id: Uuid, names: &[String]) -> Result<Vec<Tag>, DatabaseError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO tags (name) SELECT unnest($1::text[]) ON CONFLICT (name) DO NOTHING")
+        .bind(names)
+        .execute(&mut *tx)
+        .await?;
+
+    // A trashed tag keeps its name, so it cannot be attached until it is restored
+    let tag_ids: Vec<(Uuid, String)> = sqlx::query_as(&format!("SELECT t.id, t.name FROM tags t WHERE t.name = ANY($1) AND {}", ACTIVE))
+        .bind(names)
+        .fetch_all(&mut *tx)
+        .await?;
+    if let Some(trashed) = names.iter().find(|name| !tag_ids.iter().any(|(_, n)| n == *name)) {
+        return Err(DatabaseError::InvalidOperation(format!("Tag '{}' is trashed", trashed)));
+    }
+
+    sqlx::query(
+        "INSERT INTO record_tags (tag_id, schema_name, record_id) SELECT unnest($1::uuid[]), $2, $3 \
+         ON CONFLICT DO NOTHING"
+    )
+        .bind(tag_ids.iter().map(|(id, _)| *id).collect::<Vec<_>>())
+        .bind(schema)
+        .bind(record_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    for_record(pool, schema, record_id).await
+}
+
+/// Remove a tag from a record; returns whether it was attached
+pub async fn detach(pool: &PgPool, schema: &str, record_id: Uuid, name: &str) -> Result<bool, DatabaseError> {
+    let result = sqlx::query(
+        "DELETE FROM record_tags WHERE schema_name = $1 AND record_id = $2 \
+         AND tag_id IN (SELECT id FROM tags WHERE name = $3)"
+    )
+        .bind(schema)
+        .bind(record_id)
+        .bind(name)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Give a tag a new name; its attachments are kept. The caller checks the name is free
+pub async fn rename(pool: &PgPool, name: &str, new_name: &str) -> Result<Tag, DatabaseError> {
+    let id: Option<Uuid> = sqlx::query_scalar(&format!(
+        "UPDATE tags t SET name = $2, updated_at = now() WHERE t.name = $1 AND {} RETURNING t.id",
+        ACTIVE
+    ))
+        .bind(name)
+        .bind(new_name)
+        .fetch_optional(pool)
+        .await?;
+    let id = id.ok_or_else(|| DatabaseError::NotFound(format!("Tag '{}'", name)))?;
+    by_id(pool, id).await
+}
+
+/// Move every attachment of the `names` tags onto `into` and delete them
+///
+/// Records that already carry `into` keep a single attachment. Runs in one transaction,
+/// with the tags locked, so a concurrent attach cannot land on a tag being merged away.
+pub async fn merge(pool: &PgPool, names: &[String], into: &str) -> Result<Tag, DatabaseError> {
+    let mut tx = pool.begin().await?;
+    let target: Option<Uuid> = sqlx::query_scalar(&format!("SELECT t.id FROM tags t WHERE t.name = $1 AND {} FOR UPDATE", ACTIVE))
+        .bind(into)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let target = target.ok_or_else(|| DatabaseError::NotFound(format!("Tag '{}'", into)))?;
+
+    let sources: Vec<(Uuid, String)> = sqlx::query_as(&format!(
+        "SELECT t.id, t.name FROM tags t WHERE t.name = ANY($1) AND t.id <> $2 AND {} FOR UPDATE",
+        ACTIVE
+    ))
+        .bind(names)
+        .bind(target)
+        .fetch_all(&mut *tx)
+        .await?;
+    if let Some(missing) = names.iter().find(|name| *name != into && !sources.iter().any(|(_, n)| n == *name)) {
+        return Err(DatabaseError::NotFound(format!("Tag '{}'", missing)));
+    }
+    let source_ids: Vec<Uuid> = sources.iter().map(|(id, _)| *id).collect();
+
+    sqlx::query(
+        "INSERT INTO record_tags (tag_id, schema_name, record_id) \
+         SELECT $1, schema_name, record_id FROM record_tags WHERE tag_id = ANY($2) \
+         ON CONFLICT DO NOTHING"
+    )
+        .bind(target)
+        .bind(&source_ids)
+        .execute(&mut *tx)
+        .await?;
+    // Their own attachments go with them
+    sqlx::query("DELETE FROM tags WHERE id = ANY($1)")
+        .bind(&source_ids)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    by_id(pool, target).await
+}
+
+async fn by_id(pool: &PgPool, id: Uuid) -> Result<Tag, DatabaseError> {
+    let tag = sqlx::query_as::<_, Tag>(&format!("SELECT {} FROM tags t WHERE t.id = $1", TAG_COLUMNS))
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+    Ok(tag)
+}
@@ -0,0 +1,293 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use sqlx::pool::PoolConnection;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use tokio::sync::{Mutex, OwnedMappedMutexGuard, OwnedMutexGuard};
+
+use crate::database::manager::DatabaseError;
+
+/// A transaction on a tenant pool, shared by the pipelines and observers that run inside it
+///
+/// Cloning shares the transaction. Statements go through [`TenantTransaction::connection`],
+/// which locks it for as long as the returned connection is held, so a caller must drop
+/// the connection before anything else it runs asks for one. If the transaction is dropped
+/// without a commit, sqlx rolls it back when its connection returns to the pool.
+#[derive(Clone)]
+pub struct TenantTransaction {
+    inner: Arc<Mutex<Option<Transaction<'static, Postgres>>>>,
+}
+
+/// The connection of a locked [`TenantTransaction`]
+pub type TransactionConnection = OwnedMappedMutexGuard<Option<Transaction<'static, Postgres>>, PgConnection>;
+
+impl TenantTransaction {
+    /// Open a transaction on `pool`; under a request deadline its statements are bounded
+    /// by the time left
+    pub async fn begin(pool: &PgPool) -> Result<Self, DatabaseError> {
+        let mut transaction = pool.begin().await?;
+        if crate::config::config().api.enable_request_deadlines {
+            crate::deadline::bind_transaction(&mut transaction).await;
+        }
+        Ok(Self { inner: Arc::new(Mutex::new(Some(transaction))) })
+    }
+
+    /// The transaction's connection; fails once it is committed or rolled back
+    pub async fn connection(&self) -> Result<TransactionConnection, sqlx::Error> {
+        OwnedMutexGuard::try_map(self.inner.clone().lock_owned().await, |transaction| {
+            transaction.as_mut().map(|transaction| &mut **transaction)
+        })
+        .map_err(|_| finished())
+    }
+
+    /// Mark a point that [`TenantTransaction::rollback_to`] can return to; `name` must be an identifier
+    pub async fn savepoint(&self, name: &str) -> Result<(), DatabaseError> {
+        self.execute(&format!("SAVEPOINT {}", name)).await
+    }
+
+    /// Keep the work done since the savepoint
+    pub async fn release(&self, name: &str) -> Result<(), DatabaseError> {
+        self.execute(&format!("RELEASE SAVEPOINT {}", name)).await
+    }
+
+    /// Undo the work done since the savepoint, leaving the transaction usable
+    pub async fn rollback_to(&self, name: &str) -> Result<(), DatabaseError> {
+        self.execute(&format!("ROLLBACK TO SAVEPOINT {}", name)).await
+    }
+
+    pub async fn commit(&self) -> Result<(), DatabaseError> {
+        let transaction = self.inner.lock().await.take().ok_or_else(finished)?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn rollback(&self) -> Result<(), DatabaseError> {
+        let transaction = self.inner.lock().await.take().ok_or_else(finished)?;
+        transaction.rollback().await?;
+        Ok(())
+    }
+
+    async fn execute(&self, statement: &str) -> Result<(), DatabaseError> {
+        let mut connection = self.connection().await?;
+        sqlx::query(statement).execute(&mut *connection).await?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for TenantTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TenantTransaction").finish_non_exhaustive()
+    }
+}
+
+fn finished() -> sqlx::Error {
+    sqlx::Error::Io(std::io::Error::new(
+        std::io::ErrorKind::NotConnected,
+        "transaction already committed or rolled back",
+    ))
+}
+
+/// A connection for a run of statements: the enclosing transaction's, or one from the pool
+pub enum TenantConnection {
+    Pool(Box<PoolConnection<Postgres>>),
+    Transaction(TransactionConnection),
+}
+
+impl TenantConnection {
+    /// The connection of `transaction` when there is one, otherwise one acquired from `pool`
+    pub async fn acquire(pool: &PgPool, transaction: Option<&TenantTransaction>) -> Result<Self, sqlx::Error> {
+        match transaction {
+            Some(transaction) => transaction.connection().await.map(TenantConnection::Transaction),
+            None => pool.acquire().await.map(|connection| TenantConnection::Pool(Box::new(connection))),
+        }
+    }
+}
+
+impl Deref for TenantConnection {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        match self {
+            TenantConnection::Pool(connection) => connection,
+            TenantConnection::Transaction(connection) => connection,
+        }
+    }
+}
+
+impl DerefMut for TenantConnection {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        match self {
+            TenantConnection::Pool(connection) => connection,
+            TenantConnection::Transaction(connection) => connection,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use crate::database::record::Record;
+    use crate::observer::implementations::CreateSqlExecutor;
+    use crate::observer::{Observer, ObserverBox, ObserverContext, ObserverError, ObserverPipeline, ObserverRing, Operation, Ring6, Ring7, WriteMode};
+
+    const TABLE: &str = "CREATE TEMPORARY TABLE items (name text NOT NULL)";
+
+    async fn insert(transaction: &TenantTransaction, name: &str) {
+        let mut connection = transaction.connection().await.unwrap();
+        sqlx::query("INSERT INTO items (name) VALUES ($1)").bind(name).execute(&mut *connection).await.unwrap();
+    }
+
+    async fn names(pool: &PgPool) -> Vec<String> {
+        sqlx::query_scalar("SELECT name FROM items ORDER BY name").fetch_all(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_commit_and_savepoints() {
+        let Some(pool) = crate::testing::scratch_pool(TABLE).await else { return };
+        let transaction = TenantTransaction::begin(&pool).await.unwrap();
+        insert(&transaction, "kept").await;
+
+        transaction.savepoint("item").await.unwrap();
+        insert(&transaction, "undone").await;
+        transaction.rollback_to("item").await.unwrap();
+        transaction.savepoint("item").await.unwrap();
+        insert(&transaction, "released").await;
+        transaction.release("item").await.unwrap();
+
+        // Clones share the transaction
+        let mut connection = TenantConnection::acquire(&pool, Some(&transaction.clone())).await.unwrap();
+        let seen: i64 = sqlx::query_scalar("SELECT count(*) FROM items").fetch_one(&mut *connection).await.unwrap();
+        assert_eq!(seen, 2);
+        drop(connection);
+
+        transaction.commit().await.unwrap();
+        assert_eq!(names(&pool).await, vec!["kept", "released"]);
+        assert!(transaction.connection().await.is_err());
+        assert!(transaction.commit().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rollback_and_drop() {
+        let Some(pool) = crate::testing::scratch_pool(TABLE).await else { return };
+        let transaction = TenantTransaction::begin(&pool).await.unwrap();
+        insert(&transaction, "rolled back").await;
+        transaction.rollback().await.unwrap();
+        assert!(names(&pool).await.is_empty());
+
+        let transaction = TenantTransaction::begin(&pool).await.unwrap();
+        insert(&transaction, "dropped").await;
+        drop(transaction);
+        assert!(names(&pool).await.is_empty());
+    }
+
+
+    const PIPELINE_TABLES: &str = "CREATE TEMPORARY TABLE items (id uuid PRIMARY KEY DEFAULT gen_random_uuid(), name text NOT NULL); \
+                                   CREATE TEMPORARY TABLE item_log (name text NOT NULL, in_transaction boolean NOT NULL)";
+    const LOG: &str = "INSERT INTO item_log (name, in_transaction) VALUES ($1, $2)";
+    const BROKEN: &str = "INSERT INTO missing_table (name, in_transaction) VALUES ($1, $2)";
+
+    /// Ring 6 or 7 observer of `items` running `sql` for each record's name
+    struct Statement {
+        ring: ObserverRing,
+        sql: &'static str,
+    }
+
+    impl Statement {
+        fn boxed(ring: ObserverRing, sql: &'static str) -> ObserverBox {
+            match ring {
+                ObserverRing::PostDatabase => ObserverBox::Ring6(Box::new(Statement { ring, sql })),
+                _ => ObserverBox::Ring7(Box::new(Statement { ring, sql })),
+            }
+        }
+
+        async fn run(&self, ctx: &ObserverContext) -> Result<(), ObserverError> {
+            let mut connection = ctx.connection().await.map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+            for record in &ctx.records {
+                sqlx::query(self.sql)
+                    .bind(record.get("name").and_then(|v| v.as_str()))
+                    .bind(ctx.transaction().is_some())
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Observer for Statement {
+        fn name(&self) -> &'static str { "Statement" }
+        fn ring(&self) -> ObserverRing { self.ring }
+        fn applies_to_operation(&self, _op: Operation) -> bool { true }
+        fn applies_to_schema(&self, schema: &str) -> bool { schema == "items" }
+    }
+
+    #[async_trait]
+    impl Ring6 for Statement {
+        async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+            self.run(ctx).await
+        }
+    }
+
+    #[async_trait]
+    impl Ring7 for Statement {
+        async fn execute(&self, ctx: &ObserverContext) -> Result<(), ObserverError> {
+            self.run(ctx).await
+        }
+    }
+
+    fn pipeline(observers: Vec<ObserverBox>) -> ObserverPipeline {
+        let mut pipeline = ObserverPipeline::new();
+        pipeline.register_observer(ObserverBox::Ring5(Box::new(CreateSqlExecutor)));
+        for observer in observers {
+            pipeline.register_observer(observer);
+        }
+        pipeline
+    }
+
+    async fn create_one(pipeline: &ObserverPipeline, pool: &PgPool) -> Result<Vec<Record>, ObserverError> {
+        let record = Record::from_json(json!({ "name": "first" })).unwrap();
+        pipeline.modify(Operation::Create, "items", vec![record], pool.clone(), None, None, None, WriteMode::Atomic).await
+    }
+
+    async fn log(pool: &PgPool) -> Vec<(String, bool)> {
+        sqlx::query_as("SELECT name, in_transaction FROM item_log").fetch_all(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_single_record_commits_with_ring_6() {
+        let Some(pool) = crate::testing::scratch_pool(PIPELINE_TABLES).await else { return };
+        let pipeline = pipeline(vec![Statement::boxed(ObserverRing::PostDatabase, LOG)]);
+
+        create_one(&pipeline, &pool).await.unwrap();
+        assert_eq!(names(&pool).await, vec!["first"]);
+        assert_eq!(log(&pool).await, vec![("first".to_string(), true)]);
+    }
+
+    #[tokio::test]
+    async fn test_ring_6_failure_rolls_back_single_record() {
+        let Some(pool) = crate::testing::scratch_pool(PIPELINE_TABLES).await else { return };
+        let pipeline = pipeline(vec![
+            Statement::boxed(ObserverRing::PostDatabase, LOG),
+            Statement::boxed(ObserverRing::PostDatabase, BROKEN),
+        ]);
+
+        assert!(create_one(&pipeline, &pool).await.is_err());
+        assert!(names(&pool).await.is_empty());
+        assert!(log(&pool).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ring_7_failure_is_undone_alone() {
+        let Some(pool) = crate::testing::scratch_pool(PIPELINE_TABLES).await else { return };
+        let pipeline = pipeline(vec![
+            Statement::boxed(ObserverRing::Audit, BROKEN),
+            Statement::boxed(ObserverRing::Audit, LOG),
+        ]);
+
+        create_one(&pipeline, &pool).await.unwrap();
+        assert_eq!(names(&pool).await, vec!["first"]);
+        assert_eq!(log(&pool).await, vec![("first".to_string(), true)]);
+    }
+}
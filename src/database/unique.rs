@@ -0,0 +1,51 @@
+use serde_json::Value;
+
+/// Column groups of a schema's `x-monk-unique`, e.g. `[["email", "workspace_id"]]`
+pub fn unique_groups(definition: &Value) -> Vec<Vec<String>> {
+    definition
+        .get("x-monk-unique")
+        .and_then(|groups| groups.as_array())
+        .map(|groups| {
+            groups
+                .iter()
+                .filter_map(|group| group.as_array())
+                .map(|group| group.iter().filter_map(|f| f.as_str()).map(str::to_string).collect::<Vec<_>>())
+                .filter(|group| !group.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Unique index over `fields` of `table`, named after its columns
+pub fn unique_index_ddl(table: &str, fields: &[String]) -> String {
+    format!(
+        "CREATE UNIQUE INDEX IF NOT EXISTS \"{}_{}_unique\" ON \"{}\" ({})",
+        table,
+        fields.join("_"),
+        table,
+        fields.iter().map(|f| format!("\"{}\"", f)).collect::<Vec<_>>().join(", ")
+    )
+}
+
+/// Columns named by a unique violation (SQLSTATE 23505), from its "Key (a, b)=(...)" detail
+///
+/// Returns an empty list for a unique violation whose detail cannot be read.
+pub fn violated_fields(error: &sqlx::Error) -> Option<Vec<String>> {
+    let sqlx::Error::Database(e) = error else {
+        return None;
+    };
+    if e.code().as_deref() != Some("23505") {
+        return None;
+    }
+
+    let detail = e
+        .try_downcast_ref::<sqlx::postgres::PgDatabaseError>()
+        .and_then(|pg| pg.detail())
+        .unwrap_or_default();
+    let fields = detail
+        .strip_prefix("Key (")
+        .and_then(|rest| rest.split_once(")="))
+        .map(|(columns, _)| columns.split(", ").map(|c| c.trim_matches('"').to_string()).collect())
+        .unwrap_or_default();
+    Some(fields)
+}
@@ -0,0 +1,269 @@
+//! Checks of record fields against their schema's JSON Schema definition
+//!
+//! Covers what can be told from the definition alone: unknown fields, `required`, `type`,
+//! `enum` and the length and range bounds. `pattern` is matched by Postgres (see
+//! [`pattern_errors`]) so it behaves exactly like the CHECK constraint generated from the
+//! same property; formats are left to the column types.
+//!
+//! Definitions are cached per tenant database for [`DEFINITION_TTL`]. Schema changes made
+//! through this instance drop the tenant's entries at once (see [`forget_definitions`]);
+//! other instances pick them up when their entries expire.
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::database::columns::parse_number;
+use crate::database::manager::DatabaseError;
+use crate::observer::implementations::create_schema_ddl::SYSTEM_FIELDS;
+
+/// One field that does not match the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// How long a loaded definition is used before it is read again
+pub const DEFINITION_TTL: Duration = Duration::from_secs(30);
+
+/// Loaded definitions by (database, table), with when they were loaded
+type DefinitionCache = HashMap<(String, String), (Instant, Option<Value>)>;
+
+static DEFINITIONS: Lazy<RwLock<DefinitionCache>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn database_of(pool: &PgPool) -> String {
+    pool.connect_options().get_database().unwrap_or_default().to_string()
+}
+
+/// Definition of the active schema stored in `table`, from the cache when it is fresh
+pub async fn cached_definition(pool: &PgPool, table: &str) -> Result<Option<Value>, DatabaseError> {
+    let key = (database_of(pool), table.to_string());
+    if let Some((loaded, definition)) = DEFINITIONS.read().unwrap().get(&key) {
+        if loaded.elapsed() < DEFINITION_TTL {
+            return Ok(definition.clone());
+        }
+    }
+
+    let definition = load_definition(pool, table).await?;
+    DEFINITIONS.write().unwrap().insert(key, (Instant::now(), definition.clone()));
+    Ok(definition)
+}
+
+/// Drop the cached definitions of the tenant database `pool` connects to
+pub fn forget_definitions(pool: &PgPool) {
+    let database = database_of(pool);
+    DEFINITIONS.write().unwrap().retain(|(cached, _), _| *cached != database);
+}
+
+/// Definition of the active schema stored in `table`, if there is one
+///
+/// Reads the schemas table directly since this runs inside the observer pipeline.
+pub async fn load_definition(executor: impl PgExecutor<'_>, table: &str) -> Result<Option<Value>, DatabaseError> {
+    let definition: Option<(Value,)> = sqlx::query_as(
+        "SELECT definition FROM schemas WHERE table_name = $1 AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .bind(table)
+        .fetch_optional(executor)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(definition.map(|(definition,)| definition))
+}
+
+/// Check `fields` of one record against `definition`
+///
+/// With `complete` (a create) every required property without a `default` must be present;
+/// otherwise (an update) only the given fields are checked. System columns are not part of
+/// the definition and pass unchecked.
+pub fn validate_fields(definition: &Value, fields: &Map<String, Value>, complete: bool) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let mut error = |field: &str, message: String| errors.push(FieldError { field: field.to_string(), message });
+
+    let empty = Map::new();
+    let properties = definition.get("properties").and_then(|p| p.as_object()).unwrap_or(&empty);
+    let required: Vec<&str> = definition
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| r.iter().filter_map(|f| f.as_str()).collect())
+        .unwrap_or_default();
+
+    for (field, value) in fields {
+        if SYSTEM_FIELDS.contains(&field.as_str()) {
+            continue;
+        }
+        let Some(property) = properties.get(field) else {
+            error(field, "is not defined in the schema".to_string());
+            continue;
+        };
+        if value.is_null() {
+            if required.contains(&field.as_str()) {
+                error(field, "is required".to_string());
+            }
+            continue;
+        }
+        if let Some(message) = property_error(property, value) {
+            error(field, message);
+        }
+    }
+
+    if complete {
+        for field in required {
+            let has_default = properties.get(field).is_some_and(|p| p.get("default").is_some());
+            if !fields.contains_key(field) && !has_default {
+                error(field, "is required".to_string());
+            }
+        }
+    }
+    errors
+}
+
+/// String fields of one record with a `pattern` to match, as (field, value, pattern)
+///
+/// Fields that fail `validate_fields` are left out so each field reports one error.
+pub fn pattern_checks(definition: &Value, fields: &Map<String, Value>, failed: &[FieldError]) -> Vec<(String, String, String)> {
+    let Some(properties) = definition.get("properties").and_then(|p| p.as_object()) else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter(|(field, _)| !failed.iter().any(|e| &e.field == *field))
+        .filter_map(|(field, value)| {
+            let pattern = properties.get(field)?.get("pattern")?.as_str()?;
+            Some((field.clone(), value.as_str()?.to_string(), pattern.to_string()))
+        })
+        .collect()
+}
+
+/// Match `values` against their `patterns` with the Postgres regex engine
+///
+/// Returns the indexes of the values that do not match, in one round trip.
+pub async fn pattern_errors(executor: impl PgExecutor<'_>, values: Vec<String>, patterns: Vec<String>) -> Result<Vec<usize>, DatabaseError> {
+    if values.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mismatches: Vec<(i64,)> = sqlx::query_as(
+        "SELECT v.n FROM UNNEST($1::text[], $2::text[]) WITH ORDINALITY AS v(value, pattern, n) \
+         WHERE v.value !~ v.pattern ORDER BY v.n"
+    )
+        .bind(values)
+        .bind(patterns)
+        .fetch_all(executor)
+        .await
+        .map_err(DatabaseError::Sqlx)?;
+
+    Ok(mismatches.into_iter().map(|(n,)| n as usize - 1).collect())
+}
+
+/// Why `value` does not satisfy `property`, if it does not
+///
+/// Numeric strings pass as numbers since the enrichment ring converts them before the write.
+fn property_error(property: &Value, value: &Value) -> Option<String> {
+    let expected = property.get("type").and_then(|t| t.as_str())?;
+    let number = match (expected, value.as_str()) {
+        ("integer" | "number", Some(text)) => parse_number(text, expected == "integer"),
+        _ => None,
+    };
+    let value = number.as_ref().unwrap_or(value);
+    let matches = match expected {
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        // Unknown types are stored as TEXT
+        _ => true,
+    };
+    if !matches {
+        return Some(format!("must be of type {}", expected));
+    }
+
+    if let Some(allowed) = property.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(|v| v.to_string()).collect();
+            return Some(format!("must be one of {}", allowed.join(", ")));
+        }
+    }
+    if let Some(text) = value.as_str() {
+        let length = text.chars().count() as u64;
+        if let Some(min) = property.get("minLength").and_then(|m| m.as_u64()).filter(|min| length < *min) {
+            return Some(format!("must be at least {} characters", min));
+        }
+        if let Some(max) = property.get("maxLength").and_then(|m| m.as_u64()).filter(|max| length > *max) {
+            return Some(format!("must be at most {} characters", max));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        if let Some(min) = property.get("minimum").and_then(|m| m.as_f64()).filter(|min| number < *min) {
+            return Some(format!("must be at least {}", min));
+        }
+        if let Some(max) = property.get("maximum").and_then(|m| m.as_f64()).filter(|max| number > *max) {
+            return Some(format!("must be at most {}", max));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_fields() {
+        let definition = json!({
+            "properties": {
+                "name": { "type": "string", "maxLength": 5 },
+                "status": { "type": "string", "enum": ["open", "closed"], "default": "open" },
+                "total": { "type": "integer", "minimum": 0 }
+            },
+            "required": ["name", "status"]
+        });
+        let fields = |value: Value| value.as_object().unwrap().clone();
+        let messages = |errors: Vec<FieldError>| errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+
+        assert!(validate_fields(&definition, &fields(json!({ "name": "Ann", "created_at": "now" })), true).is_empty());
+        assert_eq!(
+            messages(validate_fields(&definition, &fields(json!({ "status": "gone", "total": 1.5, "color": "red" })), true)),
+            ["color: is not defined in the schema", "status: must be one of \"open\", \"closed\"", "total: must be of type integer", "name: is required"]
+        );
+        assert_eq!(
+            messages(validate_fields(&definition, &fields(json!({ "name": "Annabel", "total": -1 })), false)),
+            ["name: must be at most 5 characters", "total: must be at least 0"]
+        );
+        assert_eq!(messages(validate_fields(&definition, &fields(json!({ "name": null })), false)), ["name: is required"]);
+        assert_eq!(
+            messages(validate_fields(&definition, &fields(json!({ "total": "7" })), false)),
+            Vec::<String>::new()
+        );
+        assert_eq!(messages(validate_fields(&definition, &fields(json!({ "total": "-7" })), false)), ["total: must be at least 0"]);
+    }
+
+    #[test]
+    fn test_pattern_checks() {
+        let definition = json!({
+            "properties": {
+                "code": { "type": "string", "pattern": "^[A-Z]{3}$" },
+                "sku": { "type": "string", "pattern": "^[0-9]+$" },
+                "name": { "type": "string" }
+            }
+        });
+        let fields = json!({ "code": "abc", "sku": "12", "name": "Ann" });
+        let failed = [FieldError { field: "sku".to_string(), message: "must be at least 3 characters".to_string() }];
+
+        assert_eq!(
+            pattern_checks(&definition, fields.as_object().unwrap(), &failed),
+            [("code".to_string(), "abc".to_string(), "^[A-Z]{3}$".to_string())]
+        );
+    }
+}
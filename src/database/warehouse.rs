@@ -0,0 +1,562 @@
+//! Incremental copy of tenant records into an analytics warehouse
+//!
+//! Changes are captured in the `warehouse_outbox` table by the `monk_warehouse_outbox()`
+//! trigger, which the sync attaches to each user table the first time it copies it,
+//! queueing the existing records as `snapshot` changes in the same transaction. Each pass
+//! drains the outbox of every schema into Parquet files at
+//! `<database>/<schema>/dt=<YYYY-MM-DD>/part-<seq>.parquet`, listed in
+//! `<database>/<schema>/_manifest.json`, in the warehouse bucket (`database.warehouse_s3_*`)
+//! or else the warehouse directory. Analytics tools read the files without querying the
+//! operational database.
+//!
+//! Every file row is the record as it was when the file was written, with the `_seq`,
+//! `_operation` (insert, update, delete or snapshot) and `_changed_at` of its latest
+//! change. Outbox entries are deleted only once their file and the manifest are stored,
+//! so delivery is at least once: readers keep the row with the highest `_seq` per `id`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDateTime};
+use parquet::basic::{Compression, LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::{Type, TypePtr};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::columns::load_columns;
+use crate::database::events::active_tenant_databases;
+use crate::database::identifiers;
+use crate::database::manager::{DatabaseError, DatabaseManager};
+use crate::database::s3::S3Bucket;
+
+/// Outbox entries per warehouse file; entries are deleted after each file
+const FILE_ROWS: i64 = 50_000;
+
+/// Media type of the warehouse files
+const PARQUET_CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+
+/// One file listed in a schema manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestFile {
+    /// Path relative to the schema prefix
+    pub path: String,
+    pub partition: String,
+    pub rows: i64,
+    pub min_seq: i64,
+    pub max_seq: i64,
+    pub min_changed_at: NaiveDateTime,
+    pub max_changed_at: NaiveDateTime,
+    pub written_at: NaiveDateTime,
+}
+
+/// `_manifest.json`: every file written for one schema, oldest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub database: String,
+    pub schema: String,
+    pub format: String,
+    pub files: Vec<ManifestFile>,
+}
+
+/// The latest queued change of a record, with the record as it is now
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub seq: i64,
+    pub operation: String,
+    pub changed_at: NaiveDateTime,
+    /// Column values; only `id` once the record is gone
+    pub record: Map<String, Value>,
+}
+
+/// Where warehouse files are kept
+pub enum WarehouseStore {
+    Bucket(S3Bucket),
+    Directory(PathBuf),
+}
+
+impl WarehouseStore {
+    /// The warehouse bucket when configured, else the warehouse directory
+    pub fn configured() -> Self {
+        match S3Bucket::warehouse() {
+            Some(bucket) => Self::Bucket(bucket),
+            None => Self::Directory(PathBuf::from(&crate::config::config().database.warehouse_directory)),
+        }
+    }
+
+    /// Contents of a stored file, or None when it does not exist
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, String> {
+        match self {
+            Self::Bucket(bucket) => {
+                let response = bucket.get(key).await?;
+                if response.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                if !response.status().is_success() {
+                    return Err(format!("S3 GET answered {}", response.status()));
+                }
+                let body = response.bytes().await.map_err(|e| e.to_string())?;
+                Ok(Some(body.to_vec()))
+            }
+            Self::Directory(root) => {
+                let path = root.join(key);
+                tokio::task::spawn_blocking(move || match std::fs::read(path) {
+                    Ok(content) => Ok(Some(content)),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => Err(e.to_string()),
+                })
+                    .await
+                    .map_err(|e| e.to_string())?
+            }
+        }
+    }
+
+    /// Store a file, replacing any previous one; readers never see a partial file
+    async fn put(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<(), String> {
+        match self {
+            Self::Bucket(bucket) => bucket.put(key, body, content_type).await,
+            Self::Directory(root) => {
+                let path = root.join(key);
+                tokio::task::spawn_blocking(move || write_atomically(&path, &body))
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// Sync every active tenant on the configured interval; runs forever
+pub async fn run_sync() {
+    let interval = crate::config::config().database.warehouse_sync_interval_secs.max(1);
+    let store = WarehouseStore::configured();
+
+    loop {
+        match active_tenant_databases().await {
+            Ok(databases) => {
+                for database in databases {
+                    if let Err(e) = sync_tenant(&store, &database).await {
+                        tracing::warn!("Warehouse sync of {} failed: {}", database, e);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Warehouse sync could not list tenants: {}", e),
+        }
+
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+/// Sync every user schema of one tenant database
+pub async fn sync_tenant(store: &WarehouseStore, database: &str) -> Result<(), DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    let schemas: Vec<(String, String)> = sqlx::query_as(
+        "SELECT name, table_name FROM schemas \
+         WHERE status <> 'system' AND trashed_at IS NULL AND deleted_at IS NULL ORDER BY name"
+    )
+        .fetch_all(&pool)
+        .await?;
+
+    for (schema, table) in schemas {
+        let rows = sync_schema(store, database, &pool, &schema, &table).await?;
+        if rows > 0 {
+            tracing::info!("Warehouse sync copied {} record(s) of {}.{}", rows, database, schema);
+        }
+    }
+    Ok(())
+}
+
+/// Attach the outbox trigger to a table the sync has not copied yet, queueing its records
+/// as snapshot changes; returns whether the table was enrolled now
+///
+/// CREATE TRIGGER holds off writes to the table until the transaction commits, so no
+/// change falls between the snapshot and the trigger.
+async fn enroll(pool: &PgPool, table: &str) -> Result<bool, DatabaseError> {
+    let trigger = identifiers::derived_name(table, "warehouse_outbox");
+    let enrolled: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM pg_trigger WHERE tgrelid = to_regclass(quote_ident($1)) AND tgname = $2)"
+    )
+        .bind(table)
+        .bind(&trigger)
+        .fetch_one(pool)
+        .await?;
+    if enrolled {
+        return Ok(false);
+    }
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!(
+        "CREATE TRIGGER {} AFTER INSERT OR UPDATE OR DELETE ON {} \
+         FOR EACH ROW EXECUTE FUNCTION monk_warehouse_outbox()",
+        quoted(&trigger), quoted(table)
+    ))
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(&format!(
+        "INSERT INTO warehouse_outbox (table_name, record_id, operation) SELECT $1, id, 'snapshot' FROM {} ORDER BY id",
+        quoted(table)
+    ))
+        .bind(table)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Copy the queued changes of one schema; returns the number of records written
+async fn sync_schema(store: &WarehouseStore, database: &str, pool: &PgPool, schema: &str, table: &str) -> Result<i64, DatabaseError> {
+    if enroll(pool, table).await? {
+        tracing::info!("Warehouse sync enrolled {}.{}", database, schema);
+    }
+    let mut copied = 0;
+
+    loop {
+        let entries: Vec<(i64, Uuid, String, NaiveDateTime)> = sqlx::query_as(
+            "SELECT seq, record_id, operation, changed_at FROM warehouse_outbox \
+             WHERE table_name = $1 ORDER BY seq LIMIT $2"
+        )
+            .bind(table)
+            .bind(FILE_ROWS)
+            .fetch_all(pool)
+            .await?;
+        let Some(first) = entries.first() else { break };
+
+        let seqs: Vec<i64> = entries.iter().map(|(seq, ..)| *seq).collect();
+        let min_changed_at = entries.iter().map(|(.., at)| *at).min().unwrap_or(first.3);
+        let max_changed_at = entries.iter().map(|(.., at)| *at).max().unwrap_or(first.3);
+
+        // Named after the first entry so a pass interrupted before its entries are
+        // deleted rewrites the same file instead of adding another
+        let partition = format!("dt={}", first.3.format("%Y-%m-%d"));
+        let path = format!("{}/part-{:020}.parquet", partition, first.0);
+
+        let changes = latest_changes(pool, table, &entries).await?;
+        let rows = changes.len() as i64;
+        let columns = load_columns(pool, table).await?;
+        let body = tokio::task::spawn_blocking(move || encode_parquet(&columns, &changes))
+            .await
+            .map_err(|e| DatabaseError::InvalidOperation(format!("Encoding warehouse file {}: {}", path, e)))?
+            .map_err(|e| DatabaseError::InvalidOperation(format!("Encoding warehouse file {}: {}", path, e)))?;
+
+        store.put(&format!("{}/{}/{}", database, schema, path), body, PARQUET_CONTENT_TYPE)
+            .await
+            .map_err(|e| DatabaseError::InvalidOperation(format!("Storing warehouse file {}: {}", path, e)))?;
+
+        update_manifest(store, database, schema, ManifestFile {
+            path,
+            partition,
+            rows,
+            min_seq: seqs[0],
+            max_seq: seqs[seqs.len() - 1],
+            min_changed_at,
+            max_changed_at,
+            written_at: crate::clock::now().naive_utc(),
+        })
+            .await
+            .map_err(|e| DatabaseError::InvalidOperation(format!("Storing warehouse manifest for {}: {}", schema, e)))?;
+
+        sqlx::query("DELETE FROM warehouse_outbox WHERE seq = ANY($1)")
+            .bind(&seqs)
+            .execute(pool)
+            .await?;
+        sqlx::query(
+            "INSERT INTO warehouse_sync_state (schema_name, rows_synced, last_synced_at) VALUES ($1, $2, now()) \
+             ON CONFLICT (schema_name) DO UPDATE SET rows_synced = warehouse_sync_state.rows_synced + EXCLUDED.rows_synced, \
+             last_synced_at = now()"
+        )
+            .bind(schema)
+            .bind(rows)
+            .execute(pool)
+            .await?;
+        copied += rows;
+
+        if (entries.len() as i64) < FILE_ROWS {
+            break;
+        }
+    }
+
+    Ok(copied)
+}
+
+/// The latest of the outbox entries of each record, in outbox order, with the record's
+/// current values
+async fn latest_changes(pool: &PgPool, table: &str, entries: &[(i64, Uuid, String, NaiveDateTime)]) -> Result<Vec<Change>, DatabaseError> {
+    let mut latest: HashMap<Uuid, &(i64, Uuid, String, NaiveDateTime)> = HashMap::new();
+    for entry in entries {
+        latest.insert(entry.1, entry);
+    }
+    let ids: Vec<Uuid> = latest.keys().copied().collect();
+
+    let records: Vec<(Uuid, Value)> = sqlx::query_as(&format!(
+        "SELECT t.id, row_to_json(t)::jsonb FROM {} t WHERE t.id = ANY($1)",
+        quoted(table)
+    ))
+        .bind(&ids)
+        .fetch_all(pool)
+        .await?;
+    let mut records: HashMap<Uuid, Map<String, Value>> = records
+        .into_iter()
+        .filter_map(|(id, record)| match record {
+            Value::Object(record) => Some((id, record)),
+            _ => None,
+        })
+        .collect();
+
+    let mut changes: Vec<Change> = latest
+        .into_values()
+        .map(|(seq, record_id, operation, changed_at)| Change {
+            seq: *seq,
+            operation: operation.clone(),
+            changed_at: *changed_at,
+            record: records
+                .remove(record_id)
+                .unwrap_or_else(|| Map::from_iter([("id".to_string(), Value::String(record_id.to_string()))])),
+        })
+        .collect();
+    changes.sort_by_key(|change| change.seq);
+    Ok(changes)
+}
+
+async fn update_manifest(store: &WarehouseStore, database: &str, schema: &str, file: ManifestFile) -> Result<(), String> {
+    let key = format!("{}/{}/_manifest.json", database, schema);
+    let mut manifest: Manifest = store
+        .get(&key)
+        .await?
+        .and_then(|content| serde_json::from_slice(&content).ok())
+        .unwrap_or_else(|| Manifest {
+            database: database.to_string(),
+            schema: schema.to_string(),
+            format: "parquet".to_string(),
+            files: Vec::new(),
+        });
+
+    manifest.files.retain(|existing| existing.path != file.path);
+    manifest.files.push(file);
+
+    let body = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    store.put(&key, body, "application/json").await
+}
+
+/// Write then rename so readers never see a partial file
+fn write_atomically(path: &Path, body: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let staging = path.with_extension("tmp");
+    std::fs::write(&staging, body)?;
+    std::fs::File::open(&staging)?.sync_all()?;
+    std::fs::rename(staging, path)
+}
+
+fn quoted(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Parquet column type of a PostgreSQL column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Text,
+    Boolean,
+    Int32,
+    Int64,
+    Double,
+    /// Microseconds since the epoch; timestamptz values are converted to UTC
+    Timestamp,
+}
+
+impl ColumnKind {
+    /// Kind of an information_schema data type; None for columns left out of the files.
+    /// Types without a Parquet counterpart (uuid, jsonb, arrays, ...) are written as text
+    fn of(data_type: &str) -> Option<Self> {
+        Some(match data_type {
+            "tsvector" => return None,
+            "boolean" => Self::Boolean,
+            "smallint" | "integer" => Self::Int32,
+            "bigint" => Self::Int64,
+            "real" | "double precision" | "numeric" => Self::Double,
+            "timestamp without time zone" | "timestamp with time zone" => Self::Timestamp,
+            _ => Self::Text,
+        })
+    }
+
+    fn field(self, name: &str, repetition: Repetition) -> Result<TypePtr, ParquetError> {
+        let (physical, logical) = match self {
+            Self::Text => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            Self::Boolean => (PhysicalType::BOOLEAN, None),
+            Self::Int32 => (PhysicalType::INT32, None),
+            Self::Int64 => (PhysicalType::INT64, None),
+            Self::Double => (PhysicalType::DOUBLE, None),
+            Self::Timestamp => (
+                PhysicalType::INT64,
+                Some(LogicalType::Timestamp { is_adjusted_to_u_t_c: false, unit: TimeUnit::MICROS(Default::default()) }),
+            ),
+        };
+        Ok(Arc::new(
+            Type::primitive_type_builder(name, physical)
+                .with_repetition(repetition)
+                .with_logical_type(logical)
+                .build()?,
+        ))
+    }
+}
+
+/// Encode changes as a Parquet file: the `_seq`, `_operation` and `_changed_at` of each
+/// change followed by the record's `columns`, as (name, data_type) from `load_columns`
+pub fn encode_parquet(columns: &[(String, String)], changes: &[Change]) -> Result<Vec<u8>, ParquetError> {
+    let columns: Vec<(&str, ColumnKind)> = columns
+        .iter()
+        .filter_map(|(name, data_type)| ColumnKind::of(data_type).map(|kind| (name.as_str(), kind)))
+        .collect();
+
+    let mut fields = vec![
+        ColumnKind::Int64.field("_seq", Repetition::REQUIRED)?,
+        ColumnKind::Text.field("_operation", Repetition::REQUIRED)?,
+        ColumnKind::Timestamp.field("_changed_at", Repetition::REQUIRED)?,
+    ];
+    for (name, kind) in &columns {
+        fields.push(kind.field(name, Repetition::OPTIONAL)?);
+    }
+    let schema = Arc::new(Type::group_type_builder("record").with_fields(fields).build()?);
+    let properties = Arc::new(WriterProperties::builder().set_compression(Compression::SNAPPY).build());
+
+    let mut writer = SerializedFileWriter::new(Vec::new(), schema, properties)?;
+    let mut row_group = writer.next_row_group()?;
+    let meta: [(ColumnKind, Vec<Value>); 3] = [
+        (ColumnKind::Int64, changes.iter().map(|c| Value::from(c.seq)).collect()),
+        (ColumnKind::Text, changes.iter().map(|c| Value::from(c.operation.as_str())).collect()),
+        (ColumnKind::Timestamp, changes.iter().map(|c| Value::from(c.changed_at.format("%Y-%m-%dT%H:%M:%S%.f").to_string())).collect()),
+    ];
+    let cells = meta.into_iter().map(|(kind, values)| (kind, values, false)).chain(columns.iter().map(|(name, kind)| {
+        let values = changes.iter().map(|c| c.record.get(*name).cloned().unwrap_or(Value::Null)).collect();
+        (*kind, values, true)
+    }));
+
+    for (kind, values, optional) in cells {
+        let mut column = row_group
+            .next_column()?
+            .ok_or_else(|| ParquetError::General("Parquet schema has fewer columns than written".to_string()))?;
+        write_column(column.untyped(), kind, &values, optional)?;
+        column.close()?;
+    }
+    row_group.close()?;
+    writer.into_inner()
+}
+
+/// Write one column chunk; null and unconvertible values are written as nulls
+fn write_column(writer: &mut ColumnWriter<'_>, kind: ColumnKind, values: &[Value], optional: bool) -> Result<(), ParquetError> {
+    fn write<T>(
+        writer: &mut parquet::column::writer::ColumnWriterImpl<'_, T>,
+        cells: Vec<Option<T::T>>,
+        optional: bool,
+    ) -> Result<(), ParquetError>
+    where
+        T: parquet::data_type::DataType,
+    {
+        let levels: Vec<i16> = cells.iter().map(|cell| cell.is_some() as i16).collect();
+        let cells: Vec<T::T> = cells.into_iter().flatten().collect();
+        writer.write_batch(&cells, optional.then_some(levels.as_slice()), None)?;
+        Ok(())
+    }
+
+    match (kind, writer) {
+        (ColumnKind::Text, ColumnWriter::ByteArrayColumnWriter(w)) => write(w, values.iter().map(|v| text(v).map(|s| ByteArray::from(s.into_bytes()))).collect(), optional),
+        (ColumnKind::Boolean, ColumnWriter::BoolColumnWriter(w)) => write(w, values.iter().map(Value::as_bool).collect(), optional),
+        (ColumnKind::Int32, ColumnWriter::Int32ColumnWriter(w)) => write(w, values.iter().map(|v| v.as_i64().and_then(|n| i32::try_from(n).ok())).collect(), optional),
+        (ColumnKind::Int64, ColumnWriter::Int64ColumnWriter(w)) => write(w, values.iter().map(Value::as_i64).collect(), optional),
+        (ColumnKind::Double, ColumnWriter::DoubleColumnWriter(w)) => write(w, values.iter().map(Value::as_f64).collect(), optional),
+        (ColumnKind::Timestamp, ColumnWriter::Int64ColumnWriter(w)) => write(w, values.iter().map(timestamp_micros).collect(), optional),
+        (kind, _) => Err(ParquetError::General(format!("No {:?} column writer", kind))),
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Microseconds since the epoch of a row_to_json timestamp, e.g. "2024-05-01T12:30:00.25"
+/// or "2024-05-01T12:30:00.25+02:00"
+fn timestamp_micros(value: &Value) -> Option<i64> {
+    let text = value.as_str()?;
+    DateTime::parse_from_rfc3339(text)
+        .map(|at| at.timestamp_micros())
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").map(|at| at.and_utc().timestamp_micros()))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::RowAccessor;
+    use serde_json::json;
+
+    #[test]
+    fn test_encode_parquet_round_trip() {
+        let at = NaiveDateTime::parse_from_str("2024-05-01T12:30:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+        let columns: Vec<(String, String)> = [
+            ("id", "uuid"),
+            ("name", "text"),
+            ("pages", "integer"),
+            ("price", "numeric"),
+            ("active", "boolean"),
+            ("updated_at", "timestamp without time zone"),
+            ("search_vector", "tsvector"),
+        ]
+        .iter()
+        .map(|(name, data_type)| (name.to_string(), data_type.to_string()))
+        .collect();
+        let record = |value: Value| value.as_object().unwrap().clone();
+        let changes = vec![
+            Change {
+                seq: 7,
+                operation: "update".to_string(),
+                changed_at: at,
+                record: record(json!({
+                    "id": "0b6f7c52-4f4e-4d5c-9c1e-5d1f0b1e2a3c",
+                    "name": "Dune",
+                    "pages": 412,
+                    "price": 9.5,
+                    "active": true,
+                    "updated_at": "2024-05-01T12:29:59.5",
+                    "search_vector": "'dune':1"
+                })),
+            },
+            Change {
+                seq: 9,
+                operation: "delete".to_string(),
+                changed_at: at,
+                record: record(json!({ "id": "6a0f1f0e-0c7b-4e57-8d8a-1f2e3d4c5b6a" })),
+            },
+        ];
+
+        let file = encode_parquet(&columns, &changes).unwrap();
+        let reader = SerializedFileReader::new(bytes::Bytes::from(file)).unwrap();
+        let schema = reader.metadata().file_metadata().schema_descr();
+        let names: Vec<&str> = schema.columns().iter().map(|c| c.name()).collect();
+        assert_eq!(names, ["_seq", "_operation", "_changed_at", "id", "name", "pages", "price", "active", "updated_at"]);
+
+        let rows: Vec<_> = reader.get_row_iter(None).unwrap().map(Result::unwrap).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].get_long(0).unwrap(), 7);
+        assert_eq!(rows[0].get_string(4).unwrap(), "Dune");
+        assert_eq!(rows[0].get_int(5).unwrap(), 412);
+        assert_eq!(rows[0].get_double(6).unwrap(), 9.5);
+        assert!(rows[0].get_bool(7).unwrap());
+        assert_eq!(rows[0].get_timestamp_micros(8).unwrap(), at.and_utc().timestamp_micros() - 500_000);
+        assert_eq!(rows[1].get_string(1).unwrap(), "delete");
+        assert_eq!(rows[1].get_string(3).unwrap(), "6a0f1f0e-0c7b-4e57-8d8a-1f2e3d4c5b6a");
+        assert!(rows[1].get_string(4).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! Cache warm-up after boot
+//!
+//! A fresh process starts with empty pools, so the first request per schema pays for
+//! opening connections, preparing statements (sqlx caches them per connection) and
+//! Postgres loading catalog entries for the table. With `database.enable_cache_warmup`
+//! on, [`run_warmup`] does that work ahead of traffic for each tenant's busiest schemas,
+//! ranked by the table activity counters in `pg_stat_user_tables`. Tenants of other
+//! regions are warmed on the local replica when one exists, since that is where their
+//! reads go.
+//!
+//! Warm-up only reads, and failures are logged and skipped: the API serves requests
+//! while it runs and a cold schema is merely slower.
+
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sqlx::PgPool;
+
+use crate::database::manager::{DatabaseError, DatabaseManager};
+use crate::database::regions::{self, DEFAULT_CLUSTER};
+use crate::database::repository::Repository;
+use crate::database::validation;
+use crate::services::describe_service::DescribeService;
+
+/// Progress of the warm-up, reported in `/health`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WarmupStatus {
+    /// disabled | running | done
+    pub state: &'static str,
+    pub tenants_total: usize,
+    pub tenants_warmed: usize,
+    pub schemas_warmed: usize,
+    /// Tenants or schemas that could not be warmed
+    pub failures: usize,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+static STATUS: Lazy<Mutex<WarmupStatus>> = Lazy::new(|| Mutex::new(WarmupStatus { state: "disabled", ..Default::default() }));
+
+pub fn status() -> WarmupStatus {
+    STATUS.lock().unwrap().clone()
+}
+
+fn update(change: impl FnOnce(&mut WarmupStatus)) {
+    change(&mut STATUS.lock().unwrap());
+}
+
+/// Warm every active tenant once, one tenant at a time
+pub async fn run_warmup() {
+    update(|status| {
+        status.state = "running";
+        status.started_at = Some(crate::clock::now());
+    });
+
+    match active_tenants().await {
+        Ok(tenants) => {
+            update(|status| status.tenants_total = tenants.len());
+            for (database, region, cluster) in tenants {
+                match warm_tenant(&database, &region, &cluster).await {
+                    Ok((warmed, failed)) => {
+                        tracing::debug!("Warmed {} schema(s) of {} ({} failed)", warmed, database, failed);
+                        update(|status| {
+                            status.tenants_warmed += 1;
+                            status.schemas_warmed += warmed;
+                            status.failures += failed;
+                        });
+                    }
+                    Err(e) => {
+                        tracing::warn!("Cache warm-up of {} failed: {}", database, e);
+                        update(|status| status.failures += 1);
+                    }
+                }
+            }
+        }
+        Err(e) => tracing::warn!("Cache warm-up could not list tenants: {}", e),
+    }
+
+    update(|status| {
+        status.state = "done";
+        status.finished_at = Some(crate::clock::now());
+    });
+    let status = status();
+    tracing::info!(
+        "Cache warm-up finished: {} of {} tenant(s), {} schema(s), {} failure(s)",
+        status.tenants_warmed, status.tenants_total, status.schemas_warmed, status.failures
+    );
+}
+
+/// Database, region and cluster of each active tenant
+async fn active_tenants() -> Result<Vec<(String, String, String)>, DatabaseError> {
+    let main_pool = DatabaseManager::main_pool().await?;
+    regions::ensure_columns(&main_pool).await?;
+    let tenants: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT database, region, cluster FROM tenants WHERE is_active = true AND trashed_at IS NULL AND deleted_at IS NULL"
+    )
+        .fetch_all(&main_pool)
+        .await?;
+
+    let default_region = &crate::config::config().database.default_region;
+    Ok(tenants
+        .into_iter()
+        .map(|(database, region, cluster)| {
+            (
+                database,
+                region.unwrap_or_else(|| default_region.clone()),
+                cluster.unwrap_or_else(|| DEFAULT_CLUSTER.to_string()),
+            )
+        })
+        .collect())
+}
+
+/// Warm the busiest schemas of one tenant, returning how many were warmed and failed
+async fn warm_tenant(database: &str, region: &str, cluster: &str) -> Result<(usize, usize), DatabaseError> {
+    let replica = if region != regions::local_region() {
+        DatabaseManager::tenant_replica_pool_on(database, cluster).await?
+    } else {
+        None
+    };
+    let pool = match replica {
+        Some(pool) => pool,
+        None => DatabaseManager::tenant_data_pool_on(database, cluster).await?,
+    };
+
+    let config = &crate::config::config().database;
+    let schemas = busiest_schemas(&pool, config.warmup_schemas_per_tenant).await?;
+
+    // Concurrent passes keep several pooled connections busy at once, so the statements
+    // are prepared on each of them rather than only the first one free
+    let passes = (0..config.warmup_connections.max(1)).map(|_| warm_schemas(&pool, &schemas));
+    let results = futures::future::join_all(passes).await;
+
+    let failed = results.into_iter().max().unwrap_or(0);
+    Ok((schemas.len() - failed, failed))
+}
+
+/// Active schemas of a tenant as (name, table), most scanned and written first
+async fn busiest_schemas(pool: &PgPool, limit: u32) -> Result<Vec<(String, String)>, DatabaseError> {
+    let schemas = sqlx::query_as(
+        "SELECT s.name, s.table_name FROM schemas s \
+         JOIN pg_stat_user_tables t ON t.relname = s.table_name AND t.schemaname = current_schema() \
+         WHERE s.trashed_at IS NULL AND s.deleted_at IS NULL \
+         ORDER BY COALESCE(t.seq_scan, 0) + COALESCE(t.idx_scan, 0) + t.n_tup_ins + t.n_tup_upd + t.n_tup_del DESC, s.name \
+         LIMIT $1"
+    )
+        .bind(limit as i64)
+        .fetch_all(pool)
+        .await?;
+    Ok(schemas)
+}
+
+/// Run the lookups a request for each schema makes, returning the number that failed
+async fn warm_schemas(pool: &PgPool, schemas: &[(String, String)]) -> usize {
+    let mut failed = 0;
+    for (name, table) in schemas {
+        let describe = DescribeService::new(pool.clone()).select_one(name).await.map(|_| ()).map_err(|e| e.to_string());
+        let definition = validation::cached_definition(pool, table).await.map(|_| ()).map_err(|e| e.to_string());
+        let records = Repository::new(table.as_str(), pool.clone()).select_all(Some(1), None).await.map(|_| ()).map_err(|e| e.to_string());
+
+        if let Err(e) = describe.and(definition).and(records) {
+            tracing::debug!("Cache warm-up of schema {} failed: {}", name, e);
+            failed += 1;
+        }
+    }
+    failed
+}
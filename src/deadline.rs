@@ -0,0 +1,108 @@
+//! Client deadlines for the work done on behalf of a request
+//!
+//! The deadline middleware runs each request inside [`scope`] when the client sent a
+//! deadline (or `api.max_request_timeout_ms` sets one). Code on the request's task can
+//! then ask how much time is left: the observer pipeline stops between rings once it has
+//! passed, and pools opened with [`with_statement_timeouts`] (and transactions bound with
+//! [`bind_transaction`]) set Postgres' `statement_timeout` to the remaining budget, so
+//! a query the client no longer waits for is cancelled by the server instead of running
+//! to completion.
+//!
+//! The deadline is a task-local and does not follow work spawned onto other tasks, such
+//! as the side effect rings, which are not bound by the request.
+
+use std::future::Future;
+use std::time::Duration;
+
+use sqlx::postgres::{PgConnection, PgPoolOptions};
+use sqlx::Executor;
+use tokio::time::Instant;
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Run `work` with `deadline` as the current deadline
+pub async fn scope<F: Future>(deadline: Instant, work: F) -> F::Output {
+    DEADLINE.scope(deadline, work).await
+}
+
+/// Deadline of the current request, if it has one
+pub fn current() -> Option<Instant> {
+    DEADLINE.try_with(|deadline| *deadline).ok()
+}
+
+/// Time left until the current deadline; zero once it has passed
+pub fn remaining() -> Option<Duration> {
+    current().map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+pub fn expired() -> bool {
+    remaining().is_some_and(|left| left.is_zero())
+}
+
+/// Pool options that bound statements run under a deadline by the time left
+///
+/// A connection handed out under a deadline gets `SET statement_timeout` to the remaining
+/// milliseconds (at least 1, since 0 disables the timeout); acquires without a deadline
+/// are untouched. Connections are reset when they go back to the pool, since the pool
+/// cannot tell which of them were bound.
+pub fn with_statement_timeouts(options: PgPoolOptions) -> PgPoolOptions {
+    bind_statement_timeouts(options).after_release(|connection, _| Box::pin(async move {
+        Ok(connection.execute("RESET statement_timeout").await.is_ok())
+    }))
+}
+
+/// Set the timeout on connections as they are opened and handed out
+fn bind_statement_timeouts(options: PgPoolOptions) -> PgPoolOptions {
+    options
+        .after_connect(|connection, _| Box::pin(async move {
+            apply(connection).await;
+            Ok(())
+        }))
+        .before_acquire(|connection, _| Box::pin(async move {
+            apply(connection).await;
+            Ok(true)
+        }))
+}
+
+/// Bound the statements of the transaction open on `connection` by the time left; the
+/// setting ends with the transaction, so the connection goes back to the pool unbound
+pub async fn bind_transaction(connection: &mut PgConnection) {
+    set_timeout(connection, "SET LOCAL").await
+}
+
+async fn apply(connection: &mut PgConnection) {
+    set_timeout(connection, "SET").await
+}
+
+async fn set_timeout(connection: &mut PgConnection, set: &str) {
+    let Some(left) = remaining() else {
+        return;
+    };
+    let millis = left.as_millis().clamp(1, i32::MAX as u128);
+    if let Err(e) = connection.execute(format!("{} statement_timeout = {}", set, millis).as_str()).await {
+        tracing::warn!("Could not bound statement by the request deadline: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn deadline_is_scoped_to_the_task() {
+        assert_eq!(remaining(), None);
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        scope(deadline, async {
+            assert_eq!(current(), Some(deadline));
+            assert!(!expired());
+            let spawned = tokio::spawn(async { current() }).await.unwrap();
+            assert_eq!(spawned, None);
+        })
+        .await;
+
+        scope(Instant::now(), async { assert!(expired()) }).await;
+    }
+}
@@ -26,6 +26,12 @@ pub enum ApiError {
     // 409 Conflict
     Conflict(String),
     
+    // 410 Gone (retired endpoints and API versions)
+    Gone(String),
+    
+    // 413 Payload Too Large (request body over the configured limit)
+    PayloadTooLarge(String),
+    
     // 422 Unprocessable Entity (validation but semantically valid JSON)
     UnprocessableEntity { 
         message: String, 
@@ -43,6 +49,9 @@ pub enum ApiError {
     
     // 503 Service Unavailable  
     ServiceUnavailable(String),
+
+    // 504 Gateway Timeout (the client's request deadline passed)
+    GatewayTimeout(String),
 }
 
 impl ApiError {
@@ -56,11 +65,14 @@ impl ApiError {
             ApiError::Forbidden(_) => 403,
             ApiError::NotFound(_) => 404,
             ApiError::Conflict(_) => 409,
+            ApiError::Gone(_) => 410,
+            ApiError::PayloadTooLarge(_) => 413,
             ApiError::UnprocessableEntity { .. } => 422,
             ApiError::TooManyRequests(_) => 429,
             ApiError::InternalServerError(_) => 500,
             ApiError::BadGateway(_) => 502,
             ApiError::ServiceUnavailable(_) => 503,
+            ApiError::GatewayTimeout(_) => 504,
         }
     }
     
@@ -74,14 +86,39 @@ impl ApiError {
             ApiError::Forbidden(msg) => msg,
             ApiError::NotFound(msg) => msg,
             ApiError::Conflict(msg) => msg,
+            ApiError::Gone(msg) => msg,
+            ApiError::PayloadTooLarge(msg) => msg,
             ApiError::UnprocessableEntity { message, .. } => message,
             ApiError::TooManyRequests(msg) => msg,
             ApiError::InternalServerError(msg) => msg,
             ApiError::BadGateway(msg) => msg,
             ApiError::ServiceUnavailable(msg) => msg,
+            ApiError::GatewayTimeout(msg) => msg,
         }
     }
     
+    /// The same error with `context` prefixed to its message
+    pub fn with_context(self, context: impl std::fmt::Display) -> Self {
+        let prefix = |msg: String| format!("{}: {}", context, msg);
+        match self {
+            ApiError::BadRequest(msg) => ApiError::BadRequest(prefix(msg)),
+            ApiError::ValidationError { message, field_errors } => ApiError::ValidationError { message: prefix(message), field_errors },
+            ApiError::InvalidJson(msg) => ApiError::InvalidJson(prefix(msg)),
+            ApiError::Unauthorized(msg) => ApiError::Unauthorized(prefix(msg)),
+            ApiError::Forbidden(msg) => ApiError::Forbidden(prefix(msg)),
+            ApiError::NotFound(msg) => ApiError::NotFound(prefix(msg)),
+            ApiError::Conflict(msg) => ApiError::Conflict(prefix(msg)),
+            ApiError::Gone(msg) => ApiError::Gone(prefix(msg)),
+            ApiError::PayloadTooLarge(msg) => ApiError::PayloadTooLarge(prefix(msg)),
+            ApiError::UnprocessableEntity { message, field_errors } => ApiError::UnprocessableEntity { message: prefix(message), field_errors },
+            ApiError::TooManyRequests(msg) => ApiError::TooManyRequests(prefix(msg)),
+            ApiError::InternalServerError(msg) => ApiError::InternalServerError(prefix(msg)),
+            ApiError::BadGateway(msg) => ApiError::BadGateway(prefix(msg)),
+            ApiError::ServiceUnavailable(msg) => ApiError::ServiceUnavailable(prefix(msg)),
+            ApiError::GatewayTimeout(msg) => ApiError::GatewayTimeout(prefix(msg)),
+        }
+    }
+
     /// Convert to JSON response body
     pub fn to_json(&self) -> Value {
         match self {
@@ -126,11 +163,14 @@ impl ApiError {
             ApiError::Forbidden(_) => "FORBIDDEN",
             ApiError::NotFound(_) => "NOT_FOUND",
             ApiError::Conflict(_) => "CONFLICT",
+            ApiError::Gone(_) => "GONE",
+            ApiError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
             ApiError::UnprocessableEntity { .. } => "UNPROCESSABLE_ENTITY",
             ApiError::TooManyRequests(_) => "TOO_MANY_REQUESTS",
             ApiError::InternalServerError(_) => "INTERNAL_SERVER_ERROR",
             ApiError::BadGateway(_) => "BAD_GATEWAY",
             ApiError::ServiceUnavailable(_) => "SERVICE_UNAVAILABLE",
+            ApiError::GatewayTimeout(_) => "DEADLINE_EXCEEDED",
         }
     }
 }
@@ -171,6 +211,14 @@ impl ApiError {
         ApiError::Conflict(message.into())
     }
     
+    pub fn gone(message: impl Into<String>) -> Self {
+        ApiError::Gone(message.into())
+    }
+    
+    pub fn payload_too_large(message: impl Into<String>) -> Self {
+        ApiError::PayloadTooLarge(message.into())
+    }
+    
     pub fn unprocessable_entity(
         message: impl Into<String>, 
         field_errors: HashMap<String, String>
@@ -196,6 +244,10 @@ impl ApiError {
     pub fn service_unavailable(message: impl Into<String>) -> Self {
         ApiError::ServiceUnavailable(message.into())
     }
+
+    pub fn gateway_timeout(message: impl Into<String>) -> Self {
+        ApiError::GatewayTimeout(message.into())
+    }
 }
 
 // Convert other error types to ApiError
@@ -236,6 +288,23 @@ impl From<crate::database::record::RecordError> for ApiError {
     }
 }
 
+impl From<crate::filter::error::FilterError> for ApiError {
+    fn from(err: crate::filter::error::FilterError) -> Self {
+        match err {
+            crate::filter::error::FilterError::UnknownColumn { column, clause } => unknown_column(column, clause),
+            other => ApiError::bad_request(other.to_string()),
+        }
+    }
+}
+
+/// 400 naming the filter column that does not exist, e.g. `field_errors: { "nmae": "Unknown column in where" }`
+fn unknown_column(column: String, clause: &str) -> ApiError {
+    let message = format!("Unknown column '{}' in {}", column, clause);
+    let mut field_errors = HashMap::new();
+    field_errors.insert(column, format!("Unknown column in {}", clause));
+    ApiError::validation_error(message, Some(field_errors))
+}
+
 impl From<crate::database::manager::DatabaseError> for ApiError {
     fn from(err: crate::database::manager::DatabaseError) -> Self {
         match err {
@@ -250,6 +319,10 @@ impl From<crate::database::manager::DatabaseError> for ApiError {
                 tracing::error!("Database query error: {}", msg);
                 ApiError::internal_server_error("An error occurred while processing your request")
             }
+            crate::database::manager::DatabaseError::Sqlx(sqlx_err) if crate::deadline::expired() => {
+                tracing::warn!("Statement cancelled at the request deadline: {}", sqlx_err);
+                ApiError::gateway_timeout("Request deadline exceeded")
+            }
             crate::database::manager::DatabaseError::Sqlx(sqlx_err) => {
                 // Log the real error but return generic message
                 tracing::error!("SQLx error: {}", sqlx_err);
@@ -259,6 +332,12 @@ impl From<crate::database::manager::DatabaseError> for ApiError {
                 tracing::error!("Migration error: {}", msg);
                 ApiError::service_unavailable("Service is being updated, please try again later")
             }
+            crate::database::manager::DatabaseError::Observer(observer_err) => observer_err.into(),
+            crate::database::manager::DatabaseError::Filter(filter_err) => filter_err.into(),
+            crate::database::manager::DatabaseError::UnknownCluster(cluster) => {
+                tracing::error!("Unknown database cluster: {}", cluster);
+                ApiError::internal_server_error("Database error occurred")
+            }
         }
     }
 }
@@ -289,6 +368,35 @@ impl From<crate::services::describe_service::DescribeError> for ApiError {
     }
 }
 
+impl From<crate::services::tenant_service::TenantError> for ApiError {
+    fn from(err: crate::services::tenant_service::TenantError) -> Self {
+        use crate::services::tenant_service::TenantError;
+        match err {
+            TenantError::InvalidName(name) => ApiError::bad_request(format!(
+                "Invalid tenant name '{}': use 3-50 letters, digits, hyphens or underscores",
+                name
+            )),
+            TenantError::AlreadyExists(name) => ApiError::conflict(format!("Tenant '{}' already exists", name)),
+            TenantError::TemplateNotFound(name) => ApiError::not_found(format!("Template '{}' not found", name)),
+            TenantError::NotFound(name) => ApiError::not_found(format!("Tenant '{}' not found", name)),
+            TenantError::InvalidRegion(region) => ApiError::bad_request(format!(
+                "Region '{}' has no database clusters; configured regions: {}",
+                region,
+                crate::database::regions::regions().join(", ")
+            )),
+            TenantError::InvalidCluster { cluster, region } => {
+                ApiError::bad_request(format!("Cluster '{}' is not in region '{}'", cluster, region))
+            }
+            TenantError::NotReplicated { database, cluster } => ApiError::conflict(format!(
+                "Database '{}' does not exist on cluster '{}'; copy it there before moving the tenant",
+                database, cluster
+            )),
+            TenantError::Database(db_err) => ApiError::from(db_err),
+            TenantError::Schema { schema, source } => ApiError::from(source).with_context(format!("schema '{}'", schema)),
+        }
+    }
+}
+
 impl From<crate::observer::error::ObserverError> for ApiError {
     fn from(err: crate::observer::error::ObserverError) -> Self {
         match err {
@@ -298,10 +406,41 @@ impl From<crate::observer::error::ObserverError> for ApiError {
             crate::observer::error::ObserverError::NotFound(msg) => {
                 ApiError::not_found(msg)
             }
+            crate::observer::error::ObserverError::SecurityError(msg) => {
+                ApiError::forbidden(msg)
+            }
+            crate::observer::error::ObserverError::UniqueViolation(fields) if fields.is_empty() => {
+                ApiError::conflict("A record with the same unique values already exists")
+            }
+            crate::observer::error::ObserverError::UniqueViolation(fields) => {
+                ApiError::conflict(format!("A record with the same {} already exists", fields.join(", ")))
+            }
+            crate::observer::error::ObserverError::SchemaViolation { schema, errors } => {
+                let message = format!(
+                    "Record does not match schema '{}': {}",
+                    schema,
+                    errors.iter().map(|(field, message)| format!("{}: {}", field, message)).collect::<Vec<_>>().join("; ")
+                );
+                ApiError::unprocessable_entity(message, errors.into_iter().collect())
+            }
+            crate::observer::error::ObserverError::CheckViolation { field, rule } => {
+                let message = crate::database::checks::violation_message(&field, &rule);
+                let mut field_errors = HashMap::new();
+                field_errors.insert(field, message.clone());
+                ApiError::validation_error(message, Some(field_errors))
+            }
+            crate::observer::error::ObserverError::InvalidFilter(msg) => ApiError::bad_request(msg),
+            crate::observer::error::ObserverError::UnknownColumn { column, clause } => {
+                unknown_column(column, &clause)
+            }
             crate::observer::error::ObserverError::DatabaseError(msg) => {
                 tracing::error!("Observer database error: {}", msg);
                 ApiError::internal_server_error("An error occurred while processing your request")
             }
+            crate::observer::error::ObserverError::DeadlineExceeded(msg) => {
+                tracing::warn!("Request deadline exceeded: {}", msg);
+                ApiError::gateway_timeout("Request deadline exceeded")
+            }
             crate::observer::error::ObserverError::TimeoutError(msg) => {
                 tracing::error!("Observer timeout: {}", msg);
                 ApiError::internal_server_error("Request processing timed out")
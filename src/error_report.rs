@@ -0,0 +1,151 @@
+//! Reporting of panics and 5xx responses to an external [`ErrorReporter`]
+//!
+//! Events carry the request id, tenant and schema so a report can be matched to the
+//! request log and to the response the client saw. Delivery happens off the request path.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::json;
+
+/// What went wrong while serving a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// A handler or middleware panicked; the client got a generic 500
+    Panic,
+    /// A handler returned a 5xx response
+    ServerError,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorEvent {
+    pub request_id: String,
+    pub kind: ErrorKind,
+    pub status: u16,
+    pub message: String,
+    pub method: String,
+    pub path: String,
+    pub tenant: Option<String>,
+    pub schema: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Destination for error events
+#[async_trait]
+pub trait ErrorReporter: Send + Sync {
+    async fn report(&self, event: &ErrorEvent);
+}
+
+/// Writes each event as one error-level log line under the `error_report` target
+pub struct LogReporter;
+
+#[async_trait]
+impl ErrorReporter for LogReporter {
+    async fn report(&self, event: &ErrorEvent) {
+        tracing::error!(
+            target: "error_report",
+            request_id = %event.request_id,
+            kind = ?event.kind,
+            status = event.status,
+            tenant = event.tenant.as_deref().unwrap_or("-"),
+            schema = event.schema.as_deref().unwrap_or("-"),
+            "{} {}: {}", event.method, event.path, event.message
+        );
+    }
+}
+
+/// Sends events to Sentry's store endpoint, as described by a DSN
+pub struct SentryReporter {
+    client: reqwest::Client,
+    store_url: String,
+    auth_header: String,
+}
+
+impl SentryReporter {
+    /// Parse `https://<key>@<host>[:port]/<project>`
+    pub fn from_dsn(dsn: &str) -> Result<Self, String> {
+        let url = url::Url::parse(dsn).map_err(|e| format!("invalid DSN: {}", e))?;
+        let key = url.username();
+        let project = url.path().trim_matches('/');
+        let host = url.host_str().unwrap_or_default();
+        if key.is_empty() || project.is_empty() || host.is_empty() {
+            return Err("DSN must have the form https://<key>@<host>/<project>".to_string());
+        }
+
+        let port = url.port().map(|port| format!(":{}", port)).unwrap_or_default();
+        Ok(Self {
+            client: reqwest::Client::new(),
+            store_url: format!("{}://{}{}/api/{}/store/", url.scheme(), host, port, project),
+            auth_header: format!(
+                "Sentry sentry_version=7, sentry_key={}, sentry_client={}/{}",
+                key,
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            ),
+        })
+    }
+}
+
+#[async_trait]
+impl ErrorReporter for SentryReporter {
+    async fn report(&self, event: &ErrorEvent) {
+        let payload = json!({
+            "event_id": uuid::Uuid::new_v4().simple().to_string(),
+            "timestamp": event.timestamp.to_rfc3339(),
+            "level": if event.kind == ErrorKind::Panic { "fatal" } else { "error" },
+            "platform": "other",
+            "logger": "monk-api",
+            "release": env!("CARGO_PKG_VERSION"),
+            "environment": format!("{:?}", crate::config::config().environment).to_lowercase(),
+            "message": { "formatted": event.message },
+            "request": { "method": event.method, "url": event.path },
+            "tags": {
+                "request_id": event.request_id,
+                "kind": event.kind,
+                "status": event.status,
+                "tenant": event.tenant,
+                "schema": event.schema,
+            },
+        });
+
+        let sent = self
+            .client
+            .post(&self.store_url)
+            .header("X-Sentry-Auth", &self.auth_header)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = sent {
+            tracing::warn!("Failed to report error {} to Sentry: {}", event.request_id, e);
+        }
+    }
+}
+
+static REPORTER: Lazy<Option<Box<dyn ErrorReporter>>> = Lazy::new(|| {
+    let api = &crate::config::config().api;
+    match api.error_reporting_backend.as_str() {
+        "none" => None,
+        "sentry" => match SentryReporter::from_dsn(&api.error_reporting_dsn) {
+            Ok(reporter) => Some(Box::new(reporter)),
+            Err(e) => {
+                tracing::warn!("Sentry error reporting disabled, falling back to log: {}", e);
+                Some(Box::new(LogReporter))
+            }
+        },
+        "log" => Some(Box::new(LogReporter)),
+        other => {
+            tracing::warn!("Unknown error reporting backend '{}', using log", other);
+            Some(Box::new(LogReporter))
+        }
+    }
+});
+
+/// Hand `event` to the configured reporter without waiting for delivery
+pub fn report(event: ErrorEvent) {
+    if let Some(reporter) = REPORTER.as_ref() {
+        tokio::spawn(async move { reporter.report(&event).await });
+    }
+}
@@ -8,9 +8,16 @@ pub enum FilterError {
     #[error("Invalid column name: {0}")]
     InvalidColumn(String),
 
+    /// A well-formed column name the table does not have; `clause` is where it was used
+    #[error("Unknown column '{column}' in {clause}")]
+    UnknownColumn { column: String, clause: &'static str },
+
     #[error("Invalid WHERE clause: {0}")]
     InvalidWhereClause(String),
 
+    #[error("WHERE clause nested too deeply: {0}")]
+    NestingTooDeep(String),
+
     #[error("Unsupported operator: {0}")]
     UnsupportedOperator(String),
 
@@ -23,6 +30,24 @@ pub enum FilterError {
     #[error("Invalid offset: {0}")]
     InvalidOffset(String),
 
+    #[error("Invalid GROUP BY: {0}")]
+    InvalidGroupBy(String),
+
+    #[error("Invalid aggregation: {0}")]
+    InvalidAggregation(String),
+
+    #[error("Invalid HAVING clause: {0}")]
+    InvalidHaving(String),
+
+    #[error("Invalid relationship: {0}")]
+    InvalidRelationship(String),
+
+    #[error("Invalid rank_by: {0}")]
+    InvalidRankBy(String),
+
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
 }
@@ -1,15 +1,35 @@
+use std::collections::HashMap;
+
 use serde_json::Value;
 
 use super::error::FilterError;
+use super::filter_aggregate::FilterAggregate;
+use super::filter_cursor::FilterCursor;
+use super::filter_group::FilterGroup;
+use super::filter_join::FilterJoin;
 use super::filter_order::FilterOrder;
+use super::filter_search::FilterSearch;
+use super::sql_audit;
 use super::filter_where::FilterWhere;
-use super::types::{FilterData, FilterOrderInfo, FilterWhereOptions, SqlResult};
+use super::types::{
+    AggregateData, FilterAggregateInfo, FilterData, FilterGroupInfo, FilterHavingInfo, FilterJoinInfo, FilterOrderInfo,
+    FilterRankBy, FilterRankInfo, FilterRelationship, FilterWhereOptions, RecordAccess, SqlResult,
+};
 
 pub struct Filter {
     table_name: String,
     select_columns: Vec<String>,
     where_data: Option<Value>,
     order_data: Vec<FilterOrderInfo>,
+    group_data: Vec<FilterGroupInfo>,
+    aggregate_data: Vec<FilterAggregateInfo>,
+    having_data: Vec<FilterHavingInfo>,
+    relationships: Vec<FilterRelationship>,
+    rank_data: Option<FilterRankInfo>,
+    known_columns: Vec<String>,
+    column_types: HashMap<String, String>,
+    /// Sort key values of the row a cursor continues after
+    cursor: Option<Vec<Value>>,
     limit: Option<i32>,
     offset: Option<i32>,
     options: FilterWhereOptions,
@@ -19,14 +39,23 @@ impl Filter {
     pub fn new(table_name: impl Into<String>) -> Result<Self, FilterError> {
         let table_name = table_name.into();
         Self::validate_table_name(&table_name)?;
+        let options = FilterWhereOptions { schema_name: Some(table_name.clone()), ..FilterWhereOptions::default() };
         Ok(Self {
             table_name,
             select_columns: vec![],
             where_data: None,
             order_data: vec![],
+            group_data: vec![],
+            aggregate_data: FilterAggregate::default_aggregates(),
+            having_data: vec![],
+            relationships: vec![],
+            rank_data: None,
+            known_columns: vec![],
+            column_types: HashMap::new(),
+            cursor: None,
             limit: None,
             offset: None,
-            options: FilterWhereOptions::default(),
+            options,
         })
     }
 
@@ -35,27 +64,158 @@ impl Filter {
         if let Some(where_clause) = data.where_clause { self.where_clause(where_clause)?; }
         if let Some(order) = data.order { self.order(order)?; }
         if let Some(limit) = data.limit { self.limit(limit, data.offset)?; }
+        if let Some(rank_by) = data.rank_by { self.rank(rank_by, data.top_n_per_group.unwrap_or(1))?; }
+        if let Some(cursor) = data.cursor { self.cursor(&cursor)?; }
         Ok(self)
     }
 
-    pub fn select(&mut self, columns: Vec<String>) -> Result<&mut Self, FilterError> {
+    pub fn assign_aggregate(&mut self, data: AggregateData) -> Result<&mut Self, FilterError> {
+        if let Some(where_clause) = data.where_clause { self.where_clause(where_clause)?; }
+        if let Some(group_by) = data.group_by { self.group(group_by)?; }
+        if let Some(aggregations) = data.aggregations { self.aggregations(aggregations)?; }
+        if let Some(having) = data.having { self.having(having)?; }
+        if let Some(limit) = data.limit { self.limit(limit, None)?; }
+        Ok(self)
+    }
+
+    /// Fetch only these columns; `id` is always included so records can still be
+    /// addressed, and an empty list or `*` selects every column
+    pub fn select(&mut self, mut columns: Vec<String>) -> Result<&mut Self, FilterError> {
         Self::validate_select_columns(&columns)?;
+        self.check_known_columns(columns.iter().map(String::as_str).filter(|c| *c != "*"), "select")?;
+        let has_id = self.known_columns.is_empty() || self.known_columns.iter().any(|c| c == "id");
+        if has_id && !columns.is_empty() && !columns.iter().any(|c| c == "*" || c == "id") {
+            columns.insert(0, "id".to_string());
+        }
+        let mut seen = std::collections::HashSet::new();
+        columns.retain(|c| seen.insert(c.clone()));
         self.select_columns = columns;
         Ok(self)
     }
 
     pub fn where_clause(&mut self, conditions: Value) -> Result<&mut Self, FilterError> {
         FilterWhere::validate(&conditions)?;
+        let columns = FilterWhere::columns(&conditions, &self.options.json_columns);
+        self.check_known_columns(columns.iter().map(String::as_str), "where")?;
+        let searchable = self.known_columns.is_empty() || self.known_columns.iter().any(|c| c == FilterSearch::COLUMN);
+        if !searchable && FilterSearch::used(&conditions) {
+            return Err(FilterError::InvalidOperatorData(format!(
+                "$search requires a schema with searchable columns; '{}' has none", self.table_name
+            )));
+        }
         self.where_data = Some(conditions);
         Ok(self)
     }
 
     pub fn order(&mut self, order_spec: Value) -> Result<&mut Self, FilterError> {
         let order_info = FilterOrder::validate_and_parse(&order_spec)?;
+        let columns: Vec<String> = order_info.iter().map(|o| o.column.clone()).collect();
+        Self::validate_select_columns(&columns)?;
+        self.check_known_columns(columns.iter().map(String::as_str), "order")?;
         self.order_data = order_info;
         Ok(self)
     }
 
+    pub fn group(&mut self, group_spec: Value) -> Result<&mut Self, FilterError> {
+        let group_info = FilterGroup::validate_and_parse(&group_spec)?;
+        if let Some(g) = group_info.iter().find(|g| self.aggregate_data.iter().any(|a| a.alias == g.alias)) {
+            return Err(FilterError::InvalidGroupBy(format!("Group alias '{}' is used by an aggregation", g.alias)));
+        }
+        self.check_known_columns(group_info.iter().map(|g| g.expr.column()), "group_by")?;
+        self.group_data = group_info;
+        Ok(self)
+    }
+
+    /// Aggregate functions computed per group, see `FilterAggregate`; replaces the default count
+    pub fn aggregations(&mut self, aggregations: Value) -> Result<&mut Self, FilterError> {
+        let aggregate_info = FilterAggregate::validate_and_parse(&aggregations)?;
+        if let Some(a) = aggregate_info.iter().find(|a| self.group_data.iter().any(|g| g.alias == a.alias)) {
+            return Err(FilterError::InvalidAggregation(format!("Alias '{}' is used by a group", a.alias)));
+        }
+        self.check_known_columns(aggregate_info.iter().filter_map(|a| a.column.as_deref()), "aggregations")?;
+        self.aggregate_data = aggregate_info;
+        Ok(self)
+    }
+
+    /// Conditions on aggregate results; set after `aggregations`
+    pub fn having(&mut self, having: Value) -> Result<&mut Self, FilterError> {
+        self.having_data = FilterAggregate::parse_having(&having, &self.aggregate_data)?;
+        Ok(self)
+    }
+
+    /// Keep the first `top_n` rows of each partition, ranked by the window order
+    ///
+    /// Partition and order columns are checked against `columns()` when known.
+    pub fn rank(&mut self, rank_by: FilterRankBy, top_n: i32) -> Result<&mut Self, FilterError> {
+        if rank_by.partition.is_empty() {
+            return Err(FilterError::InvalidRankBy("partition requires at least one column".to_string()));
+        }
+        if top_n < 1 {
+            return Err(FilterError::InvalidRankBy("top_n_per_group must be at least 1".to_string()));
+        }
+        let order = match rank_by.order {
+            Some(ref order) => FilterOrder::validate_and_parse(order)?,
+            None => vec![],
+        };
+        if order.is_empty() {
+            return Err(FilterError::InvalidRankBy("order is required to rank rows within a partition".to_string()));
+        }
+
+        let columns: Vec<String> = rank_by.partition.iter().cloned().chain(order.iter().map(|o| o.column.clone())).collect();
+        Self::validate_select_columns(&columns)?;
+        self.check_known_columns(columns.iter().map(String::as_str), "rank_by")?;
+
+        self.rank_data = Some(FilterRankInfo { partition: rank_by.partition, order, top_n });
+        Ok(self)
+    }
+
+    /// Columns of the table; select, where, order, group_by and rank_by references to
+    /// any other column are rejected. Set before `assign`
+    pub fn columns(&mut self, columns: Vec<String>) -> &mut Self {
+        self.known_columns = columns;
+        self
+    }
+
+    /// Data types of the table's columns as (name, type) from `load_columns`; cursor values
+    /// are cast to them
+    pub fn column_types(&mut self, columns: &[(String, String)]) -> &mut Self {
+        self.column_types = columns.iter().cloned().collect();
+        self
+    }
+
+    /// Continue after the row a `FilterCursor` token names; set after `order`, whose sort
+    /// keys the cursor must have been issued for. Any offset counts from the cursor
+    pub fn cursor(&mut self, cursor: &str) -> Result<&mut Self, FilterError> {
+        self.cursor = Some(FilterCursor::decode(cursor, &self.order_data)?);
+        Ok(self)
+    }
+
+    /// Restrict rows to those the given ids may read, see `RecordAccess`
+    pub fn access(&mut self, access: RecordAccess) -> &mut Self {
+        self.options.access = Some(access);
+        self
+    }
+
+    /// Match `$ilike` patterns with accents stripped from both sides; the database must
+    /// have the unaccent extension
+    pub fn unaccent(&mut self, enabled: bool) -> &mut Self {
+        self.options.unaccent = enabled;
+        self
+    }
+
+    /// JSONB columns of the table, so dotted where-clause keys such as "profile.address.city"
+    /// query inside them instead of joining a relationship; set before `to_sql`
+    pub fn json_columns(&mut self, columns: Vec<String>) -> &mut Self {
+        self.options.json_columns = columns;
+        self
+    }
+
+    /// Declared relationships that dotted where-clause paths may join through
+    pub fn relationships(&mut self, relationships: Vec<FilterRelationship>) -> &mut Self {
+        self.relationships = relationships;
+        self
+    }
+
     pub fn limit(&mut self, limit: i32, offset: Option<i32>) -> Result<&mut Self, FilterError> {
         if limit < 0 { return Err(FilterError::InvalidLimit("Limit must be non-negative".to_string())); }
         if let Some(off) = offset { if off < 0 { return Err(FilterError::InvalidOffset("Offset must be non-negative".to_string())); } }
@@ -77,43 +237,228 @@ impl Filter {
     }
 
     pub fn to_sql(&self) -> Result<SqlResult, FilterError> {
-        let select_clause = self.build_select_clause();
-        let (where_clause, params) = if let Some(ref where_data) = self.where_data {
-            FilterWhere::generate(where_data, 0, &self.options)?
-        } else {
-            FilterWhere::generate_empty(&self.options)
-        };
-        let order_clause = FilterOrder::generate(&self.order_data)?;
+        let joins = self.resolve_joins()?;
+        let table_alias = self.table_alias(&joins);
+        let select_clause = self.build_select_clause(table_alias);
+        let mut where_result = self.where_sql(table_alias)?;
+        let join_clause = self.join_sql(&joins, &where_result);
+        let order = self.page_order();
+        let mut order_clause = FilterOrder::generate_qualified(&order, table_alias)?;
         let limit_clause = self.build_limit_clause();
 
+        if let Some(ref rank) = self.rank_data {
+            // Rank inside a subquery aliased back to the table name, so outer
+            // references stay "table"."column"
+            let table = Some(self.table_name.as_str());
+            let mut inner = self.ranked_sql(rank, &joins, table)?;
+            let mut conditions = format!("\"{}\".\"_rank\" <= {}", self.table_name, rank.top_n);
+            if let Some((after, params)) = self.cursor_sql(table, inner.params.len()) {
+                conditions = format!("{} AND {}", conditions, after);
+                inner.params.extend(params);
+            }
+            let query = [
+                format!("SELECT {}", self.build_select_clause(table)),
+                format!("FROM ({}) AS \"{}\"", inner.query, self.table_name),
+                format!("WHERE {}", conditions),
+                FilterOrder::generate_qualified(&order, table)?,
+                limit_clause,
+            ].into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ");
+            return Ok(self.audited(SqlResult { query, params: inner.params }));
+        }
+
+        if let Some((after, params)) = self.cursor_sql(table_alias, where_result.params.len()) {
+            where_result.query = if where_result.query.is_empty() { after } else { format!("{} AND {}", where_result.query, after) };
+            where_result.params.extend(params);
+        }
+
+        if let Some(text) = self.search_ranking() {
+            where_result.params.push(Value::String(text.to_string()));
+            let param = format!("${}", where_result.params.len());
+            let prefix = table_alias.map(|t| format!("\"{}\".", t)).unwrap_or_default();
+            order_clause = format!("ORDER BY {}", FilterSearch::rank_order(&prefix, &param));
+        }
+
         let query = [
             format!("SELECT {}", select_clause),
             format!("FROM \"{}\"", self.table_name),
-            if where_clause.is_empty() { String::new() } else { format!("WHERE {}", where_clause) },
+            join_clause,
+            if where_result.query.is_empty() { String::new() } else { format!("WHERE {}", where_result.query) },
             order_clause,
             limit_clause,
         ].into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ");
 
-        Ok(SqlResult { query, params })
+        Ok(self.audited(SqlResult { query, params: where_result.params }))
     }
 
-    pub fn to_where_sql(&self) -> Result<SqlResult, FilterError> {
-        let (where_clause, params) = if let Some(ref where_data) = self.where_data {
-            FilterWhere::generate(where_data, 0, &self.options)?
+    /// Sort keys for ORDER BY; pages (a limit or cursor) without an order are sorted by `id`
+    /// so that they do not overlap
+    fn page_order(&self) -> Vec<FilterOrderInfo> {
+        let paged = self.limit.is_some() || self.cursor.is_some();
+        let has_id = self.known_columns.is_empty() || self.known_columns.iter().any(|c| c == "id");
+        if self.order_data.is_empty() && paged && has_id {
+            FilterOrder::with_tiebreaker(&[])
         } else {
-            FilterWhere::generate_empty(&self.options)
-        };
-        Ok(SqlResult { query: where_clause, params })
+            self.order_data.clone()
+        }
+    }
+
+    /// Search text to rank results by: a top-level `$search` in a find without its own
+    /// order or cursor, whose keys a relevance order could not be continued from
+    fn search_ranking(&self) -> Option<&str> {
+        if !self.order_data.is_empty() || self.cursor.is_some() {
+            return None;
+        }
+        FilterSearch::top_level(self.where_data.as_ref()?).filter(|text| !text.trim().is_empty())
+    }
+
+    /// Keyset condition for the rows after the cursor, numbered after `param_offset` params
+    fn cursor_sql(&self, table_alias: Option<&str>, param_offset: usize) -> Option<(String, Vec<Value>)> {
+        let values = self.cursor.as_ref()?;
+        Some(FilterCursor::generate(&self.order_data, values, &self.column_types, table_alias, param_offset))
+    }
+
+    /// Filtered rows plus a "_rank" column numbering them within each partition
+    fn ranked_sql(&self, rank: &FilterRankInfo, joins: &[FilterJoinInfo], table_alias: Option<&str>) -> Result<SqlResult, FilterError> {
+        let where_result = self.where_sql(if joins.is_empty() { None } else { table_alias })?;
+        let partition = rank
+            .partition
+            .iter()
+            .map(|c| format!("\"{}\".\"{}\"", self.table_name, c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let query = [
+            format!(
+                "SELECT \"{}\".*, ROW_NUMBER() OVER (PARTITION BY {} ORDER BY {}) AS \"_rank\"",
+                self.table_name,
+                partition,
+                FilterOrder::columns_sql(&rank.order, table_alias),
+            ),
+            format!("FROM \"{}\"", self.table_name),
+            self.join_sql(joins, &where_result),
+            if where_result.query.is_empty() { String::new() } else { format!("WHERE {}", where_result.query) },
+        ].into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ");
+        Ok(SqlResult { query, params: where_result.params })
+    }
+
+    /// WHERE conditions only; when the filter joins related schemas, columns are qualified
+    /// and the caller must add `to_join_sql()` after the table in its FROM clause
+    pub fn to_where_sql(&self) -> Result<SqlResult, FilterError> {
+        let joins = self.resolve_joins()?;
+        Ok(self.audited(self.where_sql(self.table_alias(&joins))?))
+    }
+
+    /// JOIN clauses needed by relationship paths in the where clause (empty when none)
+    pub fn to_join_sql(&self) -> Result<String, FilterError> {
+        let joins = self.resolve_joins()?;
+        let where_result = self.where_sql(self.table_alias(&joins))?;
+        Ok(self.join_sql(&joins, &where_result))
+    }
+
+    /// JOIN clauses holding related rows to the caller's access; they reuse the access
+    /// parameter, which is always the last one `where_sql` binds
+    fn join_sql(&self, joins: &[FilterJoinInfo], where_result: &SqlResult) -> String {
+        let access_param = self.options.access.as_ref().map(|_| format!("${}", where_result.params.len()));
+        FilterJoin::generate(joins, access_param.as_deref())
     }
 
     pub fn to_count_sql(&self) -> Result<SqlResult, FilterError> {
-        let where_result = self.to_where_sql()?;
+        let joins = self.resolve_joins()?;
+        if let Some(ref rank) = self.rank_data {
+            let inner = self.ranked_sql(rank, &joins, Some(self.table_name.as_str()))?;
+            let query = format!(
+                "SELECT COUNT(*) as count FROM ({}) AS \"{}\" WHERE \"_rank\" <= {}",
+                inner.query, self.table_name, rank.top_n
+            );
+            return Ok(self.audited(SqlResult { query, params: inner.params }));
+        }
+        let where_result = self.where_sql(self.table_alias(&joins))?;
+        let from = [format!("\"{}\"", self.table_name), self.join_sql(&joins, &where_result)]
+            .into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ");
         let query = if where_result.query.is_empty() {
-            format!("SELECT COUNT(*) as count FROM \"{}\"", self.table_name)
+            format!("SELECT COUNT(*) as count FROM {}", from)
         } else {
-            format!("SELECT COUNT(*) as count FROM \"{}\" WHERE {}", self.table_name, where_result.query)
+            format!("SELECT COUNT(*) as count FROM {} WHERE {}", from, where_result.query)
         };
-        Ok(SqlResult { query, params: where_result.params })
+        Ok(self.audited(SqlResult { query, params: where_result.params }))
+    }
+
+    /// Grouped aggregates: one row per group with its keys and one column per aggregation
+    /// ("count" by default), ordered by group keys
+    pub fn to_aggregate_sql(&self) -> Result<SqlResult, FilterError> {
+        let joins = self.resolve_joins()?;
+        let table_alias = self.table_alias(&joins);
+        let mut where_result = self.where_sql(table_alias)?;
+        let join_clause = self.join_sql(&joins, &where_result);
+        let (mut selects, group_clause) = FilterGroup::generate(&self.group_data, table_alias);
+        selects.extend(FilterAggregate::generate(&self.aggregate_data, table_alias));
+        let (having_clause, having_params) =
+            FilterAggregate::generate_having(&self.having_data, &self.aggregate_data, table_alias, where_result.params.len());
+        where_result.params.extend(having_params);
+
+        let order_clause = if self.group_data.is_empty() {
+            String::new()
+        } else {
+            format!("ORDER BY {}", (1..=self.group_data.len()).map(|n| n.to_string()).collect::<Vec<_>>().join(", "))
+        };
+
+        let query = [
+            format!("SELECT {}", selects.join(", ")),
+            format!("FROM \"{}\"", self.table_name),
+            join_clause,
+            if where_result.query.is_empty() { String::new() } else { format!("WHERE {}", where_result.query) },
+            group_clause,
+            having_clause,
+            order_clause,
+            self.build_limit_clause(),
+        ].into_iter().filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ");
+
+        Ok(self.audited(SqlResult { query, params: where_result.params }))
+    }
+
+    /// Run generated SQL past the injection audit, unless the filter carries raw SQL
+    /// predicates, which are unparameterized by design
+    fn audited(&self, result: SqlResult) -> SqlResult {
+        let raw = self.where_data.as_ref().map(|w| Self::has_raw_predicate(w, true)).unwrap_or(false);
+        if !raw {
+            sql_audit::audit(&result.query, result.params.len());
+        }
+        result
+    }
+
+    /// Raw SQL strings appear as the whole where clause or as $and/$or/$not members
+    fn has_raw_predicate(where_data: &Value, clause_position: bool) -> bool {
+        match where_data {
+            Value::String(_) => clause_position,
+            Value::Array(arr) => arr.iter().any(|v| Self::has_raw_predicate(v, clause_position)),
+            Value::Object(obj) => obj.iter().any(|(key, value)| {
+                matches!(key.as_str(), "$and" | "$or" | "$not") && Self::has_raw_predicate(value, true)
+            }),
+            _ => false,
+        }
+    }
+
+    fn resolve_joins(&self) -> Result<Vec<FilterJoinInfo>, FilterError> {
+        let paths = match self.where_data {
+            Some(ref where_data) => FilterJoin::paths(where_data, &self.options.json_columns),
+            None => return Ok(vec![]),
+        };
+        if paths.is_empty() { return Ok(vec![]); }
+        FilterJoin::resolve(&self.table_name, &paths, &self.relationships, crate::config::CONFIG.filter.max_join_depth)
+    }
+
+    /// Base table qualifier, needed only once other tables are joined in
+    fn table_alias(&self, joins: &[FilterJoinInfo]) -> Option<&str> {
+        if joins.is_empty() { None } else { Some(self.table_name.as_str()) }
+    }
+
+    fn where_sql(&self, table_alias: Option<&str>) -> Result<SqlResult, FilterError> {
+        let options = FilterWhereOptions { table_alias: table_alias.map(|t| t.to_string()), ..self.options.clone() };
+        let (where_clause, params) = if let Some(ref where_data) = self.where_data {
+            FilterWhere::generate(where_data, 0, &options)?
+        } else {
+            FilterWhere::generate_empty(&options)
+        };
+        Ok(SqlResult { query: where_clause, params })
     }
 
     fn validate_table_name(name: &str) -> Result<(), FilterError> {
@@ -124,6 +469,17 @@ impl Filter {
         Ok(())
     }
 
+    /// Reject the first column the table does not have; skipped while its columns are unknown
+    fn check_known_columns<'a>(&self, mut columns: impl Iterator<Item = &'a str>, clause: &'static str) -> Result<(), FilterError> {
+        if self.known_columns.is_empty() {
+            return Ok(());
+        }
+        match columns.find(|column| !self.known_columns.iter().any(|known| known == column)) {
+            Some(column) => Err(FilterError::UnknownColumn { column: column.to_string(), clause }),
+            None => Ok(()),
+        }
+    }
+
     fn validate_select_columns(columns: &[String]) -> Result<(), FilterError> {
         for column in columns {
             if column == "*" { continue; }
@@ -135,11 +491,12 @@ impl Filter {
         Ok(())
     }
 
-    fn build_select_clause(&self) -> String {
+    fn build_select_clause(&self, table_alias: Option<&str>) -> String {
+        let prefix = table_alias.map(|t| format!("\"{}\".", t)).unwrap_or_default();
         if self.select_columns.is_empty() || self.select_columns.contains(&"*".to_string()) {
-            "*".to_string()
+            format!("{}*", prefix)
         } else {
-            self.select_columns.iter().map(|c| format!("\"{}\"", c)).collect::<Vec<_>>().join(", ")
+            self.select_columns.iter().map(|c| format!("{}\"{}\"", prefix, c)).collect::<Vec<_>>().join(", ")
         }
     }
 
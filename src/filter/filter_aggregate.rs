@@ -0,0 +1,223 @@
+use serde_json::Value;
+
+use super::error::FilterError;
+use super::filter_group::FilterGroup;
+use super::types::{AggregateFn, FilterAggregateInfo, FilterHavingInfo};
+
+/// HAVING operators and the SQL comparison each compiles to
+const HAVING_OPERATORS: &[(&str, &str)] = &[
+    ("$eq", "="),
+    ("$ne", "<>"),
+    ("$gt", ">"),
+    ("$gte", ">="),
+    ("$lt", "<"),
+    ("$lte", "<="),
+];
+
+pub struct FilterAggregate;
+
+impl FilterAggregate {
+    /// Parse aggregate specs
+    ///
+    /// Accepts one object or an array of them, each naming a function and its column:
+    /// - `{ "count": "*" }` rows per group, returned as `count`
+    /// - `{ "count": "email" }` non-null values, returned as `count_email`
+    /// - `{ "sum": "amount", "as": "revenue" }`; likewise `avg`, `min` and `max`
+    ///
+    /// Sums and averages are returned as floating point numbers.
+    pub fn validate_and_parse(aggregations: &Value) -> Result<Vec<FilterAggregateInfo>, FilterError> {
+        let specs = match aggregations {
+            Value::Null => return Ok(Self::default_aggregates()),
+            Value::Array(arr) => arr.iter().collect::<Vec<_>>(),
+            other => vec![other],
+        };
+        if specs.is_empty() {
+            return Err(FilterError::InvalidAggregation("at least one aggregation is required".to_string()));
+        }
+
+        let mut out: Vec<FilterAggregateInfo> = Vec::new();
+        for spec in specs {
+            let info = Self::parse_spec(spec)?;
+            if out.iter().any(|existing| existing.alias == info.alias) {
+                return Err(FilterError::InvalidAggregation(format!("Duplicate aggregation alias: {}", info.alias)));
+            }
+            out.push(info);
+        }
+        Ok(out)
+    }
+
+    /// A single row count, as returned when no aggregations are requested
+    pub fn default_aggregates() -> Vec<FilterAggregateInfo> {
+        vec![FilterAggregateInfo { alias: "count".to_string(), function: AggregateFn::Count, column: None }]
+    }
+
+    fn parse_spec(spec: &Value) -> Result<FilterAggregateInfo, FilterError> {
+        let obj = spec
+            .as_object()
+            .ok_or_else(|| FilterError::InvalidAggregation("Aggregations must be objects such as { \"sum\": \"amount\" }".to_string()))?;
+
+        let functions = [AggregateFn::Count, AggregateFn::Sum, AggregateFn::Avg, AggregateFn::Min, AggregateFn::Max];
+        let mut found = functions.iter().filter_map(|f| obj.get(f.name()).map(|column| (*f, column)));
+        let (function, column) = match (found.next(), found.next()) {
+            (Some(only), None) => only,
+            _ => {
+                return Err(FilterError::InvalidAggregation(
+                    "Each aggregation must name exactly one of count, sum, avg, min, max".to_string(),
+                ))
+            }
+        };
+        if let Some(key) = obj.keys().find(|k| *k != function.name() && *k != "as") {
+            return Err(FilterError::InvalidAggregation(format!("Unexpected key '{}' in {} aggregation", key, function.name())));
+        }
+
+        let column = column
+            .as_str()
+            .ok_or_else(|| FilterError::InvalidAggregation(format!("{} requires a column name", function.name())))?;
+        let column = match column {
+            "*" if function == AggregateFn::Count => None,
+            "*" => return Err(FilterError::InvalidAggregation(format!("{} requires a column name", function.name()))),
+            column => {
+                FilterGroup::validate_identifier(column)?;
+                Some(column.to_string())
+            }
+        };
+
+        let alias = match obj.get("as") {
+            Some(alias) => alias
+                .as_str()
+                .ok_or_else(|| FilterError::InvalidAggregation("'as' must be a string".to_string()))?
+                .to_string(),
+            None => match &column {
+                Some(column) => format!("{}_{}", function.name(), column),
+                None => function.name().to_string(),
+            },
+        };
+        FilterGroup::validate_identifier(&alias)?;
+        Ok(FilterAggregateInfo { alias, function, column })
+    }
+
+    /// Parse HAVING conditions against the aggregates' aliases
+    ///
+    /// `{ "revenue": { "$gte": 1000 }, "count": 2 }`: a bare value is an equality test,
+    /// and an object may combine $eq, $ne, $gt, $gte, $lt and $lte. All conditions must hold.
+    pub fn parse_having(having: &Value, aggregates: &[FilterAggregateInfo]) -> Result<Vec<FilterHavingInfo>, FilterError> {
+        let conditions = match having {
+            Value::Null => return Ok(vec![]),
+            Value::Object(obj) => obj,
+            _ => return Err(FilterError::InvalidHaving("HAVING must be an object keyed by aggregation alias".to_string())),
+        };
+
+        let mut out = Vec::new();
+        for (alias, condition) in conditions {
+            if !aggregates.iter().any(|a| &a.alias == alias) {
+                return Err(FilterError::InvalidHaving(format!("'{}' is not an aggregation alias", alias)));
+            }
+            let comparisons: Vec<(&str, &Value)> = match condition {
+                Value::Object(ops) => ops.iter().map(|(op, value)| (op.as_str(), value)).collect(),
+                value => vec![("$eq", value)],
+            };
+            if comparisons.is_empty() {
+                return Err(FilterError::InvalidHaving(format!("No condition given for '{}'", alias)));
+            }
+            for (op, value) in comparisons {
+                let operator = HAVING_OPERATORS
+                    .iter()
+                    .find(|(name, _)| *name == op)
+                    .map(|(_, sql)| *sql)
+                    .ok_or_else(|| FilterError::UnsupportedOperator(format!("{} in HAVING", op)))?;
+                if !value.is_number() && !value.is_string() {
+                    return Err(FilterError::InvalidHaving(format!("'{}' must be compared to a number or string", alias)));
+                }
+                out.push(FilterHavingInfo { alias: alias.clone(), operator, value: value.clone() });
+            }
+        }
+        Ok(out)
+    }
+
+    /// SQL expression for an aggregate, without alias; columns are prefixed by `table_alias` if given
+    pub fn expression(info: &FilterAggregateInfo, table_alias: Option<&str>) -> String {
+        let prefix = table_alias.map(|t| format!("\"{}\".", t)).unwrap_or_default();
+        let column = match &info.column {
+            Some(column) => format!("{}\"{}\"", prefix, column),
+            None => "*".to_string(),
+        };
+        match info.function {
+            AggregateFn::Count => format!("COUNT({})", column),
+            // numeric results are returned as float8, which records can carry
+            AggregateFn::Sum => format!("SUM({})::float8", column),
+            AggregateFn::Avg => format!("AVG({})::float8", column),
+            AggregateFn::Min => format!("MIN({})", column),
+            AggregateFn::Max => format!("MAX({})", column),
+        }
+    }
+
+    /// SELECT list entries for the aggregates
+    pub fn generate(infos: &[FilterAggregateInfo], table_alias: Option<&str>) -> Vec<String> {
+        infos
+            .iter()
+            .map(|i| format!("{} AS \"{}\"", Self::expression(i, table_alias), i.alias))
+            .collect()
+    }
+
+    /// HAVING clause with parameters numbered after the first `param_offset`
+    pub fn generate_having(
+        conditions: &[FilterHavingInfo],
+        aggregates: &[FilterAggregateInfo],
+        table_alias: Option<&str>,
+        param_offset: usize,
+    ) -> (String, Vec<Value>) {
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        for condition in conditions {
+            let Some(aggregate) = aggregates.iter().find(|a| a.alias == condition.alias) else { continue };
+            params.push(condition.value.clone());
+            clauses.push(format!(
+                "{} {} ${}",
+                Self::expression(aggregate, table_alias),
+                condition.operator,
+                param_offset + params.len()
+            ));
+        }
+        if clauses.is_empty() {
+            (String::new(), params)
+        } else {
+            (format!("HAVING {}", clauses.join(" AND ")), params)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::Filter;
+    use serde_json::json;
+
+    #[test]
+    fn test_aggregations_and_having_compile_to_grouped_sql() {
+        let mut filter = Filter::new("orders").unwrap();
+        filter.columns(vec!["id".to_string(), "status".to_string(), "amount".to_string()]);
+        filter.where_clause(json!({ "status": { "$ne": "void" } })).unwrap();
+        filter.group(json!(["status"])).unwrap();
+        filter.aggregations(json!([{ "count": "*" }, { "sum": "amount", "as": "revenue" }, { "max": "amount" }])).unwrap();
+        filter.having(json!({ "revenue": { "$gte": 1000, "$lt": 5000 }, "count": 2 })).unwrap();
+
+        let sql = filter.to_aggregate_sql().unwrap();
+        assert_eq!(
+            sql.query,
+            "SELECT \"status\" AS \"status\", COUNT(*) AS \"count\", SUM(\"amount\")::float8 AS \"revenue\", \
+             MAX(\"amount\") AS \"max_amount\" FROM \"orders\" WHERE \"trashed_at\" IS NULL AND \"deleted_at\" IS NULL \
+             AND \"status\" <> $1 GROUP BY 1 HAVING COUNT(*) = $2 AND SUM(\"amount\")::float8 >= $3 \
+             AND SUM(\"amount\")::float8 < $4 ORDER BY 1"
+        );
+        assert_eq!(sql.params, vec![json!("void"), json!(2), json!(1000), json!(5000)]);
+
+        assert!(filter.aggregations(json!([{ "sum": "*" }])).is_err());
+        assert!(filter.aggregations(json!([{ "sum": "amount", "avg": "amount" }])).is_err());
+        assert!(filter.aggregations(json!([{ "median": "amount" }])).is_err());
+        assert!(filter.aggregations(json!([{ "min": "amount", "as": "status" }])).is_err());
+        let err = filter.aggregations(json!({ "avg": "amuont" })).err().unwrap();
+        assert!(matches!(err, FilterError::UnknownColumn { ref column, clause: "aggregations" } if column == "amuont"));
+        assert!(filter.having(json!({ "total": { "$gt": 1 } })).is_err());
+        assert!(filter.having(json!({ "count": { "$like": "1%" } })).is_err());
+    }
+}
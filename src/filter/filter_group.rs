@@ -0,0 +1,137 @@
+use serde_json::Value;
+
+use super::types::{FilterGroupInfo, GroupExpr};
+use super::error::FilterError;
+
+/// Units accepted by date_bucket, passed straight to date_trunc
+const DATE_BUCKET_UNITS: &[&str] = &["minute", "hour", "day", "week", "month", "quarter", "year"];
+
+/// Upper bound on histogram bucket count to keep result sets chart-sized
+const MAX_HISTOGRAM_BUCKETS: f64 = 10_000.0;
+
+pub struct FilterGroup;
+
+impl FilterGroup {
+    /// Parse GROUP BY specs
+    ///
+    /// Accepts a column name, or an array whose entries are column names or bucket objects:
+    /// - `{ "date_bucket": { "column": "created_at", "unit": "day" }, "as": "day" }`
+    /// - `{ "histogram": { "column": "amount", "width": 10, "min": 0, "max": 1000 }, "as": "amount" }`
+    pub fn validate_and_parse(group_by: &Value) -> Result<Vec<FilterGroupInfo>, FilterError> {
+        let specs = match group_by {
+            Value::Null => return Ok(vec![]),
+            Value::Array(arr) => arr.iter().collect::<Vec<_>>(),
+            other => vec![other],
+        };
+
+        let mut out: Vec<FilterGroupInfo> = Vec::new();
+        for spec in specs {
+            let info = Self::parse_spec(spec)?;
+            if out.iter().any(|existing| existing.alias == info.alias) {
+                return Err(FilterError::InvalidGroupBy(format!("Duplicate group alias: {}", info.alias)));
+            }
+            out.push(info);
+        }
+        Ok(out)
+    }
+
+    fn parse_spec(spec: &Value) -> Result<FilterGroupInfo, FilterError> {
+        match spec {
+            Value::String(column) => {
+                Self::validate_identifier(column)?;
+                Ok(FilterGroupInfo { alias: column.clone(), expr: GroupExpr::Column(column.clone()) })
+            }
+            Value::Object(obj) => {
+                let alias = obj.get("as").and_then(|v| v.as_str()).map(|s| s.to_string());
+
+                let (expr, default_alias) = if let Some(bucket) = obj.get("date_bucket") {
+                    let column = Self::required_str(bucket, "date_bucket", "column")?;
+                    let unit = Self::required_str(bucket, "date_bucket", "unit")?.to_ascii_lowercase();
+                    if !DATE_BUCKET_UNITS.contains(&unit.as_str()) {
+                        return Err(FilterError::InvalidGroupBy(format!(
+                            "Unsupported date_bucket unit '{}'; expected one of {}", unit, DATE_BUCKET_UNITS.join(", ")
+                        )));
+                    }
+                    let default_alias = format!("{}_{}", column, unit);
+                    (GroupExpr::DateBucket { column, unit }, default_alias)
+                } else if let Some(bucket) = obj.get("histogram") {
+                    let column = Self::required_str(bucket, "histogram", "column")?;
+                    let width = Self::required_f64(bucket, "width")?;
+                    let min = Self::required_f64(bucket, "min")?;
+                    let max = Self::required_f64(bucket, "max")?;
+                    if width <= 0.0 || max <= min {
+                        return Err(FilterError::InvalidGroupBy("histogram requires width > 0 and max > min".to_string()));
+                    }
+                    if ((max - min) / width).ceil() > MAX_HISTOGRAM_BUCKETS {
+                        return Err(FilterError::InvalidGroupBy(format!(
+                            "histogram would produce more than {} buckets", MAX_HISTOGRAM_BUCKETS
+                        )));
+                    }
+                    let default_alias = format!("{}_bucket", column);
+                    (GroupExpr::Histogram { column, width, min, max }, default_alias)
+                } else {
+                    return Err(FilterError::InvalidGroupBy("Group object must contain date_bucket or histogram".to_string()));
+                };
+
+                let alias = alias.unwrap_or(default_alias);
+                Self::validate_identifier(&alias)?;
+                Ok(FilterGroupInfo { alias, expr })
+            }
+            _ => Err(FilterError::InvalidGroupBy("Group entries must be column names or bucket objects".to_string())),
+        }
+    }
+
+    fn required_str(bucket: &Value, kind: &str, key: &str) -> Result<String, FilterError> {
+        let value = bucket
+            .get(key)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| FilterError::InvalidGroupBy(format!("{} requires '{}'", kind, key)))?;
+        if key == "column" { Self::validate_identifier(value)?; }
+        Ok(value.to_string())
+    }
+
+    fn required_f64(bucket: &Value, key: &str) -> Result<f64, FilterError> {
+        bucket
+            .get(key)
+            .and_then(|v| v.as_f64())
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| FilterError::InvalidGroupBy(format!("histogram requires numeric '{}'", key)))
+    }
+
+    pub(crate) fn validate_identifier(name: &str) -> Result<(), FilterError> {
+        let mut chars = name.chars();
+        let valid = match chars.next() {
+            Some(first) => (first.is_alphabetic() || first == '_') && chars.all(|c| c.is_alphanumeric() || c == '_'),
+            None => false,
+        };
+        if valid { Ok(()) } else { Err(FilterError::InvalidColumn(format!("Invalid column name format: {}", name))) }
+    }
+
+    /// SQL expression for a group, without alias; columns are prefixed by `table_alias` if given
+    pub fn expression(info: &FilterGroupInfo, table_alias: Option<&str>) -> String {
+        let prefix = table_alias.map(|t| format!("\"{}\".", t)).unwrap_or_default();
+        match &info.expr {
+            GroupExpr::Column(column) => format!("{}\"{}\"", prefix, column),
+            GroupExpr::DateBucket { column, unit } => format!("date_trunc('{}', {}\"{}\")", unit, prefix, column),
+            GroupExpr::Histogram { column, width, min, max } => {
+                let buckets = ((max - min) / width).ceil() as i64;
+                // width_bucket is 1-based; 0 and buckets+1 catch values outside [min, max)
+                format!(
+                    "({:?}::float8 + (width_bucket({}\"{}\"::float8, {:?}::float8, {:?}::float8, {}) - 1) * {:?}::float8)",
+                    min, prefix, column, min, max, buckets, width
+                )
+            }
+        }
+    }
+
+    /// SELECT list entries and GROUP BY clause for the parsed groups
+    pub fn generate(infos: &[FilterGroupInfo], table_alias: Option<&str>) -> (Vec<String>, String) {
+        if infos.is_empty() { return (vec![], String::new()); }
+        let selects = infos
+            .iter()
+            .map(|i| format!("{} AS \"{}\"", Self::expression(i, table_alias), i.alias))
+            .collect();
+        let positions = (1..=infos.len()).map(|n| n.to_string()).collect::<Vec<_>>().join(", ");
+        (selects, format!("GROUP BY {}", positions))
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+use super::types::{FilterJoinInfo, FilterRelationship};
+use super::error::FilterError;
+use super::filter_where::FilterWhere;
+
+pub struct FilterJoin;
+
+impl FilterJoin {
+    /// Relationship paths referenced by dotted where-clause keys
+    ///
+    /// `{ "customer.region.name": "EU" }` yields "customer" and "customer.region", so every
+    /// intermediate hop gets its own join. Keys addressing `json_columns` documents are
+    /// skipped, see `FilterWhere::json_path`.
+    pub fn paths(where_data: &Value, json_columns: &[String]) -> BTreeSet<String> {
+        let mut paths = BTreeSet::new();
+        Self::collect_paths(where_data, json_columns, &mut paths);
+        paths
+    }
+
+    fn collect_paths(where_data: &Value, json_columns: &[String], paths: &mut BTreeSet<String>) {
+        match where_data {
+            Value::Object(obj) => {
+                for (key, value) in obj {
+                    // The column holding a JSON path may still sit across a relationship
+                    let column = match FilterWhere::json_path(key, json_columns) {
+                        Some((column, _)) => column,
+                        None => key.as_str(),
+                    };
+                    if key.starts_with('$') {
+                        Self::collect_paths(value, json_columns, paths);
+                    } else if let Some((path, _field)) = column.rsplit_once('.') {
+                        let mut prefix = String::new();
+                        for segment in path.split('.') {
+                            if !prefix.is_empty() { prefix.push('.'); }
+                            prefix.push_str(segment);
+                            paths.insert(prefix.clone());
+                        }
+                    }
+                }
+            }
+            Value::Array(arr) => {
+                for v in arr { Self::collect_paths(v, json_columns, paths); }
+            }
+            _ => {}
+        }
+    }
+
+    /// Table alias for a relationship path: "customer.region" -> "customer__region"
+    pub fn alias(path: &str) -> String {
+        path.replace('.', "__")
+    }
+
+    /// Resolve paths against declared relationships, starting from `table`
+    ///
+    /// Only edges declared with x-monk-relationship can be traversed, and no path may
+    /// be longer than `max_depth` hops.
+    pub fn resolve(
+        table: &str,
+        paths: &BTreeSet<String>,
+        relationships: &[FilterRelationship],
+        max_depth: u32,
+    ) -> Result<Vec<FilterJoinInfo>, FilterError> {
+        let mut joins: Vec<FilterJoinInfo> = Vec::new();
+
+        // BTreeSet order guarantees a parent path is resolved before its children
+        for path in paths {
+            let depth = path.split('.').count() as u32;
+            if depth > max_depth {
+                return Err(FilterError::InvalidRelationship(format!(
+                    "Path '{}' exceeds the maximum join depth of {}", path, max_depth
+                )));
+            }
+
+            let (parent_path, name) = match path.rsplit_once('.') {
+                Some((parent, name)) => (Some(parent), name),
+                None => (None, path.as_str()),
+            };
+            let (parent_table, parent_alias, parent_inner) = match parent_path {
+                Some(parent) => {
+                    let parent_join = joins.iter().find(|j| j.path == parent).ok_or_else(|| {
+                        FilterError::InvalidRelationship(format!("Unresolved relationship path '{}'", parent))
+                    })?;
+                    (parent_join.relationship.related_table.clone(), parent_join.alias.clone(), parent_join.inner)
+                }
+                None => (table.to_string(), table.to_string(), true),
+            };
+
+            let relationship = relationships
+                .iter()
+                .find(|r| r.table == parent_table && r.name == name)
+                .ok_or_else(|| FilterError::InvalidRelationship(format!(
+                    "'{}' has no declared relationship named '{}'", parent_table, name
+                )))?;
+
+            let alias = Self::alias(path);
+            if alias == table {
+                return Err(FilterError::InvalidRelationship(format!(
+                    "Relationship path '{}' collides with the table name", path
+                )));
+            }
+
+            joins.push(FilterJoinInfo {
+                path: path.clone(),
+                alias,
+                relationship: relationship.clone(),
+                parent_alias,
+                // An INNER JOIN below a LEFT JOIN would drop rows the LEFT JOIN kept
+                inner: parent_inner && relationship.required,
+            });
+        }
+
+        Ok(joins)
+    }
+
+    /// JOIN clauses for resolved joins
+    ///
+    /// Soft-deleted related rows never match; through an INNER JOIN that also excludes the
+    /// base row, which is only used when every hop is a required relationship. With
+    /// `access_param` (the caller's uuid[] parameter) related rows the caller cannot read
+    /// are treated the same way, so conditions on their columns never see them.
+    pub fn generate(joins: &[FilterJoinInfo], access_param: Option<&str>) -> String {
+        joins
+            .iter()
+            .map(|j| {
+                let kind = if j.inner { "INNER JOIN" } else { "LEFT JOIN" };
+                let access = access_param
+                    .map(|param| format!(" AND {}", FilterWhere::access_condition(&format!("\"{}\".", j.alias), param)))
+                    .unwrap_or_default();
+                format!(
+                    "{} \"{}\" AS \"{}\" ON \"{}\".\"{}\" = \"{}\".\"{}\" AND \"{}\".\"trashed_at\" IS NULL AND \"{}\".\"deleted_at\" IS NULL{}",
+                    kind,
+                    j.relationship.related_table,
+                    j.alias,
+                    j.alias,
+                    j.relationship.related_column,
+                    j.parent_alias,
+                    j.relationship.column,
+                    j.alias,
+                    j.alias,
+                    access,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relationship(table: &str, name: &str, related_table: &str, required: bool) -> FilterRelationship {
+        FilterRelationship {
+            table: table.to_string(),
+            name: name.to_string(),
+            column: format!("{}_id", name),
+            related_table: related_table.to_string(),
+            related_column: "id".to_string(),
+            required,
+        }
+    }
+
+    #[test]
+    fn test_joined_rows_are_held_to_record_access() {
+        let relationships = vec![
+            relationship("orders", "customer", "customers", true),
+            relationship("customers", "region", "regions", false),
+        ];
+        let paths = FilterJoin::paths(&serde_json::json!({ "customer.region.name": "EU" }), &[]);
+        let joins = FilterJoin::resolve("orders", &paths, &relationships, 3).unwrap();
+
+        let sql = FilterJoin::generate(&joins, Some("$2"));
+        for alias in ["customer", "customer__region"] {
+            assert!(sql.contains(&format!("NOT COALESCE(\"{}\".\"access_deny\" && $2::uuid[], false)", alias)), "{}", sql);
+            assert!(sql.contains(&format!("(\"{a}\".\"access_read\" || \"{a}\".\"access_edit\" || \"{a}\".\"access_full\") && $2::uuid[]", a = alias)), "{}", sql);
+        }
+        assert!(!FilterJoin::generate(&joins, None).contains("access_deny"));
+    }
+}
@@ -3,6 +3,14 @@ use serde_json::Value;
 use super::types::{FilterOrderInfo, SortDirection};
 use super::error::FilterError;
 
+/// Column appended to every sort so rows with equal sort keys keep a fixed order
+pub const TIEBREAKER_COLUMN: &str = "id";
+
+/// ORDER BY generation
+///
+/// Every generated sort is total: unless the order already names `id`, `"id" ASC` is
+/// appended as a final key. Rows that tie on the requested columns therefore come back in
+/// the same order on every query, so limit/offset pages neither repeat nor skip rows.
 pub struct FilterOrder;
 
 impl FilterOrder {
@@ -50,11 +58,58 @@ impl FilterOrder {
     }
 
     pub fn generate(infos: &[FilterOrderInfo]) -> Result<String, FilterError> {
+        Self::generate_qualified(infos, None)
+    }
+
+    /// ORDER BY with columns prefixed by `table_alias`, for queries that join related schemas
+    pub fn generate_qualified(infos: &[FilterOrderInfo], table_alias: Option<&str>) -> Result<String, FilterError> {
         if infos.is_empty() { return Ok(String::new()); }
-        let parts: Vec<String> = infos
+        Ok(format!("ORDER BY {}", Self::columns_sql(infos, table_alias)))
+    }
+
+    /// Comma-separated sort terms without the ORDER BY keyword, e.g. for window clauses
+    ///
+    /// Includes the `id` tiebreaker, so window row numbers are deterministic too.
+    pub fn columns_sql(infos: &[FilterOrderInfo], table_alias: Option<&str>) -> String {
+        let prefix = table_alias.map(|t| format!("\"{}\".", t)).unwrap_or_default();
+        Self::with_tiebreaker(infos)
             .iter()
-            .map(|i| format!("\"{}\" {}", i.column, i.sort.to_sql()))
-            .collect();
-        Ok(format!("ORDER BY {}", parts.join(", ")))
+            .map(|i| format!("{}\"{}\" {}", prefix, i.column, i.sort.to_sql()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// The sort keys actually applied: `infos` plus `"id" ASC` unless they already name `id`
+    pub fn with_tiebreaker(infos: &[FilterOrderInfo]) -> Vec<FilterOrderInfo> {
+        let mut keys = infos.to_vec();
+        if !infos.iter().any(|i| i.column == TIEBREAKER_COLUMN) {
+            keys.push(FilterOrderInfo { column: TIEBREAKER_COLUMN.to_string(), sort: SortDirection::Asc });
+        }
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn order_sql(order: Value) -> String {
+        FilterOrder::generate(&FilterOrder::validate_and_parse(&order).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_order_has_tiebreaker() {
+        assert_eq!(order_sql(json!("status")), "ORDER BY \"status\" ASC, \"id\" ASC");
+        assert_eq!(
+            order_sql(json!(["priority desc", "status"])),
+            "ORDER BY \"priority\" DESC, \"status\" ASC, \"id\" ASC"
+        );
+        assert_eq!(order_sql(json!("status, id desc")), "ORDER BY \"status\" ASC, \"id\" DESC");
+        assert_eq!(order_sql(json!(null)), "");
+        assert_eq!(
+            FilterOrder::columns_sql(&FilterOrder::validate_and_parse(&json!("name")).unwrap(), Some("users")),
+            "\"users\".\"name\" ASC, \"users\".\"id\" ASC"
+        );
     }
 }
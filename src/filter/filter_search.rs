@@ -0,0 +1,58 @@
+use serde_json::Value;
+
+/// Full-text search over a table's `search_vector`
+///
+/// Tables whose schema marks string properties `"x-monk-searchable": true` carry a generated
+/// tsvector column over them, with a GIN index (see `database::search`). The clause-level
+/// `{ "$search": "quick fox -lazy" }` matches it with `websearch_to_tsquery`, which accepts
+/// quoted phrases, `or` and `-` exclusions and never fails on user input. A find whose
+/// top-level where clause searches, with no order or cursor of its own, is returned best
+/// match first.
+pub struct FilterSearch;
+
+impl FilterSearch {
+    /// Generated tsvector column of searchable tables
+    pub const COLUMN: &'static str = "search_vector";
+
+    /// Text search configuration from `filter.search_language`; names that are not plain
+    /// identifiers fall back to "simple", since the name is written into the SQL
+    pub fn language() -> String {
+        let language = &crate::config::CONFIG.filter.search_language;
+        let valid = !language.is_empty()
+            && language.len() <= 63
+            && language.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if valid { language.clone() } else { "simple".to_string() }
+    }
+
+    /// `websearch_to_tsquery` of the text bound at `param`
+    pub fn tsquery(param: &str) -> String {
+        format!("websearch_to_tsquery('{}'::regconfig, {})", Self::language(), param)
+    }
+
+    /// Rows whose search vector matches the text bound at `param`
+    pub fn condition(prefix: &str, param: &str) -> String {
+        format!("{}\"{}\" @@ {}", prefix, Self::COLUMN, Self::tsquery(param))
+    }
+
+    /// ORDER BY terms ranking rows by relevance to the text bound at `param`, ties by id
+    pub fn rank_order(prefix: &str, param: &str) -> String {
+        format!(
+            "ts_rank({p}\"{c}\", {q}) DESC, {p}\"id\" ASC",
+            p = prefix, c = Self::COLUMN, q = Self::tsquery(param)
+        )
+    }
+
+    /// Search text of a top-level `$search` in the where clause
+    pub fn top_level(where_data: &Value) -> Option<&str> {
+        where_data.get("$search").and_then(Value::as_str)
+    }
+
+    /// Whether `$search` appears anywhere in the where clause
+    pub fn used(where_data: &Value) -> bool {
+        match where_data {
+            Value::Object(obj) => obj.iter().any(|(key, value)| key == "$search" || (key.starts_with('$') && Self::used(value))),
+            Value::Array(arr) => arr.iter().any(Self::used),
+            _ => false,
+        }
+    }
+}
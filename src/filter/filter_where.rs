@@ -1,12 +1,22 @@
+use std::collections::BTreeSet;
+
 use serde_json::Value;
 
 use super::types::{FilterOp, FilterWhereInfo, FilterWhereOptions};
 use super::error::FilterError;
+use super::filter_join::FilterJoin;
+use super::filter_search::FilterSearch;
 
 pub struct FilterWhere {
     param_values: Vec<Value>,
     param_index: usize,
     conditions: Vec<FilterWhereInfo>,
+    table_alias: Option<String>,
+    unaccent: bool,
+    json_columns: Vec<String>,
+    schema_name: Option<String>,
+    /// How many `$and`/`$or`/`$not` groups enclose this clause
+    depth: u32,
 }
 
 impl FilterWhere {
@@ -15,6 +25,11 @@ impl FilterWhere {
             param_values: vec![],
             param_index: starting_param_index,
             conditions: vec![],
+            table_alias: None,
+            unaccent: false,
+            json_columns: vec![],
+            schema_name: None,
+            depth: 0,
         }
     }
 
@@ -25,10 +40,25 @@ impl FilterWhere {
 
     pub fn generate_empty(options: &FilterWhereOptions) -> (String, Vec<Value>) {
         let mut conditions = vec![];
-        if !options.include_trashed { conditions.push("\"trashed_at\" IS NULL".to_string()); }
-        if !options.include_deleted { conditions.push("\"deleted_at\" IS NULL".to_string()); }
+        let prefix = Self::table_prefix(options.table_alias.as_deref());
+        if !options.include_trashed { conditions.push(format!("{}\"trashed_at\" IS NULL", prefix)); }
+        if !options.include_deleted { conditions.push(format!("{}\"deleted_at\" IS NULL", prefix)); }
+        let mut params = vec![];
+        if let Some(ref access) = options.access {
+            params.push(Value::String(access.to_array_literal()));
+            conditions.push(Self::access_condition(&prefix, "$1"));
+        }
         let where_clause = if conditions.is_empty() { "1=1".to_string() } else { conditions.join(" AND ") };
-        (where_clause, vec![])
+        (where_clause, params)
+    }
+
+    /// Row-level ACL check against one uuid[] parameter; NULL lists count as empty
+    pub(crate) fn access_condition(prefix: &str, param: &str) -> String {
+        let grants = format!("({p}\"access_read\" || {p}\"access_edit\" || {p}\"access_full\")", p = prefix);
+        format!(
+            "NOT COALESCE({p}\"access_deny\" && {param}::uuid[], false) AND (COALESCE(cardinality({grants}), 0) = 0 OR {grants} && {param}::uuid[])",
+            p = prefix, param = param, grants = grants
+        )
     }
 
     pub fn validate(where_data: &Value) -> Result<(), FilterError> {
@@ -42,17 +72,25 @@ impl FilterWhere {
     fn build(&mut self, where_data: &Value, options: &FilterWhereOptions) -> Result<(String, Vec<Value>), FilterError> {
         self.param_values.clear();
         self.conditions.clear();
-        self.param_index = 0;
+        self.table_alias = options.table_alias.clone();
+        self.unaccent = options.unaccent;
+        self.json_columns = options.json_columns.clone();
+        self.schema_name = options.schema_name.clone();
 
         self.parse_where_data(where_data)?;
 
         let mut sql_conditions = vec![];
-        if !options.include_trashed { sql_conditions.push("\"trashed_at\" IS NULL".to_string()); }
-        if !options.include_deleted { sql_conditions.push("\"deleted_at\" IS NULL".to_string()); }
+        let prefix = Self::table_prefix(options.table_alias.as_deref());
+        if !options.include_trashed { sql_conditions.push(format!("{}\"trashed_at\" IS NULL", prefix)); }
+        if !options.include_deleted { sql_conditions.push(format!("{}\"deleted_at\" IS NULL", prefix)); }
         let conditions_snapshot = self.conditions.clone();
         for condition in &conditions_snapshot {
             if let Some(sql) = self.build_sql_condition(condition)? { sql_conditions.push(sql); }
         }
+        if let Some(ref access) = options.access {
+            let param = self.param(Value::String(access.to_array_literal()));
+            sql_conditions.push(Self::access_condition(&prefix, &param));
+        }
         let where_clause = if sql_conditions.is_empty() { "1=1".to_string() } else { sql_conditions.join(" AND ") };
         Ok((where_clause, self.param_values.clone()))
     }
@@ -81,7 +119,7 @@ impl FilterWhere {
                     tracing::warn!("Raw SQL query attempted: {}", s);
                 }
                 // Raw SQL predicate (use cautiously)
-                self.conditions.push(FilterWhereInfo { column: s.clone(), operator: FilterOp::Text, data: Value::Null });
+                self.conditions.push(FilterWhereInfo { column: s.clone(), operator: FilterOp::Text, data: Value::Null, collation: None });
                 Ok(())
             }
             _ => Err(FilterError::InvalidWhereClause("Unsupported WHERE format".to_string())),
@@ -94,45 +132,162 @@ impl FilterWhere {
                 let arr = value.as_array().ok_or_else(|| FilterError::InvalidOperatorData(format!("{} requires array", op)))?;
                 let mut sql_parts = Vec::new();
                 for v in arr {
-                    let (sql, params) = Self::generate(v, self.param_index, &FilterWhereOptions::default())?;
+                    let (sql, params) = self.generate_nested(v)?;
+                    // Nested placeholders continue from this clause's numbering
+                    self.param_index += params.len();
                     self.param_values.extend(params);
                     // Wrap subclause
                     sql_parts.push(format!("({})", sql));
-                    self.param_index = self.param_values.len();
                 }
                 let joiner = if op == "$and" { " AND " } else { " OR " };
-                let combined = sql_parts.join(joiner);
+                // Parenthesized so an OR group does not absorb the surrounding AND conditions
+                let combined = format!("({})", sql_parts.join(joiner));
                 // Store as a pseudo-condition using column="( … )"
-                self.conditions.push(FilterWhereInfo { column: combined, operator: FilterOp::Text, data: Value::Null });
+                self.conditions.push(FilterWhereInfo { column: combined, operator: FilterOp::Text, data: Value::Null, collation: None });
                 Ok(())
             }
             "$not" => {
-                let (sql, params) = Self::generate(value, self.param_index, &FilterWhereOptions::default())?;
+                let (sql, params) = self.generate_nested(value)?;
+                self.param_index += params.len();
                 self.param_values.extend(params);
-                self.param_index = self.param_values.len();
-                self.conditions.push(FilterWhereInfo { column: format!("NOT ({})", sql), operator: FilterOp::Text, data: Value::Null });
+                self.conditions.push(FilterWhereInfo { column: format!("NOT ({})", sql), operator: FilterOp::Text, data: Value::Null, collation: None });
+                Ok(())
+            }
+            "$search" => {
+                let text = value
+                    .as_str()
+                    .filter(|text| !text.trim().is_empty())
+                    .ok_or_else(|| FilterError::InvalidOperatorData("$search requires a non-empty search string".to_string()))?;
+                let param = self.param(Value::String(text.to_string()));
+                let sql = FilterSearch::condition(&Self::table_prefix(self.table_alias.as_deref()), &param);
+                self.conditions.push(FilterWhereInfo { column: sql, operator: FilterOp::Text, data: Value::Null, collation: None });
+                Ok(())
+            }
+            "$tagged" => {
+                let sql = self.tagged_condition(value)?;
+                self.conditions.push(FilterWhereInfo { column: sql, operator: FilterOp::Text, data: Value::Null, collation: None });
                 Ok(())
             }
             _ => Err(FilterError::UnsupportedOperator(op.to_string())),
         }
     }
 
+    /// `{ "$tagged": "urgent" }` or `{ "$tagged": ["urgent", "billing"] }`: records carrying
+    /// every listed tag
+    ///
+    /// Resolved through the record_tags primary key, so the cost follows the number of
+    /// records with those tags rather than the size of the queried table. Trashed tags
+    /// match nothing.
+    fn tagged_condition(&mut self, value: &Value) -> Result<String, FilterError> {
+        let invalid = || FilterError::InvalidOperatorData("$tagged requires a tag name or an array of tag names".to_string());
+        let mut names: Vec<&str> = match value {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(items) => items.iter().map(Value::as_str).collect::<Option<_>>().ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+        names.sort_unstable();
+        names.dedup();
+        if names.is_empty() {
+            return Ok("1=0".to_string());
+        }
+        let schema_name = self
+            .schema_name
+            .clone()
+            .ok_or_else(|| FilterError::InvalidOperatorData("$tagged needs the queried schema".to_string()))?;
+
+        let schema_param = self.param(Value::String(schema_name));
+        let name_params: Vec<String> = names.iter().map(|name| self.param(Value::String(name.to_string()))).collect();
+        Ok(format!(
+            "{}\"id\" IN (SELECT rt.\"record_id\" FROM \"record_tags\" rt JOIN \"tags\" t ON t.\"id\" = rt.\"tag_id\" \
+             WHERE rt.\"schema_name\" = {} AND t.\"name\" IN ({}) AND t.\"trashed_at\" IS NULL AND t.\"deleted_at\" IS NULL \
+             GROUP BY rt.\"record_id\" HAVING COUNT(*) = {})",
+            Self::table_prefix(self.table_alias.as_deref()),
+            schema_param,
+            name_params.join(", "),
+            names.len()
+        ))
+    }
+
     fn parse_field_condition(&mut self, field: &str, value: &Value) -> Result<(), FilterError> {
         if let Value::Object(obj) = value {
-            for (op_key, op_val) in obj {
+            // { field: { "$eq": "Zoë", "$collate": "und-x-icu" } } compares under that collation
+            let collation = match obj.get("$collate") {
+                Some(Value::String(name)) => Some(Self::validate_collation(name)?),
+                Some(_) => return Err(FilterError::InvalidOperatorData("$collate requires a collation name".to_string())),
+                None => None,
+            };
+            for (op_key, op_val) in obj.iter().filter(|(k, _)| k.as_str() != "$collate") {
                 let operator = Self::map_operator(op_key)?;
-                self.conditions.push(FilterWhereInfo { column: field.to_string(), operator, data: op_val.clone() });
+                self.conditions.push(FilterWhereInfo { column: field.to_string(), operator, data: op_val.clone(), collation: collation.clone() });
             }
         } else {
             // Implicit equality: { field: value }
-            self.conditions.push(FilterWhereInfo { column: field.to_string(), operator: FilterOp::Eq, data: value.clone() });
+            self.conditions.push(FilterWhereInfo { column: field.to_string(), operator: FilterOp::Eq, data: value.clone(), collation: None });
         }
         Ok(())
     }
 
+    /// Collation names are quoted into the SQL, so only name characters are accepted
+    fn validate_collation(name: &str) -> Result<String, FilterError> {
+        let valid = !name.is_empty()
+            && name.len() <= 63
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if !valid {
+            return Err(FilterError::InvalidOperatorData(format!("Invalid collation name '{}'", name)));
+        }
+        Ok(name.to_string())
+    }
+
+    /// Whether `op` is applied to any field in the where clause, at any nesting depth
+    pub fn uses_operator(where_data: &Value, op: &str) -> bool {
+        match where_data {
+            Value::Object(obj) => obj.iter().any(|(key, value)| {
+                if key.starts_with('$') {
+                    Self::uses_operator(value, op)
+                } else {
+                    value.as_object().is_some_and(|ops| ops.contains_key(op))
+                }
+            }),
+            Value::Array(arr) => arr.iter().any(|v| Self::uses_operator(v, op)),
+            _ => false,
+        }
+    }
+
+    /// Columns of the queried table that the where clause tests, at any nesting depth
+    ///
+    /// JSON paths count as their document column. Relationship paths are left out, since
+    /// they name columns of other tables and are checked when their joins are resolved.
+    pub fn columns(where_data: &Value, json_columns: &[String]) -> BTreeSet<String> {
+        let mut columns = BTreeSet::new();
+        Self::collect_columns(where_data, json_columns, &mut columns);
+        columns
+    }
+
+    fn collect_columns(where_data: &Value, json_columns: &[String], columns: &mut BTreeSet<String>) {
+        match where_data {
+            Value::Object(obj) => {
+                for (key, value) in obj {
+                    if key.starts_with('$') {
+                        Self::collect_columns(value, json_columns, columns);
+                        continue;
+                    }
+                    let column = Self::json_path(key, json_columns).map_or(key.as_str(), |(column, _)| column);
+                    if !column.contains('.') {
+                        columns.insert(column.to_string());
+                    }
+                }
+            }
+            Value::Array(arr) => {
+                for v in arr { Self::collect_columns(v, json_columns, columns); }
+            }
+            _ => {}
+        }
+    }
+
     fn map_operator(op_key: &str) -> Result<FilterOp, FilterError> {
         Ok(match op_key {
             "$eq" => FilterOp::Eq,
+            "$ieq" => FilterOp::IEq,
             "$ne" | "$neq" => FilterOp::Neq,
             "$gt" => FilterOp::Gt,
             "$gte" => FilterOp::Gte,
@@ -145,6 +300,13 @@ impl FilterWhere {
             "$any" => FilterOp::Any,
             "$all" => FilterOp::All,
             "$size" => FilterOp::Size,
+            "$regex" => FilterOp::Regex,
+            "$iregex" => FilterOp::IRegex,
+            "$nregex" => FilterOp::NRegex,
+            "$niregex" => FilterOp::NIRegex,
+            "$exists" => FilterOp::Exists,
+            "$null" => FilterOp::Null,
+            "$contains" => FilterOp::Contains,
             other => return Err(FilterError::UnsupportedOperator(other.to_string())),
         })
     }
@@ -155,35 +317,47 @@ impl FilterWhere {
             return Ok(Some(condition.column.clone()));
         }
 
-        let quoted_column = format!("\"{}\"", condition.column);
+        let quoted_column = self.column_sql(condition)?;
+        // Scalar comparisons honor the field's $collate; array operators do not take one
+        let compared = match condition.collation {
+            Some(ref collation) => format!("{} COLLATE \"{}\"", quoted_column, collation),
+            None => quoted_column.clone(),
+        };
         match condition.operator {
             FilterOp::Eq => {
                 if condition.data.is_null() { Ok(Some(format!("{} IS NULL", quoted_column))) }
-                else { Ok(Some(format!("{} = {}", quoted_column, self.param(condition.data.clone())))) }
+                else { Ok(Some(format!("{} = {}", compared, self.param(condition.data.clone())))) }
+            }
+            FilterOp::IEq => {
+                if condition.data.is_null() { Ok(Some(format!("{} IS NULL", quoted_column))) }
+                else { Ok(Some(format!("LOWER({}) = LOWER({})", compared, self.param(condition.data.clone())))) }
             }
             FilterOp::Ne | FilterOp::Neq => {
                 if condition.data.is_null() { Ok(Some(format!("{} IS NOT NULL", quoted_column))) }
-                else { Ok(Some(format!("{} <> {}", quoted_column, self.param(condition.data.clone())))) }
-            }
-            FilterOp::Gt => Ok(Some(format!("{} > {}", quoted_column, self.param(condition.data.clone())))),
-            FilterOp::Gte => Ok(Some(format!("{} >= {}", quoted_column, self.param(condition.data.clone())))),
-            FilterOp::Lt => Ok(Some(format!("{} < {}", quoted_column, self.param(condition.data.clone())))),
-            FilterOp::Lte => Ok(Some(format!("{} <= {}", quoted_column, self.param(condition.data.clone())))),
-            FilterOp::Like => Ok(Some(format!("{} LIKE {}", quoted_column, self.param(condition.data.clone())))),
-            FilterOp::ILike => Ok(Some(format!("{} ILIKE {}", quoted_column, self.param(condition.data.clone())))),
+                else { Ok(Some(format!("{} <> {}", compared, self.param(condition.data.clone())))) }
+            }
+            FilterOp::Gt => Ok(Some(format!("{} > {}", compared, self.param(condition.data.clone())))),
+            FilterOp::Gte => Ok(Some(format!("{} >= {}", compared, self.param(condition.data.clone())))),
+            FilterOp::Lt => Ok(Some(format!("{} < {}", compared, self.param(condition.data.clone())))),
+            FilterOp::Lte => Ok(Some(format!("{} <= {}", compared, self.param(condition.data.clone())))),
+            FilterOp::Like => Ok(Some(format!("{} LIKE {}", compared, self.param(condition.data.clone())))),
+            FilterOp::ILike if self.unaccent => {
+                Ok(Some(format!("unaccent({}) ILIKE unaccent({})", compared, self.param(condition.data.clone()))))
+            }
+            FilterOp::ILike => Ok(Some(format!("{} ILIKE {}", compared, self.param(condition.data.clone())))),
             FilterOp::In => {
                 if let Value::Array(values) = &condition.data {
                     if values.is_empty() { return Ok(Some("1=0".to_string())); }
                     let params: Vec<String> = values.iter().map(|v| self.param(v.clone())).collect();
-                    Ok(Some(format!("{} IN ({})", quoted_column, params.join(", "))))
+                    Ok(Some(format!("{} IN ({})", compared, params.join(", "))))
                 } else {
-                    Ok(Some(format!("{} = {}", quoted_column, self.param(condition.data.clone()))))
+                    Ok(Some(format!("{} = {}", compared, self.param(condition.data.clone()))))
                 }
             }
             FilterOp::Between => {
                 if let Value::Array(values) = &condition.data {
                     if values.len() != 2 { return Err(FilterError::InvalidOperatorData("$between requires exactly 2 values".to_string())); }
-                    Ok(Some(format!("{} BETWEEN {} AND {}", quoted_column, self.param(values[0].clone()), self.param(values[1].clone()))))
+                    Ok(Some(format!("{} BETWEEN {} AND {}", compared, self.param(values[0].clone()), self.param(values[1].clone()))))
                 } else { Err(FilterError::InvalidOperatorData("$between requires array with 2 values".to_string())) }
             }
             FilterOp::Any => {
@@ -204,13 +378,364 @@ impl FilterWhere {
                 }
             }
             FilterOp::Size => Ok(Some(format!("array_length({}, 1) = {}", quoted_column, self.param(condition.data.clone())))),
+            FilterOp::Regex | FilterOp::IRegex | FilterOp::NRegex | FilterOp::NIRegex => {
+                let Value::String(pattern) = &condition.data else {
+                    return Err(FilterError::InvalidOperatorData("Regex operators require a pattern string".to_string()));
+                };
+                let sql_op = match condition.operator {
+                    FilterOp::Regex => "~",
+                    FilterOp::IRegex => "~*",
+                    FilterOp::NRegex => "!~",
+                    _ => "!~*",
+                };
+                Ok(Some(format!("{} {} {}", compared, sql_op, self.param(Value::String(pattern.clone())))))
+            }
+            // { field: true } tests presence; a key or array of keys tests a JSONB field's top-level keys
+            FilterOp::Exists => match &condition.data {
+                Value::Bool(true) => Ok(Some(format!("{} IS NOT NULL", quoted_column))),
+                Value::Bool(false) => Ok(Some(format!("{} IS NULL", quoted_column))),
+                Value::String(key) => Ok(Some(format!("{} ? {}", quoted_column, self.param(Value::String(key.clone()))))),
+                Value::Array(keys) if keys.iter().all(Value::is_string) => {
+                    Ok(Some(format!("{} ?& {}::text[]", quoted_column, self.param(Value::String(Self::text_array(keys))))))
+                }
+                _ => Err(FilterError::InvalidOperatorData("$exists requires true, false, a key or an array of keys".to_string())),
+            },
+            FilterOp::Null => match condition.data {
+                Value::Bool(true) => Ok(Some(format!("{} IS NULL", quoted_column))),
+                Value::Bool(false) => Ok(Some(format!("{} IS NOT NULL", quoted_column))),
+                _ => Err(FilterError::InvalidOperatorData("$null requires true or false".to_string())),
+            },
+            // JSONB containment; the value is sent as JSON text so scalars and arrays cast alike
+            FilterOp::Contains => Ok(Some(format!("{} @> {}::jsonb", quoted_column, self.param(Value::String(condition.data.to_string()))))),
             _ => Ok(None),
         }
     }
 
+    /// PostgreSQL text[] literal of the strings in `values`, quoted and escaped
+    fn text_array(values: &[Value]) -> String {
+        let items: Vec<String> = values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|s| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect();
+        format!("{{{}}}", items.join(","))
+    }
+
+    /// SQL for a `$and`/`$or`/`$not` member, one level deeper than this clause and
+    /// numbering its placeholders on from it
+    fn generate_nested(&self, where_data: &Value) -> Result<(String, Vec<Value>), FilterError> {
+        let max_depth = crate::config::CONFIG.filter.max_nested_depth;
+        if self.depth >= max_depth {
+            return Err(FilterError::NestingTooDeep(format!(
+                "$and, $or and $not groups may be nested at most {} levels deep", max_depth
+            )));
+        }
+        let mut nested = Self::new(self.param_index);
+        nested.depth = self.depth + 1;
+        nested.build(where_data, &self.nested_options())
+    }
+
+    fn nested_options(&self) -> FilterWhereOptions {
+        FilterWhereOptions {
+            table_alias: self.table_alias.clone(),
+            unaccent: self.unaccent,
+            json_columns: self.json_columns.clone(),
+            schema_name: self.schema_name.clone(),
+            ..FilterWhereOptions::default()
+        }
+    }
+
+    /// Split a where-clause key into a column and a path into the JSON document it holds
+    ///
+    /// `profile->address->city` always addresses JSON; `profile.address.city` does when
+    /// `profile` is one of `json_columns`, and is a relationship path otherwise. The column
+    /// of an arrow path may itself be a relationship path, as in `customer.profile->tier`.
+    pub fn json_path<'a>(key: &'a str, json_columns: &[String]) -> Option<(&'a str, Vec<&'a str>)> {
+        if let Some((column, path)) = key.split_once("->") {
+            return Some((column, path.split("->").map(|s| s.trim_start_matches('>')).collect()));
+        }
+        let (column, path) = key.split_once('.')?;
+        json_columns.iter().any(|c| c == column).then(|| (column, path.split('.').collect()))
+    }
+
+    /// SQL for the column a condition tests
+    ///
+    /// A JSON path becomes `"profile"->$1->>$2` with its keys bound as parameters; all-digit
+    /// keys index arrays. The extracted text is cast to numeric or boolean to compare with
+    /// such values, while `$contains` and key tests with `$exists` get the jsonb value.
+    fn column_sql(&mut self, condition: &FilterWhereInfo) -> Result<String, FilterError> {
+        let Some((column, path)) = Self::json_path(&condition.column, &self.json_columns) else {
+            return self.quote_column(&condition.column);
+        };
+        let max_depth = crate::config::CONFIG.filter.max_nested_depth;
+        if path.len() as u32 > max_depth {
+            return Err(FilterError::InvalidColumn(format!(
+                "Path '{}' exceeds the maximum nesting depth of {}", condition.column, max_depth
+            )));
+        }
+        if column.is_empty() || path.iter().any(|key| key.is_empty()) {
+            return Err(FilterError::InvalidColumn(format!("Invalid JSON path '{}'", condition.column)));
+        }
+        if matches!(condition.operator, FilterOp::Any | FilterOp::All | FilterOp::Size) {
+            return Err(FilterError::InvalidOperatorData(format!("Array operators do not apply to JSON path '{}'", condition.column)));
+        }
+
+        let as_json = match condition.operator {
+            FilterOp::Contains => true,
+            FilterOp::Exists => !condition.data.is_boolean(),
+            _ => false,
+        };
+        let mut sql = self.quote_column(column)?;
+        for (i, key) in path.iter().enumerate() {
+            let arrow = if i + 1 == path.len() && !as_json { "->>" } else { "->" };
+            let key = match key.parse::<i64>() {
+                Ok(index) => format!("{}::int", self.param(Value::from(index))),
+                Err(_) => self.param(Value::String(key.to_string())),
+            };
+            sql = format!("{}{}{}", sql, arrow, key);
+        }
+        if as_json || matches!(condition.operator, FilterOp::Exists | FilterOp::Null) {
+            return Ok(sql);
+        }
+
+        let compared = match &condition.data {
+            Value::Array(values) => values.first().unwrap_or(&Value::Null),
+            value => value,
+        };
+        Ok(match compared {
+            Value::Number(_) => format!("({})::numeric", sql),
+            Value::Bool(_) => format!("({})::boolean", sql),
+            _ => sql,
+        })
+    }
+
+    fn table_prefix(table_alias: Option<&str>) -> String {
+        table_alias.map(|t| format!("\"{}\".", t)).unwrap_or_default()
+    }
+
+    /// Quote a column, qualifying it when joins are in play
+    ///
+    /// Dotted names refer to related schemas: "customer.region.name" becomes
+    /// "customer__region"."name", matching the aliases produced by `FilterJoin`. Fields of
+    /// related schemas are not in the known-column check, so every segment must be a
+    /// plain identifier before it is quoted.
+    fn quote_column(&self, column: &str) -> Result<String, FilterError> {
+        if !column.split('.').all(Self::is_identifier) {
+            return Err(FilterError::InvalidColumn(format!("Invalid column name format: {}", column)));
+        }
+        Ok(match column.rsplit_once('.') {
+            Some((path, field)) => format!("\"{}\".\"{}\"", FilterJoin::alias(path), field),
+            None => format!("{}\"{}\"", Self::table_prefix(self.table_alias.as_deref()), column),
+        })
+    }
+
+    fn is_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+    }
+
     fn param(&mut self, value: Value) -> String {
         self.param_values.push(value);
         self.param_index += 1;
         format!("${}", self.param_index)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_case_and_accent_insensitive_matching() {
+        let options = FilterWhereOptions { include_trashed: true, include_deleted: true, ..Default::default() };
+        let (sql, params) = FilterWhere::generate(&json!({ "email": { "$ieq": "Ann@Example.com" } }), 0, &options).unwrap();
+        assert_eq!(sql, "LOWER(\"email\") = LOWER($1)");
+        assert_eq!(params, vec![json!("Ann@Example.com")]);
+
+        let (sql, _) = FilterWhere::generate(&json!({ "name": { "$gte": "m", "$collate": "und-x-icu" } }), 0, &options).unwrap();
+        assert_eq!(sql, "\"name\" COLLATE \"und-x-icu\" >= $1");
+        assert!(FilterWhere::generate(&json!({ "name": { "$eq": "x", "$collate": "C\" x" } }), 0, &options).is_err());
+
+        let unaccent = FilterWhereOptions { unaccent: true, ..options };
+        let (sql, _) = FilterWhere::generate(&json!({ "$or": [{ "city": { "$ilike": "%sao%" } }] }), 0, &unaccent).unwrap();
+        assert!(sql.ends_with("unaccent(\"city\") ILIKE unaccent($1)))"), "{}", sql);
+        assert!(FilterWhere::uses_operator(&json!({ "$or": [{ "city": { "$ilike": "%sao%" } }] }), "$ilike"));
+        assert!(!FilterWhere::uses_operator(&json!({ "city": "sao" }), "$ilike"));
+    }
+
+    #[test]
+    fn test_presence_containment_and_regex_operators() {
+        let options = FilterWhereOptions { include_trashed: true, include_deleted: true, ..Default::default() };
+        let where_data = json!({
+            "email": { "$exists": true },
+            "manager_id": { "$null": true },
+            "meta": { "$contains": { "tier": "gold" }, "$exists": ["region", "say \"hi\""] },
+            "sku": { "$iregex": "^ab-[0-9]+$" },
+        });
+        let (sql, params) = FilterWhere::generate(&where_data, 0, &options).unwrap();
+        assert_eq!(
+            sql,
+            "\"email\" IS NOT NULL AND \"manager_id\" IS NULL AND \"meta\" @> $1::jsonb AND \"meta\" ?& $2::text[] AND \"sku\" ~* $3"
+        );
+        assert_eq!(params, vec![json!("{\"tier\":\"gold\"}"), json!("{\"region\",\"say \\\"hi\\\"\"}"), json!("^ab-[0-9]+$")]);
+
+        assert!(FilterWhere::generate(&json!({ "sku": { "$regex": 5 } }), 0, &options).is_err());
+        assert!(FilterWhere::generate(&json!({ "email": { "$null": "yes" } }), 0, &options).is_err());
+    }
+
+    #[test]
+    fn test_jsonb_paths() {
+        let options = FilterWhereOptions {
+            include_trashed: true,
+            include_deleted: true,
+            json_columns: vec!["profile".to_string()],
+            ..Default::default()
+        };
+        let where_data = json!({
+            "profile.address.city": { "$eq": "Oslo" },
+            "profile->age": { "$gte": 18 },
+            "settings->tags->0": "admin",
+            "settings->flags": { "$contains": { "beta": true } },
+        });
+        let (sql, params) = FilterWhere::generate(&where_data, 0, &options).unwrap();
+        assert_eq!(
+            sql,
+            "(\"profile\"->>$1)::numeric >= $2 AND \"profile\"->$3->>$4 = $5 \
+             AND \"settings\"->$6 @> $7::jsonb AND \"settings\"->$8->>$9::int = $10"
+        );
+        assert_eq!(params, vec![
+            json!("age"), json!(18), json!("address"), json!("city"), json!("Oslo"),
+            json!("flags"), json!("{\"beta\":true}"), json!("tags"), json!(0), json!("admin"),
+        ]);
+
+        // Without the column declared as JSON, a dotted key stays a relationship path
+        let (sql, _) = FilterWhere::generate(&json!({ "customer.name": "Ann" }), 0, &options).unwrap();
+        assert_eq!(sql, "\"customer\".\"name\" = $1");
+
+        let max_depth = crate::config::CONFIG.filter.max_nested_depth as usize;
+        let too_deep = format!("profile{}", "->k".repeat(max_depth + 1));
+        assert!(FilterWhere::generate(&json!({ too_deep: 1 }), 0, &options).is_err());
+        assert!(FilterWhere::generate(&json!({ "profile->tags": { "$size": 2 } }), 0, &options).is_err());
+    }
+
+    #[test]
+    fn test_tagged_operator() {
+        let options = FilterWhereOptions {
+            include_trashed: true,
+            include_deleted: true,
+            schema_name: Some("tickets".to_string()),
+            ..Default::default()
+        };
+        let where_data = json!({ "status": "open", "$or": [{ "$tagged": ["vip", "urgent", "vip"] }, { "$tagged": "billing" }] });
+        let (sql, params) = FilterWhere::generate(&where_data, 0, &options).unwrap();
+        assert!(sql.contains(
+            "\"id\" IN (SELECT rt.\"record_id\" FROM \"record_tags\" rt JOIN \"tags\" t ON t.\"id\" = rt.\"tag_id\" \
+             WHERE rt.\"schema_name\" = $1 AND t.\"name\" IN ($2, $3) AND t.\"trashed_at\" IS NULL AND t.\"deleted_at\" IS NULL \
+             GROUP BY rt.\"record_id\" HAVING COUNT(*) = 2)"
+        ), "{}", sql);
+        assert!(sql.contains("rt.\"schema_name\" = $4 AND t.\"name\" IN ($5)"), "{}", sql);
+        assert_eq!(params, vec![json!("tickets"), json!("urgent"), json!("vip"), json!("tickets"), json!("billing"), json!("open")]);
+        assert!(crate::filter::sql_audit::inspect(&sql, params.len()).is_ok());
+
+        let (sql, _) = FilterWhere::generate(&json!({ "$tagged": [] }), 0, &options).unwrap();
+        assert_eq!(sql, "1=0");
+        assert!(FilterWhere::generate(&json!({ "$tagged": [1] }), 0, &options).is_err());
+        let untagged = FilterWhereOptions { schema_name: None, ..options };
+        assert!(FilterWhere::generate(&json!({ "$tagged": "vip" }), 0, &untagged).is_err());
+    }
+
+    #[test]
+    fn test_search_operator_and_relevance_order() {
+        let options = FilterWhereOptions { include_trashed: true, include_deleted: true, ..Default::default() };
+        let (sql, params) = FilterWhere::generate(&json!({ "$search": "\"quick fox\" -lazy" }), 0, &options).unwrap();
+        assert_eq!(sql, "\"search_vector\" @@ websearch_to_tsquery('english'::regconfig, $1)");
+        assert_eq!(params, vec![json!("\"quick fox\" -lazy")]);
+        assert!(FilterWhere::generate(&json!({ "$search": " " }), 0, &options).is_err());
+
+        let mut filter = crate::filter::Filter::new("articles").unwrap();
+        filter.assign(crate::filter::FilterData {
+            where_clause: Some(json!({ "$search": "fox", "status": "published" })),
+            limit: Some(10),
+            ..Default::default()
+        }).unwrap();
+        let result = filter.to_sql().unwrap();
+        assert!(result.query.ends_with(
+            "ORDER BY ts_rank(\"search_vector\", websearch_to_tsquery('english'::regconfig, $3)) DESC, \"id\" ASC LIMIT 10"
+        ), "{}", result.query);
+        assert_eq!(result.params[2], json!("fox"));
+
+        let mut unsearchable = crate::filter::Filter::new("articles").unwrap();
+        unsearchable.columns(vec!["id".to_string(), "title".to_string()]);
+        assert!(unsearchable.where_clause(json!({ "$or": [{ "$search": "fox" }] })).is_err());
+    }
+
+    #[test]
+    fn test_logical_nesting_depth_limit() {
+        let options = FilterWhereOptions { include_trashed: true, include_deleted: true, ..Default::default() };
+        let max_depth = crate::config::CONFIG.filter.max_nested_depth;
+        let nest = |levels: u32| (0..levels).fold(json!({ "a": 1 }), |inner, _| json!({ "$or": [inner] }));
+
+        let (sql, params) = FilterWhere::generate(&nest(max_depth), 0, &options).unwrap();
+        assert!(sql.contains("\"a\" = $1"), "{}", sql);
+        assert_eq!(params, vec![json!(1)]);
+
+        let err = FilterWhere::generate(&nest(max_depth + 1), 0, &options).unwrap_err();
+        assert!(matches!(err, FilterError::NestingTooDeep(_)));
+        let not = (0..=max_depth).fold(json!({ "a": 1 }), |inner, _| json!({ "$not": inner }));
+        assert!(FilterWhere::generate(&not, 0, &options).is_err());
+    }
+
+    #[test]
+    fn test_unknown_columns_are_rejected() {
+        let json_columns = vec!["profile".to_string()];
+        let where_data = json!({
+            "name": "Ada",
+            "$or": [{ "profile.address.city": "Oslo" }, { "settings->theme": "dark" }],
+            "customer.country": "NO",
+        });
+        let columns: Vec<String> = FilterWhere::columns(&where_data, &json_columns).into_iter().collect();
+        assert_eq!(columns, vec!["name", "profile", "settings"]);
+
+        let mut filter = crate::filter::Filter::new("users").unwrap();
+        filter.columns(vec!["id".to_string(), "name".to_string(), "profile".to_string()]).json_columns(json_columns);
+        let err = filter.where_clause(where_data).err().unwrap();
+        assert!(matches!(err, FilterError::UnknownColumn { ref column, clause: "where" } if column == "settings"));
+        let err = filter.order(json!("name asc, nmae desc")).err().unwrap();
+        assert!(matches!(err, FilterError::UnknownColumn { ref column, clause: "order" } if column == "nmae"));
+        assert!(filter.order(json!({ "name\" desc": "asc" })).is_err());
+        assert!(filter.select(vec!["*".to_string(), "name".to_string()]).is_ok());
+    }
+
+    #[test]
+    fn test_column_identifiers_cannot_inject_sql() {
+        let options = FilterWhereOptions { include_trashed: true, include_deleted: true, ..Default::default() };
+        let (sql, _) = FilterWhere::generate(&json!({ "customer.region.name": "EU" }), 0, &options).unwrap();
+        assert_eq!(sql, "\"customer__region\".\"name\" = $1");
+
+        // Relationship fields skip the known-column check, so the field itself is vetted
+        for key in ["customer.id\" IS NOT NULL OR \"x", "customer\" OR 1=1 --.id", "name\" OR \"x", "customer..id"] {
+            let err = FilterWhere::generate(&json!({ key: 1 }), 0, &options).err().unwrap();
+            assert!(matches!(err, FilterError::InvalidColumn(_)), "{}: {:?}", key, err);
+        }
+        let nested = json!({ "$or": [{ "customer.id\" IS NOT NULL OR \"x": 1 }] });
+        assert!(FilterWhere::generate(&nested, 0, &options).is_err());
+        let json_path = json!({ "customer.x\" OR \"y->tier": "gold" });
+        assert!(FilterWhere::generate(&json_path, 0, &options).is_err());
+    }
+
+    #[test]
+    fn test_select_projection_includes_id() {
+        let mut filter = crate::filter::Filter::new("users").unwrap();
+        filter.columns(vec!["id".to_string(), "name".to_string(), "email".to_string()]);
+        filter.select(vec!["name".to_string(), "email".to_string(), "name".to_string()]).unwrap();
+        let sql = filter.to_sql().unwrap();
+        assert!(sql.query.starts_with("SELECT \"id\", \"name\", \"email\" FROM \"users\""), "{}", sql.query);
+
+        let err = filter.select(vec!["name".to_string(), "pasword".to_string()]).err().unwrap();
+        assert!(matches!(err, FilterError::UnknownColumn { ref column, clause: "select" } if column == "pasword"));
+
+        filter.select(vec![]).unwrap();
+        assert!(filter.to_sql().unwrap().query.starts_with("SELECT * FROM"));
+    }
+}
@@ -2,6 +2,12 @@ pub mod types;
 pub mod filter;
 pub mod filter_where;
 pub mod filter_order;
+pub mod filter_group;
+pub mod filter_aggregate;
+pub mod filter_join;
+pub mod filter_cursor;
+pub mod filter_search;
+pub mod sql_audit;
 pub mod error;
 
 pub use types::*;
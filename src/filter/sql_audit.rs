@@ -0,0 +1,143 @@
+use thiserror::Error;
+
+/// A generated SQL string that does not look fully parameterized
+#[derive(Debug, Error, PartialEq)]
+#[error("{reason} at byte {position}")]
+pub struct SqlAuditViolation {
+    pub reason: &'static str,
+    pub position: usize,
+}
+
+/// What to do when a generated statement fails the audit (`database.sql_audit_mode`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SqlAuditMode {
+    Off,
+    Log,
+    Panic,
+}
+
+impl SqlAuditMode {
+    fn configured() -> Self {
+        match crate::config::config().database.sql_audit_mode.as_str() {
+            "panic" => Self::Panic,
+            "log" => Self::Log,
+            _ => Self::Off,
+        }
+    }
+}
+
+/// Safety net for the dynamic SQL builders, run before a generated statement executes
+///
+/// Logs (or panics, in `panic` mode) when the statement fails `inspect`. Hand-written
+/// administrative SQL such as DDL does not go through here.
+pub fn audit(query: &str, param_count: usize) {
+    let mode = SqlAuditMode::configured();
+    if mode == SqlAuditMode::Off {
+        return;
+    }
+
+    if let Err(violation) = inspect(query, param_count) {
+        tracing::error!("SQL audit violation: {} in generated query: {}", violation, query);
+        if mode == SqlAuditMode::Panic {
+            panic!("SQL audit violation: {} in generated query: {}", violation, query);
+        }
+    }
+}
+
+/// Lexically check that user values can only have reached `query` through placeholders
+///
+/// Generated SQL may contain quoted identifiers, numbers and short keyword-like string
+/// literals (date_trunc units, channel names), but never statement separators, comments,
+/// dollar quoting, escaped quotes or backslashes, or a placeholder with no bound value.
+pub fn inspect(query: &str, param_count: usize) -> Result<(), SqlAuditViolation> {
+    let bytes = query.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                // Quoted identifier; builders reject identifiers containing quotes
+                let end = find_byte(bytes, i + 1, b'"').ok_or(SqlAuditViolation {
+                    reason: "unterminated quoted identifier",
+                    position: i,
+                })?;
+                if bytes.get(end + 1) == Some(&b'"') {
+                    return Err(SqlAuditViolation { reason: "escaped quote in identifier", position: end });
+                }
+                i = end + 1;
+            }
+            b'\'' => {
+                let end = find_byte(bytes, i + 1, b'\'').ok_or(SqlAuditViolation {
+                    reason: "unterminated string literal",
+                    position: i,
+                })?;
+                if bytes.get(end + 1) == Some(&b'\'') {
+                    return Err(SqlAuditViolation { reason: "escaped quote in string literal", position: end });
+                }
+                if i > 0 && matches!(bytes[i - 1], b'E' | b'e') && !is_word_byte(bytes.get(i.wrapping_sub(2)).copied()) {
+                    return Err(SqlAuditViolation { reason: "escape string literal", position: i - 1 });
+                }
+                let literal = &bytes[i + 1..end];
+                if literal.len() > 64 || !literal.iter().all(|b| b.is_ascii_alphanumeric() || b"_ .:/-{}".contains(b)) {
+                    return Err(SqlAuditViolation { reason: "string literal in value position", position: i });
+                }
+                i = end + 1;
+            }
+            b';' => {
+                if !query[i + 1..].trim().is_empty() {
+                    return Err(SqlAuditViolation { reason: "multiple statements", position: i });
+                }
+                i += 1;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                return Err(SqlAuditViolation { reason: "line comment", position: i });
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                return Err(SqlAuditViolation { reason: "block comment", position: i });
+            }
+            b'$' => {
+                let digits = bytes[i + 1..].iter().take_while(|b| b.is_ascii_digit()).count();
+                if digits == 0 {
+                    return Err(SqlAuditViolation { reason: "dollar-quoted string", position: i });
+                }
+                let index: usize = query[i + 1..i + 1 + digits].parse().unwrap_or(usize::MAX);
+                if index == 0 || index > param_count {
+                    return Err(SqlAuditViolation { reason: "placeholder without a bound parameter", position: i });
+                }
+                i += 1 + digits;
+            }
+            _ => i += 1,
+        }
+    }
+
+    Ok(())
+}
+
+fn find_byte(bytes: &[u8], from: usize, needle: u8) -> Option<usize> {
+    bytes[from..].iter().position(|&b| b == needle).map(|p| from + p)
+}
+
+fn is_word_byte(byte: Option<u8>) -> bool {
+    matches!(byte, Some(b) if b.is_ascii_alphanumeric() || b == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_parameterized_queries() {
+        assert!(inspect(r#"SELECT "t".* FROM "t" WHERE "name" = $1 AND "trashed_at" IS NULL LIMIT 10"#, 1).is_ok());
+        assert!(inspect(r#"SELECT date_trunc('day', "created_at") AS "day" FROM "t" GROUP BY 1"#, 0).is_ok());
+        assert!(inspect(r#"INSERT INTO "t" ("a") VALUES ($1) RETURNING *;"#, 1).is_ok());
+    }
+
+    #[test]
+    fn rejects_inlined_values() {
+        assert!(inspect(r#"SELECT * FROM "t" WHERE "name" = 'O''Brien'"#, 0).is_err());
+        assert!(inspect(r#"SELECT * FROM "t" WHERE "name" = 'x' OR 1=1 --'"#, 0).is_err());
+        assert!(inspect(r#"SELECT * FROM "t"; DROP TABLE "t""#, 0).is_err());
+        assert!(inspect(r#"SELECT * FROM "t" WHERE "name" = E'\x27'"#, 0).is_err());
+        assert!(inspect(r#"SELECT * FROM "t" WHERE "id" = $2"#, 1).is_err());
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "snake_case")]
 pub enum FilterOp {
     #[serde(rename = "$eq")] Eq,
+    #[serde(rename = "$ieq")] IEq,
     #[serde(rename = "$ne")] Ne,
     #[serde(rename = "$neq")] Neq,
     #[serde(rename = "$gt")] Gt,
@@ -16,7 +17,9 @@ pub enum FilterOp {
     #[serde(rename = "$ilike")] ILike,
     #[serde(rename = "$nilike")] NILike,
     #[serde(rename = "$regex")] Regex,
+    #[serde(rename = "$iregex")] IRegex,
     #[serde(rename = "$nregex")] NRegex,
+    #[serde(rename = "$niregex")] NIRegex,
 
     #[serde(rename = "$in")] In,
     #[serde(rename = "$nin")] NIn,
@@ -40,6 +43,7 @@ pub enum FilterOp {
 
     #[serde(rename = "$exists")] Exists,
     #[serde(rename = "$null")] Null,
+    #[serde(rename = "$contains")] Contains,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -49,6 +53,46 @@ pub struct FilterData {
     pub order: Option<serde_json::Value>,
     pub limit: Option<i32>,
     pub offset: Option<i32>,
+    /// Rank rows within groups, e.g. latest record per customer
+    pub rank_by: Option<FilterRankBy>,
+    /// Rows kept per `rank_by` group. Default: 1
+    pub top_n_per_group: Option<i32>,
+    /// `next_cursor` from the previous page's response meta: continue after that page's
+    /// last row, in the same order, see `FilterCursor`
+    pub cursor: Option<String>,
+}
+
+impl FilterData {
+    /// Limit a read that names none to `max_limit`, so API listings are never unbounded;
+    /// bulk updates and deletes by filter keep matching every row
+    pub fn default_limit(&mut self, max_limit: Option<i32>) {
+        if self.limit.is_none() {
+            self.limit = max_limit;
+        }
+    }
+}
+
+/// Window ranking spec: rows are numbered within each `partition` group by `order`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FilterRankBy {
+    pub partition: Vec<String>,
+    /// Same formats as FilterData.order, e.g. "created_at desc"
+    pub order: Option<serde_json::Value>,
+}
+
+/// Body of an aggregate query: filter conditions, GROUP BY specs, aggregate functions and
+/// conditions on their results
+///
+/// `group_by` entries are column names or bucket objects, see `FilterGroup`;
+/// `aggregations` and `having` are described in `FilterAggregate`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AggregateData {
+    pub where_clause: Option<serde_json::Value>,
+    pub group_by: Option<serde_json::Value>,
+    /// Default: a single `count` of rows per group
+    pub aggregations: Option<serde_json::Value>,
+    pub having: Option<serde_json::Value>,
+    pub limit: Option<i32>,
 }
 
 #[derive(Debug, Clone)]
@@ -56,12 +100,25 @@ pub struct FilterWhereInfo {
     pub column: String,
     pub operator: FilterOp,
     pub data: serde_json::Value,
+    /// Collation the column is compared under, from the field's `$collate` key
+    pub collation: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct FilterWhereOptions {
     pub include_trashed: bool,
     pub include_deleted: bool,
+    /// Qualify unprefixed columns with this table; set when the query joins related schemas
+    pub table_alias: Option<String>,
+    /// Only rows whose access_* lists admit one of these ids; None skips the check
+    pub access: Option<RecordAccess>,
+    /// Compare `$ilike` patterns with accents stripped; needs the unaccent extension
+    pub unaccent: bool,
+    /// JSONB columns, whose dotted where-clause keys are paths into the document rather
+    /// than relationship paths
+    pub json_columns: Vec<String>,
+    /// Schema of the queried table, whose record tags `$tagged` conditions look up
+    pub schema_name: Option<String>,
 }
 
 impl Default for FilterWhereOptions {
@@ -69,10 +126,49 @@ impl Default for FilterWhereOptions {
         Self {
             include_trashed: false,
             include_deleted: false,
+            table_alias: None,
+            access: None,
+            unaccent: false,
+            json_columns: vec![],
+            schema_name: None,
         }
     }
 }
 
+/// Ids a query's rows are checked against: the user's own id plus any group ids it acts for
+///
+/// A row is visible when none of the ids is in `access_deny`, and either one of them is in
+/// `access_read`, `access_edit` or `access_full`, or all three of those lists are empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordAccess {
+    pub ids: Vec<uuid::Uuid>,
+}
+
+impl RecordAccess {
+    pub fn new(ids: Vec<uuid::Uuid>) -> Self {
+        Self { ids }
+    }
+
+    /// PostgreSQL array literal of the ids, bound as one parameter and cast to uuid[]
+    pub fn to_array_literal(&self) -> String {
+        format!("{{{}}}", self.ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(","))
+    }
+
+    /// Condition that a row may be changed with the ids bound at `param` as uuid[]
+    ///
+    /// None of the ids is in `access_deny`, and either one is in `access_edit` or
+    /// `access_full`, or the row has no grants at all. NULL lists count as empty.
+    pub fn editable_condition(param: &str) -> String {
+        format!(
+            "NOT COALESCE(\"access_deny\" && {param}::uuid[], false) AND (\
+                 COALESCE(cardinality(\"access_read\" || \"access_edit\" || \"access_full\"), 0) = 0 \
+                 OR COALESCE((\"access_edit\" || \"access_full\") && {param}::uuid[], false)\
+             )",
+            param = param
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum SortDirection {
     Asc,
@@ -94,8 +190,199 @@ pub struct FilterOrderInfo {
     pub sort: SortDirection,
 }
 
+/// Parsed `rank_by` compiled to ROW_NUMBER() OVER (PARTITION BY ... ORDER BY ...)
+#[derive(Debug, Clone)]
+pub struct FilterRankInfo {
+    pub partition: Vec<String>,
+    pub order: Vec<FilterOrderInfo>,
+    pub top_n: i32,
+}
+
 #[derive(Debug, Clone)]
 pub struct SqlResult {
     pub query: String,
     pub params: Vec<serde_json::Value>,
 }
+
+/// A single GROUP BY expression and the output column it is returned as
+#[derive(Debug, Clone)]
+pub struct FilterGroupInfo {
+    pub alias: String,
+    pub expr: GroupExpr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupExpr {
+    /// Plain column value
+    Column(String),
+    /// date_trunc(unit, column)
+    DateBucket { column: String, unit: String },
+    /// Lower bound of the width_bucket() bucket containing column
+    Histogram { column: String, width: f64, min: f64, max: f64 },
+}
+
+impl GroupExpr {
+    /// The column the expression reads
+    pub fn column(&self) -> &str {
+        match self {
+            GroupExpr::Column(column) => column,
+            GroupExpr::DateBucket { column, .. } | GroupExpr::Histogram { column, .. } => column,
+        }
+    }
+}
+
+/// An aggregate function over a column and the output column it is returned as
+#[derive(Debug, Clone)]
+pub struct FilterAggregateInfo {
+    pub alias: String,
+    pub function: AggregateFn,
+    /// None for COUNT(*)
+    pub column: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateFn {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl AggregateFn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AggregateFn::Count => "count",
+            AggregateFn::Sum => "sum",
+            AggregateFn::Avg => "avg",
+            AggregateFn::Min => "min",
+            AggregateFn::Max => "max",
+        }
+    }
+}
+
+/// A HAVING comparison between an aggregate's result and a bound value
+#[derive(Debug, Clone)]
+pub struct FilterHavingInfo {
+    /// Alias of the aggregate being compared
+    pub alias: String,
+    /// SQL comparison operator, e.g. ">="
+    pub operator: &'static str,
+    pub value: serde_json::Value,
+}
+
+/// Declared many-to-one edge (x-monk-relationship) that where-clause paths may traverse
+///
+/// `table.column` references `related_table.related_column`; `name` is the path segment
+/// used in filters, e.g. `{ "customer.country": "NO" }` on orders.
+#[derive(Debug, Clone)]
+pub struct FilterRelationship {
+    pub table: String,
+    pub name: String,
+    pub column: String,
+    pub related_table: String,
+    pub related_column: String,
+    /// The foreign key is mandatory, so every row has a related row
+    pub required: bool,
+}
+
+/// A resolved join for one relationship path
+#[derive(Debug, Clone)]
+pub struct FilterJoinInfo {
+    /// Dotted relationship path, e.g. "customer.region"
+    pub path: String,
+    pub alias: String,
+    pub relationship: FilterRelationship,
+    /// Alias of the table this join hangs off
+    pub parent_alias: String,
+    /// Every hop up to the base table is required, so INNER JOIN is safe
+    pub inner: bool,
+}
+
+/// How a total row count is produced for list and find results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountMode {
+    /// COUNT(*) for filtered queries, the maintained record_counts row otherwise
+    Exact,
+    /// Planner statistics: pg_class.reltuples, or the plan's row estimate when filtered
+    Estimated,
+    None,
+}
+
+impl CountMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "exact" => Some(CountMode::Exact),
+            "estimated" | "estimate" => Some(CountMode::Estimated),
+            "none" => Some(CountMode::None),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CountMode::Exact => "exact",
+            CountMode::Estimated => "estimated",
+            CountMode::None => "none",
+        }
+    }
+
+    /// Mode from `filter.default_count_mode`, falling back to none when misconfigured
+    pub fn config_default() -> Self {
+        Self::parse(&crate::config::CONFIG.filter.default_count_mode).unwrap_or(CountMode::None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_default_limit() {
+        let mut absent = FilterData { offset: Some(20), ..Default::default() };
+        absent.default_limit(Some(1000));
+        assert_eq!((absent.limit, absent.offset), (Some(1000), Some(20)));
+
+        let mut given = FilterData { limit: Some(5), ..Default::default() };
+        given.default_limit(Some(1000));
+        assert_eq!(given.limit, Some(5));
+
+        let mut unconfigured = FilterData::default();
+        unconfigured.default_limit(None);
+        assert_eq!(unconfigured.limit, None);
+    }
+
+    #[tokio::test]
+    async fn test_editable_condition() {
+        let Some(pool) = crate::testing::scratch_pool(
+            "CREATE TEMPORARY TABLE records (name text, access_read uuid[], access_edit uuid[], access_full uuid[], access_deny uuid[])"
+        ).await else { return };
+        let user = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let rows: &[(&str, &[Uuid], &[Uuid], &[Uuid], &[Uuid])] = &[
+            ("open", &[], &[], &[], &[]),
+            ("read_only", &[user], &[], &[], &[]),
+            ("edit", &[], &[user], &[], &[]),
+            ("full", &[], &[], &[user], &[]),
+            ("others", &[], &[other], &[], &[]),
+            ("denied", &[], &[user], &[], &[user]),
+        ];
+        for (name, read, edit, full, deny) in rows {
+            sqlx::query("INSERT INTO records VALUES ($1, $2, $3, $4, $5)")
+                .bind(name).bind(read).bind(edit).bind(full).bind(deny)
+                .execute(&pool).await.unwrap();
+        }
+        sqlx::query("INSERT INTO records (name) VALUES ('null_lists')").execute(&pool).await.unwrap();
+
+        let editable: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT name FROM records WHERE {} ORDER BY name",
+            RecordAccess::editable_condition("$1")
+        ))
+            .bind(RecordAccess::new(vec![user]).to_array_literal())
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(editable, vec!["edit", "full", "null_lists", "open"]);
+    }
+}
@@ -0,0 +1,93 @@
+// handlers/elevated/root/diagnostics.rs - GET /api/root/diagnostics handler
+
+use axum::extract::Extension;
+use chrono::{DateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use serde_json::{json, Map, Value};
+
+use crate::database::manager::DatabaseManager;
+use crate::middleware::{AuthUser, ApiResponse, ApiResult};
+use crate::observer::{register_all_sql_executors, ObserverPipeline};
+
+static STARTED_AT: Lazy<DateTime<Utc>> = Lazy::new(Utc::now);
+
+/// Record the process start time; call once from main before serving
+pub fn mark_started() {
+    Lazy::force(&STARTED_AT);
+}
+
+/// GET /api/root/diagnostics - Build info and runtime state of this instance
+///
+/// Reports the build (git sha, rustc, build time), uptime, database pool usage and
+/// pool cache hit rate, active tenants, the observers registered per ring, and
+/// process memory from /proc (Linux only; null elsewhere).
+pub async fn root_diagnostics(
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let now = Utc::now();
+    let started_at = *STARTED_AT;
+
+    let (hits, misses) = DatabaseManager::pool_cache_stats();
+    let lookups = hits + misses;
+    let tenants = match crate::database::events::active_tenant_databases().await {
+        Ok(databases) => json!({ "active": databases.len(), "databases": databases }),
+        Err(e) => json!({ "error": e.to_string() }),
+    };
+
+    let mut pipeline = ObserverPipeline::new();
+    register_all_sql_executors(&mut pipeline);
+    let observers: Vec<Value> = pipeline
+        .summary()
+        .into_iter()
+        .map(|(ring, names)| json!({ "ring": ring as u8, "name": format!("{:?}", ring), "observers": names }))
+        .collect();
+
+    tracing::info!("Diagnostics requested by '{}'", auth_user.user);
+    Ok(ApiResponse::success(json!({
+        "build": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+            "git_sha": env!("MONK_GIT_SHA"),
+            "rustc": env!("MONK_RUSTC_VERSION"),
+            "built_at": build_time(),
+            "environment": format!("{:?}", crate::config::config().environment),
+        },
+        "uptime": {
+            "started_at": started_at,
+            "seconds": (now - started_at).num_seconds(),
+        },
+        "pools": DatabaseManager::pool_stats().await,
+        "pool_cache": {
+            "hits": hits,
+            "misses": misses,
+            "hit_rate": if lookups == 0 { Value::Null } else { json!(hits as f64 / lookups as f64) },
+        },
+        "tenants": tenants,
+        "observers": observers,
+        "memory": memory_stats(),
+    })))
+}
+
+fn build_time() -> Option<DateTime<Utc>> {
+    let seconds: i64 = env!("MONK_BUILD_TIMESTAMP").parse().ok()?;
+    Utc.timestamp_opt(seconds, 0).single().filter(|_| seconds > 0)
+}
+
+/// Resident, peak resident and virtual size in bytes, from /proc/self/status
+fn memory_stats() -> Value {
+    let Ok(status) = std::fs::read_to_string("/proc/self/status") else {
+        return Value::Null;
+    };
+
+    let mut stats = Map::new();
+    for (field, key) in [("VmRSS", "resident_bytes"), ("VmHWM", "peak_resident_bytes"), ("VmSize", "virtual_bytes")] {
+        let kilobytes = status
+            .lines()
+            .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok());
+        if let Some(kilobytes) = kilobytes {
+            stats.insert(key.to_string(), json!(kilobytes * 1024));
+        }
+    }
+    Value::Object(stats)
+}
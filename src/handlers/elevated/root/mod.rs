@@ -4,10 +4,14 @@
 // These handlers provide system-wide management capabilities.
 
 // Root operation modules
-pub mod tenant;  // Multi-tenant management operations
+pub mod tenant;       // Multi-tenant management operations
+pub mod diagnostics;  // GET /api/root/diagnostics
+pub mod webhooks;     // POST /api/root/webhooks/:id/test
 
 // Re-export tenant management handlers
 pub use tenant::*;
+pub use diagnostics::root_diagnostics;
+pub use webhooks::webhook_test;
 
 /*
 ROOT HANDLER ORGANIZATION:
@@ -22,6 +26,13 @@ Current Modules:
    - Database provisioning and health monitoring  
    - Cross-tenant administrative operations
 
+2. **Diagnostics** (/api/root/diagnostics):
+   - Build info, uptime and memory of the running instance
+   - Pool usage, active tenants and registered observers
+
+3. **Webhooks** (/api/root/webhooks/:id/test):
+   - Signed sample deliveries for checking receivers' signature verification
+
 Future Modules:
 - System configuration management
 - Platform-wide analytics and reporting
@@ -0,0 +1,34 @@
+// handlers/elevated/root/tenant/advisor.rs - GET /api/root/tenant/:name/advisor handler
+
+use axum::extract::{Extension, Path};
+use serde_json::{json, Value};
+
+use crate::database::{index_advisor, service, DatabaseManager};
+use crate::error::ApiError;
+use crate::middleware::{AuthUser, ApiResponse, ApiResult};
+
+/// GET /api/root/tenant/:name/advisor - Index suggestions and unused indexes for a tenant
+///
+/// Read-only: suggested `CREATE INDEX` statements are applied by the maintenance task
+/// when DATABASE_INDEX_ADVISOR_AUTO_APPLY is set, and unused indexes are only reported.
+pub async fn tenant_advisor(
+    Path(name): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let tenant = service::find_tenant_by_name(&name)
+        .await?
+        .filter(|tenant| tenant.trashed_at.is_none() && tenant.deleted_at.is_none())
+        .ok_or_else(|| ApiError::not_found(format!("Tenant '{}' not found", name)))?;
+
+    let pool = DatabaseManager::tenant_pool(&tenant.database).await?;
+    let report = index_advisor::analyze(&pool).await?;
+    tracing::info!("Index advisor run for tenant '{}' by '{}'", name, auth_user.user);
+
+    let config = &crate::config::config().database;
+    Ok(ApiResponse::success(json!({
+        "tenant": tenant.name,
+        "auto_apply": config.index_advisor_auto_apply,
+        "maintenance_window": config.index_advisor_window,
+        "report": report,
+    })))
+}
@@ -1,128 +1,82 @@
 // handlers/elevated/root/tenant/create.rs - POST /api/root/tenant handler
-// Create new tenant with database provisioning
+// Create new tenant by cloning a template database
 
-use axum::{http::StatusCode, response::Json};
+use axum::{extract::Extension, Json};
+use serde::Deserialize;
 use serde_json::{json, Value};
 
-/**
- * POST /api/root/tenant - Create new tenant with complete setup
- * 
- * Provisions a new tenant including:
- * - Tenant configuration record
- * - Dedicated PostgreSQL database
- * - Initial schema setup
- * - Default permissions and roles
- * 
- * Expected Input:
- * ```json
- * {
- *   "name": "string",         // Required: Tenant identifier (URL-safe)
- *   "display_name": "string", // Required: Human-readable name
- *   "description": "string",  // Optional: Tenant description
- *   "settings": {             // Optional: Tenant-specific configuration
- *     "max_users": 100,
- *     "storage_limit": "10GB"
- *   }
- * }
- * ```
- * 
- * @returns JSON response with created tenant information
- */
-pub async fn tenant_create() -> (StatusCode, Json<Value>) {
-    // TODO: Extract and validate tenant creation request
-    // TODO: Check if tenant name is available (unique constraint)
-    // TODO: Create tenant configuration record in system database
-    // TODO: Provision dedicated PostgreSQL database for tenant
-    // TODO: Run initial schema migrations for tenant database
-    // TODO: Set up default permissions and roles
-    // TODO: Create audit log entry for tenant creation
-    // TODO: Return tenant information and database details
-    
-    (
-        StatusCode::NOT_IMPLEMENTED,
-        Json(json!({
-            "error": "Tenant creation endpoint not yet implemented",
-            "message": "This will create new tenant with complete database provisioning",
-            "security_note": "Requires root JWT token obtained via POST /api/auth/sudo",
-            "expected_input": {
-                "name": "string (required, URL-safe tenant identifier)",
-                "display_name": "string (required, human-readable name)",
-                "description": "string (optional)",
-                "settings": "object (optional, tenant configuration)"
-            },
-            "planned_operations": [
-                "Validate tenant name availability",
-                "Create tenant configuration record", 
-                "Provision PostgreSQL database",
-                "Run initial schema migrations",
-                "Set up default permissions",
-                "Create comprehensive audit log"
-            ]
-        }))
-    )
-}
-
-/*
-TENANT CREATION IMPLEMENTATION PLAN:
-
-1. **Input Validation**:
-   ```rust
-   #[derive(Deserialize)]
-   struct CreateTenantRequest {
-       name: String,           // Must be URL-safe, unique
-       display_name: String,   // Human-readable name
-       description: Option<String>,
-       settings: Option<TenantSettings>,
-   }
-   ```
-
-2. **Validation Rules**:
-   - Tenant name: alphanumeric + hyphens, 3-50 chars
-   - Display name: any UTF-8, 1-100 chars  
-   - Must be unique across all tenants
-   - Settings must conform to schema
-
-3. **Database Provisioning**:
-   ```sql
-   -- Create dedicated database for tenant
-   CREATE DATABASE tenant_${tenant_uuid};
-   
-   -- Create tenant user with limited privileges
-   CREATE USER tenant_${tenant_uuid}_user WITH PASSWORD '${generated_password}';
-   GRANT ALL PRIVILEGES ON DATABASE tenant_${tenant_uuid} TO tenant_${tenant_uuid}_user;
-   ```
+use crate::middleware::{ApiResponse, ApiResult, AuthUser};
+use crate::services::TenantService;
 
-4. **Schema Initialization**:
-   - Run migration scripts for tenant database
-   - Create system tables: _schema_registry, _audit_log, _user_permissions
-   - Set up initial admin user account
-
-5. **System Record Creation**:
-   ```rust
-   struct Tenant {
-       id: Uuid,
-       name: String,
-       display_name: String,
-       database_name: String,
-       database_user: String,
-       status: TenantStatus,
-       created_at: DateTime<Utc>,
-       settings: TenantSettings,
-   }
-   ```
+fn default_template() -> String {
+    "system".to_string()
+}
 
-6. **Rollback Strategy**:
-   - If any step fails, rollback all changes
-   - Delete created database and user
-   - Remove tenant configuration record
-   - Comprehensive error logging
+#[derive(Debug, Deserialize)]
+pub struct CreateTenantRequest {
+    /// Tenant identifier used at login
+    pub name: String,
+    /// Template to clone, as in `template_<name>`
+    #[serde(default = "default_template")]
+    pub template: String,
+    /// JSON Schemas applied on top of the template
+    #[serde(default)]
+    pub schemas: Vec<Value>,
+    /// Region to place the tenant's database in; `database.default_region` when omitted
+    #[serde(default)]
+    pub region: Option<String>,
+}
 
-7. **Security Considerations**:
-   - Generate cryptographically secure database passwords
-   - Use database transactions for atomicity
-   - Log all operations for security audit
-   - Validate admin user has root permissions
+/// POST /api/root/tenant - Create new tenant from a template
+///
+/// Clones `template_<template>` into a new tenant database, then applies each inline
+/// schema through the describe service: schemas the template already has are updated,
+/// others are created. If any schema fails, the database is dropped and nothing is
+/// registered, so the tenant comes up fully formed in one call or not at all.
+///
+/// The database is created on the least loaded cluster of `region`; a region without
+/// configured clusters is rejected with 400.
+///
+/// Expected Input:
+/// ```json
+/// {
+///   "name": "acme",
+///   "template": "basic",
+///   "region": "eu",
+///   "schemas": [
+///     { "name": "invoice", "title": "Invoice", "properties": { "total": { "type": "number", "minimum": 0 } } }
+///   ]
+/// }
+/// ```
+///
+/// Expected Output:
+/// ```json
+/// {
+///   "success": true,
+///   "data": {
+///     "name": "acme",
+///     "database": "tenant_4f1c...",
+///     "template": "basic",
+///     "schemas": ["invoice"],
+///     "region": "eu",
+///     "cluster": "eu-1"
+///   }
+/// }
+/// ```
+pub async fn tenant_create(
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<CreateTenantRequest>,
+) -> ApiResult<Value> {
+    let service = TenantService::new().await?;
+    let info = service
+        .create_tenant_with_schemas(&payload.name, &payload.template, payload.region.as_deref(), payload.schemas)
+        .await?;
+    tracing::info!(
+        "Tenant '{}' created from template '{}' by '{}'",
+        info.name,
+        info.template,
+        auth_user.user
+    );
 
-This operation is high-risk and should be implemented with comprehensive
-error handling, rollback capabilities, and security validation.
-*/
\ No newline at end of file
+    Ok(ApiResponse::success(json!(info)))
+}
@@ -1,11 +1,33 @@
 // handlers/elevated/root/tenant/list.rs - GET /api/root/tenant handler
 
-use axum::{http::StatusCode, response::Json};
+use axum::extract::Extension;
 use serde_json::{json, Value};
 
-pub async fn tenant_list() -> (StatusCode, Json<Value>) {
-    (StatusCode::NOT_IMPLEMENTED, Json(json!({
-        "error": "Tenant listing not yet implemented",
-        "message": "This will list all tenants with health status and metrics"
-    })))
-}
\ No newline at end of file
+use super::usage::storage_usage;
+use crate::database::service;
+use crate::middleware::{AuthUser, ApiResponse, ApiResult};
+
+/// GET /api/root/tenant - List tenants with their attachment storage
+///
+/// Trashed tenants are listed, deleted ones are not. `storage` is as reported by
+/// GET /api/root/tenant/:name/usage, or null when the tenant's database is unreachable.
+pub async fn tenant_list(Extension(auth_user): Extension<AuthUser>) -> ApiResult<Value> {
+    let tenants = service::list_tenants().await?;
+
+    let mut listed = Vec::with_capacity(tenants.len());
+    for tenant in tenants {
+        let storage = match storage_usage(&tenant.database).await {
+            Ok(storage) => json!(storage),
+            Err(e) => {
+                tracing::warn!("Storage usage of tenant '{}' unavailable: {}", tenant.name, e);
+                Value::Null
+            }
+        };
+        let mut entry = json!(tenant);
+        entry["storage"] = storage;
+        listed.push(entry);
+    }
+
+    tracing::info!("Tenant list requested by '{}'", auth_user.user);
+    Ok(ApiResponse::success(json!(listed)))
+}
@@ -14,6 +14,9 @@ pub mod update;   // PATCH /api/root/tenant/:name
 pub mod delete;   // DELETE /api/root/tenant/:name  
 pub mod restore;  // PUT /api/root/tenant/:name
 pub mod health;   // GET /api/root/tenant/:name/health
+pub mod advisor;  // GET /api/root/tenant/:name/advisor
+pub mod placement; // POST /api/root/tenant/:name/move
+pub mod usage;    // GET /api/root/tenant/:name/usage
 
 // Re-export handler functions
 pub use create::tenant_create;     // Create new tenant
@@ -23,6 +26,9 @@ pub use update::tenant_update;     // Update tenant config
 pub use delete::tenant_delete;     // Soft delete tenant  
 pub use restore::tenant_restore;   // Restore deleted tenant
 pub use health::tenant_health;     // Check tenant health
+pub use advisor::tenant_advisor;   // Index suggestions
+pub use placement::tenant_move;    // Route to another region
+pub use usage::tenant_usage;       // Storage usage
 
 /*
 TENANT MANAGEMENT OPERATIONS:
@@ -37,7 +43,7 @@ These handlers provide complete tenant lifecycle management:
 2. **Tenant Listing** (GET /api/root/tenant):
    - List all tenants with status
    - Support pagination and filtering
-   - Include health and usage metrics
+   - Include health and usage metrics (attachment storage is reported)
 
 3. **Tenant Details** (GET /api/root/tenant/:name):
    - Detailed tenant information
@@ -64,6 +70,19 @@ These handlers provide complete tenant lifecycle management:
    - Table count and size metrics
    - Performance indicators
 
+8. **Index Advisor** (GET /api/root/tenant/:name/advisor):
+   - Index suggestions from pg_stat_statements and column statistics
+   - Mostly sequentially scanned tables and never-used indexes
+   - Suggestions auto-applied only during the configured maintenance window
+
+9. **Tenant Placement** (POST /api/root/tenant/:name/move):
+   - Route the tenant to a cluster in another region
+   - Database must already be copied to the target cluster
+
+10. **Tenant Usage** (GET /api/root/tenant/:name/usage):
+   - Attachment bytes stored, file count and the configured quota
+   - Uploads past the quota are rejected with 413
+
 SECURITY CONSIDERATIONS:
 
 - All operations require root JWT token
@@ -0,0 +1,48 @@
+// handlers/elevated/root/tenant/placement.rs - POST /api/root/tenant/:name/move handler
+
+use axum::extract::{Extension, Path};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::middleware::{ApiResponse, ApiResult, AuthUser};
+use crate::services::TenantService;
+
+#[derive(Debug, Deserialize)]
+pub struct MoveTenantRequest {
+    /// Region to serve the tenant from
+    pub region: String,
+    /// Cluster within the region; the least loaded one when omitted
+    #[serde(default)]
+    pub cluster: Option<String>,
+}
+
+/// POST /api/root/tenant/:name/move - Route a tenant to another region
+///
+/// Switches which cluster serves the tenant's database. The database itself is not
+/// copied: it must already exist on the target cluster, otherwise the move is refused
+/// with 409 and the tenant stays where it is.
+///
+/// Expected Input:
+/// ```json
+/// { "region": "eu", "cluster": "eu-1" }
+/// ```
+pub async fn tenant_move(
+    Path(name): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<MoveTenantRequest>,
+) -> ApiResult<Value> {
+    let service = TenantService::new().await?;
+    let placement = service
+        .move_tenant(&name, &payload.region, payload.cluster.as_deref())
+        .await?;
+    tracing::info!(
+        "Tenant '{}' moved to cluster '{}' ({}) by '{}'",
+        placement.name,
+        placement.cluster,
+        placement.region,
+        auth_user.user
+    );
+
+    Ok(ApiResponse::success(json!(placement)))
+}
@@ -0,0 +1,37 @@
+// handlers/elevated/root/tenant/usage.rs - GET /api/root/tenant/:name/usage handler
+
+use axum::extract::{Extension, Path};
+use serde_json::{json, Value};
+
+use crate::database::files::{self, StorageUsage};
+use crate::database::{service, DatabaseManager};
+use crate::error::ApiError;
+use crate::middleware::{AuthUser, ApiResponse, ApiResult};
+
+/// GET /api/root/tenant/:name/usage - Resource usage of a tenant
+///
+/// Reports attachment storage: bytes in use, counting files that share bytes once, the
+/// number of files and the configured quota (null when unlimited).
+pub async fn tenant_usage(
+    Path(name): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let tenant = service::find_tenant_by_name(&name)
+        .await?
+        .filter(|tenant| tenant.trashed_at.is_none() && tenant.deleted_at.is_none())
+        .ok_or_else(|| ApiError::not_found(format!("Tenant '{}' not found", name)))?;
+
+    let storage = storage_usage(&tenant.database).await?;
+    tracing::info!("Usage of tenant '{}' requested by '{}'", name, auth_user.user);
+
+    Ok(ApiResponse::success(json!({
+        "tenant": tenant.name,
+        "storage": storage,
+    })))
+}
+
+/// Attachment storage of the tenant database `database`
+pub(super) async fn storage_usage(database: &str) -> Result<StorageUsage, ApiError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    Ok(files::storage_usage(&pool).await?)
+}
@@ -0,0 +1,51 @@
+// handlers/elevated/root/webhooks.rs - POST /api/root/webhooks/:id/test handler
+
+use axum::extract::{Extension, Path};
+use serde_json::{json, Value};
+
+use crate::canonical;
+use crate::error::ApiError;
+use crate::middleware::{AuthUser, ApiResponse, ApiResult};
+use crate::services::webhook_signing;
+
+/// POST /api/root/webhooks/:id/test - Send a signed sample event to one endpoint
+///
+/// The sample has the shape of a real delivery (operation "test", one made-up record)
+/// and is signed exactly as record changes are, so integrators can check their
+/// verification code. Answers with what was sent and how the endpoint responded.
+pub async fn webhook_test(
+    Path(id): Path<String>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let endpoint = crate::config::config()
+        .api
+        .webhook_endpoints()
+        .into_iter()
+        .find(|endpoint| endpoint.id == id)
+        .ok_or_else(|| ApiError::not_found(format!("Webhook '{}' is not configured", id)))?;
+
+    let body = canonical::to_string(&json!({
+        "database": auth_user.database,
+        "schema": "webhook_test",
+        "operation": "test",
+        "actor": auth_user.user,
+        "records": [{ "id": uuid::Uuid::new_v4(), "message": "Sample event from POST /api/root/webhooks/:id/test" }],
+        "timestamp": crate::clock::now(),
+    }));
+
+    let (delivered, status, error) = match webhook_signing::deliver(&reqwest::Client::new(), &endpoint, &body).await {
+        Ok(status) => (true, Some(status), None),
+        Err(e) => (false, None, Some(e)),
+    };
+
+    tracing::info!("Webhook '{}' test sent by '{}': delivered={}", endpoint.id, auth_user.user, delivered);
+    Ok(ApiResponse::success(json!({
+        "id": endpoint.id,
+        "url": endpoint.url,
+        "delivered": delivered,
+        "status": status,
+        "error": error,
+        "signed": !endpoint.secret.is_empty(),
+        "body": body,
+    })))
+}
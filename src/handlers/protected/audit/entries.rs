@@ -0,0 +1,25 @@
+use axum::extract::{Extension, Query};
+use serde_json::{json, Value};
+
+use crate::database::audit::{self, AuditQuery};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// GET /api/audit - Audit trail of record writes in the caller's tenant, newest first
+///
+/// Query parameters filter the entries: `schema`, `record_id`, `operation`
+/// (create | update | delete | revert), `actor`, `since` and `until` (timestamps), plus
+/// `limit` (default 100, at most 1000) and `offset`. Restricted to root and full access.
+pub async fn list(
+    Query(query): Query<AuditQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    if auth_user.access != "root" && auth_user.access != "full" {
+        return Err(ApiError::forbidden("Reading the audit log requires root or full access"));
+    }
+
+    let entries = audit::list(&pool, &query).await?;
+    let count = entries.len();
+    Ok(ApiResponse::success(json!(entries)).with_meta(json!({ "count": count })))
+}
@@ -0,0 +1,4 @@
+pub mod entries;
+
+// Re-export handler functions for use in routing
+pub use entries::list as audit_list;
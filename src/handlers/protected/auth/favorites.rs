@@ -0,0 +1,93 @@
+use axum::extract::{Extension, Path, Query};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::{comments, favorites};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// Views listed by /auth/recent when no limit is given
+const DEFAULT_RECENT_LIMIT: u32 = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    /// Only entries of this schema
+    pub schema: Option<String>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FavoriteRequest {
+    pub schema: String,
+    pub record_id: Uuid,
+}
+
+/// GET /api/auth/recent - Records the user recently opened, latest first
+///
+/// `?schema=` limits to one schema and `?limit=` (default 20) to that many views. Only
+/// schemas with `"x-monk-track-views": true` record views. Each entry carries the record
+/// under `record`; records since deleted or no longer readable are left out.
+pub async fn recent(
+    Query(query): Query<ListQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let kept = crate::config::config().api.recent_views_per_user;
+    let limit = query.limit.unwrap_or(DEFAULT_RECENT_LIMIT);
+    if limit == 0 || limit > kept.max(1) {
+        return Err(ApiError::bad_request(format!("limit must be between 1 and {}", kept.max(1))));
+    }
+
+    let views = favorites::recent(&pool, auth_user.user_id, query.schema.as_deref(), limit as i64).await?;
+    let views = favorites::with_records(&pool, auth_user.record_access(), &views, |v| (v.schema_name.as_str(), v.record_id)).await?;
+    Ok(ApiResponse::success(json!(views)))
+}
+
+/// GET /api/auth/favorites - The user's favorite records, latest first
+///
+/// `?schema=` limits to one schema. Each entry carries the record under `record`; records
+/// since deleted or no longer readable are left out.
+pub async fn list(
+    Query(query): Query<ListQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let entries = favorites::favorites(&pool, auth_user.user_id, query.schema.as_deref()).await?;
+    let entries = favorites::with_records(&pool, auth_user.record_access(), &entries, |f| (f.schema_name.as_str(), f.record_id)).await?;
+    Ok(ApiResponse::success(json!(entries)))
+}
+
+/// POST /api/auth/favorites - Mark a record as favorite
+///
+/// Body: { "schema": "accounts", "record_id": "..." }. Requires read access to the record.
+/// Marking a favorite again is a no-op; a user holds at most `api.favorites_per_user`.
+pub async fn add(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<FavoriteRequest>,
+) -> ApiResult<Value> {
+    comments::check_record(&pool, &payload.schema, payload.record_id, auth_user.record_access().as_ref()).await?;
+
+    let cap = crate::config::config().api.favorites_per_user as i64;
+    let marked = favorites::is_favorite(&pool, auth_user.user_id, &payload.schema, payload.record_id).await?;
+    if !marked && favorites::count_favorites(&pool, auth_user.user_id).await? >= cap {
+        return Err(ApiError::conflict(format!("A user can hold at most {} favorites", cap)));
+    }
+
+    let favorite = favorites::add_favorite(&pool, auth_user.user_id, &payload.schema, payload.record_id).await?;
+    Ok(ApiResponse::created(json!(favorite)))
+}
+
+/// DELETE /api/auth/favorites/:schema/:record - Unmark a favorite
+pub async fn remove(
+    Path((schema, record_id)): Path<(String, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    if !favorites::remove_favorite(&pool, auth_user.user_id, &schema, record_id).await? {
+        return Err(ApiError::not_found("Record is not a favorite"));
+    }
+    Ok(ApiResponse::success(json!({ "schema_name": schema, "record_id": record_id, "removed": true })))
+}
@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Extension, Path},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::api_keys::{self, ApiKeyScope, OPERATIONS};
+use crate::error::ApiError;
+use crate::middleware::{ApiResponse, ApiResult, TenantPool, ValidatedTenant, ValidatedUser};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Days until the key stops working; keys without it never expire
+    pub expires_in_days: Option<u32>,
+}
+
+/// POST /api/auth/keys - Create an API key acting as the current user
+///
+/// The key is returned once, in `key`; only a digest is stored. Send it in the
+/// `X-API-Key` header instead of a Bearer token. Each scope grants operations
+/// (`read`, `create`, `update`, `delete`) on one schema, or on every schema with `"*"`.
+///
+/// Expected Input:
+/// ```json
+/// {
+///   "name": "billing-sync",
+///   "scopes": [{ "schema": "invoices", "operations": ["read", "create"] }],
+///   "expires_in_days": 90
+/// }
+/// ```
+pub async fn create(
+    Extension(user): Extension<ValidatedUser>,
+    Extension(tenant): Extension<ValidatedTenant>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Json(payload): Json<CreateKeyRequest>,
+) -> ApiResult<Value> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::bad_request("API key name is required"));
+    }
+    if payload.scopes.is_empty() {
+        return Err(ApiError::bad_request("API key needs at least one scope"));
+    }
+    for scope in &payload.scopes {
+        if scope.schema.is_empty() || scope.operations.is_empty() {
+            return Err(ApiError::bad_request("Each scope needs a schema and at least one operation"));
+        }
+        if let Some(op) = scope.operations.iter().find(|op| !OPERATIONS.contains(&op.as_str())) {
+            return Err(ApiError::bad_request(format!(
+                "Unknown operation '{}'; expected one of {}",
+                op,
+                OPERATIONS.join(", ")
+            )));
+        }
+    }
+
+    let expires_at = payload
+        .expires_in_days
+        .map(|days| (crate::clock::now() + chrono::Duration::days(days as i64)).naive_utc());
+    let (api_key, key) = api_keys::create(&pool, &tenant.name, user.id, name, payload.scopes, expires_at).await?;
+
+    tracing::info!("User {} in {} created API key '{}' ({})", user.auth, tenant.name, api_key.name, api_key.id);
+    let mut data = serde_json::to_value(&api_key).map_err(|e| ApiError::internal_server_error(e.to_string()))?;
+    data["key"] = Value::String(key);
+    Ok(ApiResponse::success(data))
+}
+
+/// GET /api/auth/keys - List the current user's API keys
+///
+/// Keys are listed with their hint (first characters of the secret) but never the key itself.
+pub async fn list(
+    Extension(user): Extension<ValidatedUser>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+) -> ApiResult<Value> {
+    let keys = api_keys::list(&pool, user.id).await?;
+    Ok(ApiResponse::success(json!(keys)))
+}
+
+/// DELETE /api/auth/keys/:id - Revoke one of the current user's API keys
+pub async fn revoke(
+    Path(id): Path<Uuid>,
+    Extension(user): Extension<ValidatedUser>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+) -> ApiResult<Value> {
+    if !api_keys::revoke(&pool, id, user.id).await? {
+        return Err(ApiError::not_found(format!("API key '{}' not found", id)));
+    }
+
+    tracing::info!("User {} revoked API key {}", user.auth, id);
+    Ok(ApiResponse::success(json!({ "id": id, "revoked": true })))
+}
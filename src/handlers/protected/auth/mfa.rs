@@ -0,0 +1,111 @@
+use axum::{extract::Extension, Json};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::auth::{generate_jwt, totp, Claims};
+use crate::database::mfa;
+use crate::error::ApiError;
+use crate::middleware::{ApiResponse, ApiResult, TenantPool, ValidatedTenant, ValidatedUser};
+
+#[derive(Debug, Deserialize)]
+pub struct MfaCodeRequest {
+    /// Current code from the authenticator app
+    pub code: String,
+}
+
+/// POST /api/auth/mfa/enroll - Start TOTP enrollment
+///
+/// Returns a new secret and its `otpauth://` provisioning URI, usually rendered as a QR
+/// code for the authenticator app. MFA is enabled once a code is confirmed through
+/// POST /api/auth/mfa/verify; enrolling again before that replaces the secret.
+///
+/// Expected Output:
+/// ```json
+/// {
+///   "success": true,
+///   "data": {
+///     "secret": "JBSWY3DPEHPK3PXP...",
+///     "provisioning_uri": "otpauth://totp/Monk:admin%20(my-tenant)?secret=...&issuer=Monk"
+///   }
+/// }
+/// ```
+pub async fn enroll(
+    Extension(user): Extension<ValidatedUser>,
+    Extension(tenant): Extension<ValidatedTenant>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+) -> ApiResult<Value> {
+    let secret = totp::generate_secret();
+    if !mfa::start_enrollment(&pool, user.id, &totp::seal_secret(&secret)).await? {
+        return Err(ApiError::conflict("MFA is already enabled; disable it before enrolling again"));
+    }
+
+    tracing::info!("User {} in {} started MFA enrollment", user.auth, tenant.name);
+    Ok(ApiResponse::success(json!({
+        "provisioning_uri": totp::provisioning_uri(&secret, &tenant.name, &user.auth),
+        "secret": secret,
+    })))
+}
+
+/// POST /api/auth/mfa/verify - Verify a TOTP code for the current session
+///
+/// The first verified code completes enrollment and enables MFA. Returns a new token marked
+/// MFA-verified, which sudo requires when the tenant enforces MFA. Tokens obtained by
+/// refreshing are not marked, so step up again after a refresh.
+///
+/// Expected Input:
+/// ```json
+/// {
+///   "code": "123456"
+/// }
+/// ```
+pub async fn verify(
+    Extension(user): Extension<ValidatedUser>,
+    Extension(tenant): Extension<ValidatedTenant>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Json(payload): Json<MfaCodeRequest>,
+) -> ApiResult<Value> {
+    let state = mfa::load(&pool, user.id).await?;
+    if state.mfa_secret.is_none() {
+        return Err(ApiError::bad_request("MFA is not enrolled; start with POST /api/auth/mfa/enroll"));
+    }
+    if !totp::check_user_code(&pool, user.id, &payload.code).await? {
+        return Err(ApiError::unauthorized("Invalid TOTP code"));
+    }
+    if !state.is_enabled() {
+        tracing::info!("User {} in {} enabled MFA", user.auth, tenant.name);
+    }
+
+    let claims = Claims::new(tenant.name, user.auth, tenant.database, user.access, user.id).with_mfa(true);
+    let token = generate_jwt(claims).map_err(|e| ApiError::internal_server_error(format!("Token generation failed: {}", e)))?;
+    Ok(ApiResponse::success(json!({
+        "enabled": true,
+        "token": token,
+        "expires_in": crate::config::config().security.jwt_expiry_hours * 3600,
+    })))
+}
+
+/// DELETE /api/auth/mfa - Disable MFA
+///
+/// Requires a current code when MFA is enabled.
+///
+/// Expected Input:
+/// ```json
+/// {
+///   "code": "123456"
+/// }
+/// ```
+pub async fn disable(
+    Extension(user): Extension<ValidatedUser>,
+    Extension(tenant): Extension<ValidatedTenant>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Json(payload): Json<MfaCodeRequest>,
+) -> ApiResult<Value> {
+    let state = mfa::load(&pool, user.id).await?;
+    if state.is_enabled() && !totp::check_user_code(&pool, user.id, &payload.code).await? {
+        return Err(ApiError::unauthorized("Invalid TOTP code"));
+    }
+    mfa::disable(&pool, user.id).await?;
+
+    tracing::warn!("User {} in {} disabled MFA", user.auth, tenant.name);
+    Ok(ApiResponse::success(json!({ "enabled": false })))
+}
@@ -1,3 +1,6 @@
+pub mod favorites;
+pub mod keys;
+pub mod mfa;
 pub mod session;
 pub mod utils;
 
@@ -5,4 +8,14 @@ pub mod utils;
 pub use session::whoami as session_whoami;
 pub use session::sudo as session_sudo;
 pub use session::refresh_session as session_refresh;
-pub use session::logout as session_logout;
\ No newline at end of file
+pub use session::logout as session_logout;
+pub use mfa::enroll as mfa_enroll;
+pub use mfa::verify as mfa_verify;
+pub use mfa::disable as mfa_disable;
+pub use keys::create as keys_create;
+pub use keys::list as keys_list;
+pub use keys::revoke as keys_revoke;
+pub use favorites::recent as recent_list;
+pub use favorites::list as favorites_list;
+pub use favorites::add as favorites_add;
+pub use favorites::remove as favorites_remove;
@@ -1,20 +1,33 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Extension},
+    response::{IntoResponse, Response},
+    Json,
+};
 use serde::Deserialize;
 use serde_json::{json, Value};
 
+use crate::auth::{generate_jwt, totp, Claims};
+use crate::database::{feature_flags, login_attempts, mfa};
+use crate::database::refresh_tokens::{self, Rotation};
+use crate::error::ApiError;
+use crate::handlers::public::auth::session::too_many_attempts;
+use crate::middleware::{ApiResponse, ApiResult, AuthUser, TenantPool, ValidatedTenant, ValidatedUser};
+
 #[derive(Debug, Deserialize)]
 pub struct SudoRequest {
-    /// Optional: Specific permissions being requested
-    pub permissions: Option<Vec<String>>,
-    /// Optional: Password confirmation for sensitive operations
-    pub password: Option<String>,
+    /// Why elevation is needed; carried in the token and written to the audit log
+    pub reason: String,
+    /// Current TOTP code, when the tenant enforces MFA and the session is not MFA-verified
+    #[serde(default)]
+    pub mfa_code: Option<String>,
 }
 
 /// GET /api/auth/whoami - Get current authenticated user details
 /// 
-/// This endpoint returns information about the currently authenticated user
-/// based on their JWT token. It provides user profile, tenant information,
-/// and current permissions.
+/// Returns the user validated by the auth middleware, their tenant, and the
+/// tenant's evaluated feature flags so clients can adapt to enabled capabilities.
 /// 
 /// Expected Output:
 /// ```json
@@ -22,57 +35,50 @@ pub struct SudoRequest {
 ///   "success": true,
 ///   "data": {
 ///     "id": "user_uuid",
-///     "username": "admin",
-///     "email": "user@example.com", 
+///     "name": "Admin",
+///     "auth": "admin",
+///     "access": "root",
 ///     "tenant": "tenant_name",
-///     "role": "admin",
-///     "permissions": ["read", "write", "admin"],
-///     "created_at": "2025-01-01T00:00:00Z",
-///     "last_login": "2025-01-01T12:00:00Z"
+///     "database": "tenant_db",
+///     "features": { "graphql": false, "find_aggregate": true }
 ///   }
 /// }
 /// ```
-pub async fn whoami() -> impl IntoResponse {
-    // TODO: Extract JWT claims from Authorization header
-    // TODO: Get user information from claims or query database for fresh data
-    // TODO: Include tenant information and current permissions
-    // TODO: Return comprehensive user profile
-    
-    // Placeholder response - mirrors expected structure
-    (
-        StatusCode::NOT_IMPLEMENTED,
-        Json(json!({
-            "success": false,
-            "error": "Auth whoami endpoint not yet implemented",
-            "message": "This will return current user information from JWT token",
-            "planned_response": {
-                "success": true,
-                "data": {
-                    "id": "user_uuid",
-                    "username": "admin", 
-                    "email": "user@example.com",
-                    "tenant": "tenant_name",
-                    "role": "admin",
-                    "permissions": ["read", "write", "admin"],
-                    "created_at": "2025-01-01T00:00:00Z",
-                    "last_login": "2025-01-01T12:00:00Z"
-                }
-            }
-        })),
-    )
+pub async fn whoami(
+    Extension(user): Extension<ValidatedUser>,
+    Extension(tenant): Extension<ValidatedTenant>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+) -> ApiResult<Value> {
+    let features = feature_flags::load_feature_flags(&pool).await?;
+
+    Ok(ApiResponse::success(json!({
+        "id": user.id,
+        "name": user.name,
+        "auth": user.auth,
+        "access": user.access,
+        "tenant": tenant.name,
+        "database": tenant.database,
+        "features": features,
+    })))
 }
 
-/// POST /api/auth/sudo - Elevate user permissions to sudo/admin level
+/// POST /api/auth/sudo - Elevate the session to root for administrative operations
+/// 
+/// Users with `root` access (as currently stored, not as claimed by the token) receive a
+/// short-lived root token carrying the stated reason. Only such elevated tokens are
+/// accepted by /api/root/*, whose tenant routes reach every tenant, so tenant `full`
+/// users cannot elevate. Expiry is `SECURITY_SUDO_EXPIRY_MINUTES`.
 /// 
-/// This endpoint allows users with appropriate privileges to elevate their
-/// session to perform administrative operations. It returns a new JWT token
-/// with elevated permissions and possibly shorter expiration time.
+/// When the tenant enables the `mfa_required` feature flag, the session must be
+/// MFA-verified or the request must carry a current TOTP code; users without MFA enabled
+/// cannot elevate until they enroll. Wrong codes count towards the same lockout as failed
+/// logins: once locked out, sudo answers 429 `TOO_MANY_ATTEMPTS` with `Retry-After`.
 /// 
 /// Expected Input:
 /// ```json
 /// {
-///   "permissions": ["admin", "root"],  // Optional: Specific permissions
-///   "password": "string"               // Optional: Password confirmation
+///   "reason": "string",   // Required: Why elevation is needed
+///   "mfa_code": "123456"  // Optional: TOTP code when MFA is enforced
 /// }
 /// ```
 /// 
@@ -83,80 +89,137 @@ pub async fn whoami() -> impl IntoResponse {
 ///   "data": {
 ///     "token": "eyJhbGciOiJIUzI1NiI...",
 ///     "expires_at": "2025-01-01T01:00:00Z",
-///     "permissions": ["admin", "sudo", "root_access"],
+///     "expires_in": 900,
+///     "access": "root",
+///     "reason": "rotate tenant keys",
 ///     "session_type": "elevated"
 ///   }
 /// }
 /// ```
-pub async fn sudo(Json(_payload): Json<SudoRequest>) -> impl IntoResponse {
-    // TODO: Extract current user from JWT token
-    // TODO: Validate user has sudo/elevation permissions
-    // TODO: If password provided, verify it matches current user
-    // TODO: Generate new JWT token with elevated privileges
-    // TODO: Set shorter expiration time for security
-    // TODO: Log sudo elevation attempt for audit
-    // TODO: Return new elevated token
-    
-    (
-        StatusCode::NOT_IMPLEMENTED,
-        Json(json!({
-            "success": false,
-            "error": "Auth sudo endpoint not yet implemented",
-            "message": "This will elevate user permissions for admin operations",
-            "expected_input": {
-                "permissions": "array[string] (optional - specific permissions requested)",
-                "password": "string (optional - password confirmation)"
-            },
-            "planned_response": {
-                "success": true,
-                "data": {
-                    "token": "jwt_token_with_sudo_privileges",
-                    "expires_at": "2025-01-01T01:00:00Z",
-                    "permissions": ["admin", "sudo", "root_access"],
-                    "session_type": "elevated"
+pub async fn sudo(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Extension(auth_user): Extension<AuthUser>,
+    Extension(user): Extension<ValidatedUser>,
+    Extension(tenant): Extension<ValidatedTenant>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Json(payload): Json<SudoRequest>,
+) -> Result<Response, ApiError> {
+    if user.access != "root" {
+        tracing::warn!("Sudo refused for {} in {} with access '{}'", user.auth, tenant.name, user.access);
+        return Err(ApiError::forbidden("Sudo requires root access"));
+    }
+    let reason = payload.reason.trim();
+    if reason.is_empty() {
+        return Err(ApiError::bad_request("A reason for elevation is required"));
+    }
+
+    let mut mfa_verified = auth_user.mfa;
+    if !mfa_verified && mfa::is_required(&pool).await? {
+        if !mfa::load(&pool, user.id).await?.is_enabled() {
+            return Err(ApiError::forbidden("This tenant requires MFA for sudo; enroll with POST /api/auth/mfa/enroll"));
+        }
+        let Some(code) = payload.mfa_code.as_deref() else {
+            return Err(ApiError::unauthorized("A TOTP code is required for sudo"));
+        };
+        let address = peer.ip().to_string();
+        match login_attempts::locked_for(&tenant.name, &user.auth, &address).await {
+            Ok(Some(retry_after)) => return Ok(too_many_attempts(retry_after)),
+            Ok(None) => {}
+            // As for logins, the lockout fails open; the code is still checked
+            Err(e) => tracing::error!("Sudo lockout check failed for {} in {}: {}", user.auth, tenant.name, e),
+        }
+        if !totp::check_user_code(&pool, user.id, code).await? {
+            match login_attempts::record_failure(&tenant.name, &user.auth, &address).await {
+                Ok(Some(lockout)) => {
+                    tracing::warn!("User {} in {} locked out from {} for {}s after sudo codes", user.auth, tenant.name, address, lockout);
+                    return Ok(too_many_attempts(lockout));
                 }
+                Ok(None) => {}
+                Err(e) => tracing::error!("Sudo attempt tracking failed for {} in {}: {}", user.auth, tenant.name, e),
             }
-        })),
-    )
+            return Err(ApiError::unauthorized("Invalid TOTP code"));
+        }
+        mfa_verified = true;
+    }
+
+    let claims = Claims::elevated(tenant.name.clone(), user.auth.clone(), tenant.database, user.id, reason.to_string())
+        .with_mfa(mfa_verified);
+    let expires_at = chrono::DateTime::from_timestamp(claims.exp, 0);
+    let token = generate_jwt(claims).map_err(|e| ApiError::internal_server_error(format!("Token generation failed: {}", e)))?;
+
+    tracing::warn!("User {} in {} elevated to root: {}", user.auth, tenant.name, reason);
+    Ok(ApiResponse::success(json!({
+        "token": token,
+        "expires_at": expires_at,
+        "expires_in": crate::config::config().security.sudo_expiry_minutes * 60,
+        "access": "root",
+        "reason": reason,
+        "session_type": "elevated",
+    })).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionRequest {
+    pub refresh_token: String,
 }
 
 /// PUT /api/auth/session/refresh - Refresh current session token
 /// 
-/// Alternative refresh endpoint for authenticated users to refresh their
-/// current token without providing the token in the body (extracted from headers).
-pub async fn refresh_session() -> impl IntoResponse {
-    // TODO: Extract current JWT token from Authorization header
-    // TODO: Validate token and extract claims
-    // TODO: Generate new token with same permissions but fresh expiration
-    // TODO: Return new token
-
-    (
-        StatusCode::NOT_IMPLEMENTED,
-        Json(json!({
-            "success": false,
-            "error": "Session refresh endpoint not yet implemented", 
-            "message": "This will refresh the current authenticated session"
-        })),
-    )
+/// Same rotation as `POST /auth/refresh/:tenant/:user` for a client that still holds a
+/// valid JWT: the refresh token must belong to the authenticated user and is retired.
+/// 
+/// Expected Input:
+/// ```json
+/// {
+///   "refresh_token": "string"
+/// }
+/// ```
+pub async fn refresh_session(
+    Extension(user): Extension<ValidatedUser>,
+    Extension(tenant): Extension<ValidatedTenant>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Json(payload): Json<SessionRequest>,
+) -> ApiResult<Value> {
+    let next = match refresh_tokens::rotate(&pool, &payload.refresh_token, user.id).await? {
+        Rotation::Rotated(next) => next,
+        Rotation::Reused { .. } => return Err(ApiError::unauthorized("Refresh token already used; session revoked")),
+        Rotation::Expired => return Err(ApiError::unauthorized("Refresh token expired")),
+        Rotation::Unknown => return Err(ApiError::unauthorized("Invalid refresh token")),
+    };
+
+    let claims = Claims::new(tenant.name, user.auth, tenant.database, user.access, user.id).with_mfa(next.mfa);
+    let token = generate_jwt(claims).map_err(|e| ApiError::internal_server_error(format!("Token generation failed: {}", e)))?;
+
+    Ok(ApiResponse::success(json!({
+        "token": token,
+        "expires_in": crate::config::config().security.jwt_expiry_hours * 3600,
+        "refresh_token": next.token,
+        "refresh_expires_at": next.expires_at,
+    })))
 }
 
 /// DELETE /api/auth/session - Revoke/logout current session
 /// 
-/// Invalidates the current JWT token and logs out the user.
-pub async fn logout() -> impl IntoResponse {
-    // TODO: Extract current JWT token from Authorization header
-    // TODO: Add token to blacklist/revocation list
-    // TODO: Log logout event for audit
-    // TODO: Return success confirmation
-
-    (
-        StatusCode::NOT_IMPLEMENTED,
-        Json(json!({
-            "success": false,
-            "error": "Session logout endpoint not yet implemented",
-            "message": "This will revoke the current session token"
-        })),
-    )
+/// Revokes the refresh token family of the session, i.e. every refresh token rotated from
+/// the same login. The JWT itself stays valid until it expires.
+/// 
+/// Expected Input:
+/// ```json
+/// {
+///   "refresh_token": "string"
+/// }
+/// ```
+pub async fn logout(
+    Extension(user): Extension<ValidatedUser>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Json(payload): Json<SessionRequest>,
+) -> ApiResult<Value> {
+    let revoked = refresh_tokens::revoke(&pool, &payload.refresh_token, user.id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Refresh token not found"))?;
+
+    tracing::info!("User {} logged out; revoked {} refresh token(s)", user.auth, revoked);
+    Ok(ApiResponse::success(json!({ "revoked": revoked })))
 }
 
 /*
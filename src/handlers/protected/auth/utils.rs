@@ -91,13 +91,13 @@ impl AuthenticatedUser {
 
     /// Get token expiration time
     pub fn token_expires_in(&self) -> chrono::Duration {
-        self.token_expires - chrono::Utc::now()
+        self.token_expires - crate::clock::now()
     }
 
     /// Check if token is expired or expiring soon
     pub fn is_token_expired(&self, buffer_minutes: i64) -> bool {
         let buffer = chrono::Duration::minutes(buffer_minutes);
-        chrono::Utc::now() + buffer >= self.token_expires
+        crate::clock::now() + buffer >= self.token_expires
     }
 }
 
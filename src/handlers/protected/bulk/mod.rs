@@ -0,0 +1,4 @@
+pub mod operations;
+
+// Re-export handler functions for use in routing
+pub use operations::post as bulk_post;
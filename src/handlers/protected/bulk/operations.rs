@@ -0,0 +1,271 @@
+use axum::extract::Extension;
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::database::transaction::TenantTransaction;
+use crate::observer::{deferred, WriteMode};
+use crate::database::record::{Record, RecordVecExt};
+use crate::database::repository::Repository;
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// Operations accepted in one request
+const MAX_OPERATIONS: usize = 1000;
+
+/// Savepoint wrapped around each record with `on_error: skip`; reused after every release
+const RECORD_SAVEPOINT: &str = "bulk_record";
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkOperationKind {
+    Create,
+    Update,
+    Upsert,
+    Delete,
+}
+
+impl BulkOperationKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BulkOperationKind::Create => "create",
+            BulkOperationKind::Update => "update",
+            BulkOperationKind::Upsert => "upsert",
+            BulkOperationKind::Delete => "delete",
+        }
+    }
+}
+
+/// What a transactional bulk request does when a record fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    /// Roll back the whole request
+    #[default]
+    Abort,
+    /// Roll back only the failing record, via a per-record savepoint, and commit the rest
+    Skip,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkOperation {
+    pub operation: BulkOperationKind,
+    pub schema: String,
+    /// One record or an array of records; update and delete records need an `id`
+    pub data: Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkRequest {
+    pub operations: Vec<BulkOperation>,
+    /// Run every operation in one transaction; see `on_error` for how failures are handled
+    #[serde(default)]
+    pub transaction: bool,
+    /// Only valid with `transaction`
+    #[serde(default)]
+    pub on_error: OnError,
+    /// Without `transaction`, apply each record of an operation on its own, so a failing
+    /// record no longer undoes the records before it
+    #[serde(default)]
+    pub best_effort: bool,
+}
+
+/// POST /api/bulk - Run create/update/upsert/delete operations across schemas in one request
+///
+/// Body: { "transaction": false, "operations": [{ "operation": "create", "schema": "users",
+/// "data": [...] }, ...] }. Every operation runs through the observer pipeline like the
+/// matching /api/data call. Without `transaction`, operations run independently and each
+/// result reports its own success or error; the records of one operation are written
+/// all or nothing, unless `"best_effort": true` commits them one by one. With
+/// `transaction`, a failing operation
+/// rolls back the whole request and its error is returned instead, unless
+/// `"on_error": "skip"` is set: then each record runs under its own savepoint, a failing
+/// record is rolled back alone and listed in its operation's `skipped` array, and the
+/// rest commit.
+pub async fn post(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(request): Json<BulkRequest>,
+) -> ApiResult<Value> {
+    if request.operations.is_empty() {
+        return Err(ApiError::bad_request("Bulk request contains no operations"));
+    }
+    if request.operations.len() > MAX_OPERATIONS {
+        return Err(ApiError::bad_request(format!("Bulk requests are limited to {} operations", MAX_OPERATIONS)));
+    }
+    if request.on_error == OnError::Skip && !request.transaction {
+        return Err(ApiError::bad_request("on_error: skip requires transaction: true"));
+    }
+    if request.best_effort && request.transaction {
+        return Err(ApiError::bad_request("best_effort cannot be combined with transaction: true"));
+    }
+
+    let total = request.operations.len();
+    let results = if request.transaction {
+        // Side effects (webhooks, search updates) wait for the commit and vanish on rollback
+        let (results, held) = deferred::hold(run_transaction(&pool, request.operations, request.on_error, &auth_user)).await;
+        let results = results?;
+        deferred::release(held);
+        results
+    } else {
+        let mode = if request.best_effort { WriteMode::BestEffort } else { WriteMode::Atomic };
+        let mut results = Vec::with_capacity(total);
+        for (index, operation) in request.operations.into_iter().enumerate() {
+            let (kind, schema) = (operation.operation, operation.schema.clone());
+            results.push(match run(&pool, None, operation, mode, &auth_user).await {
+                Ok(records) => success(index, kind, &schema, records),
+                Err(error) => failure(index, kind, &schema, error),
+            });
+        }
+        results
+    };
+
+    let succeeded = results.iter().filter(|result| result["success"] == json!(true)).count();
+    let skipped: usize = results.iter().filter_map(|result| result["skipped"].as_array()).map(Vec::len).sum();
+    tracing::info!(
+        "Bulk request by '{}' in tenant '{}': {}/{} operations succeeded (transaction: {})",
+        auth_user.user, auth_user.tenant, succeeded, total, request.transaction
+    );
+
+    Ok(ApiResponse::success(json!(results)).with_meta(json!({
+        "transaction": request.transaction,
+        "total": total,
+        "succeeded": succeeded,
+        "failed": total - succeeded,
+        "skipped_records": skipped,
+    })))
+}
+
+async fn run_transaction(
+    pool: &PgPool,
+    operations: Vec<BulkOperation>,
+    on_error: OnError,
+    auth_user: &AuthUser,
+) -> Result<Vec<Value>, ApiError> {
+    let transaction = TenantTransaction::begin(pool).await?;
+    let mut results = Vec::with_capacity(operations.len());
+
+    for (index, operation) in operations.into_iter().enumerate() {
+        let (kind, schema) = (operation.operation, operation.schema.clone());
+
+        if on_error == OnError::Skip {
+            results.push(match parse_records(operation.data) {
+                Ok(records) => run_isolated(pool, &transaction, index, kind, &schema, records, auth_user).await?,
+                Err(error) => failure(index, kind, &schema, error),
+            });
+            continue;
+        }
+
+        match run(pool, Some(&transaction), operation, WriteMode::default(), auth_user).await {
+            Ok(records) => results.push(success(index, kind, &schema, records)),
+            Err(error) => {
+                transaction.rollback().await?;
+                return Err(error.with_context(format!(
+                    "Bulk operation {} ({} {}) failed, transaction rolled back", index, kind.as_str(), schema
+                )));
+            }
+        }
+    }
+
+    transaction.commit().await?;
+    Ok(results)
+}
+
+/// Apply records one at a time under a savepoint, rolling back and listing each one that fails
+async fn run_isolated(
+    pool: &PgPool,
+    transaction: &TenantTransaction,
+    index: usize,
+    kind: BulkOperationKind,
+    schema: &str,
+    records: Vec<Record>,
+    auth_user: &AuthUser,
+) -> Result<Value, ApiError> {
+    let mut applied = Vec::with_capacity(records.len());
+    let mut skipped = Vec::new();
+
+    for (position, record) in records.into_iter().enumerate() {
+        transaction.savepoint(RECORD_SAVEPOINT).await?;
+        let (result, held) = deferred::hold(apply(pool, Some(transaction), kind, schema, vec![record], WriteMode::default(), auth_user)).await;
+        match result {
+            Ok(records) => {
+                transaction.release(RECORD_SAVEPOINT).await?;
+                deferred::release(held);
+                applied.extend(records);
+            }
+            Err(error) => {
+                transaction.rollback_to(RECORD_SAVEPOINT).await?;
+                skipped.push(json!({ "record": position, "error": error.to_json() }));
+            }
+        }
+    }
+
+    let mut result = success(index, kind, schema, applied);
+    result["skipped"] = json!(skipped);
+    Ok(result)
+}
+
+async fn run(
+    pool: &PgPool,
+    transaction: Option<&TenantTransaction>,
+    operation: BulkOperation,
+    mode: WriteMode,
+    auth_user: &AuthUser,
+) -> Result<Vec<Record>, ApiError> {
+    let records = parse_records(operation.data)?;
+    apply(pool, transaction, operation.operation, &operation.schema, records, mode, auth_user).await
+}
+
+/// One record or an array of records
+fn parse_records(data: Value) -> Result<Vec<Record>, ApiError> {
+    let data = match data {
+        Value::Object(_) => Value::Array(vec![data]),
+        other => other,
+    };
+    Ok(Record::from_json_array(data)?)
+}
+
+/// Write `records` through the pipeline, inside `transaction` when given
+async fn apply(
+    pool: &PgPool,
+    transaction: Option<&TenantTransaction>,
+    kind: BulkOperationKind,
+    schema: &str,
+    records: Vec<Record>,
+    mode: WriteMode,
+    auth_user: &AuthUser,
+) -> Result<Vec<Record>, ApiError> {
+    let repository = Repository::new(schema, pool.clone())
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor())
+        .with_write_mode(mode)
+        .with_transaction(transaction.cloned());
+
+    Ok(match kind {
+        BulkOperationKind::Create => repository.create_all(records).await?,
+        BulkOperationKind::Update => repository.update_all(records).await?,
+        BulkOperationKind::Upsert => repository.upsert_all(records).await?,
+        BulkOperationKind::Delete => repository.delete_all(records).await?,
+    })
+}
+
+fn success(index: usize, kind: BulkOperationKind, schema: &str, records: Vec<Record>) -> Value {
+    json!({
+        "index": index,
+        "operation": kind.as_str(),
+        "schema": schema,
+        "success": true,
+        "data": records.to_api(),
+    })
+}
+
+fn failure(index: usize, kind: BulkOperationKind, schema: &str, error: ApiError) -> Value {
+    json!({
+        "index": index,
+        "operation": kind.as_str(),
+        "schema": schema,
+        "success": false,
+        "error": error.to_json(),
+    })
+}
@@ -0,0 +1,7 @@
+pub mod thread;
+
+// Re-export handler functions for use in routing
+pub use thread::list as comment_list;
+pub use thread::create as comment_create;
+pub use thread::patch as comment_patch;
+pub use thread::delete as comment_delete;
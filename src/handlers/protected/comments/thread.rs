@@ -0,0 +1,105 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::comments::{self, MAX_COMMENT_LENGTH};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct CommentRequest {
+    pub body: String,
+    /// Comment being replied to, on the same record
+    pub parent_id: Option<Uuid>,
+}
+
+/// GET /api/comments/:schema/:record - The record's comment threads
+///
+/// Top-level comments oldest first, each with its `replies`. Requires read access to the
+/// record; the same threads are returned by GET /api/data/:schema/:record?meta=comments.
+pub async fn list(
+    Path((schema, record_id)): Path<(String, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    comments::check_record(&pool, &schema, record_id, auth_user.record_access().as_ref()).await?;
+    let threads = comments::thread(comments::list(&pool, &schema, record_id).await?);
+    Ok(ApiResponse::success(Value::Array(threads)))
+}
+
+/// POST /api/comments/:schema/:record - Comment on a record, or reply to a comment
+///
+/// Body: { "body": "@ada can you check the totals?", "parent_id": null }. Anyone who can
+/// read the record may comment; `@login` mentions of users who can read it are listed in
+/// `mentions`. Answers 201 with the comment.
+pub async fn create(
+    Path((schema, record_id)): Path<(String, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<CommentRequest>,
+) -> ApiResult<Value> {
+    let body = validate_body(&payload.body)?;
+    comments::check_record(&pool, &schema, record_id, auth_user.record_access().as_ref()).await?;
+
+    let comment = comments::create(&pool, &schema, record_id, payload.parent_id, body, auth_user.user_id, &auth_user.user)
+        .await?;
+    Ok(ApiResponse::created(json!(comment)))
+}
+
+/// PATCH /api/comments/:schema/:record/:id - Edit one of your comments
+///
+/// Body: { "body": "..." }. Only the author may edit a comment; `edited_at` is set.
+pub async fn patch(
+    Path((schema, record_id, id)): Path<(String, Uuid, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<CommentRequest>,
+) -> ApiResult<Value> {
+    let body = validate_body(&payload.body)?;
+    comments::check_record(&pool, &schema, record_id, auth_user.record_access().as_ref()).await?;
+
+    let comment = comments::get(&pool, &schema, record_id, id).await?;
+    if comment.deleted_at.is_some() {
+        return Err(ApiError::gone(format!("Comment '{}' is deleted", id)));
+    }
+    if comment.author_id != Some(auth_user.user_id) {
+        return Err(ApiError::forbidden("Only the author may edit a comment"));
+    }
+    let comment = comments::edit(&pool, &comment, body).await?;
+    Ok(ApiResponse::success(json!(comment)))
+}
+
+/// DELETE /api/comments/:schema/:record/:id - Delete a comment
+///
+/// Allowed to the author and to users who can edit the record. Replies are kept; the
+/// deleted comment stays in the thread without its body while it has any.
+pub async fn delete(
+    Path((schema, record_id, id)): Path<(String, Uuid, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let editable = comments::check_record(&pool, &schema, record_id, auth_user.record_access().as_ref()).await?;
+
+    let comment = comments::get(&pool, &schema, record_id, id).await?;
+    if comment.author_id != Some(auth_user.user_id) && !editable {
+        return Err(ApiError::forbidden("Only the author or editors of the record may delete a comment"));
+    }
+    if comment.deleted_at.is_none() {
+        comments::delete(&pool, &comment).await?;
+        tracing::info!("Comment {} on {}/{} deleted by '{}'", id, schema, record_id, auth_user.user);
+    }
+    Ok(ApiResponse::success(json!({ "id": id, "deleted": true })))
+}
+
+fn validate_body(body: &str) -> Result<&str, ApiError> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(ApiError::bad_request("Comment body is required"));
+    }
+    if body.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(ApiError::bad_request(format!("Comment body exceeds {} characters", MAX_COMMENT_LENGTH)));
+    }
+    Ok(body)
+}
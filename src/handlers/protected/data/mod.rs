@@ -1,4 +1,5 @@
 pub mod record;
+pub mod render;
 pub mod schema;
 pub mod utils;
 
@@ -8,6 +9,7 @@ pub use record::put as record_put;
 pub use record::patch as record_patch;
 pub use record::delete as record_delete;
 pub use record::restore as record_restore;
+pub use render::post as record_render;
 
 pub use schema::get as schema_get;
 pub use schema::post as schema_post;
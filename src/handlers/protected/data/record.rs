@@ -7,36 +7,74 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 use uuid::Uuid;
 
-use crate::database::repository::Repository;
+use crate::database::comments;
+use crate::database::repository::{QueryParam, Repository};
 use crate::database::record::Record;
+use crate::database::slugs::load_slug_fields;
+use crate::filter::FilterData;
 use crate::error::ApiError;
 use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
 
 
 #[derive(Debug, Deserialize)]
 pub struct RecordQuery {
-    /// Include metadata sections. Examples: meta=true, meta=system,permissions
+    /// Include metadata sections. Examples: meta=true, meta=system,permissions, meta=comments
     pub meta: Option<String>,
+    /// GET only: comma-separated columns to return, e.g. select=name,email; id is always included
+    pub select: Option<String>,
 }
 
-/// GET /api/data/:schema/:id - Get a single record by ID
+/// GET /api/data/:schema/:id - Get a single record by ID, or by slug for x-monk-slug schemas
+///
+/// `?select=name,email` returns only those columns plus `id`. `?meta=comments` adds the
+/// record's comment threads under `meta.relationships.comments`.
 pub async fn get(
     Path((schema, id)): Path<(String, String)>,
     Query(query): Query<RecordQuery>,
     Extension(TenantPool(pool)): Extension<TenantPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
-    // Parse ID as UUID
-    let record_id: Uuid = id.parse()
-        .map_err(|_| ApiError::bad_request(format!("Invalid UUID format: {}", id)))?;
-
-    // Use Repository to select single record by ID
-    let repository = Repository::new(&schema, pool);
-    let record = repository.select_404(record_id).await?;
+    let mut lookup = match id.parse::<Uuid>() {
+        Ok(record_id) => QueryParam::from(record_id).to_filter_data(),
+        Err(_) => {
+            let slug_field = load_slug_fields(&pool, &schema)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| ApiError::bad_request(format!("Invalid UUID format: {}", id)))?;
+            FilterData {
+                where_clause: Some(json!({ slug_field.field: id })),
+                ..Default::default()
+            }
+        }
+    };
+    lookup.select = query.select.as_deref().map(|select| {
+        select.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect()
+    });
+
+    // Use Repository to select single record by ID or slug; opening it counts as a view
+    let repository = Repository::new(&schema, pool.clone())
+        .with_access(auth_user.record_access())
+        .with_viewer(Some(auth_user.user_id));
+    let record = repository.select_404(lookup).await?;
 
     // Return single record (not array)
     let data = record.to_api_output();
-    Ok(ApiResponse::success(data))
+    let with_comments = query
+        .meta
+        .as_deref()
+        .is_some_and(|meta| meta.split(',').any(|section| section.trim() == "comments"));
+    if !with_comments {
+        return Ok(ApiResponse::success(data));
+    }
+
+    let record_id = data
+        .get("id")
+        .and_then(Value::as_str)
+        .and_then(|id| id.parse::<Uuid>().ok())
+        .ok_or_else(|| ApiError::internal_server_error("Record has no id"))?;
+    let threads = comments::thread(comments::list(&pool, &schema, record_id).await?);
+    Ok(ApiResponse::success(data).with_meta(json!({ "relationships": { "comments": threads } })))
 }
 
 /// PUT /api/data/:schema/:id - Update a record by ID (upsert behavior)
@@ -56,7 +94,9 @@ pub async fn put(
     record.set_id(record_id);
 
     // Use Repository upsert (update if exists, create if not)
-    let repository = Repository::new(&schema, pool);
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
     let upserted_record = repository.upsert_one(record).await?;
 
     // Return single updated/created record
@@ -80,7 +120,9 @@ pub async fn patch(
     let updates_record = Record::from_json_object(payload)?;
 
     // Use Repository update_404 (requires record to exist)
-    let repository = Repository::new(&schema, pool);
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
     let updated_record = repository.update_404(record_id, updates_record).await?;
 
     // Return single updated record
@@ -100,7 +142,9 @@ pub async fn delete(
         .map_err(|_| ApiError::bad_request(format!("Invalid UUID format: {}", id)))?;
 
     // Use Repository delete_404 (requires record to exist, handles soft delete)
-    let repository = Repository::new(&schema, pool);
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
     let deleted_record = repository.delete_404(record_id).await?;
 
     // Return single deleted record (with soft delete timestamps)
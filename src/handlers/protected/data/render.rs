@@ -0,0 +1,83 @@
+use axum::extract::{Extension, Path, Query};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::documents::{self, DocumentError};
+use crate::database::files::{self, NewFile};
+use crate::database::repository::Repository;
+use crate::error::ApiError;
+use crate::handlers::protected::features::require_feature;
+use crate::handlers::protected::file::attachments::rejected;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct RenderQuery {
+    /// Name of a document_templates record
+    pub template: String,
+    /// html | pdf (default: the template's format)
+    pub format: Option<String>,
+    /// Comma-separated relationship and related schema names to include (default: all)
+    pub include: Option<String>,
+}
+
+/// POST /api/data/:schema/:id/render?template=invoice - Render a document from a record
+///
+/// Renders the named document template with the record and its relationships, and stores
+/// the result as a file attached to the record; answers 201 with the file, downloadable from
+/// GET /api/file/:schema/:id/:file_id. `?format=pdf` prints the HTML with a headless
+/// browser and requires the tenant's `render_pdf` feature flag.
+pub async fn post(
+    Path((schema, id)): Path<(String, Uuid)>,
+    Query(query): Query<RenderQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let template = documents::load_template(&pool, &query.template).await.map_err(document_error)?;
+    let format = query.format.unwrap_or_else(|| template.format.clone());
+    if format == "pdf" {
+        require_feature(&pool, "render_pdf").await?;
+    }
+
+    let access = auth_user.record_access();
+    let record = Repository::new(&schema, pool.clone())
+        .with_access(access.clone())
+        .select_404(id)
+        .await?;
+    let includes: Option<Vec<String>> = query.include.as_deref().map(|include| {
+        include.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect()
+    });
+    let context = documents::context(&pool, &schema, record.to_api_output(), access, includes.as_deref()).await?;
+    let document = documents::render(&template, &schema, id, &context, &format)
+        .await
+        .map_err(document_error)?;
+
+    let max_size = crate::config::config().api.file_max_size_bytes;
+    let file = files::upload(&pool, &auth_user.database, NewFile {
+        schema_name: schema,
+        record_id: id,
+        filename: document.filename,
+        content_type: document.content_type.to_string(),
+        created_by: Some(auth_user.user_id),
+    }, futures::stream::iter([Ok::<_, std::io::Error>(document.bytes)]), max_size)
+        .await;
+    let file = match file {
+        Ok(file) => file,
+        Err(e) => return Err(rejected(&pool, e, &auth_user).await),
+    };
+
+    tracing::info!("Template '{}' rendered to {} ({} bytes) for {}/{} by '{}'", template.name, format, file.size, file.schema_name, id, auth_user.user);
+    Ok(ApiResponse::created(json!(file)))
+}
+
+fn document_error(e: DocumentError) -> ApiError {
+    match e {
+        DocumentError::TemplateNotFound(_) => ApiError::not_found(e.to_string()),
+        DocumentError::WrongSchema { .. } | DocumentError::Format(_) | DocumentError::Template { .. } => {
+            ApiError::bad_request(e.to_string())
+        }
+        DocumentError::Pdf(_) => ApiError::bad_gateway(e.to_string()),
+        DocumentError::Timeout(_) => ApiError::gateway_timeout(e.to_string()),
+        DocumentError::Database(e) => e.into(),
+    }
+}
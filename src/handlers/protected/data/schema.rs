@@ -6,11 +6,14 @@ use axum::{
 use serde::Deserialize;
 use serde_json::{json, Value};
 
+use crate::api::postgrest;
 use crate::database::repository::Repository;
 use crate::database::record::{Record, RecordVecExt};
 use crate::filter::FilterData;
+use crate::observer::Operation;
 use crate::error::ApiError;
 use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+use super::utils::{list_meta, resolve_count_mode};
 
 
 #[derive(Debug, Deserialize)]
@@ -20,28 +23,59 @@ pub struct ListQuery {
     /// Pagination (optional)
     pub limit: Option<i64>,
     pub offset: Option<i64>,
+    /// Total count in response meta: exact | estimated | none (default from config)
+    pub count_mode: Option<String>,
+    /// Add the exact number of matching records to response meta as `total`. Default: false
+    pub include_total: Option<bool>,
+    /// `next_cursor` from the previous page's meta, to continue after it
+    pub cursor: Option<String>,
+    /// POST only: run validation, security and enrichment and return the statements that
+    /// would be executed instead of writing
+    pub dry_run: Option<bool>,
 }
 
 /// GET /api/data/:schema - List all records in a schema
+///
+/// Also accepts the PostgREST query dialect for clients migrating from PostgREST,
+/// e.g. `?name=eq.Alice&age=gt.30&order=age.desc&select=id,name`. Response meta carries
+/// `limit`, `offset` and `has_more` for building pagers (the limit defaults to
+/// filter.max_limit when configured), `next_cursor` for keyset paging
+/// with `?cursor=`, and the exact `total` with `?include_total=true`.
 pub async fn get(
     Path(schema): Path<String>, 
     Query(query): Query<ListQuery>,
+    Query(params): Query<Vec<(String, String)>>,
     Extension(TenantPool(pool)): Extension<TenantPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
-    // Use Repository with clean select_all method
-    let repository = Repository::new(&schema, pool);
-    let records = repository.select_all(
-        query.limit.map(|l| l.max(0) as i32),
-        query.offset.map(|o| o.max(0) as i32)
-    ).await?;
+    let count_mode = resolve_count_mode(query.count_mode.as_deref())?;
+    let repository = Repository::new(&schema, pool).with_access(auth_user.record_access());
+
+    let mut filter_data = if postgrest::is_postgrest_query(&params) {
+        postgrest::to_filter_data(&params)
+            .map_err(|e| ApiError::bad_request(e.to_string()))?
+    } else {
+        FilterData {
+            limit: query.limit.map(|l| l.max(0) as i32),
+            offset: query.offset.map(|o| o.max(0) as i32),
+            ..Default::default()
+        }
+    };
+    filter_data.cursor = query.cursor.clone();
+    filter_data.default_limit(crate::config::CONFIG.filter.max_limit);
+    let total = repository.total(&filter_data, count_mode).await?;
+    let records = repository.select_any(filter_data.clone()).await?;
+    let meta = list_meta(&repository, &filter_data, &records, query.include_total.unwrap_or(false), total, count_mode).await?;
 
     // Use Record's ergonomic API output helper and return clean data
     let data = records.to_api();
-    Ok(ApiResponse::success(data))
+    Ok(ApiResponse::success(data).with_meta(meta))
 }
 
 /// POST /api/data/:schema - Create multiple records in the schema (bulk operation)
+///
+/// With `?dry_run=true` nothing is written: Rings 0-4 run as usual and the response lists
+/// one SQL operation per record, with its statement, parameters and field changes.
 pub async fn post(
     Path(schema): Path<String>,
     Query(query): Query<ListQuery>,
@@ -53,7 +87,13 @@ pub async fn post(
     let records = Record::from_json_array(payload)?;
 
     // Use Repository to create all records (handles observer pipeline)
-    let repository = Repository::new(&schema, pool);
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
+    if query.dry_run.unwrap_or(false) {
+        let operations = repository.preview(Operation::Create, records).await?;
+        return Ok(ApiResponse::success(json!({ "dry_run": true, "operations": operations })));
+    }
     let created_records = repository.create_all(records).await?;
 
     // Return array of created records with 201 Created status
@@ -73,7 +113,9 @@ pub async fn put(
     let records = Record::from_json_array(payload)?;
 
     // Use Repository upsert_all method (handles splitting and operations internally)
-    let repository = Repository::new(&schema, pool);
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
     let upserted_records = repository.upsert_all(records).await?;
 
     // Return array of all upserted records
@@ -93,7 +135,9 @@ pub async fn delete(
     let records = Record::from_json_array(payload)?;
 
     // Delete records directly (handles soft delete and ID validation via repository/observer pipeline)
-    let repository = Repository::new(&schema, pool);
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
     let deleted_records = repository.delete_all(records).await?;
 
     // Return array of deleted records (with soft delete timestamps)
@@ -113,7 +157,9 @@ pub async fn patch(
     let records = Record::from_json_array(payload)?;
 
     // Update all records (ID validation and 404 handling via repository/observer pipeline)
-    let repository = Repository::new(&schema, pool);
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
     let updated_records = repository.update_all(records).await?;
 
     // Return array of updated records
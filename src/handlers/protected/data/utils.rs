@@ -7,4 +7,88 @@ pub fn resolve_tenant_db(param: &Option<String>) -> Result<String, String> {
         return Ok(env_db);
     }
     Err("tenant database not specified; provide ?tenant=tenant_<hash> or set MONK_TENANT_DB".to_string())
-}
\ No newline at end of file
+}
+
+/// Resolve ?count_mode, defaulting to `filter.default_count_mode`
+pub fn resolve_count_mode(param: Option<&str>) -> Result<crate::filter::CountMode, crate::error::ApiError> {
+    match param {
+        Some(mode) => crate::filter::CountMode::parse(mode).ok_or_else(|| {
+            crate::error::ApiError::bad_request(format!(
+                "Invalid count_mode '{}'; expected exact, estimated or none",
+                mode
+            ))
+        }),
+        None => Ok(crate::filter::CountMode::config_default()),
+    }
+}
+
+/// Response meta for a computed total, or None when counting is disabled
+pub fn count_meta(total: Option<i64>, mode: crate::filter::CountMode) -> Option<serde_json::Value> {
+    total.map(|count| serde_json::json!({ "count": count, "count_mode": mode.as_str() }))
+}
+
+/// Response meta for one page of a list or find: the count from `count_meta`, plus
+/// `limit`, `offset` and `has_more`, and the exact `total` when `include_total` is set
+///
+/// `has_more` is read from the exact total when there is one, and otherwise by probing
+/// for a row just past the page, so it costs at most one indexed lookup. While there are
+/// more rows, `next_cursor` continues after the page's last record; it is left out when
+/// the select omits one of the sort columns.
+pub async fn list_meta(
+    repository: &crate::database::repository::Repository,
+    filter_data: &crate::filter::FilterData,
+    records: &[crate::database::record::Record],
+    include_total: bool,
+    counted: Option<i64>,
+    mode: crate::filter::CountMode,
+) -> Result<serde_json::Value, crate::database::manager::DatabaseError> {
+    use crate::filter::filter_cursor::FilterCursor;
+    use crate::filter::filter_order::FilterOrder;
+    use crate::filter::filter_search::FilterSearch;
+    use crate::filter::{CountMode, FilterData};
+
+    let returned = records.len();
+    let limit = filter_data
+        .limit
+        .map(|limit| crate::config::CONFIG.filter.max_limit.map_or(limit, |max| limit.min(max)));
+    // offsets only apply together with a limit
+    let offset = limit.and(filter_data.offset).unwrap_or(0).max(0) as i64;
+    let exact = match (mode, counted) {
+        (CountMode::Exact, Some(count)) => Some(count),
+        _ if include_total => Some(repository.count(filter_data.clone()).await?),
+        _ => None,
+    };
+
+    // a cursor page's position within the total is unknown
+    let has_more = match (limit, exact) {
+        (Some(_), Some(total)) if filter_data.cursor.is_none() => offset + (returned as i64) < total,
+        (Some(limit), None) if returned >= limit as usize => {
+            let next = FilterData {
+                select: Some(vec!["id".to_string()]),
+                limit: Some(1),
+                offset: Some(offset as i32 + returned as i32),
+                ..filter_data.clone()
+            };
+            !repository.select_any(next).await?.is_empty()
+        }
+        _ => false,
+    };
+
+    let mut meta = count_meta(counted, mode).unwrap_or_else(|| serde_json::json!({}));
+    if include_total {
+        meta["total"] = serde_json::json!(exact);
+    }
+    meta["limit"] = serde_json::json!(limit);
+    meta["offset"] = serde_json::json!(offset);
+    meta["has_more"] = serde_json::json!(has_more);
+    // pages ranked by search relevance have no sort keys to continue after; they use offset
+    let ranked = filter_data.order.is_none() && filter_data.where_clause.as_ref().and_then(FilterSearch::top_level).is_some();
+    if has_more && !ranked {
+        let order = FilterOrder::validate_and_parse(filter_data.order.as_ref().unwrap_or(&serde_json::Value::Null))
+            .map_err(crate::database::manager::DatabaseError::Filter)?;
+        if let Some(cursor) = records.last().and_then(|last| FilterCursor::encode(&order, &last.to_json())) {
+            meta["next_cursor"] = serde_json::json!(cursor);
+        }
+    }
+    Ok(meta)
+}
@@ -7,7 +7,7 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 
 use crate::services::describe_service::DescribeService;
-use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+use crate::middleware::{TenantPool, TenantDdlPool, AuthUser, ApiResponse, ApiResult};
 use crate::error::ApiError;
 
 #[derive(Debug, Deserialize)]
@@ -49,7 +49,7 @@ pub async fn post(
     Path((schema, column)): Path<(String, String)>,
     Query(query): Query<ColumnQuery>,
     Json(payload): Json<Value>,
-    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(TenantDdlPool(pool)): Extension<TenantDdlPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
     // Extract required flag from query params or default to false
@@ -80,7 +80,7 @@ pub async fn patch(
     Path((schema, column)): Path<(String, String)>,
     Query(query): Query<ColumnQuery>,
     Json(payload): Json<Value>,
-    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(TenantDdlPool(pool)): Extension<TenantDdlPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
     // Extract required flag from query params (optional for updates)
@@ -108,7 +108,7 @@ pub async fn patch(
 pub async fn delete(
     Path((schema, column)): Path<(String, String)>,
     Query(_query): Query<ColumnQuery>,
-    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(TenantDdlPool(pool)): Extension<TenantDdlPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
     let service = DescribeService::new(pool);
@@ -6,6 +6,7 @@ pub use schema::get as schema_get;
 pub use schema::post as schema_post;
 pub use schema::patch as schema_patch;
 pub use schema::delete as schema_delete;
+pub use schema::lint as schema_lint;
 
 // Re-export column handler functions for use in routing
 pub use column::get as column_get;
@@ -7,7 +7,7 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 
 use crate::error::ApiError;
-use crate::middleware::{ApiResponse, ApiResult, AuthUser, TenantPool};
+use crate::middleware::{ApiResponse, ApiResult, AuthUser, TenantPool, TenantDdlPool};
 use crate::services::describe_service::DescribeService;
 
 #[derive(Debug, Deserialize)]
@@ -52,7 +52,7 @@ pub async fn post(
     Path(schema): Path<String>,
     Query(_query): Query<DescribeQuery>,
     Json(payload): Json<Value>,
-    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(TenantDdlPool(pool)): Extension<TenantDdlPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
     let service = DescribeService::new(pool);
@@ -77,7 +77,7 @@ pub async fn patch(
     Path(schema): Path<String>,
     Query(_query): Query<DescribeQuery>,
     Json(payload): Json<Value>,
-    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(TenantDdlPool(pool)): Extension<TenantDdlPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
     let service = DescribeService::new(pool);
@@ -99,7 +99,7 @@ pub async fn patch(
 pub async fn delete(
     Path(schema): Path<String>,
     Query(_query): Query<DescribeQuery>,
-    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(TenantDdlPool(pool)): Extension<TenantDdlPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
     // Create DescribeService and delete schema
@@ -113,6 +113,37 @@ pub async fn delete(
     })))
 }
 
+/// POST /api/meta/_lint - Check a schema definition for problems before applying it
+///
+/// Takes the same body as POST /api/describe/:schema, with the schema name in `name`.
+/// Reports errors (the definition would be rejected or leave a broken table, e.g. system
+/// field collisions, names that are not lowercase identifiers or are SQL keywords, derived
+/// constraint names over 63 bytes, missing relationship targets) and warnings (missing
+/// descriptions, keywords the property type ignores). Nothing is written.
+///
+/// Expected Output:
+/// ```json
+/// {
+///   "success": true,
+///   "data": {
+///     "schema": "invoice",
+///     "valid": false,
+///     "errors": [{ "path": "properties.id", "message": "'id' is a system column every table already has" }],
+///     "warnings": [{ "path": "properties.total.description", "message": "Property has no description" }]
+///   }
+/// }
+/// ```
+pub async fn lint(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Json(payload): Json<Value>,
+) -> ApiResult<Value> {
+    let schema_name = payload.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+    let service = DescribeService::new(pool);
+    let report = service.lint(&schema_name, &payload).await?;
+
+    Ok(ApiResponse::success(json!(report)))
+}
+
 /*
 SCHEMA MANAGEMENT IN RUST:
 
@@ -0,0 +1,154 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::export_jobs::{self, ExportJob, NewExportJob, EXPORT_COMPRESSIONS, EXPORT_DESTINATIONS, EXPORT_FORMATS};
+use crate::error::ApiError;
+use crate::filter::FilterData;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// Jobs returned by GET /api/export
+const LIST_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct ExportRequest {
+    pub schema: String,
+    /// Same shape as the POST /api/find body; `order`, `limit` and `offset` are ignored
+    #[serde(default)]
+    pub filter: FilterData,
+    #[serde(default = "default_format")]
+    pub format: String,
+    #[serde(default = "default_compression")]
+    pub compression: String,
+    #[serde(default = "default_destination")]
+    pub destination: String,
+    /// Presigned S3 PUT URL, required when destination is "s3"
+    pub upload_url: Option<String>,
+}
+
+fn default_format() -> String {
+    "ndjson".to_string()
+}
+
+fn default_compression() -> String {
+    "none".to_string()
+}
+
+fn default_destination() -> String {
+    "download".to_string()
+}
+
+/// POST /api/export - Start a background export job
+///
+/// Body: { "schema": "orders", "filter": { "where": ... }, "format": "ndjson" | "json" | "csv",
+/// "compression": "none" | "gzip", "destination": "download" | "s3", "upload_url": "..." }.
+/// Answers 202 with the job; poll GET /api/export/:id for progress.
+pub async fn create(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(request): Json<ExportRequest>,
+) -> ApiResult<Value> {
+    let check = |name: &str, value: &str, allowed: &[&str]| {
+        if allowed.contains(&value) {
+            Ok(())
+        } else {
+            Err(ApiError::bad_request(format!("Invalid {} '{}': expected one of {}", name, value, allowed.join(", "))))
+        }
+    };
+    check("format", &request.format, EXPORT_FORMATS)?;
+    check("compression", &request.compression, EXPORT_COMPRESSIONS)?;
+    check("destination", &request.destination, EXPORT_DESTINATIONS)?;
+
+    let upload_url = match (request.destination.as_str(), request.upload_url) {
+        ("s3", Some(url)) if url.starts_with("https://") => Some(url),
+        ("s3", _) => return Err(ApiError::bad_request("s3 destinations require an https upload_url (a presigned PUT URL)")),
+        (_, _) => None,
+    };
+
+    let job = export_jobs::create_job(&pool, NewExportJob {
+        schema_name: request.schema,
+        filter: request.filter,
+        format: request.format,
+        compression: request.compression,
+        destination: request.destination,
+        upload_url,
+        created_by: auth_user.user_id,
+        access: auth_user.record_access(),
+    }).await?;
+
+    export_jobs::spawn(auth_user.database.clone(), pool, job.id);
+    tracing::info!("Export job {} of '{}' started by '{}' in tenant '{}'", job.id, job.schema_name, auth_user.user, auth_user.tenant);
+
+    Ok(ApiResponse::accepted(job_json(&auth_user, &job)))
+}
+
+/// GET /api/export - Recent export jobs (all jobs for root, otherwise the caller's own)
+pub async fn list(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let jobs: Vec<Value> = export_jobs::list_jobs(&pool, LIST_LIMIT)
+        .await?
+        .iter()
+        .filter(|job| can_access(&auth_user, job))
+        .map(|job| job_json(&auth_user, job))
+        .collect();
+
+    Ok(ApiResponse::success(json!(jobs)))
+}
+
+/// GET /api/export/:id - Job status and progress; completed download jobs include a signed URL
+pub async fn get(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let job = accessible_job(&pool, &auth_user, id).await?;
+    Ok(ApiResponse::success(job_json(&auth_user, &job)))
+}
+
+/// POST /api/export/:id/resume - Continue a failed or interrupted job from its last checkpoint
+pub async fn resume(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let job = accessible_job(&pool, &auth_user, id).await?;
+
+    if job.status == "completed" {
+        return Err(ApiError::conflict(format!("Export job '{}' has already completed", id)));
+    }
+    if !export_jobs::spawn(auth_user.database.clone(), pool, id) {
+        return Err(ApiError::conflict(format!("Export job '{}' is already running", id)));
+    }
+    tracing::info!("Export job {} resumed from row {} by '{}'", id, job.processed_rows, auth_user.user);
+
+    Ok(ApiResponse::accepted(job_json(&auth_user, &job)))
+}
+
+fn can_access(auth_user: &AuthUser, job: &ExportJob) -> bool {
+    auth_user.access == "root" || job.created_by == Some(auth_user.user_id)
+}
+
+async fn accessible_job(pool: &sqlx::PgPool, auth_user: &AuthUser, id: Uuid) -> Result<ExportJob, ApiError> {
+    let job = export_jobs::get_job(pool, id).await?;
+    if !can_access(auth_user, &job) {
+        return Err(ApiError::not_found(format!("Export job '{}' not found", id)));
+    }
+    Ok(job)
+}
+
+/// Job as returned to clients, with `interrupted` flagged and a download URL once complete
+fn job_json(auth_user: &AuthUser, job: &ExportJob) -> Value {
+    let mut data = json!(job);
+    data["interrupted"] = json!(job.status == "running" && !export_jobs::is_active(job.id));
+
+    if job.status == "completed" && job.destination == "download" {
+        let (url, expires) = export_jobs::download_url(&auth_user.database, job.id);
+        data["download_url"] = json!(url);
+        data["download_expires_at"] = json!(chrono::DateTime::from_timestamp(expires, 0).map(|t| t.to_rfc3339()));
+    }
+    data
+}
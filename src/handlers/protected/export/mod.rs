@@ -0,0 +1,7 @@
+pub mod jobs;
+
+// Re-export handler functions for use in routing
+pub use jobs::create as export_create;
+pub use jobs::list as export_list;
+pub use jobs::get as export_get;
+pub use jobs::resume as export_resume;
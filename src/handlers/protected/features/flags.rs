@@ -0,0 +1,65 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+
+use crate::database::feature_flags::{self, FEATURE_FLAGS};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct FeatureFlagUpdate {
+    pub enabled: bool,
+}
+
+/// GET /api/features - Feature flags evaluated for the caller's tenant
+///
+/// Returns each known flag with its effective value, default and description.
+pub async fn get(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+) -> ApiResult<Value> {
+    let flags = feature_flags::load_feature_flags(&pool).await?;
+
+    let data: Vec<Value> = FEATURE_FLAGS
+        .iter()
+        .map(|(name, default, description)| json!({
+            "name": name,
+            "enabled": flags.get(*name).copied().unwrap_or(*default),
+            "default": default,
+            "description": description,
+        }))
+        .collect();
+
+    Ok(ApiResponse::success(json!(data)))
+}
+
+/// PUT /api/features/:name - Enable or disable a flag for the caller's tenant
+///
+/// Body: { "enabled": true }. Restricted to root users.
+pub async fn put(
+    Path(name): Path<String>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(update): Json<FeatureFlagUpdate>,
+) -> ApiResult<Value> {
+    if auth_user.access != "root" {
+        return Err(ApiError::forbidden("Changing feature flags requires root access"));
+    }
+
+    feature_flags::set_feature_flag(&pool, &name, update.enabled).await?;
+    tracing::info!("Feature flag '{}' set to {} for tenant '{}' by '{}'", name, update.enabled, auth_user.tenant, auth_user.user);
+
+    Ok(ApiResponse::success(json!({ "name": name, "enabled": update.enabled })))
+}
+
+/// Reject the request unless `name` is enabled for the tenant
+///
+/// Disabled capabilities answer 404 so clients treat them like routes that do not exist.
+pub async fn require_feature(pool: &PgPool, name: &str) -> Result<(), ApiError> {
+    if feature_flags::is_enabled(pool, name).await? {
+        Ok(())
+    } else {
+        Err(ApiError::not_found(format!("Feature '{}' is not enabled for this tenant", name)))
+    }
+}
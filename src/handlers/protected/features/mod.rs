@@ -0,0 +1,6 @@
+pub mod flags;
+
+// Re-export handler functions for use in routing
+pub use flags::get as features_get;
+pub use flags::put as features_put;
+pub use flags::require_feature;
@@ -0,0 +1,225 @@
+use axum::body::Body;
+use axum::extract::{Extension, Path, Query};
+use axum::http::{header, HeaderMap, HeaderName};
+use axum::response::{IntoResponse, Redirect, Response};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::database::comments;
+use crate::database::files::{self, FileError, NewFile};
+use crate::database::images;
+use crate::database::repository::Repository;
+use crate::database::s3::S3Bucket;
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    /// Name reported on download; defaults to the file id
+    pub filename: Option<String>,
+}
+
+/// GET /api/file/:schema/:id - Attachments of a record
+pub async fn list(
+    Path((schema, id)): Path<(String, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_record(&pool, &schema, id, &auth_user).await?;
+    let attachments = files::list_files(&pool, &schema, id).await?;
+    Ok(ApiResponse::success(json!(attachments)))
+}
+
+/// POST /api/file/:schema/:id?filename=report.pdf - Attach a file to a record
+///
+/// The request body is the raw file, streamed to storage as it arrives; its
+/// Content-Type header is stored and returned on download.
+/// When a scanner is configured, an infected file is quarantined and answered with 422.
+/// Requires edit access to the record.
+pub async fn upload(
+    Path((schema, id)): Path<(String, Uuid)>,
+    Query(query): Query<UploadQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    headers: HeaderMap,
+    body: Body,
+) -> ApiResult<Value> {
+    require_editable_record(&pool, &schema, id, &auth_user).await?;
+
+    let filename = query.filename.unwrap_or_default();
+    check_filename(&filename)?;
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    let max_size = crate::config::config().api.file_max_size_bytes;
+    let file = files::upload(&pool, &auth_user.database, NewFile {
+        schema_name: schema,
+        record_id: id,
+        filename,
+        content_type,
+        created_by: Some(auth_user.user_id),
+    }, body.into_data_stream(), max_size)
+        .await;
+    let file = match file {
+        Ok(file) => file,
+        Err(e) => return Err(rejected(&pool, e, &auth_user).await),
+    };
+
+    tracing::info!("File {} ({} bytes) attached to {}/{} by '{}'", file.id, file.size, file.schema_name, id, auth_user.user);
+    Ok(ApiResponse::created(json!(file)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VariantQuery {
+    /// Scale an image down to fit this width and/or height, keeping its aspect ratio
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// GET /api/file/:schema/:id/:file_id - Stream an attachment's bytes
+///
+/// For JPEG, PNG, GIF and WebP images, `?width=` and/or `?height=` return a scaled-down
+/// variant instead, generated on first request and cached in storage. Full downloads carry
+/// the file's SHA-256 in `X-Checksum-Sha256` and are checked against it as they stream.
+pub async fn download(
+    Path((schema, id, file_id)): Path<(String, Uuid, Uuid)>,
+    Query(query): Query<VariantQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Response, ApiError> {
+    require_record(&pool, &schema, id, &auth_user).await?;
+    let file = files::get_file(&pool, &schema, id, file_id).await?;
+    if file.status == "quarantined" {
+        return Err(ApiError::forbidden(format!(
+            "File '{}' is quarantined: {}",
+            file_id,
+            file.scan_result.as_deref().unwrap_or("malware detected")
+        )));
+    }
+    let filename = if file.filename.is_empty() { file.id.to_string() } else { file.filename.clone() };
+
+    if query.width.is_some() || query.height.is_some() {
+        if !images::is_resizable(&file.content_type) {
+            return Err(ApiError::bad_request(format!("File '{}' is not a resizable image ({})", file_id, file.content_type)));
+        }
+        let max = crate::config::config().api.file_image_max_dimension;
+        if [query.width, query.height].into_iter().flatten().any(|d| d == 0 || d > max) {
+            return Err(ApiError::bad_request(format!("width and height must be between 1 and {}", max)));
+        }
+
+        let bytes = files::variant(&pool, &file, query.width, query.height).await.map_err(file_error)?;
+        return Ok((
+            [
+                (header::CONTENT_TYPE, images::variant_content_type(&file.content_type).to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+                (header::ETAG, format!("\"{}\"", images::variant_key(&file.checksum, query.width, query.height))),
+            ],
+            bytes,
+        )
+            .into_response());
+    }
+
+    // Presigned uploads are served by the bucket too; the URL outlives the redirect only briefly
+    if file.location == "s3" {
+        let url = S3Bucket::configured()
+            .ok_or_else(|| ApiError::gone(format!("Contents of file '{}' are no longer available", file_id)))?
+            .presign("GET", &file.storage_key, 300, crate::clock::now())
+            .map_err(|e| ApiError::internal_server_error(format!("Invalid S3 endpoint: {}", e)))?;
+        return Ok(Redirect::temporary(&url).into_response());
+    }
+
+    let reader = files::storage()
+        .open(&file.storage_key)
+        .await
+        .map_err(|_| ApiError::gone(format!("Contents of file '{}' are no longer available", file_id)))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, file.content_type.clone()),
+            (header::CONTENT_LENGTH, file.size.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+            (header::ETAG, format!("\"{}\"", file.checksum)),
+            (HeaderName::from_static("x-checksum-sha256"), file.checksum.clone()),
+        ],
+        // Bytes that no longer match the recorded checksum abort the response mid-body
+        Body::from_stream(files::verify_stream(ReaderStream::new(reader), file.checksum.clone())),
+    )
+        .into_response())
+}
+
+/// DELETE /api/file/:schema/:id/:file_id - Remove an attachment; requires edit access to the record
+pub async fn delete(
+    Path((schema, id, file_id)): Path<(String, Uuid, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_editable_record(&pool, &schema, id, &auth_user).await?;
+    let file = files::get_file(&pool, &schema, id, file_id).await?;
+
+    files::delete_file(&pool, &file).await.map_err(|e| match e {
+        FileError::Database(e) => e.into(),
+        other => {
+            tracing::error!("Attachment removal failed: {}", other);
+            ApiError::internal_server_error("Failed to remove file")
+        }
+    })?;
+
+    tracing::info!("File {} removed from {}/{} by '{}'", file_id, schema, id, auth_user.user);
+    Ok(ApiResponse::success(json!(file)))
+}
+
+/// Filenames end up in Content-Disposition headers and must stay a single path segment
+pub(super) fn check_filename(filename: &str) -> Result<(), ApiError> {
+    if filename.contains(['/', '\\', '"']) || filename.chars().any(char::is_control) {
+        return Err(ApiError::bad_request("filename must not contain slashes, quotes or control characters"));
+    }
+    Ok(())
+}
+
+/// Map a storage failure to its API error
+pub(super) fn file_error(e: FileError) -> ApiError {
+    match e {
+        FileError::TooLarge(_) | FileError::QuotaExceeded { .. } => ApiError::payload_too_large(e.to_string()),
+        FileError::Empty | FileError::Interrupted(_) => ApiError::bad_request(e.to_string()),
+        FileError::NotUploaded => ApiError::conflict(e.to_string()),
+        FileError::Image(_) | FileError::Infected { .. } => ApiError::unprocessable_entity(e.to_string(), Default::default()),
+        FileError::ScanFailed(_) => {
+            tracing::error!("Attachment scan failed: {}", e);
+            ApiError::service_unavailable("File could not be scanned for malware; try again later")
+        }
+        FileError::PresignDisabled => ApiError::bad_request(e.to_string()),
+        FileError::Database(e) => e.into(),
+        FileError::Io(_) | FileError::Remote(_) => {
+            tracing::error!("Attachment storage failed: {}", e);
+            ApiError::internal_server_error("Failed to store file")
+        }
+    }
+}
+
+/// Map an upload failure to its API error, reporting quarantined files first
+pub(crate) async fn rejected(pool: &PgPool, e: FileError, auth_user: &AuthUser) -> ApiError {
+    if let FileError::Infected { file, signature } = &e {
+        files::report_infection(pool, file, signature, auth_user.audit_actor().as_ref()).await;
+    }
+    file_error(e)
+}
+
+/// Attachments follow their record: a missing, deleted or unreadable record hides its files
+pub(super) async fn require_record(pool: &PgPool, schema: &str, id: Uuid, auth_user: &AuthUser) -> Result<(), ApiError> {
+    Repository::new(schema, pool.clone()).with_access(auth_user.record_access()).select_404(id).await?;
+    Ok(())
+}
+
+/// Adding or removing attachments changes the record, so it needs edit or full access to it
+pub(super) async fn require_editable_record(pool: &PgPool, schema: &str, id: Uuid, auth_user: &AuthUser) -> Result<(), ApiError> {
+    if !comments::check_record(pool, schema, id, auth_user.record_access().as_ref()).await? {
+        return Err(ApiError::forbidden("Changing attachments of a record requires edit access to it"));
+    }
+    Ok(())
+}
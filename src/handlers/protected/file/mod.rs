@@ -0,0 +1,10 @@
+pub mod attachments;
+pub mod presign;
+
+// Re-export handler functions for use in routing
+pub use attachments::list as file_list;
+pub use attachments::upload as file_upload;
+pub use attachments::download as file_download;
+pub use attachments::delete as file_delete;
+pub use presign::presign as file_presign;
+pub use presign::confirm as file_presign_confirm;
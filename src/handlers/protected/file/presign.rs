@@ -0,0 +1,92 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use chrono::Duration;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use super::attachments::{check_filename, file_error, rejected, require_editable_record};
+use crate::database::files::{self, NewFile};
+use crate::database::s3::S3Bucket;
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct PresignRequest {
+    pub schema: String,
+    pub record_id: Uuid,
+    /// Name reported on download; defaults to the file id
+    #[serde(default)]
+    pub filename: String,
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+}
+
+fn default_content_type() -> String {
+    "application/octet-stream".to_string()
+}
+
+/// POST /api/file/presign - Start a direct-to-S3 upload
+///
+/// Body: { "schema": "orders", "record_id": "...", "filename": "scan.tiff", "content_type": "image/tiff" }.
+/// Answers 201 with a pending file and a presigned PUT URL. After uploading, the client
+/// calls POST /api/file/presign/:file_id/confirm; uploads never confirmed are finalized or
+/// discarded by the reconciliation task once the URL has expired. Both calls require edit
+/// access to the record.
+pub async fn presign(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(request): Json<PresignRequest>,
+) -> ApiResult<Value> {
+    let bucket = S3Bucket::configured().ok_or_else(|| ApiError::bad_request("Presigned uploads are not configured"))?;
+    require_editable_record(&pool, &request.schema, request.record_id, &auth_user).await?;
+    check_filename(&request.filename)?;
+
+    let file = files::create_pending(&pool, &auth_user.database, NewFile {
+        schema_name: request.schema,
+        record_id: request.record_id,
+        filename: request.filename,
+        content_type: request.content_type,
+        created_by: Some(auth_user.user_id),
+    })
+        .await
+        .map_err(file_error)?;
+
+    let ttl = crate::config::config().api.file_presign_ttl_secs;
+    let now = crate::clock::now();
+    let url = bucket
+        .presign("PUT", &file.storage_key, ttl, now)
+        .map_err(|e| ApiError::internal_server_error(format!("Invalid S3 endpoint: {}", e)))?;
+
+    tracing::info!("Presigned upload {} for {}/{} issued to '{}'", file.id, file.schema_name, file.record_id, auth_user.user);
+    Ok(ApiResponse::created(json!({
+        "file": file,
+        "upload": {
+            "method": "PUT",
+            "url": url,
+            "headers": { "Content-Type": file.content_type },
+            "expires_at": now + Duration::seconds(ttl as i64),
+        },
+    })))
+}
+
+/// POST /api/file/presign/:file_id/confirm - Finalize a presigned upload
+///
+/// Reads the object back to record its size and checksum, then makes the file visible on
+/// its record. Answers 409 while the bytes have not arrived in the bucket, and 422 when the
+/// configured scanner finds malware and the file is quarantined.
+pub async fn confirm(
+    Path(file_id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let pending = files::get_pending(&pool, file_id).await?;
+    require_editable_record(&pool, &pending.schema_name, pending.record_id, &auth_user).await?;
+
+    let file = match files::finalize(&pool, &pending).await {
+        Ok(file) => file,
+        Err(e) => return Err(rejected(&pool, e, &auth_user).await),
+    };
+    tracing::info!("Presigned upload {} confirmed ({} bytes) by '{}'", file.id, file.size, auth_user.user);
+    Ok(ApiResponse::success(json!(file)))
+}
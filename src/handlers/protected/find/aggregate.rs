@@ -0,0 +1,40 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use serde_json::Value;
+
+use crate::database::record::RecordVecExt;
+use crate::database::repository::Repository;
+use crate::filter::AggregateData;
+use crate::handlers::protected::features::require_feature;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// POST /api/find/:schema/aggregate - Grouped counts, sums, averages, minimums and maximums
+///
+/// Accepts an AggregateData body:
+/// - where_clause: filter conditions, as in POST /api/find/:schema
+/// - group_by: column names and/or bucket specs
+///   - `{ "date_bucket": { "column": "created_at", "unit": "day" }, "as": "day" }`
+///     units: minute, hour, day, week, month, quarter, year
+///   - `{ "histogram": { "column": "amount", "width": 10, "min": 0, "max": 1000 }, "as": "amount" }`
+///     each bucket is reported by its lower bound
+/// - aggregations: functions per column, default `[{ "count": "*" }]`
+///   - `{ "count": "*" }`, `{ "sum": "amount", "as": "revenue" }`, `{ "avg": "amount" }`,
+///     `{ "min": "created_at" }`, `{ "max": "amount" }`; the default alias is e.g. `avg_amount`
+/// - having: conditions on aggregation aliases, e.g. `{ "revenue": { "$gte": 1000 } }`
+///   operators: $eq (or a bare value), $ne, $gt, $gte, $lt, $lte
+/// - limit: maximum number of groups
+///
+/// Returns one row per group with the group keys and aggregation values, ordered by group keys.
+pub async fn post(
+    Path(schema): Path<String>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(aggregate_data): Json<AggregateData>,
+) -> ApiResult<Value> {
+    require_feature(&pool, "find_aggregate").await?;
+
+    let repository = Repository::new(&schema, pool).with_access(auth_user.record_access());
+    let rows = repository.aggregate(aggregate_data).await?;
+
+    Ok(ApiResponse::success(rows.to_api()))
+}
@@ -0,0 +1,44 @@
+use axum::extract::{Extension, Path, Query};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::database::repository::Repository;
+use crate::error::ApiError;
+use crate::filter::{CountMode, FilterData};
+use crate::handlers::protected::data::utils::resolve_count_mode;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct CountQuery {
+    /// exact | estimated. Default: exact
+    pub count_mode: Option<String>,
+}
+
+/// POST /api/find/:schema/count - Number of records matching a filter
+///
+/// Accepts the same FilterData body as POST /api/find/:schema without fetching rows;
+/// select, order, limit and offset are ignored. Returns { count, count_mode }.
+pub async fn post(
+    Path(schema): Path<String>,
+    Query(query): Query<CountQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(filter_data): Json<FilterData>,
+) -> ApiResult<Value> {
+    let count_mode = match query.count_mode.as_deref() {
+        Some(mode) => resolve_count_mode(Some(mode))?,
+        None => CountMode::Exact,
+    };
+    if count_mode == CountMode::None {
+        return Err(ApiError::bad_request("count_mode must be exact or estimated"));
+    }
+
+    let repository = Repository::new(&schema, pool).with_access(auth_user.record_access());
+    let count = repository.total(&filter_data, count_mode).await?.unwrap_or(0);
+
+    Ok(ApiResponse::success(json!({
+        "count": count,
+        "count_mode": count_mode.as_str(),
+    })))
+}
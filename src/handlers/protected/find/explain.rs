@@ -0,0 +1,86 @@
+use axum::extract::{Extension, Path, Query};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::database::repository::Repository;
+use crate::error::ApiError;
+use crate::filter::FilterData;
+use crate::handlers::protected::features::require_feature;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// Longest string parameter echoed back in the parameter summary
+const MAX_PARAM_PREVIEW: usize = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct ExplainQuery {
+    /// Execute the query (EXPLAIN ANALYZE) to report actual timings. Default: false
+    pub analyze: Option<bool>,
+}
+
+/// POST /api/find/:schema/explain - Show the SQL and query plan for a filter
+///
+/// Accepts the same FilterData body as POST /api/find/:schema and returns:
+/// - sql: the generated SELECT statement
+/// - params: bound parameters as { index, type, value }
+/// - plan: PostgreSQL EXPLAIN (FORMAT JSON) output; pass ?analyze=true for actual timings
+///
+/// Restricted to root users, and to tenants with the `find_explain` feature, since it
+/// exposes table internals.
+pub async fn post(
+    Path(schema): Path<String>,
+    Query(query): Query<ExplainQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(filter_data): Json<FilterData>,
+) -> ApiResult<Value> {
+    if auth_user.access != "root" {
+        return Err(ApiError::forbidden("Query explain requires root access"));
+    }
+    require_feature(&pool, "find_explain").await?;
+
+    let analyze = query.analyze.unwrap_or(false);
+    let repository = Repository::new(&schema, pool).with_access(auth_user.record_access());
+    let explain = repository.explain(filter_data, analyze).await?;
+
+    let params: Vec<Value> = explain
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, value)| json!({
+            "index": format!("${}", i + 1),
+            "type": param_type(value),
+            "value": param_preview(value),
+        }))
+        .collect();
+
+    Ok(ApiResponse::success(json!({
+        "schema": schema,
+        "sql": explain.query,
+        "params": params,
+        "analyze": analyze,
+        "plan": explain.plan,
+    })))
+}
+
+fn param_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "float",
+        Value::Number(_) => "integer",
+        Value::String(_) => "text",
+        Value::Array(_) => "array",
+        Value::Object(_) => "jsonb",
+    }
+}
+
+fn param_preview(value: &Value) -> Value {
+    match value {
+        Value::String(s) if s.chars().count() > MAX_PARAM_PREVIEW => {
+            let truncated: String = s.chars().take(MAX_PARAM_PREVIEW).collect();
+            Value::String(format!("{}… ({} chars)", truncated, s.chars().count()))
+        }
+        _ => value.clone(),
+    }
+}
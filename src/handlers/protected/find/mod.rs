@@ -1,5 +1,14 @@
 pub mod schema;
+pub mod explain;
+pub mod sample;
+pub mod aggregate;
+pub mod count;
 
 // Re-export handler functions for use in routing
 pub use schema::post as find_post;
-pub use schema::delete as find_delete;
\ No newline at end of file
+pub use schema::delete as find_delete;
+pub use schema::patch as find_patch;
+pub use explain::post as find_explain;
+pub use sample::post as find_sample;
+pub use aggregate::post as find_aggregate;
+pub use count::post as find_count;
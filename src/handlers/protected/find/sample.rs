@@ -0,0 +1,57 @@
+use axum::extract::{Extension, Path, Query};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::database::record::RecordVecExt;
+use crate::database::repository::Repository;
+use crate::filter::FilterData;
+use crate::handlers::protected::features::require_feature;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// Sample size when ?size is not given
+const DEFAULT_SAMPLE_SIZE: i32 = 20;
+
+/// Hard cap on sample size when filter.max_limit is not configured
+const MAX_SAMPLE_SIZE: i32 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct SampleQuery {
+    /// Number of random rows to return. Default: 20, capped by filter.max_limit
+    pub size: Option<i32>,
+    /// Include per-column statistics for the filtered set. Default: true
+    pub stats: Option<bool>,
+}
+
+/// POST /api/find/:schema/sample - Random sample of matching records with column statistics
+///
+/// Accepts a FilterData body; only `where` is applied (order, limit and offset are
+/// ignored). Returns:
+/// - total: number of rows matching the filter
+/// - method: "random", or "tablesample" when large tables are pre-sampled
+/// - records: up to `size` randomly chosen rows
+/// - stats: per column { type, distinct, min, max } over all matching rows
+pub async fn post(
+    Path(schema): Path<String>,
+    Query(query): Query<SampleQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(filter_data): Json<FilterData>,
+) -> ApiResult<Value> {
+    require_feature(&pool, "find_sample").await?;
+
+    let max_size = crate::config::CONFIG.filter.max_limit.unwrap_or(MAX_SAMPLE_SIZE).min(MAX_SAMPLE_SIZE);
+    let size = query.size.unwrap_or(DEFAULT_SAMPLE_SIZE).clamp(1, max_size);
+
+    let repository = Repository::new(&schema, pool).with_access(auth_user.record_access());
+    let sample = repository.sample(filter_data, size, query.stats.unwrap_or(true)).await?;
+
+    Ok(ApiResponse::success(json!({
+        "schema": schema,
+        "size": size,
+        "total": sample.total,
+        "method": sample.method,
+        "records": sample.records.to_api(),
+        "stats": sample.stats,
+    })))
+}
@@ -7,38 +7,65 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 
 use crate::database::repository::Repository;
+use crate::observer::WriteMode;
 use crate::database::record::{Record, RecordVecExt};
 use crate::filter::FilterData;
 use crate::error::ApiError;
 use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+use crate::handlers::protected::data::utils::{list_meta, resolve_count_mode};
+
+#[derive(Debug, Deserialize)]
+pub struct PatchRequest {
+    /// Records to change: `where`, `order` and `limit` as for POST /api/find/:schema
+    pub filter: FilterData,
+    /// Fields set on every matching record
+    pub change: Value,
+    /// Update each record on its own, so one failing record no longer undoes the others
+    #[serde(default)]
+    pub best_effort: bool,
+}
 
 #[derive(Debug, Deserialize)]
 pub struct FindQuery {
     /// Include metadata sections. Examples: meta=true, meta=system,permissions
     pub meta: Option<String>,
+    /// Total count in response meta: exact | estimated | none (default from config)
+    pub count_mode: Option<String>,
+    /// Add the exact number of matching records to response meta as `total`. Default: false
+    pub include_total: Option<bool>,
 }
 
 /// POST /api/find/:schema - Advanced filtered search
 /// 
 /// Accepts a FilterData JSON body with:
-/// - select: fields to return
+/// - select: fields to return; `id` is always included and unknown fields are rejected
 /// - where: filter conditions
 /// - order: sort order
-/// - limit/offset: pagination
+/// - limit/offset: pagination; an absent limit defaults to filter.max_limit when configured
+/// - cursor: keyset pagination, continuing after the page that returned this `next_cursor`
+///
+/// Response meta carries `limit`, `offset` and `has_more`, `next_cursor` while there are
+/// more rows, and the exact `total` with `?include_total=true`. Cursor pages stay fast on
+/// large tables and do not shift when earlier rows change; send the same order with them.
 pub async fn post(
     Path(schema): Path<String>,
     Query(query): Query<FindQuery>,
-    Json(filter_data): Json<FilterData>,
+    Json(mut filter_data): Json<FilterData>,
     Extension(TenantPool(pool)): Extension<TenantPool>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
+    let count_mode = resolve_count_mode(query.count_mode.as_deref())?;
+    filter_data.default_limit(crate::config::CONFIG.filter.max_limit);
+
     // Use Repository to select records with filter criteria
-    let repository = Repository::new(&schema, pool);
-    let records = repository.select_any(filter_data).await?;
+    let repository = Repository::new(&schema, pool).with_access(auth_user.record_access());
+    let total = repository.total(&filter_data, count_mode).await?;
+    let records = repository.select_any(filter_data.clone()).await?;
+    let meta = list_meta(&repository, &filter_data, &records, query.include_total.unwrap_or(false), total, count_mode).await?;
 
     // Return array of matching records
     let data = records.to_api();
-    Ok(ApiResponse::success(data))
+    Ok(ApiResponse::success(data).with_meta(meta))
 }
 
 /// DELETE /api/find/:schema - Bulk delete matching records
@@ -52,10 +79,66 @@ pub async fn delete(
     Extension(auth_user): Extension<AuthUser>,
 ) -> ApiResult<Value> {
     // Use Repository to delete records matching filter criteria
-    let repository = Repository::new(&schema, pool);
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
     let deleted_records = repository.delete_any(filter_data).await?;
 
     // Return array of deleted records (with soft delete timestamps)
     let data = deleted_records.to_api();
     Ok(ApiResponse::success(data))
-}
\ No newline at end of file
+}
+/// PATCH /api/find/:schema - Apply one partial change to every matching record
+///
+/// Body: { "filter": { "where": ... }, "change": { "status": "archived" } }. Each record is
+/// updated through the observer pipeline like PATCH /api/data/:schema/:id, so validation,
+/// access control and audit apply per record. A where clause is required; `{}` matches
+/// every record. The records are updated all or nothing, unless `"best_effort": true`
+/// updates them one by one and reports each failure in its own result. Meta carries the
+/// number of records matched, updated and failed.
+pub async fn patch(
+    Path(schema): Path<String>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(request): Json<PatchRequest>,
+) -> ApiResult<Value> {
+    if request.filter.where_clause.is_none() {
+        return Err(ApiError::bad_request("PATCH by filter requires a where clause; use {} to match every record"));
+    }
+    let change = Record::from_json(request.change)?;
+    if change.to_hashmap().is_empty() {
+        return Err(ApiError::bad_request("Change document has no fields"));
+    }
+
+    let repository = Repository::new(&schema, pool)
+        .with_access(auth_user.record_access())
+        .with_actor(auth_user.audit_actor());
+
+    let results: Vec<Value> = if request.best_effort {
+        let repository = repository.with_write_mode(WriteMode::BestEffort);
+        let mut results = Vec::new();
+        for mut record in repository.select_any(request.filter).await? {
+            let id = record.id();
+            record.apply_changes(change.to_hashmap());
+            results.push(match repository.update_one(record).await {
+                Ok(updated) => json!({ "id": id, "success": true, "record": updated.to_api_output() }),
+                Err(error) => json!({ "id": id, "success": false, "error": ApiError::from(error).to_json() }),
+            });
+        }
+        results
+    } else {
+        repository
+            .update_any(request.filter, change)
+            .await?
+            .iter()
+            .map(|record| json!({ "id": record.id(), "success": true, "record": record.to_api_output() }))
+            .collect()
+    };
+
+    let updated = results.iter().filter(|result| result["success"] == json!(true)).count();
+    Ok(ApiResponse::success(json!(results)).with_meta(json!({
+        "matched": results.len(),
+        "updated": updated,
+        "failed": results.len() - updated,
+    })))
+}
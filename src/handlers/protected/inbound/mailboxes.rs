@@ -0,0 +1,104 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::database::mailboxes::{self, Mailbox, NewMailbox};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+use crate::services::inbound_email::{EMAIL_PARTS, EMAIL_PROVIDERS};
+
+#[derive(Debug, Deserialize)]
+pub struct MailboxRequest {
+    pub name: String,
+    pub schema: String,
+    /// sendgrid, mailgun or ses
+    pub provider: String,
+    /// Message part -> record field; defaults to from, to, subject and text -> body
+    pub field_mapping: Option<Map<String, Value>>,
+    /// Mailgun webhook signing key; Mailgun posts must be signed with it when set
+    pub signing_key: Option<String>,
+}
+
+/// POST /api/inbound/mailboxes - Create a mailbox that turns received email into records
+///
+/// Body: { "name": "support", "schema": "tickets", "provider": "sendgrid",
+/// "field_mapping": { "from": "requester", "subject": "title", "text": "description" } }.
+/// Answers 201 with the mailbox and the `webhook_url` to configure at the provider.
+/// Restricted to root users.
+pub async fn create(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(request): Json<MailboxRequest>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    if request.name.trim().is_empty() || request.schema.trim().is_empty() {
+        return Err(ApiError::bad_request("Mailboxes need a name and a schema"));
+    }
+    if !EMAIL_PROVIDERS.contains(&request.provider.as_str()) {
+        return Err(ApiError::bad_request(format!("provider must be one of {}", EMAIL_PROVIDERS.join(", "))));
+    }
+    let field_mapping = request.field_mapping.unwrap_or_else(mailboxes::default_field_mapping);
+    for (part, field) in &field_mapping {
+        if !EMAIL_PARTS.contains(&part.as_str()) {
+            return Err(ApiError::bad_request(format!("Unknown message part '{}'; expected one of {}", part, EMAIL_PARTS.join(", "))));
+        }
+        if !field.as_str().is_some_and(|field| !field.is_empty()) {
+            return Err(ApiError::bad_request(format!("field_mapping.{} must name a field", part)));
+        }
+    }
+    if mailboxes::list_mailboxes(&pool).await?.iter().any(|mailbox| mailbox.name == request.name) {
+        return Err(ApiError::conflict(format!("Mailbox '{}' already exists", request.name)));
+    }
+
+    let mailbox = mailboxes::create_mailbox(&pool, NewMailbox {
+        name: request.name,
+        schema_name: request.schema,
+        provider: request.provider,
+        field_mapping,
+        signing_key: request.signing_key.filter(|key| !key.is_empty()),
+        created_by: auth_user.user_id,
+    }).await?;
+    tracing::info!("Mailbox '{}' for '{}' created by '{}' in tenant '{}'", mailbox.name, mailbox.schema_name, auth_user.user, auth_user.tenant);
+
+    Ok(ApiResponse::created(with_webhook_url(&auth_user.database, mailbox)))
+}
+
+/// GET /api/inbound/mailboxes - Every mailbox with its webhook URL and last delivery
+pub async fn list(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    let mailboxes = mailboxes::list_mailboxes(&pool).await?;
+    Ok(ApiResponse::success(Value::Array(
+        mailboxes.into_iter().map(|mailbox| with_webhook_url(&auth_user.database, mailbox)).collect(),
+    )))
+}
+
+/// DELETE /api/inbound/mailboxes/:id - Delete a mailbox, revoking its webhook URL
+pub async fn delete(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    mailboxes::delete_mailbox(&pool, id).await?;
+    tracing::info!("Mailbox {} deleted by '{}'", id, auth_user.user);
+    Ok(ApiResponse::success(json!({ "id": id, "deleted": true })))
+}
+
+fn with_webhook_url(database: &str, mailbox: Mailbox) -> Value {
+    let webhook_url = mailboxes::webhook_path(database, mailbox.id);
+    let mut value = json!(mailbox);
+    value["webhook_url"] = Value::String(webhook_url);
+    value
+}
+
+fn require_root(auth_user: &AuthUser) -> Result<(), ApiError> {
+    if auth_user.access != "root" {
+        return Err(ApiError::forbidden("Managing mailboxes requires root access"));
+    }
+    Ok(())
+}
@@ -0,0 +1,6 @@
+pub mod mailboxes;
+
+// Re-export handler functions for use in routing
+pub use mailboxes::create as mailbox_create;
+pub use mailboxes::list as mailbox_list;
+pub use mailboxes::delete as mailbox_delete;
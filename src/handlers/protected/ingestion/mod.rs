@@ -0,0 +1,8 @@
+pub mod sources;
+
+// Re-export handler functions for use in routing
+pub use sources::create as ingestion_create;
+pub use sources::list as ingestion_list;
+pub use sources::delete as ingestion_delete;
+pub use sources::runs as ingestion_runs;
+pub use sources::poll as ingestion_poll;
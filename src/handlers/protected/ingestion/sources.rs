@@ -0,0 +1,134 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::ingestion::{self, NewIngestionSource};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// Runs returned by GET /api/ingestion/:id/runs
+const RUNS_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct IngestionSourceRequest {
+    pub name: String,
+    /// sftp://, ftp:// or ftps:// URL of the directory files are dropped into
+    pub url: String,
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    pub schema: String,
+    /// Name of an import template (see `monk data template`)
+    pub template: Option<String>,
+    #[serde(default = "default_archive_path")]
+    pub archive_path: String,
+    pub webhook_url: Option<String>,
+    /// HMAC key for the X-Monk-Signature header of webhook deliveries
+    #[serde(default)]
+    pub webhook_secret: String,
+}
+
+fn default_archive_path() -> String {
+    "processed".to_string()
+}
+
+/// POST /api/ingestion - Register an SFTP/FTP directory to poll for CSV files
+///
+/// Body: { "name": "acme-orders", "url": "sftp://files.acme.com/outbound", "username": "monk",
+/// "password": "...", "schema": "orders", "template": "acme-orders", "archive_path": "processed",
+/// "webhook_url": "https://...", "webhook_secret": "..." }. Restricted to root users; the
+/// password and webhook secret are never returned.
+pub async fn create(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(request): Json<IngestionSourceRequest>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    if request.name.trim().is_empty() || request.schema.trim().is_empty() {
+        return Err(ApiError::bad_request("Ingestion sources need a name and a schema"));
+    }
+    crate::database::inbox::Inbox::new(&request.url, &request.username, &request.password, &request.archive_path)
+        .map_err(ApiError::bad_request)?;
+    if let Some(url) = &request.webhook_url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(ApiError::bad_request("webhook_url must be an http(s) URL"));
+        }
+    }
+    if ingestion::list_sources(&pool).await?.iter().any(|source| source.name == request.name) {
+        return Err(ApiError::conflict(format!("Ingestion source '{}' already exists", request.name)));
+    }
+
+    let source = ingestion::create_source(&pool, NewIngestionSource {
+        name: request.name,
+        url: request.url,
+        username: request.username,
+        password: request.password,
+        schema_name: request.schema,
+        import_template: request.template.filter(|name| !name.is_empty()),
+        archive_path: request.archive_path,
+        webhook_url: request.webhook_url.filter(|url| !url.is_empty()),
+        webhook_secret: request.webhook_secret,
+        created_by: auth_user.user_id,
+    }).await?;
+    tracing::info!("Ingestion source '{}' for '{}' created by '{}' in tenant '{}'", source.name, source.schema_name, auth_user.user, auth_user.tenant);
+
+    Ok(ApiResponse::created(json!(source)))
+}
+
+/// GET /api/ingestion - Every ingestion source with its last poll and error
+pub async fn list(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    Ok(ApiResponse::success(json!(ingestion::list_sources(&pool).await?)))
+}
+
+/// DELETE /api/ingestion/:id - Stop polling a source and drop its run history
+pub async fn delete(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    ingestion::delete_source(&pool, id).await?;
+    tracing::info!("Ingestion source {} deleted by '{}'", id, auth_user.user);
+    Ok(ApiResponse::success(json!({ "id": id, "deleted": true })))
+}
+
+/// GET /api/ingestion/:id/runs - Imported files, most recent first
+pub async fn runs(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    ingestion::get_source(&pool, id).await?;
+    Ok(ApiResponse::success(json!(ingestion::list_runs(&pool, id, RUNS_LIMIT).await?)))
+}
+
+/// POST /api/ingestion/:id/poll - Poll a source now instead of waiting for the watcher
+///
+/// Answers 202 at once; the files imported show up in GET /api/ingestion/:id/runs.
+pub async fn poll(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    let source = ingestion::get_source(&pool, id).await?;
+    if !ingestion::spawn_poll(auth_user.database.clone(), pool, source.clone()) {
+        return Err(ApiError::conflict(format!("Ingestion source '{}' is already being polled", source.name)));
+    }
+    tracing::info!("Ingestion source '{}' polled by '{}'", source.name, auth_user.user);
+    Ok(ApiResponse::accepted(json!(source)))
+}
+
+fn require_root(auth_user: &AuthUser) -> Result<(), ApiError> {
+    if auth_user.access != "root" {
+        return Err(ApiError::forbidden("Managing ingestion sources requires root access"));
+    }
+    Ok(())
+}
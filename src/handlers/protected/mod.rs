@@ -8,10 +8,22 @@
 // Middleware: JWT validation + user context + system dependencies
 
 // Protected module declarations
+pub mod audit;   // Audit trail of record writes
 pub mod auth;  // User account management endpoints
+pub mod bulk;   // Multi-schema bulk operations
+pub mod comments;   // Threaded comments on records
 pub mod data;   // Dynamic data CRUD operations  
 pub mod describe;   // JSON Schema management endpoints
+pub mod export;   // Background export jobs
+pub mod file;   // Record attachments
 pub mod find;   // Advanced filtered finds
+pub mod features;   // Per-tenant feature flags
+pub mod inbound;   // Inbound email mailboxes
+pub mod ingestion;   // SFTP/FTP CSV ingestion sources
+pub mod notifications;   // Digest preferences and subscriptions
+pub mod odata;   // Read-only OData adapter for BI tools
+pub mod tags;   // Tags on records, rename and merge
+pub mod views;   // Saved list views and schema defaults
 
 // Re-export all handler functions for easy importing
 pub use auth::*;
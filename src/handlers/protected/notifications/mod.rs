@@ -0,0 +1,10 @@
+pub mod preferences;
+pub mod subscriptions;
+
+// Re-export handler functions for use in routing
+pub use preferences::get as preferences_get;
+pub use preferences::put as preferences_put;
+pub use preferences::digest as digest_preview;
+pub use subscriptions::create as subscription_create;
+pub use subscriptions::list as subscription_list;
+pub use subscriptions::delete as subscription_delete;
@@ -0,0 +1,110 @@
+use axum::extract::{Extension, Query};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::database::notifications::{self, FREQUENCIES};
+use crate::error::ApiError;
+use crate::handlers::public::auth::utils::validate_email_format;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct PreferencesRequest {
+    /// Address digests are sent to; defaults to the login name when it is an email address
+    pub email: Option<String>,
+    /// daily, weekly or off
+    pub frequency: Option<String>,
+    /// ISO day of the week for weekly digests, 1 = Monday
+    pub weekly_day: Option<i16>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DigestQuery {
+    /// Also list the records of each change. Default: false
+    pub records: Option<bool>,
+}
+
+/// GET /api/notifications/preferences - The current user's digest settings
+///
+/// Answers `null` data until preferences are saved; no digests are sent before then.
+pub async fn get(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let preferences = notifications::get_preferences(&pool, auth_user.user_id).await?;
+    Ok(ApiResponse::success(json!(preferences)))
+}
+
+/// PUT /api/notifications/preferences - Set where and how often digests are sent
+///
+/// Body: { "email": "ada@example.com", "frequency": "weekly", "weekly_day": 1 }. Fields left
+/// out keep their current values; new preferences default to daily digests at the login
+/// name's address. Digests are sent from `api.digest_hour_utc` on the day they are due.
+pub async fn put(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<PreferencesRequest>,
+) -> ApiResult<Value> {
+    let current = notifications::get_preferences(&pool, auth_user.user_id).await?;
+    let email = payload
+        .email
+        .map(|email| email.trim().to_string())
+        .or_else(|| current.as_ref().map(|c| c.email.clone()))
+        .unwrap_or_else(|| auth_user.user.clone());
+    validate_email_format(&email)
+        .map_err(|e| ApiError::bad_request(format!("{}; set \"email\" to receive digests", e)))?;
+
+    let frequency = payload
+        .frequency
+        .or_else(|| current.as_ref().map(|c| c.frequency.clone()))
+        .unwrap_or_else(|| "daily".to_string());
+    if !FREQUENCIES.contains(&frequency.as_str()) {
+        return Err(ApiError::bad_request(format!(
+            "Unknown frequency '{}'; expected one of {}",
+            frequency,
+            FREQUENCIES.join(", ")
+        )));
+    }
+    let weekly_day = payload.weekly_day.or(current.as_ref().map(|c| c.weekly_day)).unwrap_or(1);
+    if !(1..=7).contains(&weekly_day) {
+        return Err(ApiError::bad_request("weekly_day must be 1 (Monday) to 7 (Sunday)"));
+    }
+
+    let preferences = notifications::save_preferences(&pool, auth_user.user_id, &email, &frequency, weekly_day).await?;
+    tracing::info!("User '{}' in tenant '{}' set {} notification digests", auth_user.user, auth_user.tenant, frequency);
+    Ok(ApiResponse::success(json!(preferences)))
+}
+
+/// GET /api/notifications/digest - Preview the current user's next digest
+///
+/// Collects the changes the next digest would cover so far, without sending it or
+/// starting a new period. `?records=true` includes each changed record.
+pub async fn digest(
+    Query(query): Query<DigestQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let preferences = notifications::get_preferences(&pool, auth_user.user_id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("No notification preferences; PUT /api/notifications/preferences first"))?;
+    let until = crate::clock::now().naive_utc();
+    let digest = notifications::build_digest(&pool, &preferences, auth_user.record_access(), until).await?;
+
+    let mut data = json!(digest);
+    if !query.records.unwrap_or(false) {
+        strip_records(&mut data);
+    }
+    Ok(ApiResponse::success(data))
+}
+
+fn strip_records(digest: &mut Value) {
+    let Some(sections) = digest.get_mut("sections").and_then(Value::as_array_mut) else { return };
+    for section in sections {
+        let Some(changes) = section.get_mut("changes").and_then(Value::as_array_mut) else { continue };
+        for change in changes {
+            if let Some(change) = change.as_object_mut() {
+                change.remove("record");
+            }
+        }
+    }
+}
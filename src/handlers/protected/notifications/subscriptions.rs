@@ -0,0 +1,49 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::notifications::{self, NewSubscription};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// POST /api/notifications/subscriptions - Follow changes to a schema
+///
+/// Body: { "schema": "orders", "name": "Large orders", "where": { "total": { "$gte": 1000 } } }.
+/// Records of `schema` created or updated by other users, and matching `where` when given,
+/// are listed in the user's digests. Answers 201 with the subscription.
+pub async fn create(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<NewSubscription>,
+) -> ApiResult<Value> {
+    if payload.schema.trim().is_empty() {
+        return Err(ApiError::bad_request("Subscription schema is required"));
+    }
+    if payload.where_clause.as_ref().is_some_and(|w| !w.is_object() && !w.is_null()) {
+        return Err(ApiError::bad_request("Subscription 'where' must be an object"));
+    }
+
+    let subscription = notifications::create_subscription(&pool, auth_user.user_id, payload).await?;
+    tracing::info!("User '{}' in tenant '{}' follows '{}'", auth_user.user, auth_user.tenant, subscription.schema_name);
+    Ok(ApiResponse::created(json!(subscription)))
+}
+
+/// GET /api/notifications/subscriptions - Schemas the current user follows
+pub async fn list(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let subscriptions = notifications::list_subscriptions(&pool, auth_user.user_id).await?;
+    Ok(ApiResponse::success(json!(subscriptions)))
+}
+
+/// DELETE /api/notifications/subscriptions/:id - Stop following a schema
+pub async fn delete(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    notifications::delete_subscription(&pool, auth_user.user_id, id).await?;
+    Ok(ApiResponse::success(json!({ "id": id, "deleted": true })))
+}
@@ -0,0 +1,35 @@
+use axum::extract::{Extension, Path, Query};
+use axum::response::Json;
+use serde_json::Value;
+
+use crate::api::odata::{self, ODataQuery};
+use crate::database::record::RecordVecExt;
+use crate::database::repository::Repository;
+use crate::error::ApiError;
+use crate::filter::CountMode;
+use crate::middleware::{TenantPool, AuthUser};
+
+/// GET /api/odata/:schema - Read-only OData entity set
+///
+/// Translates $filter, $select, $orderby, $top, $skip and $count into a regular
+/// find. Responds with an OData payload rather than the API envelope:
+/// `{"@odata.context": "$metadata#schema", "@odata.count": n, "value": [...]}`.
+pub async fn get(
+    Path(schema): Path<String>,
+    Query(query): Query<ODataQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<Json<Value>, ApiError> {
+    let mut filter_data = query.to_filter_data().map_err(|e| ApiError::bad_request(e.to_string()))?;
+    filter_data.default_limit(crate::config::CONFIG.filter.max_limit);
+
+    let repository = Repository::new(&schema, pool).with_access(auth_user.record_access());
+    let count = if query.count.unwrap_or(false) {
+        repository.total(&filter_data, CountMode::Exact).await?
+    } else {
+        None
+    };
+    let records = repository.select_any(filter_data).await?;
+
+    Ok(Json(odata::entity_set(&schema, records.to_api(), count)))
+}
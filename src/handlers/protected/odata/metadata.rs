@@ -0,0 +1,44 @@
+use axum::extract::Extension;
+use axum::http::header;
+use axum::response::{IntoResponse, Json, Response};
+use serde_json::Value;
+
+use crate::api::odata;
+use crate::database::repository::Repository;
+use crate::error::ApiError;
+use crate::middleware::TenantPool;
+
+/// GET /api/odata - OData service document listing one entity set per schema
+pub async fn service(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+) -> Result<Json<Value>, ApiError> {
+    let names = schema_definitions(pool).await?.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+    Ok(Json(odata::service_document(&names)))
+}
+
+/// GET /api/odata/$metadata - CSDL document describing every schema as an entity type
+pub async fn get(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+) -> Result<Response, ApiError> {
+    let schemas = schema_definitions(pool).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/xml")],
+        odata::metadata_document(&schemas),
+    ).into_response())
+}
+
+/// (name, definition) for every active schema, ordered by name
+async fn schema_definitions(pool: sqlx::PgPool) -> Result<Vec<(String, Value)>, ApiError> {
+    let records = Repository::new("schemas", pool).select_all(None, None).await?;
+
+    let mut schemas: Vec<(String, Value)> = records
+        .iter()
+        .filter_map(|record| {
+            let name = record.get("name")?.as_str()?.to_string();
+            let definition = record.get("definition").cloned().unwrap_or(Value::Null);
+            Some((name, definition))
+        })
+        .collect();
+    schemas.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(schemas)
+}
@@ -0,0 +1,7 @@
+pub mod entity;
+pub mod metadata;
+
+// Re-export handler functions for use in routing
+pub use entity::get as odata_entity_get;
+pub use metadata::service as odata_service;
+pub use metadata::get as odata_metadata;
@@ -0,0 +1,88 @@
+use axum::extract::Extension;
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::database::tags::{self, MAX_TAG_LENGTH};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct RenameRequest {
+    pub tag: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeRequest {
+    /// Tags merged away
+    pub tags: Vec<String>,
+    /// Tag that receives their records
+    pub into: String,
+}
+
+/// GET /api/tags - Every tag with the number of records carrying it
+///
+/// Tags are also records of the `tags` schema; create them through /api/data/tags, where
+/// only root may also update or trash them.
+pub async fn list(Extension(TenantPool(pool)): Extension<TenantPool>) -> ApiResult<Value> {
+    let tags = tags::list(&pool).await?;
+    Ok(ApiResponse::success(json!(tags)))
+}
+
+/// POST /api/tags/rename - Rename a tag, keeping its records
+///
+/// Body: { "tag": "urgnet", "name": "urgent" }. Answers 409 when the new name is taken;
+/// merge the tags instead. Requires root access.
+pub async fn rename(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<RenameRequest>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    let name = tag_name(&payload.name)?;
+    if name != payload.tag && tags::get(&pool, &name).await?.is_some() {
+        return Err(ApiError::conflict(format!("Tag '{}' already exists; merge the tags instead", name)));
+    }
+    let tag = tags::rename(&pool, &payload.tag, &name).await?;
+    tracing::info!("Tag '{}' renamed to '{}' by '{}'", payload.tag, name, auth_user.user);
+    Ok(ApiResponse::success(json!(tag)))
+}
+
+/// POST /api/tags/merge - Merge tags into another
+///
+/// Body: { "tags": ["bug", "defect"], "into": "issue" }. Records carrying any of the tags
+/// carry `into` instead, and the merged tags are deleted, all in one transaction. Requires
+/// root access.
+pub async fn merge(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<MergeRequest>,
+) -> ApiResult<Value> {
+    require_root(&auth_user)?;
+    if payload.tags.is_empty() {
+        return Err(ApiError::bad_request("Name at least one tag to merge"));
+    }
+    let tag = tags::merge(&pool, &payload.tags, &payload.into).await?;
+    tracing::info!("Tags {:?} merged into '{}' by '{}'", payload.tags, payload.into, auth_user.user);
+    Ok(ApiResponse::success(json!(tag)))
+}
+
+/// Tag name without surrounding whitespace, rejected when empty or too long
+pub(super) fn tag_name(name: &str) -> Result<String, ApiError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ApiError::bad_request("Tag names may not be empty"));
+    }
+    if name.chars().count() > MAX_TAG_LENGTH {
+        return Err(ApiError::bad_request(format!("Tag names are limited to {} characters", MAX_TAG_LENGTH)));
+    }
+    Ok(name.to_string())
+}
+
+fn require_root(auth_user: &AuthUser) -> Result<(), ApiError> {
+    if auth_user.access != "root" {
+        return Err(ApiError::forbidden("Renaming and merging tags requires root access"));
+    }
+    Ok(())
+}
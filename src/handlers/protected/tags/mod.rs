@@ -0,0 +1,10 @@
+pub mod manage;
+pub mod record;
+
+// Re-export handler functions for use in routing
+pub use manage::list as tag_list;
+pub use manage::rename as tag_rename;
+pub use manage::merge as tag_merge;
+pub use record::list as record_tag_list;
+pub use record::attach as record_tag_attach;
+pub use record::detach as record_tag_detach;
@@ -0,0 +1,74 @@
+use axum::extract::{Extension, Path};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::{comments, tags};
+use crate::error::ApiError;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+use super::manage::tag_name;
+
+/// Tags attached by one request
+const MAX_TAGS_PER_REQUEST: usize = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct AttachRequest {
+    pub tags: Vec<String>,
+}
+
+/// GET /api/tags/:schema/:record - The record's tags, by name
+///
+/// Requires read access to the record.
+pub async fn list(
+    Path((schema, record_id)): Path<(String, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    comments::check_record(&pool, &schema, record_id, auth_user.record_access().as_ref()).await?;
+    let tags = tags::for_record(&pool, &schema, record_id).await?;
+    Ok(ApiResponse::success(json!(tags)))
+}
+
+/// POST /api/tags/:schema/:record - Tag a record
+///
+/// Body: { "tags": ["urgent", "billing"] }. Names without a tag create one. Requires edit
+/// access to the record; answers with all of the record's tags.
+pub async fn attach(
+    Path((schema, record_id)): Path<(String, Uuid)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<AttachRequest>,
+) -> ApiResult<Value> {
+    if payload.tags.is_empty() || payload.tags.len() > MAX_TAGS_PER_REQUEST {
+        return Err(ApiError::bad_request(format!("Provide 1 to {} tags", MAX_TAGS_PER_REQUEST)));
+    }
+    let names = payload.tags.iter().map(|name| tag_name(name)).collect::<Result<Vec<_>, _>>()?;
+    require_editable(&pool, &schema, record_id, &auth_user).await?;
+
+    let tags = tags::attach(&pool, &schema, record_id, &names).await?;
+    Ok(ApiResponse::success(json!(tags)))
+}
+
+/// DELETE /api/tags/:schema/:record/:tag - Remove a tag from a record
+///
+/// Requires edit access to the record. The tag itself is kept.
+pub async fn detach(
+    Path((schema, record_id, name)): Path<(String, Uuid, String)>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    require_editable(&pool, &schema, record_id, &auth_user).await?;
+    if !tags::detach(&pool, &schema, record_id, &name).await? {
+        return Err(ApiError::not_found(format!("Record is not tagged '{}'", name)));
+    }
+    Ok(ApiResponse::success(json!({ "tag": name, "removed": true })))
+}
+
+async fn require_editable(pool: &sqlx::PgPool, schema: &str, record_id: Uuid, auth_user: &AuthUser) -> Result<(), ApiError> {
+    if !comments::check_record(pool, schema, record_id, auth_user.record_access().as_ref()).await? {
+        return Err(ApiError::forbidden("Tagging a record requires edit access to it"));
+    }
+    Ok(())
+}
@@ -0,0 +1,270 @@
+use axum::extract::{Extension, Path, Query};
+use axum::response::Json;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::database::columns::{json_columns, load_columns};
+use crate::database::saved_views::{self, SavedView, ViewSettings};
+use crate::error::ApiError;
+use crate::filter::Filter;
+use crate::middleware::{TenantPool, AuthUser, ApiResponse, ApiResult};
+
+/// Access levels a view can be the schema default for; "deny" users cannot list records
+const DEFAULT_LEVELS: &[&str] = &["root", "full", "edit", "read"];
+
+/// Longest view name accepted
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Page size cap when filter.max_limit is not configured
+const MAX_PAGE_SIZE: i32 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    /// Only views of this schema
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViewRequest {
+    pub schema: String,
+    pub name: String,
+    /// Where clause, as in find requests
+    pub filter: Option<Value>,
+    /// Order, as in find requests
+    pub sort: Option<Value>,
+    /// Visible columns, in display order
+    pub columns: Option<Vec<String>>,
+    pub page_size: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DefaultRequest {
+    /// Access levels the view becomes the default for; empty clears its defaults
+    pub roles: Vec<String>,
+}
+
+/// GET /api/views - The user's saved views, and the schema defaults for their access level
+///
+/// `?schema=` limits to one schema. Defaults saved by other users are listed too, since
+/// clients apply them; only their owner can change them.
+pub async fn list(
+    Query(query): Query<ListQuery>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let views = saved_views::list(&pool, auth_user.user_id, &auth_user.access, query.schema.as_deref()).await?;
+    Ok(ApiResponse::success(json!(views)))
+}
+
+/// POST /api/views - Save a view of a schema
+///
+/// Body: { "schema": "accounts", "name": "Open accounts", "filter": { "status": "open" },
+/// "sort": "created_at desc", "columns": ["name", "status"], "page_size": 50 }. The filter,
+/// sort and columns are checked against the schema's columns. Answers 409 when the user
+/// already has a view of that name for the schema.
+pub async fn create(
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<ViewRequest>,
+) -> ApiResult<Value> {
+    let settings = view_settings(&pool, &payload).await?;
+    if saved_views::name_taken(&pool, auth_user.user_id, &payload.schema, &settings.name, None).await? {
+        return Err(ApiError::conflict(format!("You already have a view named '{}' for '{}'", settings.name, payload.schema)));
+    }
+    let view = saved_views::create(&pool, auth_user.user_id, &payload.schema, settings).await?;
+    Ok(ApiResponse::created(json!(view)))
+}
+
+/// GET /api/views/:id - One view, if the user owns it or it is a default for their level
+pub async fn get(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let view = saved_views::get(&pool, id).await?
+        .filter(|view| view.user_id == auth_user.user_id || view.default_for.contains(&auth_user.access))
+        .ok_or_else(|| ApiError::not_found("View not found"))?;
+    Ok(ApiResponse::success(json!(view)))
+}
+
+/// PUT /api/views/:id - Replace the settings of one of the user's views
+///
+/// Body as for POST; the schema of a view cannot change. Defaults set by root are kept.
+pub async fn update(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<ViewRequest>,
+) -> ApiResult<Value> {
+    let view = owned_view(&pool, id, &auth_user).await?;
+    if payload.schema != view.schema_name {
+        return Err(ApiError::bad_request(format!("View '{}' lists '{}'; save a new view for '{}'", view.name, view.schema_name, payload.schema)));
+    }
+    let settings = view_settings(&pool, &payload).await?;
+    if saved_views::name_taken(&pool, view.user_id, &view.schema_name, &settings.name, Some(id)).await? {
+        return Err(ApiError::conflict(format!("A view named '{}' already exists for '{}'", settings.name, view.schema_name)));
+    }
+    let view = saved_views::update(&pool, id, settings).await?;
+    Ok(ApiResponse::success(json!(view)))
+}
+
+/// DELETE /api/views/:id - Delete one of the user's views; root may delete any view
+pub async fn delete(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    owned_view(&pool, id, &auth_user).await?;
+    saved_views::delete(&pool, id).await?;
+    Ok(ApiResponse::success(json!({ "id": id, "deleted": true })))
+}
+
+/// GET /api/views/default/:schema - The view the user's access level lists the schema with
+///
+/// Answers `null` when root has not marked a default for the level.
+pub async fn default_view(
+    Path(schema): Path<String>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> ApiResult<Value> {
+    let view = saved_views::default_for(&pool, &schema, &auth_user.access).await?;
+    Ok(ApiResponse::success(json!(view)))
+}
+
+/// PUT /api/views/:id/default - Make a view the schema default for access levels
+///
+/// Body: { "roles": ["read", "edit"] }. The view becomes the default for exactly these
+/// levels, taking them from any other view of the schema; an empty list clears its
+/// defaults. Requires root access.
+pub async fn set_default(
+    Path(id): Path<Uuid>,
+    Extension(TenantPool(pool)): Extension<TenantPool>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<DefaultRequest>,
+) -> ApiResult<Value> {
+    if auth_user.access != "root" {
+        return Err(ApiError::forbidden("Setting default views requires root access"));
+    }
+    let levels = default_levels(payload.roles)?;
+    if saved_views::get(&pool, id).await?.is_none() {
+        return Err(ApiError::not_found("View not found"));
+    }
+    let view = saved_views::set_defaults(&pool, id, &levels).await?;
+    tracing::info!("View '{}' of '{}' made default for [{}] by '{}'", view.name, view.schema_name, levels.join(", "), auth_user.user);
+    Ok(ApiResponse::success(json!(view)))
+}
+
+/// A view the user may change: their own, or any view for root
+async fn owned_view(pool: &PgPool, id: Uuid, auth_user: &AuthUser) -> Result<SavedView, ApiError> {
+    let view = saved_views::get(pool, id).await?.ok_or_else(|| ApiError::not_found("View not found"))?;
+    if view.user_id != auth_user.user_id && auth_user.access != "root" {
+        // Views of other users are not revealed, unless they are a default for the user
+        if view.default_for.contains(&auth_user.access) {
+            return Err(ApiError::forbidden("Only the owner of a view can change it"));
+        }
+        return Err(ApiError::not_found("View not found"));
+    }
+    Ok(view)
+}
+
+/// Check a view request against the columns of its schema
+async fn view_settings(pool: &PgPool, payload: &ViewRequest) -> Result<ViewSettings, ApiError> {
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(ApiError::bad_request(format!("View name must be 1 to {} characters", MAX_NAME_LENGTH)));
+    }
+
+    let columns = load_columns(pool, &payload.schema).await?;
+    if columns.is_empty() {
+        return Err(ApiError::not_found(format!("Schema '{}' not found", payload.schema)));
+    }
+    let mut filter = Filter::new(&payload.schema)?;
+    filter.json_columns(json_columns(&columns));
+    filter.columns(columns.into_iter().map(|(name, _)| name).collect());
+    if let Some(ref where_clause) = payload.filter {
+        filter.where_clause(where_clause.clone())?;
+    }
+    if let Some(ref sort) = payload.sort {
+        filter.order(sort.clone())?;
+    }
+    if let Some(ref visible) = payload.columns {
+        filter.select(visible.clone())?;
+    }
+
+    let max_page_size = crate::config::CONFIG.filter.max_limit.unwrap_or(MAX_PAGE_SIZE);
+    if let Some(size) = payload.page_size {
+        if size < 1 || size > max_page_size {
+            return Err(ApiError::bad_request(format!("page_size must be between 1 and {}", max_page_size)));
+        }
+    }
+
+    Ok(ViewSettings {
+        name,
+        filter: payload.filter.clone(),
+        sort: payload.sort.clone(),
+        columns: payload.columns.clone(),
+        page_size: payload.page_size,
+    })
+}
+
+/// Access levels of a default request, deduplicated in request order
+fn default_levels(roles: Vec<String>) -> Result<Vec<String>, ApiError> {
+    let mut levels: Vec<String> = Vec::new();
+    for role in roles {
+        if !DEFAULT_LEVELS.contains(&role.as_str()) {
+            return Err(ApiError::bad_request(format!("Unknown access level '{}'; expected one of {}", role, DEFAULT_LEVELS.join(", "))));
+        }
+        if !levels.contains(&role) {
+            levels.push(role);
+        }
+    }
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::saved_views::tests::{settings, VIEWS_DDL};
+
+    fn auth_user(access: &str) -> AuthUser {
+        AuthUser {
+            tenant: "tenant".to_string(),
+            user: access.to_string(),
+            database: "tenant_test".to_string(),
+            access: access.to_string(),
+            user_id: Uuid::new_v4(),
+            elevation: None,
+            mfa: false,
+            address: None,
+        }
+    }
+
+    fn roles(roles: &[&str]) -> Json<DefaultRequest> {
+        Json(DefaultRequest { roles: roles.iter().map(|r| r.to_string()).collect() })
+    }
+
+    #[tokio::test]
+    async fn test_only_root_sets_default_views() {
+        // Refused before the database is touched
+        let pool = PgPool::connect_lazy("postgres://localhost/tenant_test").unwrap();
+        for access in ["full", "edit", "read"] {
+            let result = set_default(Path(Uuid::new_v4()), Extension(TenantPool(pool.clone())), Extension(auth_user(access)), roles(&["read"])).await;
+            assert!(matches!(result, Err(ApiError::Forbidden(_))), "{} may not set defaults", access);
+        }
+
+        let Some(pool) = crate::testing::scratch_pool(VIEWS_DDL).await else { return };
+        let owner = auth_user("edit");
+        let view = saved_views::create(&pool, owner.user_id, "accounts", settings("Open")).await.unwrap();
+        let result = set_default(Path(view.id), Extension(TenantPool(pool.clone())), Extension(auth_user("root")), roles(&["read", "read", "edit"])).await;
+        assert!(result.is_ok());
+        let view = saved_views::get(&pool, view.id).await.unwrap().unwrap();
+        assert_eq!(view.default_for, vec!["read".to_string(), "edit".to_string()]);
+
+        let result = set_default(Path(view.id), Extension(TenantPool(pool.clone())), Extension(auth_user("root")), roles(&["deny"])).await;
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+        let result = set_default(Path(Uuid::new_v4()), Extension(TenantPool(pool)), Extension(auth_user("root")), roles(&["read"])).await;
+        assert!(matches!(result, Err(ApiError::NotFound(_))));
+    }
+}
@@ -0,0 +1,10 @@
+pub mod manage;
+
+// Re-export handler functions for use in routing
+pub use manage::list as view_list;
+pub use manage::create as view_create;
+pub use manage::get as view_get;
+pub use manage::update as view_update;
+pub use manage::delete as view_delete;
+pub use manage::default_view as view_default;
+pub use manage::set_default as view_set_default;
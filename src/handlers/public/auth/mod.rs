@@ -1,8 +1,11 @@
+pub mod oidc;
 pub mod session;
 pub mod user;
 pub mod utils;
 
 // Re-export handler functions for use in routing
+pub use oidc::start as oidc_start;
+pub use oidc::callback as oidc_callback;
 pub use session::login as session_login;
 pub use session::refresh as session_refresh;
 pub use user::register as user_register;
@@ -0,0 +1,233 @@
+use axum::{
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::auth::oidc::{self, STATE_COOKIE, STATE_TTL_SECS};
+use crate::auth::{generate_jwt, Claims};
+use crate::database::mfa;
+use crate::database::service::{find_tenant_by_name, find_user_by_auth};
+use crate::database::{DatabaseError, DatabaseManager};
+
+use super::session::issue_refresh_token;
+
+#[derive(Debug, Deserialize)]
+pub struct StartQuery {
+    pub tenant: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+fn failure(status: StatusCode, error: &str, error_code: &str) -> Response {
+    (status, Json(json!({ "success": false, "error": error, "error_code": error_code }))).into_response()
+}
+
+fn state_cookie(provider: &str, nonce: &str, max_age: i64) -> String {
+    let secure = if crate::config::config().security.require_https { "; Secure" } else { "" };
+    format!(
+        "{}={}; Path=/auth/oidc/{}; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        STATE_COOKIE, nonce, provider, max_age, secure
+    )
+}
+
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// GET /auth/oidc/:provider/start?tenant=:tenant - Begin sign-in with an external provider
+///
+/// Redirects the browser to the provider's sign-in page. After signing in, the provider
+/// sends the browser to /auth/oidc/:provider/callback, which completes the login.
+pub async fn start(Path(provider_name): Path<String>, Query(query): Query<StartQuery>) -> Response {
+    let Some(provider) = crate::config::config().security.oidc_provider(&provider_name) else {
+        return failure(StatusCode::NOT_FOUND, "Identity provider not found", "PROVIDER_NOT_FOUND");
+    };
+    match find_tenant_by_name(&query.tenant).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "Tenant not found", "TENANT_NOT_FOUND"),
+        Err(e) => {
+            tracing::error!("Database error checking tenant {}: {}", query.tenant, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error", "DATABASE_ERROR");
+        }
+    }
+
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let state = oidc::sign_state(&provider.name, &query.tenant, &nonce);
+    let url = match oidc::authorization_url(provider, &state) {
+        Ok(url) => url,
+        Err(e) => {
+            tracing::error!("Invalid authorization URL for provider {}: {}", provider.name, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Identity provider misconfigured", "PROVIDER_ERROR");
+        }
+    };
+
+    (
+        [(header::SET_COOKIE, state_cookie(&provider.name, &nonce, STATE_TTL_SECS))],
+        Redirect::to(&url),
+    )
+        .into_response()
+}
+
+/// GET /auth/oidc/:provider/callback - Complete sign-in with an external provider
+///
+/// Exchanges the authorization code, reads the provider's `user_claim` for the signed-in
+/// identity and logs in the tenant user whose `auth` matches it. Responds like
+/// POST /auth/login/:tenant/:user. Users must already exist and be activated in the tenant.
+/// The provider cannot supply a TOTP code, so users who enabled MFA get 401 `MFA_REQUIRED`
+/// and must log in with their password and code instead.
+pub async fn callback(
+    Path(provider_name): Path<String>,
+    Query(query): Query<CallbackQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(provider) = crate::config::config().security.oidc_provider(&provider_name) else {
+        return failure(StatusCode::NOT_FOUND, "Identity provider not found", "PROVIDER_NOT_FOUND");
+    };
+    if let Some(error) = query.error {
+        tracing::info!("Provider {} declined sign-in: {}", provider.name, error);
+        return failure(StatusCode::UNAUTHORIZED, "Sign-in was declined by the identity provider", "OIDC_DENIED");
+    }
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        return failure(StatusCode::BAD_REQUEST, "Callback requires code and state", "INVALID_CALLBACK");
+    };
+    let state = match oidc::verify_state(&provider.name, &state) {
+        Some(state) if cookie_value(&headers, STATE_COOKIE) == Some(state.nonce.as_str()) => state,
+        _ => return failure(StatusCode::UNAUTHORIZED, "Invalid or expired sign-in state", "INVALID_STATE"),
+    };
+
+    let userinfo = match oidc::fetch_userinfo(provider, &code).await {
+        Ok(userinfo) => userinfo,
+        Err(e) => {
+            tracing::warn!("Provider {} sign-in failed: {}", provider.name, e);
+            return failure(StatusCode::BAD_GATEWAY, "Identity provider request failed", "PROVIDER_ERROR");
+        }
+    };
+    let Some(identity) = oidc::identity(provider, &userinfo) else {
+        return failure(StatusCode::UNAUTHORIZED, "Identity provider returned no verified identity", "IDENTITY_MISSING");
+    };
+
+    let tenant = match find_tenant_by_name(&state.tenant).await {
+        Ok(Some(tenant)) => tenant,
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "Tenant not found", "TENANT_NOT_FOUND"),
+        Err(e) => {
+            tracing::error!("Database error checking tenant {}: {}", state.tenant, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error", "DATABASE_ERROR");
+        }
+    };
+    let user = match find_user_by_auth(&tenant.database, &identity).await {
+        Ok(Some(user)) if !user.is_deleted() => user,
+        Ok(_) => return failure(StatusCode::NOT_FOUND, "User not found", "USER_NOT_FOUND"),
+        Err(e) => {
+            tracing::error!("Database error checking user {} in {}: {}", identity, tenant.database, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error", "DATABASE_ERROR");
+        }
+    };
+    if !user.is_activated() {
+        return failure(StatusCode::FORBIDDEN, "Account has not been activated", "ACCOUNT_NOT_ACTIVATED");
+    }
+    let without_mfa = match DatabaseManager::tenant_pool(&tenant.database).await {
+        Ok(pool) => mfa_not_enabled(&pool, user.id).await,
+        Err(e) => Err(e),
+    };
+    match without_mfa {
+        Ok(true) => {}
+        Ok(false) => return failure(
+            StatusCode::UNAUTHORIZED,
+            "MFA is enabled for this user; log in with a password and TOTP code",
+            "MFA_REQUIRED",
+        ),
+        Err(e) => {
+            tracing::error!("MFA check error for {} in {}: {}", user.auth, tenant.database, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error", "DATABASE_ERROR");
+        }
+    }
+
+    let claims = Claims::new(tenant.name.clone(), user.auth.clone(), tenant.database.clone(), user.access.clone(), user.id);
+    let token = match generate_jwt(claims) {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("JWT generation error: {}", e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Token generation failed", "JWT_ERROR");
+        }
+    };
+    let refresh = match issue_refresh_token(&tenant.database, user.id, false).await {
+        Ok(refresh) => refresh,
+        Err(e) => {
+            tracing::error!("Refresh token error for {} in {}: {}", user.auth, tenant.database, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Token generation failed", "REFRESH_TOKEN_ERROR");
+        }
+    };
+
+    tracing::info!("User {} in {} signed in with {}", user.auth, tenant.name, provider.name);
+    (
+        StatusCode::OK,
+        [(header::SET_COOKIE, state_cookie(&provider.name, "", 0))],
+        Json(json!({
+            "success": true,
+            "data": {
+                "token": token,
+                "user": {
+                    "id": user.id,
+                    "database": tenant.database,
+                    "tenant": tenant.name,
+                    "auth": user.auth,
+                    "name": user.name,
+                    "access": user.access
+                },
+                "provider": provider.name,
+                "expires_in": crate::config::config().security.jwt_expiry_hours * 3600,
+                "refresh_token": refresh.token,
+                "refresh_expires_at": refresh.expires_at
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// Whether `user_id` may sign in through a provider, which vouches for the first factor only
+async fn mfa_not_enabled(pool: &PgPool, user_id: Uuid) -> Result<bool, DatabaseError> {
+    Ok(!mfa::load(pool, user_id).await?.is_enabled())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_enrolled_users_cannot_sign_in_with_provider() {
+        let Some(pool) = crate::testing::scratch_pool(
+            "CREATE TEMPORARY TABLE user_credentials (user_id uuid PRIMARY KEY, mfa_secret text, mfa_enabled_at timestamp)"
+        ).await else { return };
+        let plain = Uuid::new_v4();
+        let pending = Uuid::new_v4();
+        let enrolled = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO user_credentials VALUES ($1, 'sealed', NULL), ($2, 'sealed', now())"
+        )
+            .bind(pending).bind(enrolled)
+            .execute(&pool).await.unwrap();
+
+        assert!(mfa_not_enabled(&pool, plain).await.unwrap());
+        // A secret that was never verified does not gate logins yet
+        assert!(mfa_not_enabled(&pool, pending).await.unwrap());
+        assert!(!mfa_not_enabled(&pool, enrolled).await.unwrap());
+    }
+}
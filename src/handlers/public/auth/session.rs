@@ -1,18 +1,35 @@
-use axum::{extract::Path, http::StatusCode, response::IntoResponse, Json};
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Path},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
 use serde::Deserialize;
 use serde_json::{json, Value};
+use uuid::Uuid;
 
-use crate::auth::{generate_jwt, Claims};
+use crate::auth::{generate_jwt, totp, Claims};
+use crate::config::Environment;
+use crate::database::{login_attempts, mfa};
+use crate::database::refresh_tokens::{self, Rotation};
 use crate::database::service::{find_tenant_by_name, find_user_by_auth};
+use crate::database::{DatabaseError, DatabaseManager};
+
+use super::utils::verify_password;
 
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
     pub password: String,
+    /// Current TOTP code; required once the user has enabled MFA
+    #[serde(default)]
+    pub mfa_code: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct RefreshRequest {
-    pub token: String,
+    pub refresh_token: String,
 }
 
 /// POST /auth/login/:tenant/:user - Authenticate user and receive JWT token
@@ -27,10 +44,18 @@ pub struct RefreshRequest {
 /// Expected Input:
 /// ```json
 /// {
-///   "password": "string"    // Required: User password
+///   "password": "string",   // Required: User password
+///   "mfa_code": "123456"    // Required once the user has enabled MFA
 /// }
 /// ```
 ///
+/// Users with MFA enabled get 401 `MFA_REQUIRED` without a code and `INVALID_MFA_CODE`
+/// for a wrong or reused one. Tokens from a login with a code are marked MFA-verified.
+///
+/// Wrong passwords and TOTP codes count towards a lockout of the user from the client's
+/// address: after SECURITY_LOGIN_MAX_FAILURES of them within the failure window, logins
+/// get 429 `TOO_MANY_ATTEMPTS` with a `Retry-After` header until the lockout ends.
+///
 /// Expected Output (Success):
 /// ```json
 /// {
@@ -50,8 +75,54 @@ pub struct RefreshRequest {
 /// ```
 pub async fn login(
     Path((tenant_name, user_auth)): Path<(String, String)>,
-    Json(_payload): Json<LoginRequest>,
-) -> impl IntoResponse {
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Json(payload): Json<LoginRequest>,
+) -> Response {
+    let address = peer.ip().to_string();
+    match login_attempts::locked_for(&tenant_name, &user_auth, &address).await {
+        Ok(Some(retry_after)) => return too_many_attempts(retry_after),
+        Ok(None) => {}
+        // Lockout is a safeguard on top of the password check, so it fails open
+        Err(e) => tracing::error!("Login lockout check failed for {} in {}: {}", user_auth, tenant_name, e),
+    }
+
+    let (status, body) = authenticate(&tenant_name, &user_auth, payload).await;
+    let outcome = match body["error_code"].as_str() {
+        Some("INVALID_CREDENTIALS") | Some("INVALID_MFA_CODE") => {
+            login_attempts::record_failure(&tenant_name, &user_auth, &address).await
+        }
+        None if status == StatusCode::OK => login_attempts::clear(&tenant_name, &user_auth, &address).await.map(|_| None),
+        _ => Ok(None),
+    };
+    match outcome {
+        Ok(Some(lockout)) => {
+            tracing::warn!("User {} in {} locked out from {} for {}s", user_auth, tenant_name, address, lockout);
+            too_many_attempts(lockout)
+        }
+        Ok(None) => (status, body).into_response(),
+        Err(e) => {
+            tracing::error!("Login attempt tracking failed for {} in {}: {}", user_auth, tenant_name, e);
+            (status, body).into_response()
+        }
+    }
+}
+
+/// 429 for a locked out user; sudo answers its TOTP lockout the same way
+pub(crate) fn too_many_attempts(retry_after: i64) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after.to_string())],
+        Json(json!({
+            "success": false,
+            "error": "Too many failed login attempts; try again later",
+            "error_code": "TOO_MANY_ATTEMPTS",
+            "retry_after": retry_after
+        })),
+    )
+        .into_response()
+}
+
+async fn authenticate(tenant_name: &str, user_auth: &str, payload: LoginRequest) -> (StatusCode, Json<Value>) {
     // 1. Check if tenant exists
     let tenant = match find_tenant_by_name(&tenant_name).await {
         Ok(Some(tenant)) => tenant,
@@ -80,8 +151,8 @@ pub async fn login(
 
     // 2. Check if user exists in tenant database
     let user = match find_user_by_auth(&tenant.database, &user_auth).await {
-        Ok(Some(user)) => user,
-        Ok(None) => {
+        Ok(Some(user)) if !user.is_deleted() => user,
+        Ok(_) => {
             return (
                 StatusCode::NOT_FOUND,
                 Json(json!({
@@ -109,14 +180,105 @@ pub async fn login(
         }
     };
 
-    // 3. Generate JWT token
+    // 3. Verify password; argon2 is CPU-bound, so keep it off the async workers
+    let verified = match user.password_hash.clone() {
+        Some(hash) => {
+            let password = payload.password;
+            tokio::task::spawn_blocking(move || verify_password(&password, &hash))
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result)
+        }
+        // Fixture accounts without a password stay usable while developing
+        None if matches!(crate::config::config().environment, Environment::Development) => {
+            tracing::warn!("User {} in {} has no password set; allowed in development", user_auth, tenant.database);
+            Ok(true)
+        }
+        None => Ok(false),
+    };
+    match verified {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({
+                    "success": false,
+                    "error": "Invalid credentials",
+                    "error_code": "INVALID_CREDENTIALS"
+                })),
+            );
+        }
+        Err(e) => {
+            tracing::error!("Password verification error for {} in {}: {}", user_auth, tenant.database, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "success": false,
+                    "error": "Password verification failed",
+                    "error_code": "PASSWORD_ERROR"
+                })),
+            );
+        }
+    }
+
+    // Checked after the password so pending accounts are not revealed to guessers
+    if !user.is_activated() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "success": false,
+                "error": "Account has not been activated",
+                "error_code": "ACCOUNT_NOT_ACTIVATED"
+            })),
+        );
+    }
+
+    // 4. Second factor for users who enabled MFA
+    let mfa_verified = match check_mfa(&tenant.database, user.id, payload.mfa_code.as_deref()).await {
+        Ok(MfaCheck::NotEnabled) => false,
+        Ok(MfaCheck::Verified) => true,
+        Ok(MfaCheck::Missing) => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({
+                    "success": false,
+                    "error": "A TOTP code is required",
+                    "error_code": "MFA_REQUIRED"
+                })),
+            );
+        }
+        Ok(MfaCheck::Invalid) => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({
+                    "success": false,
+                    "error": "Invalid TOTP code",
+                    "error_code": "INVALID_MFA_CODE"
+                })),
+            );
+        }
+        Err(e) => {
+            tracing::error!("MFA check error for {} in {}: {}", user_auth, tenant.database, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "success": false,
+                    "error": "Database error",
+                    "error_code": "DATABASE_ERROR"
+                })),
+            );
+        }
+    };
+
+    // 5. Generate JWT token
     let claims = Claims::new(
         tenant.name.clone(),
         user.auth.clone(),
         tenant.database.clone(),
         user.access.clone(),
         user.id,
-    );
+    )
+    .with_mfa(mfa_verified);
 
     let token = match generate_jwt(claims) {
         Ok(token) => token,
@@ -133,7 +295,23 @@ pub async fn login(
         }
     };
 
-    // 4. Return success response
+    // 6. Start a refresh token family for this session
+    let refresh = match issue_refresh_token(&tenant.database, user.id, mfa_verified).await {
+        Ok(refresh) => refresh,
+        Err(e) => {
+            tracing::error!("Refresh token error for {} in {}: {}", user_auth, tenant.database, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "success": false,
+                    "error": "Token generation failed",
+                    "error_code": "REFRESH_TOKEN_ERROR"
+                })),
+            );
+        }
+    };
+
+    // 7. Return success response
     let expires_in = crate::config::config().security.jwt_expiry_hours * 3600; // Convert to seconds
 
     (
@@ -150,17 +328,43 @@ pub async fn login(
                     "name": user.name,
                     "access": user.access
                 },
-                "expires_in": expires_in
+                "expires_in": expires_in,
+                "refresh_token": refresh.token,
+                "refresh_expires_at": refresh.expires_at
             }
         })),
     )
 }
 
-/// POST /auth/refresh/:tenant/:user - Refresh expired JWT token
+enum MfaCheck {
+    NotEnabled,
+    Missing,
+    Invalid,
+    Verified,
+}
+
+async fn check_mfa(database: &str, user_id: Uuid, code: Option<&str>) -> Result<MfaCheck, DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    if !mfa::load(&pool, user_id).await?.is_enabled() {
+        return Ok(MfaCheck::NotEnabled);
+    }
+    let Some(code) = code else {
+        return Ok(MfaCheck::Missing);
+    };
+    Ok(if totp::check_user_code(&pool, user_id, code).await? { MfaCheck::Verified } else { MfaCheck::Invalid })
+}
+
+pub(super) async fn issue_refresh_token(database: &str, user_id: Uuid, mfa: bool) -> Result<refresh_tokens::IssuedRefreshToken, DatabaseError> {
+    let pool = DatabaseManager::tenant_pool(database).await?;
+    refresh_tokens::issue(&pool, user_id, mfa).await
+}
+
+/// POST /auth/refresh/:tenant/:user - Exchange a refresh token for a new JWT
 ///
-/// Allows clients to refresh their JWT tokens without requiring full
-/// re-authentication. Accepts an existing JWT token (which may be expired)
-/// and returns a new token with extended expiration.
+/// Refresh tokens are opaque, single use and rotated: every successful call returns a new
+/// refresh token and retires the one presented. Presenting a retired token revokes every
+/// token issued since the login it came from, so a stolen token stops working as soon as
+/// either party uses it again. The new JWT is MFA-verified when the login was.
 ///
 /// URL Parameters:
 /// - tenant: Tenant name (from tenants.name column in monk_main DB)
@@ -169,7 +373,7 @@ pub async fn login(
 /// Expected Input:
 /// ```json
 /// {
-///   "token": "string"    // Required: Current JWT token (may be expired)
+///   "refresh_token": "string"    // Required: Refresh token from login or the last refresh
 /// }
 /// ```
 ///
@@ -179,37 +383,72 @@ pub async fn login(
 ///   "success": true,
 ///   "data": {
 ///     "token": "eyJhbGciOiJIUzI1NiI...",
-///     "expires_in": 3600
+///     "expires_in": 3600,
+///     "refresh_token": "string",
+///     "refresh_expires_at": "2025-01-31T00:00:00"
 ///   }
 /// }
 /// ```
 pub async fn refresh(
-    Path((tenant, user)): Path<(String, String)>,
-    Json(_payload): Json<RefreshRequest>,
+    Path((tenant_name, user_auth)): Path<(String, String)>,
+    Json(payload): Json<RefreshRequest>,
 ) -> impl IntoResponse {
-    // TODO: Validate tenant and user parameters match token claims
-    // TODO: Extract JWT token from request body
-    // TODO: Validate token signature using utils::validate_jwt_token (even if expired)
-    // TODO: Check token hasn't been revoked/blacklisted
-    // TODO: Extract user claims from existing token
-    // TODO: Generate new JWT token with same claims but new expiration
-    // TODO: Return new token to client
+    let failure = |status: StatusCode, error: &str, error_code: &str| {
+        (status, Json(json!({ "success": false, "error": error, "error_code": error_code })))
+    };
+
+    let tenant = match find_tenant_by_name(&tenant_name).await {
+        Ok(Some(tenant)) => tenant,
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "Tenant not found", "TENANT_NOT_FOUND"),
+        Err(e) => {
+            tracing::error!("Database error checking tenant {}: {}", tenant_name, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error", "DATABASE_ERROR");
+        }
+    };
+    let user = match find_user_by_auth(&tenant.database, &user_auth).await {
+        Ok(Some(user)) if !user.is_deleted() && user.is_activated() => user,
+        Ok(_) => return failure(StatusCode::UNAUTHORIZED, "Invalid refresh token", "INVALID_REFRESH_TOKEN"),
+        Err(e) => {
+            tracing::error!("Database error checking user {} in {}: {}", user_auth, tenant.database, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error", "DATABASE_ERROR");
+        }
+    };
+
+    let rotated = match DatabaseManager::tenant_pool(&tenant.database).await {
+        Ok(pool) => refresh_tokens::rotate(&pool, &payload.refresh_token, user.id).await,
+        Err(e) => Err(e),
+    };
+    let next = match rotated {
+        Ok(Rotation::Rotated(next)) => next,
+        Ok(Rotation::Reused { .. }) => {
+            return failure(StatusCode::UNAUTHORIZED, "Refresh token already used; session revoked", "REFRESH_TOKEN_REUSED")
+        }
+        Ok(Rotation::Expired) => return failure(StatusCode::UNAUTHORIZED, "Refresh token expired", "REFRESH_TOKEN_EXPIRED"),
+        Ok(Rotation::Unknown) => return failure(StatusCode::UNAUTHORIZED, "Invalid refresh token", "INVALID_REFRESH_TOKEN"),
+        Err(e) => {
+            tracing::error!("Refresh token error for {} in {}: {}", user_auth, tenant.database, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error", "DATABASE_ERROR");
+        }
+    };
+
+    let claims = Claims::new(tenant.name, user.auth, tenant.database, user.access, user.id).with_mfa(next.mfa);
+    let token = match generate_jwt(claims) {
+        Ok(token) => token,
+        Err(e) => {
+            tracing::error!("JWT generation error: {}", e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Token generation failed", "JWT_ERROR");
+        }
+    };
 
     (
-        StatusCode::NOT_IMPLEMENTED,
+        StatusCode::OK,
         Json(json!({
-            "success": false,
-            "error": "Token refresh endpoint not yet implemented",
-            "message": "This will refresh JWT tokens without full re-authentication",
-            "expected_input": {
-                "token": "string (required - existing JWT token)"
-            },
-            "planned_response": {
-                "success": true,
-                "data": {
-                    "token": "eyJhbGciOiJIUzI1NiI...",
-                    "expires_in": 3600
-                }
+            "success": true,
+            "data": {
+                "token": token,
+                "expires_in": crate::config::config().security.jwt_expiry_hours * 3600,
+                "refresh_token": next.token,
+                "refresh_expires_at": next.expires_at
             }
         })),
     )
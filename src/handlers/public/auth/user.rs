@@ -1,8 +1,37 @@
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use serde::Deserialize;
-use serde_json::{json, Value};
+use serde_json::json;
 
-use super::utils::validate_tenant_exists;
+use crate::database::service::{
+    activate_user, create_user, discard_pending_user, find_pending_activation, find_tenant_by_name,
+    soft_delete_user, NewUser,
+};
+use crate::services::handlebars;
+use crate::services::mailer::{self, OutgoingMail};
+
+use super::utils::{
+    activation_token_hash, generate_activation_token, hash_password, validate_email_format,
+    validate_username_format,
+};
+
+const ACTIVATION_HTML: &str = r#"<p>The account <b>{{username}}</b> was registered in <b>{{tenant}}</b> with this address.</p>
+<p>Activation token: <code>{{token}}</code></p>
+<p>Activate it before {{expires}} (UTC) with <code>PUT {{activate_url}}</code>, sending the tenant and token.</p>
+<p>If you did not register, ignore this email; the account is never activated.</p>
+"#;
+
+const ACTIVATION_TEXT: &str = r#"The account {{username}} was registered in {{tenant}} with this address.
+
+Activation token: {{token}}
+
+Activate it before {{expires}} (UTC) with PUT {{activate_url}}, sending the tenant and token.
+
+If you did not register, ignore this email; the account is never activated.
+"#;
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
@@ -14,16 +43,22 @@ pub struct RegisterRequest {
 
 /// POST /auth/register - Register new user account
 /// 
-/// Creates a new user account within a tenant. This endpoint may be disabled
-/// in production environments where user registration is handled through
-/// administrative processes.
+/// Creates a new user account with `read` access within a tenant. Disabled unless
+/// `SECURITY_ALLOW_USER_REGISTRATION` is set (development only by default); a supplied
+/// password is stored as an argon2id hash. Every account starts pending activation.
+/// 
+/// The single-use activation token is emailed to the address given, which is stored with
+/// the user; self-service registration therefore needs an email and outgoing mail
+/// (`API_SMTP_URL`). Only when a root user of the tenant registers someone, authenticated
+/// by their bearer token, is the token returned in the response instead, for them to hand
+/// over; the email is then optional.
 /// 
 /// Expected Input:
 /// ```json
 /// {
 ///   "tenant": "string",     // Required: Tenant identifier
 ///   "username": "string",   // Required: Desired username
-///   "email": "string",      // Optional: User email address
+///   "email": "string",      // Required unless registered by root: Where the token is sent
 ///   "password": "string"    // Optional: May be set in activation flow
 /// }
 /// ```
@@ -37,104 +72,298 @@ pub struct RegisterRequest {
 ///     "username": "new_user",
 ///     "tenant": "my-tenant",
 ///     "status": "pending_activation",
-///     "message": "Registration successful. Check email for activation instructions."
+///     "activation_expires_at": "2024-01-03T00:00:00Z",
+///     "message": "Registration successful. The activation token was sent to the email address."
 ///   }
 /// }
 /// ```
-pub async fn register(Json(_payload): Json<RegisterRequest>) -> impl IntoResponse {
-    // TODO: Check if registration is enabled for this environment (config)
-    // TODO: Validate tenant exists using utils::validate_tenant_exists
-    // TODO: Validate tenant allows self-registration
-    // TODO: Validate username format and availability
-    // TODO: Validate email format if provided
-    // TODO: Create user account in tenant database
-    // TODO: Send welcome email or activation instructions
-    // TODO: Return success confirmation
-
-    // Check if registration is disabled
-    if !crate::config::CONFIG.security.enable_cors {
-        // TODO: Add proper registration enable flag to config
-        return (
+/// 
+/// Registrations by root also carry `"activation_token": "hex_token"`.
+pub async fn register(headers: HeaderMap, Json(payload): Json<RegisterRequest>) -> impl IntoResponse {
+    let failure = |status: StatusCode, error: String, error_code: &str| {
+        (status, Json(json!({ "success": false, "error": error, "error_code": error_code })))
+    };
+
+    if !crate::config::config().security.allow_user_registration {
+        return failure(
             StatusCode::FORBIDDEN,
-            Json(json!({
-                "success": false,
-                "error": "Registration is disabled",
-                "message": "User registration is not available in this environment"
-            })),
+            "User registration is not available in this environment".to_string(),
+            "REGISTRATION_DISABLED",
         );
     }
 
-    (
-        StatusCode::NOT_IMPLEMENTED,
-        Json(json!({
-            "success": false,
-            "error": "Registration endpoint not yet implemented",
-            "message": "This will create new user accounts within tenants",
-            "note": "Registration may be disabled in production environments",
-            "expected_input": {
-                "tenant": "string (required)",
-                "username": "string (required)",
-                "email": "string (optional)",
-                "password": "string (optional - may be set during activation)"
-            },
-            "planned_response": {
+    if let Err(e) = validate_username_format(&payload.username) {
+        return failure(StatusCode::BAD_REQUEST, e, "INVALID_USERNAME");
+    }
+    let email = payload.email.as_deref().map(str::trim).filter(|email| !email.is_empty());
+    if let Some(email) = email {
+        if let Err(e) = validate_email_format(email) {
+            return failure(StatusCode::BAD_REQUEST, e, "INVALID_EMAIL");
+        }
+    }
+
+    // A root bearer token for the tenant lets an administrator receive the token directly
+    let by_root = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| crate::auth::validate_jwt(token.trim(), false).ok())
+        .is_some_and(|claims| claims.access == "root" && claims.tenant == payload.tenant);
+    if !by_root {
+        if email.is_none() {
+            return failure(StatusCode::BAD_REQUEST, "An email address is required to receive the activation token".to_string(), "EMAIL_REQUIRED");
+        }
+        if !mailer::configured() {
+            return failure(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Self-service registration needs outgoing mail, which is not configured".to_string(),
+                "MAIL_NOT_CONFIGURED",
+            );
+        }
+    }
+
+    let tenant = match find_tenant_by_name(&payload.tenant).await {
+        Ok(Some(tenant)) => tenant,
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "Tenant not found".to_string(), "TENANT_NOT_FOUND"),
+        Err(e) => {
+            tracing::error!("Database error checking tenant {}: {}", payload.tenant, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string(), "DATABASE_ERROR");
+        }
+    };
+
+    // argon2 is CPU-bound, so keep it off the async workers
+    let password_hash = match payload.password {
+        Some(password) => match tokio::task::spawn_blocking(move || hash_password(&password)).await {
+            Ok(Ok(hash)) => Some(hash),
+            Ok(Err(e)) => return failure(StatusCode::BAD_REQUEST, e, "INVALID_PASSWORD"),
+            Err(e) => {
+                tracing::error!("Password hashing task failed: {}", e);
+                return failure(StatusCode::INTERNAL_SERVER_ERROR, "Password hashing failed".to_string(), "PASSWORD_ERROR");
+            }
+        },
+        None => None,
+    };
+
+    let activation_token = generate_activation_token();
+    let expires_at = crate::clock::now()
+        + chrono::Duration::hours(crate::config::config().security.activation_token_ttl_hours as i64);
+    let token_hash = activation_token_hash(&activation_token);
+    let created = create_user(&tenant.database, NewUser {
+        name: &payload.username,
+        auth: &payload.username,
+        access: "read",
+        email,
+        password_hash: password_hash.as_deref(),
+        activation_token_hash: &token_hash,
+        activation_expires_at: expires_at.naive_utc(),
+    })
+    .await;
+    let user = match created {
+        Ok(Some(user)) => user,
+        Ok(None) => return failure(StatusCode::CONFLICT, "Username already exists".to_string(), "USERNAME_TAKEN"),
+        Err(e) => {
+            tracing::error!("Database error registering {} in {}: {}", payload.username, tenant.database, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string(), "DATABASE_ERROR");
+        }
+    };
+
+    if by_root {
+        tracing::info!("Registered user {} in {} by root, pending activation", user.auth, tenant.database);
+        return (
+            StatusCode::CREATED,
+            Json(json!({
                 "success": true,
                 "data": {
-                    "user_id": "user_uuid",
-                    "username": "new_user",
-                    "tenant": "my-tenant",
+                    "user_id": user.id,
+                    "username": user.auth,
+                    "tenant": tenant.name,
                     "status": "pending_activation",
-                    "message": "Registration successful. Check email for activation instructions."
+                    "activation_token": activation_token,
+                    "activation_expires_at": expires_at,
+                    "message": "Registration successful. Activate the account with PUT /auth/activate."
                 }
+            })),
+        );
+    }
+
+    let email = email.unwrap_or_default();
+    if let Err(e) = mailer::send(activation_mail(&tenant.name, &user.auth, email, &activation_token, expires_at)).await {
+        // Without the token the account could never be activated, so it is not kept
+        tracing::warn!("Activation email for {} in {} not sent: {}", user.auth, tenant.database, e);
+        if let Err(e) = discard_pending_user(&tenant.database, user.id).await {
+            tracing::error!("Database error discarding user {} in {}: {}", user.id, tenant.database, e);
+        }
+        return failure(StatusCode::BAD_GATEWAY, "The activation email could not be sent".to_string(), "MAIL_FAILED");
+    }
+
+    tracing::info!("Registered user {} in {}, activation token emailed", user.auth, tenant.database);
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "success": true,
+            "data": {
+                "user_id": user.id,
+                "username": user.auth,
+                "tenant": tenant.name,
+                "status": "pending_activation",
+                "activation_expires_at": expires_at,
+                "message": "Registration successful. The activation token was sent to the email address."
             }
         })),
     )
 }
 
-/// DELETE /auth/user/:id - Delete user account (self-service)
+/// The email carrying a new account's activation token
+fn activation_mail(tenant: &str, username: &str, email: &str, token: &str, expires_at: chrono::DateTime<chrono::Utc>) -> OutgoingMail {
+    let context = json!({
+        "tenant": tenant,
+        "username": username,
+        "token": token,
+        "expires": expires_at.format("%Y-%m-%d %H:%M").to_string(),
+        "activate_url": format!("{}/auth/activate", crate::config::config().api.public_url.trim_end_matches('/')),
+    });
+    let render = |source: &str, html: bool| {
+        let template = handlebars::Template::parse(source).expect("built-in activation templates parse");
+        if html { template.render(&context) } else { template.render_text(&context) }
+    };
+    OutgoingMail {
+        to: email.to_string(),
+        subject: format!("Activate your account in {}", tenant),
+        html: render(ACTIVATION_HTML, true),
+        text: render(ACTIVATION_TEXT, false),
+        unsubscribe_url: None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivateRequest {
+    pub tenant: String,
+    pub token: String,
+    /// Required when no password was given at registration
+    pub password: Option<String>,
+}
+
+/// DELETE /auth/user - Delete the caller's own account (self-service)
 /// 
-/// Allows users to delete their own accounts. This is a destructive operation
-/// that may be restricted or disabled in some environments.
-pub async fn delete_account(
-    // Path(user_id): Path<String>,
-    // TODO: Extract user ID from authenticated token
-) -> impl IntoResponse {
-    // TODO: Verify user can only delete their own account
-    // TODO: Check if account deletion is enabled
-    // TODO: Soft delete user account (set deleted_at timestamp)
-    // TODO: Invalidate all JWT tokens for this user
-    // TODO: Send confirmation email
-    // TODO: Log account deletion for audit
+/// Authenticated by the bearer token of the account being deleted. The user is soft
+/// deleted (`trashed_at` set): login is refused and existing tokens stop working.
+/// 
+/// Expected Output (Success):
+/// ```json
+/// {
+///   "success": true,
+///   "data": { "user_id": "user_uuid", "status": "deleted" }
+/// }
+/// ```
+pub async fn delete_account(headers: HeaderMap) -> impl IntoResponse {
+    let failure = |status: StatusCode, error: String, error_code: &str| {
+        (status, Json(json!({ "success": false, "error": error, "error_code": error_code })))
+    };
 
-    (
-        StatusCode::NOT_IMPLEMENTED,
-        Json(json!({
-            "success": false,
-            "error": "Account deletion not yet implemented",
-            "message": "This will allow users to delete their own accounts"
-        })),
-    )
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| !token.trim().is_empty());
+    let Some(token) = token else {
+        return failure(StatusCode::UNAUTHORIZED, "Missing bearer token".to_string(), "UNAUTHORIZED");
+    };
+    let claims = match crate::auth::validate_jwt(token, false) {
+        Ok(claims) => claims,
+        Err(e) => return failure(StatusCode::UNAUTHORIZED, e.to_string(), "UNAUTHORIZED"),
+    };
+
+    match soft_delete_user(&claims.database, claims.user_id).await {
+        Ok(true) => {
+            tracing::info!("User {} deleted their account in {}", claims.user, claims.database);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "success": true,
+                    "data": { "user_id": claims.user_id, "status": "deleted" }
+                })),
+            )
+        }
+        Ok(false) => failure(StatusCode::NOT_FOUND, "User not found".to_string(), "USER_NOT_FOUND"),
+        Err(e) => {
+            tracing::error!("Database error deleting user {} in {}: {}", claims.user_id, claims.database, e);
+            failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string(), "DATABASE_ERROR")
+        }
+    }
 }
 
-/// PUT /auth/user/activate - Activate user account
+/// PUT /auth/activate - Activate user account
+/// 
+/// Complete user registration with the activation token emailed at registration. The
+/// token is single use and expires after `SECURITY_ACTIVATION_TOKEN_TTL_HOURS`.
 /// 
-/// Complete user registration by activating the account with an activation token.
-pub async fn activate(Json(_payload): Json<Value>) -> impl IntoResponse {
-    // TODO: Extract activation token from request
-    // TODO: Validate activation token (check expiry, usage)
-    // TODO: Set user account status to active
-    // TODO: Allow password setup if not already set
-    // TODO: Send welcome email
-    // TODO: Return success confirmation
+/// Expected Input:
+/// ```json
+/// {
+///   "tenant": "string",     // Required: Tenant the account was registered in
+///   "token": "string",      // Required: Activation token
+///   "password": "string"    // Required unless set at registration
+/// }
+/// ```
+pub async fn activate(Json(payload): Json<ActivateRequest>) -> impl IntoResponse {
+    let failure = |status: StatusCode, error: String, error_code: &str| {
+        (status, Json(json!({ "success": false, "error": error, "error_code": error_code })))
+    };
 
-    (
-        StatusCode::NOT_IMPLEMENTED,
-        Json(json!({
-            "success": false,
-            "error": "Account activation not yet implemented",
-            "message": "This will activate user accounts using activation tokens"
-        })),
-    )
+    let tenant = match find_tenant_by_name(&payload.tenant).await {
+        Ok(Some(tenant)) => tenant,
+        Ok(None) => return failure(StatusCode::NOT_FOUND, "Tenant not found".to_string(), "TENANT_NOT_FOUND"),
+        Err(e) => {
+            tracing::error!("Database error checking tenant {}: {}", payload.tenant, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string(), "DATABASE_ERROR");
+        }
+    };
+
+    let token_hash = activation_token_hash(&payload.token);
+    let pending = match find_pending_activation(&tenant.database, &token_hash).await {
+        Ok(Some(pending)) => pending,
+        Ok(None) => {
+            return failure(StatusCode::NOT_FOUND, "Invalid or already used activation token".to_string(), "INVALID_TOKEN")
+        }
+        Err(e) => {
+            tracing::error!("Database error checking activation in {}: {}", tenant.database, e);
+            return failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string(), "DATABASE_ERROR");
+        }
+    };
+    if pending.activation_expires_at < crate::clock::now().naive_utc() {
+        return failure(StatusCode::GONE, "Activation token expired".to_string(), "TOKEN_EXPIRED");
+    }
+
+    let password_hash = match payload.password {
+        Some(password) => match tokio::task::spawn_blocking(move || hash_password(&password)).await {
+            Ok(Ok(hash)) => Some(hash),
+            Ok(Err(e)) => return failure(StatusCode::BAD_REQUEST, e, "INVALID_PASSWORD"),
+            Err(e) => {
+                tracing::error!("Password hashing task failed: {}", e);
+                return failure(StatusCode::INTERNAL_SERVER_ERROR, "Password hashing failed".to_string(), "PASSWORD_ERROR");
+            }
+        },
+        None if pending.has_password => None,
+        None => return failure(StatusCode::BAD_REQUEST, "A password is required to activate this account".to_string(), "PASSWORD_REQUIRED"),
+    };
+
+    match activate_user(&tenant.database, pending.id, &token_hash, password_hash.as_deref()).await {
+        Ok(true) => {
+            tracing::info!("Activated user {} in {}", pending.auth, tenant.database);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "success": true,
+                    "data": { "user_id": pending.id, "username": pending.auth, "status": "active" }
+                })),
+            )
+        }
+        Ok(false) => failure(StatusCode::NOT_FOUND, "Invalid or already used activation token".to_string(), "INVALID_TOKEN"),
+        Err(e) => {
+            tracing::error!("Database error activating user {} in {}: {}", pending.id, tenant.database, e);
+            failure(StatusCode::INTERNAL_SERVER_ERROR, "Database error".to_string(), "DATABASE_ERROR")
+        }
+    }
 }
 
 /*
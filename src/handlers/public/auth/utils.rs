@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 
+use crate::auth::password;
+
 /// JWT Claims structure for authentication tokens
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JWTClaims {
@@ -58,7 +61,7 @@ pub fn generate_jwt_token(user_info: &UserInfo, expiry_hours: u64) -> Result<Str
     use jsonwebtoken::{encode, Header, EncodingKey};
     use chrono::{Utc, Duration};
     
-    let expiration = (Utc::now() + Duration::hours(expiry_hours as i64)).timestamp();
+    let expiration = (crate::clock::now() + Duration::hours(expiry_hours as i64)).timestamp();
     
     let claims = JWTClaims {
         sub: user_info.id.clone(),
@@ -66,7 +69,7 @@ pub fn generate_jwt_token(user_info: &UserInfo, expiry_hours: u64) -> Result<Str
         database: user_info.database.clone(),
         access: user_info.access.clone(),
         exp: expiration,
-        iat: Utc::now().timestamp(),
+        iat: crate::clock::now().timestamp(),
         iss: "monk-api-rust".to_string(),
     };
     
@@ -95,36 +98,17 @@ pub fn generate_jwt_token(user_info: &UserInfo, expiry_hours: u64) -> Result<Str
 /// # Returns
 /// * `Result<JWTClaims, String>` - Decoded claims or error message
 pub fn validate_jwt_token(token: &str, allow_expired: bool) -> Result<JWTClaims, String> {
-    // TODO: Implement JWT token validation
-    // 1. Get JWT signing key from configuration
-    // 2. Configure validation settings (expiration, issuer, etc.)
-    // 3. Decode and verify token signature
-    // 4. Return claims if valid
-    
-    // Placeholder implementation
-    let _ = (token, allow_expired);
-    Err("JWT token validation not yet implemented".to_string())
-    
-    /*
-    PRODUCTION IMPLEMENTATION:
-    
-    use jsonwebtoken::{decode, DecodingKey, Validation};
-    
-    let secret = std::env::var("JWT_SECRET")
-        .map_err(|_| "JWT_SECRET not configured")?;
-    
-    let mut validation = Validation::default();
-    validation.validate_exp = !allow_expired;
-    validation.iss = Some("monk-api-rust".to_string());
-    
-    let token_data = decode::<JWTClaims>(
-        token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &validation
-    ).map_err(|e| format!("Invalid token: {}", e))?;
-    
-    Ok(token_data.claims)
-    */
+    let claims = crate::auth::validate_jwt(token, allow_expired).map_err(|e| e.to_string())?;
+
+    Ok(JWTClaims {
+        sub: claims.user_id.to_string(),
+        tenant: claims.tenant,
+        database: claims.database,
+        access: claims.access,
+        exp: claims.exp,
+        iat: claims.iat,
+        iss: claims.iss.unwrap_or_default(),
+    })
 }
 
 /// Validate that a tenant exists and is active
@@ -138,37 +122,18 @@ pub fn validate_jwt_token(token: &str, allow_expired: bool) -> Result<JWTClaims,
 /// # Returns
 /// * `Result<TenantInfo, String>` - Tenant information or error message
 pub async fn validate_tenant_exists(tenant_name: &str) -> Result<TenantInfo, String> {
-    // TODO: Implement tenant validation
-    // 1. Query system database for tenant record
-    // 2. Check tenant status (active, suspended, etc.)
-    // 3. Verify tenant allows requested operation
-    // 4. Return tenant information
-    
-    // Placeholder implementation
-    let _ = tenant_name;
-    Err("Tenant validation not yet implemented".to_string())
-    
-    /*
-    PRODUCTION IMPLEMENTATION:
-    
-    use crate::database::manager::DatabaseManager;
-    
-    let system_pool = DatabaseManager::main_pool().await
-        .map_err(|e| format!("Database connection failed: {}", e))?;
-    
-    let tenant = sqlx::query_as!(
-        TenantInfo,
-        "SELECT name, database_name, status, created_at 
-         FROM tenants 
-         WHERE name = $1 AND status = 'active'",
-        tenant_name
-    )
-    .fetch_optional(&system_pool)
-    .await
-    .map_err(|e| format!("Database query failed: {}", e))?;
-    
-    tenant.ok_or_else(|| format!("Tenant '{}' not found or inactive", tenant_name))
-    */
+    let tenant = crate::database::service::find_tenant_by_name(tenant_name)
+        .await
+        .map_err(|e| format!("Database query failed: {}", e))?
+        .filter(|tenant| tenant.trashed_at.is_none() && tenant.deleted_at.is_none())
+        .ok_or_else(|| format!("Tenant '{}' not found or inactive", tenant_name))?;
+
+    Ok(TenantInfo {
+        name: tenant.name,
+        database_name: tenant.database,
+        status: "active".to_string(),
+        created_at: tenant.created_at,
+    })
 }
 
 /// Tenant information structure
@@ -249,8 +214,8 @@ pub fn validate_email_format(email: &str) -> Result<(), String> {
 
 /// Hash password for secure storage
 /// 
-/// Uses bcrypt or argon2 to hash passwords with appropriate
-/// work factors for security.
+/// Argon2id with the cost configured in `security.password_*`, returned as a PHC
+/// string that records its own salt and parameters.
 /// 
 /// # Arguments
 /// * `password` - Plain text password to hash
@@ -258,23 +223,17 @@ pub fn validate_email_format(email: &str) -> Result<(), String> {
 /// # Returns
 /// * `Result<String, String>` - Hashed password or error message
 pub fn hash_password(password: &str) -> Result<String, String> {
-    // TODO: Implement secure password hashing
-    // 1. Use bcrypt or argon2 for hashing
-    // 2. Use appropriate work factor from configuration
-    // 3. Return hashed password for storage
-    
-    // Placeholder implementation
-    let _ = password;
-    Err("Password hashing not yet implemented".to_string())
-    
-    /*
-    PRODUCTION IMPLEMENTATION:
-    
-    use bcrypt::{hash, DEFAULT_COST};
-    
-    hash(password, DEFAULT_COST)
-        .map_err(|e| format!("Password hashing failed: {}", e))
-    */
+    if password.len() < auth::MIN_PASSWORD_LENGTH {
+        return Err(format!("Password must be at least {} characters", auth::MIN_PASSWORD_LENGTH));
+    }
+
+    let security = &crate::config::config().security;
+    let params = password::Params {
+        memory_kib: security.password_memory_kib,
+        iterations: security.password_iterations,
+        parallelism: security.password_parallelism,
+    };
+    password::hash(password, params).map_err(|e| format!("Password hashing failed: {}", e))
 }
 
 /// Verify password against stored hash
@@ -289,50 +248,26 @@ pub fn hash_password(password: &str) -> Result<String, String> {
 /// # Returns
 /// * `Result<bool, String>` - True if password matches, false if not, error on failure
 pub fn verify_password(password: &str, hash: &str) -> Result<bool, String> {
-    // TODO: Implement password verification
-    // 1. Use bcrypt or argon2 to verify password against hash
-    // 2. Return true if password matches, false otherwise
-    
-    // Placeholder implementation
-    let _ = (password, hash);
-    Err("Password verification not yet implemented".to_string())
-    
-    /*
-    PRODUCTION IMPLEMENTATION:
-    
-    use bcrypt::verify;
-    
-    verify(password, hash)
-        .map_err(|e| format!("Password verification failed: {}", e))
-    */
+    password::verify(password, hash).map_err(|e| format!("Password verification failed: {}", e))
 }
 
 /// Generate secure activation token
 /// 
-/// Creates a cryptographically secure token for account activation
-/// or password reset operations.
+/// 32 bytes from the operating system's CSPRNG (via v4 UUIDs), hex encoded so the
+/// token is URL-safe. Only `activation_token_hash` of it is stored.
 /// 
 /// # Returns
 /// * `String` - Secure random token
 pub fn generate_activation_token() -> String {
-    // TODO: Generate cryptographically secure random token
-    // 1. Use secure random number generator
-    // 2. Create URL-safe token of appropriate length
-    // 3. Ensure uniqueness within reasonable bounds
-    
-    // Placeholder implementation
-    "placeholder_token".to_string()
-    
-    /*
-    PRODUCTION IMPLEMENTATION:
-    
-    use rand::{thread_rng, Rng};
-    use base64::{Engine as _, engine::general_purpose};
-    
-    let mut token = [0u8; 32];
-    thread_rng().fill(&mut token);
-    general_purpose::URL_SAFE_NO_PAD.encode(token)
-    */
+    let mut digest = Sha256::new();
+    digest.update(uuid::Uuid::new_v4().as_bytes());
+    digest.update(uuid::Uuid::new_v4().as_bytes());
+    hex::encode(digest.finalize())
+}
+
+/// Digest under which an activation token is stored and looked up
+pub fn activation_token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
 
 /// Authentication utility functions and constants
@@ -346,8 +281,7 @@ pub mod auth {
     /// Default password minimum length
     pub const MIN_PASSWORD_LENGTH: usize = 8;
     
-    /// Rate limiting constants
-    pub const LOGIN_RATE_LIMIT_PER_MINUTE: u32 = 5;
+    /// Rate limiting constants; login lockout is configured by SECURITY_LOGIN_*
     pub const REGISTRATION_RATE_LIMIT_PER_HOUR: u32 = 3;
 }
 
@@ -369,7 +303,7 @@ This module provides shared functionality for authentication operations:
    - Input sanitization
 
 3. **Security Functions**:
-   - Secure password hashing (bcrypt/argon2)
+   - Secure password hashing (argon2id)
    - Password verification
    - Activation token generation
    - Rate limiting support
@@ -391,7 +325,7 @@ IMPLEMENTATION NOTES:
 CONFIGURATION DEPENDENCIES:
 
 - JWT_SECRET: Secret key for token signing
-- BCRYPT_COST: Work factor for password hashing
+- SECURITY_PASSWORD_*: Argon2id cost for password hashing
 - TOKEN_EXPIRY_HOURS: Default token lifetime
 - RATE_LIMIT_*: Rate limiting configuration
 
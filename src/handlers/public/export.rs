@@ -0,0 +1,55 @@
+use axum::body::Body;
+use axum::extract::{Path, Query};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use serde::Deserialize;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::database::export_jobs;
+use crate::database::DatabaseManager;
+use crate::error::ApiError;
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadQuery {
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// GET /api/export/download/:database/:id - Stream a completed export file
+///
+/// Authorized by the expiring signature from GET /api/export/:id rather than a bearer
+/// token, so the URL can be handed to a browser or download tool.
+pub async fn download(
+    Path((database, id)): Path<(String, Uuid)>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<Response, ApiError> {
+    if !export_jobs::verify_download(&database, id, query.expires, &query.signature) {
+        return Err(ApiError::forbidden("Download link is invalid or has expired"));
+    }
+
+    let pool = DatabaseManager::tenant_pool(&database).await?;
+    let job = export_jobs::get_job(&pool, id).await?;
+    if job.status != "completed" || job.destination != "download" {
+        return Err(ApiError::not_found(format!("Export job '{}' has no download", id)));
+    }
+
+    let file = tokio::fs::File::open(export_jobs::file_path(&database, &job))
+        .await
+        .map_err(|_| ApiError::gone(format!("Export file for job '{}' is no longer available", id)))?;
+    let length = file.metadata().await.map(|m| m.len()).unwrap_or_default();
+
+    let mut response = (
+        [
+            (header::CONTENT_TYPE, job.content_type().to_string()),
+            (header::CONTENT_LENGTH, length.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", job.file_name())),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response();
+    if let Some(etag) = job.checksum.and_then(|checksum| format!("\"{}\"", checksum).parse().ok()) {
+        response.headers_mut().insert(header::ETAG, etag);
+    }
+    Ok(response)
+}
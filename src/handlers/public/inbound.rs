@@ -0,0 +1,85 @@
+use axum::body::{to_bytes, Body};
+use axum::extract::{Path, Query};
+use axum::http::{header, HeaderMap};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::mailboxes;
+use crate::database::DatabaseManager;
+use crate::error::ApiError;
+use crate::middleware::{ApiResponse, ApiResult};
+use crate::services::inbound_email::{self, InboundPayload};
+
+#[derive(Debug, Deserialize)]
+pub struct InboundQuery {
+    pub signature: String,
+}
+
+/// POST /api/inbound/email/:database/:id - Receive a message from an email provider
+///
+/// Authorized by the signature in the webhook URL returned when the mailbox was created.
+/// Answers 201 with the record created and the attachments stored on it. SES subscription
+/// confirmations are confirmed here, so the SNS topic can be subscribed to the URL directly.
+pub async fn email(
+    Path((database, id)): Path<(String, Uuid)>,
+    Query(query): Query<InboundQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> ApiResult<Value> {
+    if !mailboxes::verify_webhook(&database, id, &query.signature) {
+        return Err(ApiError::forbidden("Webhook URL is invalid"));
+    }
+    let pool = DatabaseManager::tenant_pool(&database).await?;
+    let mailbox = mailboxes::get_mailbox(&pool, id).await?;
+    if !mailbox.enabled {
+        return Err(ApiError::gone(format!("Mailbox '{}' is disabled", mailbox.name)));
+    }
+
+    let limit = crate::config::config().api.inbound_email_max_bytes;
+    let body = to_bytes(body, limit)
+        .await
+        .map_err(|_| ApiError::payload_too_large(format!("Inbound email exceeds {} bytes", limit)))?;
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let signing_key = mailbox.signing_key.as_deref().filter(|key| !key.is_empty());
+
+    match inbound_email::parse(&mailbox.provider, content_type, &body, signing_key).map_err(ApiError::bad_request)? {
+        InboundPayload::Email(email) => {
+            let delivery = mailboxes::deliver(&pool, &database, &mailbox, email).await?;
+            tracing::info!(
+                "Mailbox '{}' in tenant '{}' received a message with {} attachment(s)",
+                mailbox.name,
+                database,
+                delivery.files.len()
+            );
+            Ok(ApiResponse::created(json!(delivery)))
+        }
+        InboundPayload::SubscriptionConfirmation(url) => {
+            confirm_subscription(&url).await?;
+            tracing::info!("Mailbox '{}' in tenant '{}' confirmed its SNS subscription", mailbox.name, database);
+            Ok(ApiResponse::success(json!({ "confirmed": true })))
+        }
+        InboundPayload::Ignored(reason) => Ok(ApiResponse::success(json!({ "ignored": reason }))),
+    }
+}
+
+/// Fetch an SNS SubscribeURL; only AWS endpoints are contacted
+async fn confirm_subscription(url: &str) -> Result<(), ApiError> {
+    let parsed = url::Url::parse(url).map_err(|_| ApiError::bad_request("Invalid SubscribeURL"))?;
+    let aws = parsed.scheme() == "https" && parsed.host_str().is_some_and(|host| host.ends_with(".amazonaws.com"));
+    if !aws {
+        return Err(ApiError::bad_request("SubscribeURL is not an Amazon SNS endpoint"));
+    }
+    let response = crate::observer::deferred::http_client()
+        .get(parsed)
+        .send()
+        .await
+        .map_err(|e| ApiError::bad_gateway(format!("Could not confirm SNS subscription: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(ApiError::bad_gateway(format!("SNS subscription confirmation answered {}", response.status())));
+    }
+    Ok(())
+}
@@ -10,6 +10,18 @@
 // Public authentication module for token acquisition
 pub mod auth;
 
+// Server version and feature negotiation
+pub mod version;
+
+// Signed downloads of completed export jobs
+pub mod export;
+
+// Signed inbound email webhooks from SendGrid, Mailgun and SES
+pub mod inbound;
+
+// Signed unsubscribe links of notification digests
+pub mod notifications;
+
 // Re-export auth handlers for easy importing  
 pub use auth::*;
 
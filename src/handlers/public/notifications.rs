@@ -0,0 +1,43 @@
+use axum::extract::{Path, Query};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::database::notifications;
+use crate::database::DatabaseManager;
+use crate::error::ApiError;
+use crate::middleware::{ApiResponse, ApiResult};
+
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeQuery {
+    /// Subscription to stop; all digests are stopped without it
+    pub subscription: Option<Uuid>,
+    pub signature: String,
+}
+
+/// GET|POST /api/notifications/unsubscribe/:database/:user_id - Unsubscribe from digests
+///
+/// Authorized by the signature in the link sent with each digest. Stops the named
+/// subscription, or sets the user's digests to `off`. POST serves mail clients' one-click
+/// `List-Unsubscribe-Post` requests. Following a link again succeeds.
+pub async fn unsubscribe(
+    Path((database, user_id)): Path<(String, Uuid)>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> ApiResult<Value> {
+    if !notifications::verify_unsubscribe(&database, user_id, query.subscription, &query.signature) {
+        return Err(ApiError::forbidden("Unsubscribe link is invalid"));
+    }
+    let pool = DatabaseManager::tenant_pool(&database).await?;
+    notifications::unsubscribe(&pool, user_id, query.subscription).await?;
+
+    tracing::info!(
+        "User {} in {} unsubscribed from {}",
+        user_id,
+        database,
+        query.subscription.map(|id| format!("subscription {}", id)).unwrap_or_else(|| "all digests".to_string())
+    );
+    Ok(ApiResponse::success(json!({
+        "unsubscribed": true,
+        "subscription": query.subscription,
+    })))
+}
@@ -0,0 +1,73 @@
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+
+/// Feature flags advertised to clients
+///
+/// Clients gate subcommands on these names rather than on version numbers, so a
+/// feature is added here in the same change that ships its endpoint.
+pub const API_FEATURES: &[&str] = &[
+    "audit",
+    "auth",
+    "auth.favorites",
+    "auth.recent",
+    "bulk",
+    "comments",
+    "data",
+    "data.bulk",
+    "data.import_templates",
+    "data.postgrest",
+    "data.render",
+    "data.versioned",
+    "describe",
+    "describe.lint",
+    "export",
+    "features",
+    "file",
+    "find",
+    "find.aggregate",
+    "find.count",
+    "find.count_mode",
+    "find.cursor",
+    "find.explain",
+    "find.patch",
+    "find.rank",
+    "find.relationships",
+    "find.sample",
+    "find.search",
+    "format.jsonapi",
+    "notifications",
+    "odata",
+    "root.advisor",
+    "root.diagnostics",
+    "tags",
+    "views",
+];
+
+/// GET /api/version - Report server version and supported API features
+///
+/// Public so that clients can negotiate compatibility before authenticating.
+///
+/// Expected Output:
+/// ```json
+/// {
+///   "success": true,
+///   "data": {
+///     "name": "monk-api-rust",
+///     "version": "0.1.0",
+///     "features": ["auth", "data", "describe", "find"]
+///   }
+/// }
+/// ```
+pub async fn get() -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "data": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+                "features": API_FEATURES
+            }
+        })),
+    )
+}
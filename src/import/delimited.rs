@@ -0,0 +1,57 @@
+use serde_json::Value;
+
+/// Parse delimited text with a header row; fields may be double-quoted ("" escapes a quote)
+pub fn parse(content: &str, delimiter: char) -> anyhow::Result<Vec<Value>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow::anyhow!("Unterminated quoted field in delimited input"));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    let mut rows = rows.into_iter().filter(|r| !(r.len() == 1 && r[0].trim().is_empty()));
+    let header = rows.next().ok_or_else(|| anyhow::anyhow!("Delimited input has no header row"))?;
+
+    rows.enumerate()
+        .map(|(idx, values)| {
+            if values.len() != header.len() {
+                return Err(anyhow::anyhow!(
+                    "Row {} has {} fields but the header has {}", idx + 1, values.len(), header.len()
+                ));
+            }
+            Ok(Value::Object(
+                header
+                    .iter()
+                    .zip(values)
+                    .map(|(column, value)| {
+                        let value = if value.is_empty() { Value::Null } else { Value::String(value) };
+                        (column.trim().to_string(), value)
+                    })
+                    .collect(),
+            ))
+        })
+        .collect()
+}
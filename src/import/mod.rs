@@ -0,0 +1,24 @@
+//! Import pipeline pieces shared by `monk data import` and the server's ingestion watcher:
+//! delimited text parsing, row transforms (including sandboxed Lua scripts) and import
+//! templates
+
+pub mod delimited;
+pub mod script;
+pub mod template;
+pub mod transform;
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+/// Keep the first row for each `key` value; rows without the key are always kept
+pub fn dedupe(records: Vec<Value>, key: &str) -> Vec<Value> {
+    let mut seen = HashSet::new();
+    records
+        .into_iter()
+        .filter(|record| match record.get(key).filter(|v| !v.is_null()) {
+            Some(value) => seen.insert(value.to_string()),
+            None => true,
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::transform::{Transform, TransformStep};
+
+/// Schema holding import templates in every tenant database
+pub const TEMPLATE_SCHEMA: &str = "import_templates";
+
+/// A reusable import configuration stored as an `import_templates` record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportTemplate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub schema_name: String,
+    /// Source column -> schema field
+    #[serde(default)]
+    pub column_mapping: Map<String, Value>,
+    /// Field delimiter for delimited text input; JSON input ignores it
+    #[serde(default)]
+    pub delimiter: Option<String>,
+    /// Field -> chrono format, applied after column mapping
+    #[serde(default)]
+    pub date_formats: Map<String, Value>,
+    #[serde(default)]
+    pub transform: Vec<TransformStep>,
+    /// Field whose first occurrence wins when rows repeat
+    #[serde(default)]
+    pub dedupe_key: Option<String>,
+}
+
+impl ImportTemplate {
+    /// Fields the server accepts on create and update
+    pub fn to_input(&self) -> anyhow::Result<Value> {
+        let mut input = serde_json::to_value(self)?;
+        if let Some(object) = input.as_object_mut() {
+            object.remove("id");
+        }
+        Ok(input)
+    }
+
+    /// Column mapping, date parsing and the template's own steps as one transform; fails
+    /// when a script step does not compile
+    pub fn to_transform(&self) -> anyhow::Result<Transform> {
+        let mut steps = Vec::new();
+        if !self.column_mapping.is_empty() {
+            steps.push(TransformStep::Rename(self.column_mapping.clone()));
+        }
+        if !self.date_formats.is_empty() {
+            steps.push(TransformStep::ParseDate(self.date_formats.clone()));
+        }
+        steps.extend(self.transform.iter().cloned());
+        Transform::new(steps)
+    }
+
+    /// The delimiter as a single character, if one is configured
+    pub fn delimiter_char(&self) -> anyhow::Result<Option<char>> {
+        match self.delimiter.as_deref() {
+            None | Some("") => Ok(None),
+            Some(d) if d.chars().count() == 1 => Ok(d.chars().next()),
+            Some(d) => Err(anyhow::anyhow!("Template '{}' delimiter '{}' must be a single character", self.name, d)),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::fs;
+use std::sync::Arc;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::script::TransformScript;
+
+/// One step of an import transform, applied in order to every row
+///
+/// Steps are plain data, except `script`, which runs sandboxed Lua (see `TransformScript`);
+/// no step can touch the filesystem or network.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformStep {
+    /// {"rename": {"old_name": "new_name"}}
+    Rename(Map<String, Value>),
+    /// {"drop": ["field", ...]}
+    Drop(Vec<String>),
+    /// {"default": {"field": value}} sets fields that are missing or null
+    Default(Map<String, Value>),
+    /// {"set": {"field": "{first} {last}"}} derives a string from other fields
+    Set(Map<String, Value>),
+    /// {"parse_date": {"field": "%m/%d/%Y"}} rewrites a date string as ISO 8601
+    ParseDate(Map<String, Value>),
+    /// {"cast": {"field": "integer" | "number" | "boolean" | "string"}}
+    Cast(Map<String, Value>),
+    /// {"script": "function transform(row) ... return row end"}
+    Script(String),
+}
+
+/// Row transform loaded from `monk data import --transform <file>`
+///
+/// The file is a JSON array of steps, e.g.
+/// `[{"rename": {"Name": "name"}}, {"parse_date": {"born": "%d/%m/%Y"}}, {"drop": ["notes"]}]`,
+/// or a `.lua` file defining `transform(row)`, run as a single script step.
+#[derive(Debug, Clone, Default)]
+pub struct Transform {
+    steps: Vec<TransformStep>,
+    /// Compiled `script` steps, in step order
+    scripts: Vec<Arc<TransformScript>>,
+}
+
+impl Transform {
+    /// Fails when a script step does not compile
+    pub fn new(steps: Vec<TransformStep>) -> anyhow::Result<Self> {
+        let scripts = steps
+            .iter()
+            .filter_map(|step| match step {
+                TransformStep::Script(source) => Some(TransformScript::compile(source).map(Arc::new)),
+                _ => None,
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { steps, scripts })
+    }
+
+    /// This transform followed by `next`
+    pub fn then(mut self, next: Transform) -> Self {
+        self.steps.extend(next.steps);
+        self.scripts.extend(next.scripts);
+        self
+    }
+
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read transform file '{}': {}", path, e))?;
+        if path.ends_with(".lua") {
+            return Self::new(vec![TransformStep::Script(content)])
+                .map_err(|e| anyhow::anyhow!("{} ('{}')", e, path));
+        }
+        let steps: Vec<TransformStep> = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid transform file '{}': {}", path, e))?;
+        Self::new(steps).map_err(|e| anyhow::anyhow!("{} ('{}')", e, path))
+    }
+
+    /// Apply every step to one row; rows must be JSON objects
+    pub fn apply(&self, row: &Value) -> anyhow::Result<Value> {
+        let mut record = row
+            .as_object()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Expected a JSON object, got {}", row))?;
+
+        let mut scripts = self.scripts.iter();
+        for step in &self.steps {
+            match step {
+                TransformStep::Rename(fields) => {
+                    for (from, to) in fields {
+                        let to = to.as_str().ok_or_else(|| anyhow::anyhow!("rename target for '{}' must be a string", from))?;
+                        if let Some(value) = record.remove(from) {
+                            record.insert(to.to_string(), value);
+                        }
+                    }
+                }
+                TransformStep::Drop(fields) => {
+                    for field in fields {
+                        record.remove(field);
+                    }
+                }
+                TransformStep::Default(fields) => {
+                    for (field, value) in fields {
+                        if record.get(field).is_none_or(Value::is_null) {
+                            record.insert(field.clone(), value.clone());
+                        }
+                    }
+                }
+                TransformStep::Set(fields) => {
+                    for (field, template) in fields {
+                        let template = template.as_str().ok_or_else(|| anyhow::anyhow!("set template for '{}' must be a string", field))?;
+                        let value = render_template(template, &record);
+                        record.insert(field.clone(), Value::String(value));
+                    }
+                }
+                TransformStep::ParseDate(fields) => {
+                    for (field, format) in fields {
+                        let format = format.as_str().ok_or_else(|| anyhow::anyhow!("parse_date format for '{}' must be a string", field))?;
+                        if let Some(Value::String(raw)) = record.get(field) {
+                            let parsed = parse_date(raw, format)
+                                .ok_or_else(|| anyhow::anyhow!("'{}' in field '{}' does not match date format '{}'", raw, field, format))?;
+                            record.insert(field.clone(), Value::String(parsed));
+                        }
+                    }
+                }
+                TransformStep::Cast(fields) => {
+                    for (field, target) in fields {
+                        let target = target.as_str().unwrap_or_default();
+                        if let Some(value) = record.get(field).filter(|v| !v.is_null()) {
+                            let cast = cast(value, target)
+                                .ok_or_else(|| anyhow::anyhow!("Cannot cast {} in field '{}' to {}", value, field, target))?;
+                            record.insert(field.clone(), cast);
+                        }
+                    }
+                }
+                TransformStep::Script(_) => {
+                    let script = scripts.next().ok_or_else(|| anyhow::anyhow!("Transform script was not compiled"))?;
+                    record = script.apply(record)?;
+                }
+            }
+        }
+
+        Ok(Value::Object(record))
+    }
+}
+
+/// Substitute `{field}` placeholders; missing or null fields render as empty strings
+fn render_template(template: &str, record: &Map<String, Value>) -> String {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+        let field = &rest[start + 1..start + end];
+        match record.get(field) {
+            Some(Value::String(s)) => output.push_str(s),
+            Some(Value::Null) | None => {}
+            Some(other) => output.push_str(&other.to_string()),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Dates become YYYY-MM-DD; formats with a time component become RFC 3339 timestamps in UTC
+fn parse_date(raw: &str, format: &str) -> Option<String> {
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(raw.trim(), format) {
+        return Some(datetime.and_utc().to_rfc3339());
+    }
+    NaiveDate::parse_from_str(raw.trim(), format)
+        .ok()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+fn cast(value: &Value, target: &str) -> Option<Value> {
+    let text = match value {
+        Value::String(s) => s.trim().to_string(),
+        other => other.to_string(),
+    };
+    match target {
+        "string" => Some(Value::String(text)),
+        "integer" => text.parse::<i64>().ok().map(Value::from),
+        "number" => text.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number),
+        "boolean" => match text.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "y" | "1" => Some(Value::Bool(true)),
+            "false" | "no" | "n" | "0" | "" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -1,7 +1,12 @@
+pub mod auth;
 pub mod cli;
+pub mod canonical;
+pub mod clock;
+pub mod deadline;
 pub mod database;
 pub mod services;
 pub mod filter;
+pub mod import;
 pub mod config;
 pub mod observer;
 pub mod types;
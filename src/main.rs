@@ -1,16 +1,24 @@
-use axum::{routing::get, Router};
+use axum::{extract::DefaultBodyLimit, routing::get, Router};
 use serde_json::{json, Value};
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use tower_http::{compression::CompressionLayer, limit::RequestBodyLimitLayer, trace::TraceLayer};
 
 mod api;
 mod auth;
+mod canonical;
+mod clock;
+mod deadline;
 mod config;
 mod database;
 mod error;
+mod error_report;
 mod filter;
 mod handlers;
+mod import;
 mod middleware;
 mod observer;
+mod services;
+#[cfg(test)]
+mod testing;
 
 #[tokio::main]
 async fn main() {
@@ -22,6 +30,50 @@ async fn main() {
     tracing::info!("Starting Monk API in {:?} mode", config.environment);
 
     tracing_subscriber::fmt::init();
+    handlers::elevated::root::diagnostics::mark_started();
+
+    // Validate configuration and dependencies before accepting traffic
+    let report = crate::config::self_check::run(config).await;
+    report.log();
+    if report.refuses_start() {
+        eprintln!("Startup self-check failed; fix the errors above or set API_ALLOW_DEGRADED_START=true");
+        std::process::exit(1);
+    }
+
+    // Forward row changes made outside the API (psql, ETL) to in-process subscribers
+    if config.database.enable_change_notifications {
+        tokio::spawn(crate::database::events::run_listeners());
+    }
+
+    // Copy queued record changes into the analytics warehouse
+    if config.database.enable_warehouse_sync {
+        tokio::spawn(crate::database::warehouse::run_sync());
+    }
+
+    // Create suggested indexes during the maintenance window
+    if config.database.index_advisor_auto_apply {
+        tokio::spawn(crate::database::index_advisor::run_maintenance());
+    }
+
+    // Finalize or discard presigned uploads whose URLs have expired unconfirmed
+    if crate::database::s3::S3Bucket::configured().is_some() {
+        tokio::spawn(crate::database::files::run_reconciliation());
+    }
+
+    // Import CSV files dropped on tenants' SFTP/FTP ingestion sources
+    if config.api.ingestion_enabled {
+        tokio::spawn(crate::database::ingestion::run_watcher());
+    }
+
+    // Email users digests of changes to the schemas they follow
+    if config.api.enable_notification_digests {
+        tokio::spawn(crate::database::notifications::run_digests());
+    }
+
+    // Preload metadata and statements of the busiest schemas before traffic ramps up
+    if config.database.enable_cache_warmup {
+        tokio::spawn(crate::database::warmup::run_warmup());
+    }
 
     let app = app();
 
@@ -39,49 +91,111 @@ async fn main() {
 
     println!("🚀 Monk API Rust server listening on http://{}", bind_addr);
 
-    axum::serve(listener, app).await.expect("server");
+    // Peer addresses feed login lockout, which is tracked per user and address
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .await
+        .expect("server");
 }
 
 fn app() -> Router {
-    Router::new()
+    let router = Router::new()
         // Public routes (no auth required)
         .route("/", get(root))
         .route("/health", get(health))
+        .route("/api/version", get(handlers::public::version::get))
+        .route("/api/export/download/:database/:id", get(handlers::public::export::download))
+        .route("/api/inbound/email/:database/:id", axum::routing::post(handlers::public::inbound::email))
+        .route(
+            "/api/notifications/unsubscribe/:database/:user_id",
+            get(handlers::public::notifications::unsubscribe).post(handlers::public::notifications::unsubscribe),
+        )
         // Public auth routes (no auth required)
         .merge(auth_public_routes())
         // Protected API routes (all require auth middleware)
         .nest("/api", protected_api_routes())
+        .nest(&api::format::ApiVersion::V1.prefix(), protected_api_routes())
         // Global middleware
-        .layer(CorsLayer::permissive())
+        .layer(axum::middleware::from_fn(crate::middleware::api_version_middleware))
+        .layer(axum::middleware::from_fn(crate::middleware::payload_too_large_middleware))
+        .layer(axum::middleware::from_fn(crate::middleware::origin_check_middleware))
+        .layer(axum::middleware::from_fn(crate::middleware::deadline_middleware));
+
+    let router = if crate::config::config().api.enable_response_compression {
+        router.layer(CompressionLayer::new())
+    } else {
+        router
+    };
+
+    router
+        .layer(crate::middleware::cors_layer())
         .layer(TraceLayer::new_for_http())
+        .layer(axum::middleware::from_fn(crate::middleware::catch_panic_middleware))
+}
+
+/// Cap request bodies at `api.max_request_size_bytes`
+///
+/// Replaces axum's fixed 2MB extractor limit, so JSON bodies and declared
+/// `Content-Length` are held to the same configured size.
+fn limit_request_bodies(router: Router) -> Router {
+    let limit = crate::config::config().api.max_request_size_bytes;
+    router
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(limit))
 }
 
 /// All protected API routes under /api/* with shared middleware
 fn protected_api_routes() -> Router {
-    Router::new()
+    let routes = Router::new()
         // Merge all protected route groups (without /api prefix since we're nested)
         .merge(data_routes())
+        .merge(bulk_routes())
+        .merge(comment_routes())
+        .merge(tag_routes())
+        .merge(view_routes())
         .merge(find_routes()) 
         .merge(describe_routes())
-        .merge(auth_routes())
+        .merge(feature_routes())
+        .merge(odata_routes())
+        .merge(export_routes())
+        .merge(ingestion_routes())
+        .merge(inbound_routes())
+        .merge(notification_routes())
+        .merge(audit_routes())
+        .merge(root_routes())
+        .merge(auth_routes());
+
+    limit_request_bodies(routes)
+        // Attachments are streamed and held to api.file_max_size_bytes by the upload handler
+        .merge(file_routes())
         // Apply shared middleware stack to ALL /api/* routes
-        .layer(middleware::from_fn(crate::middleware::validate_user_middleware))      // 3rd: Validate user in tenant DB
-        .layer(middleware::from_fn(crate::middleware::validate_tenant_middleware))    // 2nd: Validate tenant + get DB pool
-        .layer(middleware::from_fn(crate::middleware::jwt_auth_middleware))           // 1st: Extract JWT claims
+        .layer(axum::middleware::from_fn(crate::middleware::pipeline_report_middleware)) // 7th: Observer timings for ?debug=pipeline
+        .layer(axum::middleware::from_fn(crate::middleware::wire_format_middleware))     // 6th: Select response wire format
+        .layer(axum::middleware::from_fn(crate::middleware::validate_user_middleware))   // 5th: Validate user in tenant DB
+        .layer(axum::middleware::from_fn(crate::middleware::validate_tenant_middleware)) // 4th: Validate tenant + get DB pool
+        .layer(axum::middleware::from_fn(crate::middleware::tenant_limit_middleware))    // 3rd: Cap in-flight requests per tenant
+        .layer(axum::middleware::from_fn(crate::middleware::rate_limit_middleware))      // 2nd: Limit requests per user
+        .layer(axum::middleware::from_fn(crate::middleware::jwt_auth_middleware))        // 1st: Extract JWT claims
 }
 
 fn auth_public_routes() -> Router {
-    use axum::routing::{delete, post, put};
+    use axum::routing::{delete, get, post, put};
     use handlers::public::auth;
 
-    Router::new()
+    let routes = Router::new()
         // Session management with tenant and user in path
         .route("/auth/login/:tenant/:user", post(auth::session_login))
         .route("/auth/refresh/:tenant/:user", post(auth::session_refresh))
+        // Sign-in with external identity providers
+        .route("/auth/oidc/:provider/start", get(auth::oidc_start))
+        .route("/auth/oidc/:provider/callback", get(auth::oidc_callback))
         // User management
         .route("/auth/register", post(auth::user_register))
         .route("/auth/activate", put(auth::user_activate))
-        .route("/auth/user", delete(auth::user_delete))
+        .route("/auth/user", delete(auth::user_delete));
+
+    limit_request_bodies(routes)
+        // Unauthenticated, so limited per client address
+        .layer(axum::middleware::from_fn(crate::middleware::rate_limit_middleware))
 }
 
 fn auth_routes() -> Router {
@@ -94,6 +208,17 @@ fn auth_routes() -> Router {
         .route("/auth/sudo", post(auth::session_sudo))
         .route("/auth/session/refresh", put(auth::session_refresh))
         .route("/auth/session", delete(auth::session_logout))
+        // TOTP multi-factor authentication
+        .route("/auth/mfa/enroll", post(auth::mfa_enroll))
+        .route("/auth/mfa/verify", post(auth::mfa_verify))
+        .route("/auth/mfa", delete(auth::mfa_disable))
+        // API keys for machine-to-machine access (X-API-Key header)
+        .route("/auth/keys", post(auth::keys_create).get(auth::keys_list))
+        .route("/auth/keys/:id", delete(auth::keys_revoke))
+        // Recently viewed records and favorites of the current user
+        .route("/auth/recent", get(auth::recent_list))
+        .route("/auth/favorites", get(auth::favorites_list).post(auth::favorites_add))
+        .route("/auth/favorites/:schema/:record", delete(auth::favorites_remove))
         // No middleware here - applied at the /api level
 }
 
@@ -121,6 +246,32 @@ fn data_routes() -> Router {
         )
         // Record restore endpoint
         .route("/data/:schema/:id/restore", post(data::record_restore))
+        // Document rendering from stored templates
+        .route("/data/:schema/:id/render", post(data::record_render))
+        // No middleware here - applied at the /api level
+}
+
+fn bulk_routes() -> Router {
+    use axum::routing::post;
+    use handlers::protected::bulk;
+
+    Router::new()
+        // Multi-schema bulk operations - routes without /api prefix since we're nested
+        .route("/bulk", post(bulk::bulk_post))
+        // No middleware here - applied at the /api level
+}
+
+fn file_routes() -> Router {
+    use axum::routing::post;
+    use handlers::protected::file;
+
+    Router::new()
+        // Record attachments - routes without /api prefix since we're nested
+        .route("/file/:schema/:id", get(file::file_list).post(file::file_upload))
+        .route("/file/:schema/:id/:file_id", get(file::file_download).delete(file::file_delete))
+        // Direct-to-S3 uploads for files too large to stream through the API
+        .route("/file/presign", post(file::file_presign))
+        .route("/file/presign/:file_id/confirm", post(file::file_presign_confirm))
         // No middleware here - applied at the /api level
 }
 
@@ -130,16 +281,163 @@ fn find_routes() -> Router {
 
     Router::new()
         // Find/search operations with filters - routes without /api prefix since we're nested
-        .route("/find/:schema", post(find::find_post).delete(find::find_delete))
+        .route("/find/:schema", post(find::find_post).patch(find::find_patch).delete(find::find_delete))
+        .route("/find/:schema/explain", post(find::find_explain))
+        .route("/find/:schema/sample", post(find::find_sample))
+        .route("/find/:schema/aggregate", post(find::find_aggregate))
+        .route("/find/:schema/count", post(find::find_count))
+        // No middleware here - applied at the /api level
+}
+
+fn feature_routes() -> Router {
+    use axum::routing::put;
+    use handlers::protected::features;
+
+    Router::new()
+        // Per-tenant feature flags - routes without /api prefix since we're nested
+        .route("/features", get(features::features_get))
+        .route("/features/:name", put(features::features_put))
+        // No middleware here - applied at the /api level
+}
+
+fn odata_routes() -> Router {
+    use handlers::protected::odata;
+
+    Router::new()
+        // Read-only OData adapter - routes without /api prefix since we're nested
+        .route("/odata", get(odata::odata_service))
+        .route("/odata/$metadata", get(odata::odata_metadata))
+        .route("/odata/:schema", get(odata::odata_entity_get))
         // No middleware here - applied at the /api level
 }
 
+fn export_routes() -> Router {
+    use axum::routing::post;
+    use handlers::protected::export;
+
+    Router::new()
+        // Background export jobs - routes without /api prefix since we're nested
+        .route("/export", get(export::export_list).post(export::export_create))
+        .route("/export/:id", get(export::export_get))
+        .route("/export/:id/resume", post(export::export_resume))
+        // No middleware here - applied at the /api level
+}
+
+fn ingestion_routes() -> Router {
+    use axum::routing::{delete, post};
+    use handlers::protected::ingestion;
+
+    Router::new()
+        // SFTP/FTP ingestion sources - routes without /api prefix since we're nested
+        .route("/ingestion", get(ingestion::ingestion_list).post(ingestion::ingestion_create))
+        .route("/ingestion/:id", delete(ingestion::ingestion_delete))
+        .route("/ingestion/:id/runs", get(ingestion::ingestion_runs))
+        .route("/ingestion/:id/poll", post(ingestion::ingestion_poll))
+        // No middleware here - applied at the /api level
+}
+
+fn comment_routes() -> Router {
+    use axum::routing::patch;
+    use handlers::protected::comments;
+
+    Router::new()
+        // Record comments - routes without /api prefix since we're nested
+        .route("/comments/:schema/:record", get(comments::comment_list).post(comments::comment_create))
+        .route("/comments/:schema/:record/:id", patch(comments::comment_patch).delete(comments::comment_delete))
+        // No middleware here - applied at the /api level
+}
+
+fn tag_routes() -> Router {
+    use axum::routing::{delete, post};
+    use handlers::protected::tags;
+
+    Router::new()
+        // Record tags, rename and merge - routes without /api prefix since we're nested
+        .route("/tags", get(tags::tag_list))
+        .route("/tags/rename", post(tags::tag_rename))
+        .route("/tags/merge", post(tags::tag_merge))
+        .route("/tags/:schema/:record", get(tags::record_tag_list).post(tags::record_tag_attach))
+        .route("/tags/:schema/:record/:tag", delete(tags::record_tag_detach))
+        // No middleware here - applied at the /api level
+}
+
+fn view_routes() -> Router {
+    use axum::routing::put;
+    use handlers::protected::views;
+
+    Router::new()
+        // Saved list views and role defaults - routes without /api prefix since we're nested
+        .route("/views", get(views::view_list).post(views::view_create))
+        .route("/views/default/:schema", get(views::view_default))
+        .route("/views/:id", get(views::view_get).put(views::view_update).delete(views::view_delete))
+        .route("/views/:id/default", put(views::view_set_default))
+        // No middleware here - applied at the /api level
+}
+
+fn notification_routes() -> Router {
+    use axum::routing::delete;
+    use handlers::protected::notifications;
+
+    Router::new()
+        // Digest preferences and subscriptions - routes without /api prefix since we're nested
+        .route("/notifications/preferences", get(notifications::preferences_get).put(notifications::preferences_put))
+        .route("/notifications/digest", get(notifications::digest_preview))
+        .route("/notifications/subscriptions", get(notifications::subscription_list).post(notifications::subscription_create))
+        .route("/notifications/subscriptions/:id", delete(notifications::subscription_delete))
+        // No middleware here - applied at the /api level
+}
+
+fn inbound_routes() -> Router {
+    use axum::routing::delete;
+    use handlers::protected::inbound;
+
+    Router::new()
+        // Inbound email mailboxes - routes without /api prefix since we're nested
+        .route("/inbound/mailboxes", get(inbound::mailbox_list).post(inbound::mailbox_create))
+        .route("/inbound/mailboxes/:id", delete(inbound::mailbox_delete))
+        // No middleware here - applied at the /api level
+}
+
+fn audit_routes() -> Router {
+    use handlers::protected::audit;
+
+    Router::new()
+        // Audit trail - routes without /api prefix since we're nested
+        .route("/audit", get(audit::audit_list))
+        // No middleware here - applied at the /api level
+}
+
+fn root_routes() -> Router {
+    use axum::routing::post;
+    use handlers::elevated::root;
+
+    Router::new()
+        // Tenant lifecycle and cross-tenant administration - routes without /api prefix since we're nested
+        .route("/root/tenant", get(root::tenant_list).post(root::tenant_create))
+        .route(
+            "/root/tenant/:name",
+            get(root::tenant_show)
+                .patch(root::tenant_update)
+                .delete(root::tenant_delete)
+                .put(root::tenant_restore),
+        )
+        .route("/root/tenant/:name/health", get(root::tenant_health))
+        .route("/root/tenant/:name/advisor", get(root::tenant_advisor))
+        .route("/root/tenant/:name/usage", get(root::tenant_usage))
+        .route("/root/tenant/:name/move", post(root::tenant_move))
+        .route("/root/diagnostics", get(root::root_diagnostics))
+        .route("/root/webhooks/:id/test", post(root::webhook_test))
+        // JWT, tenant and user validation are applied at the /api level; root routes add the access check
+        .route_layer(axum::middleware::from_fn(crate::middleware::root_access_middleware))
+}
+
 fn describe_routes() -> Router {
     use axum::routing::{delete, patch, post};
     use handlers::protected::describe;
 
     Router::new()
         // Schema definition management - routes without /api prefix since we're nested
+        .route("/meta/_lint", post(describe::schema_lint))
         .route(
             "/describe/:schema",
             get(describe::schema_get)
@@ -169,13 +467,23 @@ async fn root() -> axum::response::Json<Value> {
             "description": "Lightweight PaaS backend API built with Rust (Axum)",
             "endpoints": {
                 "home": "/ (public)",
-                "public_auth": "/auth/login/:tenant/:user, /auth/refresh/:tenant/:user (public - token acquisition)",
+                "version": "/api/version (public)",
+                "public_auth": "/auth/login/:tenant/:user, /auth/refresh/:tenant/:user, /auth/oidc/:provider/start, /auth/oidc/:provider/callback (public - token acquisition)",
                 "docs": "/docs[/:api] (public)",
-                "auth": "/api/auth/* (protected - user management)",
+                "auth": "/api/auth/* (protected - user management, recent records, favorites)",
                 "describe": "/api/describe/:schema (protected)",
-                "data": "/api/data/:schema[/:record] (protected)",
-                "find": "/api/find/:schema (protected)",
+                "features": "/api/features[/:name] (protected)",
+                "odata": "/api/odata[/$metadata|/:schema] (protected, read-only)",
+                "export": "/api/export[/:id[/resume]] (protected), /api/export/download/:database/:id (signed URL)",
+                "ingestion": "/api/ingestion[/:id[/runs|/poll]] (protected, root)",
+                "inbound": "/api/inbound/mailboxes[/:id] (protected, root), /api/inbound/email/:database/:id (signed URL)",
+                "notifications": "/api/notifications/[preferences|digest|subscriptions[/:id]] (protected), /api/notifications/unsubscribe/:database/:user_id (signed URL)",
+                "data": "/api/data/:schema[/:record[/render]] (protected)",
+                "find": "/api/find/:schema[/explain|/sample|/aggregate|/count] (protected)",
                 "bulk": "/api/bulk (protected)",
+                "comments": "/api/comments/:schema/:record[/:id] (protected)",
+                "tags": "/api/tags[/rename|/merge] (protected, root to rename or merge), /api/tags/:schema/:record[/:tag] (protected)",
+                "views": "/api/views[/:id[/default]] (protected, root to set defaults), /api/views/default/:schema (protected)",
                 "file": "/api/file/* (protected)",
                 "acls": "/api/acls/:schema/:record (protected)",
                 "root": "/api/root/* (restricted, requires sudo or localhost)",
@@ -196,7 +504,22 @@ async fn root() -> axum::response::Json<Value> {
 }
 
 async fn health() -> impl axum::response::IntoResponse {
-    let now = chrono::Utc::now();
+    let now = crate::clock::now();
+    let failed_checks: Vec<_> = crate::config::self_check::report()
+        .map(|report| report.checks.iter().filter(|c| c.severity == crate::config::self_check::Severity::Fatal).collect())
+        .unwrap_or_default();
+
+    // Per-region cluster status; one unreachable cluster degrades only its tenants
+    let clusters = crate::database::manager::DatabaseManager::cluster_health().await;
+    let regions: serde_json::Map<String, serde_json::Value> = crate::database::regions::regions()
+        .into_iter()
+        .map(|region| {
+            let members: Vec<_> = clusters.iter().filter(|c| c.region == region).collect();
+            let status = if members.iter().all(|c| c.ok) { "ok" } else { "degraded" };
+            (region, json!({ "status": status, "clusters": members }))
+        })
+        .collect();
+    let clusters_ok = clusters.iter().all(|c| c.ok);
 
     match crate::database::manager::DatabaseManager::health_check().await {
         Ok(_) => (
@@ -204,9 +527,14 @@ async fn health() -> impl axum::response::IntoResponse {
             axum::response::Json(json!({
                 "success": true,
                 "data": {
-                    "status": "ok",
+                    "status": if failed_checks.is_empty() && clusters_ok { "ok" } else { "degraded" },
                     "timestamp": now,
-                    "database": "ok"
+                    "database": "ok",
+                    "region": crate::database::regions::local_region(),
+                    "regions": regions,
+                    "warmup": crate::database::warmup::status(),
+                    "failed_checks": failed_checks,
+                    "wire_formats": crate::api::format::WireFormat::usage()
                 }
             })),
         ),
@@ -218,7 +546,9 @@ async fn health() -> impl axum::response::IntoResponse {
                 "data": {
                     "status": "degraded",
                     "timestamp": now,
-                    "database_error": e.to_string()
+                    "database_error": e.to_string(),
+                    "region": crate::database::regions::local_region(),
+                    "regions": regions
                 }
             })),
         ),
@@ -0,0 +1,165 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use crate::canonical;
+use crate::api::format::ApiVersion;
+use crate::config;
+use crate::error::ApiError;
+
+/// Middleware that negotiates the API version for /api/* requests
+///
+/// Resolution order: explicit `/api/vN/` path prefix, then the `Accept-Version`
+/// header, then `config.api.default_version`. Versions past their configured sunset
+/// date are rejected with 410 Gone; versions with an upcoming sunset are served with
+/// `Deprecation` and `Sunset` headers. The negotiated `ApiVersion` is inserted into
+/// request extensions and its wire-format adapter is applied to the response.
+pub async fn api_version_middleware(
+    mut request: Request,
+    next: Next,
+) -> Result<Response, impl IntoResponse> {
+    let path = request.uri().path();
+
+    // Version discovery must keep working for clients on retired versions
+    if !path.starts_with("/api/") || path == "/api/version" {
+        return Ok(next.run(request).await);
+    }
+
+    let requested = path_version(path)
+        .or_else(|| {
+            request
+                .headers()
+                .get("accept-version")
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.trim().to_lowercase())
+        })
+        .unwrap_or_else(|| config::config().api.default_version.clone());
+
+    let sunset = sunset_date(&requested);
+    if let Some(date) = sunset {
+        if crate::clock::now().date_naive() > date {
+            let api_error = ApiError::gone(format!(
+                "API version {} was retired on {}; use {}",
+                requested,
+                date,
+                config::config().api.default_version
+            ));
+            let mut response = (
+                StatusCode::from_u16(api_error.status_code()).unwrap(),
+                Json(api_error.to_json()),
+            )
+                .into_response();
+            insert_sunset_headers(&mut response, date);
+            return Err(response);
+        }
+    }
+
+    let version = ApiVersion::parse(&requested).ok_or_else(|| {
+        let api_error = ApiError::bad_request(format!(
+            "Unsupported API version '{}'; supported: {}",
+            requested,
+            ApiVersion::SUPPORTED.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(", ")
+        ));
+        (
+            StatusCode::from_u16(api_error.status_code()).unwrap(),
+            Json(api_error.to_json()),
+        )
+            .into_response()
+    })?;
+
+    request.extensions_mut().insert(version);
+    let mut response = next.run(request).await;
+
+    let adapter = version.adapter();
+    if !adapter.is_passthrough() && is_json(&response) {
+        response = adapt_response(response, |body| adapter.response(body), false).await;
+    }
+
+    response
+        .headers_mut()
+        .insert("api-version", HeaderValue::from_static(version.as_str()));
+    if let Some(date) = sunset {
+        insert_sunset_headers(&mut response, date);
+    }
+
+    Ok(response)
+}
+
+/// Extract "vN" from "/api/vN/..." paths
+fn path_version(path: &str) -> Option<String> {
+    let segment = path.strip_prefix("/api/")?.split('/').next()?;
+    let is_version = segment.len() > 1
+        && segment.starts_with('v')
+        && segment[1..].chars().all(|c| c.is_ascii_digit());
+
+    is_version.then(|| segment.to_string())
+}
+
+fn sunset_date(version: &str) -> Option<NaiveDate> {
+    config::config()
+        .api
+        .sunset_versions
+        .iter()
+        .find(|s| s.version.eq_ignore_ascii_case(version))
+        .and_then(|s| match NaiveDate::parse_from_str(&s.sunset_date, "%Y-%m-%d") {
+            Ok(date) => Some(date),
+            Err(e) => {
+                tracing::warn!("Invalid sunset date '{}' for API {}: {}", s.sunset_date, s.version, e);
+                None
+            }
+        })
+}
+
+fn insert_sunset_headers(response: &mut Response, date: NaiveDate) {
+    let http_date = date.format("%a, %d %b %Y 00:00:00 GMT").to_string();
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(value) = HeaderValue::from_str(&http_date) {
+        headers.insert("sunset", value);
+    }
+}
+
+pub(crate) fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("application/json"))
+        .unwrap_or(false)
+}
+
+/// Buffer a JSON response body, transform it, and rebuild the response
+///
+/// With `canonical` the rebuilt body is written in canonical JSON form.
+pub(crate) async fn adapt_response(
+    response: Response,
+    transform: impl FnOnce(Value) -> Value,
+    canonical: bool,
+) -> Response {
+    let (mut parts, body) = response.into_parts();
+
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for format adapter: {}", e);
+            return ApiError::internal_server_error("Failed to format response").into_response();
+        }
+    };
+
+    match serde_json::from_slice::<Value>(&bytes) {
+        Ok(body) => {
+            let body = transform(body);
+            let adapted = if canonical { canonical::to_vec(&body) } else { serde_json::to_vec(&body).unwrap_or_default() };
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(adapted))
+        }
+        // Not actually JSON; pass through untouched
+        Err(_) => Response::from_parts(parts, Body::from(bytes)),
+    }
+}
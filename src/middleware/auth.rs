@@ -1,15 +1,28 @@
 use axum::{
-    extract::{Request},
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Json, Response},
 };
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::auth::Claims;
-use crate::config;
+use crate::database::{api_keys, mfa};
+use crate::database::audit::AuditActor;
+use crate::database::service::find_tenant_by_name;
+use crate::database::DatabaseManager;
 use crate::error::ApiError;
+use crate::filter::RecordAccess;
+use super::catch_panic::{route_target, RequestContext};
+use super::TenantPool;
+
+/// Header carrying an API key, accepted in place of a Bearer JWT
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Route groups reachable with an API key; the segment after the group is the schema
+const API_KEY_ROUTES: &[&str] = &["data", "find", "file", "odata"];
 
 /// Authenticated user context extracted from JWT
 #[derive(Clone, Debug)]
@@ -19,6 +32,12 @@ pub struct AuthUser {
     pub database: String,
     pub access: String,
     pub user_id: Uuid,
+    /// Sudo reason of an elevated token
+    pub elevation: Option<String>,
+    /// Whether the session verified a TOTP code
+    pub mfa: bool,
+    /// Client IP address of the request
+    pub address: Option<String>,
 }
 
 impl From<Claims> for AuthUser {
@@ -29,43 +48,160 @@ impl From<Claims> for AuthUser {
             database: claims.database,
             access: claims.access,
             user_id: claims.user_id,
+            elevation: claims.elevation,
+            mfa: claims.mfa,
+            address: None,
         }
     }
 }
 
+impl AuthUser {
+    /// Ids checked against records' access_* lists on reads; None for root, which sees every record
+    ///
+    /// Users do not belong to groups yet, so this is the user's own id.
+    pub fn record_access(&self) -> Option<RecordAccess> {
+        if self.access == "root" {
+            None
+        } else {
+            Some(RecordAccess::new(vec![self.user_id]))
+        }
+    }
+
+    /// Actor recorded in the audit log for writes made by this request
+    pub fn audit_actor(&self) -> Option<AuditActor> {
+        Some(AuditActor { user_id: self.user_id, user: self.user.clone(), address: self.address.clone() })
+    }
+}
+
+/// Middleware for /api/root/* routes: only elevated root tokens (from POST /api/auth/sudo) pass
+///
+/// A login token of a root user is not enough; the token must carry the sudo elevation claim,
+/// and be MFA-verified when the tenant enforces MFA. Runs inside the /api stack, after
+/// `jwt_auth_middleware` has inserted the `AuthUser` and tenant validation the `TenantPool`.
+pub async fn root_access_middleware(
+    request: Request,
+    next: Next,
+) -> Result<Response, impl IntoResponse> {
+    let forbidden = |message: &str| {
+        let api_error = ApiError::forbidden(message);
+        (
+            StatusCode::from_u16(api_error.status_code()).unwrap(),
+            Json(api_error.to_json()),
+        )
+    };
+
+    let Some(user) = request.extensions().get::<AuthUser>().filter(|user| user.access == "root" && user.elevation.is_some()) else {
+        return Err(forbidden("Root access required; elevate with POST /api/auth/sudo"));
+    };
+    if !user.mfa {
+        let required = match request.extensions().get::<TenantPool>() {
+            Some(TenantPool(pool)) => mfa::is_required(pool).await.unwrap_or(true),
+            None => true,
+        };
+        if required {
+            return Err(forbidden("This tenant requires an MFA-verified sudo session"));
+        }
+    }
+    Ok(next.run(request).await)
+}
+
 /// JWT authentication middleware that validates tokens and extracts user context
+///
+/// Requests without an Authorization header may instead authenticate with an API key in
+/// `X-API-Key`; such requests are limited to the schema routes and operations the key's
+/// scopes grant.
 pub async fn jwt_auth_middleware(
     headers: HeaderMap,
     mut request: Request,
     next: Next,
 ) -> Result<Response, impl IntoResponse> {
-    // Extract JWT from Authorization header
-    let token = extract_jwt_from_headers(&headers)
-        .map_err(|msg| {
-            let api_error = ApiError::unauthorized(msg);
-            (
-                StatusCode::from_u16(api_error.status_code()).unwrap(),
-                Json(api_error.to_json()),
-            )
-        })?;
-
-    // Validate and decode JWT
-    let claims = validate_jwt(&token)
-        .map_err(|msg| {
-            let api_error = ApiError::unauthorized(msg);
-            (
-                StatusCode::from_u16(api_error.status_code()).unwrap(),
-                Json(api_error.to_json()),
-            )
-        })?;
-
-    // Convert claims to AuthUser and inject into request
-    let auth_user = AuthUser::from(claims);
+    let api_key = headers.get(API_KEY_HEADER).filter(|_| {
+        headers.get("authorization").is_none() && headers.get("Authorization").is_none()
+    });
+    let mut auth_user = match api_key {
+        Some(key) => api_key_user(key.to_str().unwrap_or_default(), request.method(), request.uri().path())
+            .await
+            .map_err(|api_error| {
+                (
+                    StatusCode::from_u16(api_error.status_code()).unwrap(),
+                    Json(api_error.to_json()),
+                )
+            })?,
+        None => {
+            // Extract JWT from Authorization header
+            let token = extract_jwt_from_headers(&headers)
+                .map_err(|msg| {
+                    let api_error = ApiError::unauthorized(msg);
+                    (
+                        StatusCode::from_u16(api_error.status_code()).unwrap(),
+                        Json(api_error.to_json()),
+                    )
+                })?;
+
+            // Validate and decode JWT
+            let claims = crate::auth::validate_jwt(&token, false)
+                .map_err(|e| {
+                    let api_error = ApiError::unauthorized(e.to_string());
+                    (
+                        StatusCode::from_u16(api_error.status_code()).unwrap(),
+                        Json(api_error.to_json()),
+                    )
+                })?;
+
+            AuthUser::from(claims)
+        }
+    };
+
+    auth_user.address = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| peer.ip().to_string());
+
+    // Inject the authenticated user into the request
+    if let Some(context) = request.extensions().get::<Arc<RequestContext>>() {
+        context.set_tenant(&auth_user.tenant);
+    }
     request.extensions_mut().insert(auth_user);
 
     Ok(next.run(request).await)
 }
 
+/// Resolve an API key to its owner, checking the key's scopes against the request
+async fn api_key_user(key: &str, method: &Method, path: &str) -> Result<AuthUser, ApiError> {
+    let invalid = || ApiError::unauthorized("Invalid API key");
+    let (tenant_name, _) = api_keys::parse_key(key).ok_or_else(invalid)?;
+
+    let tenant = find_tenant_by_name(tenant_name)
+        .await?
+        .filter(|tenant| tenant.trashed_at.is_none() && tenant.deleted_at.is_none())
+        .ok_or_else(invalid)?;
+    let pool = DatabaseManager::tenant_pool(&tenant.database).await?;
+    let (api_key, owner) = api_keys::authenticate(&pool, key).await?.ok_or_else(invalid)?;
+
+    let (group, schema) = route_target(path)
+        .filter(|(group, _)| API_KEY_ROUTES.contains(group))
+        .ok_or_else(|| ApiError::forbidden("API keys can only access schema data routes"))?;
+    let operation = api_keys::route_operation(group, method.as_str())
+        .ok_or_else(|| ApiError::forbidden("Operation not available to API keys"))?;
+    if !api_key.allows(schema, operation) {
+        return Err(ApiError::forbidden(format!(
+            "API key '{}' has no {} scope on schema '{}'",
+            api_key.name, operation, schema
+        )));
+    }
+
+    Ok(AuthUser {
+        tenant: tenant.name,
+        user: owner.auth,
+        database: tenant.database,
+        access: owner.access,
+        user_id: api_key.user_id,
+        elevation: None,
+        mfa: false,
+        address: None,
+    })
+}
+
 /// Extract JWT token from Authorization header
 fn extract_jwt_from_headers(headers: &HeaderMap) -> Result<String, String> {
     let auth_header = headers
@@ -86,20 +222,3 @@ fn extract_jwt_from_headers(headers: &HeaderMap) -> Result<String, String> {
         Err("Authorization header must use Bearer token format".to_string())
     }
 }
-
-/// Validate JWT token and extract claims
-fn validate_jwt(token: &str) -> Result<Claims, String> {
-    let secret = &config::config().security.jwt_secret;
-    
-    if secret.is_empty() {
-        return Err("JWT secret not configured".to_string());
-    }
-
-    let decoding_key = DecodingKey::from_secret(secret.as_bytes());
-    let validation = Validation::default();
-
-    let token_data = decode::<Claims>(token, &decoding_key, &validation)
-        .map_err(|e| format!("Invalid JWT token: {}", e))?;
-
-    Ok(token_data.claims)
-}
\ No newline at end of file
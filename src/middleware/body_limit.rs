@@ -0,0 +1,32 @@
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+
+use crate::error::ApiError;
+
+/// Middleware that gives 413 responses the standard error envelope
+///
+/// Oversized bodies are rejected by `RequestBodyLimitLayer` (declared `Content-Length`)
+/// or by the body extractors (streamed bodies), both of which answer with plain text.
+/// Responses that are already JSON come from handlers and are passed through.
+pub async fn payload_too_large_middleware(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE {
+        return response;
+    }
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if is_json {
+        return response;
+    }
+
+    let limit = crate::config::config().api.max_request_size_bytes;
+    let api_error = ApiError::payload_too_large(format!("Request body exceeds the limit of {} bytes", limit));
+    (StatusCode::PAYLOAD_TOO_LARGE, Json(api_error.to_json())).into_response()
+}
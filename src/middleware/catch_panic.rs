@@ -0,0 +1,146 @@
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use futures::FutureExt;
+use serde_json::Value;
+
+use crate::error::ApiError;
+use crate::error_report::{self, ErrorEvent, ErrorKind};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Largest 5xx body read back to extract the error message for a report
+const MAX_REPORTED_BODY_BYTES: usize = 64 * 1024;
+
+/// Route groups whose second path segment is a schema name
+const SCHEMA_ROUTES: &[&str] = &["data", "describe", "file", "find", "odata"];
+
+/// Per-request context shared with inner middleware, used when reporting errors
+///
+/// The tenant is only known once `jwt_auth_middleware` has run, so it is filled in later.
+#[derive(Debug)]
+pub struct RequestContext {
+    pub id: String,
+    tenant: Mutex<Option<String>>,
+}
+
+impl RequestContext {
+    pub fn set_tenant(&self, tenant: &str) {
+        *self.tenant.lock().unwrap_or_else(|e| e.into_inner()) = Some(tenant.to_string());
+    }
+
+    fn tenant(&self) -> Option<String> {
+        self.tenant.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Outermost middleware: assigns the request id and turns panics into 500 responses
+///
+/// The id is taken from an incoming `X-Request-Id` header when present, otherwise generated,
+/// and is echoed on every response. Panics and 5xx responses are passed to the configured
+/// error reporter with the method, path, tenant and schema of the request.
+pub async fn catch_panic_middleware(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let context = Arc::new(RequestContext { id, tenant: Mutex::new(None) });
+    request.extensions_mut().insert(context.clone());
+
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+
+    let mut response = match AssertUnwindSafe(next.run(request)).catch_unwind().await {
+        Ok(response) if response.status().is_server_error() => {
+            let (response, message) = error_message(response).await;
+            error_report::report(event(&context, ErrorKind::ServerError, response.status(), message, &method, &path));
+            response
+        }
+        Ok(response) => response,
+        Err(panic) => {
+            let message = panic_message(panic.as_ref());
+            tracing::error!(request_id = %context.id, "Panic while serving {} {}: {}", method, path, message);
+            error_report::report(event(&context, ErrorKind::Panic, StatusCode::INTERNAL_SERVER_ERROR, message, &method, &path));
+
+            let api_error = ApiError::internal_server_error("Internal server error");
+            let mut body = api_error.to_json();
+            body["request_id"] = Value::String(context.id.clone());
+            (StatusCode::from_u16(api_error.status_code()).unwrap(), Json(body)).into_response()
+        }
+    };
+
+    if let Ok(value) = HeaderValue::from_str(&context.id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+fn event(context: &RequestContext, kind: ErrorKind, status: StatusCode, message: String, method: &str, path: &str) -> ErrorEvent {
+    ErrorEvent {
+        request_id: context.id.clone(),
+        kind,
+        status: status.as_u16(),
+        message,
+        method: method.to_string(),
+        path: path.to_string(),
+        tenant: context.tenant(),
+        schema: schema_from_path(path),
+        timestamp: crate::clock::now(),
+    }
+}
+
+/// The `message` of an ApiError body, or the status reason; the body is put back unchanged
+async fn error_message(response: Response) -> (Response, String) {
+    let (parts, body) = response.into_parts();
+    let fallback = parts.status.canonical_reason().unwrap_or("Server error").to_string();
+    match axum::body::to_bytes(body, MAX_REPORTED_BODY_BYTES).await {
+        Ok(bytes) => {
+            let message = serde_json::from_slice::<Value>(&bytes)
+                .ok()
+                .and_then(|body| body.get("message").and_then(Value::as_str).map(str::to_string))
+                .unwrap_or(fallback);
+            (Response::from_parts(parts, Body::from(bytes)), message)
+        }
+        // Too large or already failed: the original body is gone, so answer with the status alone
+        Err(_) => (Response::from_parts(parts, Body::empty()), fallback),
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "panic with a non-string payload".to_string()
+    }
+}
+
+/// Schema named in a path such as /api/data/users/... or /api/v1/find/orders
+fn schema_from_path(path: &str) -> Option<String> {
+    let (group, schema) = route_target(path)?;
+    (SCHEMA_ROUTES.contains(&group) && !schema.starts_with('$')).then(|| schema.to_string())
+}
+
+/// Route group and the segment after it, skipping a leading /api and version segment
+pub(crate) fn route_target(path: &str) -> Option<(&str, &str)> {
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty()).peekable();
+    if segments.peek() == Some(&"api") {
+        segments.next();
+    }
+    if segments.peek().is_some_and(|segment| segment.len() > 1 && segment.starts_with('v') && segment[1..].chars().all(|c| c.is_ascii_digit())) {
+        segments.next();
+    }
+    Some((segments.next()?, segments.next()?))
+}
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use tower_http::cors::{AllowHeaders, AllowOrigin, CorsLayer};
+
+use crate::config::{self, Environment, SecurityConfig};
+use crate::error::ApiError;
+
+/// Response headers browsers may read from cross-origin responses
+const EXPOSED_HEADERS: &[&str] = &[
+    "x-request-id",
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-reset",
+    "retry-after",
+    "x-monk-region",
+];
+
+fn is_development() -> bool {
+    matches!(config::config().environment, Environment::Development)
+}
+
+/// Whether `origin` may make cross-origin requests under `security.cors_origins`
+fn origin_allowed(security: &SecurityConfig, origin: &str) -> bool {
+    security
+        .cors_origins
+        .iter()
+        .any(|allowed| allowed == "*" || allowed.trim_end_matches('/').eq_ignore_ascii_case(origin))
+}
+
+/// CORS policy built from `SecurityConfig`
+///
+/// Development allows any origin, method and header. Elsewhere only `cors_origins` (or
+/// any origin when it lists `*`) and `cors_methods` are allowed, with credentials and
+/// preflight caching as configured. With CORS disabled no CORS headers are sent, so
+/// browsers refuse every cross-origin call.
+pub fn cors_layer() -> CorsLayer {
+    let security = &config::config().security;
+    if !security.enable_cors {
+        return CorsLayer::new();
+    }
+    if is_development() {
+        return CorsLayer::permissive();
+    }
+
+    let origins = if security.cors_origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            security
+                .cors_origins
+                .iter()
+                .filter_map(|o| HeaderValue::from_str(o.trim_end_matches('/')).ok()),
+        )
+    };
+    let methods: Vec<Method> = security.cors_methods.iter().filter_map(|m| m.parse().ok()).collect();
+
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        // Mirroring instead of `*`, which browsers ignore on credentialed requests
+        .allow_headers(AllowHeaders::mirror_request())
+        .expose_headers(EXPOSED_HEADERS.iter().map(|h| HeaderName::from_static(h)).collect::<Vec<_>>())
+        .allow_credentials(security.cors_allow_credentials)
+        .max_age(Duration::from_secs(security.cors_max_age_secs))
+}
+
+/// Middleware that refuses requests from origins outside `security.cors_origins`
+///
+/// The CORS layer only withholds headers, leaving the browser to drop the response after
+/// the request has run; outside development, disallowed origins get 403 before any
+/// handler runs. Requests without an `Origin` header, or from the API's own host, pass.
+pub async fn origin_check_middleware(request: Request, next: Next) -> Response {
+    let security = &config::config().security;
+    if !security.enable_cors || is_development() {
+        return next.run(request).await;
+    }
+
+    let Some(origin) = request.headers().get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
+        return next.run(request).await;
+    };
+    let same_host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|host| origin.split_once("://").is_some_and(|(_, authority)| authority.eq_ignore_ascii_case(host)));
+    if same_host || origin_allowed(security, origin) {
+        return next.run(request).await;
+    }
+
+    tracing::warn!("Rejected {} {} from disallowed origin {}", request.method(), request.uri().path(), origin);
+    let api_error = ApiError::forbidden(format!("Origin '{}' is not allowed", origin));
+    (StatusCode::from_u16(api_error.status_code()).unwrap(), Json(api_error.to_json())).into_response()
+}
@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::DateTime;
+use tokio::time::Instant;
+
+use crate::error::ApiError;
+
+/// Absolute deadline: an RFC 3339 time or Unix seconds
+pub const DEADLINE_HEADER: &str = "x-request-deadline";
+/// Relative budget in milliseconds
+pub const TIMEOUT_HEADER: &str = "x-request-timeout";
+
+/// Middleware that bounds a request by the client's deadline
+///
+/// The earlier of the two headers wins, capped by `api.max_request_timeout_ms`, which is
+/// also the budget of requests that send neither. The rest of the request runs inside
+/// `deadline::scope`, so the pipeline and SQL see the deadline, and is abandoned with 504
+/// once it passes. A deadline that has already passed on arrival is answered at once.
+pub async fn deadline_middleware(request: Request, next: Next) -> Response {
+    let api = &crate::config::config().api;
+    if !api.enable_request_deadlines {
+        return next.run(request).await;
+    }
+
+    let budget = match client_budget(request.headers()) {
+        Ok(budget) => budget,
+        Err(message) => return error_response(ApiError::bad_request(message)),
+    };
+    let limit = (api.max_request_timeout_ms > 0).then(|| Duration::from_millis(api.max_request_timeout_ms));
+    let Some(budget) = budget.into_iter().chain(limit).min() else {
+        return next.run(request).await;
+    };
+    if budget.is_zero() {
+        return error_response(ApiError::gateway_timeout("Request deadline has already passed"));
+    }
+
+    let deadline = Instant::now() + budget;
+    match tokio::time::timeout_at(deadline, crate::deadline::scope(deadline, next.run(request))).await {
+        Ok(response) => response,
+        Err(_) => {
+            tracing::warn!("Request abandoned at its deadline after {:?}", budget);
+            error_response(ApiError::gateway_timeout("Request deadline exceeded"))
+        }
+    }
+}
+
+/// Time the client allows, from the earlier of its deadline headers
+fn client_budget(headers: &HeaderMap) -> Result<Option<Duration>, String> {
+    let header = |name: &str| headers.get(name).map(|v| v.to_str().map(str::trim).unwrap_or_default());
+
+    let timeout = header(TIMEOUT_HEADER)
+        .map(|v| v.parse::<u64>().map(Duration::from_millis).map_err(|_| format!("{} must be milliseconds, got '{}'", TIMEOUT_HEADER, v)))
+        .transpose()?;
+
+    let deadline = header(DEADLINE_HEADER)
+        .map(|v| {
+            let at = DateTime::parse_from_rfc3339(v)
+                .map(|at| at.timestamp_millis())
+                .or_else(|_| v.parse::<f64>().map(|secs| (secs * 1000.0) as i64))
+                .map_err(|_| format!("{} must be an RFC 3339 time or Unix seconds, got '{}'", DEADLINE_HEADER, v))?;
+            let left = at - crate::clock::now().timestamp_millis();
+            Ok::<_, String>(Duration::from_millis(left.max(0) as u64))
+        })
+        .transpose()?;
+
+    Ok(timeout.into_iter().chain(deadline).min())
+}
+
+fn error_response(api_error: ApiError) -> Response {
+    (StatusCode::from_u16(api_error.status_code()).unwrap(), Json(api_error.to_json())).into_response()
+}
@@ -1,9 +1,28 @@
+pub mod api_version;
 pub mod auth;
+pub mod body_limit;
+pub mod catch_panic;
+pub mod cors;
+pub mod deadline;
+pub mod pipeline_report;
+pub mod rate_limit;
+pub mod region_routing;
 pub mod response;
+pub mod tenant_limit;
 pub mod validate_tenant;
 pub mod validate_user;
+pub mod wire_format;
 
-pub use auth::{jwt_auth_middleware, AuthUser};
+pub use api_version::api_version_middleware;
+pub use auth::{jwt_auth_middleware, root_access_middleware, AuthUser};
+pub use body_limit::payload_too_large_middleware;
+pub use catch_panic::catch_panic_middleware;
+pub use cors::{cors_layer, origin_check_middleware};
+pub use deadline::deadline_middleware;
+pub use pipeline_report::pipeline_report_middleware;
+pub use rate_limit::rate_limit_middleware;
 pub use response::{ApiResponse, ApiResult, ApiSuccess, IntoApiResponse};
-pub use validate_tenant::{validate_tenant_middleware, ValidatedTenant, TenantPool};
-pub use validate_user::{validate_user_middleware, ValidatedUser};
\ No newline at end of file
+pub use tenant_limit::tenant_limit_middleware;
+pub use validate_tenant::{validate_tenant_middleware, ValidatedTenant, TenantPool, TenantDdlPool};
+pub use validate_user::{validate_user_middleware, ValidatedUser};
+pub use wire_format::wire_format_middleware;
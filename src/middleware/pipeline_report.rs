@@ -0,0 +1,41 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+use serde_json::{Map, Value};
+
+use super::api_version::{adapt_response, is_json};
+use crate::config::{self, Environment};
+use crate::observer::report;
+
+/// Middleware that adds observer timings to the response for `?debug=pipeline`
+///
+/// Development only: elsewhere the flag is ignored. The request runs inside
+/// `report::collect` and every pipeline it ran is listed, with the time spent in each
+/// observer, under `meta.pipeline` of the JSON response.
+pub async fn pipeline_report_middleware(request: Request, next: Next) -> Response {
+    if !matches!(config::config().environment, Environment::Development) || !requested(request.uri().query()) {
+        return next.run(request).await;
+    }
+
+    let (response, reports) = report::collect(next.run(request)).await;
+    if !is_json(&response) {
+        return response;
+    }
+    adapt_response(response, |body| with_report(body, reports), false).await
+}
+
+/// Whether the query string has `debug=pipeline`
+fn requested(query: Option<&str>) -> bool {
+    query
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .any(|(key, value)| key == "debug" && value.split(',').any(|v| v == "pipeline"))
+}
+
+fn with_report(body: Value, reports: Vec<Value>) -> Value {
+    let Value::Object(mut envelope) = body else { return body };
+    let meta = envelope.entry("meta").or_insert_with(|| Value::Object(Map::new()));
+    if let Value::Object(meta) = meta {
+        meta.insert("pipeline".to_string(), Value::Array(reports));
+    }
+    Value::Object(envelope)
+}
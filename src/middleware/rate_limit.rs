@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use once_cell::sync::Lazy;
+
+use crate::config;
+use crate::error::ApiError;
+use super::auth::AuthUser;
+
+/// Windows are pruned once this many keys are tracked
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Request counts of one client in the current and previous fixed windows
+struct Window {
+    started: Instant,
+    current: u32,
+    previous: u32,
+}
+
+impl Window {
+    /// Roll the window forward to `now`, dropping counts older than the previous window
+    fn advance(&mut self, now: Instant, length: Duration) {
+        let elapsed = now.duration_since(self.started);
+        if elapsed >= length * 2 {
+            self.previous = 0;
+            self.current = 0;
+            self.started = now;
+        } else if elapsed >= length {
+            self.previous = self.current;
+            self.current = 0;
+            self.started += length;
+        }
+    }
+
+    /// Requests in the sliding window ending at `now`: the current window plus the part
+    /// of the previous one that still overlaps it
+    fn weighted_count(&self, now: Instant, length: Duration) -> f64 {
+        let overlap = 1.0 - now.duration_since(self.started).as_secs_f64() / length.as_secs_f64();
+        self.previous as f64 * overlap.max(0.0) + self.current as f64
+    }
+}
+
+static WINDOWS: Lazy<Mutex<HashMap<String, Window>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Outcome of counting one request against its client's limit
+struct Decision {
+    allowed: bool,
+    limit: u32,
+    remaining: u32,
+    reset_secs: u64,
+}
+
+fn check(key: &str, limit: u32, length: Duration) -> Decision {
+    let now = Instant::now();
+    let mut windows = WINDOWS.lock().unwrap_or_else(|e| e.into_inner());
+    if windows.len() >= PRUNE_THRESHOLD {
+        windows.retain(|_, window| now.duration_since(window.started) < length * 2);
+    }
+
+    let window = windows
+        .entry(key.to_string())
+        .or_insert_with(|| Window { started: now, current: 0, previous: 0 });
+    window.advance(now, length);
+
+    let used = window.weighted_count(now, length);
+    let allowed = used + 1.0 <= limit as f64;
+    if allowed {
+        window.current += 1;
+    }
+    let used = if allowed { used + 1.0 } else { used };
+    let reset = (window.started + length).saturating_duration_since(now);
+
+    Decision {
+        allowed,
+        limit,
+        remaining: (limit as f64 - used).floor().max(0.0) as u32,
+        reset_secs: reset.as_secs_f64().ceil() as u64,
+    }
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert("x-ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("x-ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("x-ratelimit-reset", HeaderValue::from(decision.reset_secs));
+}
+
+/// Middleware that limits each client to `rate_limit_requests` per `rate_limit_window_secs`
+///
+/// Authenticated requests are counted per tenant and user, so it runs after JWT
+/// authentication on protected routes; other requests are counted per client address.
+/// The window slides: requests from the previous fixed window count in proportion to
+/// how much of it still overlaps. Every response carries `X-RateLimit-Limit`,
+/// `X-RateLimit-Remaining` and `X-RateLimit-Reset` (seconds until the current window
+/// ends); requests over the limit get 429 with `Retry-After`.
+pub async fn rate_limit_middleware(request: Request, next: Next) -> Response {
+    let api = &config::config().api;
+    if !api.enable_rate_limiting {
+        return next.run(request).await;
+    }
+
+    let key = match request.extensions().get::<AuthUser>() {
+        Some(user) => format!("user:{}:{}", user.tenant, user.user_id),
+        None => match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(peer)) => format!("ip:{}", peer.ip()),
+            None => return next.run(request).await,
+        },
+    };
+
+    let decision = check(&key, api.rate_limit_requests, Duration::from_secs(api.rate_limit_window_secs.max(1)));
+    if !decision.allowed {
+        tracing::warn!("Rate limit exceeded for {}", key);
+        let api_error = ApiError::too_many_requests(format!(
+            "Rate limit of {} requests per {}s exceeded; retry in {}s",
+            decision.limit, api.rate_limit_window_secs, decision.reset_secs
+        ));
+        let mut response = (
+            StatusCode::from_u16(api_error.status_code()).unwrap(),
+            Json(api_error.to_json()),
+        )
+            .into_response();
+        set_headers(response.headers_mut(), &decision);
+        response
+            .headers_mut()
+            .insert(axum::http::header::RETRY_AFTER, HeaderValue::from(decision.reset_secs.max(1)));
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    set_headers(response.headers_mut(), &decision);
+    response
+}
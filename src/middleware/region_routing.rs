@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Request},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use once_cell::sync::Lazy;
+
+use crate::auth::signed_url;
+use crate::database::{api_keys, regions};
+use crate::error::ApiError;
+use crate::middleware::catch_panic::route_target;
+
+/// Set on forwarded requests to `<region>.<expires>.<signature>` of the region they were
+/// forwarded from; unsigned or expired values are ignored
+pub const FORWARDED_FROM: &str = "x-monk-forwarded-from";
+/// `primary` makes a read skip replicas
+pub const CONSISTENCY: &str = "x-monk-consistency";
+/// Region of the instance that served the request
+pub const SERVED_BY: &str = "x-monk-region";
+/// Unix time until which the client's reads go to the primary
+pub const STICKY_COOKIE: &str = "monk_sticky_until";
+
+/// Hop-by-hop headers that are not copied between the client and a proxied instance
+const HOP_HEADERS: &[&str] = &["connection", "keep-alive", "transfer-encoding", "upgrade", "host", "content-length"];
+
+/// Lifetime of a forwarding signature, the forwarding client's timeout
+const FORWARD_TTL_SECS: i64 = 60;
+
+static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("HTTP client for write forwarding")
+});
+
+/// Where a tenant request is served
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    /// On the tenant's primary database
+    Primary,
+    /// On this region's replica of the tenant's cluster, or the primary if there is none
+    Replica,
+    /// By the API instances of the tenant's region at this base URL
+    Forward(String),
+}
+
+/// Requests that may write; everything else can be answered from a replica
+///
+/// `path` is the request path below /api. Routes are classified like API key scopes, so
+/// POST /find/:schema and its explain, sample, aggregate and count routes only read.
+/// Routes outside the schema groups, /bulk among them, count as writes for any method
+/// but GET, HEAD and OPTIONS.
+pub fn is_write(method: &Method, path: &str) -> bool {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return false;
+    }
+    match route_target(path) {
+        Some((group, _)) => api_keys::route_operation(group, method.as_str()) != Some("read"),
+        None => true,
+    }
+}
+
+/// Decide where to serve a request for a tenant whose primary is in `tenant_region`
+///
+/// Requests for tenants of this region, and writes that were already forwarded once, are
+/// served on the primary. Other writes are forwarded when forwarding is on and the
+/// tenant's region has an endpoint; otherwise they write to the remote primary directly.
+/// Reads use a replica unless the client asks for primary consistency or wrote recently.
+pub fn route(method: &Method, path: &str, headers: &HeaderMap, tenant_region: &str) -> Route {
+    if tenant_region == regions::local_region() {
+        return Route::Primary;
+    }
+
+    if is_write(method, path) {
+        if crate::config::config().api.write_forwarding == "off" {
+            return Route::Primary;
+        }
+        if let Some(from) = forwarded_from(headers) {
+            tracing::warn!("Request forwarded from {} is not in the tenant's region {}; writing directly", from, tenant_region);
+            return Route::Primary;
+        }
+        return regions::endpoint(tenant_region).map_or(Route::Primary, Route::Forward);
+    }
+
+    let wants_primary = headers
+        .get(CONSISTENCY)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("primary"));
+    if wants_primary || sticky_until(headers).is_some_and(|until| until > crate::clock::now().timestamp()) {
+        Route::Primary
+    } else {
+        Route::Replica
+    }
+}
+
+/// Send a write to the instances at `base_url` as configured by `api.write_forwarding`
+///
+/// `redirect` answers 307, which clients follow with the same method and body; `proxy`
+/// relays the request and returns the primary's response, cookies included.
+pub async fn forward(request: Request, base_url: &str) -> Response {
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.clone())
+        .unwrap_or_else(|| request.uri().clone());
+    let target = format!("{}{}", base_url, path.path_and_query().map_or("/", |pq| pq.as_str()));
+
+    if crate::config::config().api.write_forwarding != "proxy" {
+        return match HeaderValue::from_str(&target) {
+            Ok(location) => (StatusCode::TEMPORARY_REDIRECT, [(header::LOCATION, location)]).into_response(),
+            Err(_) => error_response(ApiError::internal_server_error("Invalid write forwarding endpoint")),
+        };
+    }
+
+    let (parts, body) = request.into_parts();
+    let limit = crate::config::config().api.max_request_size_bytes;
+    let body = match axum::body::to_bytes(body, limit).await {
+        Ok(body) => body,
+        Err(_) => return error_response(ApiError::payload_too_large(format!("Request body exceeds the limit of {} bytes", limit))),
+    };
+
+    let mut headers = parts.headers;
+    strip_hop_headers(&mut headers);
+    if let Ok(hop) = HeaderValue::from_str(&sign_hop(&regions::local_region(), crate::clock::now().timestamp() + FORWARD_TTL_SECS)) {
+        headers.insert(FORWARDED_FROM, hop);
+    }
+
+    let forwarded = CLIENT.request(parts.method, &target).headers(headers).body(body).send().await;
+    let forwarded = match forwarded {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::error!("Write forwarding to {} failed: {}", target, e);
+            return error_response(ApiError::bad_gateway("The tenant's primary region did not respond"));
+        }
+    };
+
+    let status = forwarded.status();
+    let mut headers = forwarded.headers().clone();
+    strip_hop_headers(&mut headers);
+    match forwarded.bytes().await {
+        Ok(bytes) => {
+            let mut response = Response::new(Body::from(bytes));
+            *response.status_mut() = status;
+            *response.headers_mut() = headers;
+            response
+        }
+        Err(e) => {
+            tracing::error!("Reading forwarded response from {} failed: {}", target, e);
+            error_response(ApiError::bad_gateway("The tenant's primary region did not respond"))
+        }
+    }
+}
+
+/// Tag a locally served response with this region, and pin the client's reads to the
+/// primary for `api.sticky_read_secs` after a successful write
+pub fn finish(response: &mut Response, wrote: bool) {
+    if let Ok(region) = HeaderValue::from_str(&regions::local_region()) {
+        response.headers_mut().insert(HeaderName::from_static(SERVED_BY), region);
+    }
+
+    let window = crate::config::config().api.sticky_read_secs;
+    if !wrote || window == 0 || !response.status().is_success() {
+        return;
+    }
+    let until = crate::clock::now().timestamp() + window as i64;
+    let cookie = format!("{}={}; Max-Age={}; Path=/; HttpOnly; SameSite=Lax", STICKY_COOKIE, until, window);
+    if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+}
+
+/// Expiry of the client's sticky-read cookie, if it sent one
+fn sticky_until(headers: &HeaderMap) -> Option<i64> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == STICKY_COOKIE)
+        .and_then(|(_, value)| value.parse().ok())
+}
+
+fn sign_hop(region: &str, expires: i64) -> String {
+    let signature = signed_url::sign_until("forwarded", &[region], expires);
+    format!("{}.{}.{}", region, expires, signature)
+}
+
+/// Region a request was forwarded from, when another instance signed the hop; clients
+/// cannot set the header to skip forwarding
+fn forwarded_from(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(FORWARDED_FROM)?.to_str().ok()?;
+    let mut parts = value.rsplitn(3, '.');
+    let (signature, expires, region) = (parts.next()?, parts.next()?.parse::<i64>().ok()?, parts.next()?);
+    signed_url::verify_until("forwarded", &[region], expires, signature).then(|| region.to_string())
+}
+
+fn strip_hop_headers(headers: &mut HeaderMap) {
+    for name in HOP_HEADERS {
+        headers.remove(*name);
+    }
+}
+
+fn error_response(api_error: ApiError) -> Response {
+    (StatusCode::from_u16(api_error.status_code()).unwrap(), Json(api_error.to_json())).into_response()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_by_route() {
+        assert!(!is_write(&Method::GET, "/data/accounts"));
+        assert!(!is_write(&Method::POST, "/find/accounts"));
+        assert!(!is_write(&Method::POST, "/find/accounts/count"));
+        assert!(!is_write(&Method::POST, "/v1/find/accounts/explain"));
+        assert!(is_write(&Method::PATCH, "/find/accounts"));
+        assert!(is_write(&Method::DELETE, "/find/accounts"));
+        assert!(is_write(&Method::POST, "/data/accounts"));
+        assert!(is_write(&Method::POST, "/bulk"));
+        assert!(is_write(&Method::POST, "/views"));
+    }
+
+    #[test]
+    fn test_only_signed_hops_count_as_forwarded() {
+        let mut headers = HeaderMap::new();
+        headers.insert(FORWARDED_FROM, HeaderValue::from_static("eu-west"));
+        assert_eq!(forwarded_from(&headers), None);
+
+        let expires = crate::clock::now().timestamp() + FORWARD_TTL_SECS;
+        headers.insert(FORWARDED_FROM, HeaderValue::from_str(&sign_hop("eu-west", expires)).unwrap());
+        assert_eq!(forwarded_from(&headers), Some("eu-west".to_string()));
+
+        let forged = sign_hop("eu-west", expires).replacen("eu-west", "us-east", 1);
+        headers.insert(FORWARDED_FROM, HeaderValue::from_str(&forged).unwrap());
+        assert_eq!(forwarded_from(&headers), None);
+
+        let expired = sign_hop("eu-west", crate::clock::now().timestamp() - 1);
+        headers.insert(FORWARDED_FROM, HeaderValue::from_str(&expired).unwrap());
+        assert_eq!(forwarded_from(&headers), None);
+    }
+}
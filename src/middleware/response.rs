@@ -10,6 +10,8 @@ use serde_json::{json, Value};
 pub struct ApiResponse<T: Serialize> {
     pub data: T,
     pub status_code: Option<StatusCode>,
+    /// Optional top-level "meta" object, e.g. result counts
+    pub meta: Option<Value>,
 }
 
 impl<T: Serialize> ApiResponse<T> {
@@ -18,6 +20,7 @@ impl<T: Serialize> ApiResponse<T> {
         Self {
             data,
             status_code: None, // Default to 200 OK
+            meta: None,
         }
     }
 
@@ -26,9 +29,16 @@ impl<T: Serialize> ApiResponse<T> {
         Self {
             data,
             status_code: Some(status_code),
+            meta: None,
         }
     }
 
+    /// Attach a "meta" object alongside "data" in the envelope
+    pub fn with_meta(mut self, meta: Value) -> Self {
+        self.meta = Some(meta);
+        self
+    }
+
     /// Create a 201 Created response
     pub fn created(data: T) -> Self {
         Self::with_status(data, StatusCode::CREATED)
@@ -70,10 +80,13 @@ impl<T: Serialize> IntoResponse for ApiResponse<T> {
         };
 
         // Wrap in success envelope
-        let envelope = json!({
+        let mut envelope = json!({
             "success": true,
             "data": data_value
         });
+        if let Some(meta) = self.meta {
+            envelope["meta"] = meta;
+        }
 
         (status, Json(envelope)).into_response()
     }
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use once_cell::sync::Lazy;
+use tokio::sync::Semaphore;
+
+use crate::config;
+use crate::error::ApiError;
+use super::auth::AuthUser;
+
+/// In-flight request slots of one tenant plus the number of requests waiting for one
+struct TenantSlots {
+    permits: Arc<Semaphore>,
+    queued: AtomicU32,
+}
+
+static TENANT_SLOTS: Lazy<Mutex<HashMap<String, Arc<TenantSlots>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn slots_for(tenant: &str, max_in_flight: u32) -> Arc<TenantSlots> {
+    let mut slots = TENANT_SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+    slots
+        .entry(tenant.to_string())
+        .or_insert_with(|| Arc::new(TenantSlots {
+            permits: Arc::new(Semaphore::new(max_in_flight.max(1) as usize)),
+            queued: AtomicU32::new(0),
+        }))
+        .clone()
+}
+
+/// Middleware that caps the requests each tenant can have in flight
+///
+/// Runs after JWT authentication. A request beyond `tenant_max_in_flight` waits for a
+/// slot, up to `tenant_queue_timeout_ms`; once `tenant_max_queued` requests are already
+/// waiting, or the wait times out, it is rejected with 429 so one tenant's spike cannot
+/// occupy every worker and connection of the shared instance.
+pub async fn tenant_limit_middleware(
+    request: Request,
+    next: Next,
+) -> Result<Response, impl IntoResponse> {
+    let api = &config::config().api;
+    if !api.enable_tenant_limits {
+        return Ok(next.run(request).await);
+    }
+    let Some(tenant) = request.extensions().get::<AuthUser>().map(|user| user.tenant.clone()) else {
+        return Ok(next.run(request).await);
+    };
+
+    let slots = slots_for(&tenant, api.tenant_max_in_flight);
+    let permit = match slots.permits.clone().try_acquire_owned() {
+        Ok(permit) => permit,
+        Err(_) => {
+            if slots.queued.fetch_add(1, Ordering::SeqCst) >= api.tenant_max_queued {
+                slots.queued.fetch_sub(1, Ordering::SeqCst);
+                return Err(rejected(&tenant, "too many queued requests"));
+            }
+            let waited = tokio::time::timeout(
+                Duration::from_millis(api.tenant_queue_timeout_ms),
+                slots.permits.clone().acquire_owned(),
+            )
+            .await;
+            slots.queued.fetch_sub(1, Ordering::SeqCst);
+            match waited {
+                Ok(Ok(permit)) => permit,
+                _ => return Err(rejected(&tenant, "timed out waiting for a request slot")),
+            }
+        }
+    };
+
+    let response = next.run(request).await;
+    drop(permit);
+    Ok(response)
+}
+
+fn rejected(tenant: &str, reason: &str) -> Response {
+    tracing::warn!("Rejected request for tenant '{}': {}", tenant, reason);
+    let api_error = ApiError::too_many_requests(format!(
+        "Tenant '{}' has too many concurrent requests; retry shortly", tenant
+    ));
+    let mut response = (
+        StatusCode::from_u16(api_error.status_code()).unwrap(),
+        Json(api_error.to_json()),
+    )
+        .into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from_static("1"));
+    response
+}
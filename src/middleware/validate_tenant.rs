@@ -8,6 +8,8 @@ use sqlx::{PgPool, Row};
 use uuid::Uuid;
 
 use crate::database::manager::DatabaseManager;
+use crate::database::regions::{self, DEFAULT_CLUSTER};
+use super::region_routing::{self, Route};
 use crate::error::ApiError;
 use super::auth::AuthUser;
 
@@ -15,6 +17,13 @@ use super::auth::AuthUser;
 #[derive(Clone)]
 pub struct TenantPool(pub PgPool);
 
+/// Tenant database pool with DDL privileges, for schema and column management
+///
+/// Same pool as `TenantPool` unless restricted roles are enabled, in which case
+/// `TenantPool` runs as the tenant's data role and cannot alter tables.
+#[derive(Clone)]
+pub struct TenantDdlPool(pub PgPool);
+
 /// Validated tenant information from monk_main.tenants
 #[derive(Clone, Debug)]
 pub struct ValidatedTenant {
@@ -28,10 +37,17 @@ pub struct ValidatedTenant {
     pub access_edit: Vec<Uuid>,
     pub access_full: Vec<Uuid>,
     pub access_deny: Vec<Uuid>,
+    /// Region of the tenant's primary database
+    pub region: String,
+    /// Database cluster the tenant lives on
+    pub cluster: String,
 }
 
 /// Middleware that validates the tenant from JWT claims against monk_main.tenants
 /// Ensures the tenant exists and is active (not trashed/deleted)
+///
+/// Requests for tenants of another region are routed by `region_routing`: writes may be
+/// forwarded to that region's instances and reads served from a local replica.
 pub async fn validate_tenant_middleware(
     mut request: Request,
     next: Next,
@@ -57,11 +73,19 @@ pub async fn validate_tenant_middleware(
             )
         })?;
 
+    regions::ensure_columns(&main_pool).await.map_err(|e| {
+        let api_error: ApiError = e.into();
+        (
+            StatusCode::from_u16(api_error.status_code()).unwrap(),
+            Json(api_error.to_json()),
+        )
+    })?;
+
     // Query tenant by database name from JWT claims
     let query = r#"
         SELECT 
             id, name, database, host, is_active, tenant_type,
-            access_read, access_edit, access_full, access_deny
+            access_read, access_edit, access_full, access_deny, region, cluster
         FROM tenants 
         WHERE database = $1 
         AND is_active = true
@@ -103,27 +127,56 @@ pub async fn validate_tenant_middleware(
         access_edit: tenant_row.get("access_edit"),
         access_full: tenant_row.get("access_full"),
         access_deny: tenant_row.get("access_deny"),
+        region: tenant_row
+            .get::<Option<String>, _>("region")
+            .unwrap_or_else(|| crate::config::config().database.default_region.clone()),
+        cluster: tenant_row
+            .get::<Option<String>, _>("cluster")
+            .unwrap_or_else(|| DEFAULT_CLUSTER.to_string()),
     };
 
     tracing::debug!("Tenant validation successful: {} ({})", validated_tenant.name, validated_tenant.database);
 
-    // Get database pool for the validated tenant
-    let tenant_pool = DatabaseManager::tenant_pool(&validated_tenant.database)
+    let route = region_routing::route(request.method(), request.uri().path(), request.headers(), &validated_tenant.region);
+    if let Route::Forward(base_url) = route {
+        tracing::debug!("Forwarding {} for {} to {}", request.method(), validated_tenant.database, base_url);
+        return Ok(region_routing::forward(request, &base_url).await);
+    }
+
+    // Get database pools for the validated tenant
+    let pool_error = |e: crate::database::manager::DatabaseError| {
+        tracing::error!("Failed to get database pool for tenant '{}': {}", validated_tenant.database, e);
+        let api_error: ApiError = e.into();
+        (
+            StatusCode::from_u16(api_error.status_code()).unwrap(),
+            Json(api_error.to_json()),
+        )
+    };
+    let replica_pool = match route {
+        Route::Replica => DatabaseManager::tenant_replica_pool_on(&validated_tenant.database, &validated_tenant.cluster)
+            .await
+            .map_err(pool_error)?,
+        _ => None,
+    };
+    let tenant_pool = match replica_pool {
+        Some(pool) => pool,
+        None => DatabaseManager::tenant_data_pool_on(&validated_tenant.database, &validated_tenant.cluster)
+            .await
+            .map_err(pool_error)?,
+    };
+    let ddl_pool = DatabaseManager::tenant_pool_on(&validated_tenant.database, &validated_tenant.cluster)
         .await
-        .map_err(|e| {
-            tracing::error!("Failed to get database pool for tenant '{}': {}", validated_tenant.database, e);
-            let api_error: ApiError = e.into();
-            (
-                StatusCode::from_u16(api_error.status_code()).unwrap(),
-                Json(api_error.to_json()),
-            )
-        })?;
+        .map_err(pool_error)?;
 
     tracing::debug!("Tenant database pool acquired for: {}", validated_tenant.database);
 
     // Inject both validated tenant and tenant pool into request
     request.extensions_mut().insert(validated_tenant);
     request.extensions_mut().insert(TenantPool(tenant_pool));
+    request.extensions_mut().insert(TenantDdlPool(ddl_pool));
 
-    Ok(next.run(request).await)
+    let wrote = region_routing::is_write(request.method(), request.uri().path());
+    let mut response = next.run(request).await;
+    region_routing::finish(&mut response, wrote);
+    Ok(response)
 }
\ No newline at end of file
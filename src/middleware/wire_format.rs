@@ -0,0 +1,103 @@
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+use super::api_version::{adapt_response, is_json};
+use super::validate_tenant::TenantPool;
+use crate::canonical;
+use crate::api::format::WireFormat;
+use crate::database::feature_flags;
+
+/// Middleware that selects the response wire format for protected /api/* routes
+///
+/// Resolution order: `X-Wire-Format` header, then `Accept: application/vnd.api+json`,
+/// then the tenant's `wire_format_v2` feature flag. Runs after tenant validation so
+/// the flag can be read; every response is counted toward `WireFormat::usage()`.
+///
+/// Independently of the format, `X-Canonical-Json: true` asks for the body in canonical
+/// JSON form (sorted keys, normalized numbers) so clients can hash or diff it.
+pub async fn wire_format_middleware(request: Request, next: Next) -> Response {
+    let format = match header_format(&request) {
+        Some(format) => format,
+        None => tenant_format(request.extensions().get::<TenantPool>().cloned()).await,
+    };
+    let canonical = canonical::is_requested(request.headers());
+    let resource_type = resource_type(request.uri().path());
+
+    format.record_usage();
+    let mut response = next.run(request).await;
+
+    let reshape = format != WireFormat::Canonical;
+    if (reshape || canonical) && is_json(&response) {
+        let status = response.status().as_u16();
+        response = adapt_response(
+            response,
+            |body| if reshape { format.response(body, resource_type.as_deref(), status) } else { body },
+            canonical,
+        )
+        .await;
+        if reshape {
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(WireFormat::JSON_API_MEDIA_TYPE));
+        }
+        if canonical {
+            response
+                .headers_mut()
+                .insert("canonical-json", HeaderValue::from_static("true"));
+        }
+    }
+
+    response
+        .headers_mut()
+        .insert("wire-format", HeaderValue::from_static(format.as_str()));
+    response
+}
+
+/// Format explicitly requested by the client, if any
+fn header_format(request: &Request) -> Option<WireFormat> {
+    let headers = request.headers();
+    if let Some(format) = headers
+        .get("x-wire-format")
+        .and_then(|v| v.to_str().ok())
+        .and_then(WireFormat::parse)
+    {
+        return Some(format);
+    }
+
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| v.contains(WireFormat::JSON_API_MEDIA_TYPE))
+        .map(|_| WireFormat::JsonApi)
+}
+
+/// Format selected by the tenant's wire_format_v2 flag
+async fn tenant_format(tenant_pool: Option<TenantPool>) -> WireFormat {
+    let Some(TenantPool(pool)) = tenant_pool else {
+        return WireFormat::Canonical;
+    };
+    match feature_flags::is_enabled(&pool, "wire_format_v2").await {
+        Ok(true) => WireFormat::JsonApi,
+        Ok(false) => WireFormat::Canonical,
+        Err(e) => {
+            tracing::warn!("Failed to read wire_format_v2 flag, serving canonical format: {}", e);
+            WireFormat::Canonical
+        }
+    }
+}
+
+/// Schema name for record resources: "/api[/vN]/{data,find}/:schema/..."
+fn resource_type(path: &str) -> Option<String> {
+    let mut segments = path.trim_start_matches('/').split('/').skip(1).peekable();
+    if segments.peek().map(|s| s.starts_with('v') && s[1..].chars().all(|c| c.is_ascii_digit())) == Some(true) {
+        segments.next();
+    }
+    match segments.next() {
+        Some("data") | Some("find") => segments.next().filter(|s| !s.is_empty()).map(|s| s.to_string()),
+        _ => None,
+    }
+}
@@ -6,6 +6,7 @@ use sqlx::PgPool;
 use crate::observer::traits::{ObserverRing, Operation};
 use crate::observer::error::{ObserverError, ObserverWarning};
 use crate::database::record::Record;
+use crate::database::transaction::{TenantConnection, TenantTransaction};
 use crate::filter::FilterData;
 
 /// Type-safe observer context with Record support
@@ -21,6 +22,9 @@ pub struct ObserverContext {
     
     // Database connection - tenant-specific pool for all database operations
     pool: PgPool,
+
+    // Transaction the pipeline's statements run in, if any (see `connection`)
+    transaction: Option<TenantTransaction>,
     
     // SELECT-specific: Query filter data (for SELECT operations)
     pub filter_data: Option<FilterData>,
@@ -53,6 +57,7 @@ impl ObserverContext {
             schema_name,
             records,
             pool,
+            transaction: None,
             filter_data: None,
             result: None,
             metadata: HashMap::new(),
@@ -74,6 +79,7 @@ impl ObserverContext {
             schema_name,
             records: Vec::new(), // Empty until Ring 5 populates from database
             pool,
+            transaction: None,
             filter_data: Some(filter_data),
             result: None,
             metadata: HashMap::new(),
@@ -162,10 +168,30 @@ impl ObserverContext {
             .any(|record| record.has_changes())
     }
 
-    /// Get tenant-specific database pool for SQL operations
+    /// Get tenant-specific database pool
+    ///
+    /// Statements that write, or must see what the pipeline wrote, go through `connection`
+    /// instead so they join the pipeline's transaction.
     pub fn get_pool(&self) -> &PgPool {
         &self.pool
     }
+
+    /// Connection for the pipeline's statements: its transaction's when it runs in one,
+    /// otherwise one from the pool
+    ///
+    /// A transaction's connection is locked until dropped, so hold it only around the
+    /// statements themselves.
+    pub async fn connection(&self) -> Result<TenantConnection, sqlx::Error> {
+        TenantConnection::acquire(&self.pool, self.transaction.as_ref()).await
+    }
+
+    pub fn transaction(&self) -> Option<&TenantTransaction> {
+        self.transaction.as_ref()
+    }
+
+    pub fn set_transaction(&mut self, transaction: Option<TenantTransaction>) {
+        self.transaction = transaction;
+    }
 }
 
 // Make ObserverContext cloneable for async rings (they get read-only copy)
@@ -176,6 +202,7 @@ impl Clone for ObserverContext {
             schema_name: self.schema_name.clone(),
             records: self.records.clone(),
             pool: self.pool.clone(),
+            transaction: self.transaction.clone(),
             filter_data: self.filter_data.clone(),
             result: self.result.clone(),
             metadata: HashMap::new(), // Metadata is not cloneable - async observers get fresh context
@@ -214,6 +241,47 @@ pub struct SecurityCheckResults {
     pub protected_record_count: usize,
 }
 
+/// Time spent in each observer of a pipeline run, in execution order
+#[derive(Debug, Clone, Default)]
+pub struct ProcessingMetadata {
+    pub observers: Vec<ObserverTiming>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObserverTiming {
+    pub ring: ObserverRing,
+    pub observer: &'static str,
+    pub elapsed: std::time::Duration,
+    pub outcome: ObserverOutcome,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObserverOutcome {
+    Completed,
+    Failed,
+    TimedOut,
+}
+
+impl ObserverOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObserverOutcome::Completed => "completed",
+            ObserverOutcome::Failed => "failed",
+            ObserverOutcome::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// Marks a run of `ObserverPipeline::preview`, which stops before the Database ring
+#[derive(Debug, Clone, Copy)]
+pub struct DryRun;
+
+/// Retries of statements that failed with a deadlock or serialization failure
+#[derive(Debug, Clone, Default)]
+pub struct SqlRetryStats {
+    pub retries: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct QueryMetadata {
     /// Original filter from API request
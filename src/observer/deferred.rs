@@ -0,0 +1,145 @@
+// Background execution of the side effect rings (8 and 9)
+//
+// Ring 8 and 9 observers (webhooks, cache purges, search index updates) talk to other
+// services, so the pipeline hands them to a single background worker instead of awaiting
+// them. The worker runs jobs one at a time in submission order, which is commit order
+// for writes outside a transaction. Writes inside a transaction are held until it
+// commits (see `hold`), so nothing is announced for changes that were rolled back.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use crate::observer::context::ObserverContext;
+use crate::observer::traits::ObserverBox;
+
+/// Side effect observers to run for one finished pipeline
+pub struct DeferredRun {
+    observers: Vec<Arc<ObserverBox>>,
+    context: ObserverContext,
+}
+
+impl DeferredRun {
+    pub fn new(observers: Vec<Arc<ObserverBox>>, context: ObserverContext) -> Self {
+        Self { observers, context }
+    }
+
+    async fn execute(self) {
+        for observer in &self.observers {
+            match timeout(observer.timeout(), observer.execute_async(&self.context)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(error)) => tracing::warn!(
+                    "Side effect {} failed for {:?} on {}: {}",
+                    observer.name(), self.context.operation, self.context.schema_name, error
+                ),
+                Err(_) => tracing::warn!("Side effect {} timed out after {:?}", observer.name(), observer.timeout()),
+            }
+        }
+    }
+}
+
+tokio::task_local! {
+    static HELD: RefCell<Vec<DeferredRun>>;
+}
+
+static QUEUE: Lazy<mpsc::Sender<DeferredRun>> = Lazy::new(|| {
+    let capacity = crate::config::config().api.side_effect_queue_capacity.max(1);
+    let (sender, mut receiver) = mpsc::channel::<DeferredRun>(capacity);
+    tokio::spawn(async move {
+        while let Some(run) = receiver.recv().await {
+            run.execute().await;
+        }
+    });
+    sender
+});
+
+/// Queue a run for the background worker, or hold it while inside `hold`
+pub fn submit(run: DeferredRun) {
+    let mut run = Some(run);
+    let _ = HELD.try_with(|held| held.borrow_mut().extend(run.take()));
+    let Some(run) = run else {
+        return;
+    };
+    if let Err(mpsc::error::TrySendError::Full(run) | mpsc::error::TrySendError::Closed(run)) = QUEUE.try_send(run) {
+        tracing::warn!(
+            "Side effect queue is full or closed; dropped {:?} of {} record(s) in {}",
+            run.context.operation, run.context.records.len(), run.context.schema_name
+        );
+    }
+}
+
+/// Submit runs collected by `hold`, once the work they describe is committed
+pub fn release(runs: impl IntoIterator<Item = DeferredRun>) {
+    for run in runs {
+        submit(run);
+    }
+}
+
+/// Run `work`, collecting the side effect runs it submits instead of queueing them
+///
+/// The caller releases them after committing, or drops them on rollback. Holds nest: runs
+/// released inside an outer hold go to that hold.
+pub async fn hold<F: Future>(work: F) -> (F::Output, Vec<DeferredRun>) {
+    HELD.scope(RefCell::new(Vec::new()), async move {
+        let output = work.await;
+        let held = HELD.with(|held| held.take());
+        (output, held)
+    })
+    .await
+}
+
+/// HTTP client shared by the side effect observers
+pub fn http_client() -> &'static reqwest::Client {
+    static CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+        reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("HTTP client for side effects")
+    });
+    &CLIENT
+}
+
+/// Name of the tenant database a context's pool connects to
+pub fn tenant_database(context: &ObserverContext) -> String {
+    context.get_pool().connect_options().get_database().unwrap_or_default().to_string()
+}
+
+/// Ids of the rows a pipeline wrote, as reported by Ring 5
+pub fn written_ids(context: &ObserverContext) -> Vec<String> {
+    context
+        .result
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|row| row.get("id").and_then(|id| id.as_str()).map(str::to_string))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+    use crate::observer::traits::Operation;
+
+    #[tokio::test]
+    async fn holds_nest_until_released() {
+        let pool = PgPool::connect_lazy("postgres://localhost/tenant_test").unwrap();
+        let run = || DeferredRun::new(Vec::new(), ObserverContext::new(Operation::Create, "x".to_string(), Vec::new(), pool.clone()));
+
+        let ((), outer) = hold(async {
+            let ((), inner) = hold(async { submit(run()) }).await;
+            assert_eq!(inner.len(), 1);
+            release(inner);
+
+            // Dropped instead of released, as after a savepoint rollback
+            let ((), _) = hold(async { submit(run()) }).await;
+        })
+        .await;
+        assert_eq!(outer.len(), 1);
+    }
+}
@@ -15,9 +15,33 @@ pub enum ObserverError {
     
     #[error("Timeout error: {0}")]
     TimeoutError(String),
+
+    /// The client's request deadline passed; holds where the pipeline stopped
+    #[error("Request deadline exceeded: {0}")]
+    DeadlineExceeded(String),
     
     #[error("Database error: {0}")]
     DatabaseError(String),
+
+    /// The request's filter could not be turned into SQL
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    /// The filter names a column the schema does not have
+    #[error("Unknown column '{column}' in {clause}")]
+    UnknownColumn { column: String, clause: String },
+
+    /// A unique index rejected the write; holds the columns of the index
+    #[error("Duplicate value for unique ({})", .0.join(", "))]
+    UniqueViolation(Vec<String>),
+
+    /// Record fields do not match the schema definition, as (field, message) pairs
+    #[error("Fields do not match schema '{schema}': {}", .errors.iter().map(|(field, message)| format!("{}: {}", field, message)).collect::<Vec<_>>().join("; "))]
+    SchemaViolation { schema: String, errors: Vec<(String, String)> },
+
+    /// A CHECK constraint generated from the schema rejected the write
+    #[error("Value of {field} violates the schema {rule} check")]
+    CheckViolation { field: String, rule: String },
     
     #[error("Observer recursion error: depth {depth} exceeds maximum {max_depth}")]
     RecursionError { depth: usize, max_depth: usize },
@@ -29,6 +53,17 @@ pub enum ObserverError {
     PipelineError(String),
 }
 
+impl From<crate::filter::error::FilterError> for ObserverError {
+    fn from(err: crate::filter::error::FilterError) -> Self {
+        match err {
+            crate::filter::error::FilterError::UnknownColumn { column, clause } => {
+                ObserverError::UnknownColumn { column, clause: clause.to_string() }
+            }
+            other => ObserverError::InvalidFilter(other.to_string()),
+        }
+    }
+}
+
 /// Observer warnings (non-fatal issues)
 #[derive(Debug, Clone)]
 pub struct ObserverWarning {
@@ -63,6 +98,8 @@ pub struct ObserverResult {
     pub warnings: Vec<ObserverWarning>,
     pub execution_time: Duration,
     pub rings_executed: Vec<crate::observer::traits::ObserverRing>,
+    /// Statements re-run after deadlocks or serialization failures
+    pub retries: u32,
 }
 
 impl ObserverResult {
@@ -74,6 +111,7 @@ impl ObserverResult {
             warnings: Vec::new(),
             execution_time,
             rings_executed: rings,
+            retries: 0,
         }
     }
     
@@ -85,6 +123,7 @@ impl ObserverResult {
             warnings: Vec::new(),
             execution_time,
             rings_executed: Vec::new(),
+            retries: 0,
         }
     }
 }
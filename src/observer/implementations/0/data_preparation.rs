@@ -70,8 +70,9 @@ impl DataPreparationObserver {
             ));
         }
 
-        // Create repository and query existing records
-        let repository = self.create_repository(&ctx.schema_name).await?;
+        // Query existing records through the context's tenant pool (or its transaction)
+        let repository = Repository::new(&ctx.schema_name, ctx.get_pool().clone())
+            .with_transaction(ctx.transaction().cloned());
         let existing_records = match ctx.operation {
             Operation::Revert => {
                 // Query for trashed records only
@@ -138,14 +139,4 @@ impl DataPreparationObserver {
 
         Ok(())
     }
-
-    /// Create a Repository for the schema
-    async fn create_repository(&self, schema_name: &str) -> Result<Repository, ObserverError> {
-        use crate::database::manager::DatabaseManager;
-        
-        let pool = DatabaseManager::main_pool().await
-            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
-        
-        Ok(Repository::new(schema_name, pool))
-    }
 }
\ No newline at end of file
@@ -0,0 +1,75 @@
+// Ring 1: Record Id Generator - assigns time-ordered ids to new records
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use uuid::{Builder, Uuid};
+
+use crate::observer::traits::{Observer, Ring1, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Millisecond timestamp and counter/random bytes of the last v7 id handed out
+static LAST_V7: Mutex<(u64, [u8; 10])> = Mutex::new((0, [0; 10]));
+
+/// A UUIDv7 from `crate::clock`, strictly greater than every v7 id generated before it
+///
+/// Ids from one process sort in generation order even within a millisecond or when the
+/// clock steps back: the previous timestamp is kept and its random tail incremented.
+pub fn new_v7() -> Uuid {
+    let now = crate::clock::now().timestamp_millis().max(0) as u64;
+    let mut last = LAST_V7.lock().unwrap_or_else(|e| e.into_inner());
+
+    if now > last.0 {
+        let mut bytes = [0u8; 10];
+        bytes.copy_from_slice(&Uuid::new_v4().as_bytes()[..10]);
+        *last = (now, bytes);
+    } else {
+        // Bytes 0 and 2 carry the version and variant bits, so count in the low 7 bytes only
+        for byte in last.1[3..].iter_mut().rev() {
+            *byte = byte.wrapping_add(1);
+            if *byte != 0 {
+                break;
+            }
+        }
+    }
+    Builder::from_unix_timestamp_millis(last.0, &last.1).into_uuid()
+}
+
+/// Ring 1: Record Id Generator - gives new records a UUIDv7 before insert
+///
+/// Enabled by `database.enable_uuid_v7_ids`; otherwise ids come from the column default
+/// (random v4). Ids supplied by the client are kept.
+#[derive(Default)]
+pub struct RecordIdGenerator;
+
+impl Observer for RecordIdGenerator {
+    fn name(&self) -> &'static str {
+        "RecordIdGenerator"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::InputValidation
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create)
+    }
+
+    fn applies_to_schema(&self, _schema: &str) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl Ring1 for RecordIdGenerator {
+    async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        if !crate::config::config().database.enable_uuid_v7_ids {
+            return Ok(());
+        }
+
+        for record in ctx.records.iter_mut().filter(|record| record.get("id").is_none()) {
+            record.set_id(new_v7());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+// Ring 1: Schema Validator - checks incoming fields against the schema definition
+use async_trait::async_trait;
+use serde_json::Map;
+
+use crate::database::record::ChangeType;
+use crate::database::validation::{cached_definition, forget_definitions, load_definition, pattern_checks, pattern_errors, validate_fields};
+use crate::observer::traits::{Observer, Ring1, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 1: Schema Validator - rejects fields the schema does not define or allow
+///
+/// Creates are checked in full, including required properties; updates only check the
+/// fields they change. Registered after the slug and sequence observers so the values
+/// they fill in count as present.
+///
+/// Every record of the batch is checked and all problems are reported together, keyed by
+/// field, or by `<index>.<field>` when the batch has more than one record. Writes to the
+/// schemas and columns tables are not validated; they drop the tenant's cached definitions.
+#[derive(Default)]
+pub struct SchemaValidator;
+
+impl Observer for SchemaValidator {
+    fn name(&self) -> &'static str {
+        "SchemaValidator"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::InputValidation
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Update)
+    }
+
+    fn applies_to_schema(&self, schema: &str) -> bool {
+        schema != "users"
+    }
+}
+
+#[async_trait]
+impl Ring1 for SchemaValidator {
+    async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        if ctx.records.is_empty() {
+            return Ok(());
+        }
+
+        if ["schemas", "columns"].contains(&ctx.schema_name.as_str()) {
+            forget_definitions(ctx.get_pool());
+            return Ok(());
+        }
+
+        // A transaction may have changed the schema, so its definition is read uncached
+        let definition = match ctx.transaction() {
+            Some(_) => {
+                let mut connection = ctx.connection()
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+                load_definition(&mut *connection, &ctx.schema_name).await
+            }
+            None => cached_definition(ctx.get_pool(), &ctx.schema_name).await,
+        };
+        let Some(definition) = definition.map_err(|e| ObserverError::DatabaseError(e.to_string()))? else {
+            return Ok(());
+        };
+
+        let complete = ctx.operation == Operation::Create;
+        let batch = ctx.records.len() > 1;
+        let key = |index: usize, field: &str| if batch { format!("{}.{}", index, field) } else { field.to_string() };
+
+        let mut errors = Vec::new();
+        let mut patterns = Vec::new();
+        for (index, record) in ctx.records.iter().enumerate() {
+            let fields: Map<_, _> = if complete {
+                record.to_map()
+            } else {
+                record
+                    .changes()
+                    .into_iter()
+                    .filter(|(_, change)| change.change_type != ChangeType::Removed)
+                    .map(|(field, change)| (field, change.new_value.unwrap_or_default()))
+                    .collect()
+            };
+
+            let failed = validate_fields(&definition, &fields, complete);
+            for (field, value, pattern) in pattern_checks(&definition, &fields, &failed) {
+                patterns.push((key(index, &field), value, pattern));
+            }
+            errors.extend(failed.into_iter().map(|e| (key(index, &e.field), e.message)));
+        }
+
+        let (fields, (values, expressions)): (Vec<_>, (Vec<_>, Vec<_>)) =
+            patterns.into_iter().map(|(field, value, pattern)| (field, (value, pattern))).unzip();
+        let mut connection = ctx.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let mismatches = pattern_errors(&mut *connection, values, expressions)
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        errors.extend(mismatches.into_iter().map(|i| (fields[i].clone(), "does not match the schema pattern".to_string())));
+
+        if !errors.is_empty() {
+            return Err(ObserverError::SchemaViolation { schema: ctx.schema_name.clone(), errors });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+// Ring 1: Sequence Allocator - numbers x-monk-sequence properties of new records
+use async_trait::async_trait;
+
+use crate::database::sequences::{load_sequence_fields, next_values};
+use crate::observer::traits::{Observer, Ring1, ObserverRing, Operation};
+use crate::observer::context::{DryRun, ObserverContext};
+use crate::observer::error::ObserverError;
+
+/// Ring 1: Sequence Allocator - assigns the next sequence number to each new record
+///
+/// Sequence properties are read-only through the API: a value supplied on create, or a
+/// change on update, is rejected.
+#[derive(Default)]
+pub struct SequenceAllocator;
+
+impl Observer for SequenceAllocator {
+    fn name(&self) -> &'static str {
+        "SequenceAllocator"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::InputValidation
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Update)
+    }
+
+    fn applies_to_schema(&self, schema: &str) -> bool {
+        !["schemas", "columns", "users"].contains(&schema)
+    }
+}
+
+#[async_trait]
+impl Ring1 for SequenceAllocator {
+    async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        if ctx.records.is_empty() {
+            return Ok(());
+        }
+
+        let mut connection = ctx.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let sequences = load_sequence_fields(&mut *connection, &ctx.schema_name)
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+
+        for sequence in &sequences {
+            let written = ctx.records.iter().any(|record| match ctx.operation {
+                Operation::Create => record.get(&sequence.field).is_some_and(|v| !v.is_null()),
+                _ => record.original().is_some() && record.changed(&sequence.field),
+            });
+            if written {
+                return Err(ObserverError::ValidationError(format!(
+                    "{} is assigned automatically and cannot be written",
+                    sequence.field
+                )));
+            }
+
+            // Numbers drawn from a sequence are never handed back, so a preview leaves them unset
+            if ctx.operation == Operation::Create && ctx.get_metadata::<DryRun>().is_none() {
+                let numbers = next_values(&mut *connection, &ctx.schema_name, &sequence.field, ctx.records.len())
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to allocate {}: {}", sequence.field, e)))?;
+                for (record, n) in ctx.records.iter_mut().zip(numbers) {
+                    record.set(sequence.field.as_str(), sequence.format(n));
+                }
+            }
+        }
+        Ok(())
+    }
+}
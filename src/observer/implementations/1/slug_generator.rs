@@ -0,0 +1,132 @@
+// Ring 1: Slug Generator - fills x-monk-slug properties with unique slugs
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::database::slugs::{is_valid_slug, load_slug_fields, slugify, SlugField};
+use crate::observer::traits::{Observer, Ring1, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 1: Slug Generator - derives slugs for properties declared with `x-monk-slug`
+///
+/// On create, a missing slug is built from the `from` property and suffixed `-2`, `-3`, ...
+/// until it is unused in the table. Slugs supplied by the client (create or update) must
+/// already be in slug form; the unique index catches any remaining collision.
+#[derive(Default)]
+pub struct SlugGenerator;
+
+impl Observer for SlugGenerator {
+    fn name(&self) -> &'static str {
+        "SlugGenerator"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::InputValidation
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Update)
+    }
+
+    fn applies_to_schema(&self, schema: &str) -> bool {
+        !["schemas", "columns", "users"].contains(&schema)
+    }
+}
+
+#[async_trait]
+impl Ring1 for SlugGenerator {
+    async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        if ctx.records.is_empty() {
+            return Ok(());
+        }
+
+        let slug_fields = {
+            let mut connection = ctx.connection()
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+            load_slug_fields(&mut *connection, &ctx.schema_name)
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?
+        };
+
+        for slug_field in &slug_fields {
+            self.check_supplied(ctx, slug_field)?;
+            if ctx.operation == Operation::Create {
+                self.generate(ctx, slug_field).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SlugGenerator {
+    fn check_supplied(&self, ctx: &ObserverContext, slug_field: &SlugField) -> Result<(), ObserverError> {
+        for record in &ctx.records {
+            match record.get(&slug_field.field) {
+                Some(Value::String(slug)) if !slug.is_empty() && !is_valid_slug(slug) => {
+                    return Err(ObserverError::ValidationError(format!(
+                        "Invalid slug '{}' for {}: use lowercase letters, digits and single hyphens",
+                        slug, slug_field.field
+                    )));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn generate(&self, ctx: &mut ObserverContext, slug_field: &SlugField) -> Result<(), ObserverError> {
+        let SlugField { field, from } = slug_field;
+        let mut assigned: HashSet<String> = ctx
+            .records
+            .iter()
+            .filter_map(|record| record.get(field).and_then(|v| v.as_str()))
+            .filter(|slug| !slug.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        for index in 0..ctx.records.len() {
+            let record = &ctx.records[index];
+            if record.get(field).and_then(|v| v.as_str()).is_some_and(|slug| !slug.is_empty()) {
+                continue;
+            }
+            let base = match record.get(from).and_then(|v| v.as_str()).map(slugify) {
+                Some(base) if !base.is_empty() => base,
+                // Nothing to derive from; the slug stays empty (NULL is not unique-constrained)
+                _ => continue,
+            };
+
+            let taken = self.taken_slugs(ctx, field, &base).await?;
+            let slug = std::iter::once(base.clone())
+                .chain((2..).map(|n| format!("{}-{}", base, n)))
+                .find(|slug| !taken.contains(slug) && !assigned.contains(slug) && is_valid_slug(slug))
+                .expect("an unused suffix always exists");
+
+            assigned.insert(slug.clone());
+            ctx.records[index].set(field.as_str(), slug);
+        }
+        Ok(())
+    }
+
+    /// Slugs in the table equal to `base` or of the form `base-...`
+    async fn taken_slugs(&self, ctx: &ObserverContext, field: &str, base: &str) -> Result<HashSet<String>, ObserverError> {
+        // Slugs only contain [a-z0-9-], so `base` needs no LIKE escaping
+        let query = format!(
+            "SELECT \"{}\" FROM \"{}\" WHERE \"{}\" = $1 OR \"{}\" LIKE $2",
+            field, ctx.schema_name, field, field
+        );
+        let mut connection = ctx.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let rows: Vec<(String,)> = sqlx::query_as(&query)
+            .bind(base)
+            .bind(format!("{}-%", base))
+            .fetch_all(&mut *connection)
+            .await
+            .map_err(|e| ObserverError::DatabaseError(format!("Failed to check slugs in {}: {}", ctx.schema_name, e)))?;
+
+        Ok(rows.into_iter().map(|(slug,)| slug).collect())
+    }
+}
@@ -0,0 +1,71 @@
+// Ring 2: Access Control - checks the caller may change the records it writes
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::filter::RecordAccess;
+use crate::observer::traits::{Observer, Ring2, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 2: Access Control - enforces records' access_* lists on writes
+///
+/// A record may be changed by ids in neither `access_deny` nor outside its grants: either
+/// one of them is in `access_edit` or `access_full`, or the record has no grants at all.
+/// Reads are filtered the same way by the select executor. Runs when the pipeline carries
+/// a `RecordAccess`, which root callers and internal writes do not.
+#[derive(Default)]
+pub struct AccessControl;
+
+impl Observer for AccessControl {
+    fn name(&self) -> &'static str {
+        "AccessControl"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Security
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Update | Operation::Delete | Operation::Revert)
+    }
+
+    fn applies_to_schema(&self, _schema: &str) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl Ring2 for AccessControl {
+    async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        let Some(access) = ctx.get_metadata::<RecordAccess>() else {
+            return Ok(());
+        };
+        let ids: Vec<Uuid> = ctx.records.iter().filter_map(|record| record.id()).collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let query = format!(
+            "SELECT id FROM \"{}\" WHERE id = ANY($1) AND NOT ({}) LIMIT 1",
+            ctx.schema_name,
+            RecordAccess::editable_condition("$2")
+        );
+        let mut connection = ctx.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let denied: Option<(Uuid,)> = sqlx::query_as(&query)
+            .bind(&ids)
+            .bind(access.to_array_literal())
+            .fetch_optional(&mut *connection)
+            .await
+            .map_err(|e| ObserverError::DatabaseError(format!("Failed to check access in {}: {}", ctx.schema_name, e)))?;
+
+        match denied {
+            Some((id,)) => Err(ObserverError::SecurityError(format!(
+                "Not allowed to {} record {} in '{}'",
+                format!("{:?}", ctx.operation).to_lowercase(), id, ctx.schema_name
+            ))),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Ring 2: Managed Schema Guard - keeps record writes off tables that have their own endpoints
+use async_trait::async_trait;
+
+use crate::filter::RecordAccess;
+use crate::observer::traits::{Observer, Ring2, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Tables written only through a dedicated endpoint, which is named in the rejection
+const MANAGED_SCHEMAS: &[(&str, &str)] = &[
+    // Ownership, filter and column checks, and one default per access level
+    ("views", "/api/views"),
+];
+
+/// Tables whose records anyone may create but only root may change or trash, matching
+/// the root-only rename and merge of the named endpoint
+const ROOT_MANAGED_SCHEMAS: &[(&str, &str)] = &[
+    ("tags", "/api/tags"),
+];
+
+/// Ring 2: Managed Schema Guard - rejects data API writes to tables managed elsewhere
+///
+/// The endpoints of these tables enforce rules the pipeline knows nothing of, so writing
+/// their rows as plain records would skip them. The endpoints write with SQL of their
+/// own, so every write reaching the pipeline is refused, root's included. Root-managed
+/// tables only refuse updates, deletes and reverts, and only for callers carrying a
+/// `RecordAccess`, which root does not.
+#[derive(Default)]
+pub struct ManagedSchemaGuard;
+
+impl Observer for ManagedSchemaGuard {
+    fn name(&self) -> &'static str {
+        "ManagedSchemaGuard"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Security
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        !matches!(op, Operation::Select)
+    }
+
+    fn applies_to_schema(&self, schema: &str) -> bool {
+        endpoint(MANAGED_SCHEMAS, schema).is_some() || endpoint(ROOT_MANAGED_SCHEMAS, schema).is_some()
+    }
+}
+
+#[async_trait]
+impl Ring2 for ManagedSchemaGuard {
+    async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        if let Some(endpoint) = endpoint(MANAGED_SCHEMAS, &ctx.schema_name) {
+            return Err(ObserverError::SecurityError(format!(
+                "Records of '{}' cannot be written through the data API; use {}",
+                ctx.schema_name, endpoint
+            )));
+        }
+        if let Some(endpoint) = endpoint(ROOT_MANAGED_SCHEMAS, &ctx.schema_name) {
+            if ctx.operation != Operation::Create && ctx.has_metadata::<RecordAccess>() {
+                return Err(ObserverError::SecurityError(format!(
+                    "Only root may {} records of '{}'; use {}",
+                    format!("{:?}", ctx.operation).to_lowercase(), ctx.schema_name, endpoint
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn endpoint(schemas: &[(&str, &'static str)], schema: &str) -> Option<&'static str> {
+    schemas.iter().find(|(name, _)| *name == schema).map(|(_, endpoint)| *endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    #[tokio::test]
+    async fn rejects_every_write_to_views() {
+        let pool = PgPool::connect_lazy("postgres://localhost/tenant_test").unwrap();
+        let guard = ManagedSchemaGuard;
+        for op in [Operation::Create, Operation::Update, Operation::Delete, Operation::Revert] {
+            assert!(guard.applies_to_operation(op));
+            let mut ctx = ObserverContext::new(op, "views".to_string(), Vec::new(), pool.clone());
+            assert!(matches!(guard.execute(&mut ctx).await, Err(ObserverError::SecurityError(_))));
+        }
+        assert!(!guard.applies_to_operation(Operation::Select));
+        assert!(!guard.applies_to_schema("accounts"));
+    }
+
+    #[tokio::test]
+    async fn only_root_changes_tags() {
+        let pool = PgPool::connect_lazy("postgres://localhost/tenant_test").unwrap();
+        let guard = ManagedSchemaGuard;
+        let user = || RecordAccess::new(vec![uuid::Uuid::new_v4()]);
+        for op in [Operation::Update, Operation::Delete, Operation::Revert] {
+            let mut ctx = ObserverContext::new(op, "tags".to_string(), Vec::new(), pool.clone());
+            assert!(guard.execute(&mut ctx).await.is_ok(), "root may {:?} tags", op);
+            ctx.set_metadata(user());
+            assert!(matches!(guard.execute(&mut ctx).await, Err(ObserverError::SecurityError(_))));
+        }
+        let mut ctx = ObserverContext::new(Operation::Create, "tags".to_string(), Vec::new(), pool);
+        ctx.set_metadata(user());
+        assert!(guard.execute(&mut ctx).await.is_ok());
+    }
+}
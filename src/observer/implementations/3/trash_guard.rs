@@ -0,0 +1,64 @@
+// Ring 3: Trash Guard - keeps updates off trashed and deleted records
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::observer::traits::{Observer, Ring3, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 3: Trash Guard - rejects updates to records in the trash or deleted
+///
+/// Reads hide trashed records, but an update by id would still reach them; a trashed
+/// record has to be reverted before it can change again.
+#[derive(Default)]
+pub struct TrashGuard;
+
+impl Observer for TrashGuard {
+    fn name(&self) -> &'static str {
+        "TrashGuard"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Business
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Update)
+    }
+
+    fn applies_to_schema(&self, _schema: &str) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl Ring3 for TrashGuard {
+    async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        let ids: Vec<Uuid> = ctx.records.iter().filter_map(|record| record.id()).collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let query = format!(
+            "SELECT id, deleted_at IS NOT NULL FROM \"{}\" \
+             WHERE id = ANY($1) AND (trashed_at IS NOT NULL OR deleted_at IS NOT NULL) LIMIT 1",
+            ctx.schema_name
+        );
+        let mut connection = ctx.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let removed: Option<(Uuid, bool)> = sqlx::query_as(&query)
+            .bind(&ids)
+            .fetch_optional(&mut *connection)
+            .await
+            .map_err(|e| ObserverError::DatabaseError(format!("Failed to check trash in {}: {}", ctx.schema_name, e)))?;
+
+        match removed {
+            Some((id, true)) => Err(ObserverError::ValidationError(format!("Record {} is deleted and cannot be updated", id))),
+            Some((id, false)) => Err(ObserverError::ValidationError(format!(
+                "Record {} is in the trash; revert it before updating", id
+            ))),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Ring 4: Record Enricher - fills defaults, normalizes formats and stamps timestamps before the write
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::database::columns::{coerce, load_column_formats};
+use crate::observer::traits::{Observer, Ring4, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 4: Record Enricher - completes records from the schema and the clock
+///
+/// Driven by the columns table: new records get the `default` of each missing property,
+/// so later rings see the values the row will hold, and values are converted to their
+/// column's canonical form (numeric strings to numbers, UUIDs and timestamps normalized;
+/// see `columns::coerce`). Updates convert the fields they change. Creates also get
+/// `created_at`/`updated_at` and updates `updated_at`, from `clock::now()`.
+#[derive(Default)]
+pub struct RecordEnricher;
+
+impl Observer for RecordEnricher {
+    fn name(&self) -> &'static str {
+        "RecordEnricher"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Enrichment
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Update)
+    }
+
+    fn applies_to_schema(&self, _schema: &str) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl Ring4 for RecordEnricher {
+    async fn execute(&self, ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        if ctx.records.is_empty() {
+            return Ok(());
+        }
+        let now = Value::String(crate::clock::now().to_rfc3339());
+        let mut connection = ctx.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let columns = load_column_formats(&mut *connection, &ctx.schema_name)
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+
+        if ctx.operation == Operation::Update {
+            for record in &mut ctx.records {
+                // Records without changes are skipped by the executor; keep them that way
+                let changes = record.changes();
+                if changes.is_empty() {
+                    continue;
+                }
+                for (field, change) in changes {
+                    let column = columns.iter().find(|c| c.column_name == field);
+                    let coerced = column.zip(change.new_value.as_ref()).and_then(|(c, value)| coerce(&c.pg_type, value));
+                    if let Some(value) = coerced {
+                        record.set(field.as_str(), value);
+                    }
+                }
+                record.set_system_field("updated_at", now.clone());
+            }
+            return Ok(());
+        }
+
+        for record in &mut ctx.records {
+            for column in &columns {
+                let field = column.column_name.as_str();
+                match record.get(field) {
+                    None => {
+                        if let Some(default) = &column.default {
+                            record.set(field, default.clone());
+                        }
+                    }
+                    Some(value) => {
+                        if let Some(value) = coerce(&column.pg_type, value) {
+                            record.set(field, value);
+                        }
+                    }
+                }
+            }
+            for field in ["created_at", "updated_at"] {
+                if record.get(field).is_none() {
+                    record.set_system_field(field, now.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+}
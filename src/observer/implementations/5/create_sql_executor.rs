@@ -1,12 +1,54 @@
 // Ring 5: Create SQL Executor - handles INSERT operations
 use async_trait::async_trait;
 use serde_json::{Value, Map};
-use sqlx::{PgPool, Row, Column, TypeInfo};
+use sqlx::{Row, Column, TypeInfo};
 use uuid::Uuid;
 
 use crate::observer::traits::{Observer, Ring5, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
+use crate::filter::sql_audit;
+use super::sql_retry;
+
+/// System timestamps set by observers as RFC 3339 strings, cast to fit their TIMESTAMP columns
+const TIMESTAMP_FIELDS: &[&str] = &["created_at", "updated_at", "trashed_at", "deleted_at"];
+
+/// Bind placeholder `$index` for `field`
+pub(crate) fn placeholder(field: &str, index: usize) -> String {
+    if TIMESTAMP_FIELDS.contains(&field) {
+        format!("${}::timestamptz", index)
+    } else {
+        format!("${}", index)
+    }
+}
+
+/// The INSERT for `record` and its bind parameters; None when the record has no fields
+pub(crate) fn insert_statement(record: &crate::database::record::Record, table_name: &str) -> Option<(String, Vec<Value>)> {
+    let record_data = record.to_hashmap();
+    if record_data.is_empty() {
+        return None;
+    }
+
+    let fields: Vec<&String> = record_data.keys().collect();
+
+    // Build parameterized INSERT query
+    let placeholders = fields.iter()
+        .enumerate()
+        .map(|(i, field)| placeholder(field, i + 1))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let field_list = fields.iter()
+        .map(|f| format!("\"{}\"", f))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let query = format!(
+        "INSERT INTO \"{}\" ({}) VALUES ({}) RETURNING *",
+        table_name, field_list, placeholders
+    );
+    Some((query, record_data.values().cloned().collect()))
+}
 
 /// Ring 5: Create SQL Executor - handles INSERT operations only
 #[derive(Default)]
@@ -38,15 +80,13 @@ impl Ring5 for CreateSqlExecutor {
             return Ok(());
         }
 
-        // Get tenant-specific database connection from context
-        let pool = ctx.get_pool().clone();
-        
         let mut results = Vec::new();
         let mut successful_operations = 0;
+        let mut retries = 0;
         
         // Process each Record
         for record in &ctx.records {
-            match self.execute_insert_record(&pool, record, &ctx.schema_name).await {
+            match self.execute_insert_record(ctx, record, &ctx.schema_name, &mut retries).await {
                 Ok(result) => {
                     results.push(result);
                     successful_operations += 1;
@@ -62,9 +102,10 @@ impl Ring5 for CreateSqlExecutor {
         }
         
         tracing::info!(
-            "CREATE operations completed: {}/{} successful",
-            successful_operations, ctx.records.len()
+            "CREATE operations completed: {}/{} successful ({} retries)",
+            successful_operations, ctx.records.len(), retries
         );
+        sql_retry::record_retries(ctx, retries);
         
         // Store results in context
         ctx.result = Some(results);
@@ -77,45 +118,28 @@ impl CreateSqlExecutor {
     /// Execute INSERT operation for a Record
     async fn execute_insert_record(
         &self, 
-        pool: &PgPool, 
+        ctx: &ObserverContext, 
         record: &crate::database::record::Record, 
-        table_name: &str
+        table_name: &str,
+        retries: &mut u32,
     ) -> Result<Value, ObserverError> {
-        let record_data = record.to_hashmap();
-        
-        if record_data.is_empty() {
+        let Some((query, values)) = insert_statement(record, table_name) else {
             tracing::debug!("Empty record for CREATE operation");
             return Ok(serde_json::json!({}));
-        }
-        
-        let fields: Vec<String> = record_data.keys().cloned().collect();
-        let values: Vec<&Value> = record_data.values().collect();
-        
-        tracing::debug!("Inserting record into {}: fields={:?}", table_name, fields);
-        
-        // Build parameterized INSERT query
-        let placeholders = (1..=fields.len())
-            .map(|i| format!("${}", i))
-            .collect::<Vec<_>>()
-            .join(", ");
-        
-        let field_list = fields.iter()
-            .map(|f| format!("\"{}\"", f))
-            .collect::<Vec<_>>()
-            .join(", ");
-        
-        let query = format!(
-            "INSERT INTO \"{}\" ({}) VALUES ({}) RETURNING *",
-            table_name, field_list, placeholders
-        );
-        
-        let mut q = sqlx::query(&query);
-        for value in &values {
-            q = bind_param(q, value);
-        }
-        
-        let row = q.fetch_one(pool).await
-            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        };
+        tracing::debug!("Inserting record into {}", table_name);
+        
+        sql_audit::audit(&query, values.len());
+        let row = sql_retry::retrying(ctx, retries, || async {
+            let mut connection = ctx.connection().await?;
+            let mut q = sqlx::query(&query);
+            for value in &values {
+                q = bind_param(q, value);
+            }
+            q.fetch_one(&mut *connection).await
+        })
+            .await
+            .map_err(sql_retry::write_error)?;
         
         self.row_to_json(row)
     }
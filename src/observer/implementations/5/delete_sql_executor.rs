@@ -1,12 +1,26 @@
 // Ring 5: Delete SQL Executor - handles soft DELETE operations
 use async_trait::async_trait;
 use serde_json::{Value, Map};
-use sqlx::{PgPool, Row, Column, TypeInfo};
+use sqlx::{Row, Column, TypeInfo};
 use uuid::Uuid;
 
 use crate::observer::traits::{Observer, Ring5, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
+use crate::filter::sql_audit;
+use super::sql_retry;
+
+/// The soft DELETE UPDATE for `record` and its id, the statement's only parameter
+pub(crate) fn delete_statement(record: &crate::database::record::Record, table_name: &str) -> Result<(String, String), ObserverError> {
+    let record_id = record.id().ok_or_else(|| {
+        ObserverError::DatabaseError("DELETE operation requires record ID".to_string())
+    })?;
+    let query = format!(
+        "UPDATE \"{}\" SET trashed_at = NOW(), updated_at = NOW() WHERE id = $1 RETURNING *",
+        table_name
+    );
+    Ok((query, record_id.to_string()))
+}
 
 /// Ring 5: Delete SQL Executor - handles soft DELETE operations only
 #[derive(Default)]
@@ -38,15 +52,13 @@ impl Ring5 for DeleteSqlExecutor {
             return Ok(());
         }
 
-        // Get database connection
-        let pool = ctx.get_pool().clone();
-        
         let mut results = Vec::new();
         let mut successful_operations = 0;
+        let mut retries = 0;
         
         // Process each Record
         for record in &ctx.records {
-            match self.execute_delete_record(&pool, record, &ctx.schema_name).await {
+            match self.execute_delete_record(ctx, record, &ctx.schema_name, &mut retries).await {
                 Ok(result) => {
                     results.push(result);
                     successful_operations += 1;
@@ -62,9 +74,10 @@ impl Ring5 for DeleteSqlExecutor {
         }
         
         tracing::info!(
-            "DELETE operations completed: {}/{} successful",
-            successful_operations, ctx.records.len()
+            "DELETE operations completed: {}/{} successful ({} retries)",
+            successful_operations, ctx.records.len(), retries
         );
+        sql_retry::record_retries(ctx, retries);
         
         // Store results in context
         ctx.result = Some(results);
@@ -77,25 +90,25 @@ impl DeleteSqlExecutor {
     /// Execute soft DELETE operation for a Record
     async fn execute_delete_record(
         &self, 
-        pool: &PgPool, 
+        ctx: &ObserverContext, 
         record: &crate::database::record::Record, 
-        table_name: &str
+        table_name: &str,
+        retries: &mut u32,
     ) -> Result<Value, ObserverError> {
-        let record_id = record.id().ok_or_else(|| {
-            ObserverError::DatabaseError("DELETE operation requires record ID".to_string())
-        })?;
+        let (query, record_id) = delete_statement(record, table_name)?;
         
         tracing::debug!("Soft deleting record {} from {}", record_id, table_name);
         
-        let query = format!(
-            "UPDATE \"{}\" SET trashed_at = NOW(), updated_at = NOW() WHERE id = $1 RETURNING *",
-            table_name
-        );
-        
-        let row = sqlx::query(&query)
-            .bind(record_id.to_string())
-            .fetch_one(pool)
-            .await.map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        sql_audit::audit(&query, 1);
+        let row = sql_retry::retrying(ctx, retries, || async {
+            let mut connection = ctx.connection().await?;
+            sqlx::query(&query)
+                .bind(&record_id)
+                .fetch_one(&mut *connection)
+                .await
+        })
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
         
         self.row_to_json(row)
     }
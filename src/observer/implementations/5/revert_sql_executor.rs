@@ -1,12 +1,26 @@
 // Ring 5: Revert SQL Executor - handles REVERT operations (undo soft delete)
 use async_trait::async_trait;
 use serde_json::{Value, Map};
-use sqlx::{PgPool, Row, Column, TypeInfo};
+use sqlx::{Row, Column, TypeInfo};
 use uuid::Uuid;
 
 use crate::observer::traits::{Observer, Ring5, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
+use crate::filter::sql_audit;
+use super::sql_retry;
+
+/// The REVERT UPDATE for `record` and its id, the statement's only parameter
+pub(crate) fn revert_statement(record: &crate::database::record::Record, table_name: &str) -> Result<(String, String), ObserverError> {
+    let record_id = record.id().ok_or_else(|| {
+        ObserverError::DatabaseError("REVERT operation requires record ID".to_string())
+    })?;
+    let query = format!(
+        "UPDATE \"{}\" SET trashed_at = NULL, updated_at = NOW() WHERE id = $1 RETURNING *",
+        table_name
+    );
+    Ok((query, record_id.to_string()))
+}
 
 /// Ring 5: Revert SQL Executor - handles REVERT operations only
 #[derive(Default)]
@@ -38,15 +52,13 @@ impl Ring5 for RevertSqlExecutor {
             return Ok(());
         }
 
-        // Get database connection
-        let pool = ctx.get_pool().clone();
-        
         let mut results = Vec::new();
         let mut successful_operations = 0;
+        let mut retries = 0;
         
         // Process each Record
         for record in &ctx.records {
-            match self.execute_revert_record(&pool, record, &ctx.schema_name).await {
+            match self.execute_revert_record(ctx, record, &ctx.schema_name, &mut retries).await {
                 Ok(result) => {
                     results.push(result);
                     successful_operations += 1;
@@ -62,9 +74,10 @@ impl Ring5 for RevertSqlExecutor {
         }
         
         tracing::info!(
-            "REVERT operations completed: {}/{} successful",
-            successful_operations, ctx.records.len()
+            "REVERT operations completed: {}/{} successful ({} retries)",
+            successful_operations, ctx.records.len(), retries
         );
+        sql_retry::record_retries(ctx, retries);
         
         // Store results in context
         ctx.result = Some(results);
@@ -77,25 +90,25 @@ impl RevertSqlExecutor {
     /// Execute REVERT operation for a Record (undo soft delete)
     async fn execute_revert_record(
         &self, 
-        pool: &PgPool, 
+        ctx: &ObserverContext, 
         record: &crate::database::record::Record, 
-        table_name: &str
+        table_name: &str,
+        retries: &mut u32,
     ) -> Result<Value, ObserverError> {
-        let record_id = record.id().ok_or_else(|| {
-            ObserverError::DatabaseError("REVERT operation requires record ID".to_string())
-        })?;
+        let (query, record_id) = revert_statement(record, table_name)?;
         
         tracing::debug!("Reverting soft-deleted record {} in {}", record_id, table_name);
         
-        let query = format!(
-            "UPDATE \"{}\" SET trashed_at = NULL, updated_at = NOW() WHERE id = $1 RETURNING *",
-            table_name
-        );
-        
-        let row = sqlx::query(&query)
-            .bind(record_id.to_string())
-            .fetch_one(pool)
-            .await.map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        sql_audit::audit(&query, 1);
+        let row = sql_retry::retrying(ctx, retries, || async {
+            let mut connection = ctx.connection().await?;
+            sqlx::query(&query)
+                .bind(&record_id)
+                .fetch_one(&mut *connection)
+                .await
+        })
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
         
         self.row_to_json(row)
     }
@@ -7,7 +7,11 @@ use uuid::Uuid;
 use crate::observer::traits::{Observer, Ring5, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
-use crate::filter::Filter;
+use crate::filter::{Filter, RecordAccess};
+use crate::filter::filter_join::FilterJoin;
+use crate::database::relationships::load_relationships;
+use crate::database::columns::{json_columns, load_columns};
+use crate::database::extensions::use_unaccent;
 
 /// Ring 5: Select SQL Executor - handles SELECT operations only
 #[derive(Default)]
@@ -38,18 +42,48 @@ impl Ring5 for SelectSqlExecutor {
         
         tracing::info!("Executing SELECT operation for schema: {}", ctx.schema_name);
         
-        // Get database connection
-        let pool = ctx.get_pool();
+        // Extension lookup on the pool, before holding a connection of our own
+        let unaccent = use_unaccent(ctx.get_pool(), filter_data.where_clause.as_ref())
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+
+        // Inside a transaction this is its connection, so the rows include its writes
+        let mut connection = ctx.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
         
         // Build SQL query using Filter system
-        let mut filter = Filter::new(&ctx.schema_name)
+        // Dotted where keys filter through declared relationships; load them only when used
+        let dotted = filter_data.where_clause.as_ref().is_some_and(|w| !FilterJoin::paths(w, &[]).is_empty());
+        let relationships = if dotted {
+            load_relationships(&mut *connection)
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?
+        } else {
+            Vec::new()
+        };
+
+        // Column references are validated against the table's columns, and dotted keys
+        // into JSONB columns are document paths rather than relationships
+        let table_columns = load_columns(&mut *connection, &ctx.schema_name)
+            .await
             .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let json = json_columns(&table_columns);
+        let columns = table_columns.iter().map(|(name, _)| name.clone()).collect();
+
+        let mut filter = Filter::new(&ctx.schema_name)
+            .map_err(ObserverError::from)?;
+        filter.relationships(relationships).column_types(&table_columns).columns(columns).json_columns(json).unaccent(unaccent);
+        // Row-level ACLs of the requesting user, when the caller supplied them
+        if let Some(access) = ctx.get_metadata::<RecordAccess>() {
+            filter.access(access.clone());
+        }
         
         filter.assign(filter_data)
-            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+            .map_err(ObserverError::from)?;
         
         let sql_result = filter.to_sql()
-            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+            .map_err(ObserverError::from)?;
         
         // Execute query
         let query_start = std::time::Instant::now();
@@ -59,7 +93,7 @@ impl Ring5 for SelectSqlExecutor {
             query = bind_param(query, param);
         }
         
-        let rows = query.fetch_all(pool).await.map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let rows = query.fetch_all(&mut *connection).await.map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
         
         let query_time = query_start.elapsed();
         
@@ -73,6 +107,10 @@ impl Ring5 for SelectSqlExecutor {
             
             // Convert row to Map<String, Value>
             for (i, column) in row.columns().iter().enumerate() {
+                // The generated search vector is an index, not record data
+                if column.type_info().name() == "TSVECTOR" {
+                    continue;
+                }
                 let column_name = column.name();
                 let value = self.extract_column_value(&row, i, column.type_info())?;
                 record_data.insert(column_name.to_string(), value);
@@ -150,13 +188,23 @@ impl SelectSqlExecutor {
                     Ok(Value::Null)
                 }
             }
-            "TIMESTAMPTZ" | "TIMESTAMP" => {
+            "TIMESTAMPTZ" => {
                 if let Ok(ts) = row.try_get::<Option<chrono::DateTime<chrono::Utc>>, _>(index) {
                     Ok(ts.map(|t| Value::String(t.to_rfc3339())).unwrap_or(Value::Null))
                 } else {
                     Ok(Value::Null)
                 }
             }
+            "TIMESTAMP" => {
+                // Full precision, so the value can be compared with the column again, e.g. in cursors
+                if let Ok(ts) = row.try_get::<Option<chrono::NaiveDateTime>, _>(index) {
+                    Ok(ts
+                        .map(|t| Value::String(t.and_utc().to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)))
+                        .unwrap_or(Value::Null))
+                } else {
+                    Ok(Value::Null)
+                }
+            }
             _ => {
                 tracing::warn!("Unhandled PostgreSQL type: {}, falling back to string", type_name);
                 Ok(Value::String(format!("<unsupported type: {}>", type_name)))
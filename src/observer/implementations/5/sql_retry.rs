@@ -0,0 +1,82 @@
+// Ring 5: Retry policy for transient Postgres failures shared by the write executors
+use std::future::Future;
+use std::time::Duration;
+
+use crate::database::checks::violated_check;
+use crate::database::unique::violated_fields;
+use crate::observer::context::{ObserverContext, SqlRetryStats};
+use crate::observer::error::ObserverError;
+
+/// Attempts per statement, including the first
+pub const MAX_ATTEMPTS: u32 = 4;
+
+/// Backoff before the first retry; doubles on each further retry
+const BASE_DELAY_MS: u64 = 25;
+
+/// serialization_failure and deadlock_detected: Postgres aborted the statement and
+/// running it again is safe when it ran in its own transaction
+const RETRYABLE_CODES: &[&str] = &["40001", "40P01"];
+
+pub fn is_retryable(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(e) => e.code().is_some_and(|code| RETRYABLE_CODES.contains(&code.as_ref())),
+        _ => false,
+    }
+}
+
+/// Run `attempt` until it succeeds, fails with a non-retryable error or runs out of
+/// attempts, sleeping with jittered exponential backoff between tries
+///
+/// Each retry is added to `retries`. When `ctx` runs in a transaction the failure has
+/// aborted all of it, so the first error is returned as is.
+pub async fn retrying<T, F, Fut>(ctx: &ObserverContext, retries: &mut u32, mut attempt: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let attempts = if ctx.transaction().is_some() { 1 } else { MAX_ATTEMPTS };
+    let mut tries = 1;
+    loop {
+        match attempt().await {
+            Err(e) if tries < attempts && is_retryable(&e) => {
+                let delay = backoff(tries);
+                tracing::warn!("Retrying statement after {:?} (attempt {}/{}): {}", delay, tries + 1, MAX_ATTEMPTS, e);
+                *retries += 1;
+                tries += 1;
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Observer error for a failed INSERT/UPDATE; unique and check violations keep the
+/// columns and rule involved
+pub fn write_error(error: sqlx::Error) -> ObserverError {
+    if let Some(fields) = violated_fields(&error) {
+        return ObserverError::UniqueViolation(fields);
+    }
+    match violated_check(&error) {
+        Some((field, rule)) => ObserverError::CheckViolation { field, rule: rule.to_string() },
+        None => ObserverError::DatabaseError(error.to_string()),
+    }
+}
+
+/// Add an executor's retries to the context's processing metadata
+pub fn record_retries(ctx: &mut ObserverContext, retries: u32) {
+    if retries == 0 {
+        return;
+    }
+    match ctx.get_metadata_mut::<SqlRetryStats>() {
+        Some(stats) => stats.retries += retries,
+        None => ctx.set_metadata(SqlRetryStats { retries }),
+    }
+}
+
+/// Half the exponential delay plus a random share of the other half, so concurrent
+/// writers that collided do not retry in lockstep
+fn backoff(tries: u32) -> Duration {
+    let ceiling = BASE_DELAY_MS << (tries - 1);
+    let jitter = (uuid::Uuid::new_v4().as_u128() % (ceiling / 2 + 1) as u128) as u64;
+    Duration::from_millis(ceiling / 2 + jitter)
+}
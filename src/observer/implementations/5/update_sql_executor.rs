@@ -1,12 +1,52 @@
 // Ring 5: Update SQL Executor - handles UPDATE operations
 use async_trait::async_trait;
 use serde_json::{Value, Map};
-use sqlx::{PgPool, Row, Column, TypeInfo};
+use sqlx::{Row, Column, TypeInfo};
 use uuid::Uuid;
 
 use crate::observer::traits::{Observer, Ring5, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
+use crate::filter::sql_audit;
+use super::create_sql_executor::placeholder;
+use super::sql_retry;
+
+/// The UPDATE for `record`'s changed fields and its bind parameters, the record id last;
+/// None when nothing changed
+pub(crate) fn update_statement(record: &crate::database::record::Record, table_name: &str) -> Result<Option<(String, Vec<Value>)>, ObserverError> {
+    let record_id = record.id().ok_or_else(|| {
+        ObserverError::DatabaseError("UPDATE operation requires record ID".to_string())
+    })?;
+    
+    // Get only changed fields for the update
+    let changes = record.changes();
+    let changed_fields: Vec<(&String, &crate::database::record::FieldChange)> = changes.iter()
+        .filter(|(_, change)| matches!(change.change_type, crate::database::record::ChangeType::Modified | crate::database::record::ChangeType::Added))
+        .collect();
+    
+    if changed_fields.is_empty() {
+        return Ok(None);
+    }
+    
+    // Build SET clause for only changed fields
+    let set_clauses: Vec<String> = changed_fields.iter()
+        .enumerate()
+        .map(|(i, (field, _))| format!("\"{}\" = {}", field, placeholder(field, i + 1)))
+        .collect();
+    
+    let mut values: Vec<Value> = changed_fields.iter()
+        .filter_map(|(_, change)| change.new_value.clone())
+        .collect();
+    
+    // Ring 4 stamps updated_at; fall back to the database clock if it did not
+    let touch = if changed_fields.iter().any(|(field, _)| field.as_str() == "updated_at") { "" } else { ", updated_at = NOW()" };
+    let query = format!(
+        "UPDATE \"{}\" SET {}{} WHERE id = ${} RETURNING *",
+        table_name, set_clauses.join(", "), touch, values.len() + 1
+    );
+    values.push(Value::String(record_id.to_string()));
+    Ok(Some((query, values)))
+}
 
 /// Ring 5: Update SQL Executor - handles UPDATE operations only
 #[derive(Default)]
@@ -38,15 +78,13 @@ impl Ring5 for UpdateSqlExecutor {
             return Ok(());
         }
 
-        // Get database connection
-        let pool = ctx.get_pool().clone();
-        
         let mut results = Vec::new();
         let mut successful_operations = 0;
+        let mut retries = 0;
         
         // Process each Record
         for record in &ctx.records {
-            match self.execute_update_record(&pool, record, &ctx.schema_name).await {
+            match self.execute_update_record(ctx, record, &ctx.schema_name, &mut retries).await {
                 Ok(result) => {
                     results.push(result);
                     successful_operations += 1;
@@ -62,9 +100,10 @@ impl Ring5 for UpdateSqlExecutor {
         }
         
         tracing::info!(
-            "UPDATE operations completed: {}/{} successful",
-            successful_operations, ctx.records.len()
+            "UPDATE operations completed: {}/{} successful ({} retries)",
+            successful_operations, ctx.records.len(), retries
         );
+        sql_retry::record_retries(ctx, retries);
         
         // Store results in context
         ctx.result = Some(results);
@@ -77,57 +116,28 @@ impl UpdateSqlExecutor {
     /// Execute UPDATE operation for a Record
     async fn execute_update_record(
         &self, 
-        pool: &PgPool, 
+        ctx: &ObserverContext, 
         record: &crate::database::record::Record, 
-        table_name: &str
+        table_name: &str,
+        retries: &mut u32,
     ) -> Result<Value, ObserverError> {
-        let record_id = record.id().ok_or_else(|| {
-            ObserverError::DatabaseError("UPDATE operation requires record ID".to_string())
-        })?;
-        
-        // Get only changed fields for the update
-        let changes = record.changes();
-        
-        if changes.is_empty() {
-            tracing::debug!("No changes for record {}, skipping update", record_id);
+        let Some((query, values)) = update_statement(record, table_name)? else {
+            tracing::debug!("No changes for record {:?}, skipping update", record.id());
             // Return the current record state
             return Ok(record.to_json());
-        }
-        
-        let changed_fields: Vec<(&String, &crate::database::record::FieldChange)> = changes.iter()
-            .filter(|(_, change)| matches!(change.change_type, crate::database::record::ChangeType::Modified | crate::database::record::ChangeType::Added))
-            .collect();
-        
-        if changed_fields.is_empty() {
-            tracing::debug!("No meaningful changes for record {}, skipping update", record_id);
-            return Ok(record.to_json());
-        }
-        
-        tracing::debug!("Updating record {} in {}: fields={:?}", 
-                       record_id, table_name, changed_fields.iter().map(|(k, _)| k).collect::<Vec<_>>());
-        
-        // Build SET clause for only changed fields
-        let set_clauses: Vec<String> = changed_fields.iter()
-            .enumerate()
-            .map(|(i, (field, _))| format!("\"{}\" = ${}", field, i + 1))
-            .collect();
-        
-        let values: Vec<Value> = changed_fields.iter()
-            .filter_map(|(_, change)| change.new_value.clone())
-            .collect();
-        
-        let query = format!(
-            "UPDATE \"{}\" SET {}, updated_at = NOW() WHERE id = ${} RETURNING *",
-            table_name, set_clauses.join(", "), values.len() + 1
-        );
-        
-        let mut q = sqlx::query(&query);
-        for value in &values {
-            q = bind_param(q, value);
-        }
-        q = q.bind(record_id.to_string());
-        
-        let row = q.fetch_one(pool).await.map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        };
+        
+        sql_audit::audit(&query, values.len());
+        let row = sql_retry::retrying(ctx, retries, || async {
+            let mut connection = ctx.connection().await?;
+            let mut q = sqlx::query(&query);
+            for value in &values {
+                q = bind_param(q, value);
+            }
+            q.fetch_one(&mut *connection).await
+        })
+            .await
+            .map_err(sql_retry::write_error)?;
         
         self.row_to_json(row)
     }
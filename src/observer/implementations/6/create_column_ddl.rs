@@ -3,6 +3,8 @@ use async_trait::async_trait;
 use serde_json::{Value, Map};
 use sqlx::Row;
 
+use crate::database::checks::{add_check_ddl, column_checks};
+use crate::database::search::sync_schema_search;
 use crate::observer::traits::{Observer, Ring6, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
@@ -63,12 +65,27 @@ impl Ring6 for CreateColumnDdl {
             let ddl = self.generate_add_column_ddl(&table_name, &record.to_map())?;
             
             // Execute DDL
-            let pool = context.get_pool();
+            let mut connection = context.connection()
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
                 
             sqlx::query(&ddl)
-                .execute(pool)
+                .execute(&mut *connection)
                 .await
                 .map_err(|e| ObserverError::DatabaseError(format!("Failed to add column {} to table {}: {}", column_name, table_name, e)))?;
+
+            for check in column_checks(&table_name, column_name, &record.to_map()) {
+                sqlx::query(&add_check_ddl(&table_name, &check))
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to add {} check on {}.{}: {}", check.rule, table_name, column_name, e)))?;
+            }
+
+            if record.get("searchable").and_then(|v| v.as_bool()).unwrap_or(false) {
+                sync_schema_search(&mut connection, schema_name, &table_name, None)
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to update search vector of {}: {}", table_name, e)))?;
+            }
                 
             tracing::info!("Added column '{}' to table '{}' for schema '{}'", column_name, table_name, schema_name);
         }
@@ -91,14 +108,15 @@ impl CreateColumnDdl {
         Ok(false)
     }
     
-    async fn get_table_name_for_schema(&self, _context: &ObserverContext, schema_name: &str) -> Result<String, ObserverError> {
-        // Get database pool directly (same pattern as Ring 5 observers)
-        let pool = crate::database::manager::DatabaseManager::main_pool().await
+    async fn get_table_name_for_schema(&self, context: &ObserverContext, schema_name: &str) -> Result<String, ObserverError> {
+        // Same connection as Ring 5 observers, so the lookup sees this request's transaction
+        let mut connection = context.connection()
+            .await
             .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
-            
+
         let row = sqlx::query("SELECT table_name FROM schemas WHERE name = $1 AND deleted_at IS NULL")
             .bind(schema_name)
-            .fetch_one(&pool)
+            .fetch_one(&mut *connection)
             .await
             .map_err(|e| ObserverError::DatabaseError(format!("Failed to get table name for schema {}: {}", schema_name, e)))?;
             
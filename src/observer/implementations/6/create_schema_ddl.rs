@@ -2,10 +2,21 @@
 use async_trait::async_trait;
 use serde_json::Value;
 
+use crate::database::checks::{add_check_ddl, property_checks};
+use crate::database::search::{searchable_fields, sync_search_vector};
+use crate::database::sequences::{sequence_ddl, sequence_fields};
+use crate::database::slugs::{slug_fields, slug_index_ddl};
+use crate::database::unique::{unique_groups, unique_index_ddl};
 use crate::observer::traits::{Observer, Ring6, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
 
+/// Columns every table gets, which schema properties cannot redefine
+pub(crate) const SYSTEM_FIELDS: &[&str] = &[
+    "id", "access_read", "access_edit", "access_full", "access_deny",
+    "created_at", "updated_at", "trashed_at", "deleted_at",
+];
+
 /// Ring 6: Create Schema DDL Executor - executes CREATE TABLE when schema record is inserted
 #[derive(Default)]
 pub struct CreateSchemaDdl;
@@ -55,12 +66,68 @@ impl Ring6 for CreateSchemaDdl {
             let ddl = self.generate_create_table_ddl(table_name, definition)?;
             
             // Execute DDL
-            let pool = context.get_pool();
+            let mut connection = context.connection()
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
                 
             sqlx::query(&ddl)
-                .execute(pool)
+                .execute(&mut *connection)
                 .await
                 .map_err(|e| ObserverError::DatabaseError(format!("Failed to create table {}: {}", table_name, e)))?;
+
+            // Publish row changes on monk_changes, including writes made outside the API
+            let trigger = format!(
+                "CREATE TRIGGER \"{}_notify_change\" AFTER INSERT OR UPDATE OR DELETE ON \"{}\" \
+                 FOR EACH ROW EXECUTE FUNCTION monk_notify_change()",
+                table_name, table_name
+            );
+            sqlx::query(&trigger)
+                .execute(&mut *connection)
+                .await
+                .map_err(|e| ObserverError::DatabaseError(format!("Failed to add change trigger to {}: {}", table_name, e)))?;
+
+            // x-monk-slug properties are secondary keys, unique within the table
+            for slug_field in slug_fields(definition) {
+                sqlx::query(&slug_index_ddl(table_name, &slug_field.field))
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to add slug index on {}.{}: {}", table_name, slug_field.field, e)))?;
+            }
+
+            // x-monk-unique column groups
+            for fields in unique_groups(definition) {
+                sqlx::query(&unique_index_ddl(table_name, &fields))
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to add unique index on {}({}): {}", table_name, fields.join(", "), e)))?;
+            }
+
+            // Numeric ranges, string lengths, patterns and enums are also enforced by Postgres,
+            // so writes made outside the API cannot break the schema
+            for check in self.property_checks(table_name, definition) {
+                sqlx::query(&add_check_ddl(table_name, &check))
+                    .execute(&mut *connection)
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to add {} check on {}.{}: {}", check.rule, table_name, check.field, e)))?;
+            }
+
+            // x-monk-sequence properties draw their numbers from a sequence owned by the column
+            for sequence in sequence_fields(definition) {
+                for ddl in sequence_ddl(table_name, &sequence) {
+                    sqlx::query(&ddl)
+                        .execute(&mut *connection)
+                        .await
+                        .map_err(|e| ObserverError::DatabaseError(format!("Failed to add sequence for {}.{}: {}", table_name, sequence.field, e)))?;
+                }
+            }
+
+            // x-monk-searchable properties are indexed together for $search
+            let searchable = searchable_fields(definition);
+            if !searchable.is_empty() {
+                sync_search_vector(&mut connection, table_name, &searchable)
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to add search vector to {}: {}", table_name, e)))?;
+            }
                 
             tracing::info!("Created table '{}' for schema '{}'", table_name, schema_name);
         }
@@ -70,6 +137,20 @@ impl Ring6 for CreateSchemaDdl {
 }
 
 impl CreateSchemaDdl {
+    fn property_checks(&self, table_name: &str, definition: &Value) -> Vec<crate::database::checks::CheckConstraint> {
+        definition
+            .get("properties")
+            .and_then(|p| p.as_object())
+            .map(|properties| {
+                properties
+                    .iter()
+                    .filter(|(field, _)| !SYSTEM_FIELDS.contains(&field.as_str()))
+                    .flat_map(|(field, property)| property_checks(table_name, field, property))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn generate_create_table_ddl(&self, table_name: &str, definition: &Value) -> Result<String, ObserverError> {
         // Parse the JSON Schema definition
         let schema_def = definition.as_object()
@@ -100,8 +181,7 @@ impl CreateSchemaDdl {
         // Schema-specific fields
         for (field_name, property) in properties {
             // Skip system fields
-            if ["id", "access_read", "access_edit", "access_full", "access_deny", 
-                "created_at", "updated_at", "trashed_at", "deleted_at"].contains(&field_name.as_str()) {
+            if SYSTEM_FIELDS.contains(&field_name.as_str()) {
                 continue;
             }
 
@@ -2,6 +2,7 @@
 use async_trait::async_trait;
 use sqlx::Row;
 
+use crate::database::search::sync_schema_search;
 use crate::observer::traits::{Observer, Ring6, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
@@ -69,10 +70,17 @@ impl Ring6 for DeleteColumnDdl {
             let ddl = format!("ALTER TABLE \"{}\" DROP COLUMN IF EXISTS \"{}\"", table_name, column_name);
             
             // Execute DDL
-            let pool = context.get_pool();
+            let mut connection = context.connection()
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+
+            // The search vector depends on its columns, so it is regenerated without this one first
+            sync_schema_search(&mut connection, schema_name, &table_name, Some(column_name))
+                .await
+                .map_err(|e| ObserverError::DatabaseError(format!("Failed to update search vector of {}: {}", table_name, e)))?;
                 
             sqlx::query(&ddl)
-                .execute(pool)
+                .execute(&mut *connection)
                 .await
                 .map_err(|e| ObserverError::DatabaseError(format!("Failed to drop column {} from table {}: {}", column_name, table_name, e)))?;
                 
@@ -85,13 +93,15 @@ impl Ring6 for DeleteColumnDdl {
 
 impl DeleteColumnDdl {
     async fn schema_exists_and_active(&self, context: &ObserverContext, schema_name: &str) -> Result<bool, ObserverError> {
-        let pool = context.get_pool();
+        let mut connection = context.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
             
         let result = sqlx::query(
             "SELECT COUNT(*) as count FROM schemas WHERE name = $1 AND deleted_at IS NULL AND trashed_at IS NULL"
         )
         .bind(schema_name)
-        .fetch_one(pool)
+        .fetch_one(&mut *connection)
         .await
         .map_err(|e| ObserverError::DatabaseError(format!("Failed to check schema existence: {}", e)))?;
         
@@ -100,11 +110,13 @@ impl DeleteColumnDdl {
     }
     
     async fn get_table_name_for_schema(&self, context: &ObserverContext, schema_name: &str) -> Result<String, ObserverError> {
-        let pool = context.get_pool();
+        let mut connection = context.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
             
         let row = sqlx::query("SELECT table_name FROM schemas WHERE name = $1 AND deleted_at IS NULL")
             .bind(schema_name)
-            .fetch_one(pool)
+            .fetch_one(&mut *connection)
             .await
             .map_err(|e| ObserverError::DatabaseError(format!("Failed to get table name for schema {}: {}", schema_name, e)))?;
             
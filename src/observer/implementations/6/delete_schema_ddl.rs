@@ -1,5 +1,6 @@
 // Ring 6: Delete Schema DDL Executor - handles DROP TABLE after schema record delete
 use async_trait::async_trait;
+use sqlx::PgConnection;
 
 use crate::observer::traits::{Observer, Ring6, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
@@ -65,17 +66,19 @@ impl Ring6 for DeleteSchemaDdl {
             let ddl = format!("DROP TABLE IF EXISTS \"{}\"", table_name);
             
             // Execute DDL
-            let pool = context.get_pool();
+            let mut connection = context.connection()
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
                 
             sqlx::query(&ddl)
-                .execute(pool)
+                .execute(&mut *connection)
                 .await
                 .map_err(|e| ObserverError::DatabaseError(format!("Failed to drop table {}: {}", table_name, e)))?;
                 
             tracing::info!("Dropped table '{}' for deleted schema '{}'", table_name, schema_name);
             
             // Also clean up related column records
-            self.cleanup_column_records(context, schema_name).await?;
+            self.cleanup_column_records(&mut connection, schema_name).await?;
         }
 
         Ok(())
@@ -87,17 +90,15 @@ impl DeleteSchemaDdl {
         ["schemas", "users", "columns"].contains(&schema_name)
     }
     
-    async fn cleanup_column_records(&self, context: &ObserverContext, schema_name: &str) -> Result<(), ObserverError> {
-        let pool = context.get_pool();
-            
+    async fn cleanup_column_records(&self, connection: &mut PgConnection, schema_name: &str) -> Result<(), ObserverError> {
         // Soft delete all column records for this schema
-        let now = chrono::Utc::now().to_rfc3339();
+        let now = crate::clock::now().to_rfc3339();
         sqlx::query(
             "UPDATE columns SET deleted_at = $1, updated_at = $1 WHERE schema_name = $2 AND deleted_at IS NULL"
         )
         .bind(&now)
         .bind(schema_name)
-        .execute(pool)
+        .execute(connection)
         .await
         .map_err(|e| ObserverError::DatabaseError(format!("Failed to cleanup column records for schema {}: {}", schema_name, e)))?;
         
@@ -0,0 +1,81 @@
+// Ring 6: Record Count Maintainer - keeps record_counts in step with record changes
+use async_trait::async_trait;
+
+use crate::observer::traits::{Observer, Ring6, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Savepoint around the counter update when the write runs in a transaction
+const SAVEPOINT: &str = "record_count";
+
+/// Ring 6: Record Count Maintainer - adjusts the exact counter after create, delete and revert
+///
+/// Only schemas whose counter has been seeded (by an exact count request) are updated, so
+/// writes to schemas nobody counts cost a single no-op UPDATE.
+#[derive(Default)]
+pub struct RecordCountMaintainer;
+
+impl Observer for RecordCountMaintainer {
+    fn name(&self) -> &'static str {
+        "RecordCountMaintainer"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::PostDatabase
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Delete | Operation::Revert)
+    }
+
+    fn applies_to_schema(&self, schema: &str) -> bool {
+        schema != "record_counts"
+    }
+}
+
+#[async_trait]
+impl Ring6 for RecordCountMaintainer {
+    async fn execute(&self, context: &mut ObserverContext) -> Result<(), ObserverError> {
+        // Ring 5 reports only the rows it actually changed
+        let changed = context.result.as_ref().map(|r| r.len()).unwrap_or(0) as i64;
+        if changed == 0 {
+            return Ok(());
+        }
+
+        // Delete is a soft delete (trashed_at), which removes rows from the live count
+        let delta = match context.operation {
+            Operation::Delete => -changed,
+            _ => changed,
+        };
+
+        // Inside a transaction a failed statement would abort the write too, so it runs
+        // under a savepoint that is undone on failure
+        if let Some(transaction) = context.transaction() {
+            transaction.savepoint(SAVEPOINT).await?;
+        }
+        let mut connection = context.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        let result = sqlx::query(
+            "UPDATE record_counts SET count = GREATEST(count + $2, 0), updated_at = now() WHERE schema_name = $1"
+        )
+            .bind(&context.schema_name)
+            .bind(delta)
+            .execute(&mut *connection)
+            .await;
+        drop(connection);
+
+        // A stale counter must not fail the write that already happened
+        if let Err(e) = &result {
+            tracing::warn!("Failed to update record count for {}: {}", context.schema_name, e);
+        }
+        if let Some(transaction) = context.transaction() {
+            match result {
+                Ok(_) => transaction.release(SAVEPOINT).await?,
+                Err(_) => transaction.rollback_to(SAVEPOINT).await?,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -3,6 +3,8 @@ use async_trait::async_trait;
 use serde_json::{Value, Map};
 use sqlx::Row;
 
+use crate::database::checks::{add_check_ddl, column_checks, drop_checks_ddl};
+use crate::database::search::sync_schema_search;
 use crate::observer::traits::{Observer, Ring6, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
@@ -75,19 +77,38 @@ impl Ring6 for UpdateColumnDdl {
             // - Adding/removing DEFAULT values
             // - Changing column comments/descriptions
             
-            let ddl_operations = self.generate_safe_column_updates(&table_name, &record.to_map())?;
+            let mut ddl_operations = self.generate_safe_column_updates(&table_name, &record.to_map())?;
+
+            // Bounds changed: replace the column's CHECK constraints
+            if ["minimum", "maximum", "pattern_regex", "enum_values"].iter().any(|field| record.changed(field)) {
+                ddl_operations.extend(drop_checks_ddl(&table_name, column_name));
+                ddl_operations.extend(
+                    column_checks(&table_name, column_name, &record.to_map())
+                        .iter()
+                        .map(|check| add_check_ddl(&table_name, check)),
+                );
+            }
             
+            let mut connection = context.connection()
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+
+            // The generated search vector covers every searchable column of the table
+            if record.changed("searchable") {
+                sync_schema_search(&mut connection, schema_name, &table_name, None)
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to update search vector of {}: {}", table_name, e)))?;
+            }
+
             if ddl_operations.is_empty() {
                 tracing::debug!("No safe DDL operations for column '{}' update", column_name);
                 continue;
             }
 
             // Execute DDL operations
-            let pool = context.get_pool();
-                
             for ddl in ddl_operations {
                 sqlx::query(&ddl)
-                    .execute(pool)
+                    .execute(&mut *connection)
                     .await
                     .map_err(|e| ObserverError::DatabaseError(format!("Failed to update column {} in table {}: {}", column_name, table_name, e)))?;
             }
@@ -101,13 +122,15 @@ impl Ring6 for UpdateColumnDdl {
 
 impl UpdateColumnDdl {
     async fn schema_exists_and_active(&self, context: &ObserverContext, schema_name: &str) -> Result<bool, ObserverError> {
-        let pool = context.get_pool();
+        let mut connection = context.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
             
         let result = sqlx::query(
             "SELECT COUNT(*) as count FROM schemas WHERE name = $1 AND deleted_at IS NULL AND trashed_at IS NULL"
         )
         .bind(schema_name)
-        .fetch_one(pool)
+        .fetch_one(&mut *connection)
         .await
         .map_err(|e| ObserverError::DatabaseError(format!("Failed to check schema existence: {}", e)))?;
         
@@ -116,11 +139,13 @@ impl UpdateColumnDdl {
     }
     
     async fn get_table_name_for_schema(&self, context: &ObserverContext, schema_name: &str) -> Result<String, ObserverError> {
-        let pool = context.get_pool();
+        let mut connection = context.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
             
         let row = sqlx::query("SELECT table_name FROM schemas WHERE name = $1 AND deleted_at IS NULL")
             .bind(schema_name)
-            .fetch_one(pool)
+            .fetch_one(&mut *connection)
             .await
             .map_err(|e| ObserverError::DatabaseError(format!("Failed to get table name for schema {}: {}", schema_name, e)))?;
             
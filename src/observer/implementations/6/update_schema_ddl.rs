@@ -1,6 +1,8 @@
 // Ring 6: Update Schema DDL Executor - handles table alterations after schema record update
 use async_trait::async_trait;
 
+use crate::database::search::{searchable_fields, sync_search_vector};
+use crate::database::unique::{unique_groups, unique_index_ddl};
 use crate::observer::traits::{Observer, Ring6, ObserverRing, Operation};
 use crate::observer::context::ObserverContext;
 use crate::observer::error::ObserverError;
@@ -65,6 +67,27 @@ impl Ring6 for UpdateSchemaDdl {
             // Schema-level updates are typically metadata changes (status, description, etc.)
             // The actual table structure changes happen via column record updates
             
+            let mut connection = context.connection()
+                .await
+                .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+
+            // Unique groups added to the definition get their index; existing ones are kept
+            if let Some(definition) = record.get("definition") {
+                for fields in unique_groups(definition) {
+                    sqlx::query(&unique_index_ddl(table_name, &fields))
+                        .execute(&mut *connection)
+                        .await
+                        .map_err(|e| ObserverError::DatabaseError(format!("Failed to add unique index on {}({}): {}", table_name, fields.join(", "), e)))?;
+                }
+            }
+
+            // A changed definition may mark other properties x-monk-searchable
+            if let Some(definition) = record.get("definition").filter(|_| record.changed("definition")) {
+                sync_search_vector(&mut connection, table_name, &searchable_fields(definition))
+                    .await
+                    .map_err(|e| ObserverError::DatabaseError(format!("Failed to update search vector of {}: {}", table_name, e)))?;
+            }
+
             tracing::info!("Schema '{}' metadata updated (table: '{}')", schema_name, table_name);
             
             // Note: Major schema restructuring (like renaming tables) would require
@@ -0,0 +1,65 @@
+// Ring 7: Audit Logger - records who changed which records into audit_log
+use async_trait::async_trait;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::database::audit::{self, AuditActor, NewAuditEntry};
+use crate::observer::traits::{Observer, Ring7, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 7: Audit Logger - one audit_log row per record written by the operation
+///
+/// Runs only when `security.enable_audit_logging` is on. The actor is whatever the
+/// repository attached to the pipeline; writes made outside a request have none.
+#[derive(Default)]
+pub struct AuditLogger;
+
+impl Observer for AuditLogger {
+    fn name(&self) -> &'static str {
+        "AuditLogger"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Audit
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Update | Operation::Delete | Operation::Revert)
+            && crate::config::config().security.enable_audit_logging
+    }
+
+    fn applies_to_schema(&self, schema: &str) -> bool {
+        // Bookkeeping tables would only audit themselves
+        !matches!(schema, "audit_log" | "record_counts")
+    }
+}
+
+#[async_trait]
+impl Ring7 for AuditLogger {
+    async fn execute(&self, context: &ObserverContext) -> Result<(), ObserverError> {
+        // Ring 5 reports the rows as written, including ids assigned on create
+        let written = context.result.as_deref().unwrap_or_default();
+        let entries: Vec<NewAuditEntry> = written
+            .iter()
+            .filter_map(Value::as_object)
+            .map(|row| {
+                let record_id = row.get("id").and_then(|v| v.as_str()).and_then(|s| s.parse::<Uuid>().ok());
+                let request = context.records.iter().find(|r| r.id().is_some() && r.id() == record_id);
+                NewAuditEntry {
+                    schema_name: context.schema_name.clone(),
+                    record_id,
+                    operation: context.operation,
+                    changes: audit::change_summary(context.operation, row, request),
+                }
+            })
+            .collect();
+
+        let mut connection = context.connection()
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))?;
+        audit::record(&mut *connection, context.get_metadata::<AuditActor>(), entries)
+            .await
+            .map_err(|e| ObserverError::DatabaseError(e.to_string()))
+    }
+}
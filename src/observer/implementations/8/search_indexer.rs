@@ -0,0 +1,74 @@
+// Ring 8: Search Indexer - mirrors record changes into a Meilisearch-compatible index
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::observer::deferred;
+use crate::observer::traits::{Observer, Ring8, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 8: Search Indexer - keeps the index `<database>_<schema>` at `api.search_index_url`
+/// in step with the schema's records
+///
+/// Created, updated and reverted rows are upserted by `id`; deleted (trashed) rows are
+/// removed from the index.
+#[derive(Default)]
+pub struct SearchIndexer;
+
+impl Observer for SearchIndexer {
+    fn name(&self) -> &'static str {
+        "SearchIndexer"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Integration
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Update | Operation::Delete | Operation::Revert)
+            && !crate::config::config().api.search_index_url.is_empty()
+    }
+
+    fn applies_to_schema(&self, schema: &str) -> bool {
+        // System tables are not searchable content
+        !matches!(schema, "schemas" | "columns" | "users" | "audit_log" | "record_counts")
+    }
+
+    fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(30)
+    }
+}
+
+#[async_trait]
+impl Ring8 for SearchIndexer {
+    async fn execute(&self, context: &ObserverContext) -> Result<(), ObserverError> {
+        let rows = context.result.as_deref().unwrap_or_default();
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let api = &crate::config::config().api;
+        let index = format!("{}_{}", deferred::tenant_database(context), context.schema_name);
+        let (url, body) = match context.operation {
+            Operation::Delete => (
+                format!("{}/indexes/{}/documents/delete-batch", api.search_index_url, index),
+                json!(deferred::written_ids(context)),
+            ),
+            _ => (
+                format!("{}/indexes/{}/documents?primaryKey=id", api.search_index_url, index),
+                Value::Array(rows.to_vec()),
+            ),
+        };
+
+        let mut request = deferred::http_client().post(&url).json(&body);
+        if !api.search_index_api_key.is_empty() {
+            request = request.bearer_auth(&api.search_index_api_key);
+        }
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|e| ObserverError::SystemError(format!("search index update of {} failed: {}", index, e)))
+    }
+}
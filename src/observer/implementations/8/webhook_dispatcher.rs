@@ -0,0 +1,72 @@
+// Ring 8: Webhook Dispatcher - POSTs committed record changes to the configured URLs
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::canonical;
+use crate::database::audit::{self, AuditActor};
+use crate::services::webhook_signing;
+use crate::observer::deferred;
+use crate::observer::traits::{Observer, Ring8, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 8: Webhook Dispatcher - one JSON POST per write to every configured webhook endpoint
+///
+/// The body carries the tenant database, schema, operation, acting user and the rows as
+/// written. It is canonical JSON, so a receiver that re-serializes the parsed body gets
+/// the signed bytes back. Endpoints with a secret get a timestamped `X-Monk-Signature`
+/// (see `webhook_signing`). Deliveries are not retried; a failing endpoint is logged and
+/// the others are still called.
+#[derive(Default)]
+pub struct WebhookDispatcher;
+
+impl Observer for WebhookDispatcher {
+    fn name(&self) -> &'static str {
+        "WebhookDispatcher"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Integration
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Update | Operation::Delete | Operation::Revert)
+            && crate::config::config().api.has_webhooks()
+    }
+
+    fn applies_to_schema(&self, schema: &str) -> bool {
+        !matches!(schema, "audit_log" | "record_counts")
+    }
+
+    fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(30)
+    }
+}
+
+#[async_trait]
+impl Ring8 for WebhookDispatcher {
+    async fn execute(&self, context: &ObserverContext) -> Result<(), ObserverError> {
+        let api = &crate::config::config().api;
+        let body = canonical::to_string(&json!({
+            "database": deferred::tenant_database(context),
+            "schema": context.schema_name,
+            "operation": audit::operation_name(context.operation),
+            "actor": context.get_metadata::<AuditActor>().map(|actor| actor.user.clone()),
+            "records": context.result.as_deref().unwrap_or_default(),
+            "timestamp": crate::clock::now(),
+        }));
+
+        let mut failed = Vec::new();
+        for endpoint in api.webhook_endpoints() {
+            if let Err(e) = webhook_signing::deliver(deferred::http_client(), &endpoint, &body).await {
+                failed.push(format!("{}: {}", endpoint.id, e));
+            }
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(ObserverError::SystemError(format!("webhook delivery failed ({})", failed.join("; "))))
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Ring 9: Cache Invalidator - purges cached API responses for changed records
+use async_trait::async_trait;
+use reqwest::Method;
+
+use crate::observer::deferred;
+use crate::observer::traits::{Observer, Ring9, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 9: Cache Invalidator - sends `PURGE` to the HTTP cache at `api.cache_purge_url`
+///
+/// Purges the schema's collection path and the path of every written record. The tenant
+/// database goes in `X-Monk-Database`, for caches that vary responses by tenant.
+#[derive(Default)]
+pub struct CacheInvalidator;
+
+impl Observer for CacheInvalidator {
+    fn name(&self) -> &'static str {
+        "CacheInvalidator"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Notification
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        matches!(op, Operation::Create | Operation::Update | Operation::Delete | Operation::Revert)
+            && !crate::config::config().api.cache_purge_url.is_empty()
+    }
+
+    fn applies_to_schema(&self, _schema: &str) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl Ring9 for CacheInvalidator {
+    async fn execute(&self, context: &ObserverContext) -> Result<(), ObserverError> {
+        let base = format!("{}/api/data/{}", crate::config::config().api.cache_purge_url, context.schema_name);
+        let database = deferred::tenant_database(context);
+        let purge = Method::from_bytes(b"PURGE").expect("PURGE is a valid method");
+
+        let paths = std::iter::once(base.clone()).chain(deferred::written_ids(context).into_iter().map(|id| format!("{}/{}", base, id)));
+        for path in paths {
+            deferred::http_client()
+                .request(purge.clone(), &path)
+                .header("X-Monk-Database", &database)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(|e| ObserverError::SystemError(format!("cache purge of {} failed: {}", path, e)))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+// Ring 9: Recent View Tracker - keeps each user's recently viewed records
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::database::favorites::{self, RecordViewer};
+use crate::observer::deferred;
+use crate::observer::traits::{Observer, Ring9, ObserverRing, Operation};
+use crate::observer::context::ObserverContext;
+use crate::observer::error::ObserverError;
+
+/// Ring 9: Recent View Tracker - records the records a user opened, for /api/auth/recent
+///
+/// Runs only on selects marked with a `RecordViewer`, on schemas that set
+/// `"x-monk-track-views": true`. The user's views beyond `api.recent_views_per_user` are dropped.
+#[derive(Default)]
+pub struct RecentViewTracker;
+
+impl Observer for RecentViewTracker {
+    fn name(&self) -> &'static str {
+        "RecentViewTracker"
+    }
+
+    fn ring(&self) -> ObserverRing {
+        ObserverRing::Notification
+    }
+
+    fn applies_to_operation(&self, op: Operation) -> bool {
+        op == Operation::Select && crate::config::config().api.recent_views_per_user > 0
+    }
+
+    fn applies_to_schema(&self, _schema: &str) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl Ring9 for RecentViewTracker {
+    async fn execute(&self, context: &ObserverContext) -> Result<(), ObserverError> {
+        let Some(viewer) = context.get_metadata::<RecordViewer>() else {
+            return Ok(());
+        };
+        let ids: Vec<Uuid> = deferred::written_ids(context).iter().filter_map(|id| id.parse().ok()).collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let pool = context.get_pool();
+        let tracked = favorites::tracks_views(pool, &context.schema_name)
+            .await
+            .map_err(|e| ObserverError::SystemError(format!("view tracking lookup failed: {}", e)))?;
+        if !tracked {
+            return Ok(());
+        }
+
+        let keep = crate::config::config().api.recent_views_per_user;
+        favorites::record_views(pool, viewer.user_id, &context.schema_name, &ids, keep)
+            .await
+            .map_err(|e| ObserverError::SystemError(format!("recording views failed: {}", e)))
+    }
+}
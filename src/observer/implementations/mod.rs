@@ -5,6 +5,30 @@
 #[path = "0/data_preparation.rs"]
 pub mod data_preparation;
 
+// Ring 1: Input Validation - ids and input checks before the database
+#[path = "1/record_id_generator.rs"]
+pub mod record_id_generator;
+#[path = "1/slug_generator.rs"]
+pub mod slug_generator;
+#[path = "1/sequence_allocator.rs"]
+pub mod sequence_allocator;
+#[path = "1/schema_validator.rs"]
+pub mod schema_validator;
+
+// Ring 2: Security - access control on writes
+#[path = "2/access_control.rs"]
+pub mod access_control;
+#[path = "2/managed_schema_guard.rs"]
+pub mod managed_schema_guard;
+
+// Ring 3: Business Logic - record state rules
+#[path = "3/trash_guard.rs"]
+pub mod trash_guard;
+
+// Ring 4: Enrichment - defaults and timestamps
+#[path = "4/record_enricher.rs"]
+pub mod record_enricher;
+
 // Ring 5: Database - SQL execution
 #[path = "5/create_sql_executor.rs"]
 pub mod create_sql_executor;
@@ -16,6 +40,8 @@ pub mod revert_sql_executor;
 pub mod select_sql_executor;
 #[path = "5/update_sql_executor.rs"]
 pub mod update_sql_executor;
+#[path = "5/sql_retry.rs"]
+pub mod sql_retry;
 
 // Ring 6: Post-Database - DDL operations following record changes
 #[path = "6/create_column_ddl.rs"]
@@ -30,6 +56,24 @@ pub mod delete_schema_ddl;
 pub mod update_column_ddl;
 #[path = "6/update_schema_ddl.rs"]
 pub mod update_schema_ddl;
+#[path = "6/record_count_maintainer.rs"]
+pub mod record_count_maintainer;
+
+// Ring 7: Audit - change tracking after the write
+#[path = "7/audit_logger.rs"]
+pub mod audit_logger;
+
+// Ring 8: Integration - external services, run in the background after commit
+#[path = "8/webhook_dispatcher.rs"]
+pub mod webhook_dispatcher;
+#[path = "8/search_indexer.rs"]
+pub mod search_indexer;
+
+// Ring 9: Notification - cache purges and view tracking, run in the background after commit
+#[path = "9/cache_invalidator.rs"]
+pub mod cache_invalidator;
+#[path = "9/recent_view_tracker.rs"]
+pub mod recent_view_tracker;
 
 // Helper for registering observers (not ring-specific)
 pub mod sql_executors;
@@ -38,6 +82,22 @@ pub use sql_executors::*;
 // Ring 0 re-exports
 pub use data_preparation::*;
 
+// Ring 1 re-exports
+pub use record_id_generator::*;
+pub use slug_generator::*;
+pub use sequence_allocator::*;
+pub use schema_validator::*;
+
+// Ring 2 re-exports
+pub use access_control::*;
+pub use managed_schema_guard::*;
+
+// Ring 3 re-exports
+pub use trash_guard::*;
+
+// Ring 4 re-exports
+pub use record_enricher::*;
+
 // Ring 5 re-exports
 pub use create_sql_executor::*;
 pub use delete_sql_executor::*;
@@ -52,3 +112,15 @@ pub use delete_column_ddl::*;
 pub use delete_schema_ddl::*;
 pub use update_column_ddl::*;
 pub use update_schema_ddl::*;
+pub use record_count_maintainer::*;
+
+// Ring 7 re-exports
+pub use audit_logger::*;
+
+// Ring 8 re-exports
+pub use webhook_dispatcher::*;
+pub use search_indexer::*;
+
+// Ring 9 re-exports
+pub use cache_invalidator::*;
+pub use recent_view_tracker::*;
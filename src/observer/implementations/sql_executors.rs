@@ -3,15 +3,49 @@ use crate::observer::pipeline::ObserverPipeline;
 use crate::observer::traits::ObserverBox;
 use super::{
     CreateSqlExecutor, UpdateSqlExecutor, DeleteSqlExecutor, 
-    RevertSqlExecutor, SelectSqlExecutor
+    RevertSqlExecutor, SelectSqlExecutor, RecordCountMaintainer, RecordIdGenerator,
+    SlugGenerator, SequenceAllocator, SchemaValidator, AccessControl, ManagedSchemaGuard, TrashGuard,
+    RecordEnricher, AuditLogger, WebhookDispatcher, SearchIndexer, CacheInvalidator,
+    RecentViewTracker,
 };
 
 /// Register all SQL executors for complete REST API CRUD support
 /// Since this is a REST API, all CRUD operations must be available
 pub fn register_all_sql_executors(pipeline: &mut ObserverPipeline) {
+    // Time-ordered ids (database.enable_uuid_v7_ids) are assigned before the INSERT
+    pipeline.register_observer(ObserverBox::Ring1(Box::new(RecordIdGenerator::default())));
+    // x-monk-slug properties are filled and checked before the INSERT/UPDATE
+    pipeline.register_observer(ObserverBox::Ring1(Box::new(SlugGenerator::default())));
+    // x-monk-sequence properties are numbered before the INSERT and read-only afterwards
+    pipeline.register_observer(ObserverBox::Ring1(Box::new(SequenceAllocator::default())));
+    // Fields are checked against the schema once slugs and sequence numbers are filled in
+    pipeline.register_observer(ObserverBox::Ring1(Box::new(SchemaValidator::default())));
+
+    // Writes are limited to records the caller may edit
+    pipeline.register_observer(ObserverBox::Ring2(Box::new(AccessControl::default())));
+    // Tables with their own endpoints, such as saved views, are not written as records
+    pipeline.register_observer(ObserverBox::Ring2(Box::new(ManagedSchemaGuard::default())));
+    // Trashed records must be reverted before they change
+    pipeline.register_observer(ObserverBox::Ring3(Box::new(TrashGuard::default())));
+    // Array/object defaults and clock-based timestamps
+    pipeline.register_observer(ObserverBox::Ring4(Box::new(RecordEnricher::default())));
+
     pipeline.register_observer(ObserverBox::Ring5(Box::new(CreateSqlExecutor::default())));
     pipeline.register_observer(ObserverBox::Ring5(Box::new(UpdateSqlExecutor::default())));
     pipeline.register_observer(ObserverBox::Ring5(Box::new(DeleteSqlExecutor::default())));
     pipeline.register_observer(ObserverBox::Ring5(Box::new(RevertSqlExecutor::default())));
     pipeline.register_observer(ObserverBox::Ring5(Box::new(SelectSqlExecutor::default())));
+
+    // Exact counts (?count_mode=exact) depend on every write adjusting record_counts
+    pipeline.register_observer(ObserverBox::Ring6(Box::new(RecordCountMaintainer::default())));
+
+    // security.enable_audit_logging records every write in audit_log
+    pipeline.register_observer(ObserverBox::Ring7(Box::new(AuditLogger::default())));
+
+    // Side effects run on the background worker once the change is committed
+    pipeline.register_observer(ObserverBox::Ring8(Box::new(WebhookDispatcher::default())));
+    pipeline.register_observer(ObserverBox::Ring8(Box::new(SearchIndexer::default())));
+    pipeline.register_observer(ObserverBox::Ring9(Box::new(CacheInvalidator::default())));
+    // Reads marked with a RecordViewer, on x-monk-track-views schemas
+    pipeline.register_observer(ObserverBox::Ring9(Box::new(RecentViewTracker::default())));
 }
\ No newline at end of file
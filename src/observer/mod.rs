@@ -6,6 +6,10 @@ pub mod traits;
 pub mod pipeline;
 pub mod error;
 pub mod implementations;
+pub mod deferred;
+pub mod preview;
+pub mod report;
+pub mod scope;
 
 // Re-export core types
 pub use context::*;
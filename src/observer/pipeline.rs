@@ -2,21 +2,58 @@
 // Based on superior Rust design from OBSERVER_SYSTEM.md
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::time::timeout;
 use serde_json::Value;
 
 use crate::observer::traits::{ObserverRing, Operation, ObserverBox};
-use crate::observer::context::ObserverContext;
+use crate::observer::context::{DryRun, ObserverContext, ObserverOutcome, ObserverTiming, ProcessingMetadata, SqlRetryStats};
 use crate::observer::error::{ObserverError, ObserverResult};
-use crate::filter::FilterData;
+use crate::filter::{FilterData, RecordAccess};
+use crate::database::audit::AuditActor;
+use crate::database::favorites::RecordViewer;
+use crate::database::transaction::TenantTransaction;
+use crate::observer::deferred::{self, DeferredRun};
+use crate::observer::preview::SqlOperation;
+use crate::observer::report;
+use crate::observer::scope::SchemaScope;
 
 
+/// Savepoint around each Ring 7 observer that runs inside a transaction
+const AUDIT_SAVEPOINT: &str = "observer_audit";
+
+/// How the statements of one write pipeline are committed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Rings 5 to 7 run in one transaction, committed once they all succeed and rolled back
+    /// on any error. Used when the write is more than one statement: several records, or
+    /// Ring 6 or 7 observers that apply to it. Otherwise it runs on the pool directly
+    #[default]
+    Atomic,
+    /// Each statement commits on its own, so a failure mid-batch keeps the earlier records
+    BestEffort,
+}
+
 /// High-performance observer pipeline with compile-time registration
 /// Executes observers in ring order with selective execution and async optimization
 pub struct ObserverPipeline {
     // Observer registry by ring
-    observers: HashMap<ObserverRing, Vec<ObserverBox>>,
+    observers: HashMap<ObserverRing, Vec<Registration>>,
+}
+
+/// A registered observer and the schemas it was bound to, if any
+struct Registration {
+    observer: Arc<ObserverBox>,
+    scope: Option<SchemaScope>,
+}
+
+impl Registration {
+    fn applies(&self, operation: Operation, schema: &str) -> bool {
+        self.observer.applies_to_operation(operation)
+            && self.observer.applies_to_schema(schema)
+            && self.scope.as_ref().is_none_or(|scope| scope.matches(schema))
+    }
 }
 
 impl ObserverPipeline {
@@ -28,39 +65,167 @@ impl ObserverPipeline {
         }
     }
     
+    /// Registered observer names per ring, in ring order
+    pub fn summary(&self) -> Vec<(ObserverRing, Vec<&'static str>)> {
+        let mut rings: Vec<_> = self.observers
+            .iter()
+            .map(|(ring, observers)| (*ring, observers.iter().map(|r| r.observer.name()).collect()))
+            .collect();
+        rings.sort_by_key(|(ring, _)| *ring as u8);
+        rings
+    }
+
     /// Register an observer (type-safe registration)
     pub fn register_observer(&mut self, observer: ObserverBox) {
+        self.register(observer, None);
+    }
+
+    /// Register an observer that only runs on schemas matching one of `patterns`
+    ///
+    /// Patterns are schema names or globs such as `"invoice*"` (see `SchemaScope`); the
+    /// observer's own `applies_to_schema` must still agree.
+    pub fn register_observer_for_schemas<I, S>(&mut self, observer: ObserverBox, patterns: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.register(observer, Some(SchemaScope::new(patterns)));
+    }
+
+    fn register(&mut self, observer: ObserverBox, scope: Option<SchemaScope>) {
         let ring = observer.ring();
         let name = observer.name();
+        match &scope {
+            Some(scope) => tracing::debug!("Registered observer '{}' for ring {:?} on schemas {:?}", name, ring, scope.patterns()),
+            None => tracing::debug!("Registered observer '{}' for ring {:?}", name, ring),
+        }
         self.observers
             .entry(ring)
-            .or_insert_with(Vec::new)
-            .push(observer);
-        
-        tracing::debug!("Registered observer '{}' for ring {:?}", name, ring);
+            .or_default()
+            .push(Registration { observer: Arc::new(observer), scope });
     }
     
-    /// Execute modification operations (CREATE, UPDATE, DELETE, REVERT)
+    /// Execute modification operations (CREATE, UPDATE, DELETE, REVERT); `access` limits
+    /// changes to records its ids may edit, and `actor` is recorded by the audit ring
+    ///
+    /// Inside the caller's `transaction` every statement joins it and `mode` is ignored.
+    /// Otherwise, with `WriteMode::Atomic` a multi-statement write runs in its own
+    /// transaction; its side effect rings are held until the commit and dropped on rollback.
+    #[allow(clippy::too_many_arguments)]
     pub async fn modify(
         &self,
         operation: Operation,
         schema_name: impl Into<String>,
         records: Vec<crate::database::record::Record>,
         pool: sqlx::PgPool,
+        transaction: Option<TenantTransaction>,
+        access: Option<RecordAccess>,
+        actor: Option<AuditActor>,
+        mode: WriteMode,
     ) -> Result<Vec<crate::database::record::Record>, ObserverError> {
-        let ctx = ObserverContext::new(operation, schema_name.into(), records, pool);
-        let result = self.execute_internal(ctx).await?;
-        self.extract_records(result)
+        let schema_name = schema_name.into();
+        let owned = transaction.is_none()
+            && mode == WriteMode::Atomic
+            && (records.len() > 1 || self.writes_after_database(operation, &schema_name));
+        let transaction = match transaction {
+            None if owned => Some(TenantTransaction::begin(&pool).await?),
+            transaction => transaction,
+        };
+
+        let mut ctx = ObserverContext::new(operation, schema_name, records, pool);
+        ctx.set_transaction(transaction.clone());
+        if let Some(access) = access {
+            ctx.set_metadata(access);
+        }
+        if let Some(actor) = actor {
+            ctx.set_metadata(actor);
+        }
+
+        let Some(transaction) = transaction.filter(|_| owned) else {
+            let result = self.execute_internal(ctx).await?;
+            return self.extract_records(result);
+        };
+
+        let (result, held) = deferred::hold(self.execute_internal(ctx)).await;
+        match result {
+            Ok(result) if result.success => {
+                transaction.commit().await?;
+                deferred::release(held);
+                self.extract_records(result)
+            }
+            result => {
+                if let Err(e) = transaction.rollback().await {
+                    tracing::warn!("Rolling back failed write pipeline: {}", e);
+                }
+                result.and_then(|result| self.extract_records(result))
+            }
+        }
+    }
+    
+    /// Whether Ring 6 or 7 observers write alongside the records of `operation` on `schema`
+    fn writes_after_database(&self, operation: Operation, schema: &str) -> bool {
+        [ObserverRing::PostDatabase, ObserverRing::Audit]
+            .iter()
+            .filter_map(|ring| self.observers.get(ring))
+            .flatten()
+            .any(|registration| registration.applies(operation, schema))
+    }
+
+    /// Run Rings 0-4 of a write without executing it, returning the statements the Database
+    /// ring would run; the first error of an earlier ring is returned as `modify` would
+    ///
+    /// Nothing is written and no later ring runs. The context carries `DryRun`, so observers
+    /// that consume shared state, such as sequence numbers, can leave it untouched.
+    pub async fn preview(
+        &self,
+        operation: Operation,
+        schema_name: impl Into<String>,
+        records: Vec<crate::database::record::Record>,
+        pool: sqlx::PgPool,
+        access: Option<RecordAccess>,
+    ) -> Result<Vec<SqlOperation>, ObserverError> {
+        let start_time = Instant::now();
+        let mut ctx = ObserverContext::new(operation, schema_name.into(), records, pool);
+        if let Some(access) = access {
+            ctx.set_metadata(access);
+        }
+        ctx.set_metadata(DryRun);
+
+        let rings: Vec<ObserverRing> = ObserverRing::for_operation(&operation)
+            .into_iter()
+            .filter(|&ring| (ring as u8) < ObserverRing::Database as u8)
+            .collect();
+        self.execute_sync_rings(&rings, &mut ctx).await?;
+        report::record(operation, &ctx.schema_name, start_time.elapsed(), ctx.get_metadata::<ProcessingMetadata>());
+
+        if !ctx.errors.is_empty() {
+            return Err(ctx.errors.remove(0));
+        }
+        ctx.records
+            .iter()
+            .map(|record| SqlOperation::for_record(operation, &ctx.schema_name, record))
+            .collect()
     }
     
-    /// Execute SELECT operations
+    /// Execute SELECT operations; `access` restricts rows to those its ids may read, and
+    /// inside `transaction` the rows include its uncommitted writes
     pub async fn select(
         &self,
         schema_name: impl Into<String>,
         filter_data: FilterData,
         pool: sqlx::PgPool,
+        transaction: Option<TenantTransaction>,
+        access: Option<RecordAccess>,
+        viewer: Option<RecordViewer>,
     ) -> Result<Vec<crate::database::record::Record>, ObserverError> {
-        let ctx = ObserverContext::new_select(schema_name.into(), filter_data, pool);
+        let mut ctx = ObserverContext::new_select(schema_name.into(), filter_data, pool);
+        ctx.set_transaction(transaction);
+        if let Some(access) = access {
+            ctx.set_metadata(access);
+        }
+        if let Some(viewer) = viewer {
+            ctx.set_metadata(viewer);
+        }
         let result = self.execute_internal(ctx).await?;
         self.extract_records(result)
     }
@@ -75,27 +240,51 @@ impl ObserverPipeline {
             ctx.operation, ctx.schema_name, relevant_rings
         );
         
-        // Execute all synchronous rings in order
-        for &ring in relevant_rings.iter().filter(|r| r.is_synchronous()) {
+        self.execute_sync_rings(&relevant_rings, &mut ctx).await?;
+        
+        // Audit runs once the write has happened; integration and notification go to the
+        // background worker
+        if ctx.result.is_some() {
+            self.execute_async_rings(&relevant_rings, &mut ctx).await;
+            self.defer_rings(&relevant_rings, &ctx);
+        }
+        
+        self.build_result(ctx, start_time.elapsed(), relevant_rings)
+    }
+
+    /// Execute the synchronous rings among `rings` in order, stopping at the first that fails
+    async fn execute_sync_rings(&self, rings: &[ObserverRing], ctx: &mut ObserverContext) -> Result<(), ObserverError> {
+        for &ring in rings.iter().filter(|r| r.is_synchronous()) {
             ctx.current_ring = Some(ring);
-            
-            let should_continue = self.execute_ring(ring, &mut ctx).await?;
+
+            // Give up before the write once the client has; rings after it complete the write
+            if (ring as u8) <= 5 && crate::deadline::expired() {
+                ctx.errors.insert(0, ObserverError::DeadlineExceeded(format!("stopped before ring {:?}", ring)));
+                break;
+            }
+
+            let should_continue = self.execute_ring(ring, ctx).await?;
+            let reported = matches!(ctx.errors.first(), Some(ObserverError::DeadlineExceeded(_)));
+            if !ctx.errors.is_empty() && !reported && crate::deadline::expired() {
+                // Statements cancelled by statement_timeout surface as database errors
+                ctx.errors.insert(0, ObserverError::DeadlineExceeded(format!("cancelled in ring {:?}", ring)));
+            }
             if !should_continue {
                 tracing::warn!("Pipeline stopped at ring {:?} due to errors", ring);
                 break;
             }
         }
-        
-        // Execute async rings (when implemented)
-        if ctx.result.is_some() {
-            self.execute_async_rings(&relevant_rings, &ctx).await;
-        }
-        
-        self.build_result(ctx, start_time.elapsed(), relevant_rings)
+        Ok(())
     }
 
     /// Build final result from context
     fn build_result(&self, ctx: ObserverContext, duration: Duration, rings: Vec<ObserverRing>) -> Result<ObserverResult, ObserverError> {
+        let retries = ctx.get_metadata::<SqlRetryStats>().map_or(0, |stats| stats.retries);
+        if retries > 0 {
+            tracing::info!("Pipeline for {} retried {} statement(s) after transient failures", ctx.schema_name, retries);
+        }
+        report::record(ctx.operation, &ctx.schema_name, duration, ctx.get_metadata::<ProcessingMetadata>());
+
         let result_data = ctx.result.unwrap_or_else(|| {
             ctx.records.into_iter().map(|record| record.to_json()).collect()
         });
@@ -107,15 +296,17 @@ impl ObserverPipeline {
             warnings: ctx.warnings,
             execution_time: duration,
             rings_executed: rings,
+            retries,
         })
     }
     
     /// Extract Records from ObserverResult
     fn extract_records(&self, result: ObserverResult) -> Result<Vec<crate::database::record::Record>, ObserverError> {
         if !result.success {
-            return Err(ObserverError::ValidationError(
-                format!("Pipeline failed with {} errors", result.errors.len())
-            ));
+            // Report the first error as-is so callers can tell a conflict from a bad request
+            return Err(result.errors.into_iter().next().unwrap_or_else(|| {
+                ObserverError::ValidationError("Pipeline failed".to_string())
+            }));
         }
         
         let json_results = result.result.unwrap_or_default();
@@ -146,12 +337,12 @@ impl ObserverPipeline {
         
         tracing::debug!("Executing ring {:?} with {} observers", ring, observers.len());
         
-        for observer in observers {
-            if !observer.applies_to_operation(ctx.operation) || !observer.applies_to_schema(&ctx.schema_name) {
+        for registration in observers {
+            if !registration.applies(ctx.operation, &ctx.schema_name) {
                 continue;
             }
             
-            self.execute_observer(observer, ctx).await;
+            self.execute_observer(&registration.observer, ctx).await;
         }
         
         // Stop on errors for pre-database rings
@@ -164,13 +355,15 @@ impl ObserverPipeline {
         let result = timeout(observer.timeout(), observer.execute_sync(ctx)).await;
         let duration = start.elapsed();
         
-        match result {
+        let outcome = match result {
             Ok(Ok(_)) => {
                 tracing::debug!("Observer {} completed in {:?}", observer.name(), duration);
+                ObserverOutcome::Completed
             }
             Ok(Err(error)) => {
                 tracing::warn!("Observer {} failed in {:?}: {}", observer.name(), duration, error);
                 ctx.errors.push(error);
+                ObserverOutcome::Failed
             }
             Err(_) => {
                 let timeout_error = ObserverError::TimeoutError(
@@ -178,16 +371,99 @@ impl ObserverPipeline {
                 );
                 tracing::error!("Observer {} timed out after {:?}", observer.name(), observer.timeout());
                 ctx.errors.push(timeout_error);
+                ObserverOutcome::TimedOut
             }
-        }
+        };
+        record_timing(ctx, observer, duration, outcome);
     }
     
-    /// Execute asynchronous rings in parallel (non-blocking)
-    /// TODO: Implement full async execution when needed
-    async fn execute_async_rings(&self, _relevant_rings: &[ObserverRing], _ctx: &ObserverContext) {
-        tracing::debug!("Async ring execution not yet implemented - skipping");
-        // For now, skip async execution to get the framework compiling
-        // This can be implemented later when specific async observers are needed
+    /// Execute ring 7 after the database work
+    ///
+    /// These observers see the finished context read-only. Their failures and timeouts are
+    /// logged but never fail the operation, whose changes are already written. Inside a
+    /// transaction each runs under a savepoint, so a failed statement is undone alone
+    /// instead of aborting the transaction.
+    async fn execute_async_rings(&self, relevant_rings: &[ObserverRing], ctx: &mut ObserverContext) {
+        for &ring in relevant_rings.iter().filter(|r| r.is_asynchronous() && !r.is_deferred()) {
+            let Some(observers) = self.observers.get(&ring) else {
+                continue;
+            };
+            for registration in observers {
+                if !registration.applies(ctx.operation, &ctx.schema_name) {
+                    continue;
+                }
+                let observer = &registration.observer;
+                let transaction = ctx.transaction().cloned();
+                if let Some(transaction) = &transaction {
+                    if let Err(e) = transaction.savepoint(AUDIT_SAVEPOINT).await {
+                        ctx.errors.push(e.into());
+                        return;
+                    }
+                }
+                let start = Instant::now();
+                let outcome = match timeout(observer.timeout(), observer.execute_async(ctx)).await {
+                    Ok(Ok(())) => ObserverOutcome::Completed,
+                    Ok(Err(error)) => {
+                        tracing::warn!("Observer {} failed after {:?}: {}", observer.name(), ctx.operation, error);
+                        ObserverOutcome::Failed
+                    }
+                    Err(_) => {
+                        tracing::warn!("Observer {} timed out after {:?}", observer.name(), observer.timeout());
+                        ObserverOutcome::TimedOut
+                    }
+                };
+                record_timing(ctx, observer, start.elapsed(), outcome);
+                if let Some(transaction) = &transaction {
+                    let restored = match outcome {
+                        ObserverOutcome::Completed => transaction.release(AUDIT_SAVEPOINT).await,
+                        _ => transaction.rollback_to(AUDIT_SAVEPOINT).await,
+                    };
+                    if let Err(e) = restored {
+                        ctx.errors.push(e.into());
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hand rings 8 and 9 to the background worker with a copy of the finished context
+    fn defer_rings(&self, relevant_rings: &[ObserverRing], ctx: &ObserverContext) {
+        // Only a user opening a record has deferred work on a read
+        if ctx.operation == Operation::Select && ctx.get_metadata::<RecordViewer>().is_none() {
+            return;
+        }
+        let observers: Vec<Arc<ObserverBox>> = relevant_rings
+            .iter()
+            .filter(|r| r.is_deferred())
+            .filter_map(|ring| self.observers.get(ring))
+            .flatten()
+            .filter(|r| r.applies(ctx.operation, &ctx.schema_name))
+            .map(|r| r.observer.clone())
+            .collect();
+        if observers.is_empty() {
+            return;
+        }
+
+        // Side effects run after the commit, on the tenant pool
+        let mut context = ctx.clone();
+        context.set_transaction(None);
+        if let Some(actor) = ctx.get_metadata::<AuditActor>() {
+            context.set_metadata(actor.clone());
+        }
+        if let Some(viewer) = ctx.get_metadata::<RecordViewer>() {
+            context.set_metadata(*viewer);
+        }
+        deferred::submit(DeferredRun::new(observers, context));
+    }
+}
+
+/// Add one observer run to the context's `ProcessingMetadata`
+fn record_timing(ctx: &mut ObserverContext, observer: &ObserverBox, elapsed: Duration, outcome: ObserverOutcome) {
+    let timing = ObserverTiming { ring: observer.ring(), observer: observer.name(), elapsed, outcome };
+    match ctx.get_metadata_mut::<ProcessingMetadata>() {
+        Some(processing) => processing.observers.push(timing),
+        None => ctx.set_metadata(ProcessingMetadata { observers: vec![timing] }),
     }
 }
 
@@ -196,3 +472,24 @@ impl Default for ObserverPipeline {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observer::implementations::{CreateSchemaDdl, CreateSqlExecutor};
+
+    #[test]
+    fn test_writes_after_database() {
+        let mut pipeline = ObserverPipeline::new();
+        pipeline.register_observer(ObserverBox::Ring5(Box::new(CreateSqlExecutor)));
+        assert!(!pipeline.writes_after_database(Operation::Create, "schemas"));
+
+        pipeline.register_observer_for_schemas(ObserverBox::Ring6(Box::new(CreateSchemaDdl)), ["other"]);
+        assert!(!pipeline.writes_after_database(Operation::Create, "schemas"));
+
+        pipeline.register_observer(ObserverBox::Ring6(Box::new(CreateSchemaDdl)));
+        assert!(pipeline.writes_after_database(Operation::Create, "schemas"));
+        assert!(!pipeline.writes_after_database(Operation::Update, "schemas"));
+        assert!(!pipeline.writes_after_database(Operation::Create, "items"));
+    }
+}
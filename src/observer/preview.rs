@@ -0,0 +1,84 @@
+//! Dry-run previews of a write pipeline
+//!
+//! `ObserverPipeline::preview` runs Rings 0-4 and stops before the Database ring; each
+//! record that passed them becomes a [`SqlOperation`] holding the statement Ring 5 would
+//! run and the change the audit ring would log.
+
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::database::audit;
+use crate::database::record::Record;
+use crate::observer::error::ObserverError;
+use crate::observer::implementations::{create_sql_executor, delete_sql_executor, revert_sql_executor, update_sql_executor};
+use crate::observer::traits::Operation;
+
+/// A statement the Database ring would run for one record
+#[derive(Debug, Clone, Serialize)]
+pub struct SqlOperation {
+    pub operation: &'static str,
+    pub schema: String,
+    pub record_id: Option<Uuid>,
+    /// None when the record has nothing to write, such as an update that changes nothing
+    pub sql: Option<String>,
+    pub params: Vec<Value>,
+    /// Changed fields as `{ field: { old, new } }`, in the shape of audit log entries
+    pub changes: Value,
+}
+
+impl SqlOperation {
+    pub fn for_record(operation: Operation, schema_name: &str, record: &Record) -> Result<Self, ObserverError> {
+        let statement = match operation {
+            Operation::Create => create_sql_executor::insert_statement(record, schema_name),
+            Operation::Update => update_sql_executor::update_statement(record, schema_name)?,
+            Operation::Delete => {
+                let (sql, id) = delete_sql_executor::delete_statement(record, schema_name)?;
+                Some((sql, vec![Value::String(id)]))
+            }
+            Operation::Revert => {
+                let (sql, id) = revert_sql_executor::revert_statement(record, schema_name)?;
+                Some((sql, vec![Value::String(id)]))
+            }
+            Operation::Select => {
+                return Err(ObserverError::ValidationError("SELECT has no write to preview".to_string()));
+            }
+        };
+        let (sql, params) = statement.map_or((None, Vec::new()), |(sql, params)| (Some(sql), params));
+
+        Ok(Self {
+            operation: audit::operation_name(operation),
+            schema: schema_name.to_string(),
+            record_id: record.id(),
+            sql,
+            params,
+            changes: audit::change_summary(operation, &record.to_map(), Some(record)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn previews_inserts_and_updates() {
+        let record = Record::from_json(json!({ "name": "Alice" })).unwrap();
+        let insert = SqlOperation::for_record(Operation::Create, "users_list", &record).unwrap();
+        assert_eq!(insert.sql.as_deref(), Some("INSERT INTO \"users_list\" (\"name\") VALUES ($1) RETURNING *"));
+        assert_eq!(insert.params, vec![json!("Alice")]);
+        assert_eq!(insert.changes, json!({ "name": { "new": "Alice" } }));
+
+        let id = Uuid::new_v4();
+        let mut record = Record::from_sql_data([("id".to_string(), json!(id)), ("name".to_string(), json!("Al"))].into());
+        record.set("name", "Alice");
+        let update = SqlOperation::for_record(Operation::Update, "users_list", &record).unwrap();
+        assert_eq!(
+            update.sql.as_deref(),
+            Some("UPDATE \"users_list\" SET \"name\" = $1, updated_at = NOW() WHERE id = $2 RETURNING *")
+        );
+        assert_eq!(update.params, vec![json!("Alice"), json!(id.to_string())]);
+        assert_eq!(update.changes, json!({ "name": { "old": "Al", "new": "Alice" } }));
+    }
+}
@@ -0,0 +1,89 @@
+//! Pipeline execution reports for `?debug=pipeline`
+//!
+//! The pipeline report middleware runs a request inside [`collect`]; every pipeline that
+//! finishes on the request's task then adds its per-observer timings, nested pipelines
+//! (such as the lookup Ring 0 runs for an update) included. Outside a collection,
+//! [`record`] does nothing.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::database::audit;
+use crate::observer::context::ProcessingMetadata;
+use crate::observer::traits::Operation;
+
+tokio::task_local! {
+    static REPORTS: RefCell<Vec<Value>>;
+}
+
+/// Run `work`, returning the reports of the pipelines it ran, in completion order
+pub async fn collect<F: Future>(work: F) -> (F::Output, Vec<Value>) {
+    REPORTS
+        .scope(RefCell::new(Vec::new()), async move {
+            let output = work.await;
+            let reports = REPORTS.with(|reports| reports.take());
+            (output, reports)
+        })
+        .await
+}
+
+/// Add a finished pipeline run to the current collection, if any
+pub fn record(operation: Operation, schema_name: &str, elapsed: Duration, processing: Option<&ProcessingMetadata>) {
+    let _ = REPORTS.try_with(|reports| {
+        let observers: Vec<Value> = processing
+            .map(|processing| processing.observers.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|timing| {
+                json!({
+                    "ring": timing.ring as u8,
+                    "observer": timing.observer,
+                    "elapsed_ms": millis(timing.elapsed),
+                    "outcome": timing.outcome.as_str(),
+                })
+            })
+            .collect();
+        reports.borrow_mut().push(json!({
+            "operation": audit::operation_name(operation),
+            "schema": schema_name,
+            "elapsed_ms": millis(elapsed),
+            "observers": observers,
+        }));
+    });
+}
+
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observer::context::{ObserverOutcome, ObserverTiming};
+    use crate::observer::traits::ObserverRing;
+
+    #[tokio::test]
+    async fn records_only_inside_a_collection() {
+        let processing = ProcessingMetadata {
+            observers: vec![ObserverTiming {
+                ring: ObserverRing::Database,
+                observer: "CreateSqlExecutor",
+                elapsed: Duration::from_micros(1500),
+                outcome: ObserverOutcome::Completed,
+            }],
+        };
+        record(Operation::Create, "orders", Duration::from_millis(2), Some(&processing));
+
+        let ((), reports) = collect(async {
+            record(Operation::Create, "orders", Duration::from_millis(2), Some(&processing));
+        })
+        .await;
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0]["operation"], "create");
+        assert_eq!(reports[0]["observers"][0]["ring"], 5);
+        assert_eq!(reports[0]["observers"][0]["elapsed_ms"], 1.5);
+    }
+}
@@ -0,0 +1,74 @@
+// Schema scopes for observers registered on specific schemas
+
+/// Schema names or glob patterns an observer is bound to
+///
+/// `*` matches any run of characters and `?` exactly one, so `"invoice*"` covers
+/// `invoices` and `invoice_lines`. A scope applies when any of its patterns matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaScope {
+    patterns: Vec<String>,
+}
+
+impl SchemaScope {
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self { patterns: patterns.into_iter().map(Into::into).collect() }
+    }
+
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    pub fn matches(&self, schema: &str) -> bool {
+        self.patterns.iter().any(|pattern| glob_match(pattern.as_bytes(), schema.as_bytes()))
+    }
+}
+
+/// Iterative wildcard match, backtracking only to the most recent `*`
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_names_and_globs() {
+        let scope = SchemaScope::new(["invoice*", "orders", "line_?"]);
+        assert!(scope.matches("invoice"));
+        assert!(scope.matches("invoice_lines"));
+        assert!(scope.matches("orders"));
+        assert!(scope.matches("line_a"));
+        assert!(!scope.matches("order"));
+        assert!(!scope.matches("line_ab"));
+        assert!(!scope.matches("proforma_invoice"));
+        assert!(SchemaScope::new(["*_audit*"]).matches("order_audit_log"));
+    }
+}
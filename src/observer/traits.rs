@@ -29,6 +29,13 @@ impl ObserverRing {
     pub fn is_asynchronous(&self) -> bool {
         (*self as u8) >= 7
     }
+
+    /// Check if ring runs on the background worker rather than before the pipeline returns
+    ///
+    /// Audit (7) stays in the request so its rows commit or roll back with the change.
+    pub fn is_deferred(&self) -> bool {
+        (*self as u8) >= 8
+    }
     
     /// Get all rings for an operation type
     pub fn for_operation(operation: &Operation) -> Vec<Self> {
@@ -212,7 +219,11 @@ impl ObserverBox {
         }
     }
     
+    /// Time limit per run: an `api.observer_timeouts` entry when there is one, else the observer's own
     pub fn timeout(&self) -> Duration {
+        if let Some(limit) = crate::config::config().api.observer_timeout(self.name(), self.ring() as u8) {
+            return limit;
+        }
         match self {
             ObserverBox::Ring0(o) => o.timeout(),
             ObserverBox::Ring1(o) => o.timeout(), 
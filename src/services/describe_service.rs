@@ -3,9 +3,16 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::PgPool;
 
+use crate::database::checks::property_checks;
+use crate::database::identifiers::{validate_derived, validate_identifier, IdentifierError};
+use crate::database::sequences::sequence_fields;
+use crate::database::slugs::slug_fields;
+use crate::database::unique::unique_groups;
+use crate::observer::implementations::create_schema_ddl::SYSTEM_FIELDS;
 use crate::database::manager::DatabaseError;
 use crate::database::record::Record;
 use crate::database::repository::Repository;
+use crate::services::schema_lint::{lint_definition, LintIssue, LintReport};
 
 // Note: SchemaInfo and ColumnInfo are now replaced by Record type
 
@@ -13,6 +20,9 @@ use crate::database::repository::Repository;
 pub struct JsonSchemaProperty {
     #[serde(rename = "type")]
     pub property_type: String,
+    /// Display name; the property key is the physical column name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
     pub format: Option<String>,
     pub pattern: Option<String>,
     #[serde(rename = "enum")]
@@ -27,6 +37,13 @@ pub struct JsonSchemaProperty {
     pub description: Option<String>,
     #[serde(rename = "x-monk-relationship")]
     pub x_monk_relationship: Option<XMonkRelationship>,
+    #[serde(rename = "x-monk-slug", skip_serializing_if = "Option::is_none")]
+    pub x_monk_slug: Option<XMonkSlug>,
+    #[serde(rename = "x-monk-sequence", skip_serializing_if = "Option::is_none")]
+    pub x_monk_sequence: Option<XMonkSequence>,
+    /// Include this string property in the schema's full-text `search_vector`
+    #[serde(rename = "x-monk-searchable", skip_serializing_if = "Option::is_none")]
+    pub x_monk_searchable: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +58,23 @@ pub struct XMonkRelationship {
     pub required: Option<bool>,
 }
 
+/// Marks a string property as a unique slug generated from another property
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XMonkSlug {
+    pub from: String,
+}
+
+/// Numbers a property from a per-table sequence, e.g. `{"prefix": "INV-", "padding": 6}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XMonkSequence {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<i64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonSchema {
     pub name: String,
@@ -49,6 +83,12 @@ pub struct JsonSchema {
     pub description: Option<String>,
     pub properties: std::collections::HashMap<String, JsonSchemaProperty>,
     pub required: Option<Vec<String>>,
+    /// Column groups that must be unique together, e.g. `[["email", "workspace_id"]]`
+    #[serde(rename = "x-monk-unique", skip_serializing_if = "Option::is_none")]
+    pub x_monk_unique: Option<Vec<Vec<String>>>,
+    /// Keep each user's recently viewed records of this schema, for /api/auth/recent
+    #[serde(rename = "x-monk-track-views", skip_serializing_if = "Option::is_none")]
+    pub x_monk_track_views: Option<bool>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -67,6 +107,12 @@ pub enum DescribeError {
     JsonParse(#[from] serde_json::Error),
 }
 
+impl From<IdentifierError> for DescribeError {
+    fn from(err: IdentifierError) -> Self {
+        DescribeError::InvalidFormat(err.to_string())
+    }
+}
+
 pub struct DescribeService {
     pool: PgPool,
 }
@@ -88,6 +134,12 @@ impl DescribeService {
         // Parse and validate JSON Schema
         let json_schema = self.parse_json_schema(json_content.clone())?;
         let table_name = json_schema.table.as_deref().unwrap_or(schema_name);
+        validate_identifier("Schema", schema_name)?;
+        if table_name != schema_name {
+            validate_identifier("Table", table_name)?;
+        }
+        validate_derived(&format!("{}_notify_change", table_name))?;
+        self.validate_identifiers(table_name, &json_schema, &[])?;
 
         // Check if schema already exists using Repository
         let schemas_repo = Repository::new("schemas", self.pool.clone());
@@ -116,6 +168,35 @@ impl DescribeService {
         Ok(created_schema)
     }
 
+    /// Check a schema definition for problems without applying it
+    ///
+    /// Relationship targets are resolved against the tenant's existing schemas. A
+    /// definition the create endpoint would reject always reports at least one error.
+    pub async fn lint(&self, schema_name: &str, json_content: &Value) -> Result<LintReport, DescribeError> {
+        use crate::filter::FilterData;
+
+        let schemas_repo = Repository::new("schemas", self.pool.clone());
+        let filter = FilterData {
+            where_clause: Some(serde_json::json!({ "deleted_at": null })),
+            ..Default::default()
+        };
+        let existing: Vec<String> = schemas_repo
+            .select_any(filter)
+            .await?
+            .iter()
+            .filter_map(|record| record.get("name").and_then(|n| n.as_str()).map(str::to_string))
+            .collect();
+
+        let mut report = lint_definition(schema_name, json_content, &existing);
+        if report.errors.is_empty() {
+            if let Err(e) = self.validate_schema_protection(schema_name).and_then(|_| self.parse_json_schema(json_content.clone())) {
+                report.errors.push(LintIssue { path: String::new(), message: e.to_string() });
+                report.valid = false;
+            }
+        }
+        Ok(report)
+    }
+
     /// Get schema by name
     pub async fn select_one(&self, schema_name: &str) -> Result<Option<Record>, DescribeError> {
         use crate::filter::FilterData;
@@ -172,6 +253,16 @@ impl DescribeService {
             .next()
             .ok_or_else(|| DescribeError::NotFound(schema_name.to_string()))?;
 
+        // Properties added by this update become new columns
+        let table_name = existing_schema.get("table_name").and_then(|t| t.as_str()).unwrap_or(schema_name).to_string();
+        let existing_fields: Vec<String> = existing_schema
+            .get("definition")
+            .and_then(|d| d.get("properties"))
+            .and_then(|p| p.as_object())
+            .map(|properties| properties.keys().cloned().collect())
+            .unwrap_or_default();
+        self.validate_identifiers(&table_name, &json_schema, &existing_fields)?;
+
         // Update using the schema ID
         let schema_id = existing_schema
             .id()
@@ -201,8 +292,8 @@ impl DescribeService {
         // Create change record with soft delete timestamps
         let mut change = Record::new();
         change
-            .set("trashed_at", chrono::Utc::now().to_rfc3339())
-            .set("updated_at", chrono::Utc::now().to_rfc3339());
+            .set("trashed_at", crate::clock::now().to_rfc3339())
+            .set("updated_at", crate::clock::now().to_rfc3339());
 
         let updated_records = schemas_repo.update_any(filter, change).await?;
         Ok(!updated_records.is_empty())
@@ -239,6 +330,17 @@ impl DescribeService {
             return Err(DescribeError::NotFound(format!("Schema '{}' not found", schema_name)));
         }
 
+        // Column and derived constraint names must survive as Postgres identifiers
+        let table_name = self
+            .select_one(schema_name)
+            .await?
+            .and_then(|schema| schema.get("table_name").and_then(|t| t.as_str()).map(str::to_string))
+            .unwrap_or_else(|| schema_name.to_string());
+        validate_identifier("Column", column_name)?;
+        for check in property_checks(&table_name, column_name, &json_property) {
+            validate_derived(&check.name)?;
+        }
+
         // Parse JSON Schema property into JsonSchemaProperty
         let column_definition: JsonSchemaProperty = serde_json::from_value(json_property)?;
 
@@ -359,8 +461,8 @@ impl DescribeService {
         // Create change record with soft delete timestamps - DeleteColumnDdl observer will handle ALTER TABLE DROP COLUMN
         let mut change = Record::new();
         change
-            .set("trashed_at", chrono::Utc::now().to_rfc3339())
-            .set("updated_at", chrono::Utc::now().to_rfc3339());
+            .set("trashed_at", crate::clock::now().to_rfc3339())
+            .set("updated_at", crate::clock::now().to_rfc3339());
 
         let updated_records = columns_repo.update_any(filter, change).await?;
         Ok(!updated_records.is_empty())
@@ -402,7 +504,8 @@ impl DescribeService {
             .set("pg_type", pg_type)
             .set("json_type", column_definition.property_type.clone())
             .set("is_required", is_required)
-            .set("is_array", column_definition.property_type == "array");
+            .set("is_array", column_definition.property_type == "array")
+            .set("searchable", column_definition.x_monk_searchable == Some(true));
 
         // Store format if present
         if let Some(format) = &column_definition.format {
@@ -451,6 +554,9 @@ impl DescribeService {
         if let Some(desc) = &column_definition.description {
             column_record.set("description", desc.as_str());
         }
+        if let Some(title) = &column_definition.title {
+            column_record.set("title", title.as_str());
+        }
 
         // Skip x-monk-relationship for now as requested
 
@@ -497,6 +603,47 @@ impl DescribeService {
         Ok(!results.is_empty())
     }
 
+    /// New property names, and the constraint, index and sequence names derived from them,
+    /// are identifiers Postgres keeps intact
+    ///
+    /// `existing` lists properties the table already has, which are not checked again.
+    fn validate_identifiers(&self, table_name: &str, schema: &JsonSchema, existing: &[String]) -> Result<(), DescribeError> {
+        let definition = serde_json::to_value(schema)?;
+        let is_new = |field: &str| !existing.iter().any(|e| e == field);
+
+        let mut derived = Vec::new();
+        for (field, property) in &schema.properties {
+            if SYSTEM_FIELDS.contains(&field.as_str()) || !is_new(field) {
+                continue;
+            }
+            validate_identifier("Property", field)?;
+            derived.extend(property_checks(table_name, field, &serde_json::to_value(property)?).into_iter().map(|c| c.name));
+        }
+        derived.extend(
+            slug_fields(&definition)
+                .into_iter()
+                .filter(|slug| is_new(&slug.field))
+                .map(|slug| format!("{}_{}_slug_idx", table_name, slug.field)),
+        );
+        derived.extend(
+            sequence_fields(&definition)
+                .into_iter()
+                .filter(|sequence| is_new(&sequence.field))
+                .map(|sequence| format!("{}_{}_sequence_idx", table_name, sequence.field)),
+        );
+        derived.extend(
+            unique_groups(&definition)
+                .into_iter()
+                .filter(|fields| fields.iter().any(|field| is_new(field)))
+                .map(|fields| format!("{}_{}_unique", table_name, fields.join("_"))),
+        );
+
+        for name in derived {
+            validate_derived(&name)?;
+        }
+        Ok(())
+    }
+
     fn validate_schema_protection(&self, schema_name: &str) -> Result<(), DescribeError> {
         let protected_schemas = ["schemas", "users", "columns"];
         if protected_schemas.contains(&schema_name) {
@@ -518,9 +665,109 @@ impl DescribeService {
             ));
         }
 
+        self.validate_slug_properties(&schema)?;
+        self.validate_sequence_properties(&schema)?;
+        self.validate_unique_groups(&schema)?;
         Ok(schema)
     }
 
+    /// Every x-monk-unique group names distinct properties of the schema
+    fn validate_unique_groups(&self, schema: &JsonSchema) -> Result<(), DescribeError> {
+        for group in schema.x_monk_unique.iter().flatten() {
+            if group.is_empty() {
+                return Err(DescribeError::InvalidFormat(
+                    "x-monk-unique groups must name at least one property".to_string(),
+                ));
+            }
+            for (index, field) in group.iter().enumerate() {
+                if !schema.properties.contains_key(field) {
+                    return Err(DescribeError::InvalidFormat(format!(
+                        "x-monk-unique names unknown property '{}'",
+                        field
+                    )));
+                }
+                if group[..index].contains(field) {
+                    return Err(DescribeError::InvalidFormat(format!(
+                        "x-monk-unique names property '{}' twice in one group",
+                        field
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// x-monk-sequence on integer properties, or string properties formatted with prefix/padding
+    fn validate_sequence_properties(&self, schema: &JsonSchema) -> Result<(), DescribeError> {
+        for (name, property) in &schema.properties {
+            let Some(sequence) = &property.x_monk_sequence else {
+                continue;
+            };
+            let formatted = match property.property_type.as_str() {
+                "string" => true,
+                "integer" => false,
+                _ => {
+                    return Err(DescribeError::InvalidFormat(format!(
+                        "x-monk-sequence property '{}' must be of type string or integer",
+                        name
+                    )))
+                }
+            };
+            if !formatted && (sequence.prefix.is_some() || sequence.padding.is_some()) {
+                return Err(DescribeError::InvalidFormat(format!(
+                    "x-monk-sequence property '{}' is an integer; prefix and padding need type string",
+                    name
+                )));
+            }
+            if sequence.padding.is_some_and(|padding| padding > 20) {
+                return Err(DescribeError::InvalidFormat(format!(
+                    "x-monk-sequence padding of '{}' must be at most 20",
+                    name
+                )));
+            }
+            if property.x_monk_slug.is_some() {
+                return Err(DescribeError::InvalidFormat(format!(
+                    "Property '{}' cannot be both a slug and a sequence",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// At most one x-monk-slug property, a string derived from another string property
+    fn validate_slug_properties(&self, schema: &JsonSchema) -> Result<(), DescribeError> {
+        let slugs: Vec<(&String, &XMonkSlug)> = schema
+            .properties
+            .iter()
+            .filter_map(|(name, property)| property.x_monk_slug.as_ref().map(|slug| (name, slug)))
+            .collect();
+
+        if slugs.len() > 1 {
+            return Err(DescribeError::InvalidFormat(
+                "Only one property may declare x-monk-slug".to_string(),
+            ));
+        }
+        for (name, slug) in slugs {
+            if schema.properties[name].property_type != "string" {
+                return Err(DescribeError::InvalidFormat(format!(
+                    "x-monk-slug property '{}' must be of type string",
+                    name
+                )));
+            }
+            match schema.properties.get(&slug.from) {
+                Some(source) if source.property_type == "string" && &slug.from != name => {}
+                _ => {
+                    return Err(DescribeError::InvalidFormat(format!(
+                        "x-monk-slug property '{}' must be generated from another string property, not '{}'",
+                        name, slug.from
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn generate_json_checksum(&self, json_content: &str) -> String {
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
@@ -0,0 +1,350 @@
+//! A Handlebars subset for rendering document templates against JSON records
+//!
+//! Supported: `{{path}}` (HTML-escaped), `{{{path}}}` (raw), `{{! comment}}`, and the
+//! `#if`, `#unless`, `#each` and `#with` blocks with `{{else}}`. Paths are dotted
+//! (`customer.name`), may start with `this` or climb scopes with `../`, and inside
+//! `#each` the `@index`, `@key`, `@first` and `@last` variables are available. Partials
+//! and custom helpers are not supported; templates using them fail to parse.
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Value { path: Path, escape: bool },
+    Block { kind: BlockKind, path: Path, body: Vec<Node>, inverse: Vec<Node> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BlockKind {
+    If,
+    Unless,
+    Each,
+    With,
+}
+
+impl BlockKind {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "if" => Some(BlockKind::If),
+            "unless" => Some(BlockKind::Unless),
+            "each" => Some(BlockKind::Each),
+            "with" => Some(BlockKind::With),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            BlockKind::If => "if",
+            BlockKind::Unless => "unless",
+            BlockKind::Each => "each",
+            BlockKind::With => "with",
+        }
+    }
+}
+
+/// A variable reference: `parents` scopes up, then `segments` down; no segments is `this`
+#[derive(Debug, Clone, PartialEq)]
+struct Path {
+    parents: usize,
+    segments: Vec<String>,
+}
+
+impl Path {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let mut rest = expr.trim();
+        if rest.is_empty() || rest.contains(char::is_whitespace) || rest.starts_with(['>', '&', '^']) {
+            return Err(format!("Unsupported expression '{}'; helpers are not available", expr.trim()));
+        }
+        let mut parents = 0;
+        while let Some(stripped) = rest.strip_prefix("../") {
+            parents += 1;
+            rest = stripped;
+        }
+        let rest = match rest.strip_prefix("this") {
+            Some(r) if r.is_empty() || r.starts_with(['.', '/']) => &r[r.len().min(1)..],
+            _ => rest,
+        };
+        let segments: Vec<String> = rest.split(['.', '/']).filter(|s| !s.is_empty()).map(str::to_string).collect();
+        if segments.iter().skip(1).any(|s| s.starts_with('@')) {
+            return Err(format!("Invalid path '{}'", expr.trim()));
+        }
+        Ok(Self { parents, segments })
+    }
+}
+
+enum Token {
+    Text(String),
+    /// Contents of a `{{ }}` tag, and whether it was a triple-stash
+    Tag(String, bool),
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut tokens = tokens.into_iter();
+        let (nodes, end) = parse_nodes(&mut tokens, None)?;
+        debug_assert!(end.is_none());
+        Ok(Self { nodes })
+    }
+
+    pub fn render(&self, data: &Value) -> String {
+        self.render_with(data, true)
+    }
+
+    /// Render for plain text such as file names: `{{path}}` is not HTML-escaped
+    pub fn render_text(&self, data: &Value) -> String {
+        self.render_with(data, false)
+    }
+
+    fn render_with(&self, data: &Value, html: bool) -> String {
+        let mut out = String::new();
+        let mut scopes = vec![Scope { value: data, vars: Vec::new() }];
+        render_nodes(&self.nodes, &mut scopes, html, &mut out);
+        out
+    }
+}
+
+/// Parse and render in one step
+pub fn render(source: &str, data: &Value) -> Result<String, String> {
+    Ok(Template::parse(source)?.render(data))
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start..];
+        let (raw, open, close) = if after.starts_with("{{{") { (true, 3, "}}}") } else { (false, 2, "}}") };
+        let end = after[open..]
+            .find(close)
+            .ok_or_else(|| format!("Unclosed tag at byte {}", source.len() - after.len()))?;
+        let content = after[open..open + end].trim().to_string();
+        if !content.starts_with('!') {
+            tokens.push(Token::Tag(content, raw));
+        }
+        rest = &after[open + end + close.len()..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Nodes up to the matching `{{/kind}}` when inside a block, with the `{{else}}` branch
+/// returned as the second list
+fn parse_nodes(
+    tokens: &mut impl Iterator<Item = Token>,
+    block: Option<BlockKind>,
+) -> Result<(Vec<Node>, Option<Vec<Node>>), String> {
+    let mut body = Vec::new();
+    let mut inverse: Option<Vec<Node>> = None;
+    while let Some(token) = tokens.next() {
+        let nodes = inverse.as_mut().unwrap_or(&mut body);
+        let (content, raw) = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(content, raw) => (content, raw),
+        };
+
+        if let Some(open) = content.strip_prefix('#') {
+            let (name, arg) = open.split_once(char::is_whitespace).unwrap_or((open, ""));
+            let kind = BlockKind::parse(name).ok_or_else(|| format!("Unsupported block helper '#{}'", name))?;
+            if arg.trim().is_empty() {
+                return Err(format!("'#{}' requires an argument", name));
+            }
+            let path = Path::parse(arg)?;
+            let (inner, else_branch) = parse_nodes(tokens, Some(kind))?;
+            nodes.push(Node::Block { kind, path, body: inner, inverse: else_branch.unwrap_or_default() });
+        } else if let Some(close) = content.strip_prefix('/') {
+            return match block {
+                Some(kind) if kind.name() == close.trim() => Ok((body, inverse)),
+                Some(kind) => Err(format!("'{{{{/{}}}}}' closes '#{}'", close.trim(), kind.name())),
+                None => Err(format!("'{{{{/{}}}}}' has no opening block", close.trim())),
+            };
+        } else if content == "else" {
+            if block.is_none() || inverse.is_some() {
+                return Err("Unexpected '{{else}}'".to_string());
+            }
+            inverse = Some(Vec::new());
+        } else {
+            nodes.push(Node::Value { path: Path::parse(&content)?, escape: !raw });
+        }
+    }
+    match block {
+        Some(kind) => Err(format!("'#{}' is never closed", kind.name())),
+        None => Ok((body, None)),
+    }
+}
+
+struct Scope<'a> {
+    value: &'a Value,
+    /// `@index`, `@key`, `@first`, `@last` inside `#each`
+    vars: Vec<(&'static str, Value)>,
+}
+
+/// The data value a path points at; `@` variables are not data, see `resolve`
+fn lookup<'a>(path: &Path, scopes: &[Scope<'a>]) -> Option<&'a Value> {
+    let scope = scopes.len().checked_sub(path.parents + 1).map(|i| &scopes[i])?;
+    let mut value = scope.value;
+    for segment in &path.segments {
+        value = match value {
+            _ if segment.starts_with('@') => return None,
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+/// Resolve a path to an owned value, including `@` variables
+fn resolve(path: &Path, scopes: &[Scope<'_>]) -> Value {
+    if let Some(first) = path.segments.first().filter(|s| s.starts_with('@')) {
+        let scope = match scopes.len().checked_sub(path.parents + 1) {
+            Some(i) => &scopes[i],
+            None => return Value::Null,
+        };
+        return scope
+            .vars
+            .iter()
+            .find(|(name, _)| *name == &first[1..])
+            .map(|(_, v)| v.clone())
+            .unwrap_or(Value::Null);
+    }
+    lookup(path, scopes).cloned().unwrap_or(Value::Null)
+}
+
+/// Handlebars truthiness: false, null, "", 0 and [] are falsy
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(to_text).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            '`' => out.push_str("&#x60;"),
+            '=' => out.push_str("&#x3D;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn render_nodes<'a>(nodes: &[Node], scopes: &mut Vec<Scope<'a>>, html: bool, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value { path, escape } => {
+                let text = to_text(&resolve(path, scopes));
+                out.push_str(&if *escape && html { escape_html(&text) } else { text });
+            }
+            Node::Block { kind, path, body, inverse } => match kind {
+                BlockKind::If | BlockKind::Unless => {
+                    let condition = truthy(&resolve(path, scopes)) == (*kind == BlockKind::If);
+                    render_nodes(if condition { body } else { inverse }, scopes, html, out);
+                }
+                BlockKind::With => match lookup(path, scopes).filter(|v| truthy(v)) {
+                    Some(value) => {
+                        scopes.push(Scope { value, vars: Vec::new() });
+                        render_nodes(body, scopes, html, out);
+                        scopes.pop();
+                    }
+                    None => render_nodes(inverse, scopes, html, out),
+                },
+                BlockKind::Each => {
+                    let items: Vec<(Option<&String>, &'a Value)> = match lookup(path, scopes) {
+                        Some(Value::Array(items)) => items.iter().map(|v| (None, v)).collect(),
+                        Some(Value::Object(map)) => map.iter().map(|(k, v)| (Some(k), v)).collect(),
+                        _ => Vec::new(),
+                    };
+                    if items.is_empty() {
+                        render_nodes(inverse, scopes, html, out);
+                        continue;
+                    }
+                    let last = items.len() - 1;
+                    for (i, (key, value)) in items.into_iter().enumerate() {
+                        let mut vars = vec![
+                            ("index", Value::from(i)),
+                            ("first", Value::Bool(i == 0)),
+                            ("last", Value::Bool(i == last)),
+                        ];
+                        if let Some(key) = key {
+                            vars.push(("key", Value::String(key.clone())));
+                        }
+                        scopes.push(Scope { value, vars });
+                        render_nodes(body, scopes, html, out);
+                        scopes.pop();
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_paths_blocks_and_escaping() {
+        let invoice = json!({
+            "number": "INV-7",
+            "customer": { "name": "Ada & Co", "vip": true },
+            "notes": "<b>Net 30</b>",
+            "lines": [
+                { "item": "Widget", "qty": 2 },
+                { "item": "Gadget", "qty": 0 },
+            ],
+            "discount": 0,
+        });
+        let source = "{{! invoice }}<h1>{{number}}</h1>{{#with customer}}<p>{{name}}{{#if vip}} (VIP){{/if}} for {{../number}}</p>{{/with}}\
+            {{{notes}}}<ul>{{#each lines}}<li>{{@index}}:{{item}}x{{qty}}{{#if @last}}.{{else}},{{/if}}</li>{{/each}}</ul>\
+            {{#unless discount}}No discount{{/unless}}{{#each missing}}x{{else}}-{{/each}}{{this.customer.name}}";
+        assert_eq!(
+            render(source, &invoice).unwrap(),
+            "<h1>INV-7</h1><p>Ada &amp; Co (VIP) for INV-7</p><b>Net 30</b>\
+             <ul><li>0:Widgetx2,</li><li>1:Gadgetx0.</li></ul>No discount-Ada &amp; Co"
+        );
+
+        assert!(Template::parse("{{#each lines}}").is_err());
+        assert!(Template::parse("{{#if a}}{{/each}}").is_err());
+        assert!(Template::parse("{{> header}}").is_err());
+        assert!(Template::parse("{{format_date created_at}}").is_err());
+        assert!(Template::parse("{{name").is_err());
+    }
+}
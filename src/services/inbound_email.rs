@@ -0,0 +1,374 @@
+//! Inbound email webhooks from SendGrid, Mailgun and Amazon SES
+//!
+//! Each provider posts received mail in its own shape: SendGrid's Inbound Parse and
+//! Mailgun's routes send `multipart/form-data` fields (Mailgun sends a urlencoded form
+//! when there are no attachments), while SES publishes to SNS, whose notification wraps
+//! a JSON document carrying the raw MIME message. All of them are reduced to one
+//! [`InboundEmail`]; raw MIME (SES, or SendGrid with "send raw" enabled) is parsed with
+//! `mail-parser`.
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use mail_parser::{Address, MessageParser, MimeHeaders};
+use serde_json::Value;
+use sha2::Sha256;
+
+pub const EMAIL_PROVIDERS: &[&str] = &["sendgrid", "mailgun", "ses"];
+
+/// Parts a mailbox's field mapping may name
+pub const EMAIL_PARTS: &[&str] = &["from", "to", "subject", "text", "html", "message_id"];
+
+/// A received message, independent of the provider that delivered it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InboundEmail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+    pub message_id: Option<String>,
+    pub attachments: Vec<InboundAttachment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboundAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+impl InboundEmail {
+    /// Value of a named part, as used in a mailbox's field mapping
+    pub fn part(&self, name: &str) -> Option<Value> {
+        Some(match name {
+            "from" => Value::String(self.from.clone()),
+            "to" => Value::String(self.to.clone()),
+            "subject" => Value::String(self.subject.clone()),
+            "text" => Value::String(self.text.clone()),
+            "html" => Value::String(self.html.clone()),
+            "message_id" => self.message_id.clone().map(Value::String).unwrap_or(Value::Null),
+            _ => return None,
+        })
+    }
+}
+
+/// What a provider's POST turned out to hold
+#[derive(Debug, Clone, PartialEq)]
+pub enum InboundPayload {
+    Email(InboundEmail),
+    /// An SNS topic subscription that must be confirmed by fetching this URL
+    SubscriptionConfirmation(String),
+    /// A notification that carries no message, e.g. an SNS unsubscribe notice
+    Ignored(String),
+}
+
+/// One field of a form body; files have a filename
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormField {
+    pub name: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl FormField {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.data).into_owned()
+    }
+}
+
+/// Parse a provider's request body
+///
+/// When `mailgun_signing_key` is given, Mailgun posts must carry a valid signature.
+pub fn parse(provider: &str, content_type: &str, body: &[u8], mailgun_signing_key: Option<&str>) -> Result<InboundPayload, String> {
+    match provider {
+        "sendgrid" => parse_sendgrid(&form_fields(content_type, body)?),
+        "mailgun" => {
+            let fields = form_fields(content_type, body)?;
+            if let Some(key) = mailgun_signing_key {
+                if !verify_mailgun(&fields, key) {
+                    return Err("Mailgun signature does not match".to_string());
+                }
+            }
+            parse_mailgun(&fields)
+        }
+        "ses" => parse_ses(body),
+        other => Err(format!("Unknown email provider '{}'", other)),
+    }
+}
+
+fn field<'a>(fields: &'a [FormField], name: &str) -> Option<&'a FormField> {
+    fields.iter().find(|f| f.name == name)
+}
+
+fn field_text(fields: &[FormField], name: &str) -> String {
+    field(fields, name).map(FormField::text).unwrap_or_default()
+}
+
+/// Inbound Parse: parsed fields plus `attachmentN` files, or the whole message in `email`
+fn parse_sendgrid(fields: &[FormField]) -> Result<InboundPayload, String> {
+    if let Some(raw) = field(fields, "email") {
+        return parse_mime(&raw.data).map(InboundPayload::Email);
+    }
+    let attachments = fields
+        .iter()
+        .filter(|f| f.name.starts_with("attachment") && f.filename.is_some())
+        .map(attachment)
+        .collect();
+    Ok(InboundPayload::Email(InboundEmail {
+        from: field_text(fields, "from"),
+        to: field_text(fields, "to"),
+        subject: field_text(fields, "subject"),
+        text: field_text(fields, "text"),
+        html: field_text(fields, "html"),
+        message_id: None,
+        attachments,
+    }))
+}
+
+/// Routes forwarding: `body-plain`/`body-html` plus `attachment-N` files
+fn parse_mailgun(fields: &[FormField]) -> Result<InboundPayload, String> {
+    let attachments = fields
+        .iter()
+        .filter(|f| f.name.starts_with("attachment-") && f.filename.is_some())
+        .map(attachment)
+        .collect();
+    let message_id = Some(field_text(fields, "Message-Id")).filter(|id| !id.is_empty());
+    Ok(InboundPayload::Email(InboundEmail {
+        from: field_text(fields, "from"),
+        to: field_text(fields, "recipient"),
+        subject: field_text(fields, "subject"),
+        text: field_text(fields, "body-plain"),
+        html: field_text(fields, "body-html"),
+        message_id,
+        attachments,
+    }))
+}
+
+fn attachment(field: &FormField) -> InboundAttachment {
+    InboundAttachment {
+        filename: field.filename.clone().unwrap_or_default(),
+        content_type: field.content_type.clone().unwrap_or_else(|| "application/octet-stream".to_string()),
+        bytes: field.data.clone(),
+    }
+}
+
+/// Mailgun signs `timestamp` followed by `token` with the domain's webhook signing key
+fn verify_mailgun(fields: &[FormField], key: &str) -> bool {
+    let Ok(signature) = hex::decode(field_text(fields, "signature")) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(field_text(fields, "timestamp").as_bytes());
+    mac.update(field_text(fields, "token").as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// An SNS notification of an SES receipt, or the receipt itself with raw message delivery
+fn parse_ses(body: &[u8]) -> Result<InboundPayload, String> {
+    let envelope: Value = serde_json::from_slice(body).map_err(|e| format!("Invalid SNS message: {}", e))?;
+    let notification = match envelope["Type"].as_str() {
+        Some("SubscriptionConfirmation") => {
+            let url = envelope["SubscribeURL"].as_str().ok_or("SubscriptionConfirmation without SubscribeURL")?;
+            return Ok(InboundPayload::SubscriptionConfirmation(url.to_string()));
+        }
+        Some("Notification") => {
+            let message = envelope["Message"].as_str().ok_or("SNS notification without a Message")?;
+            serde_json::from_str(message).map_err(|e| format!("Invalid SES notification: {}", e))?
+        }
+        Some(other) => return Ok(InboundPayload::Ignored(format!("SNS {}", other))),
+        None => envelope,
+    };
+    if notification["notificationType"].as_str() != Some("Received") {
+        return Ok(InboundPayload::Ignored(format!("SES {}", notification["notificationType"])));
+    }
+
+    if let Some(content) = notification["content"].as_str() {
+        let raw = if notification["receipt"]["action"]["encoding"].as_str() == Some("BASE64") {
+            base64::engine::general_purpose::STANDARD
+                .decode(content.trim())
+                .map_err(|e| format!("Invalid base64 message content: {}", e))?
+        } else {
+            content.as_bytes().to_vec()
+        };
+        return parse_mime(&raw).map(InboundPayload::Email);
+    }
+
+    // Without content (the message went to S3) only the headers are known
+    let headers = &notification["mail"]["commonHeaders"];
+    let join = |value: &Value| {
+        value.as_array().map(|list| list.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", ")).unwrap_or_default()
+    };
+    Ok(InboundPayload::Email(InboundEmail {
+        from: join(&headers["from"]),
+        to: join(&headers["to"]),
+        subject: headers["subject"].as_str().unwrap_or_default().to_string(),
+        message_id: headers["messageId"].as_str().map(str::to_string),
+        ..Default::default()
+    }))
+}
+
+/// A raw RFC 5322 message
+pub fn parse_mime(raw: &[u8]) -> Result<InboundEmail, String> {
+    let message = MessageParser::default().parse(raw).ok_or("Message could not be parsed")?;
+    let attachments = message
+        .attachments()
+        .map(|part| InboundAttachment {
+            filename: part.attachment_name().unwrap_or_default().to_string(),
+            content_type: part
+                .content_type()
+                .map(|ct| match &ct.c_subtype {
+                    Some(subtype) => format!("{}/{}", ct.c_type, subtype),
+                    None => ct.c_type.to_string(),
+                })
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+            bytes: part.contents().to_vec(),
+        })
+        .collect();
+    Ok(InboundEmail {
+        from: message.from().map(format_address).unwrap_or_default(),
+        to: message.to().map(format_address).unwrap_or_default(),
+        subject: message.subject().unwrap_or_default().to_string(),
+        text: message.body_text(0).map(|text| text.into_owned()).unwrap_or_default(),
+        html: message.body_html(0).map(|html| html.into_owned()).unwrap_or_default(),
+        message_id: message.message_id().map(str::to_string),
+        attachments,
+    })
+}
+
+/// `Name <address>` entries, comma-separated
+fn format_address(address: &Address) -> String {
+    address
+        .iter()
+        .map(|addr| match (addr.name(), addr.address()) {
+            (Some(name), Some(email)) => format!("{} <{}>", name, email),
+            (None, Some(email)) => email.to_string(),
+            (Some(name), None) => name.to_string(),
+            (None, None) => String::new(),
+        })
+        .filter(|entry| !entry.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Fields of a `multipart/form-data` or `application/x-www-form-urlencoded` body
+pub fn form_fields(content_type: &str, body: &[u8]) -> Result<Vec<FormField>, String> {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    if mime == "application/x-www-form-urlencoded" {
+        return Ok(url::form_urlencoded::parse(body)
+            .map(|(name, value)| FormField { name: name.into_owned(), data: value.into_owned().into_bytes(), ..Default::default() })
+            .collect());
+    }
+    if mime != "multipart/form-data" {
+        return Err(format!("Expected a form body, got '{}'", content_type));
+    }
+    let boundary = header_parameter(content_type, "boundary").ok_or("multipart/form-data without a boundary")?;
+    multipart_fields(body, &boundary)
+}
+
+fn multipart_fields(body: &[u8], boundary: &str) -> Result<Vec<FormField>, String> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut fields = Vec::new();
+    let mut rest = match find(body, &delimiter) {
+        Some(start) => &body[start + delimiter.len()..],
+        None => return Err("multipart body has no parts".to_string()),
+    };
+
+    // Each part is `\r\n<headers>\r\n\r\n<data>\r\n--boundary`; `--` after a boundary ends the body
+    while !rest.starts_with(b"--") {
+        let part_start = rest.strip_prefix(b"\r\n").ok_or("Malformed multipart boundary")?;
+        let end = find(part_start, &[b"\r\n".as_slice(), &delimiter].concat()).ok_or("Unterminated multipart part")?;
+        let part = &part_start[..end];
+        rest = &part_start[end + 2 + delimiter.len()..];
+
+        let header_end = find(part, b"\r\n\r\n").ok_or("Multipart part without headers")?;
+        let mut field = FormField { data: part[header_end + 4..].to_vec(), ..Default::default() };
+        for line in String::from_utf8_lossy(&part[..header_end]).split("\r\n") {
+            let Some((name, value)) = line.split_once(':') else { continue };
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-disposition" => {
+                    field.name = header_parameter(value, "name").unwrap_or_default();
+                    field.filename = header_parameter(value, "filename");
+                }
+                "content-type" => field.content_type = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+        fields.push(field);
+    }
+    Ok(fields)
+}
+
+/// `name=value` or `name="value"` parameter of a header such as Content-Disposition
+fn header_parameter(header: &str, name: &str) -> Option<String> {
+    header.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        if !key.trim().eq_ignore_ascii_case(name) {
+            return None;
+        }
+        let value = value.trim();
+        Some(match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(quoted) => quoted.replace("\\\"", "\""),
+            None => value.to_string(),
+        })
+    })
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sendgrid_forms_with_attachments() {
+        let body = concat!(
+            "--xYz\r\nContent-Disposition: form-data; name=\"from\"\r\n\r\nAda <ada@example.com>\r\n",
+            "--xYz\r\nContent-Disposition: form-data; name=\"to\"\r\n\r\nsupport@acme.test\r\n",
+            "--xYz\r\nContent-Disposition: form-data; name=\"subject\"\r\n\r\nPrinter on fire\r\n",
+            "--xYz\r\nContent-Disposition: form-data; name=\"text\"\r\n\r\nIt is\r\nstill burning\r\n",
+            "--xYz\r\nContent-Disposition: form-data; name=\"attachment1\"; filename=\"fire.jpg\"\r\n",
+            "Content-Type: image/jpeg\r\n\r\n\u{1}\u{2}\r\n--xYz--\r\n",
+        );
+        let payload = parse("sendgrid", "multipart/form-data; boundary=xYz", body.as_bytes(), None).unwrap();
+        let InboundPayload::Email(email) = payload else { panic!("expected an email") };
+        assert_eq!(email.from, "Ada <ada@example.com>");
+        assert_eq!(email.subject, "Printer on fire");
+        assert_eq!(email.text, "It is\r\nstill burning");
+        assert_eq!(email.attachments, vec![InboundAttachment {
+            filename: "fire.jpg".to_string(),
+            content_type: "image/jpeg".to_string(),
+            bytes: vec![1, 2],
+        }]);
+    }
+
+    #[test]
+    fn parses_ses_notifications_and_checks_mailgun_signatures() {
+        let raw = "From: Ada <ada@example.com>\r\nTo: support@acme.test\r\nSubject: Hello\r\nMessage-ID: <m1@example.com>\r\n\r\nHi there\r\n";
+        let message = serde_json::json!({
+            "notificationType": "Received",
+            "receipt": { "action": { "type": "SNS", "encoding": "BASE64" } },
+            "content": base64::engine::general_purpose::STANDARD.encode(raw),
+        });
+        let envelope = serde_json::json!({ "Type": "Notification", "Message": message.to_string() });
+        let InboundPayload::Email(email) = parse("ses", "text/plain", envelope.to_string().as_bytes(), None).unwrap() else {
+            panic!("expected an email")
+        };
+        assert_eq!((email.from.as_str(), email.subject.as_str()), ("Ada <ada@example.com>", "Hello"));
+        assert_eq!(email.message_id.as_deref(), Some("m1@example.com"));
+        assert_eq!(email.text.trim_end(), "Hi there");
+
+        let confirm = serde_json::json!({ "Type": "SubscriptionConfirmation", "SubscribeURL": "https://sns.us-east-1.amazonaws.com/?Action=ConfirmSubscription" });
+        assert!(matches!(parse("ses", "", confirm.to_string().as_bytes(), None), Ok(InboundPayload::SubscriptionConfirmation(_))));
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"key-1").unwrap();
+        mac.update(b"1700000000abc");
+        let signature = hex::encode(mac.finalize().into_bytes());
+        let form = format!("timestamp=1700000000&token=abc&signature={}&from=ada%40example.com&subject=Hi", signature);
+        let urlencoded = "application/x-www-form-urlencoded";
+        assert!(parse("mailgun", urlencoded, form.as_bytes(), Some("key-1")).is_ok());
+        assert!(parse("mailgun", urlencoded, form.as_bytes(), Some("key-2")).is_err());
+    }
+}
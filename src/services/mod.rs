@@ -1,3 +1,11 @@
 pub mod describe_service;
+pub mod handlebars;
+pub mod inbound_email;
+pub mod mailer;
+pub mod schema_lint;
+pub mod tenant_service;
+pub mod webhook_signing;
 
-pub use describe_service::*;
\ No newline at end of file
+pub use describe_service::*;
+pub use schema_lint::*;
+pub use tenant_service::*;
\ No newline at end of file
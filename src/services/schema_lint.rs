@@ -0,0 +1,247 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::database::checks::property_checks;
+use crate::database::identifiers::{validate_derived, validate_identifier};
+use crate::observer::implementations::create_schema_ddl::SYSTEM_FIELDS;
+
+/// JSON Schema types with a column mapping; anything else is stored as TEXT
+const KNOWN_TYPES: &[&str] = &["string", "integer", "number", "boolean", "array", "object"];
+
+/// String formats with their own column type or check
+const KNOWN_FORMATS: &[&str] = &["uuid", "date-time", "email", "uri", "date", "time"];
+
+/// One problem found in a schema definition
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    /// Where the problem is, e.g. `properties.email.format`
+    pub path: String,
+    pub message: String,
+}
+
+/// Problems found in a schema definition
+///
+/// Errors stop the schema from being created or leave it broken; warnings are worth a
+/// look but the schema works.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LintReport {
+    pub schema: String,
+    pub valid: bool,
+    pub errors: Vec<LintIssue>,
+    pub warnings: Vec<LintIssue>,
+}
+
+impl LintReport {
+    fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(LintIssue { path: path.into(), message: message.into() });
+    }
+
+    fn warning(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.warnings.push(LintIssue { path: path.into(), message: message.into() });
+    }
+}
+
+/// Lint the definition of `schema_name` against the schemas that already exist
+///
+/// `existing_schemas` resolves `x-monk-relationship` targets; a schema may refer to itself.
+pub fn lint_definition(schema_name: &str, definition: &Value, existing_schemas: &[String]) -> LintReport {
+    let mut report = LintReport { schema: schema_name.to_string(), ..Default::default() };
+
+    let Some(schema) = definition.as_object() else {
+        report.error("", "Schema must be a JSON object");
+        report.valid = false;
+        return report;
+    };
+
+    lint_identifier(&mut report, "name", "Schema", schema_name);
+    if let Some(table) = schema.get("table").and_then(|t| t.as_str()) {
+        lint_identifier(&mut report, "table", "Table", table);
+    }
+    if is_blank(schema.get("title")) {
+        report.error("title", "Schema has no title");
+    }
+    if is_blank(schema.get("description")) {
+        report.warning("description", "Schema has no description");
+    }
+
+    let properties = match schema.get("properties").and_then(|p| p.as_object()) {
+        Some(properties) if !properties.is_empty() => properties,
+        _ => {
+            report.error("properties", "Schema defines no properties");
+            report.valid = false;
+            return report;
+        }
+    };
+
+    let table = schema.get("table").and_then(|t| t.as_str()).unwrap_or(schema_name);
+    if let Err(e) = validate_derived(&format!("{}_notify_change", table)) {
+        report.error("name", e.to_string());
+    }
+    for (field, property) in properties {
+        lint_property(&mut report, table, field, property, schema_name, existing_schemas);
+    }
+
+    for (index, field) in schema.get("required").and_then(|r| r.as_array()).into_iter().flatten().enumerate() {
+        match field.as_str() {
+            Some(field) if properties.contains_key(field) => {}
+            Some(field) => report.error(format!("required[{}]", index), format!("Required property '{}' is not defined", field)),
+            None => report.error(format!("required[{}]", index), "Required entries must be property names"),
+        }
+    }
+
+    report.valid = report.errors.is_empty();
+    report
+}
+
+fn lint_property(
+    report: &mut LintReport,
+    table: &str,
+    field: &str,
+    property: &Value,
+    schema_name: &str,
+    existing_schemas: &[String],
+) {
+    let path = format!("properties.{}", field);
+    if SYSTEM_FIELDS.contains(&field) {
+        report.error(&path, format!("'{}' is a system column every table already has", field));
+        return;
+    }
+    lint_identifier(report, &path, "Property", field);
+    for check in property_checks(table, field, property) {
+        if let Err(e) = validate_derived(&check.name) {
+            report.error(&path, e.to_string());
+        }
+    }
+
+    let Some(property) = property.as_object() else {
+        report.error(&path, "Property must be a JSON object");
+        return;
+    };
+    let property_type = match property.get("type").and_then(|t| t.as_str()) {
+        Some(t) => t,
+        None => {
+            report.error(format!("{}.type", path), "Property has no type");
+            return;
+        }
+    };
+    if !KNOWN_TYPES.contains(&property_type) {
+        report.warning(format!("{}.type", path), format!("Unknown type '{}' is stored as TEXT", property_type));
+    }
+    if is_blank(property.get("description")) {
+        report.warning(format!("{}.description", path), "Property has no description");
+    }
+
+    lint_type_keywords(report, &path, property_type, property);
+
+    if let Some(relationship) = property.get("x-monk-relationship") {
+        let target = relationship.get("schema").and_then(|s| s.as_str()).unwrap_or_default();
+        if target.is_empty() {
+            report.error(format!("{}.x-monk-relationship.schema", path), "Relationship has no target schema");
+        } else if target != schema_name && !existing_schemas.iter().any(|s| s == target) {
+            report.error(
+                format!("{}.x-monk-relationship.schema", path),
+                format!("Relationship target schema '{}' does not exist", target),
+            );
+        }
+        match relationship.get("type").and_then(|t| t.as_str()) {
+            Some("owned") | Some("referenced") => {}
+            _ => report.error(
+                format!("{}.x-monk-relationship.type", path),
+                "Relationship type must be 'owned' or 'referenced'",
+            ),
+        }
+        if property.get("format").and_then(|f| f.as_str()) != Some("uuid") {
+            report.warning(&path, "Relationship properties usually hold record ids (format: uuid)");
+        }
+    }
+}
+
+/// Keywords that only apply to other types, which the schema would silently ignore
+fn lint_type_keywords(report: &mut LintReport, path: &str, property_type: &str, property: &Map<String, Value>) {
+    let numeric = matches!(property_type, "integer" | "number");
+    let string = property_type == "string";
+
+    for keyword in ["minLength", "maxLength", "pattern"] {
+        if property.contains_key(keyword) && !string {
+            report.warning(format!("{}.{}", path, keyword), format!("'{}' only applies to strings", keyword));
+        }
+    }
+    for keyword in ["minimum", "maximum"] {
+        if property.contains_key(keyword) && !numeric {
+            report.warning(format!("{}.{}", path, keyword), format!("'{}' only applies to numbers", keyword));
+        }
+    }
+    if property.contains_key("enum") && !string {
+        report.warning(format!("{}.enum", path), "'enum' is only enforced on strings");
+    }
+    if let Some(format) = property.get("format").and_then(|f| f.as_str()) {
+        if !string {
+            report.warning(format!("{}.format", path), "'format' only applies to strings");
+        } else if !KNOWN_FORMATS.contains(&format) {
+            report.warning(format!("{}.format", path), format!("Unknown format '{}' is stored as plain TEXT", format));
+        }
+    }
+
+    if let (Some(min), Some(max)) = (bound(property, numeric, true), bound(property, numeric, false)) {
+        if min > max {
+            report.error(path, "Lower bound is greater than upper bound; no value can satisfy both");
+        }
+    }
+}
+
+fn lint_identifier(report: &mut LintReport, path: &str, kind: &'static str, name: &str) {
+    if let Err(e) = validate_identifier(kind, name) {
+        report.error(path, e.to_string());
+    }
+}
+
+fn is_blank(value: Option<&Value>) -> bool {
+    value.and_then(|v| v.as_str()).is_none_or(|s| s.trim().is_empty())
+}
+
+fn bound(property: &Map<String, Value>, numeric: bool, lower: bool) -> Option<f64> {
+    let keyword = match (numeric, lower) {
+        (true, true) => "minimum",
+        (true, false) => "maximum",
+        (false, true) => "minLength",
+        (false, false) => "maxLength",
+    };
+    property.get(keyword).and_then(|v| v.as_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lint_definition() {
+        let definition = json!({
+            "name": "invoice",
+            "title": "Invoice",
+            "description": "Invoices",
+            "properties": {
+                "id": { "type": "string" },
+                "total": { "type": "number", "minimum": 10, "maximum": 5, "description": "Total" },
+                "customer": {
+                    "type": "string",
+                    "format": "uuid",
+                    "description": "Customer",
+                    "x-monk-relationship": { "type": "referenced", "schema": "customer", "name": "invoices" }
+                },
+                "order": { "type": "text", "description": "Order" }
+            }
+        });
+        let report = lint_definition("invoice", &definition, &[]);
+        let paths = |issues: &[LintIssue]| issues.iter().map(|i| i.path.clone()).collect::<Vec<_>>();
+        assert!(!report.valid);
+        assert_eq!(
+            paths(&report.errors),
+            ["properties.customer.x-monk-relationship.schema", "properties.id", "properties.order", "properties.total"]
+        );
+        assert_eq!(paths(&report.warnings), ["properties.order.type"]);
+
+        let report = lint_definition("invoice", &definition, &["customer".to_string()]);
+        assert_eq!(paths(&report.errors), ["properties.id", "properties.order", "properties.total"]);
+    }
+}
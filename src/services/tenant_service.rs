@@ -0,0 +1,240 @@
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use tracing::{info, warn};
+
+use crate::database::manager::{DatabaseError, DatabaseManager};
+use crate::database::regions;
+use crate::database::service::find_tenant_by_name;
+use crate::services::describe_service::{DescribeError, DescribeService};
+
+/// A provisioned tenant
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantInfo {
+    pub name: String,
+    pub database: String,
+    pub template: String,
+    /// Schemas created or overridden from the request, in the order given
+    pub schemas: Vec<String>,
+    pub region: String,
+    /// Database cluster within the region holding the tenant's database
+    pub cluster: String,
+}
+
+/// Where a tenant's database lives after a move
+#[derive(Debug, Clone, Serialize)]
+pub struct TenantPlacement {
+    pub name: String,
+    pub database: String,
+    pub region: String,
+    pub cluster: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TenantError {
+    #[error("Invalid tenant name: {0}")]
+    InvalidName(String),
+    #[error("Tenant already exists: {0}")]
+    AlreadyExists(String),
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+    #[error("Tenant not found: {0}")]
+    NotFound(String),
+    #[error("Invalid region: {0}")]
+    InvalidRegion(String),
+    #[error("Cluster '{cluster}' is not in region '{region}'")]
+    InvalidCluster { cluster: String, region: String },
+    #[error("Database {database} does not exist on cluster {cluster}")]
+    NotReplicated { database: String, cluster: String },
+    #[error("Database error: {0}")]
+    Database(#[from] DatabaseError),
+    #[error("Schema '{schema}' failed: {source}")]
+    Schema {
+        schema: String,
+        #[source]
+        source: DescribeError,
+    },
+}
+
+/// Creates tenants by cloning template databases
+pub struct TenantService {
+    main_pool: PgPool,
+}
+
+impl TenantService {
+    pub async fn new() -> Result<Self, TenantError> {
+        Ok(Self { main_pool: DatabaseManager::main_pool().await? })
+    }
+
+    /// Create a tenant from `template_{template}` in the default region
+    pub async fn create_tenant(&self, name: &str, template: &str) -> Result<TenantInfo, TenantError> {
+        self.create_tenant_with_schemas(name, template, None, Vec::new()).await
+    }
+
+    /// Create a tenant from `template_{template}` and apply `schemas` on top of it
+    ///
+    /// Each schema is a JSON Schema with a `name`. Schemas the template already has are
+    /// updated, others are created, all through [`DescribeService`] so the usual DDL
+    /// observers run. If any schema fails the new database is dropped and the tenant is
+    /// not registered, so a tenant either comes up fully formed or not at all.
+    ///
+    /// The database is placed on the least loaded cluster of `region`, or of
+    /// `database.default_region` when none is given.
+    pub async fn create_tenant_with_schemas(
+        &self,
+        name: &str,
+        template: &str,
+        region: Option<&str>,
+        schemas: Vec<Value>,
+    ) -> Result<TenantInfo, TenantError> {
+        if !Self::is_valid_tenant_name(name) {
+            return Err(TenantError::InvalidName(name.to_string()));
+        }
+        if find_tenant_by_name(name).await?.is_some() {
+            return Err(TenantError::AlreadyExists(name.to_string()));
+        }
+
+        let region = region.unwrap_or(&crate::config::config().database.default_region).to_string();
+        let cluster = regions::choose_cluster(&self.main_pool, &region)
+            .await?
+            .ok_or_else(|| TenantError::InvalidRegion(region.clone()))?
+            .name;
+
+        let template_db = format!("template_{}", template);
+        let database = format!("tenant_{}", uuid::Uuid::new_v4().simple());
+        if let Err(e) = DatabaseManager::clone_database(&template_db, &database, &cluster).await {
+            return Err(match e {
+                DatabaseError::InvalidTenantName(_) => TenantError::TemplateNotFound(template.to_string()),
+                DatabaseError::Sqlx(ref sqlx_err) if Self::is_missing_database(sqlx_err) => {
+                    TenantError::TemplateNotFound(template.to_string())
+                }
+                other => other.into(),
+            });
+        }
+
+        let result = self.provision(name, &database, &region, &cluster, schemas).await;
+        match result {
+            Ok(applied) => {
+                info!("Created tenant {} ({}) from template {} on cluster {}", name, database, template, cluster);
+                Ok(TenantInfo {
+                    name: name.to_string(),
+                    database,
+                    template: template.to_string(),
+                    schemas: applied,
+                    region,
+                    cluster,
+                })
+            }
+            Err(e) => {
+                if let Err(drop_err) = DatabaseManager::drop_database(&database, &cluster).await {
+                    warn!("Failed to drop {} after failed provisioning: {}", database, drop_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Connection pool of an existing tenant
+    pub async fn get_tenant_pool(&self, tenant_name: &str) -> Result<PgPool, TenantError> {
+        let tenant = find_tenant_by_name(tenant_name)
+            .await?
+            .filter(|tenant| tenant.trashed_at.is_none() && tenant.deleted_at.is_none())
+            .ok_or_else(|| TenantError::NotFound(tenant_name.to_string()))?;
+        Ok(DatabaseManager::tenant_pool(&tenant.database).await?)
+    }
+
+    /// Point a tenant at another region, and optionally a specific cluster in it
+    ///
+    /// Only the routing changes: the tenant's database must already have been copied to
+    /// the target cluster under the same name, e.g. with pg_dump or logical replication.
+    /// Cached pools are closed so the next request connects to the new cluster.
+    pub async fn move_tenant(
+        &self,
+        name: &str,
+        region: &str,
+        cluster: Option<&str>,
+    ) -> Result<TenantPlacement, TenantError> {
+        let tenant = find_tenant_by_name(name)
+            .await?
+            .filter(|tenant| tenant.trashed_at.is_none() && tenant.deleted_at.is_none())
+            .ok_or_else(|| TenantError::NotFound(name.to_string()))?;
+
+        let target = match cluster {
+            Some(cluster) => regions::find_cluster(cluster)
+                .filter(|found| found.region == region)
+                .ok_or_else(|| TenantError::InvalidCluster { cluster: cluster.to_string(), region: region.to_string() })?,
+            None => regions::choose_cluster(&self.main_pool, region)
+                .await?
+                .ok_or_else(|| TenantError::InvalidRegion(region.to_string()))?,
+        };
+        if !DatabaseManager::database_exists(&tenant.database, &target.name).await? {
+            return Err(TenantError::NotReplicated { database: tenant.database, cluster: target.name });
+        }
+
+        sqlx::query("UPDATE tenants SET region = $2, cluster = $3, updated_at = NOW() WHERE name = $1")
+            .bind(&tenant.name)
+            .bind(&target.region)
+            .bind(&target.name)
+            .execute(&self.main_pool)
+            .await
+            .map_err(DatabaseError::from)?;
+        DatabaseManager::close_pools(&tenant.database).await;
+
+        info!("Moved tenant {} ({}) to cluster {} in region {}", name, tenant.database, target.name, target.region);
+        Ok(TenantPlacement { name: tenant.name, database: tenant.database, region: target.region, cluster: target.name })
+    }
+
+    /// Apply inline schemas to the cloned database, then register the tenant
+    async fn provision(
+        &self,
+        name: &str,
+        database: &str,
+        region: &str,
+        cluster: &str,
+        schemas: Vec<Value>,
+    ) -> Result<Vec<String>, TenantError> {
+        let describe = DescribeService::new(DatabaseManager::tenant_pool_on(database, cluster).await?);
+        let mut applied = Vec::with_capacity(schemas.len());
+        for schema in schemas {
+            let schema_name = schema.get("name").and_then(|n| n.as_str()).unwrap_or_default().to_string();
+            let fail = |source| TenantError::Schema { schema: schema_name.clone(), source };
+            if schema_name.is_empty() {
+                return Err(fail(DescribeError::InvalidFormat("schema requires a name".to_string())));
+            }
+
+            if describe.select_one(&schema_name).await.map_err(fail)?.is_some() {
+                describe.update_404(&schema_name, schema).await.map_err(fail)?;
+            } else {
+                describe.create_one(&schema_name, schema).await.map_err(fail)?;
+            }
+            applied.push(schema_name);
+        }
+
+        regions::ensure_columns(&self.main_pool).await?;
+        sqlx::query("INSERT INTO tenants (name, database, region, cluster) VALUES ($1, $2, $3, $4)")
+            .bind(name)
+            .bind(database)
+            .bind(region)
+            .bind(cluster)
+            .execute(&self.main_pool)
+            .await
+            .map_err(|e| match &e {
+                sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
+                    TenantError::AlreadyExists(name.to_string())
+                }
+                _ => TenantError::Database(e.into()),
+            })?;
+        Ok(applied)
+    }
+
+    /// Tenant names are URL-safe identifiers of 3 to 50 characters
+    fn is_valid_tenant_name(name: &str) -> bool {
+        (3..=50).contains(&name.len())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+
+    /// SQLSTATE 3D000 invalid_catalog_name: the template database does not exist
+    fn is_missing_database(error: &sqlx::Error) -> bool {
+        matches!(error, sqlx::Error::Database(e) if e.code().as_deref() == Some("3D000"))
+    }
+}
@@ -0,0 +1,96 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::config::WebhookEndpoint;
+
+/// Header carrying `t=<unix seconds>,v1=<hex HMAC-SHA256>`
+pub const SIGNATURE_HEADER: &str = "X-Monk-Signature";
+
+/// Header naming the endpoint a delivery was made for
+pub const WEBHOOK_ID_HEADER: &str = "X-Monk-Webhook-Id";
+
+/// Signatures older (or newer) than this are rejected by `verify`
+pub const DEFAULT_TOLERANCE_SECS: i64 = 300;
+
+/// Signature header value for `body` sent at `timestamp`
+///
+/// The MAC covers `"<timestamp>.<body>"`, so a captured delivery cannot be replayed
+/// with a fresh timestamp.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    format!("t={},v1={}", timestamp, hex::encode(mac(secret, timestamp, body).finalize().into_bytes()))
+}
+
+/// Check a signature header against `body`, allowing `tolerance_secs` of clock skew
+///
+/// This is the check receivers are expected to perform; any `v1` entry may match, so
+/// a sender can include signatures for both an old and a new secret while rotating.
+pub fn verify(secret: &str, header: &str, body: &str, now: i64, tolerance_secs: i64) -> bool {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.extend(hex::decode(value).ok()),
+            _ => {}
+        }
+    }
+
+    let Some(timestamp) = timestamp else { return false };
+    if (now - timestamp).abs() > tolerance_secs {
+        return false;
+    }
+    signatures
+        .iter()
+        .any(|signature| mac(secret, timestamp, body).verify_slice(signature).is_ok())
+}
+
+/// POST a JSON body to an endpoint, signed when the endpoint has a secret
+///
+/// Returns the response status; non-2xx responses and transport failures are errors.
+pub async fn deliver(client: &reqwest::Client, endpoint: &WebhookEndpoint, body: &str) -> Result<u16, String> {
+    let mut request = client
+        .post(&endpoint.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(WEBHOOK_ID_HEADER, &endpoint.id)
+        .body(body.to_string());
+    if !endpoint.secret.is_empty() {
+        request = request.header(SIGNATURE_HEADER, sign(&endpoint.secret, crate::clock::now().timestamp(), body));
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    response.error_for_status().map(|_| status).map_err(|e| e.to_string())
+}
+
+fn mac(secret: &str, timestamp: i64, body: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_bodies_verify_within_tolerance() {
+        let header = sign("secret", 1_700_000_000, r#"{"a":1}"#);
+        assert!(header.starts_with("t=1700000000,v1="));
+        assert!(verify("secret", &header, r#"{"a":1}"#, 1_700_000_100, DEFAULT_TOLERANCE_SECS));
+        assert!(!verify("secret", &header, r#"{"a":2}"#, 1_700_000_100, DEFAULT_TOLERANCE_SECS));
+        assert!(!verify("other", &header, r#"{"a":1}"#, 1_700_000_100, DEFAULT_TOLERANCE_SECS));
+        assert!(!verify("secret", &header, r#"{"a":1}"#, 1_700_001_000, DEFAULT_TOLERANCE_SECS));
+    }
+
+    #[test]
+    fn any_listed_signature_may_match() {
+        let old = sign("old", 10, "{}");
+        let new = sign("new", 10, "{}");
+        let header = format!("{},{}", old, new.trim_start_matches("t=10,"));
+        assert!(verify("old", &header, "{}", 10, DEFAULT_TOLERANCE_SECS));
+        assert!(verify("new", &header, "{}", 10, DEFAULT_TOLERANCE_SECS));
+        assert!(!verify("new", "v1=00", "{}", 10, DEFAULT_TOLERANCE_SECS));
+    }
+}
@@ -82,6 +82,39 @@ impl TestContext {
     }
 }
 
+/// A fresh pool on DATABASE_URL, or `None` when no database is reachable
+///
+/// Pools cached by `DatabaseManager` belong to the runtime of the first test that used
+/// them, so database tests connect on their own.
+pub async fn database_pool() -> Option<sqlx::PgPool> {
+    let url = std::env::var("DATABASE_URL").ok()?;
+    sqlx::postgres::PgPoolOptions::new()
+        .max_connections(2)
+        .acquire_timeout(std::time::Duration::from_secs(5))
+        .connect(&url)
+        .await
+        .ok()
+}
+
+/// A one-connection pool on DATABASE_URL in which `ddl` has created temporary tables
+///
+/// The tables shadow any of the same name and vanish with the connection, so tests can
+/// exercise queries without a tenant database. `None` when no database is reachable.
+pub async fn scratch_pool(ddl: &str) -> Option<sqlx::PgPool> {
+    let url = std::env::var("DATABASE_URL").ok()?;
+    let pool = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(1)
+        .min_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .acquire_timeout(std::time::Duration::from_secs(5))
+        .connect(&url)
+        .await
+        .ok()?;
+    sqlx::raw_sql(ddl).execute(&pool).await.expect("scratch tables are created");
+    Some(pool)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
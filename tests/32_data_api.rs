@@ -24,3 +24,65 @@ async fn list_users_basic() -> Result<()> {
     Ok(())
 }
 
+
+/// Columns that must never come back from the data API
+const CREDENTIAL_COLUMNS: &[&str] = &[
+    "password_hash",
+    "activation_token_hash",
+    "activation_expires_at",
+    "mfa_secret",
+    "mfa_enabled_at",
+    "mfa_last_step",
+];
+
+#[tokio::test]
+async fn list_users_omits_credentials() -> Result<()> {
+    let server = common::ensure_server().await?;
+    let Some(token) = common::login(&server.base_url).await? else {
+        eprintln!("skipping: MONK_TEST_TENANT / MONK_TEST_USER not set or login failed");
+        return Ok(());
+    };
+
+    let res = reqwest::Client::new()
+        .get(format!("{}/api/data/users", server.base_url))
+        .bearer_auth(&token)
+        .send()
+        .await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let body = res.json::<serde_json::Value>().await?;
+    let users = body["data"].as_array().expect("data should be an array");
+    assert!(!users.is_empty(), "the logged in user should be listed: {}", body);
+    for user in users {
+        for column in CREDENTIAL_COLUMNS {
+            assert!(user.get(*column).is_none(), "users expose '{}': {}", column, user);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn views_are_not_written_through_data_api() -> Result<()> {
+    let server = common::ensure_server().await?;
+    let Some(token) = common::login(&server.base_url).await? else {
+        eprintln!("skipping: MONK_TEST_TENANT / MONK_TEST_USER not set or login failed");
+        return Ok(());
+    };
+
+    // A default view set here would skip the root check and the one-default-per-level rule
+    let res = reqwest::Client::new()
+        .post(format!("{}/api/data/views", server.base_url))
+        .bearer_auth(&token)
+        .json(&serde_json::json!([{
+            "user_id": "00000000-0000-0000-0000-000000000000",
+            "schema_name": "users",
+            "name": "Everyone",
+            "default_for": ["read"]
+        }]))
+        .send()
+        .await?;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use anyhow::Result;
+use reqwest::{header, redirect, StatusCode};
+use serde_json::json;
+
+/// Edge-region server whose tenants (all in the default region) live behind `primary_url`
+async fn edge_server(forwarding: &str, primary_url: &str) -> Result<common::TestServer> {
+    let endpoints = format!("default={}", primary_url);
+    common::spawn_server(&[
+        ("DATABASE_LOCAL_REGION", "edge"),
+        ("API_WRITE_FORWARDING", forwarding),
+        ("API_REGION_ENDPOINTS", endpoints.as_str()),
+    ])
+    .await
+}
+
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().redirect(redirect::Policy::none()).build()?)
+}
+
+fn served_by(res: &reqwest::Response) -> Option<&str> {
+    res.headers().get("x-monk-region").and_then(|v| v.to_str().ok())
+}
+
+#[tokio::test]
+async fn health_reports_local_region() -> Result<()> {
+    let primary = common::ensure_server().await?;
+    let edge = edge_server("redirect", &primary.base_url).await?;
+
+    let body = client()?
+        .get(format!("{}/health", edge.base_url))
+        .send()
+        .await?
+        .json::<serde_json::Value>()
+        .await?;
+    assert_eq!(body["data"]["region"], "edge", "health should report the instance region: {}", body);
+    assert!(body["data"]["regions"].is_object(), "health should list regions: {}", body);
+    Ok(())
+}
+
+#[tokio::test]
+async fn writes_redirect_to_primary_region() -> Result<()> {
+    let primary = common::ensure_server().await?;
+    let edge = edge_server("redirect", &primary.base_url).await?;
+    let Some(token) = common::login(&edge.base_url).await? else {
+        eprintln!("skipping: MONK_TEST_TENANT / MONK_TEST_USER not set or login failed");
+        return Ok(());
+    };
+
+    let res = client()?
+        .post(format!("{}/api/data/users?select=id", edge.base_url))
+        .bearer_auth(&token)
+        .json(&json!([]))
+        .send()
+        .await?;
+    assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+    let location = res.headers().get(header::LOCATION).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    assert_eq!(location, format!("{}/api/data/users?select=id", primary.base_url));
+
+    // Reads stay in the edge region
+    let res = client()?.get(format!("{}/api/data/users", edge.base_url)).bearer_auth(&token).send().await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(served_by(&res), Some("edge"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn proxied_writes_are_served_by_primary_region() -> Result<()> {
+    let primary = common::ensure_server().await?;
+    let edge = edge_server("proxy", &primary.base_url).await?;
+    let Some(token) = common::login(&edge.base_url).await? else {
+        eprintln!("skipping: MONK_TEST_TENANT / MONK_TEST_USER not set or login failed");
+        return Ok(());
+    };
+
+    // An empty bulk update writes nothing but still takes the write path
+    let res = client()?
+        .put(format!("{}/api/data/users", edge.base_url))
+        .bearer_auth(&token)
+        .json(&json!([]))
+        .send()
+        .await?;
+    assert_eq!(served_by(&res), Some("default"), "write should be answered by the primary region");
+    if res.status().is_success() {
+        let sticky = res
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .find(|cookie| cookie.starts_with("monk_sticky_until="));
+        let sticky = sticky.expect("successful write should set the sticky-read cookie").to_string();
+
+        // With the cookie the next read goes to the primary; the edge still serves it
+        let res = client()?
+            .get(format!("{}/api/data/users", edge.base_url))
+            .bearer_auth(&token)
+            .header(header::COOKIE, sticky.split(';').next().unwrap_or_default())
+            .send()
+            .await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(served_by(&res), Some("edge"));
+    }
+    Ok(())
+}
@@ -108,3 +108,37 @@ async fn limit_two_records() -> Result<()> {
     Ok(())
 }
 
+
+#[tokio::test]
+async fn pages_over_tied_sort_keys_are_stable() -> Result<()> {
+    let server = common::ensure_server().await?;
+    let client = reqwest::Client::new();
+
+    // Most users share an access level, so the order only becomes total through the id tiebreaker
+    let find = |body: serde_json::Value| {
+        let client = client.clone();
+        let url = format!("{}/api/find/users", server.base_url);
+        async move {
+            let res = client.post(url).json(&body).send().await?;
+            assert_eq!(res.status(), StatusCode::OK, "unexpected status: {}", res.status());
+            let payload = res.json::<serde_json::Value>().await?;
+            let ids = payload["data"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+                .iter()
+                .map(|rec| rec["id"].as_str().unwrap_or_default().to_string())
+                .collect::<Vec<_>>();
+            Ok::<_, anyhow::Error>(ids)
+        }
+    };
+
+    let all = find(serde_json::json!({ "order": "access", "limit": 100 })).await?;
+    let mut paged = Vec::new();
+    for offset in 0..all.len() {
+        paged.extend(find(serde_json::json!({ "order": "access", "limit": 1, "offset": offset })).await?);
+    }
+    assert_eq!(paged, all, "paging by one record repeated or skipped rows");
+
+    Ok(())
+}
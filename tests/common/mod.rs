@@ -14,7 +14,7 @@ pub struct TestServer {
 }
 
 impl TestServer {
-    fn spawn() -> Result<Self> {
+    fn spawn(env: &[(&str, &str)]) -> Result<Self> {
         // Pick an unused port for isolation
         let port = portpicker::pick_unused_port().context("failed to pick free port")?;
         let base_url = format!("http://127.0.0.1:{}", port);
@@ -23,6 +23,7 @@ impl TestServer {
         // Assumes debug profile; adjust if you run tests with --release
         let mut cmd = Command::new("target/debug/monk-api-rust");
         cmd.env("MONK_API_PORT", port.to_string())
+            .envs(env.iter().copied())
             .stdin(Stdio::null())
             .stdout(Stdio::inherit())
             .stderr(Stdio::inherit());
@@ -56,8 +57,40 @@ impl TestServer {
 
 pub async fn ensure_server() -> Result<&'static TestServer> {
     // Use stable get_or_init and convert init errors into a panic with context.
-    let server = SERVER.get_or_init(|| TestServer::spawn().expect("failed to spawn server binary"));
+    let server = SERVER.get_or_init(|| TestServer::spawn(&[]).expect("failed to spawn server binary"));
     server.wait_ready(Duration::from_secs(10)).await?;
     Ok(server)
 }
 
+/// Start a separate server with extra environment variables; it stops when dropped
+pub async fn spawn_server(env: &[(&str, &str)]) -> Result<TestServer> {
+    let server = TestServer::spawn(env)?;
+    server.wait_ready(Duration::from_secs(10)).await?;
+    Ok(server)
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+/// JWT for MONK_TEST_TENANT / MONK_TEST_USER (password MONK_TEST_PASSWORD), if those are set
+/// and the login succeeds; tests needing a tenant skip themselves otherwise
+pub async fn login(base_url: &str) -> Result<Option<String>> {
+    let (Ok(tenant), Ok(user)) = (std::env::var("MONK_TEST_TENANT"), std::env::var("MONK_TEST_USER")) else {
+        return Ok(None);
+    };
+    let password = std::env::var("MONK_TEST_PASSWORD").unwrap_or_default();
+    let res = reqwest::Client::new()
+        .post(format!("{}/auth/login/{}/{}", base_url, tenant, user))
+        .json(&serde_json::json!({ "password": password }))
+        .send()
+        .await?;
+    if !res.status().is_success() {
+        return Ok(None);
+    }
+    let body = res.json::<serde_json::Value>().await?;
+    Ok(body["data"]["token"].as_str().map(str::to_string))
+}
+